@@ -385,6 +385,13 @@ fn create_workflow_state(num_tasks: usize) -> WorkflowState {
         metadata: HashMap::new(),
         loop_states: HashMap::new(),
         loop_results: HashMap::new(),
+        subflow_states: HashMap::new(),
+        stage_statuses: HashMap::new(),
+        agent_sessions: HashMap::new(),
+        named_sessions: HashMap::new(),
+        task_usage: HashMap::new(),
+        agent_usage: HashMap::new(),
+        messages: Default::default(),
     }
 }
 
@@ -60,14 +60,28 @@ CREATE TABLE IF NOT EXISTS oauth_connections (
 CREATE INDEX idx_oauth_connections_user_id ON oauth_connections(user_id);
 CREATE INDEX idx_oauth_connections_provider ON oauth_connections(provider);
 
+-- Pending single sign-on logins, keyed by a hash of their state
+CREATE TABLE IF NOT EXISTS sso_pending_logins (
+    state_hash TEXT PRIMARY KEY,
+    provider VARCHAR(50) NOT NULL,
+    verifier_encrypted TEXT NOT NULL,
+    nonce TEXT NOT NULL,
+    expires_at TIMESTAMPTZ NOT NULL
+);
+
+CREATE INDEX idx_sso_pending_logins_expires_at ON sso_pending_logins(expires_at);
+
 -- MFA settings table
 CREATE TABLE IF NOT EXISTS mfa_settings (
     id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
     user_id UUID REFERENCES users(id) ON DELETE CASCADE UNIQUE,
     method VARCHAR(50) NOT NULL CHECK (method IN ('totp', 'sms', 'email')),
     secret_encrypted TEXT,
-    backup_codes_encrypted TEXT[],
+    recovery_code_hashes TEXT[] NOT NULL DEFAULT '{}',
     is_enabled BOOLEAN DEFAULT FALSE,
+    last_used_step BIGINT,
+    failed_attempts INTEGER NOT NULL DEFAULT 0,
+    locked_until TIMESTAMPTZ,
     verified_at TIMESTAMPTZ,
     created_at TIMESTAMPTZ DEFAULT NOW()
 );
@@ -75,6 +89,14 @@ CREATE TABLE IF NOT EXISTS mfa_settings (
 CREATE INDEX idx_mfa_settings_user_id ON mfa_settings(user_id);
 CREATE INDEX idx_mfa_settings_is_enabled ON mfa_settings(is_enabled);
 
+-- MFA login challenges already completed, so their tokens cannot be replayed
+CREATE TABLE IF NOT EXISTS mfa_challenges (
+    jti TEXT PRIMARY KEY,
+    expires_at TIMESTAMPTZ NOT NULL
+);
+
+CREATE INDEX idx_mfa_challenges_expires_at ON mfa_challenges(expires_at);
+
 -- Password reset tokens table
 CREATE TABLE IF NOT EXISTS password_reset_tokens (
     id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
@@ -142,6 +164,14 @@ BEGIN
     DELETE FROM user_sessions
     WHERE expires_at < NOW();
 
+    -- Delete expired single sign-on logins
+    DELETE FROM sso_pending_logins
+    WHERE expires_at < NOW();
+
+    -- Delete completed MFA challenges whose tokens have expired
+    DELETE FROM mfa_challenges
+    WHERE expires_at < NOW();
+
     -- Delete expired API keys
     UPDATE api_keys
     SET is_active = FALSE
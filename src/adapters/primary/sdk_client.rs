@@ -38,6 +38,7 @@ impl PeriplonSDKClient {
             self.options.can_use_tool.clone(),
             self.options.hooks.clone(),
         );
+        query.set_sdk_mcp_servers(self.options.sdk_mcp_servers.clone());
 
         // Connect transport
         {
@@ -49,8 +50,9 @@ impl PeriplonSDKClient {
         query.start(write_rx).await?;
 
         // TODO: Build hooks config and initialize
-        // Skip initialization if no hooks configured to avoid hanging
-        if self.options.hooks.is_some() && !self.options.hooks.as_ref().unwrap().is_empty() {
+        // Skip initialization if no hooks or SDK MCP servers are configured to avoid hanging
+        let has_hooks = self.options.hooks.as_ref().is_some_and(|h| !h.is_empty());
+        if has_hooks || !self.options.sdk_mcp_servers.is_empty() {
             query.initialize(None).await?;
         }
 
@@ -1,14 +1,32 @@
 //! HTTP-based LLM Client Implementation
 //!
-//! Unified client supporting Ollama, OpenAI, Anthropic, and Google APIs
+//! Unified client supporting Ollama, OpenAI, Anthropic, and Google APIs, plus
+//! Azure OpenAI and any server speaking the OpenAI chat completions format,
+//! with multi-turn conversations and streamed responses (newline-delimited
+//! JSON for Ollama, server-sent events for the others).
 
 use async_trait::async_trait;
 use reqwest::Client;
-use serde_json::json;
+use serde_json::{json, Value};
 use std::time::Duration;
 
-use crate::domain::Provider;
-use crate::ports::secondary::{LlmClient, LlmError, LlmRequest, LlmResponse, TokenUsage};
+use crate::domain::{AuthStyle, Provider};
+use crate::ports::secondary::{
+    LlmClient, LlmError, LlmMessage, LlmRequest, LlmResponse, LlmRole, LlmStream, LlmStreamEvent,
+    LlmToolCall, LlmToolHandler, LlmToolOutput, TokenUsage, DEFAULT_MAX_TOOL_ITERATIONS,
+};
+
+/// A provider request before it is sent
+///
+/// The body stays editable so the tool-calling loop can append turns to it.
+struct HttpCall {
+    url: String,
+    headers: Vec<(String, String)>,
+    body: Value,
+}
+
+/// Azure OpenAI API version used when none is configured
+pub const AZURE_DEFAULT_API_VERSION: &str = "2024-10-21";
 
 /// HTTP-based LLM client supporting multiple providers
 pub struct HttpLlmClient {
@@ -51,31 +69,63 @@ impl HttpLlmClient {
         Ok(None)
     }
 
+    /// Get the API key, failing if the provider requires one and none is set
+    fn require_api_key(&self, request: &LlmRequest, env_var: &str) -> Result<String, LlmError> {
+        self.get_api_key(request)?
+            .ok_or_else(|| LlmError::MissingApiKey(request.provider.clone(), env_var.to_string()))
+    }
+
     /// Get endpoint URL for provider
-    fn get_endpoint(&self, request: &LlmRequest) -> String {
+    fn get_endpoint(&self, request: &LlmRequest) -> Result<String, LlmError> {
         request
             .endpoint
             .clone()
             .or_else(|| request.provider.default_endpoint().map(String::from))
-            .expect("Provider should have default endpoint")
+            .map(|endpoint| endpoint.trim_end_matches('/').to_string())
+            .ok_or_else(|| {
+                LlmError::InvalidConfig(format!(
+                    "{} requires an endpoint",
+                    provider_name(&request.provider)
+                ))
+            })
+    }
+
+    /// Build the HTTP request for a provider
+    fn build_request(&self, request: &LlmRequest, stream: bool) -> Result<HttpCall, LlmError> {
+        let mut call = match request.provider {
+            Provider::Ollama => self.build_ollama(request, stream)?,
+            Provider::OpenAI | Provider::OpenAICompatible | Provider::AzureOpenAI => {
+                self.build_openai(request, stream)?
+            }
+            Provider::Anthropic => self.build_anthropic(request, stream)?,
+            Provider::Google => self.build_google(request, stream)?,
+            _ => return Err(LlmError::UnsupportedProvider(request.provider.clone())),
+        };
+
+        if !request.tools.is_empty() {
+            call.body["tools"] = tool_declarations(request);
+        }
+
+        Ok(call)
     }
 
-    /// Execute Ollama request
-    async fn execute_ollama(&self, request: LlmRequest) -> Result<LlmResponse, LlmError> {
-        let endpoint = self.get_endpoint(&request);
-        let url = format!("{}/api/generate", endpoint);
+    /// Build an Ollama chat request
+    fn build_ollama(&self, request: &LlmRequest, stream: bool) -> Result<HttpCall, LlmError> {
+        let endpoint = self.get_endpoint(request)?;
+        let url = format!("{}/api/chat", endpoint);
+
+        let messages: Vec<Value> = request
+            .conversation()
+            .iter()
+            .map(|message| json!({"role": role_name(message.role), "content": message.content}))
+            .collect();
 
         let mut body = json!({
             "model": request.model,
-            "prompt": request.prompt,
-            "stream": false,
+            "messages": messages,
+            "stream": stream,
         });
 
-        // Add system prompt if provided
-        if let Some(system) = &request.system_prompt {
-            body["system"] = json!(system);
-        }
-
         // Add options
         let mut options = serde_json::Map::new();
         if let Some(temp) = request.temperature {
@@ -98,101 +148,71 @@ impl HttpLlmClient {
             body["options"] = json!(options);
         }
 
+        // Constrain the output to the schema
+        if let Some(schema) = &request.response_schema {
+            body["format"] = schema.clone();
+        }
+
         // Add extra params
         for (key, value) in &request.extra_params {
             body[key] = value.clone();
         }
 
-        let mut http_request = self.client.post(&url).json(&body);
-
-        if let Some(timeout) = request.timeout_secs {
-            http_request = http_request.timeout(Duration::from_secs(timeout));
-        }
-
-        let response = http_request
-            .send()
-            .await
-            .map_err(|e| LlmError::HttpError(e.to_string()))?;
-
-        if !response.status().is_success() {
-            let status = response.status();
-            let error_text = response
-                .text()
-                .await
-                .unwrap_or_else(|_| "Unknown error".to_string());
+        Ok(HttpCall {
+            url,
+            headers: Vec::new(),
+            body,
+        })
+    }
 
-            // Check for model not found
-            if error_text.contains("model") && error_text.contains("not found") {
-                return Err(LlmError::ModelNotFound(request.model));
+    /// Build an OpenAI chat completions request
+    ///
+    /// Also used for OpenAI-compatible servers, whose key is optional, and for
+    /// Azure OpenAI, which addresses the model as a deployment.
+    fn build_openai(&self, request: &LlmRequest, stream: bool) -> Result<HttpCall, LlmError> {
+        let auth = request
+            .auth
+            .unwrap_or_else(|| request.provider.default_auth());
+        let api_key = match (auth, &request.provider) {
+            (AuthStyle::None, _) => None,
+            (_, Provider::OpenAI) => Some(self.require_api_key(request, "OPENAI_API_KEY")?),
+            (_, Provider::AzureOpenAI) => {
+                Some(self.require_api_key(request, "AZURE_OPENAI_API_KEY")?)
             }
+            _ => self.get_api_key(request)?,
+        };
 
-            return Err(LlmError::ApiError(format!(
-                "Ollama API error ({}): {}",
-                status, error_text
-            )));
-        }
-
-        let raw: serde_json::Value = response
-            .json()
-            .await
-            .map_err(|e| LlmError::ParseError(e.to_string()))?;
-
-        let content = raw["response"]
-            .as_str()
-            .ok_or_else(|| LlmError::ParseError("Missing 'response' field".to_string()))?
-            .to_string();
-
-        let usage = if let Some(prompt_tokens) = raw["prompt_eval_count"].as_u64() {
-            let completion_tokens = raw["eval_count"].as_u64().unwrap_or(0);
-            Some(TokenUsage {
-                prompt_tokens: prompt_tokens as u32,
-                completion_tokens: completion_tokens as u32,
-                total_tokens: (prompt_tokens + completion_tokens) as u32,
-            })
+        let endpoint = self.get_endpoint(request)?;
+        let url = if request.provider == Provider::AzureOpenAI {
+            format!(
+                "{}/openai/deployments/{}/chat/completions?api-version={}",
+                endpoint,
+                request.model,
+                request
+                    .api_version
+                    .as_deref()
+                    .unwrap_or(AZURE_DEFAULT_API_VERSION)
+            )
         } else {
-            None
+            format!("{}/chat/completions", endpoint)
         };
 
-        Ok(LlmResponse {
-            content,
-            model: request.model,
-            provider: Provider::Ollama,
-            usage,
-            finish_reason: Some(raw["done_reason"].as_str().unwrap_or("stop").to_string()),
-            raw_response: Some(raw),
-        })
-    }
-
-    /// Execute OpenAI request
-    async fn execute_openai(&self, request: LlmRequest) -> Result<LlmResponse, LlmError> {
-        let api_key = self.get_api_key(&request)?.ok_or_else(|| {
-            LlmError::MissingApiKey(Provider::OpenAI, "OPENAI_API_KEY".to_string())
-        })?;
-
-        let endpoint = self.get_endpoint(&request);
-        let url = format!("{}/chat/completions", endpoint);
-
-        let mut messages = Vec::new();
-
-        // Add system message if provided
-        if let Some(system) = &request.system_prompt {
-            messages.push(json!({
-                "role": "system",
-                "content": system
-            }));
-        }
-
-        // Add user message
-        messages.push(json!({
-            "role": "user",
-            "content": request.prompt
-        }));
+        let messages: Vec<Value> = request
+            .conversation()
+            .iter()
+            .map(|message| json!({"role": role_name(message.role), "content": message.content}))
+            .collect();
 
         let mut body = json!({
             "model": request.model,
             "messages": messages,
         });
 
+        if stream {
+            body["stream"] = json!(true);
+            body["stream_options"] = json!({"include_usage": true});
+        }
+
         // Add optional parameters
         if let Some(temp) = request.temperature {
             body["temperature"] = json!(temp);
@@ -207,88 +227,76 @@ impl HttpLlmClient {
             body["stop"] = json!(request.stop);
         }
 
+        // Use structured outputs when a schema is requested
+        if let Some(schema) = &request.response_schema {
+            body["response_format"] = json!({
+                "type": "json_schema",
+                "json_schema": {
+                    "name": "response",
+                    "schema": schema,
+                }
+            });
+        }
+
         // Add extra params
         for (key, value) in &request.extra_params {
             body[key] = value.clone();
         }
 
-        let mut http_request = self
-            .client
-            .post(&url)
-            .header("Authorization", format!("Bearer {}", api_key))
-            .json(&body);
-
-        if let Some(timeout) = request.timeout_secs {
-            http_request = http_request.timeout(Duration::from_secs(timeout));
-        }
-
-        let response = http_request
-            .send()
-            .await
-            .map_err(|e| LlmError::HttpError(e.to_string()))?;
-
-        if !response.status().is_success() {
-            let status = response.status();
-            let error_text = response
-                .text()
-                .await
-                .unwrap_or_else(|_| "Unknown error".to_string());
-            return Err(LlmError::ApiError(format!(
-                "OpenAI API error ({}): {}",
-                status, error_text
-            )));
-        }
-
-        let raw: serde_json::Value = response
-            .json()
-            .await
-            .map_err(|e| LlmError::ParseError(e.to_string()))?;
-
-        let content = raw["choices"][0]["message"]["content"]
-            .as_str()
-            .ok_or_else(|| LlmError::ParseError("Missing content in response".to_string()))?
-            .to_string();
-
-        let usage = raw["usage"].as_object().map(|usage_obj| TokenUsage {
-            prompt_tokens: usage_obj["prompt_tokens"].as_u64().unwrap_or(0) as u32,
-            completion_tokens: usage_obj["completion_tokens"].as_u64().unwrap_or(0) as u32,
-            total_tokens: usage_obj["total_tokens"].as_u64().unwrap_or(0) as u32,
-        });
-
-        let finish_reason = raw["choices"][0]["finish_reason"]
-            .as_str()
-            .map(String::from);
+        let headers = match (auth, api_key) {
+            (AuthStyle::Bearer, Some(key)) => {
+                vec![("Authorization".to_string(), format!("Bearer {}", key))]
+            }
+            (AuthStyle::ApiKey, Some(key)) => vec![(
+                request
+                    .auth_header
+                    .clone()
+                    .unwrap_or_else(|| "api-key".to_string()),
+                key,
+            )],
+            _ => Vec::new(),
+        };
 
-        Ok(LlmResponse {
-            content,
-            model: raw["model"].as_str().unwrap_or(&request.model).to_string(),
-            provider: Provider::OpenAI,
-            usage,
-            finish_reason,
-            raw_response: Some(raw),
-        })
+        Ok(HttpCall { url, headers, body })
     }
 
-    /// Execute Anthropic request
-    async fn execute_anthropic(&self, request: LlmRequest) -> Result<LlmResponse, LlmError> {
-        let api_key = self.get_api_key(&request)?.ok_or_else(|| {
-            LlmError::MissingApiKey(Provider::Anthropic, "ANTHROPIC_API_KEY".to_string())
-        })?;
+    /// Build an Anthropic messages request
+    fn build_anthropic(&self, request: &LlmRequest, stream: bool) -> Result<HttpCall, LlmError> {
+        let api_key = self.require_api_key(request, "ANTHROPIC_API_KEY")?;
 
-        let endpoint = self.get_endpoint(&request);
+        let endpoint = self.get_endpoint(request)?;
         let url = format!("{}/messages", endpoint);
 
+        let (system, messages) = split_system(request.conversation());
+        let messages: Vec<Value> = messages
+            .iter()
+            .map(|message| json!({"role": role_name(message.role), "content": message.content}))
+            .collect();
+
         let mut body = json!({
             "model": request.model,
-            "messages": [{
-                "role": "user",
-                "content": request.prompt
-            }],
+            "messages": messages,
             "max_tokens": request.max_tokens.unwrap_or(4096),
         });
 
-        // Add system prompt if provided
-        if let Some(system) = &request.system_prompt {
+        if stream {
+            body["stream"] = json!(true);
+        }
+
+        // Add system prompt if provided. Anthropic has no structured output
+        // mode, so a requested schema is given to the model as instructions
+        let mut system_prompt = system;
+        if let Some(schema) = &request.response_schema {
+            let instructions = format!(
+                "Respond with only a JSON value that conforms to this JSON Schema:\n{}",
+                schema
+            );
+            system_prompt = Some(match system_prompt {
+                Some(system) => format!("{}\n\n{}", system, instructions),
+                None => instructions,
+            });
+        }
+        if let Some(system) = system_prompt {
             body["system"] = json!(system);
         }
 
@@ -311,85 +319,48 @@ impl HttpLlmClient {
             body[key] = value.clone();
         }
 
-        let mut http_request = self
-            .client
-            .post(&url)
-            .header("x-api-key", api_key)
-            .header("anthropic-version", "2023-06-01")
-            .header("content-type", "application/json")
-            .json(&body);
-
-        if let Some(timeout) = request.timeout_secs {
-            http_request = http_request.timeout(Duration::from_secs(timeout));
-        }
-
-        let response = http_request
-            .send()
-            .await
-            .map_err(|e| LlmError::HttpError(e.to_string()))?;
-
-        if !response.status().is_success() {
-            let status = response.status();
-            let error_text = response
-                .text()
-                .await
-                .unwrap_or_else(|_| "Unknown error".to_string());
-            return Err(LlmError::ApiError(format!(
-                "Anthropic API error ({}): {}",
-                status, error_text
-            )));
-        }
-
-        let raw: serde_json::Value = response
-            .json()
-            .await
-            .map_err(|e| LlmError::ParseError(e.to_string()))?;
-
-        let content = raw["content"][0]["text"]
-            .as_str()
-            .ok_or_else(|| LlmError::ParseError("Missing content in response".to_string()))?
-            .to_string();
-
-        let usage = raw["usage"].as_object().map(|usage_obj| TokenUsage {
-            prompt_tokens: usage_obj["input_tokens"].as_u64().unwrap_or(0) as u32,
-            completion_tokens: usage_obj["output_tokens"].as_u64().unwrap_or(0) as u32,
-            total_tokens: (usage_obj["input_tokens"].as_u64().unwrap_or(0)
-                + usage_obj["output_tokens"].as_u64().unwrap_or(0))
-                as u32,
-        });
-
-        let finish_reason = raw["stop_reason"].as_str().map(String::from);
-
-        Ok(LlmResponse {
-            content,
-            model: raw["model"].as_str().unwrap_or(&request.model).to_string(),
-            provider: Provider::Anthropic,
-            usage,
-            finish_reason,
-            raw_response: Some(raw),
+        Ok(HttpCall {
+            url,
+            headers: vec![
+                ("x-api-key".to_string(), api_key),
+                ("anthropic-version".to_string(), "2023-06-01".to_string()),
+            ],
+            body,
         })
     }
 
-    /// Execute Google Gemini request
-    async fn execute_google(&self, request: LlmRequest) -> Result<LlmResponse, LlmError> {
-        let api_key = self.get_api_key(&request)?.ok_or_else(|| {
-            LlmError::MissingApiKey(Provider::Google, "GOOGLE_API_KEY".to_string())
-        })?;
+    /// Build a Google Gemini request
+    fn build_google(&self, request: &LlmRequest, stream: bool) -> Result<HttpCall, LlmError> {
+        let api_key = self.require_api_key(request, "GOOGLE_API_KEY")?;
 
-        let endpoint = self.get_endpoint(&request);
-        let url = format!(
-            "{}/models/{}:generateContent?key={}",
-            endpoint, request.model, api_key
-        );
+        let endpoint = self.get_endpoint(request)?;
+        let url = if stream {
+            format!(
+                "{}/models/{}:streamGenerateContent?alt=sse&key={}",
+                endpoint, request.model, api_key
+            )
+        } else {
+            format!(
+                "{}/models/{}:generateContent?key={}",
+                endpoint, request.model, api_key
+            )
+        };
 
-        let parts = vec![json!({
-            "text": request.prompt
-        })];
+        // Gemini calls the assistant role "model"
+        let (system, messages) = split_system(request.conversation());
+        let contents: Vec<Value> = messages
+            .iter()
+            .map(|message| {
+                let role = match message.role {
+                    LlmRole::Assistant => "model",
+                    _ => "user",
+                };
+                json!({"role": role, "parts": [{"text": message.content}]})
+            })
+            .collect();
 
         let mut body = json!({
-            "contents": [{
-                "parts": parts
-            }]
+            "contents": contents
         });
 
         // Add generation config
@@ -409,13 +380,17 @@ impl HttpLlmClient {
         if !request.stop.is_empty() {
             generation_config.insert("stopSequences".to_string(), json!(request.stop));
         }
+        if let Some(schema) = &request.response_schema {
+            generation_config.insert("responseMimeType".to_string(), json!("application/json"));
+            generation_config.insert("responseSchema".to_string(), schema.clone());
+        }
 
         if !generation_config.is_empty() {
             body["generationConfig"] = json!(generation_config);
         }
 
         // Add system instruction if provided
-        if let Some(system) = &request.system_prompt {
+        if let Some(system) = system {
             body["systemInstruction"] = json!({
                 "parts": [{
                     "text": system
@@ -428,8 +403,23 @@ impl HttpLlmClient {
             body[key] = value.clone();
         }
 
-        let mut http_request = self.client.post(&url).json(&body);
+        Ok(HttpCall {
+            url,
+            headers: Vec::new(),
+            body,
+        })
+    }
 
+    /// Send a request, turning error statuses into [`LlmError`]s
+    async fn send(
+        &self,
+        request: &LlmRequest,
+        call: &HttpCall,
+    ) -> Result<reqwest::Response, LlmError> {
+        let mut http_request = self.client.post(&call.url).json(&call.body);
+        for (name, value) in &call.headers {
+            http_request = http_request.header(name, value);
+        }
         if let Some(timeout) = request.timeout_secs {
             http_request = http_request.timeout(Duration::from_secs(timeout));
         }
@@ -445,38 +435,90 @@ impl HttpLlmClient {
                 .text()
                 .await
                 .unwrap_or_else(|_| "Unknown error".to_string());
+
+            // Check for model not found
+            if request.provider == Provider::Ollama
+                && error_text.contains("model")
+                && error_text.contains("not found")
+            {
+                return Err(LlmError::ModelNotFound(request.model.clone()));
+            }
+
             return Err(LlmError::ApiError(format!(
-                "Google API error ({}): {}",
-                status, error_text
+                "{} API error ({}): {}",
+                provider_name(&request.provider),
+                status,
+                error_text
             )));
         }
 
-        let raw: serde_json::Value = response
-            .json()
-            .await
-            .map_err(|e| LlmError::ParseError(e.to_string()))?;
-
-        let content = raw["candidates"][0]["content"]["parts"][0]["text"]
-            .as_str()
-            .ok_or_else(|| LlmError::ParseError("Missing content in response".to_string()))?
-            .to_string();
-
-        let usage = raw["usageMetadata"]
-            .as_object()
-            .map(|usage_obj| TokenUsage {
-                prompt_tokens: usage_obj["promptTokenCount"].as_u64().unwrap_or(0) as u32,
-                completion_tokens: usage_obj["candidatesTokenCount"].as_u64().unwrap_or(0) as u32,
-                total_tokens: usage_obj["totalTokenCount"].as_u64().unwrap_or(0) as u32,
-            });
+        Ok(response)
+    }
 
-        let finish_reason = raw["candidates"][0]["finishReason"]
-            .as_str()
-            .map(String::from);
+    /// Parse a complete (non-streamed) response
+    fn parse_response(request: &LlmRequest, raw: Value) -> Result<LlmResponse, LlmError> {
+        let missing = || LlmError::ParseError("Missing content in response".to_string());
+
+        let (content, model, usage, finish_reason) = match request.provider {
+            Provider::Ollama => {
+                let content = raw["message"]["content"]
+                    .as_str()
+                    .ok_or_else(|| LlmError::ParseError("Missing 'message' field".to_string()))?;
+                (
+                    content.to_string(),
+                    request.model.clone(),
+                    ollama_usage(&raw),
+                    Some(raw["done_reason"].as_str().unwrap_or("stop").to_string()),
+                )
+            }
+            Provider::OpenAI | Provider::OpenAICompatible | Provider::AzureOpenAI => {
+                let content = raw["choices"][0]["message"]["content"]
+                    .as_str()
+                    .ok_or_else(missing)?;
+                (
+                    content.to_string(),
+                    raw["model"].as_str().unwrap_or(&request.model).to_string(),
+                    openai_usage(&raw),
+                    raw["choices"][0]["finish_reason"]
+                        .as_str()
+                        .map(String::from),
+                )
+            }
+            Provider::Anthropic => {
+                // Text may be split around tool use blocks
+                let content: String = raw["content"]
+                    .as_array()
+                    .ok_or_else(missing)?
+                    .iter()
+                    .filter_map(|block| block["text"].as_str())
+                    .collect();
+                (
+                    content,
+                    raw["model"].as_str().unwrap_or(&request.model).to_string(),
+                    anthropic_usage(&raw),
+                    raw["stop_reason"].as_str().map(String::from),
+                )
+            }
+            Provider::Google => {
+                let content = raw["candidates"][0]["content"]["parts"][0]["text"]
+                    .as_str()
+                    .ok_or_else(missing)?;
+                (
+                    content.to_string(),
+                    request.model.clone(),
+                    google_usage(&raw),
+                    raw["candidates"][0]["finishReason"]
+                        .as_str()
+                        .map(String::from),
+                )
+            }
+            _ => return Err(LlmError::UnsupportedProvider(request.provider.clone())),
+        };
 
         Ok(LlmResponse {
             content,
-            model: request.model,
-            provider: Provider::Google,
+            model,
+            provider: request.provider.clone(),
             usage,
             finish_reason,
             raw_response: Some(raw),
@@ -493,20 +535,106 @@ impl Default for HttpLlmClient {
 #[async_trait]
 impl LlmClient for HttpLlmClient {
     async fn execute(&self, request: LlmRequest) -> Result<LlmResponse, LlmError> {
-        match request.provider {
-            Provider::Ollama => self.execute_ollama(request).await,
-            Provider::OpenAI => self.execute_openai(request).await,
-            Provider::Anthropic => self.execute_anthropic(request).await,
-            Provider::Google => self.execute_google(request).await,
-            _ => Err(LlmError::UnsupportedProvider(request.provider)),
+        let call = self.build_request(&request, false)?;
+        let response = self.send(&request, &call).await?;
+
+        let raw: Value = response
+            .json()
+            .await
+            .map_err(|e| LlmError::ParseError(e.to_string()))?;
+
+        Self::parse_response(&request, raw)
+    }
+
+    async fn execute_stream(&self, request: LlmRequest) -> Result<LlmStream, LlmError> {
+        let call = self.build_request(&request, true)?;
+        let mut response = self.send(&request, &call).await?;
+
+        Ok(Box::pin(async_stream::try_stream! {
+            let mut state = StreamState::new(&request);
+            let mut buffer: Vec<u8> = Vec::new();
+
+            loop {
+                let chunk = response
+                    .chunk()
+                    .await
+                    .map_err(|e| LlmError::HttpError(e.to_string()))?;
+                let finished = chunk.is_none();
+                if let Some(chunk) = chunk {
+                    buffer.extend_from_slice(&chunk);
+                } else if !buffer.is_empty() {
+                    // Treat a trailing line without newline as complete
+                    buffer.push(b'\n');
+                }
+
+                while let Some(pos) = buffer.iter().position(|&b| b == b'\n') {
+                    let line: Vec<u8> = buffer.drain(..=pos).collect();
+                    let line = String::from_utf8_lossy(&line);
+                    if let Some(delta) = state.apply_line(line.trim())? {
+                        yield LlmStreamEvent::Delta(delta);
+                    }
+                }
+
+                if finished {
+                    break;
+                }
+            }
+
+            yield LlmStreamEvent::Done(state.into_response());
+        }))
+    }
+
+    async fn execute_with_tools(
+        &self,
+        request: LlmRequest,
+        handler: &dyn LlmToolHandler,
+    ) -> Result<LlmResponse, LlmError> {
+        let max_iterations = request
+            .max_tool_iterations
+            .unwrap_or(DEFAULT_MAX_TOOL_ITERATIONS);
+        let mut call = self.build_request(&request, false)?;
+        let mut usage: Option<TokenUsage> = None;
+
+        for iteration in 0.. {
+            let raw: Value = self
+                .send(&request, &call)
+                .await?
+                .json()
+                .await
+                .map_err(|e| LlmError::ParseError(e.to_string()))?;
+            usage = add_usage(usage, response_usage(&request.provider, &raw));
+
+            let tool_calls = parse_tool_calls(&request.provider, &raw);
+            if tool_calls.is_empty() {
+                let mut response = Self::parse_response(&request, raw)?;
+                response.usage = usage;
+                return Ok(response);
+            }
+            if iteration >= max_iterations {
+                return Err(LlmError::ApiError(format!(
+                    "Model was still calling tools after {} rounds (max_tool_iterations)",
+                    max_iterations
+                )));
+            }
+
+            let mut outputs = Vec::with_capacity(tool_calls.len());
+            for tool_call in &tool_calls {
+                outputs.push(handler.call_tool(tool_call).await);
+            }
+            append_tool_turn(
+                &request.provider,
+                &mut call.body,
+                &raw,
+                &tool_calls,
+                &outputs,
+            );
         }
+
+        unreachable!("the tool loop only exits by returning")
     }
 
     fn supports_provider(&self, provider: &Provider) -> bool {
-        matches!(
-            provider,
-            Provider::Ollama | Provider::OpenAI | Provider::Anthropic | Provider::Google
-        )
+        provider.is_api_based()
     }
 
     fn name(&self) -> &str {
@@ -514,9 +642,453 @@ impl LlmClient for HttpLlmClient {
     }
 }
 
+/// Accumulates a streamed response
+struct StreamState {
+    provider: Provider,
+    model: String,
+    content: String,
+    prompt_tokens: Option<u64>,
+    completion_tokens: Option<u64>,
+    usage: Option<TokenUsage>,
+    finish_reason: Option<String>,
+}
+
+impl StreamState {
+    fn new(request: &LlmRequest) -> Self {
+        Self {
+            provider: request.provider.clone(),
+            model: request.model.clone(),
+            content: String::new(),
+            prompt_tokens: None,
+            completion_tokens: None,
+            usage: None,
+            finish_reason: None,
+        }
+    }
+
+    /// Apply one line of the stream, returning the text it adds
+    ///
+    /// Ollama sends one JSON object per line; the other providers send
+    /// server-sent events whose `data:` lines carry the JSON.
+    fn apply_line(&mut self, line: &str) -> Result<Option<String>, LlmError> {
+        let payload = if self.provider == Provider::Ollama {
+            line
+        } else {
+            match line.strip_prefix("data:") {
+                Some(data) => data.trim(),
+                None => return Ok(None),
+            }
+        };
+        if payload.is_empty() || payload == "[DONE]" {
+            return Ok(None);
+        }
+
+        let event: Value = serde_json::from_str(payload)
+            .map_err(|e| LlmError::ParseError(format!("Invalid stream event: {}", e)))?;
+        if let Some(error) = event.get("error") {
+            return Err(LlmError::ApiError(match error {
+                Value::String(message) => message.clone(),
+                other => other.to_string(),
+            }));
+        }
+
+        let delta = match self.provider {
+            Provider::Ollama => {
+                if event["done"].as_bool() == Some(true) {
+                    self.usage = ollama_usage(&event);
+                    self.finish_reason =
+                        Some(event["done_reason"].as_str().unwrap_or("stop").to_string());
+                }
+                event["message"]["content"].as_str().map(String::from)
+            }
+            Provider::OpenAI | Provider::OpenAICompatible | Provider::AzureOpenAI => {
+                if let Some(model) = event["model"].as_str() {
+                    self.model = model.to_string();
+                }
+                if let Some(reason) = event["choices"][0]["finish_reason"].as_str() {
+                    self.finish_reason = Some(reason.to_string());
+                }
+                if event["usage"].is_object() {
+                    self.usage = openai_usage(&event);
+                }
+                event["choices"][0]["delta"]["content"]
+                    .as_str()
+                    .map(String::from)
+            }
+            Provider::Anthropic => match event["type"].as_str() {
+                Some("message_start") => {
+                    if let Some(model) = event["message"]["model"].as_str() {
+                        self.model = model.to_string();
+                    }
+                    self.prompt_tokens = event["message"]["usage"]["input_tokens"].as_u64();
+                    None
+                }
+                Some("content_block_delta") => event["delta"]["text"].as_str().map(String::from),
+                Some("message_delta") => {
+                    self.finish_reason = event["delta"]["stop_reason"].as_str().map(String::from);
+                    self.completion_tokens = event["usage"]["output_tokens"].as_u64();
+                    None
+                }
+                _ => None,
+            },
+            Provider::Google => {
+                if let Some(reason) = event["candidates"][0]["finishReason"].as_str() {
+                    self.finish_reason = Some(reason.to_string());
+                }
+                if event["usageMetadata"].is_object() {
+                    self.usage = google_usage(&event);
+                }
+                event["candidates"][0]["content"]["parts"]
+                    .as_array()
+                    .map(|parts| {
+                        parts
+                            .iter()
+                            .filter_map(|part| part["text"].as_str())
+                            .collect::<String>()
+                    })
+            }
+            _ => None,
+        };
+
+        Ok(delta
+            .filter(|text| !text.is_empty())
+            .inspect(|text| self.content.push_str(text)))
+    }
+
+    fn into_response(self) -> LlmResponse {
+        let usage = self.usage.or_else(|| {
+            (self.prompt_tokens.is_some() || self.completion_tokens.is_some()).then(|| {
+                token_usage(
+                    self.prompt_tokens.unwrap_or(0),
+                    self.completion_tokens.unwrap_or(0),
+                )
+            })
+        });
+
+        LlmResponse {
+            content: self.content,
+            model: self.model,
+            provider: self.provider,
+            usage,
+            finish_reason: self.finish_reason,
+            raw_response: None,
+        }
+    }
+}
+
+/// Role name used by the OpenAI, Anthropic and Ollama message formats
+fn role_name(role: LlmRole) -> &'static str {
+    match role {
+        LlmRole::System => "system",
+        LlmRole::User => "user",
+        LlmRole::Assistant => "assistant",
+    }
+}
+
+/// Separate system messages (joined) from the rest of the conversation
+fn split_system(conversation: Vec<LlmMessage>) -> (Option<String>, Vec<LlmMessage>) {
+    let (system, messages): (Vec<_>, Vec<_>) = conversation
+        .into_iter()
+        .partition(|message| message.role == LlmRole::System);
+
+    let system = (!system.is_empty()).then(|| {
+        system
+            .into_iter()
+            .map(|message| message.content)
+            .collect::<Vec<_>>()
+            .join("\n\n")
+    });
+    (system, messages)
+}
+
+/// Provider name used in error messages
+fn provider_name(provider: &Provider) -> &'static str {
+    match provider {
+        Provider::Ollama => "Ollama",
+        Provider::OpenAI => "OpenAI",
+        Provider::Anthropic => "Anthropic",
+        Provider::Google => "Google",
+        Provider::OpenAICompatible => "OpenAI-compatible",
+        Provider::AzureOpenAI => "Azure OpenAI",
+        _ => "LLM",
+    }
+}
+
+fn token_usage(prompt_tokens: u64, completion_tokens: u64) -> TokenUsage {
+    TokenUsage {
+        prompt_tokens: prompt_tokens as u32,
+        completion_tokens: completion_tokens as u32,
+        total_tokens: (prompt_tokens + completion_tokens) as u32,
+    }
+}
+
+/// Usage reported in a complete response
+fn response_usage(provider: &Provider, raw: &Value) -> Option<TokenUsage> {
+    match provider {
+        Provider::Ollama => ollama_usage(raw),
+        Provider::OpenAI | Provider::OpenAICompatible | Provider::AzureOpenAI => openai_usage(raw),
+        Provider::Anthropic => anthropic_usage(raw),
+        Provider::Google => google_usage(raw),
+        _ => None,
+    }
+}
+
+/// Sum the usage of two requests
+fn add_usage(total: Option<TokenUsage>, usage: Option<TokenUsage>) -> Option<TokenUsage> {
+    match (total, usage) {
+        (Some(total), Some(usage)) => Some(TokenUsage {
+            prompt_tokens: total.prompt_tokens + usage.prompt_tokens,
+            completion_tokens: total.completion_tokens + usage.completion_tokens,
+            total_tokens: total.total_tokens + usage.total_tokens,
+        }),
+        (total, usage) => total.or(usage),
+    }
+}
+
+fn anthropic_usage(raw: &Value) -> Option<TokenUsage> {
+    raw["usage"].as_object().map(|usage_obj| {
+        token_usage(
+            usage_obj["input_tokens"].as_u64().unwrap_or(0),
+            usage_obj["output_tokens"].as_u64().unwrap_or(0),
+        )
+    })
+}
+
+fn ollama_usage(raw: &Value) -> Option<TokenUsage> {
+    raw["prompt_eval_count"]
+        .as_u64()
+        .map(|prompt_tokens| token_usage(prompt_tokens, raw["eval_count"].as_u64().unwrap_or(0)))
+}
+
+fn openai_usage(raw: &Value) -> Option<TokenUsage> {
+    raw["usage"].as_object().map(|usage_obj| TokenUsage {
+        prompt_tokens: usage_obj["prompt_tokens"].as_u64().unwrap_or(0) as u32,
+        completion_tokens: usage_obj["completion_tokens"].as_u64().unwrap_or(0) as u32,
+        total_tokens: usage_obj["total_tokens"].as_u64().unwrap_or(0) as u32,
+    })
+}
+
+fn google_usage(raw: &Value) -> Option<TokenUsage> {
+    raw["usageMetadata"]
+        .as_object()
+        .map(|usage_obj| TokenUsage {
+            prompt_tokens: usage_obj["promptTokenCount"].as_u64().unwrap_or(0) as u32,
+            completion_tokens: usage_obj["candidatesTokenCount"].as_u64().unwrap_or(0) as u32,
+            total_tokens: usage_obj["totalTokenCount"].as_u64().unwrap_or(0) as u32,
+        })
+}
+
+/// Tool declarations in the provider's format
+///
+/// OpenAI and Ollama share the function format; Gemini accepts only a subset
+/// of JSON Schema, so unsupported keywords are removed from its parameters.
+fn tool_declarations(request: &LlmRequest) -> Value {
+    match request.provider {
+        Provider::Anthropic => json!(request
+            .tools
+            .iter()
+            .map(|tool| json!({
+                "name": tool.name,
+                "description": tool.description,
+                "input_schema": tool.parameters,
+            }))
+            .collect::<Vec<_>>()),
+        Provider::Google => json!([{
+            "functionDeclarations": request
+                .tools
+                .iter()
+                .map(|tool| json!({
+                    "name": tool.name,
+                    "description": tool.description,
+                    "parameters": gemini_schema(&tool.parameters),
+                }))
+                .collect::<Vec<_>>()
+        }]),
+        _ => json!(request
+            .tools
+            .iter()
+            .map(|tool| json!({
+                "type": "function",
+                "function": {
+                    "name": tool.name,
+                    "description": tool.description,
+                    "parameters": tool.parameters,
+                }
+            }))
+            .collect::<Vec<_>>()),
+    }
+}
+
+/// Remove the JSON Schema keywords Gemini rejects
+fn gemini_schema(schema: &Value) -> Value {
+    match schema {
+        Value::Object(map) => Value::Object(
+            map.iter()
+                .filter(|(key, _)| !matches!(key.as_str(), "$schema" | "additionalProperties"))
+                .map(|(key, value)| (key.clone(), gemini_schema(value)))
+                .collect(),
+        ),
+        Value::Array(items) => Value::Array(items.iter().map(gemini_schema).collect()),
+        other => other.clone(),
+    }
+}
+
+/// Tool calls requested in a complete response
+fn parse_tool_calls(provider: &Provider, raw: &Value) -> Vec<LlmToolCall> {
+    let calls = match provider {
+        Provider::OpenAI | Provider::OpenAICompatible | Provider::AzureOpenAI => {
+            &raw["choices"][0]["message"]["tool_calls"]
+        }
+        Provider::Ollama => &raw["message"]["tool_calls"],
+        Provider::Anthropic => &raw["content"],
+        Provider::Google => &raw["candidates"][0]["content"]["parts"],
+        _ => return Vec::new(),
+    };
+    let Some(calls) = calls.as_array() else {
+        return Vec::new();
+    };
+
+    calls
+        .iter()
+        .enumerate()
+        .filter_map(|(index, call)| match provider {
+            Provider::Anthropic => (call["type"] == "tool_use").then(|| LlmToolCall {
+                id: call["id"].as_str().unwrap_or_default().to_string(),
+                name: call["name"].as_str().unwrap_or_default().to_string(),
+                arguments: call["input"].clone(),
+            }),
+            Provider::Google => call.get("functionCall").map(|function| LlmToolCall {
+                id: function["name"].as_str().unwrap_or_default().to_string(),
+                name: function["name"].as_str().unwrap_or_default().to_string(),
+                arguments: function["args"].clone(),
+            }),
+            _ => {
+                let function = &call["function"];
+                // OpenAI encodes the arguments as a JSON string
+                let arguments = match &function["arguments"] {
+                    Value::String(text) => {
+                        serde_json::from_str(text).unwrap_or_else(|_| Value::String(text.clone()))
+                    }
+                    other => other.clone(),
+                };
+                Some(LlmToolCall {
+                    id: call["id"]
+                        .as_str()
+                        .map(String::from)
+                        .unwrap_or_else(|| format!("call_{}", index)),
+                    name: function["name"].as_str().unwrap_or_default().to_string(),
+                    arguments,
+                })
+            }
+        })
+        .collect()
+}
+
+/// Append the model's tool calls and their outputs to the conversation
+fn append_tool_turn(
+    provider: &Provider,
+    body: &mut Value,
+    raw: &Value,
+    calls: &[LlmToolCall],
+    outputs: &[LlmToolOutput],
+) {
+    let results = calls.iter().zip(outputs);
+    let (history, turn): (&str, Vec<Value>) = match provider {
+        Provider::OpenAI | Provider::OpenAICompatible | Provider::AzureOpenAI => {
+            let mut turn = vec![raw["choices"][0]["message"].clone()];
+            turn.extend(results.map(|(call, output)| {
+                json!({"role": "tool", "tool_call_id": call.id, "content": output.content})
+            }));
+            ("messages", turn)
+        }
+        Provider::Ollama => {
+            let mut turn = vec![raw["message"].clone()];
+            turn.extend(results.map(|(call, output)| {
+                json!({"role": "tool", "tool_name": call.name, "content": output.content})
+            }));
+            ("messages", turn)
+        }
+        Provider::Anthropic => {
+            let results: Vec<Value> = results
+                .map(|(call, output)| {
+                    json!({
+                        "type": "tool_result",
+                        "tool_use_id": call.id,
+                        "content": output.content,
+                        "is_error": output.is_error,
+                    })
+                })
+                .collect();
+            (
+                "messages",
+                vec![
+                    json!({"role": "assistant", "content": raw["content"]}),
+                    json!({"role": "user", "content": results}),
+                ],
+            )
+        }
+        Provider::Google => {
+            let parts: Vec<Value> = results
+                .map(|(call, output)| {
+                    let key = if output.is_error { "error" } else { "result" };
+                    json!({
+                        "functionResponse": {
+                            "name": call.name,
+                            "response": {key: output.content},
+                        }
+                    })
+                })
+                .collect();
+            (
+                "contents",
+                vec![
+                    raw["candidates"][0]["content"].clone(),
+                    json!({"role": "user", "parts": parts}),
+                ],
+            )
+        }
+        _ => return,
+    };
+
+    if let Some(messages) = body[history].as_array_mut() {
+        messages.extend(turn);
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::collections::HashMap;
+
+    fn request(provider: Provider) -> LlmRequest {
+        LlmRequest {
+            provider,
+            model: "test-model".to_string(),
+            prompt: "And now?".to_string(),
+            system_prompt: Some("Be brief".to_string()),
+            endpoint: Some("http://localhost:1".to_string()),
+            api_key: Some("key".to_string()),
+            auth: None,
+            auth_header: None,
+            api_version: None,
+            temperature: None,
+            max_tokens: None,
+            top_p: None,
+            top_k: None,
+            stop: vec![],
+            timeout_secs: None,
+            extra_params: HashMap::new(),
+            response_schema: None,
+            messages: vec![LlmMessage::user("Hi"), LlmMessage::assistant("Hello")],
+            tools: vec![],
+            max_tool_iterations: None,
+        }
+    }
+
+    fn body(provider: Provider) -> Value {
+        let client = HttpLlmClient::new();
+        client.build_request(&request(provider), true).unwrap().body
+    }
 
     #[test]
     fn test_client_creation() {
@@ -531,7 +1103,192 @@ mod tests {
         assert!(client.supports_provider(&Provider::OpenAI));
         assert!(client.supports_provider(&Provider::Anthropic));
         assert!(client.supports_provider(&Provider::Google));
+        assert!(client.supports_provider(&Provider::OpenAICompatible));
+        assert!(client.supports_provider(&Provider::AzureOpenAI));
         assert!(!client.supports_provider(&Provider::Claude));
         assert!(!client.supports_provider(&Provider::Codex));
     }
+
+    #[test]
+    fn test_openai_compatible_and_azure_calls() {
+        let client = HttpLlmClient::new();
+
+        let mut compatible = request(Provider::OpenAICompatible);
+        compatible.endpoint = Some("http://localhost:8000/v1/".to_string());
+        let call = client.build_request(&compatible, false).unwrap();
+        assert_eq!(call.url, "http://localhost:8000/v1/chat/completions");
+        assert_eq!(
+            call.headers,
+            vec![("Authorization".to_string(), "Bearer key".to_string())]
+        );
+
+        compatible.auth = Some(AuthStyle::ApiKey);
+        compatible.auth_header = Some("X-Api-Key".to_string());
+        let call = client.build_request(&compatible, false).unwrap();
+        assert_eq!(
+            call.headers,
+            vec![("X-Api-Key".to_string(), "key".to_string())]
+        );
+
+        compatible.auth = Some(AuthStyle::None);
+        assert!(client
+            .build_request(&compatible, false)
+            .unwrap()
+            .headers
+            .is_empty());
+
+        compatible.endpoint = None;
+        assert!(matches!(
+            client.build_request(&compatible, false),
+            Err(LlmError::InvalidConfig(_))
+        ));
+
+        let mut azure = request(Provider::AzureOpenAI);
+        azure.endpoint = Some("https://example.openai.azure.com".to_string());
+        azure.model = "gpt4o-prod".to_string();
+        let call = client.build_request(&azure, false).unwrap();
+        assert_eq!(
+            call.url,
+            "https://example.openai.azure.com/openai/deployments/gpt4o-prod/chat/completions?api-version=2024-10-21"
+        );
+        assert_eq!(
+            call.headers,
+            vec![("api-key".to_string(), "key".to_string())]
+        );
+
+        azure.api_version = Some("2025-01-01-preview".to_string());
+        let call = client.build_request(&azure, false).unwrap();
+        assert!(call.url.ends_with("?api-version=2025-01-01-preview"));
+    }
+
+    #[test]
+    fn test_conversation_is_sent_with_roles() {
+        let openai = body(Provider::OpenAI);
+        assert_eq!(openai["stream"], json!(true));
+        assert_eq!(
+            openai["messages"],
+            json!([
+                {"role": "system", "content": "Be brief"},
+                {"role": "user", "content": "Hi"},
+                {"role": "assistant", "content": "Hello"},
+                {"role": "user", "content": "And now?"},
+            ])
+        );
+
+        let anthropic = body(Provider::Anthropic);
+        assert_eq!(anthropic["system"], json!("Be brief"));
+        assert_eq!(anthropic["messages"].as_array().unwrap().len(), 3);
+
+        let google = body(Provider::Google);
+        assert_eq!(
+            google["systemInstruction"]["parts"][0]["text"],
+            json!("Be brief")
+        );
+        assert_eq!(google["contents"][1]["role"], json!("model"));
+
+        let ollama = body(Provider::Ollama);
+        assert_eq!(ollama["messages"][2]["role"], json!("assistant"));
+    }
+
+    #[test]
+    fn test_anthropic_tool_turn() {
+        let raw = json!({
+            "content": [
+                {"type": "text", "text": "Checking."},
+                {"type": "tool_use", "id": "toolu_1", "name": "lookup", "input": {"q": "x"}}
+            ],
+            "stop_reason": "tool_use"
+        });
+        let calls = parse_tool_calls(&Provider::Anthropic, &raw);
+        assert_eq!(
+            calls,
+            vec![LlmToolCall {
+                id: "toolu_1".to_string(),
+                name: "lookup".to_string(),
+                arguments: json!({"q": "x"}),
+            }]
+        );
+
+        let mut body = json!({"messages": []});
+        append_tool_turn(
+            &Provider::Anthropic,
+            &mut body,
+            &raw,
+            &calls,
+            &[LlmToolOutput::error("not found")],
+        );
+        assert_eq!(body["messages"][0]["role"], json!("assistant"));
+        assert_eq!(
+            body["messages"][1]["content"][0],
+            json!({"type": "tool_result", "tool_use_id": "toolu_1", "content": "not found", "is_error": true})
+        );
+    }
+
+    #[test]
+    fn test_google_tool_declarations_and_turn() {
+        let mut request = request(Provider::Google);
+        request.tools = vec![crate::ports::secondary::LlmToolDefinition {
+            name: "lookup".to_string(),
+            description: "Look up".to_string(),
+            parameters: json!({"type": "object", "additionalProperties": false}),
+        }];
+        let declarations = tool_declarations(&request);
+        assert_eq!(
+            declarations[0]["functionDeclarations"][0]["parameters"],
+            json!({"type": "object"})
+        );
+
+        let raw = json!({"candidates": [{"content": {"role": "model", "parts": [
+            {"functionCall": {"name": "lookup", "args": {"q": "x"}}}
+        ]}}]});
+        let calls = parse_tool_calls(&Provider::Google, &raw);
+        let mut body = json!({"contents": []});
+        append_tool_turn(
+            &Provider::Google,
+            &mut body,
+            &raw,
+            &calls,
+            &[LlmToolOutput::ok("42")],
+        );
+        assert_eq!(body["contents"][0]["role"], json!("model"));
+        assert_eq!(
+            body["contents"][1]["parts"][0]["functionResponse"],
+            json!({"name": "lookup", "response": {"result": "42"}})
+        );
+    }
+
+    #[test]
+    fn test_stream_state_parses_sse_deltas() {
+        let mut state = StreamState::new(&request(Provider::Anthropic));
+        let lines = [
+            "event: message_start",
+            r#"data: {"type":"message_start","message":{"model":"claude","usage":{"input_tokens":5}}}"#,
+            r#"data: {"type":"content_block_delta","delta":{"type":"text_delta","text":"Hel"}}"#,
+            r#"data: {"type":"content_block_delta","delta":{"type":"text_delta","text":"lo"}}"#,
+            r#"data: {"type":"message_delta","delta":{"stop_reason":"end_turn"},"usage":{"output_tokens":2}}"#,
+        ];
+        let deltas: Vec<String> = lines
+            .iter()
+            .filter_map(|line| state.apply_line(line).unwrap())
+            .collect();
+        assert_eq!(deltas, vec!["Hel", "lo"]);
+
+        let response = state.into_response();
+        assert_eq!(response.content, "Hello");
+        assert_eq!(response.model, "claude");
+        assert_eq!(response.finish_reason.as_deref(), Some("end_turn"));
+        assert_eq!(response.usage.unwrap().total_tokens, 7);
+    }
+
+    #[test]
+    fn test_stream_state_reports_errors() {
+        let mut state = StreamState::new(&request(Provider::Ollama));
+        assert_eq!(
+            state
+                .apply_line(r#"{"message":{"content":"Hi"},"done":false}"#)
+                .unwrap(),
+            Some("Hi".to_string())
+        );
+        assert!(state.apply_line(r#"{"error":"model crashed"}"#).is_err());
+    }
 }
@@ -0,0 +1,699 @@
+//! MCP Client Implementations
+//!
+//! Connects to external MCP servers over stdio (newline-delimited JSON-RPC on a
+//! child process), HTTP (streamable HTTP with JSON or SSE responses) or the
+//! legacy HTTP+SSE transport, and exposes them through the `McpServer` port.
+
+use async_trait::async_trait;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::process::Stdio;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader, Lines};
+use tokio::process::{Child, ChildStdin, ChildStdout, Command};
+use tokio::sync::{oneshot, Mutex};
+use tokio::task::JoinHandle;
+
+use crate::error::{Error, Result};
+use crate::ports::secondary::{McpServer, ToolDefinition, ToolResult};
+
+/// MCP protocol version requested during the initialize handshake
+pub const MCP_PROTOCOL_VERSION: &str = "2024-11-05";
+
+/// Build a JSON-RPC request message
+fn rpc_request(id: u64, method: &str, params: Value) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "id": id,
+        "method": method,
+        "params": params,
+    })
+}
+
+/// Build a JSON-RPC notification message
+fn rpc_notification(method: &str) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "method": method,
+    })
+}
+
+/// Parameters sent with the `initialize` request
+fn initialize_params() -> Value {
+    json!({
+        "protocolVersion": MCP_PROTOCOL_VERSION,
+        "capabilities": {},
+        "clientInfo": {
+            "name": "periplon",
+            "version": env!("CARGO_PKG_VERSION"),
+        },
+    })
+}
+
+/// Extract the result from a JSON-RPC response, converting errors
+fn rpc_result(server: &str, method: &str, response: Value) -> Result<Value> {
+    if let Some(error) = response.get("error") {
+        let message = error
+            .get("message")
+            .and_then(|m| m.as_str())
+            .unwrap_or("unknown error");
+        return Err(Error::InvalidInput(format!(
+            "MCP server '{}' returned error for '{}': {}",
+            server, method, message
+        )));
+    }
+    Ok(response.get("result").cloned().unwrap_or(Value::Null))
+}
+
+/// Parse a `tools/list` result into tool definitions
+fn parse_tool_list(result: &Value) -> Vec<ToolDefinition> {
+    result
+        .get("tools")
+        .and_then(|t| t.as_array())
+        .map(|tools| {
+            tools
+                .iter()
+                .filter_map(|tool| {
+                    Some(ToolDefinition {
+                        name: tool.get("name")?.as_str()?.to_string(),
+                        description: tool
+                            .get("description")
+                            .and_then(|d| d.as_str())
+                            .unwrap_or_default()
+                            .to_string(),
+                        input_schema: tool.get("inputSchema").cloned().unwrap_or(json!({})),
+                    })
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Parse a `tools/call` result
+///
+/// Structured content is preferred when the server provides it; otherwise the
+/// raw content block array is returned.
+fn parse_tool_result(result: Value) -> ToolResult {
+    let is_error = result
+        .get("isError")
+        .and_then(|e| e.as_bool())
+        .unwrap_or(false);
+    let content = result
+        .get("structuredContent")
+        .filter(|c| !c.is_null())
+        .or_else(|| result.get("content"))
+        .cloned()
+        .unwrap_or(Value::Null);
+    ToolResult { content, is_error }
+}
+
+/// Parse one server-sent event into its type and data
+///
+/// Events without a `data:` line are skipped; the type defaults to `message`.
+fn parse_sse_event(event: &str) -> Option<(String, String)> {
+    let mut kind = "message";
+    let mut data = Vec::new();
+    for line in event.lines() {
+        if let Some(value) = line.strip_prefix("event:") {
+            kind = value.trim();
+        } else if let Some(value) = line.strip_prefix("data:") {
+            data.push(value.trim_start());
+        }
+    }
+    (!data.is_empty()).then(|| (kind.to_string(), data.join("\n")))
+}
+
+/// Find the JSON-RPC response with the given id in a server-sent event stream
+fn find_sse_response(body: &str, id: u64) -> Option<Value> {
+    body.split("\n\n")
+        .filter_map(parse_sse_event)
+        .filter_map(|(_, data)| serde_json::from_str::<Value>(&data).ok())
+        .find(|message| message.get("id").and_then(|i| i.as_u64()) == Some(id))
+}
+
+/// Pipes of a running stdio server
+struct StdioChannel {
+    stdin: ChildStdin,
+    stdout: Lines<BufReader<ChildStdout>>,
+}
+
+/// MCP client for servers launched as child processes speaking JSON-RPC on stdio
+pub struct StdioMcpClient {
+    name: String,
+    channel: Mutex<StdioChannel>,
+    next_id: AtomicU64,
+    // Held so the server is terminated when the client is dropped
+    _child: Child,
+}
+
+impl StdioMcpClient {
+    /// Launch the server process and perform the initialize handshake
+    pub async fn connect(
+        name: &str,
+        command: &str,
+        args: &[String],
+        env: &HashMap<String, String>,
+    ) -> Result<Self> {
+        let mut child = Command::new(command)
+            .args(args)
+            .envs(env)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::inherit())
+            .kill_on_drop(true)
+            .spawn()
+            .map_err(|e| {
+                Error::InvalidInput(format!(
+                    "Failed to start MCP server '{}' ({}): {}",
+                    name, command, e
+                ))
+            })?;
+
+        let stdin = child.stdin.take().ok_or(Error::StdioError)?;
+        let stdout = child.stdout.take().ok_or(Error::StdioError)?;
+
+        let client = Self {
+            name: name.to_string(),
+            channel: Mutex::new(StdioChannel {
+                stdin,
+                stdout: BufReader::new(stdout).lines(),
+            }),
+            next_id: AtomicU64::new(1),
+            _child: child,
+        };
+
+        client.request("initialize", initialize_params()).await?;
+        client.notify("notifications/initialized").await?;
+
+        Ok(client)
+    }
+
+    async fn write_message(channel: &mut StdioChannel, message: &Value) -> Result<()> {
+        let mut line = serde_json::to_string(message)?;
+        line.push('\n');
+        channel.stdin.write_all(line.as_bytes()).await?;
+        channel.stdin.flush().await?;
+        Ok(())
+    }
+
+    async fn notify(&self, method: &str) -> Result<()> {
+        let mut channel = self.channel.lock().await;
+        Self::write_message(&mut channel, &rpc_notification(method)).await
+    }
+
+    /// Send a request and wait for its response
+    async fn request(&self, method: &str, params: Value) -> Result<Value> {
+        let id = self.next_id.fetch_add(1, Ordering::SeqCst);
+        let mut channel = self.channel.lock().await;
+        Self::write_message(&mut channel, &rpc_request(id, method, params)).await?;
+
+        loop {
+            let line = channel.stdout.next_line().await?.ok_or_else(|| {
+                Error::InvalidInput(format!(
+                    "MCP server '{}' closed its output while waiting for '{}'",
+                    self.name, method
+                ))
+            })?;
+            let trimmed = line.trim();
+            if trimmed.is_empty() {
+                continue;
+            }
+
+            // Servers may log non-JSON lines; ignore them
+            let Ok(message) = serde_json::from_str::<Value>(trimmed) else {
+                continue;
+            };
+
+            if message.get("id").and_then(|i| i.as_u64()) == Some(id)
+                && message.get("method").is_none()
+            {
+                return rpc_result(&self.name, method, message);
+            }
+
+            // Server-initiated requests are not supported; reply so the server isn't left waiting
+            if let (Some(request_id), Some(_)) = (message.get("id"), message.get("method")) {
+                let reply = json!({
+                    "jsonrpc": "2.0",
+                    "id": request_id,
+                    "error": {"code": -32601, "message": "Method not supported by client"},
+                });
+                Self::write_message(&mut channel, &reply).await?;
+            }
+            // Notifications are ignored
+        }
+    }
+}
+
+#[async_trait]
+impl McpServer for StdioMcpClient {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    async fn list_tools(&self) -> Result<Vec<ToolDefinition>> {
+        let result = self.request("tools/list", json!({})).await?;
+        Ok(parse_tool_list(&result))
+    }
+
+    async fn call_tool(&self, name: &str, args: Value) -> Result<ToolResult> {
+        let result = self
+            .request("tools/call", json!({"name": name, "arguments": args}))
+            .await?;
+        Ok(parse_tool_result(result))
+    }
+}
+
+/// MCP client for servers reachable over streamable HTTP (JSON or SSE responses)
+pub struct HttpMcpClient {
+    name: String,
+    url: String,
+    headers: HashMap<String, String>,
+    client: reqwest::Client,
+    session_id: Mutex<Option<String>>,
+    next_id: AtomicU64,
+}
+
+impl HttpMcpClient {
+    /// Connect to the server and perform the initialize handshake
+    pub async fn connect(name: &str, url: &str, headers: &HashMap<String, String>) -> Result<Self> {
+        let client = Self {
+            name: name.to_string(),
+            url: url.to_string(),
+            headers: headers.clone(),
+            client: reqwest::Client::new(),
+            session_id: Mutex::new(None),
+            next_id: AtomicU64::new(1),
+        };
+
+        client.request("initialize", initialize_params()).await?;
+        client.notify("notifications/initialized").await?;
+
+        Ok(client)
+    }
+
+    fn build_post(&self, body: &Value, session_id: Option<&str>) -> reqwest::RequestBuilder {
+        let mut request = self
+            .client
+            .post(&self.url)
+            .header("Accept", "application/json, text/event-stream")
+            .json(body);
+        for (key, value) in &self.headers {
+            request = request.header(key, value);
+        }
+        if let Some(session_id) = session_id {
+            request = request.header("Mcp-Session-Id", session_id);
+        }
+        request
+    }
+
+    async fn notify(&self, method: &str) -> Result<()> {
+        let session_id = self.session_id.lock().await.clone();
+        self.build_post(&rpc_notification(method), session_id.as_deref())
+            .send()
+            .await
+            .map_err(|e| {
+                Error::InvalidInput(format!("MCP server '{}' request failed: {}", self.name, e))
+            })?;
+        Ok(())
+    }
+
+    /// Send a request and wait for its response
+    async fn request(&self, method: &str, params: Value) -> Result<Value> {
+        let id = self.next_id.fetch_add(1, Ordering::SeqCst);
+        let session_id = self.session_id.lock().await.clone();
+
+        let response = self
+            .build_post(&rpc_request(id, method, params), session_id.as_deref())
+            .send()
+            .await
+            .map_err(|e| {
+                Error::InvalidInput(format!("MCP server '{}' request failed: {}", self.name, e))
+            })?;
+
+        let status = response.status();
+        if !status.is_success() {
+            return Err(Error::InvalidInput(format!(
+                "MCP server '{}' returned status {} for '{}'",
+                self.name, status, method
+            )));
+        }
+
+        // Remember the session assigned during initialization
+        if let Some(new_session) = response
+            .headers()
+            .get("mcp-session-id")
+            .and_then(|v| v.to_str().ok())
+        {
+            *self.session_id.lock().await = Some(new_session.to_string());
+        }
+
+        let is_sse = response
+            .headers()
+            .get(reqwest::header::CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .is_some_and(|ct| ct.starts_with("text/event-stream"));
+
+        let body = response.text().await.map_err(|e| {
+            Error::InvalidInput(format!(
+                "Failed to read response from MCP server '{}': {}",
+                self.name, e
+            ))
+        })?;
+
+        let message = if is_sse {
+            find_sse_response(&body, id).ok_or_else(|| {
+                Error::InvalidInput(format!(
+                    "MCP server '{}' sent no response for '{}'",
+                    self.name, method
+                ))
+            })?
+        } else {
+            serde_json::from_str(&body)?
+        };
+
+        rpc_result(&self.name, method, message)
+    }
+}
+
+#[async_trait]
+impl McpServer for HttpMcpClient {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    async fn list_tools(&self) -> Result<Vec<ToolDefinition>> {
+        let result = self.request("tools/list", json!({})).await?;
+        Ok(parse_tool_list(&result))
+    }
+
+    async fn call_tool(&self, name: &str, args: Value) -> Result<ToolResult> {
+        let result = self
+            .request("tools/call", json!({"name": name, "arguments": args}))
+            .await?;
+        Ok(parse_tool_result(result))
+    }
+}
+
+/// Requests waiting for their response on the event stream, by id
+///
+/// `None` once the stream has ended, so later requests fail immediately.
+type PendingResponses = Arc<std::sync::Mutex<Option<HashMap<u64, oneshot::Sender<Value>>>>>;
+
+/// MCP client for servers using the legacy HTTP+SSE transport
+///
+/// The client keeps a GET event stream open. The server first announces, in an
+/// `endpoint` event, the URL to POST messages to, then sends the responses to
+/// those messages as `message` events on the stream.
+pub struct SseMcpClient {
+    name: String,
+    endpoint: String,
+    headers: HashMap<String, String>,
+    client: reqwest::Client,
+    pending: PendingResponses,
+    next_id: AtomicU64,
+    // Reads the event stream until the client is dropped
+    reader: JoinHandle<()>,
+}
+
+impl SseMcpClient {
+    /// Open the event stream, wait for the message endpoint and perform the
+    /// initialize handshake
+    pub async fn connect(name: &str, url: &str, headers: &HashMap<String, String>) -> Result<Self> {
+        let client = reqwest::Client::new();
+        let mut request = client.get(url).header("Accept", "text/event-stream");
+        for (key, value) in headers {
+            request = request.header(key, value);
+        }
+        let response = request.send().await.map_err(|e| {
+            Error::InvalidInput(format!("MCP server '{}' request failed: {}", name, e))
+        })?;
+        if !response.status().is_success() {
+            return Err(Error::InvalidInput(format!(
+                "MCP server '{}' returned status {} for its event stream",
+                name,
+                response.status()
+            )));
+        }
+
+        let pending: PendingResponses = Arc::new(std::sync::Mutex::new(Some(HashMap::new())));
+        let (endpoint_tx, endpoint_rx) = oneshot::channel();
+        let reader = tokio::spawn(Self::read_events(
+            response,
+            endpoint_tx,
+            pending.clone(),
+            client.clone(),
+            headers.clone(),
+        ));
+
+        let endpoint = endpoint_rx
+            .await
+            .map_err(|_| {
+                Error::InvalidInput(format!(
+                    "MCP server '{}' closed its event stream before announcing an endpoint",
+                    name
+                ))
+            })?
+            .map_err(|endpoint| {
+                Error::InvalidInput(format!(
+                    "MCP server '{}' announced an invalid endpoint '{}'",
+                    name, endpoint
+                ))
+            })?;
+
+        let client = Self {
+            name: name.to_string(),
+            endpoint: endpoint.to_string(),
+            headers: headers.clone(),
+            client,
+            pending,
+            next_id: AtomicU64::new(1),
+            reader,
+        };
+
+        client.request("initialize", initialize_params()).await?;
+        client.notify("notifications/initialized").await?;
+
+        Ok(client)
+    }
+
+    /// Dispatch events from the stream until it ends
+    async fn read_events(
+        mut response: reqwest::Response,
+        endpoint_tx: oneshot::Sender<std::result::Result<reqwest::Url, String>>,
+        pending: PendingResponses,
+        client: reqwest::Client,
+        headers: HashMap<String, String>,
+    ) {
+        let base = response.url().clone();
+        let mut endpoint_tx = Some(endpoint_tx);
+        let mut endpoint: Option<reqwest::Url> = None;
+        let mut buffer: Vec<u8> = Vec::new();
+
+        while let Ok(Some(chunk)) = response.chunk().await {
+            // Events end with a blank line; CRLF line endings are reduced to LF
+            buffer.extend(chunk.iter().filter(|&&b| b != b'\r'));
+            let Some(end) = buffer.windows(2).rposition(|pair| pair == b"\n\n") else {
+                continue;
+            };
+            let complete: Vec<u8> = buffer.drain(..end + 2).collect();
+            let complete = String::from_utf8_lossy(&complete);
+
+            for (kind, data) in complete.split("\n\n").filter_map(parse_sse_event) {
+                if kind == "endpoint" {
+                    let joined = base.join(data.trim()).map_err(|_| data.trim().to_string());
+                    endpoint = joined.clone().ok();
+                    if let Some(tx) = endpoint_tx.take() {
+                        let _ = tx.send(joined);
+                    }
+                    continue;
+                }
+                let Ok(message) = serde_json::from_str::<Value>(&data) else {
+                    continue;
+                };
+
+                match (message.get("id"), message.get("method")) {
+                    (Some(id), None) => {
+                        let sender = id.as_u64().and_then(|id| {
+                            pending
+                                .lock()
+                                .unwrap()
+                                .as_mut()
+                                .and_then(|waiting| waiting.remove(&id))
+                        });
+                        if let Some(sender) = sender {
+                            let _ = sender.send(message);
+                        }
+                    }
+                    // Server-initiated requests are not supported; reply so the server isn't left waiting
+                    (Some(request_id), Some(_)) => {
+                        if let Some(endpoint) = &endpoint {
+                            let reply = json!({
+                                "jsonrpc": "2.0",
+                                "id": request_id,
+                                "error": {"code": -32601, "message": "Method not supported by client"},
+                            });
+                            let mut request = client.post(endpoint.clone()).json(&reply);
+                            for (key, value) in &headers {
+                                request = request.header(key, value);
+                            }
+                            let _ = request.send().await;
+                        }
+                    }
+                    // Notifications are ignored
+                    _ => {}
+                }
+            }
+        }
+
+        // Fail the requests still waiting, and any made later
+        pending.lock().unwrap().take();
+    }
+
+    async fn post(&self, message: &Value) -> Result<()> {
+        let mut request = self.client.post(&self.endpoint).json(message);
+        for (key, value) in &self.headers {
+            request = request.header(key, value);
+        }
+        let response = request.send().await.map_err(|e| {
+            Error::InvalidInput(format!("MCP server '{}' request failed: {}", self.name, e))
+        })?;
+        if !response.status().is_success() {
+            return Err(Error::InvalidInput(format!(
+                "MCP server '{}' returned status {}",
+                self.name,
+                response.status()
+            )));
+        }
+        Ok(())
+    }
+
+    async fn notify(&self, method: &str) -> Result<()> {
+        self.post(&rpc_notification(method)).await
+    }
+
+    /// Send a request and wait for its response on the event stream
+    async fn request(&self, method: &str, params: Value) -> Result<Value> {
+        let closed = || {
+            Error::InvalidInput(format!(
+                "MCP server '{}' closed its event stream while waiting for '{}'",
+                self.name, method
+            ))
+        };
+
+        let id = self.next_id.fetch_add(1, Ordering::SeqCst);
+        let (tx, rx) = oneshot::channel();
+        self.pending
+            .lock()
+            .unwrap()
+            .as_mut()
+            .ok_or_else(closed)?
+            .insert(id, tx);
+
+        if let Err(e) = self.post(&rpc_request(id, method, params)).await {
+            if let Some(waiting) = self.pending.lock().unwrap().as_mut() {
+                waiting.remove(&id);
+            }
+            return Err(e);
+        }
+
+        let message = rx.await.map_err(|_| closed())?;
+        rpc_result(&self.name, method, message)
+    }
+}
+
+impl Drop for SseMcpClient {
+    fn drop(&mut self) {
+        self.reader.abort();
+    }
+}
+
+#[async_trait]
+impl McpServer for SseMcpClient {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    async fn list_tools(&self) -> Result<Vec<ToolDefinition>> {
+        let result = self.request("tools/list", json!({})).await?;
+        Ok(parse_tool_list(&result))
+    }
+
+    async fn call_tool(&self, name: &str, args: Value) -> Result<ToolResult> {
+        let result = self
+            .request("tools/call", json!({"name": name, "arguments": args}))
+            .await?;
+        Ok(parse_tool_result(result))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_tool_list() {
+        let result = json!({
+            "tools": [
+                {"name": "search", "description": "Search docs", "inputSchema": {"type": "object"}},
+                {"name": "ping"},
+                {"description": "missing name"}
+            ]
+        });
+        let tools = parse_tool_list(&result);
+        assert_eq!(tools.len(), 2);
+        assert_eq!(tools[0].name, "search");
+        assert_eq!(tools[0].description, "Search docs");
+        assert_eq!(tools[1].input_schema, json!({}));
+    }
+
+    #[test]
+    fn test_parse_tool_result_prefers_structured_content() {
+        let result = parse_tool_result(json!({
+            "content": [{"type": "text", "text": "{\"count\":3}"}],
+            "structuredContent": {"count": 3}
+        }));
+        assert!(!result.is_error);
+        assert_eq!(result.content, json!({"count": 3}));
+
+        let result = parse_tool_result(json!({
+            "content": [{"type": "text", "text": "boom"}],
+            "isError": true
+        }));
+        assert!(result.is_error);
+        assert_eq!(result.content[0]["text"], "boom");
+    }
+
+    #[test]
+    fn test_rpc_result_error() {
+        let err = rpc_result(
+            "files",
+            "tools/call",
+            json!({"jsonrpc": "2.0", "id": 1, "error": {"code": -32602, "message": "bad args"}}),
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("bad args"));
+    }
+
+    #[test]
+    fn test_find_sse_response() {
+        let body = "event: message\ndata: {\"jsonrpc\":\"2.0\",\"method\":\"notifications/progress\"}\n\n\
+                    event: message\ndata: {\"jsonrpc\":\"2.0\",\"id\":4,\"result\":{\"ok\":true}}\n\n";
+        let message = find_sse_response(body, 4).unwrap();
+        assert_eq!(message["result"]["ok"], true);
+        assert!(find_sse_response(body, 5).is_none());
+    }
+
+    #[test]
+    fn test_parse_sse_event() {
+        assert_eq!(
+            parse_sse_event("event: endpoint\ndata: /messages?session=1"),
+            Some(("endpoint".to_string(), "/messages?session=1".to_string()))
+        );
+        assert_eq!(
+            parse_sse_event("data: {\"a\":\ndata: 1}"),
+            Some(("message".to_string(), "{\"a\":\n1}".to_string()))
+        );
+        assert_eq!(parse_sse_event(": keep-alive"), None);
+    }
+}
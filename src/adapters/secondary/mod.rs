@@ -1,11 +1,15 @@
 pub mod callback_hook;
 pub mod callback_permission;
 pub mod http_llm_client;
+pub mod mcp_client;
 pub mod mock_transport;
+pub mod sdk_mcp_server;
 pub mod subprocess_transport;
 
 pub use callback_hook::*;
 pub use callback_permission::*;
 pub use http_llm_client::*;
+pub use mcp_client::*;
 pub use mock_transport::*;
+pub use sdk_mcp_server::*;
 pub use subprocess_transport::*;
@@ -0,0 +1,229 @@
+//! In-Process MCP Server
+//!
+//! Implements the `McpServer` port with Rust functions, so tools can be offered
+//! to the CLI without running a separate MCP server process. Register the server
+//! with `AgentOptions::add_sdk_mcp_server`; the CLI then calls it through the
+//! control protocol.
+
+use async_trait::async_trait;
+use futures::future::BoxFuture;
+use schemars::JsonSchema;
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use std::future::Future;
+use std::sync::Arc;
+
+use crate::error::{Error, Result};
+use crate::ports::secondary::{McpServer, ToolDefinition, ToolResult};
+
+/// Handler of one tool, taking the raw JSON arguments
+type ToolHandler = Arc<dyn Fn(Value) -> BoxFuture<'static, Result<ToolResult>> + Send + Sync>;
+
+struct SdkTool {
+    definition: ToolDefinition,
+    handler: ToolHandler,
+}
+
+/// MCP server whose tools are Rust functions
+///
+/// # Example
+///
+/// ```no_run
+/// use periplon_sdk::adapters::secondary::SdkMcpServer;
+/// use periplon_sdk::ports::secondary::ToolResult;
+/// use periplon_sdk::AgentOptions;
+/// use schemars::JsonSchema;
+/// use serde::Deserialize;
+/// use std::sync::Arc;
+///
+/// #[derive(Deserialize, JsonSchema)]
+/// struct AddArgs {
+///     /// First number
+///     a: f64,
+///     /// Second number
+///     b: f64,
+/// }
+///
+/// let calculator = SdkMcpServer::builder("calculator")
+///     .tool("add", "Add two numbers", |args: AddArgs| async move {
+///         Ok(ToolResult::text((args.a + args.b).to_string()))
+///     })
+///     .build();
+///
+/// let mut options = AgentOptions {
+///     allowed_tools: vec!["mcp__calculator__add".to_string()],
+///     ..Default::default()
+/// };
+/// options.add_sdk_mcp_server(Arc::new(calculator));
+/// ```
+pub struct SdkMcpServer {
+    name: String,
+    tools: Vec<SdkTool>,
+}
+
+impl SdkMcpServer {
+    /// Start declaring a server with the given name
+    pub fn builder(name: impl Into<String>) -> SdkMcpServerBuilder {
+        SdkMcpServerBuilder {
+            name: name.into(),
+            tools: Vec::new(),
+        }
+    }
+}
+
+/// Declares the tools of an `SdkMcpServer`
+pub struct SdkMcpServerBuilder {
+    name: String,
+    tools: Vec<SdkTool>,
+}
+
+impl SdkMcpServerBuilder {
+    /// Add a tool taking arguments of type `I`
+    ///
+    /// The input schema is derived from `I` with `schemars`, so doc comments
+    /// on its fields become parameter descriptions. `I` should be a struct,
+    /// since MCP tool arguments are JSON objects. Arguments that do not
+    /// deserialize into `I`, and errors returned by the handler, are reported
+    /// to the model as failed tool results.
+    pub fn tool<I, F, Fut>(
+        mut self,
+        name: impl Into<String>,
+        description: impl Into<String>,
+        handler: F,
+    ) -> Self
+    where
+        I: DeserializeOwned + JsonSchema,
+        F: Fn(I) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<ToolResult>> + Send + 'static,
+    {
+        let name = name.into();
+        let input_schema = serde_json::to_value(schemars::schema_for!(I))
+            .unwrap_or_else(|_| serde_json::json!({"type": "object"}));
+
+        let tool_name = name.clone();
+        let handler: ToolHandler = Arc::new(move |args| match serde_json::from_value::<I>(args) {
+            Ok(input) => Box::pin(handler(input)),
+            Err(e) => {
+                let message = format!("Invalid arguments for tool '{}': {}", tool_name, e);
+                Box::pin(async move { Ok(ToolResult::error(message)) })
+            }
+        });
+
+        self.tools.push(SdkTool {
+            definition: ToolDefinition {
+                name,
+                description: description.into(),
+                input_schema,
+            },
+            handler,
+        });
+        self
+    }
+
+    pub fn build(self) -> SdkMcpServer {
+        SdkMcpServer {
+            name: self.name,
+            tools: self.tools,
+        }
+    }
+}
+
+#[async_trait]
+impl McpServer for SdkMcpServer {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    async fn list_tools(&self) -> Result<Vec<ToolDefinition>> {
+        Ok(self
+            .tools
+            .iter()
+            .map(|tool| tool.definition.clone())
+            .collect())
+    }
+
+    async fn call_tool(&self, name: &str, args: Value) -> Result<ToolResult> {
+        let tool = self
+            .tools
+            .iter()
+            .find(|tool| tool.definition.name == name)
+            .ok_or_else(|| {
+                Error::InvalidInput(format!(
+                    "Tool '{}' not found on MCP server '{}'",
+                    name, self.name
+                ))
+            })?;
+
+        Ok((tool.handler)(args)
+            .await
+            .unwrap_or_else(|e| ToolResult::error(e.to_string())))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+    use serde_json::json;
+
+    #[derive(Deserialize, JsonSchema)]
+    struct EchoArgs {
+        /// Text to echo
+        text: String,
+    }
+
+    fn echo_server() -> SdkMcpServer {
+        SdkMcpServer::builder("echo")
+            .tool("echo", "Echo text", |args: EchoArgs| async move {
+                Ok(ToolResult::text(args.text))
+            })
+            .tool("fail", "Always fails", |_: EchoArgs| async move {
+                Err(Error::InvalidInput("boom".to_string()))
+            })
+            .build()
+    }
+
+    #[tokio::test]
+    async fn test_tools_have_derived_schemas() {
+        let tools = echo_server().list_tools().await.unwrap();
+        assert_eq!(tools.len(), 2);
+        assert_eq!(tools[0].name, "echo");
+        assert_eq!(tools[0].input_schema["type"], "object");
+        assert_eq!(
+            tools[0].input_schema["properties"]["text"]["description"],
+            "Text to echo"
+        );
+        assert_eq!(tools[0].input_schema["required"], json!(["text"]));
+    }
+
+    #[tokio::test]
+    async fn test_call_tool_reports_failures_as_results() {
+        let server = echo_server();
+
+        let result = server
+            .call_tool("echo", json!({"text": "hi"}))
+            .await
+            .unwrap();
+        assert!(!result.is_error);
+        assert_eq!(result.content[0]["text"], "hi");
+
+        let invalid = server
+            .call_tool("echo", json!({"txt": "hi"}))
+            .await
+            .unwrap();
+        assert!(invalid.is_error);
+        assert!(invalid.content[0]["text"]
+            .as_str()
+            .unwrap()
+            .starts_with("Invalid arguments for tool 'echo'"));
+
+        let failed = server
+            .call_tool("fail", json!({"text": "hi"}))
+            .await
+            .unwrap();
+        assert!(failed.is_error);
+        assert_eq!(failed.content[0]["text"], "Invalid input: boom");
+
+        assert!(server.call_tool("missing", json!({})).await.is_err());
+    }
+}
@@ -32,13 +32,6 @@ pub struct SubprocessCLITransport {
     max_buffer_size: usize,
 }
 
-impl SubprocessCLITransport {
-    /// Get a clone of the stdin Arc for independent locking
-    pub fn get_stdin(&self) -> Option<Arc<TokioMutex<ChildStdin>>> {
-        self.stdin.clone()
-    }
-}
-
 pub enum PromptType {
     String(String),
     Stream,
@@ -602,4 +595,8 @@ impl Transport for SubprocessCLITransport {
     fn is_ready(&self) -> bool {
         self.ready
     }
+
+    fn get_stdin(&self) -> Option<Arc<TokioMutex<ChildStdin>>> {
+        self.stdin.clone()
+    }
 }
@@ -3,8 +3,8 @@ use crate::domain::hook::{HookCallback, HookContext};
 use crate::domain::message::{parse_message, Message};
 use crate::domain::{HookEvent, HookMatcher, PermissionResult, ToolPermissionContext};
 use crate::error::{Error, Result};
-use crate::options::CanUseToolCallback;
-use crate::ports::secondary::Transport;
+use crate::options::{CanUseToolCallback, SdkMcpServers};
+use crate::ports::secondary::{McpServer, ToolResult, Transport};
 use futures::{Stream, StreamExt};
 use serde_json::json;
 use std::collections::HashMap;
@@ -18,6 +18,7 @@ pub struct Query {
     // Callbacks
     can_use_tool: Option<CanUseToolCallback>,
     hook_callbacks: Arc<Mutex<HashMap<String, HookCallback>>>,
+    sdk_mcp_servers: Arc<SdkMcpServers>,
 
     // Control protocol state
     pending_responses: Arc<Mutex<HashMap<String, oneshot::Sender<serde_json::Value>>>>,
@@ -69,6 +70,7 @@ impl Query {
             is_streaming_mode,
             can_use_tool,
             hook_callbacks: Arc::new(Mutex::new(hook_callbacks)),
+            sdk_mcp_servers: Arc::new(HashMap::new()),
             pending_responses: Arc::new(Mutex::new(HashMap::new())),
             request_counter: Arc::new(Mutex::new(0)),
             message_tx: Some(message_tx),
@@ -80,6 +82,13 @@ impl Query {
         (query, write_rx)
     }
 
+    /// Set the in-process MCP servers answering `mcp_message` requests
+    ///
+    /// Must be called before `start`.
+    pub fn set_sdk_mcp_servers(&mut self, servers: SdkMcpServers) {
+        self.sdk_mcp_servers = Arc::new(servers);
+    }
+
     /// Initialize control protocol
     pub async fn initialize(
         &mut self,
@@ -103,19 +112,12 @@ impl Query {
         let pending_responses = Arc::clone(&self.pending_responses);
         let can_use_tool = self.can_use_tool.clone();
         let hook_callbacks = Arc::clone(&self.hook_callbacks);
+        let sdk_mcp_servers = Arc::clone(&self.sdk_mcp_servers);
         let write_tx = self.write_tx.clone();
 
         // Extract stdin Arc before locking transport for reading
         // This allows write task to write independently
-        let stdin_opt = {
-            let mut transport_lock = transport.lock().await;
-            // Try to downcast to SubprocessCLITransport to get stdin
-            use crate::adapters::secondary::SubprocessCLITransport;
-            let transport_ref: &mut dyn crate::ports::secondary::Transport = &mut **transport_lock;
-            let transport_ptr = transport_ref as *mut dyn crate::ports::secondary::Transport;
-            let subprocess_ptr = transport_ptr as *mut SubprocessCLITransport;
-            unsafe { (*subprocess_ptr).get_stdin() }
-        };
+        let stdin_opt = transport.lock().await.get_stdin();
 
         // Spawn separate write task with direct stdin access
         if let Some(stdin_arc) = stdin_opt {
@@ -166,6 +168,7 @@ impl Query {
                                             write_tx.clone(),
                                             can_use_tool.clone(),
                                             Arc::clone(&hook_callbacks),
+                                            Arc::clone(&sdk_mcp_servers),
                                         ));
                                     }
                                 }
@@ -255,6 +258,7 @@ impl Query {
         write_tx: mpsc::UnboundedSender<String>,
         can_use_tool: Option<CanUseToolCallback>,
         hook_callbacks: Arc<Mutex<HashMap<String, HookCallback>>>,
+        sdk_mcp_servers: Arc<SdkMcpServers>,
     ) {
         let request_id = request.request_id.clone();
 
@@ -305,7 +309,14 @@ impl Query {
                 }
             }
 
-            IncomingControlRequestBody::McpMessage { .. } => Err("MCP not implemented".to_string()),
+            IncomingControlRequestBody::McpMessage {
+                server_name,
+                message,
+            } => {
+                let server = sdk_mcp_servers.get(&server_name);
+                let response = Self::handle_mcp_message(server, &server_name, &message).await;
+                Ok(json!({ "mcp_response": response }))
+            }
         };
 
         // Send response
@@ -327,6 +338,71 @@ impl Query {
         let _ = write_tx.send(format!("{}\n", json_str));
     }
 
+    /// Answer a JSON-RPC message addressed to an in-process MCP server
+    async fn handle_mcp_message(
+        server: Option<&Arc<dyn McpServer>>,
+        server_name: &str,
+        message: &serde_json::Value,
+    ) -> serde_json::Value {
+        let id = message
+            .get("id")
+            .cloned()
+            .unwrap_or(serde_json::Value::Null);
+        let method = message.get("method").and_then(|m| m.as_str()).unwrap_or("");
+
+        let Some(server) = server else {
+            return mcp_error(id, -32601, format!("Server '{}' not found", server_name));
+        };
+
+        let result = match method {
+            "initialize" => Ok(json!({
+                "protocolVersion": crate::adapters::secondary::MCP_PROTOCOL_VERSION,
+                "capabilities": { "tools": {} },
+                "serverInfo": {
+                    "name": server.name(),
+                    "version": env!("CARGO_PKG_VERSION"),
+                },
+            })),
+            "notifications/initialized" => Ok(json!({})),
+            "tools/list" => server.list_tools().await.map(|tools| {
+                let tools: Vec<_> = tools
+                    .into_iter()
+                    .map(|tool| {
+                        json!({
+                            "name": tool.name,
+                            "description": tool.description,
+                            "inputSchema": tool.input_schema,
+                        })
+                    })
+                    .collect();
+                json!({ "tools": tools })
+            }),
+            "tools/call" => {
+                let params = message.get("params");
+                let name = params
+                    .and_then(|p| p.get("name"))
+                    .and_then(|n| n.as_str())
+                    .unwrap_or("");
+                let arguments = params
+                    .and_then(|p| p.get("arguments"))
+                    .cloned()
+                    .unwrap_or_else(|| json!({}));
+                server
+                    .call_tool(name, arguments)
+                    .await
+                    .map(tool_result_json)
+            }
+            _ => {
+                return mcp_error(id, -32601, format!("Method '{}' not found", method));
+            }
+        };
+
+        match result {
+            Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+            Err(e) => mcp_error(id, -32603, e.to_string()),
+        }
+    }
+
     /// Receive messages from the stream
     pub fn receive_messages(&self) -> impl Stream<Item = Message> + '_ {
         async_stream::stream! {
@@ -378,3 +454,159 @@ impl Query {
             .map_err(|_| Error::ChannelClosed)
     }
 }
+
+/// Build a JSON-RPC error response
+fn mcp_error(id: serde_json::Value, code: i64, message: String) -> serde_json::Value {
+    json!({
+        "jsonrpc": "2.0",
+        "id": id,
+        "error": { "code": code, "message": message },
+    })
+}
+
+/// Convert a tool result into MCP `tools/call` content blocks
+fn tool_result_json(result: ToolResult) -> serde_json::Value {
+    let mut response = serde_json::Map::new();
+    let content = match result.content {
+        serde_json::Value::Array(blocks) => serde_json::Value::Array(blocks),
+        serde_json::Value::Null => json!([]),
+        serde_json::Value::String(text) => json!([{ "type": "text", "text": text }]),
+        other => {
+            let text = other.to_string();
+            if other.is_object() {
+                response.insert("structuredContent".to_string(), other);
+            }
+            json!([{ "type": "text", "text": text }])
+        }
+    };
+    response.insert("content".to_string(), content);
+    response.insert("isError".to_string(), json!(result.is_error));
+    serde_json::Value::Object(response)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::adapters::secondary::SdkMcpServer;
+    use serde::Deserialize;
+
+    #[derive(Deserialize, schemars::JsonSchema)]
+    struct GreetArgs {
+        name: String,
+    }
+
+    fn greeter() -> Arc<dyn McpServer> {
+        Arc::new(
+            SdkMcpServer::builder("greeter")
+                .tool("greet", "Greet someone", |args: GreetArgs| async move {
+                    Ok(ToolResult::text(format!("Hello, {}!", args.name)))
+                })
+                .tool(
+                    "profile",
+                    "Structured output",
+                    |args: GreetArgs| async move {
+                        Ok(ToolResult {
+                            content: json!({ "name": args.name }),
+                            is_error: false,
+                        })
+                    },
+                )
+                .build(),
+        )
+    }
+
+    async fn send(
+        server: Option<&Arc<dyn McpServer>>,
+        message: serde_json::Value,
+    ) -> serde_json::Value {
+        Query::handle_mcp_message(server, "greeter", &message).await
+    }
+
+    #[tokio::test]
+    async fn test_mcp_initialize_and_list_tools() {
+        let server = greeter();
+
+        let response = send(
+            Some(&server),
+            json!({"jsonrpc": "2.0", "id": 1, "method": "initialize"}),
+        )
+        .await;
+        assert_eq!(response["id"], 1);
+        assert_eq!(response["result"]["serverInfo"]["name"], "greeter");
+        assert!(response["result"]["capabilities"]["tools"].is_object());
+
+        let response = send(
+            Some(&server),
+            json!({"jsonrpc": "2.0", "id": 2, "method": "tools/list"}),
+        )
+        .await;
+        let tools = response["result"]["tools"].as_array().unwrap();
+        assert_eq!(tools.len(), 2);
+        assert_eq!(tools[0]["name"], "greet");
+        assert_eq!(tools[0]["inputSchema"]["required"], json!(["name"]));
+    }
+
+    #[tokio::test]
+    async fn test_mcp_tools_call() {
+        let server = greeter();
+
+        let response = send(
+            Some(&server),
+            json!({
+                "jsonrpc": "2.0",
+                "id": "call-1",
+                "method": "tools/call",
+                "params": {"name": "greet", "arguments": {"name": "Ada"}}
+            }),
+        )
+        .await;
+        assert_eq!(response["id"], "call-1");
+        assert_eq!(
+            response["result"],
+            json!({"content": [{"type": "text", "text": "Hello, Ada!"}], "isError": false})
+        );
+
+        let response = send(
+            Some(&server),
+            json!({
+                "jsonrpc": "2.0",
+                "id": 2,
+                "method": "tools/call",
+                "params": {"name": "profile", "arguments": {"name": "Ada"}}
+            }),
+        )
+        .await;
+        assert_eq!(
+            response["result"]["structuredContent"],
+            json!({"name": "Ada"})
+        );
+        assert_eq!(response["result"]["content"][0]["type"], "text");
+
+        let response = send(
+            Some(&server),
+            json!({"jsonrpc": "2.0", "id": 3, "method": "tools/call", "params": {"name": "missing"}}),
+        )
+        .await;
+        assert_eq!(response["error"]["code"], -32603);
+    }
+
+    #[tokio::test]
+    async fn test_mcp_errors() {
+        let server = greeter();
+
+        let response = send(
+            Some(&server),
+            json!({"jsonrpc": "2.0", "id": 1, "method": "resources/list"}),
+        )
+        .await;
+        assert_eq!(response["error"]["code"], -32601);
+
+        let response = send(
+            None,
+            json!({"jsonrpc": "2.0", "id": 1, "method": "tools/list"}),
+        )
+        .await;
+        assert_eq!(response["error"]["code"], -32601);
+        assert_eq!(response["error"]["message"], "Server 'greeter' not found");
+    }
+}
@@ -9,11 +9,13 @@
 
 use clap::{Parser, Subcommand};
 use colored::*;
+use periplon_sdk::dsl::secrets;
 use periplon_sdk::dsl::{
     generate_and_save, generate_template, parse_workflow_file, validate_workflow, DSLExecutor,
-    ReplSession, StatePersistence, DSL_GRAMMAR_VERSION,
+    ReplSession, StatePersistence, Usage, DSL_GRAMMAR_VERSION,
 };
 use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
 use std::path::PathBuf;
 use std::time::Instant;
 
@@ -96,6 +98,9 @@ struct StatusOutput {
     started_at: String,
     ended_at: Option<String>,
     duration_secs: Option<f64>,
+    usage: Usage,
+    task_usage: BTreeMap<String, Usage>,
+    agent_usage: BTreeMap<String, Usage>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -252,6 +257,18 @@ enum Commands {
         worker_id: Option<String>,
     },
 
+    /// Start the cron scheduler without serving the API
+    #[cfg(feature = "server")]
+    Scheduler {
+        /// Configuration file path
+        #[arg(short, long, value_name = "FILE")]
+        config: Option<PathBuf>,
+
+        /// Scheduler ID used for leader election (auto-generated if not specified)
+        #[arg(long)]
+        scheduler_id: Option<String>,
+    },
+
     /// Run database migrations
     #[cfg(feature = "server")]
     Migrate {
@@ -298,10 +315,45 @@ enum Commands {
         verbose: bool,
     },
 
+    /// Manage encrypted secrets files
+    Secrets {
+        #[command(subcommand)]
+        secrets_command: SecretsCommands,
+    },
+
     /// Show DSL grammar version
     Version {},
 }
 
+#[derive(Subcommand)]
+enum SecretsCommands {
+    /// Encrypt a YAML or JSON map of secrets for `encrypted` secret sources
+    Encrypt {
+        /// File with the plaintext secrets (name: value)
+        #[arg(value_name = "INPUT_FILE")]
+        input: PathBuf,
+
+        /// Encrypted file to write
+        #[arg(short, long, value_name = "FILE")]
+        output: PathBuf,
+
+        /// Environment variable holding the passphrase
+        #[arg(long, value_name = "VAR", default_value = secrets::DEFAULT_PASSPHRASE_ENV)]
+        passphrase_env: String,
+    },
+
+    /// List the entry names of an encrypted secrets file
+    List {
+        /// Encrypted secrets file
+        #[arg(value_name = "FILE")]
+        file: PathBuf,
+
+        /// Environment variable holding the passphrase
+        #[arg(long, value_name = "VAR", default_value = secrets::DEFAULT_PASSPHRASE_ENV)]
+        passphrase_env: String,
+    },
+}
+
 #[derive(Subcommand)]
 enum GroupCommands {
     /// List all available task groups
@@ -451,6 +503,11 @@ async fn main() {
             worker_id,
         } => start_worker(concurrency, config, worker_id).await,
         #[cfg(feature = "server")]
+        Commands::Scheduler {
+            config,
+            scheduler_id,
+        } => start_scheduler(config, scheduler_id).await,
+        #[cfg(feature = "server")]
         Commands::Migrate { config, action } => run_migrations(config, action).await,
         Commands::Template { output } => generate_template_cmd(output).await,
         Commands::Generate {
@@ -460,6 +517,17 @@ async fn main() {
             workflow,
             verbose,
         } => generate_from_nl_cmd(description, file, output, workflow, verbose).await,
+        Commands::Secrets { secrets_command } => match secrets_command {
+            SecretsCommands::Encrypt {
+                input,
+                output,
+                passphrase_env,
+            } => encrypt_secrets_cmd(input, output, passphrase_env).await,
+            SecretsCommands::List {
+                file,
+                passphrase_env,
+            } => list_secrets_cmd(file, passphrase_env).await,
+        },
         Commands::Version {} => show_version().await,
     };
 
@@ -688,7 +756,15 @@ async fn run_workflow(
         println!();
     }
 
+    // Ctrl+C stops the workflow cleanly; with state persistence it can be resumed
+    let cancellation = executor.cancellation_token();
+    let interrupt = tokio::spawn(async move {
+        if tokio::signal::ctrl_c().await.is_ok() {
+            cancellation.cancel();
+        }
+    });
     let exec_result = executor.execute().await;
+    interrupt.abort();
 
     if !json {
         println!();
@@ -1128,6 +1204,9 @@ async fn show_status(
             started_at: format!("{:?}", state.started_at),
             ended_at: state.ended_at.map(|t| format!("{:?}", t)),
             duration_secs,
+            usage: state.total_usage(),
+            task_usage: state.usage_by_task().into_iter().collect(),
+            agent_usage: state.usage_by_agent().into_iter().collect(),
         };
         print_json(&output)?;
     } else {
@@ -1183,6 +1262,16 @@ async fn show_status(
             println!();
         }
 
+        // Show token usage and cost
+        let usage = state.total_usage();
+        if !usage.is_empty() {
+            println!("  {}:", "Usage".bold());
+            println!("    {}", format_usage(&usage));
+            print_usage_breakdown("By task", &state.usage_by_task());
+            print_usage_breakdown("By agent", &state.usage_by_agent());
+            println!();
+        }
+
         // Show timing
         println!("  {} {:?}", "Started:".bold(), state.started_at);
         if let Some(ended) = state.ended_at {
@@ -1198,6 +1287,31 @@ async fn show_status(
     Ok(())
 }
 
+/// Tokens and cost of a usage on one line
+fn format_usage(usage: &Usage) -> String {
+    let cached = usage.cache_creation_input_tokens + usage.cache_read_input_tokens;
+    format!(
+        "{} tokens ({} input, {} output, {} cached), ${:.4}",
+        usage.total_tokens(),
+        usage.input_tokens,
+        usage.output_tokens,
+        cached,
+        usage.cost_usd
+    )
+}
+
+/// Print the usage of each task or agent, sorted by name
+fn print_usage_breakdown(title: &str, usage: &HashMap<String, Usage>) {
+    if usage.is_empty() {
+        return;
+    }
+    println!("    {}:", title);
+    let sorted: BTreeMap<_, _> = usage.iter().collect();
+    for (name, usage) in sorted {
+        println!("      • {}: {}", name, format_usage(usage));
+    }
+}
+
 /// Generate a DSL template with documentation
 async fn generate_template_cmd(output: Option<PathBuf>) -> Result<(), Box<dyn std::error::Error>> {
     let template = generate_template();
@@ -1218,6 +1332,60 @@ async fn generate_template_cmd(output: Option<PathBuf>) -> Result<(), Box<dyn st
     Ok(())
 }
 
+/// Read the passphrase of an encrypted secrets file from the environment
+fn secrets_passphrase(passphrase_env: &str) -> Result<String, Box<dyn std::error::Error>> {
+    std::env::var(passphrase_env).map_err(|_| {
+        format!(
+            "Set the passphrase in environment variable '{}'",
+            passphrase_env
+        )
+        .into()
+    })
+}
+
+/// Encrypt a plaintext secrets file
+async fn encrypt_secrets_cmd(
+    input: PathBuf,
+    output: PathBuf,
+    passphrase_env: String,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let passphrase = secrets_passphrase(&passphrase_env)?;
+    // YAML is a superset of JSON, so both formats parse here
+    let entries: std::collections::HashMap<String, String> =
+        serde_yaml::from_str(&std::fs::read_to_string(&input)?)?;
+
+    std::fs::write(&output, secrets::encrypt_secrets(&entries, &passphrase)?)?;
+    println!(
+        "{} Encrypted {} secrets to: {}",
+        "✓".green().bold(),
+        entries.len(),
+        output.display()
+    );
+    println!(
+        "  Remove the plaintext file {} once you no longer need it",
+        input.display()
+    );
+
+    Ok(())
+}
+
+/// List the entries of an encrypted secrets file
+async fn list_secrets_cmd(
+    file: PathBuf,
+    passphrase_env: String,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let passphrase = secrets_passphrase(&passphrase_env)?;
+    let entries = secrets::decrypt_secrets(&std::fs::read_to_string(&file)?, &passphrase)?;
+
+    let mut names: Vec<&String> = entries.keys().collect();
+    names.sort();
+    for name in names {
+        println!("{}", name);
+    }
+
+    Ok(())
+}
+
 /// Generate DSL workflow from natural language
 async fn generate_from_nl_cmd(
     description: Option<String>,
@@ -1927,8 +2095,13 @@ async fn start_server(
     worker_concurrency: usize,
 ) -> Result<(), Box<dyn std::error::Error>> {
     use periplon_sdk::server::{
-        api::routes, auth::JwtManager, queue::filesystem::FilesystemQueue,
-        storage::filesystem::FilesystemStorage, storage::UserStorage, worker::Worker, Config,
+        api::routes,
+        auth::{JwtManager, MfaService, SsoService},
+        queue::filesystem::FilesystemQueue,
+        storage::filesystem::FilesystemStorage,
+        storage::UserStorage,
+        worker::Worker,
+        Config, ExecutionHub,
     };
     use std::sync::Arc;
 
@@ -2054,6 +2227,9 @@ async fn start_server(
     ));
     println!("  {} JWT manager initialized", "✓".green());
 
+    // Workers in this process publish live updates to WebSocket clients
+    let hub = Arc::new(ExecutionHub::new());
+
     // Start workers if requested
     if workers {
         println!(
@@ -2068,7 +2244,8 @@ async fn start_server(
                 Arc::clone(&queue),
                 Arc::clone(&storage),
                 1,
-            );
+            )
+            .with_hub(Arc::clone(&hub));
 
             tokio::spawn(async move {
                 worker.run().await;
@@ -2076,15 +2253,76 @@ async fn start_server(
         }
     }
 
+    // Start the cron scheduler; replicas elect a leader to fire schedules
+    if config.scheduler.enabled {
+        let scheduler = build_scheduler(
+            &config,
+            format!("server-scheduler-{}", uuid::Uuid::new_v4()),
+            Arc::clone(&storage),
+            Arc::clone(&queue),
+        )
+        .await?;
+        println!(
+            "  {} Scheduler polling every {}s",
+            "✓".green(),
+            config.scheduler.poll_interval_secs
+        );
+
+        tokio::spawn(async move {
+            scheduler.run().await;
+        });
+    }
+
+    // Single sign-on through the OIDC providers, if configured
+    let sso = config
+        .auth
+        .oauth
+        .as_ref()
+        .filter(|oauth| oauth.enabled)
+        .map(|oauth| {
+            Arc::new(SsoService::new(
+                oauth,
+                &config.auth.jwt_secret,
+                Arc::clone(&user_storage),
+                storage.clone(),
+                storage.clone(),
+                storage.clone(),
+            ))
+        });
+    if sso.is_some() {
+        println!("  {} Single sign-on enabled", "✓".green());
+    }
+
+    // TOTP multi-factor authentication, if enabled
+    let mfa = config.auth.mfa_enabled.then(|| {
+        Arc::new(MfaService::new(
+            storage.clone(),
+            &config.auth.jwt_secret,
+            &config.auth.mfa_issuer,
+            config.auth.mfa_challenge_ttl_secs,
+        ))
+    });
+    if mfa.is_some() {
+        println!("  {} Multi-factor authentication enabled", "✓".green());
+    }
+
     // Create API router with user storage, JWT manager, storage, queue, CORS config, and rate limiting
-    let app = routes::create_router(
+    let mut app = routes::create_router(
         user_storage,
         jwt_manager,
         Arc::clone(&storage),
         Arc::clone(&queue),
+        hub,
         config.server.cors.clone(),
         config.rate_limit.clone(),
+        config.server.trusted_proxies.clone(),
     );
+    if let Some(sso) = sso {
+        app = app.layer(axum::Extension(sso));
+    }
+    if let Some(mfa) = mfa {
+        app = app.layer(axum::Extension(mfa));
+    }
 
     println!();
     println!("{}", "Server Status".bold());
@@ -2109,7 +2347,12 @@ async fn start_server(
 
     // Start server
     let listener = tokio::net::TcpListener::bind(format!("0.0.0.0:{}", server_port)).await?;
-    axum::serve(listener, app).await?;
+    // The peer address is needed to check API key IP whitelists
+    axum::serve(
+        listener,
+        app.into_make_service_with_connect_info::<std::net::SocketAddr>(),
+    )
+    .await?;
 
     Ok(())
 }
@@ -2223,6 +2466,159 @@ async fn start_worker(
     Ok(())
 }
 
+#[cfg(feature = "server")]
+async fn start_scheduler(
+    config_path: Option<PathBuf>,
+    scheduler_id: Option<String>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    use periplon_sdk::server::{
+        queue::filesystem::FilesystemQueue, storage::filesystem::FilesystemStorage, Config,
+    };
+    use std::sync::Arc;
+
+    println!("{}", "Starting DSL Executor Scheduler".bold().green());
+    println!("{}", "=".repeat(60).dimmed());
+
+    // Load configuration
+    let config = Config::load(config_path)?;
+    println!("  {} Configuration loaded", "✓".green());
+
+    let scheduler_id =
+        scheduler_id.unwrap_or_else(|| format!("scheduler-{}", uuid::Uuid::new_v4()));
+    println!("  {} Scheduler ID: {}", "✓".green(), scheduler_id);
+
+    // Initialize storage backend
+    let storage: Arc<dyn periplon_sdk::server::Storage> = match &config.storage.backend {
+        periplon_sdk::server::config::StorageBackend::Filesystem(fs_config) => {
+            let storage = FilesystemStorage::new(
+                fs_config.base_path.clone(),
+                fs_config.workflows_dir.clone(),
+                fs_config.executions_dir.clone(),
+                fs_config.checkpoints_dir.clone(),
+                fs_config.logs_dir.clone(),
+            )
+            .await?;
+            println!("  {} Storage backend: filesystem", "✓".green());
+            Arc::new(storage)
+        }
+        periplon_sdk::server::config::StorageBackend::Postgres(pg_config) => {
+            use periplon_sdk::server::storage::postgres::PostgresStorage;
+            let storage = PostgresStorage::new(&pg_config.url).await?;
+            println!("  {} Storage backend: PostgreSQL", "✓".green());
+            Arc::new(storage)
+        }
+        periplon_sdk::server::config::StorageBackend::S3(s3_config) => {
+            use periplon_sdk::server::storage::s3::S3Storage;
+            let storage = S3Storage::new(
+                s3_config.endpoint.clone(),
+                s3_config.region.clone(),
+                s3_config.bucket.clone(),
+                s3_config.access_key_id.clone(),
+                s3_config.secret_access_key.clone(),
+                Some(s3_config.path_prefix.clone()),
+            )
+            .await?;
+            println!("  {} Storage backend: S3", "✓".green());
+            Arc::new(storage)
+        }
+    };
+
+    // Initialize queue backend
+    let queue: Arc<dyn periplon_sdk::server::WorkQueue> = match &config.queue.backend {
+        periplon_sdk::server::config::QueueBackend::Filesystem(fs_config) => {
+            let queue = FilesystemQueue::new(
+                fs_config.queue_dir.clone(),
+                fs_config.poll_interval_ms,
+                fs_config.lock_timeout_secs,
+            )
+            .await?;
+            println!("  {} Queue backend: filesystem", "✓".green());
+            Arc::new(queue)
+        }
+        periplon_sdk::server::config::QueueBackend::Postgres(pg_config) => {
+            use periplon_sdk::server::queue::postgres::PostgresQueue;
+            let queue = PostgresQueue::new(
+                &pg_config.url,
+                pg_config.poll_interval_ms,
+                pg_config.max_retries,
+            )
+            .await?;
+            println!("  {} Queue backend: PostgreSQL", "✓".green());
+            Arc::new(queue)
+        }
+        periplon_sdk::server::config::QueueBackend::Redis(redis_config) => {
+            use periplon_sdk::server::queue::redis::RedisQueue;
+            let queue = RedisQueue::new(&redis_config.url, None).await?;
+            println!("  {} Queue backend: Redis", "✓".green());
+            Arc::new(queue)
+        }
+        _ => {
+            return Err("S3 queue is not yet implemented".into());
+        }
+    };
+
+    let scheduler = build_scheduler(&config, scheduler_id, storage, queue).await?;
+
+    println!();
+    println!("{}", "Scheduler Status".bold());
+    println!("{}", "=".repeat(60).dimmed());
+    println!(
+        "  {} Scheduler running, polling every {}s",
+        "●".green().bold(),
+        config.scheduler.poll_interval_secs
+    );
+    println!();
+    println!("Press Ctrl+C to stop the scheduler");
+
+    tokio::select! {
+        _ = scheduler.run() => {}
+        _ = tokio::signal::ctrl_c() => {
+            // Hand leadership to another replica right away
+            scheduler.shutdown().await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Create a scheduler whose leader lease lives in the configured queue backend
+#[cfg(feature = "server")]
+async fn build_scheduler(
+    config: &periplon_sdk::server::Config,
+    scheduler_id: String,
+    storage: std::sync::Arc<dyn periplon_sdk::server::Storage>,
+    queue: std::sync::Arc<dyn periplon_sdk::server::WorkQueue>,
+) -> Result<periplon_sdk::server::Scheduler, Box<dyn std::error::Error>> {
+    use periplon_sdk::server::config::QueueBackend;
+    use periplon_sdk::server::scheduler::{
+        FilesystemLease, LeaderLease, PostgresLease, RedisLease,
+    };
+    use std::sync::Arc;
+
+    let lease: Arc<dyn LeaderLease> = match &config.queue.backend {
+        QueueBackend::Filesystem(fs_config) => Arc::new(FilesystemLease::new(
+            fs_config.queue_dir.join("scheduler.lease"),
+        )),
+        QueueBackend::Postgres(pg_config) => {
+            Arc::new(PostgresLease::new(&pg_config.url, None).await?)
+        }
+        QueueBackend::Redis(redis_config) => {
+            Arc::new(RedisLease::new(&redis_config.url, None).await?)
+        }
+        QueueBackend::S3(_) => {
+            return Err("S3 queue is not yet implemented".into());
+        }
+    };
+
+    Ok(periplon_sdk::server::Scheduler::new(
+        scheduler_id,
+        storage,
+        queue,
+        lease,
+        config.scheduler.clone(),
+    ))
+}
+
 #[cfg(feature = "server")]
 async fn run_migrations(
     _config_path: Option<PathBuf>,
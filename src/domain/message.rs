@@ -26,6 +26,28 @@ impl Message {
             parent_tool_use_id: None,
         })
     }
+
+    /// Readable text of an agent message: assistant text and tool calls, or a failed result
+    pub fn display_text(&self) -> Option<String> {
+        let text = match self {
+            Message::Assistant(assistant) => assistant
+                .message
+                .content
+                .iter()
+                .filter_map(|block| match block {
+                    ContentBlock::Text { text } => Some(text.trim().to_string()),
+                    ContentBlock::ToolUse { name, .. } => Some(format!("Using tool: {}", name)),
+                    _ => None,
+                })
+                .collect::<Vec<_>>()
+                .join("\n"),
+            Message::Result(result) if result.is_error => {
+                format!("Error: {}", result.result.as_deref().unwrap_or("unknown"))
+            }
+            _ => return None,
+        };
+        (!text.trim().is_empty()).then_some(text)
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -17,6 +17,24 @@ pub enum Provider {
     Anthropic,
     /// Google Gemini API
     Google,
+    /// Any server speaking the OpenAI chat completions API (vLLM, LM Studio, llama.cpp)
+    #[serde(rename = "openai_compatible")]
+    OpenAICompatible,
+    /// Azure OpenAI, addressed by deployment and API version
+    #[serde(rename = "azure_openai")]
+    AzureOpenAI,
+}
+
+/// How the API key is sent to a provider
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum AuthStyle {
+    /// `Authorization: Bearer <key>`
+    Bearer,
+    /// The key as the value of a header (`api-key` unless another name is given)
+    ApiKey,
+    /// No authentication
+    None,
 }
 
 impl Provider {
@@ -39,10 +57,45 @@ impl Provider {
     pub fn is_api_based(&self) -> bool {
         matches!(
             self,
-            Provider::Ollama | Provider::OpenAI | Provider::Anthropic | Provider::Google
+            Provider::Ollama
+                | Provider::OpenAI
+                | Provider::Anthropic
+                | Provider::Google
+                | Provider::OpenAICompatible
+                | Provider::AzureOpenAI
+        )
+    }
+
+    /// Check if this provider speaks the OpenAI chat completions format
+    pub fn uses_openai_format(&self) -> bool {
+        matches!(
+            self,
+            Provider::OpenAI | Provider::OpenAICompatible | Provider::AzureOpenAI
         )
     }
 
+    /// Provider whose CLI runs agents configured with this provider
+    ///
+    /// Agents always run through a CLI: Claude for Anthropic endpoints and
+    /// Codex for OpenAI-style ones. Other providers can only serve `llm` tasks.
+    pub fn agent_cli(&self) -> Option<Provider> {
+        match self {
+            Provider::Claude | Provider::Anthropic => Some(Provider::Claude),
+            Provider::Codex | Provider::OpenAI | Provider::OpenAICompatible => {
+                Some(Provider::Codex)
+            }
+            _ => None,
+        }
+    }
+
+    /// How the API key is sent unless configured otherwise
+    pub fn default_auth(&self) -> AuthStyle {
+        match self {
+            Provider::AzureOpenAI => AuthStyle::ApiKey,
+            _ => AuthStyle::Bearer,
+        }
+    }
+
     /// Get the CLI binary name for this provider (only for CLI-based providers)
     pub fn cli_binary_name(&self) -> Option<&str> {
         match self {
@@ -59,6 +112,7 @@ impl Provider {
             Provider::OpenAI => Some("https://api.openai.com/v1"),
             Provider::Anthropic => Some("https://api.anthropic.com/v1"),
             Provider::Google => Some("https://generativelanguage.googleapis.com/v1beta"),
+            // Self-hosted and Azure endpoints must be configured
             _ => None,
         }
     }
@@ -110,14 +164,17 @@ impl Provider {
                 "gemini-1.5-flash",
                 "gemini-1.5-flash-8b",
             ],
+            // Served models are whatever the server or deployment offers
+            Provider::OpenAICompatible | Provider::AzureOpenAI => vec![],
         }
     }
 
     /// Validate if a model name is valid for this provider
-    /// For Ollama, we accept any model name since users can have custom models
+    /// For Ollama, OpenAI-compatible servers and Azure deployments, we accept
+    /// any model name since users serve their own models
     pub fn is_valid_model(&self, model: &str) -> bool {
         match self {
-            Provider::Ollama => true, // Accept any model for Ollama
+            Provider::Ollama | Provider::OpenAICompatible | Provider::AzureOpenAI => true,
             _ => self.valid_models().contains(&model),
         }
     }
@@ -131,6 +188,8 @@ impl Provider {
             Provider::OpenAI => "gpt-4o",
             Provider::Anthropic => "claude-3-5-sonnet-20241022",
             Provider::Google => "gemini-2.0-flash-exp",
+            // No fixed model list, so the model must be configured
+            Provider::OpenAICompatible | Provider::AzureOpenAI => "",
         }
     }
 
@@ -140,7 +199,9 @@ impl Provider {
             Provider::OpenAI => Some("OPENAI_API_KEY"),
             Provider::Anthropic => Some("ANTHROPIC_API_KEY"),
             Provider::Google => Some("GOOGLE_API_KEY"),
+            Provider::AzureOpenAI => Some("AZURE_OPENAI_API_KEY"),
             Provider::Ollama => None, // Ollama doesn't require API key by default
+            Provider::OpenAICompatible => None, // Local servers usually need no key
             _ => None,
         }
     }
@@ -251,6 +312,31 @@ mod tests {
 
         assert!(Provider::OpenAI.is_valid_model("gpt-4o"));
         assert!(!Provider::OpenAI.is_valid_model("claude-3-opus"));
+
+        // Self-hosted and Azure providers accept any model
+        assert!(Provider::OpenAICompatible.is_valid_model("Qwen/Qwen2.5-7B-Instruct"));
+        assert!(Provider::AzureOpenAI.is_valid_model("my-gpt4o-deployment"));
+    }
+
+    #[test]
+    fn test_provider_serde_names() {
+        let provider: Provider = serde_yaml::from_str("openai_compatible").unwrap();
+        assert_eq!(provider, Provider::OpenAICompatible);
+        let provider: Provider = serde_yaml::from_str("azure_openai").unwrap();
+        assert_eq!(provider, Provider::AzureOpenAI);
+        let provider: Provider = serde_yaml::from_str("openai").unwrap();
+        assert_eq!(provider, Provider::OpenAI);
+    }
+
+    #[test]
+    fn test_provider_agent_cli() {
+        assert_eq!(Provider::Anthropic.agent_cli(), Some(Provider::Claude));
+        assert_eq!(
+            Provider::OpenAICompatible.agent_cli(),
+            Some(Provider::Codex)
+        );
+        assert_eq!(Provider::AzureOpenAI.agent_cli(), None);
+        assert_eq!(Provider::Ollama.agent_cli(), None);
     }
 
     #[test]
@@ -350,6 +350,8 @@ mod tests {
             imports: HashMap::new(),
             notifications: None,
             limits: None,
+            max_concurrency: None,
+            providers: HashMap::new(),
         };
 
         // Add tasks with agent assignments
@@ -13,10 +13,11 @@ pub mod suggestions;
 
 pub use config::{create_default_config, AiConfig};
 pub use generator::{generate_task, generate_workflow_block};
-pub use providers::{AiProvider, AiProviderType, AiResponse};
+pub use providers::{AiProvider, AiProviderType, AiResponse, LlmClientProvider};
 pub use suggestions::{analyze_error, explain_workflow, suggest_fix, suggest_improvements};
 
 use crate::error::Result;
+use crate::ports::secondary::{LlmMessage, LlmStream};
 
 /// AI assistant for debugging
 pub struct DebugAiAssistant {
@@ -71,6 +72,16 @@ impl DebugAiAssistant {
         suggestions::explain_workflow(self.provider.as_ref(), workflow_yaml).await
     }
 
+    /// Continue a multi-turn conversation with the assistant
+    pub async fn chat(&self, messages: &[LlmMessage]) -> Result<AiResponse> {
+        self.provider.chat(messages).await
+    }
+
+    /// Continue a conversation, streaming the answer as it is generated
+    pub async fn chat_stream(&self, messages: &[LlmMessage]) -> Result<LlmStream> {
+        self.provider.chat_stream(messages).await
+    }
+
     /// Get current configuration
     pub fn config(&self) -> &AiConfig {
         &self.config
@@ -1,8 +1,12 @@
 //! AI provider abstraction
 use super::config::AiConfig;
+use crate::adapters::secondary::HttpLlmClient;
+use crate::domain::Provider;
 use crate::error::{Error, Result};
+use crate::ports::secondary::{LlmClient, LlmMessage, LlmRequest, LlmStream};
 use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
+use std::sync::Arc;
 
 /// AI provider type
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
@@ -21,6 +25,28 @@ pub enum AiProviderType {
     Google,
 }
 
+impl AiProviderType {
+    /// The LLM client provider backing this provider type
+    pub fn provider(self) -> Provider {
+        match self {
+            AiProviderType::Ollama => Provider::Ollama,
+            AiProviderType::OpenAi => Provider::OpenAI,
+            AiProviderType::Anthropic => Provider::Anthropic,
+            AiProviderType::Google => Provider::Google,
+        }
+    }
+
+    /// Display name
+    pub fn name(self) -> &'static str {
+        match self {
+            AiProviderType::Ollama => "Ollama",
+            AiProviderType::OpenAi => "OpenAI",
+            AiProviderType::Anthropic => "Anthropic",
+            AiProviderType::Google => "Google Gemini",
+        }
+    }
+}
+
 /// AI response
 #[derive(Debug, Clone)]
 pub struct AiResponse {
@@ -37,11 +63,22 @@ pub struct AiResponse {
 /// AI provider trait
 #[async_trait]
 pub trait AiProvider: Send + Sync {
+    /// Continue a conversation (system, user and assistant messages)
+    async fn chat(&self, messages: &[LlmMessage]) -> Result<AiResponse>;
+
+    /// Continue a conversation, streaming the answer as it is generated
+    async fn chat_stream(&self, messages: &[LlmMessage]) -> Result<LlmStream>;
+
     /// Generate text from prompt
-    async fn generate(&self, prompt: &str) -> Result<AiResponse>;
+    async fn generate(&self, prompt: &str) -> Result<AiResponse> {
+        self.chat(&[LlmMessage::user(prompt)]).await
+    }
 
     /// Generate with system prompt
-    async fn generate_with_system(&self, system: &str, prompt: &str) -> Result<AiResponse>;
+    async fn generate_with_system(&self, system: &str, prompt: &str) -> Result<AiResponse> {
+        self.chat(&[LlmMessage::system(system), LlmMessage::user(prompt)])
+            .await
+    }
 
     /// Get provider name
     fn name(&self) -> &str;
@@ -49,376 +86,149 @@ pub trait AiProvider: Send + Sync {
 
 /// Create provider from configuration
 pub fn create_provider(config: &AiConfig) -> Result<Box<dyn AiProvider>> {
-    match config.provider {
-        AiProviderType::Ollama => Ok(Box::new(OllamaProvider::new(config)?)),
-        AiProviderType::OpenAi => Ok(Box::new(OpenAiProvider::new(config)?)),
-        AiProviderType::Anthropic => Ok(Box::new(AnthropicProvider::new(config)?)),
-        AiProviderType::Google => Ok(Box::new(GoogleProvider::new(config)?)),
-    }
+    Ok(Box::new(LlmClientProvider::new(
+        config.clone(),
+        Arc::new(HttpLlmClient::new()),
+    )))
 }
 
-/// Ollama provider
-struct OllamaProvider {
+/// Provider backed by an [`LlmClient`]
+pub struct LlmClientProvider {
     config: AiConfig,
-    client: reqwest::Client,
-}
-
-impl OllamaProvider {
-    fn new(config: &AiConfig) -> Result<Self> {
-        Ok(Self {
-            config: config.clone(),
-            client: reqwest::Client::new(),
-        })
-    }
-
-    fn endpoint(&self) -> String {
-        self.config
-            .endpoint
-            .clone()
-            .unwrap_or_else(|| "http://localhost:11434".to_string())
-    }
-}
-
-#[async_trait]
-impl AiProvider for OllamaProvider {
-    async fn generate(&self, prompt: &str) -> Result<AiResponse> {
-        self.generate_with_system("", prompt).await
-    }
-
-    async fn generate_with_system(&self, system: &str, prompt: &str) -> Result<AiResponse> {
-        let url = format!("{}/api/generate", self.endpoint());
-
-        let mut body = serde_json::json!({
-            "model": self.config.model,
-            "prompt": prompt,
-            "stream": false,
-            "options": {
-                "temperature": self.config.temperature,
-                "num_predict": self.config.max_tokens,
-            }
-        });
-
-        if !system.is_empty() {
-            body["system"] = serde_json::json!(system);
-        }
-
-        let response = self
-            .client
-            .post(&url)
-            .json(&body)
-            .send()
-            .await
-            .map_err(|e| Error::InvalidInput(format!("HTTP error: {}", e)))?;
-
-        if !response.status().is_success() {
-            return Err(Error::InvalidInput(format!(
-                "Ollama request failed: {}",
-                response.status()
-            )));
+    client: Arc<dyn LlmClient>,
+}
+
+impl LlmClientProvider {
+    /// Create a provider sending requests through the given client
+    pub fn new(config: AiConfig, client: Arc<dyn LlmClient>) -> Self {
+        Self { config, client }
+    }
+
+    /// Build the request for a conversation
+    fn request(&self, messages: &[LlmMessage]) -> LlmRequest {
+        LlmRequest {
+            provider: self.config.provider.provider(),
+            model: self.config.model.clone(),
+            prompt: String::new(),
+            system_prompt: None,
+            endpoint: self.config.endpoint.clone(),
+            api_key: self.config.api_key.clone(),
+            auth: None,
+            auth_header: None,
+            api_version: None,
+            temperature: Some(self.config.temperature as f64),
+            max_tokens: Some(self.config.max_tokens),
+            top_p: None,
+            top_k: None,
+            stop: Vec::new(),
+            timeout_secs: None,
+            extra_params: self.config.extra_params.clone(),
+            response_schema: None,
+            messages: messages
+                .iter()
+                .filter(|message| !message.content.is_empty())
+                .cloned()
+                .collect(),
+
+            tools: Vec::new(),
+            max_tool_iterations: None,
         }
-
-        let data: serde_json::Value = response
-            .json()
-            .await
-            .map_err(|e| Error::InvalidInput(e.to_string()))?;
-
-        let text = data["response"]
-            .as_str()
-            .ok_or_else(|| Error::InvalidInput("No response from Ollama".to_string()))?
-            .to_string();
-
-        Ok(AiResponse {
-            text,
-            tokens_used: None,
-            metadata: data,
-        })
-    }
-
-    fn name(&self) -> &str {
-        "Ollama"
-    }
-}
-
-/// OpenAI provider
-struct OpenAiProvider {
-    config: AiConfig,
-    client: reqwest::Client,
-}
-
-impl OpenAiProvider {
-    fn new(config: &AiConfig) -> Result<Self> {
-        Ok(Self {
-            config: config.clone(),
-            client: reqwest::Client::new(),
-        })
-    }
-
-    fn endpoint(&self) -> String {
-        self.config
-            .endpoint
-            .clone()
-            .unwrap_or_else(|| "https://api.openai.com/v1".to_string())
     }
 
-    fn api_key(&self) -> Result<String> {
-        self.config
-            .api_key
-            .clone()
-            .or_else(|| std::env::var("OPENAI_API_KEY").ok())
-            .ok_or_else(|| Error::InvalidInput("OpenAI API key not found".to_string()))
+    fn error(&self, error: impl std::fmt::Display) -> Error {
+        Error::InvalidInput(format!("{} request failed: {}", self.name(), error))
     }
 }
 
 #[async_trait]
-impl AiProvider for OpenAiProvider {
-    async fn generate(&self, prompt: &str) -> Result<AiResponse> {
-        self.generate_with_system("You are a helpful assistant.", prompt)
-            .await
-    }
-
-    async fn generate_with_system(&self, system: &str, prompt: &str) -> Result<AiResponse> {
-        let url = format!("{}/chat/completions", self.endpoint());
-        let api_key = self.api_key()?;
-
-        let body = serde_json::json!({
-            "model": self.config.model,
-            "messages": [
-                {"role": "system", "content": system},
-                {"role": "user", "content": prompt}
-            ],
-            "temperature": self.config.temperature,
-            "max_tokens": self.config.max_tokens,
-        });
-
+impl AiProvider for LlmClientProvider {
+    async fn chat(&self, messages: &[LlmMessage]) -> Result<AiResponse> {
         let response = self
             .client
-            .post(&url)
-            .header("Authorization", format!("Bearer {}", api_key))
-            .header("Content-Type", "application/json")
-            .json(&body)
-            .send()
-            .await
-            .map_err(|e| Error::InvalidInput(format!("HTTP error: {}", e)))?;
-
-        if !response.status().is_success() {
-            return Err(Error::InvalidInput(format!(
-                "OpenAI request failed: {}",
-                response.status()
-            )));
-        }
-
-        let data: serde_json::Value = response
-            .json()
+            .execute(self.request(messages))
             .await
-            .map_err(|e| Error::InvalidInput(e.to_string()))?;
-
-        let text = data["choices"][0]["message"]["content"]
-            .as_str()
-            .ok_or_else(|| Error::InvalidInput("No response from OpenAI".to_string()))?
-            .to_string();
-
-        let tokens = data["usage"]["total_tokens"].as_u64().map(|t| t as u32);
+            .map_err(|e| self.error(e))?;
 
         Ok(AiResponse {
-            text,
-            tokens_used: tokens,
-            metadata: data,
+            text: response.content,
+            tokens_used: response.usage.map(|usage| usage.total_tokens),
+            metadata: response.raw_response.unwrap_or(serde_json::Value::Null),
         })
     }
 
-    fn name(&self) -> &str {
-        "OpenAI"
-    }
-}
-
-/// Anthropic provider
-struct AnthropicProvider {
-    config: AiConfig,
-    client: reqwest::Client,
-}
-
-impl AnthropicProvider {
-    fn new(config: &AiConfig) -> Result<Self> {
-        Ok(Self {
-            config: config.clone(),
-            client: reqwest::Client::new(),
-        })
-    }
-
-    fn endpoint(&self) -> String {
-        self.config
-            .endpoint
-            .clone()
-            .unwrap_or_else(|| "https://api.anthropic.com/v1".to_string())
-    }
-
-    fn api_key(&self) -> Result<String> {
-        self.config
-            .api_key
-            .clone()
-            .or_else(|| std::env::var("ANTHROPIC_API_KEY").ok())
-            .ok_or_else(|| Error::InvalidInput("Anthropic API key not found".to_string()))
-    }
-}
-
-#[async_trait]
-impl AiProvider for AnthropicProvider {
-    async fn generate(&self, prompt: &str) -> Result<AiResponse> {
-        self.generate_with_system("You are a helpful assistant.", prompt)
+    async fn chat_stream(&self, messages: &[LlmMessage]) -> Result<LlmStream> {
+        self.client
+            .execute_stream(self.request(messages))
             .await
-    }
-
-    async fn generate_with_system(&self, system: &str, prompt: &str) -> Result<AiResponse> {
-        let url = format!("{}/messages", self.endpoint());
-        let api_key = self.api_key()?;
-
-        let body = serde_json::json!({
-            "model": self.config.model,
-            "max_tokens": self.config.max_tokens,
-            "system": system,
-            "messages": [
-                {"role": "user", "content": prompt}
-            ],
-            "temperature": self.config.temperature,
-        });
-
-        let response = self
-            .client
-            .post(&url)
-            .header("x-api-key", api_key)
-            .header("anthropic-version", "2023-06-01")
-            .header("Content-Type", "application/json")
-            .json(&body)
-            .send()
-            .await
-            .map_err(|e| Error::InvalidInput(format!("HTTP error: {}", e)))?;
-
-        if !response.status().is_success() {
-            return Err(Error::InvalidInput(format!(
-                "Anthropic request failed: {}",
-                response.status()
-            )));
-        }
-
-        let data: serde_json::Value = response
-            .json()
-            .await
-            .map_err(|e| Error::InvalidInput(e.to_string()))?;
-
-        let text = data["content"][0]["text"]
-            .as_str()
-            .ok_or_else(|| Error::InvalidInput("No response from Anthropic".to_string()))?
-            .to_string();
-
-        let tokens = data["usage"]["output_tokens"].as_u64().map(|t| t as u32);
-
-        Ok(AiResponse {
-            text,
-            tokens_used: tokens,
-            metadata: data,
-        })
+            .map_err(|e| self.error(e))
     }
 
     fn name(&self) -> &str {
-        "Anthropic"
-    }
-}
-
-/// Google Gemini provider
-struct GoogleProvider {
-    config: AiConfig,
-    client: reqwest::Client,
-}
-
-impl GoogleProvider {
-    fn new(config: &AiConfig) -> Result<Self> {
-        Ok(Self {
-            config: config.clone(),
-            client: reqwest::Client::new(),
-        })
-    }
-
-    fn endpoint(&self) -> String {
-        self.config
-            .endpoint
-            .clone()
-            .unwrap_or_else(|| "https://generativelanguage.googleapis.com/v1beta".to_string())
-    }
+        self.config.provider.name()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dsl::debug_ai::config::config_for_provider;
+    use crate::ports::secondary::{LlmError, LlmResponse, LlmRole};
+    use std::sync::Mutex;
+
+    /// Client answering with the number of messages it received
+    #[derive(Default)]
+    struct RecordingClient {
+        requests: Mutex<Vec<LlmRequest>>,
+    }
+
+    #[async_trait]
+    impl LlmClient for RecordingClient {
+        async fn execute(&self, request: LlmRequest) -> std::result::Result<LlmResponse, LlmError> {
+            let content = format!("{} messages", request.conversation().len());
+            let provider = request.provider.clone();
+            self.requests.lock().unwrap().push(request);
+            Ok(LlmResponse {
+                content,
+                model: "test".to_string(),
+                provider,
+                usage: None,
+                finish_reason: None,
+                raw_response: None,
+            })
+        }
 
-    fn api_key(&self) -> Result<String> {
-        self.config
-            .api_key
-            .clone()
-            .or_else(|| std::env::var("GOOGLE_API_KEY").ok())
-            .ok_or_else(|| Error::InvalidInput("Google API key not found".to_string()))
-    }
-}
+        fn supports_provider(&self, _provider: &Provider) -> bool {
+            true
+        }
 
-#[async_trait]
-impl AiProvider for GoogleProvider {
-    async fn generate(&self, prompt: &str) -> Result<AiResponse> {
-        self.generate_with_system("", prompt).await
+        fn name(&self) -> &str {
+            "recording"
+        }
     }
 
-    async fn generate_with_system(&self, system: &str, prompt: &str) -> Result<AiResponse> {
-        let api_key = self.api_key()?;
-        let url = format!(
-            "{}/models/{}:generateContent?key={}",
-            self.endpoint(),
-            self.config.model,
-            api_key
+    #[tokio::test]
+    async fn test_generate_with_system_goes_through_client() {
+        let client = Arc::new(RecordingClient::default());
+        let provider = LlmClientProvider::new(
+            config_for_provider(AiProviderType::Anthropic, None),
+            client.clone(),
         );
 
-        let full_prompt = if system.is_empty() {
-            prompt.to_string()
-        } else {
-            format!("{}\n\n{}", system, prompt)
-        };
-
-        let body = serde_json::json!({
-            "contents": [{
-                "parts": [{"text": full_prompt}]
-            }],
-            "generationConfig": {
-                "temperature": self.config.temperature,
-                "maxOutputTokens": self.config.max_tokens,
-            }
-        });
-
-        let response = self
-            .client
-            .post(&url)
-            .header("Content-Type", "application/json")
-            .json(&body)
-            .send()
+        let response = provider
+            .generate_with_system("Be terse", "Explain")
             .await
-            .map_err(|e| Error::InvalidInput(format!("HTTP error: {}", e)))?;
-
-        if !response.status().is_success() {
-            return Err(Error::InvalidInput(format!(
-                "Google request failed: {}",
-                response.status()
-            )));
+            .unwrap();
+        assert_eq!(response.text, "2 messages");
+        assert_eq!(provider.name(), "Anthropic");
+
+        {
+            let requests = client.requests.lock().unwrap();
+            assert_eq!(requests[0].provider, Provider::Anthropic);
+            assert_eq!(requests[0].messages[0].role, LlmRole::System);
         }
 
-        let data: serde_json::Value = response
-            .json()
-            .await
-            .map_err(|e| Error::InvalidInput(e.to_string()))?;
-
-        let text = data["candidates"][0]["content"]["parts"][0]["text"]
-            .as_str()
-            .ok_or_else(|| Error::InvalidInput("No response from Google".to_string()))?
-            .to_string();
-
-        Ok(AiResponse {
-            text,
-            tokens_used: None,
-            metadata: data,
-        })
-    }
-
-    fn name(&self) -> &str {
-        "Google Gemini"
+        // An empty system prompt is not sent
+        provider.generate_with_system("", "Explain").await.unwrap();
+        assert_eq!(client.requests.lock().unwrap()[1].messages.len(), 1);
     }
 }
@@ -6,9 +6,11 @@
 //! - Call stack visualization
 //! - Side effect history
 //! - Execution timeline
+//! - Channel transcripts of the message bus
 use super::breakpoints::VariableScope;
 use super::side_effects::{SideEffect, SideEffectType};
 use super::state::DebuggerState;
+use crate::dsl::message_bus::{AgentMessage, MessageBus};
 use crate::dsl::state::{TaskOutput, WorkflowState};
 use crate::dsl::task_graph::TaskStatus;
 use std::collections::HashMap;
@@ -20,6 +22,7 @@ use tokio::sync::Mutex;
 pub struct Inspector {
     debugger: Arc<Mutex<DebuggerState>>,
     state: Arc<Mutex<Option<WorkflowState>>>,
+    message_bus: Option<Arc<MessageBus>>,
 }
 
 impl Inspector {
@@ -28,7 +31,17 @@ impl Inspector {
         debugger: Arc<Mutex<DebuggerState>>,
         state: Arc<Mutex<Option<WorkflowState>>>,
     ) -> Self {
-        Self { debugger, state }
+        Self {
+            debugger,
+            state,
+            message_bus: None,
+        }
+    }
+
+    /// Inspect the messages of a live message bus
+    pub fn with_message_bus(mut self, message_bus: Arc<MessageBus>) -> Self {
+        self.message_bus = Some(message_bus);
+        self
     }
 
     /// Get current execution position
@@ -221,6 +234,36 @@ impl Inspector {
         ExecutionTimeline { events }
     }
 
+    /// Get the transcripts of all channels and direct messages, by name
+    ///
+    /// Messages come from the message bus, or else from the messages saved in
+    /// the workflow state.
+    pub async fn channel_transcripts(&self) -> Vec<ChannelTranscript> {
+        let log = match &self.message_bus {
+            Some(bus) => bus.log(),
+            None => match *self.state.lock().await {
+                Some(ref state) => state.messages.clone(),
+                None => return Vec::new(),
+            },
+        };
+
+        let mut transcripts: Vec<ChannelTranscript> = log
+            .transcripts
+            .into_iter()
+            .map(|(name, messages)| ChannelTranscript { name, messages })
+            .collect();
+        transcripts.sort_by(|a, b| a.name.cmp(&b.name));
+        transcripts
+    }
+
+    /// Get the transcript of one channel, or of an agent's direct messages (`@<agent>`)
+    pub async fn inspect_channel(&self, name: &str) -> Option<ChannelTranscript> {
+        self.channel_transcripts()
+            .await
+            .into_iter()
+            .find(|transcript| transcript.name == name)
+    }
+
     /// Get debugger status
     pub async fn status(&self) -> super::state::DebuggerStatus {
         let debugger = self.debugger.lock().await;
@@ -319,6 +362,14 @@ pub struct TaskInspection {
     pub attempts: u32,
 }
 
+/// Messages sent on a channel, or directly to an agent
+#[derive(Debug, Clone)]
+pub struct ChannelTranscript {
+    /// Channel name, or `@<agent>` for an agent's direct messages
+    pub name: String,
+    pub messages: Vec<AgentMessage>,
+}
+
 /// Side effect filter
 #[derive(Debug, Clone)]
 pub struct SideEffectFilter {
@@ -334,6 +385,7 @@ pub enum SideEffectFilterType {
     VariableChanges,
     Commands,
     Network,
+    ToolDenials,
 }
 
 impl SideEffectFilter {
@@ -383,6 +435,9 @@ impl SideEffectFilter {
                 ) | (
                     SideEffectType::NetworkRequest { .. },
                     SideEffectFilterType::Network
+                ) | (
+                    SideEffectType::ToolDenied { .. },
+                    SideEffectFilterType::ToolDenials
                 )
             );
 
@@ -522,4 +577,27 @@ mod tests {
         assert_eq!(results.len(), 1);
         assert_eq!(results[0].0, VariableScope::Workflow);
     }
+
+    #[tokio::test]
+    async fn test_inspector_channel_transcripts() {
+        let debugger = Arc::new(Mutex::new(DebuggerState::new()));
+        let state = Arc::new(Mutex::new(None));
+        let bus = Arc::new(MessageBus::new());
+        bus.register_agent("reviewer".to_string()).await.unwrap();
+        bus.send_direct(AgentMessage::new(
+            "coder".to_string(),
+            "reviewer".to_string(),
+            "ready".to_string(),
+            serde_json::json!({}),
+        ))
+        .await
+        .unwrap();
+
+        let inspector = Inspector::new(debugger, state).with_message_bus(bus);
+        let transcripts = inspector.channel_transcripts().await;
+        assert_eq!(transcripts.len(), 1);
+        let transcript = inspector.inspect_channel("@reviewer").await.unwrap();
+        assert_eq!(transcript.messages[0].from, "coder");
+        assert!(inspector.inspect_channel("updates").await.is_none());
+    }
 }
@@ -18,7 +18,7 @@ pub use breakpoints::{
     BreakCondition, BreakpointInfo, BreakpointManager, BreakpointType, VariableScope,
     WatchCondition,
 };
-pub use inspector::{Inspector, TaskInspection, VariableSnapshot};
+pub use inspector::{ChannelTranscript, Inspector, TaskInspection, VariableSnapshot};
 pub use pointer::{
     ExecutionFrame, ExecutionHistory, ExecutionMode, ExecutionPointer, ExecutionSnapshot,
 };
@@ -107,6 +107,14 @@ pub enum SideEffectType {
         old_value: Option<String>,
         new_value: String,
     },
+
+    /// Agent was refused a tool call by the workflow's tool policy
+    ToolDenied {
+        agent: String,
+        tool_name: String,
+        input: serde_json::Value,
+        reason: String,
+    },
 }
 
 /// Variable scope identifier
@@ -376,6 +384,26 @@ impl CompensationStrategy for TaskStatusCompensation {
     }
 }
 
+/// Tool denial compensation (nothing ran, so nothing to undo)
+pub struct ToolDenialCompensation {
+    pub tool_name: String,
+}
+
+#[async_trait::async_trait]
+impl CompensationStrategy for ToolDenialCompensation {
+    async fn compensate(&self) -> Result<()> {
+        Ok(())
+    }
+
+    fn description(&self) -> String {
+        format!("Nothing to undo for denied tool {}", self.tool_name)
+    }
+
+    fn is_safe(&self) -> bool {
+        true
+    }
+}
+
 /// Side effect journal
 pub struct SideEffectJournal {
     /// All recorded side effects
@@ -543,6 +571,7 @@ impl SideEffectJournal {
                 SideEffectType::CommandExecuted { .. } => "CommandExecuted",
                 SideEffectType::NetworkRequest { .. } => "NetworkRequest",
                 SideEffectType::EnvVarSet { .. } => "EnvVarSet",
+                SideEffectType::ToolDenied { .. } => "ToolDenied",
             };
 
             *summary.entry(type_name.to_string()).or_insert(0) += 1;
@@ -0,0 +1,616 @@
+//! Execution Events
+//!
+//! This module defines the typed events a [`DSLExecutor`](crate::dsl::DSLExecutor)
+//! emits while it runs a workflow, and the observers that receive them.
+//!
+//! Observers are registered on an [`ExecutionEvents`] dispatcher. The executor
+//! installs a [`ConsoleObserver`] that prints the familiar progress output;
+//! embedding applications can add their own observers or take a channel of
+//! events with [`ExecutionEvents::channel`].
+//!
+//! # Example
+//!
+//! ```no_run
+//! use periplon_sdk::dsl::events::ExecutionEvent;
+//! use periplon_sdk::dsl::{parse_workflow_file, DSLExecutor};
+//!
+//! # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+//! let workflow = parse_workflow_file("workflow.yaml")?;
+//! let mut executor = DSLExecutor::new(workflow)?;
+//! let mut events = executor.events().channel();
+//!
+//! tokio::spawn(async move {
+//!     while let Some(event) = events.recv().await {
+//!         if let ExecutionEvent::TaskFailed { task_id, error } = event {
+//!             eprintln!("{} failed: {}", task_id, error);
+//!         }
+//!     }
+//! });
+//!
+//! executor.initialize().await?;
+//! executor.execute().await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::domain::Message;
+use crate::dsl::state::Usage;
+use serde::Serialize;
+use std::io::Write;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, RwLock};
+use tokio::sync::mpsc;
+
+/// Why a task is run again
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RetryReason {
+    /// The previous attempt failed
+    Error,
+    /// The output did not meet the definition of done
+    DefinitionOfDone,
+    /// The output did not match its schema
+    OutputSchema,
+}
+
+/// Console stream a task's output was written to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OutputStream {
+    Stdout,
+    Stderr,
+}
+
+/// Something that happened while executing a workflow
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum ExecutionEvent {
+    /// Execution of a workflow began
+    WorkflowStarted {
+        workflow: String,
+        /// Tasks in execution order
+        tasks: Vec<String>,
+    },
+    /// Execution of a workflow ended
+    WorkflowFinished {
+        workflow: String,
+        success: bool,
+        duration_ms: u64,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        error: Option<String>,
+    },
+    /// A task began its first attempt
+    TaskStarted {
+        task_id: String,
+        description: String,
+    },
+    /// A task is run again
+    TaskRetrying {
+        task_id: String,
+        /// Retry number for this reason, starting at 1
+        attempt: u32,
+        /// Retries allowed for this reason, when bounded
+        #[serde(skip_serializing_if = "Option::is_none")]
+        max_attempts: Option<u32>,
+        reason: RetryReason,
+        /// Error or feedback that caused the retry
+        message: String,
+        /// Wait before the next attempt
+        delay_secs: u64,
+    },
+    /// A task's primary agent gave up and its fallback agent takes over
+    TaskFallback {
+        task_id: String,
+        agent: String,
+        error: String,
+    },
+    /// A task completed
+    TaskSucceeded { task_id: String, duration_ms: u64 },
+    /// A task failed for good
+    TaskFailed { task_id: String, error: String },
+    /// A task was not run
+    TaskSkipped { task_id: String, reason: String },
+    /// A loop iteration began
+    LoopIterationStarted {
+        task_id: String,
+        /// Zero-based iteration index
+        iteration: usize,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        total: Option<usize>,
+        /// Item of a `for_each` loop
+        #[serde(skip_serializing_if = "Option::is_none")]
+        item: Option<serde_json::Value>,
+    },
+    /// A loop iteration ended
+    LoopIterationFinished {
+        task_id: String,
+        /// Zero-based iteration index
+        iteration: usize,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        error: Option<String>,
+    },
+    /// An agent sent a message while working on a task
+    AgentMessage {
+        task_id: String,
+        agent: String,
+        /// Retry attempt the message belongs to (0 for the first attempt)
+        attempt: u32,
+        /// Whether the agent is the task's fallback agent
+        fallback: bool,
+        message: Message,
+    },
+    /// A task's output was checked against its definition of done
+    DefinitionOfDoneChecked {
+        task_id: String,
+        met: bool,
+        /// Description of the unmet criteria
+        #[serde(skip_serializing_if = "Option::is_none")]
+        feedback: Option<String>,
+    },
+    /// An agent or LLM call of a task reported the tokens and cost it used
+    UsageRecorded {
+        task_id: String,
+        /// Agent that made the call, if the task ran one
+        #[serde(skip_serializing_if = "Option::is_none")]
+        agent: Option<String>,
+        usage: Usage,
+    },
+    /// The workflow's tool policy refused a tool call of an agent
+    ToolDenied {
+        task_id: String,
+        agent: String,
+        tool: String,
+        reason: String,
+    },
+    /// A notification was delivered, or failed to be
+    NotificationSent {
+        /// Task the notification is about, if not the workflow
+        #[serde(skip_serializing_if = "Option::is_none")]
+        task_id: Option<String>,
+        message: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        error: Option<String>,
+    },
+    /// A step of the executor's work, such as a request sent or a loop finished
+    Progress {
+        /// Task the step belongs to, if not the workflow
+        #[serde(skip_serializing_if = "Option::is_none")]
+        task_id: Option<String>,
+        message: String,
+    },
+    /// A problem that does not stop execution
+    Warning {
+        /// Task the problem belongs to, if not the workflow
+        #[serde(skip_serializing_if = "Option::is_none")]
+        task_id: Option<String>,
+        message: String,
+    },
+    /// Output of a script, command or LLM call, with secrets redacted
+    ///
+    /// Streamed LLM responses arrive in several pieces.
+    TaskOutput {
+        task_id: String,
+        stream: OutputStream,
+        text: String,
+    },
+}
+
+impl ExecutionEvent {
+    /// Qualify the task and agent IDs of an event from a child workflow
+    ///
+    /// IDs become `scope/id`, where `scope` is the parent task running the
+    /// child, so they cannot be confused with the parent's own tasks and agents.
+    pub fn scoped(mut self, scope: &str) -> Self {
+        let qualify = |id: &mut String| *id = format!("{}/{}", scope, id);
+        match &mut self {
+            ExecutionEvent::WorkflowStarted { tasks, .. } => tasks.iter_mut().for_each(qualify),
+            ExecutionEvent::WorkflowFinished { .. } => {}
+            ExecutionEvent::TaskStarted { task_id, .. }
+            | ExecutionEvent::TaskRetrying { task_id, .. }
+            | ExecutionEvent::TaskSucceeded { task_id, .. }
+            | ExecutionEvent::TaskFailed { task_id, .. }
+            | ExecutionEvent::TaskSkipped { task_id, .. }
+            | ExecutionEvent::LoopIterationStarted { task_id, .. }
+            | ExecutionEvent::LoopIterationFinished { task_id, .. }
+            | ExecutionEvent::DefinitionOfDoneChecked { task_id, .. }
+            | ExecutionEvent::TaskOutput { task_id, .. } => qualify(task_id),
+            ExecutionEvent::TaskFallback { task_id, agent, .. }
+            | ExecutionEvent::AgentMessage { task_id, agent, .. }
+            | ExecutionEvent::ToolDenied { task_id, agent, .. } => {
+                qualify(task_id);
+                qualify(agent);
+            }
+            ExecutionEvent::UsageRecorded { task_id, agent, .. } => {
+                qualify(task_id);
+                agent.iter_mut().for_each(qualify);
+            }
+            ExecutionEvent::NotificationSent { task_id, .. }
+            | ExecutionEvent::Progress { task_id, .. }
+            | ExecutionEvent::Warning { task_id, .. } => task_id.iter_mut().for_each(qualify),
+        }
+        self
+    }
+}
+
+/// Receives execution events
+///
+/// Observers are called synchronously on the executor's tasks and should
+/// return quickly; hand events to a channel for slow processing.
+pub trait ExecutionObserver: Send + Sync {
+    /// Handle an event
+    fn on_event(&self, event: &ExecutionEvent);
+}
+
+impl<F> ExecutionObserver for F
+where
+    F: Fn(&ExecutionEvent) + Send + Sync,
+{
+    fn on_event(&self, event: &ExecutionEvent) {
+        self(event)
+    }
+}
+
+/// Forwards events to an unbounded channel
+struct ChannelObserver(mpsc::UnboundedSender<ExecutionEvent>);
+
+impl ExecutionObserver for ChannelObserver {
+    fn on_event(&self, event: &ExecutionEvent) {
+        // A dropped receiver only means nobody listens anymore
+        let _ = self.0.send(event.clone());
+    }
+}
+
+/// Prints events as the executor's console progress output
+pub struct ConsoleObserver {
+    enabled: AtomicBool,
+    json_output: AtomicBool,
+}
+
+impl Default for ConsoleObserver {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ConsoleObserver {
+    /// Create an enabled observer using interactive message formatting
+    pub fn new() -> Self {
+        Self {
+            enabled: AtomicBool::new(true),
+            json_output: AtomicBool::new(false),
+        }
+    }
+
+    /// Turn console output on or off
+    pub fn set_enabled(&self, enabled: bool) {
+        self.enabled.store(enabled, Ordering::Relaxed);
+    }
+
+    /// Print agent messages in full debug form instead of condensed
+    pub fn set_json_output(&self, json: bool) {
+        self.json_output.store(json, Ordering::Relaxed);
+    }
+}
+
+impl ExecutionObserver for ConsoleObserver {
+    fn on_event(&self, event: &ExecutionEvent) {
+        if !self.enabled.load(Ordering::Relaxed) {
+            return;
+        }
+
+        match event {
+            ExecutionEvent::WorkflowStarted { workflow, tasks } => {
+                println!("Executing workflow: {}", workflow);
+                println!("Task execution order: {:?}", tasks);
+            }
+            ExecutionEvent::WorkflowFinished { success, .. } => {
+                if *success {
+                    println!("Workflow execution completed");
+                }
+            }
+            ExecutionEvent::TaskStarted {
+                task_id,
+                description,
+            } => println!("Executing task: {} - {}", task_id, description),
+            ExecutionEvent::TaskRetrying {
+                task_id,
+                attempt,
+                max_attempts,
+                reason,
+                message,
+                delay_secs,
+            } => match reason {
+                RetryReason::Error => {
+                    println!(
+                        "Task '{}' failed (attempt {}): {}",
+                        task_id, attempt, message
+                    );
+                    println!(
+                        "Retrying task '{}' (attempt {}) in {}s...",
+                        task_id,
+                        attempt + 1,
+                        delay_secs
+                    );
+                }
+                RetryReason::DefinitionOfDone => println!(
+                    "Retrying task '{}' (DoD attempt {}/{})",
+                    task_id,
+                    attempt,
+                    max_attempts.unwrap_or(*attempt)
+                ),
+                RetryReason::OutputSchema => println!(
+                    "Retrying task '{}' (schema attempt {}/{})",
+                    task_id,
+                    attempt,
+                    max_attempts.unwrap_or(*attempt)
+                ),
+            },
+            ExecutionEvent::TaskFallback {
+                task_id,
+                agent,
+                error,
+            } => {
+                println!("Task '{}' failed: {}", task_id, error);
+                println!("Attempting fallback with agent: {}", agent);
+            }
+            ExecutionEvent::TaskSucceeded { task_id, .. } => {
+                println!("Task completed: {}", task_id)
+            }
+            ExecutionEvent::TaskFailed { task_id, error } => {
+                println!("Task '{}' failed: {}", task_id, error)
+            }
+            ExecutionEvent::TaskSkipped { task_id, reason } => {
+                println!("Task '{}' {} - skipping", task_id, reason)
+            }
+            ExecutionEvent::LoopIterationStarted {
+                iteration,
+                total,
+                item,
+                ..
+            } => match (total, item) {
+                (Some(total), Some(item)) => println!(
+                    "  Iteration {}/{}: Processing item: {:?}",
+                    iteration + 1,
+                    total,
+                    item
+                ),
+                (Some(total), None) => println!("  Iteration {}/{}", iteration + 1, total),
+                _ => println!("  Iteration {}", iteration + 1),
+            },
+            ExecutionEvent::LoopIterationFinished {
+                iteration, error, ..
+            } => match error {
+                None => println!("  Iteration {} completed successfully", iteration + 1),
+                Some(e) => println!("  Iteration {} failed: {}", iteration + 1, e),
+            },
+            ExecutionEvent::AgentMessage {
+                attempt,
+                fallback,
+                message,
+                ..
+            } => {
+                let prefix = if *fallback {
+                    Some("Fallback".to_string())
+                } else {
+                    (*attempt > 0).then(|| format!("Retry {}", attempt))
+                };
+                println!(
+                    "{}",
+                    crate::dsl::message_formatter::format_message(
+                        message,
+                        self.json_output.load(Ordering::Relaxed),
+                        prefix.as_deref()
+                    )
+                );
+            }
+            ExecutionEvent::DefinitionOfDoneChecked {
+                task_id,
+                met,
+                feedback,
+            } => {
+                if *met {
+                    println!("✓ Definition of done met for task: {}", task_id);
+                } else {
+                    println!("Definition of done not met for task '{}':", task_id);
+                    if let Some(feedback) = feedback {
+                        println!("{}", feedback);
+                    }
+                }
+            }
+            // Agent results and LLM responses already print their usage
+            ExecutionEvent::UsageRecorded { .. } => {}
+            ExecutionEvent::ToolDenied {
+                task_id,
+                agent,
+                tool,
+                reason,
+            } => eprintln!(
+                "Denied {} for agent '{}' in task '{}': {}",
+                tool, agent, task_id, reason
+            ),
+            ExecutionEvent::NotificationSent { message, error, .. } => match error {
+                None => println!("Notification: {}", message),
+                Some(e) => eprintln!("Warning: Failed to send notification '{}': {}", message, e),
+            },
+            // Steps of a task are indented below the task's own lines
+            ExecutionEvent::Progress { task_id, message } => match task_id {
+                Some(_) => println!("  {}", message),
+                None => println!("{}", message),
+            },
+            ExecutionEvent::Warning { message, .. } => eprintln!("Warning: {}", message),
+            ExecutionEvent::TaskOutput { stream, text, .. } => match stream {
+                OutputStream::Stdout => {
+                    print!("{}", text);
+                    let _ = std::io::stdout().flush();
+                }
+                OutputStream::Stderr => eprint!("{}", text),
+            },
+        }
+    }
+}
+
+/// Dispatches execution events to registered observers
+pub struct ExecutionEvents {
+    observers: RwLock<Vec<Arc<dyn ExecutionObserver>>>,
+    console: Arc<ConsoleObserver>,
+    /// Dispatcher of the parent workflow, and the task scoping this one's events
+    parent: Option<(Arc<ExecutionEvents>, String)>,
+}
+
+impl Default for ExecutionEvents {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl std::fmt::Debug for ExecutionEvents {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ExecutionEvents")
+            .field("observers", &self.observers.read().unwrap().len())
+            .finish()
+    }
+}
+
+impl ExecutionEvents {
+    /// Create a dispatcher with the console observer registered
+    pub fn new() -> Self {
+        let console = Arc::new(ConsoleObserver::new());
+        Self {
+            observers: RwLock::new(vec![console.clone() as Arc<dyn ExecutionObserver>]),
+            console,
+            parent: None,
+        }
+    }
+
+    /// Create a dispatcher for a child workflow run by the task `scope`
+    ///
+    /// Events reach this dispatcher's own observers as emitted, and the
+    /// parent's observers [scoped](ExecutionEvent::scoped) to the task. The
+    /// console observer is the parent's.
+    pub fn scoped(parent: &Arc<ExecutionEvents>, scope: &str) -> Self {
+        Self {
+            observers: RwLock::new(Vec::new()),
+            console: parent.console.clone(),
+            parent: Some((parent.clone(), scope.to_string())),
+        }
+    }
+
+    /// The observer printing console output
+    pub fn console(&self) -> &ConsoleObserver {
+        &self.console
+    }
+
+    /// Register an observer
+    pub fn subscribe(&self, observer: Arc<dyn ExecutionObserver>) {
+        self.observers.write().unwrap().push(observer);
+    }
+
+    /// Receive all later events through a channel
+    pub fn channel(&self) -> mpsc::UnboundedReceiver<ExecutionEvent> {
+        let (tx, rx) = mpsc::unbounded_channel();
+        self.subscribe(Arc::new(ChannelObserver(tx)));
+        rx
+    }
+
+    /// Send an event to every observer
+    pub fn emit(&self, event: ExecutionEvent) {
+        let observers = self.observers.read().unwrap().clone();
+        for observer in observers {
+            observer.on_event(&event);
+        }
+        if let Some((parent, scope)) = &self.parent {
+            parent.emit(event.scoped(scope));
+        }
+    }
+
+    /// Emit a [`Progress`](ExecutionEvent::Progress) event
+    pub fn progress(&self, task_id: Option<&str>, message: impl Into<String>) {
+        self.emit(ExecutionEvent::Progress {
+            task_id: task_id.map(str::to_string),
+            message: message.into(),
+        });
+    }
+
+    /// Emit a [`Warning`](ExecutionEvent::Warning) event
+    pub fn warning(&self, task_id: Option<&str>, message: impl Into<String>) {
+        self.emit(ExecutionEvent::Warning {
+            task_id: task_id.map(str::to_string),
+            message: message.into(),
+        });
+    }
+
+    /// Emit a [`TaskOutput`](ExecutionEvent::TaskOutput) event, unless `text` is empty
+    pub fn output(&self, task_id: &str, stream: OutputStream, text: impl Into<String>) {
+        let text = text.into();
+        if !text.is_empty() {
+            self.emit(ExecutionEvent::TaskOutput {
+                task_id: task_id.to_string(),
+                stream,
+                text,
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    #[test]
+    fn test_events_reach_observers_and_channels() {
+        let events = ExecutionEvents::new();
+        events.console().set_enabled(false);
+
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let log = seen.clone();
+        events.subscribe(Arc::new(move |event: &ExecutionEvent| {
+            if let ExecutionEvent::TaskSucceeded { task_id, .. } = event {
+                log.lock().unwrap().push(task_id.clone());
+            }
+        }));
+        let mut rx = events.channel();
+
+        events.emit(ExecutionEvent::TaskSucceeded {
+            task_id: "build".to_string(),
+            duration_ms: 5,
+        });
+
+        assert_eq!(*seen.lock().unwrap(), vec!["build".to_string()]);
+        let received = rx.try_recv().unwrap();
+        assert_eq!(
+            serde_json::to_value(&received).unwrap(),
+            serde_json::json!({"event": "task_succeeded", "task_id": "build", "duration_ms": 5})
+        );
+    }
+
+    #[test]
+    fn test_scoped_events_reach_parent_qualified() {
+        let parent = Arc::new(ExecutionEvents::new());
+        parent.console().set_enabled(false);
+        let mut parent_rx = parent.channel();
+        let child = ExecutionEvents::scoped(&parent, "make_report");
+        let mut child_rx = child.channel();
+
+        child.emit(ExecutionEvent::UsageRecorded {
+            task_id: "render".to_string(),
+            agent: Some("writer".to_string()),
+            usage: Usage::default(),
+        });
+
+        match child_rx.try_recv().unwrap() {
+            ExecutionEvent::UsageRecorded { task_id, agent, .. } => {
+                assert_eq!(task_id, "render");
+                assert_eq!(agent.as_deref(), Some("writer"));
+            }
+            other => panic!("unexpected event {:?}", other),
+        }
+        match parent_rx.try_recv().unwrap() {
+            ExecutionEvent::UsageRecorded { task_id, agent, .. } => {
+                assert_eq!(task_id, "make_report/render");
+                assert_eq!(agent.as_deref(), Some("make_report/writer"));
+            }
+            other => panic!("unexpected event {:?}", other),
+        }
+    }
+}
@@ -4,42 +4,296 @@
 //! task scheduling, and workflow orchestration.
 
 use crate::adapters::primary::PeriplonSDKClient;
+use crate::domain::{ContentBlock, Message, Provider};
+use crate::dsl::events::{
+    ExecutionEvent, ExecutionEvents, ExecutionObserver, OutputStream, RetryReason,
+};
+use crate::dsl::expression::{Expression, ExpressionScope};
 use crate::dsl::hooks::{ErrorRecovery, HooksExecutor};
+use crate::dsl::llm_tools::{LlmTools, TaskToolRunner};
 use crate::dsl::loop_context::{substitute_task_variables, LoopContext};
+use crate::dsl::mcp_clients::McpClientPool;
 use crate::dsl::message_bus::MessageBus;
+use crate::dsl::message_tools;
 use crate::dsl::notifications::{NotificationContext, NotificationManager};
-use crate::dsl::schema::{AgentSpec, CollectionSource, DSLWorkflow, FileFormat, LoopSpec};
-use crate::dsl::state::{StatePersistence, WorkflowState};
+use crate::dsl::schema::{
+    AgentSpec, BudgetAction, BudgetConfig, CollectionSource, DSLWorkflow, FileFormat, HooksSpec,
+    LoopSpec, SessionSpec, TaskSpec,
+};
+use crate::dsl::secrets::{SecretProvider, SecretResolver, SecretStore};
+use crate::dsl::stages::{inline_stage_tasks, StagePlan};
+use crate::dsl::state::{parse_output, StatePersistence, Usage, WorkflowState};
 use crate::dsl::task_graph::{TaskGraph, TaskStatus};
+use crate::dsl::tool_policy::{FileScope, ToolGuard, ToolPolicy};
 use crate::error::{Error, Result};
 use crate::options::AgentOptions;
 use futures::StreamExt;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 use std::time::Instant;
-use tokio::sync::{Mutex, Semaphore};
+use tokio::sync::{Mutex, OwnedMutexGuard, Semaphore};
+use tokio_util::sync::CancellationToken;
 
 /// Execution context for loop and task execution
 /// Groups commonly-passed parameters to avoid too_many_arguments clippy warnings
 struct ExecutionContext<'a> {
     workflow_inputs: &'a HashMap<String, serde_json::Value>,
-    agents: &'a Arc<Mutex<HashMap<String, PeriplonSDKClient>>>,
+    agents: &'a Arc<Mutex<AgentClients>>,
     task_graph: &'a Arc<Mutex<TaskGraph>>,
     state: &'a Arc<Mutex<Option<WorkflowState>>>,
+    workflow: &'a Arc<DSLWorkflow>,
+    services: &'a ExecutionServices,
     workflow_name: &'a Arc<String>,
     json_output: bool,
 }
 
+/// Workflow inputs and loop variables visible to `expression` conditions
+#[derive(Clone, Copy, Default)]
+struct ConditionVariables<'a> {
+    workflow_inputs: Option<&'a HashMap<String, serde_json::Value>>,
+    loop_context: Option<&'a LoopContext>,
+    /// Expressions parsed when the workflow was loaded
+    expressions: Option<&'a ParsedExpressions>,
+}
+
+impl<'a> ConditionVariables<'a> {
+    /// Variables of a task running outside any loop
+    fn new(
+        workflow_inputs: &'a HashMap<String, serde_json::Value>,
+        expressions: &'a ParsedExpressions,
+    ) -> Self {
+        Self {
+            workflow_inputs: Some(workflow_inputs),
+            loop_context: None,
+            expressions: Some(expressions),
+        }
+    }
+
+    /// Variables of a loop iteration
+    fn in_loop(self, loop_context: &'a LoopContext) -> Self {
+        Self {
+            loop_context: Some(loop_context),
+            ..self
+        }
+    }
+}
+
+/// Parsed `expression` conditions of a workflow, by source text
+type ParsedExpressions = HashMap<String, Expression>;
+
+/// Parse every condition expression of the workflow's tasks, subtasks and
+/// inline stage tasks
+fn parse_condition_expressions(workflow: &DSLWorkflow) -> Result<ParsedExpressions> {
+    fn parse_task(spec: &TaskSpec, parsed: &mut ParsedExpressions) -> Result<()> {
+        for condition in spec.conditions() {
+            for source in condition.expressions() {
+                if !parsed.contains_key(source) {
+                    parsed.insert(source.to_string(), Expression::parse(source)?);
+                }
+            }
+        }
+        for subtask in spec.subtasks.iter().flat_map(HashMap::values) {
+            parse_task(subtask, parsed)?;
+        }
+        Ok(())
+    }
+
+    let mut parsed = ParsedExpressions::new();
+    let stage_tasks = workflow
+        .workflows
+        .values()
+        .flat_map(|spec| &spec.steps)
+        .flat_map(|stage| stage.tasks.iter().flat_map(HashMap::values));
+    for spec in workflow.tasks.values().chain(stage_tasks) {
+        parse_task(spec, &mut parsed)?;
+    }
+    Ok(parsed)
+}
+
+/// Connected agent clients by name
+///
+/// Each client holds its agent's long-lived conversation and has its own lock,
+/// so one task at a time queries it. See [`AgentLease`] for concurrent tasks.
+type AgentClients = HashMap<String, Arc<Mutex<PeriplonSDKClient>>>;
+
+/// Client a task without a session queries an agent through
+///
+/// Tasks share the agent's long-lived conversation while it is free. A task
+/// that starts while another task is using it gets a client of its own in a
+/// fresh session, so up to the agent's `max_concurrency` tasks run at once.
+enum AgentLease {
+    /// The agent's long-lived conversation
+    Shared(OwnedMutexGuard<PeriplonSDKClient>),
+    /// A client connected for this task only
+    Own(Box<PeriplonSDKClient>),
+}
+
+impl AgentLease {
+    async fn acquire(
+        agents: &Arc<Mutex<AgentClients>>,
+        services: &ExecutionServices,
+        agent_name: &str,
+    ) -> Result<Self> {
+        let shared = agents
+            .lock()
+            .await
+            .get(agent_name)
+            .cloned()
+            .ok_or_else(|| Error::InvalidInput(format!("Agent '{}' not found", agent_name)))?;
+        if let Ok(client) = shared.try_lock_owned() {
+            return Ok(AgentLease::Shared(client));
+        }
+
+        let options = services
+            .agent_options
+            .get(agent_name)
+            .cloned()
+            .ok_or_else(|| Error::InvalidInput(format!("Agent '{}' not found", agent_name)))?;
+        let mut client = Box::new(PeriplonSDKClient::new(options));
+        client.connect(None).await?;
+        Ok(AgentLease::Own(client))
+    }
+
+    fn client(&mut self) -> &mut PeriplonSDKClient {
+        match self {
+            AgentLease::Shared(client) => client,
+            AgentLease::Own(client) => client,
+        }
+    }
+
+    /// Whether the task ran in the agent's long-lived conversation
+    fn is_shared(&self) -> bool {
+        matches!(self, AgentLease::Shared(_))
+    }
+
+    /// Disconnect a client connected for the task
+    async fn release(self) {
+        if let AgentLease::Own(mut client) = self {
+            let _ = client.disconnect().await;
+        }
+    }
+}
+
+/// Runtime services shared by every task of a workflow run
+#[derive(Clone)]
+struct ExecutionServices {
+    /// Connections to the workflow's MCP servers
+    mcp_clients: Arc<McpClientPool>,
+    /// Resolved values of the workflow's secrets
+    secrets: Arc<SecretStore>,
+    /// Providers for the secrets of child workflows
+    secret_resolver: SecretResolver,
+    /// Observers of execution events
+    events: Arc<ExecutionEvents>,
+    /// Where loop checkpoints are saved, when persistence is enabled
+    state_persistence: Option<StatePersistence>,
+    /// Cancelled when the run should stop
+    cancellation: CancellationToken,
+    /// Tool policy guards of the agents that have one
+    tool_guards: Arc<HashMap<String, Arc<ToolGuard>>>,
+    /// Options of each agent, for tasks that run in their own session
+    agent_options: Arc<HashMap<String, AgentOptions>>,
+    /// Channels and direct messages of the workflow's participants
+    message_bus: Arc<MessageBus>,
+    /// Condition expressions parsed when the workflow was loaded
+    expressions: Arc<ParsedExpressions>,
+}
+
+impl ExecutionServices {
+    /// Attribute an agent's tool calls to the task it is about to run
+    fn start_agent_task(&self, agent: &str, task_id: &str) {
+        if let Some(guard) = self.tool_guards.get(agent) {
+            guard.set_task(task_id);
+        }
+    }
+
+    /// Fail with `Error::Cancelled` once the run has been cancelled
+    fn check_cancelled(&self) -> Result<()> {
+        if self.cancellation.is_cancelled() {
+            Err(Error::Cancelled)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// Tracks in-flight tasks against the workflow and per-agent concurrency limits
+struct ConcurrencyLimits {
+    max_total: usize,
+    per_agent: HashMap<String, usize>,
+    running: usize,
+    running_per_agent: HashMap<String, usize>,
+}
+
+impl ConcurrencyLimits {
+    /// Create limits from the workflow-wide cap and the agents' own caps
+    ///
+    /// Unset limits are unbounded.
+    fn new(max_total: Option<usize>, agents: &HashMap<String, AgentSpec>) -> Self {
+        Self {
+            max_total: max_total.unwrap_or(usize::MAX),
+            per_agent: agents
+                .iter()
+                .filter_map(|(name, spec)| spec.max_concurrency.map(|max| (name.clone(), max)))
+                .collect(),
+            running: 0,
+            running_per_agent: HashMap::new(),
+        }
+    }
+
+    /// Whether the workflow-wide limit leaves no room for another task
+    fn is_saturated(&self) -> bool {
+        self.running >= self.max_total
+    }
+
+    /// Reserve a slot for a task run by `agent`, if both limits allow it
+    fn try_acquire(&mut self, agent: Option<&str>) -> bool {
+        if self.is_saturated() {
+            return false;
+        }
+        if let Some(agent) = agent {
+            let in_flight = self.running_per_agent.entry(agent.to_string()).or_default();
+            if self
+                .per_agent
+                .get(agent)
+                .is_some_and(|max| *in_flight >= *max)
+            {
+                return false;
+            }
+            *in_flight += 1;
+        }
+        self.running += 1;
+        true
+    }
+
+    /// Release the slot held by a finished task
+    fn release(&mut self, agent: Option<&str>) {
+        self.running = self.running.saturating_sub(1);
+        if let Some(in_flight) = agent.and_then(|a| self.running_per_agent.get_mut(a)) {
+            *in_flight = in_flight.saturating_sub(1);
+        }
+    }
+}
+
 /// DSL Executor for running workflows
 pub struct DSLExecutor {
     workflow: DSLWorkflow,
-    agents: HashMap<String, PeriplonSDKClient>,
+    agents: AgentClients,
     task_graph: TaskGraph,
     message_bus: Arc<MessageBus>,
     state: Option<WorkflowState>,
     state_persistence: Option<StatePersistence>,
     resolved_inputs: HashMap<String, serde_json::Value>,
     notification_manager: Arc<NotificationManager>,
+    mcp_clients: Arc<McpClientPool>,
+    secret_resolver: SecretResolver,
+    secrets: Arc<SecretStore>,
+    events: Arc<ExecutionEvents>,
+    cancellation: CancellationToken,
+    tool_guards: HashMap<String, Arc<ToolGuard>>,
+    agent_options: HashMap<String, AgentOptions>,
+    stage_plan: StagePlan,
+    expressions: Arc<ParsedExpressions>,
     workflow_start_time: Option<Instant>,
     json_output: bool,
 
@@ -59,12 +313,30 @@ impl DSLExecutor {
     ///
     /// Result containing the executor or an error
     pub fn new(workflow: DSLWorkflow) -> Result<Self> {
+        Self::with_events(workflow, Arc::new(ExecutionEvents::new()))
+    }
+
+    /// Create an executor reporting to the given event dispatcher
+    fn with_events(workflow: DSLWorkflow, events: Arc<ExecutionEvents>) -> Result<Self> {
         // Resolve workflow inputs (use defaults)
         let resolved_inputs = Self::resolve_workflow_inputs(&workflow);
 
+        // Condition expressions are parsed once, and fail the load when invalid
+        let expressions = Arc::new(parse_condition_expressions(&workflow)?);
+
         // Initialize notification manager
         let notification_manager = Arc::new(NotificationManager::new());
 
+        // Secret values are resolved by `initialize`
+        let secrets = Arc::new(SecretStore::new());
+
+        // MCP servers are started lazily when a task first uses them
+        let mcp_clients = Arc::new(McpClientPool::new(
+            workflow.mcp_servers.clone(),
+            secrets.clone(),
+            events.clone(),
+        ));
+
         Ok(DSLExecutor {
             workflow,
             agents: HashMap::new(),
@@ -74,6 +346,15 @@ impl DSLExecutor {
             state_persistence: None,
             resolved_inputs,
             notification_manager,
+            mcp_clients,
+            secret_resolver: SecretResolver::new(),
+            secrets,
+            events,
+            cancellation: CancellationToken::new(),
+            tool_guards: HashMap::new(),
+            agent_options: HashMap::new(),
+            stage_plan: StagePlan::default(),
+            expressions,
             workflow_start_time: None,
             json_output: false,
             debugger: None,
@@ -92,10 +373,10 @@ impl DSLExecutor {
     pub fn with_debugger(mut self) -> Self {
         let debugger = Arc::new(Mutex::new(crate::dsl::debugger::DebuggerState::new()));
         let state = Arc::new(Mutex::new(self.state.clone()));
-        let inspector = Arc::new(crate::dsl::debugger::Inspector::new(
-            debugger.clone(),
-            state,
-        ));
+        let inspector = Arc::new(
+            crate::dsl::debugger::Inspector::new(debugger.clone(), state)
+                .with_message_bus(self.message_bus.clone()),
+        );
 
         self.debugger = Some(debugger);
         self.inspector = Some(inspector);
@@ -122,6 +403,65 @@ impl DSLExecutor {
         &self.workflow
     }
 
+    /// Register a secret provider for `provider` secret sources
+    ///
+    /// Secrets declared with `type: provider` and `provider: <name>` are
+    /// resolved by the provider registered under that name. Registering one of
+    /// the built-in names (`env`, `file`, `value`, `command`, `encrypted`)
+    /// replaces the built-in provider.
+    pub fn register_secret_provider(
+        &mut self,
+        name: impl Into<String>,
+        provider: Arc<dyn SecretProvider>,
+    ) {
+        self.secret_resolver.register(name, provider);
+    }
+
+    /// Resolved secret values, used to redact them from output
+    pub fn secrets(&self) -> &Arc<SecretStore> {
+        &self.secrets
+    }
+
+    /// Event dispatcher of this executor
+    ///
+    /// Use it to subscribe observers or take a channel of events before
+    /// calling `execute`. Child workflows report through the same dispatcher.
+    pub fn events(&self) -> &Arc<ExecutionEvents> {
+        &self.events
+    }
+
+    /// Register an observer of execution events
+    pub fn add_observer(&self, observer: Arc<dyn ExecutionObserver>) {
+        self.events.subscribe(observer);
+    }
+
+    /// Token that stops this execution when cancelled
+    ///
+    /// Cancelling it interrupts in-flight agent queries, kills script and
+    /// command processes and stops loops. `execute` then runs the error and
+    /// post-workflow hooks and returns `Error::Cancelled`. Child workflows
+    /// are cancelled with their parent.
+    pub fn cancellation_token(&self) -> CancellationToken {
+        self.cancellation.clone()
+    }
+
+    /// Replace the cancellation token, e.g. with one owned by a worker
+    pub fn set_cancellation_token(&mut self, token: CancellationToken) {
+        self.cancellation = token;
+    }
+
+    /// Request cancellation of the running execution
+    pub fn cancel(&self) {
+        self.cancellation.cancel();
+    }
+
+    /// Turn the console progress output on or off
+    ///
+    /// Observers still receive every event when the console is disabled.
+    pub fn set_console_output(&self, enabled: bool) {
+        self.events.console().set_enabled(enabled);
+    }
+
     /// Resolve workflow inputs by extracting default values
     fn resolve_workflow_inputs(workflow: &DSLWorkflow) -> HashMap<String, serde_json::Value> {
         let mut resolved = HashMap::new();
@@ -185,7 +525,8 @@ impl DSLExecutor {
     /// Supports all variable types including:
     /// - {{task.task_name.output}} - Replace with task output
     /// - ${task.task_name.output} - Replace with task output (dollar syntax)
-    fn substitute_variables_with_state(
+    /// - ${task.task_name.output.items[0].id} - Replace with a field of a structured output
+    pub(crate) fn substitute_variables_with_state(
         text: &str,
         workflow_inputs: &HashMap<String, serde_json::Value>,
         task_inputs: &HashMap<String, serde_json::Value>,
@@ -195,35 +536,38 @@ impl DSLExecutor {
 
         // Replace task output references if state is available
         if let Some(state) = workflow_state {
-            // Use regex to find all {{task.task_name.output}} and ${task.task_name.output} patterns
+            // Use regex to find all {{task.task_name.output...}} and ${task.task_name.output...} patterns
             use regex::Regex;
 
-            // Pattern for {{task.name.output}}
-            if let Ok(re) = Regex::new(r"\{\{task\.([a-zA-Z0-9_-]+)\.output\}\}") {
-                result = re
-                    .replace_all(&result, |caps: &regex::Captures| {
-                        let task_name = &caps[1];
-                        if let Some(task_output) = state.get_task_output(task_name) {
-                            task_output.content.clone()
-                        } else {
-                            caps[0].to_string() // Keep original if not found
+            let replace_output = |caps: &regex::Captures| {
+                let task_name = &caps[1];
+                let path = &caps[2];
+                match state.get_task_output(task_name) {
+                    // A bare reference keeps the raw output text
+                    Some(task_output) if path.is_empty() => task_output.content.clone(),
+                    Some(task_output) => {
+                        let value = task_output.structured();
+                        match crate::dsl::variables::resolve_path(&value, path) {
+                            Some(field) => crate::dsl::variables::value_to_string(field),
+                            None => caps[0].to_string(), // Keep original if not found
                         }
-                    })
-                    .to_string();
+                    }
+                    None => caps[0].to_string(), // Keep original if not found
+                }
+            };
+
+            // Pattern for {{task.name.output}}
+            if let Ok(re) = Regex::new(
+                r"\{\{task\.([a-zA-Z0-9_-]+)\.output((?:\.[a-zA-Z0-9_-]+|\[[0-9]+\])*)\}\}",
+            ) {
+                result = re.replace_all(&result, &replace_output).to_string();
             }
 
             // Pattern for ${task.name.output}
-            if let Ok(re) = Regex::new(r"\$\{task\.([a-zA-Z0-9_-]+)\.output\}") {
-                result = re
-                    .replace_all(&result, |caps: &regex::Captures| {
-                        let task_name = &caps[1];
-                        if let Some(task_output) = state.get_task_output(task_name) {
-                            task_output.content.clone()
-                        } else {
-                            caps[0].to_string() // Keep original if not found
-                        }
-                    })
-                    .to_string();
+            if let Ok(re) = Regex::new(
+                r"\$\{task\.([a-zA-Z0-9_-]+)\.output((?:\.[a-zA-Z0-9_-]+|\[[0-9]+\])*)\}",
+            ) {
+                result = re.replace_all(&result, &replace_output).to_string();
             }
         }
 
@@ -273,11 +617,9 @@ impl DSLExecutor {
             context = context.with_metadata("error", err);
         }
 
-        // Add secrets from workflow (just the keys, not values)
-        for secret_name in self.workflow.secrets.keys() {
-            // Secrets are resolved at runtime via environment or other sources
-            // For now, we'll just mark them as available in the context
-            context = context.with_secret(secret_name, format!("${{secret.{}}}", secret_name));
+        // Add resolved secrets; messages redact their values
+        for (name, value) in self.secrets.values() {
+            context = context.with_secret(name, value);
         }
 
         context
@@ -300,9 +642,12 @@ impl DSLExecutor {
         // Try to load existing state for resume
         if let Some(ref persistence) = self.state_persistence {
             if persistence.has_state(&self.workflow.name) {
-                println!(
-                    "Found existing state for workflow '{}' - will resume if possible",
-                    self.workflow.name
+                self.events.progress(
+                    None,
+                    format!(
+                        "Found existing state for workflow '{}' - will resume if possible",
+                        self.workflow.name
+                    ),
                 );
             }
         }
@@ -321,6 +666,7 @@ impl DSLExecutor {
     /// * `json` - True for JSON mode, false for interactive mode
     pub fn set_json_output(&mut self, json: bool) {
         self.json_output = json;
+        self.events.console().set_json_output(json);
     }
 
     /// Try to resume from saved state
@@ -332,18 +678,24 @@ impl DSLExecutor {
                 let saved_state = persistence.load_state(&self.workflow.name)?;
 
                 if saved_state.can_resume() {
-                    println!(
-                        "Resuming workflow '{}' from checkpoint (progress: {:.1}%)",
-                        self.workflow.name,
-                        saved_state.get_progress() * 100.0
+                    self.events.progress(
+                        None,
+                        format!(
+                            "Resuming workflow '{}' from checkpoint (progress: {:.1}%)",
+                            self.workflow.name,
+                            saved_state.get_progress() * 100.0
+                        ),
                     );
 
                     self.state = Some(saved_state);
                     return Ok(true);
                 } else {
-                    println!(
-                        "Cannot resume workflow '{}' - status: {:?}",
-                        self.workflow.name, saved_state.status
+                    self.events.progress(
+                        None,
+                        format!(
+                            "Cannot resume workflow '{}' - status: {:?}",
+                            self.workflow.name, saved_state.status
+                        ),
                     );
                 }
             }
@@ -357,6 +709,7 @@ impl DSLExecutor {
         if let (Some(ref mut state), Some(ref persistence)) =
             (&mut self.state, &self.state_persistence)
         {
+            state.messages = self.message_bus.log();
             persistence.save_state(state)?;
         }
         Ok(())
@@ -374,6 +727,13 @@ impl DSLExecutor {
 
     /// Initialize the executor by creating agents and building the task graph
     pub async fn initialize(&mut self) -> Result<()> {
+        // Resolve secrets before anything can reference them
+        let secret_values = self
+            .secret_resolver
+            .resolve_all(&self.workflow.secrets)
+            .await?;
+        self.secrets.load(secret_values);
+
         // Register agents with message bus
         for agent_name in self.workflow.agents.keys() {
             self.message_bus.register_agent(agent_name.clone()).await?;
@@ -391,6 +751,15 @@ impl DSLExecutor {
                     )
                     .await?;
             }
+            for (type_name, type_spec) in &comm_config.message_types {
+                self.message_bus
+                    .declare_message_type(type_name.clone(), type_spec.schema.clone());
+            }
+        }
+
+        // A resumed run continues with the messages sent before it stopped
+        if let Some(state) = &self.state {
+            self.message_bus.restore(state.messages.clone());
         }
 
         // Create variable context for interpolation
@@ -398,18 +767,67 @@ impl DSLExecutor {
         for (key, value) in &self.resolved_inputs {
             var_context.insert(&crate::dsl::variables::Scope::Workflow, key, value.clone());
         }
+        for (name, value) in self.secrets.values() {
+            var_context.insert(&crate::dsl::variables::Scope::Secret, &name, value.into());
+        }
 
-        // Create agent instances
+        // Create agent instances, checking their tool calls against the
+        // workflow's tool policy and their allowed directories
+        let tool_policy = Arc::new(ToolPolicy::new(
+            self.workflow.tools.clone().unwrap_or_default(),
+        ));
         for (name, spec) in &self.workflow.agents {
-            let options = self.agent_spec_to_options(
+            let mut options = self.agent_spec_to_options(
                 spec,
                 self.workflow.cwd.as_deref(),
                 self.workflow.create_cwd,
                 &var_context,
             )?;
+
+            // Agents of a workflow with channels get the message tools
+            let mut agent_tools = spec.tools.clone();
+            if self.workflow.communication.is_some() {
+                message_tools::attach(&mut options, name, self.message_bus.clone());
+                if !agent_tools.is_empty() {
+                    agent_tools.extend(message_tools::tool_names());
+                }
+            }
+            if self.workflow.tools.is_some() || !spec.permissions.allowed_directories.is_empty() {
+                let base_dir = options
+                    .cwd
+                    .clone()
+                    .or_else(|| std::env::current_dir().ok())
+                    .unwrap_or_default();
+                let guard = Arc::new(ToolGuard::new(
+                    name.clone(),
+                    agent_tools,
+                    Arc::clone(&tool_policy),
+                    FileScope::new(base_dir, &spec.permissions.allowed_directories),
+                    self.events.clone(),
+                    self.debugger.clone(),
+                ));
+                guard.apply_to(&mut options);
+                self.tool_guards.insert(name.clone(), guard);
+            }
+            self.agent_options.insert(name.clone(), options.clone());
+
+            // A resumed run reattaches the agent to its previous conversation
+            if let Some(session_id) = self
+                .state
+                .as_ref()
+                .and_then(|state| state.agent_session(name))
+            {
+                options.resume = Some(session_id.to_string());
+            }
             let mut client = PeriplonSDKClient::new(options);
             client.connect(None).await?;
-            self.agents.insert(name.clone(), client);
+            self.agents
+                .insert(name.clone(), Arc::new(Mutex::new(client)));
+        }
+
+        // Tasks defined inline in `workflows:` stages run like top-level tasks
+        for (name, spec) in inline_stage_tasks(&self.workflow) {
+            self.workflow.tasks.insert(name, spec);
         }
 
         // Build task graph with hierarchical tasks flattened
@@ -421,18 +839,32 @@ impl DSLExecutor {
             .map(|(name, spec)| (name.clone(), spec.clone()))
             .collect();
 
+        // Tasks offered as tools to `llm` tasks only run when a model calls them
+        let tool_tasks = crate::dsl::llm_tools::tool_task_ids(&self.workflow);
+
         for (name, spec) in tasks {
+            if tool_tasks.contains(&name) {
+                continue;
+            }
             self.add_hierarchical_task(&name, &spec, None)?;
         }
 
-        println!(
-            "Initialized {} agents and {} channels",
-            self.message_bus.agent_count().await,
-            self.message_bus.channel_count().await
+        // Order stage tasks according to the stages' dependencies and modes
+        self.stage_plan = StagePlan::from_workflow(&self.workflow)?;
+        self.stage_plan.apply_to_graph(&mut self.task_graph)?;
+
+        self.events.progress(
+            None,
+            format!(
+                "Initialized {} agents and {} channels",
+                self.message_bus.agent_count().await,
+                self.message_bus.channel_count().await
+            ),
         );
 
-        // Initialize workflow state if persistence is enabled and not resuming
-        if self.state_persistence.is_some() && self.state.is_none() {
+        // Initialize workflow state if not resuming. State is always tracked so
+        // tasks can reference each other's outputs; persistence only saves it
+        if self.state.is_none() {
             let mut state =
                 WorkflowState::new(self.workflow.name.clone(), self.workflow.version.clone());
 
@@ -440,14 +872,33 @@ impl DSLExecutor {
             for task_id in self.task_graph.topological_sort()? {
                 state.update_task_status(&task_id, TaskStatus::Pending);
             }
+            for stage in self.stage_plan.stages() {
+                state.update_stage_status(&stage.id, TaskStatus::Pending);
+            }
 
             self.state = Some(state);
-            println!("Initialized workflow state tracking");
+            self.events
+                .progress(None, "Initialized workflow state tracking");
         }
 
         Ok(())
     }
 
+    /// Find the spec of a task or subtask by its hierarchical name
+    ///
+    /// Subtasks are named `parent.child`, with one segment per level.
+    fn find_task_spec(&self, task_name: &str) -> Option<&crate::dsl::schema::TaskSpec> {
+        let mut segments = task_name.split('.');
+        let mut spec = self.workflow.tasks.get(segments.next()?)?;
+        for segment in segments {
+            spec = spec
+                .subtasks
+                .iter()
+                .find_map(|subtasks| subtasks.get(segment))?;
+        }
+        Some(spec)
+    }
+
     /// Add a task and its subtasks to the graph recursively
     ///
     /// # Arguments
@@ -470,7 +921,7 @@ impl DSLExecutor {
         // Build a map of sibling task names for dependency resolution
         let sibling_names: std::collections::HashSet<String> = if let Some(parent) = parent_name {
             // Get parent task spec to find all siblings
-            if let Some(parent_task) = self.workflow.tasks.get(parent) {
+            if let Some(parent_task) = self.find_task_spec(parent) {
                 parent_task
                     .subtasks
                     .iter()
@@ -532,9 +983,7 @@ impl DSLExecutor {
             // Check if parent task is executable by looking it up
             // A parent is only executable if it has no subtasks
             let parent_is_executable = self
-                .workflow
-                .tasks
-                .get(parent)
+                .find_task_spec(parent)
                 .map(|parent_spec| parent_spec.subtasks.is_empty())
                 .unwrap_or(true);
 
@@ -631,6 +1080,182 @@ impl DSLExecutor {
         }
     }
 
+    /// Token and cost limits of the workflow
+    fn budget(&self) -> Option<&BudgetConfig> {
+        self.workflow.limits.as_ref()?.budget.as_ref()
+    }
+
+    /// Fail with `Error::BudgetExceeded` if usage recorded up to the end of
+    /// `task_id` exceeds the budget
+    async fn check_budget(
+        &self,
+        task_id: &str,
+        state: &Mutex<Option<WorkflowState>>,
+    ) -> Option<Error> {
+        let budget = self.budget()?;
+        let state = state.lock().await;
+        state
+            .as_ref()?
+            .check_budget(budget, task_id)
+            .map(Error::BudgetExceeded)
+    }
+
+    /// Hooks of every `workflows:` entry, in name order
+    fn workflow_hooks(&self) -> Vec<&HooksSpec> {
+        let mut names: Vec<&String> = self.workflow.workflows.keys().collect();
+        names.sort();
+        names
+            .into_iter()
+            .filter_map(|name| self.workflow.workflows[name].hooks.as_ref())
+            .collect()
+    }
+
+    /// Record a status change for every stage containing the task
+    async fn update_stages_of_task(
+        &self,
+        task_id: &str,
+        status: TaskStatus,
+        state: &Arc<Mutex<Option<WorkflowState>>>,
+    ) {
+        if let Some(ref mut workflow_state) = *state.lock().await {
+            for stage in self.stage_plan.stages_of_task(task_id) {
+                if workflow_state.get_stage_status(&stage.id) != Some(status) {
+                    workflow_state.update_stage_status(&stage.id, status);
+                }
+            }
+        }
+    }
+
+    /// Report stages whose tasks have all finished
+    ///
+    /// Runs each stage's `on_stage_complete` hooks and records the stage as
+    /// completed so a resumed run skips it.
+    async fn complete_finished_stages(
+        &self,
+        task_graph: &Arc<Mutex<TaskGraph>>,
+        state: &Arc<Mutex<Option<WorkflowState>>>,
+        reported: &mut HashSet<String>,
+    ) -> Result<()> {
+        let finished = {
+            let graph = task_graph.lock().await;
+            self.stage_plan.finished_stages(&graph, reported)
+        };
+
+        for stage in finished {
+            reported.insert(stage.id.clone());
+            self.events.progress(
+                None,
+                format!("Stage completed: {} ({})", stage.name, stage.workflow),
+            );
+
+            let hooks = self
+                .workflow
+                .workflows
+                .get(&stage.workflow)
+                .and_then(|spec| spec.hooks.as_ref());
+            let hook_result = match hooks {
+                Some(hooks) if !hooks.on_stage_complete.is_empty() => {
+                    HooksExecutor::execute_stage_complete(
+                        &hooks.on_stage_complete,
+                        &self.workflow.name,
+                        &stage.name,
+                    )
+                    .await
+                }
+                _ => Ok(()),
+            };
+
+            if let Some(ref mut workflow_state) = *state.lock().await {
+                let status = if hook_result.is_ok() {
+                    TaskStatus::Completed
+                } else {
+                    TaskStatus::Failed
+                };
+                workflow_state.update_stage_status(&stage.id, status);
+                if let Some(ref persistence) = self.state_persistence {
+                    workflow_state.messages = self.message_bus.log();
+                    if let Err(e) = persistence.save_state(workflow_state) {
+                        self.events.warning(
+                            None,
+                            format!(
+                                "Failed to checkpoint state after stage '{}': {}",
+                                stage.name, e
+                            ),
+                        );
+                    }
+                }
+            }
+
+            hook_result?;
+        }
+
+        Ok(())
+    }
+
+    /// Debug hooks run before a task is dispatched
+    ///
+    /// Pauses at breakpoints, snapshots state and records task entry.
+    async fn debug_before_task(&self, task_id: &str, state: &Arc<Mutex<Option<WorkflowState>>>) {
+        // Check if should pause at this task
+        if self.check_debug_pause(task_id).await {
+            self.events
+                .progress(None, format!("⏸️  Breakpoint hit at task: {}", task_id));
+
+            // Display debugger status
+            if let Some(ref debugger) = self.debugger {
+                let dbg = debugger.lock().await;
+                self.events.progress(None, dbg.status_summary().to_string());
+            }
+
+            // Wait for user to continue
+            self.events
+                .progress(None, "⏸️  Execution paused. Waiting for continue...");
+            self.debug_wait_for_continue().await;
+            self.events.progress(None, "▶️  Execution resumed");
+        }
+
+        // Create snapshot before task execution
+        if let Some(ref workflow_state) = *state.lock().await {
+            if let Some(ref debugger) = self.debugger {
+                let mut dbg = debugger.lock().await;
+                dbg.create_snapshot(workflow_state, format!("Before task: {}", task_id));
+                self.events.progress(
+                    None,
+                    format!("📸 Snapshot created before task: {}", task_id),
+                );
+            }
+        }
+
+        // Record task entry
+        self.debug_enter_task(task_id, None).await;
+    }
+
+    /// Debug hooks run after a task finishes
+    async fn debug_after_task(
+        &self,
+        task_id: &str,
+        succeeded: bool,
+        state: &Arc<Mutex<Option<WorkflowState>>>,
+    ) {
+        // Record task exit
+        self.debug_exit_task().await;
+
+        // Create snapshot after task execution
+        if let Some(ref workflow_state) = *state.lock().await {
+            if let Some(ref debugger) = self.debugger {
+                let mut dbg = debugger.lock().await;
+                let description = if succeeded {
+                    format!("After task: {} (success)", task_id)
+                } else {
+                    format!("After task: {} (failed)", task_id)
+                };
+                dbg.create_snapshot(workflow_state, description);
+                self.events
+                    .progress(None, format!("📸 Snapshot created after task: {}", task_id));
+            }
+        }
+    }
+
     /// Create execution snapshot
     #[allow(dead_code)]
     async fn debug_create_snapshot(&self, description: String) {
@@ -683,16 +1308,15 @@ impl DSLExecutor {
 
     /// Execute the workflow
     ///
-    /// Executes tasks in topological order, respecting dependencies
-    /// with support for parallel execution and hooks
+    /// Starts each task as soon as its dependencies are satisfied, running
+    /// independent tasks concurrently within the workflow and per-agent
+    /// `max_concurrency` limits, with support for hooks
     pub async fn execute(&mut self) -> Result<()> {
         // Run pre-workflow hooks if they exist
-        if let Some(workflows) = self.workflow.workflows.values().next() {
-            if let Some(hooks) = &workflows.hooks {
-                if !hooks.pre_workflow.is_empty() {
-                    HooksExecutor::execute_pre_workflow(&hooks.pre_workflow, &self.workflow.name)
-                        .await?;
-                }
+        for hooks in self.workflow_hooks() {
+            if !hooks.pre_workflow.is_empty() {
+                HooksExecutor::execute_pre_workflow(&hooks.pre_workflow, &self.workflow.name)
+                    .await?;
             }
         }
 
@@ -700,36 +1324,42 @@ impl DSLExecutor {
         let execution_result = self.execute_tasks().await;
 
         // Run post-workflow hooks if they exist (even on error)
-        if let Some(workflows) = self.workflow.workflows.values().next() {
-            if let Some(hooks) = &workflows.hooks {
-                if !hooks.post_workflow.is_empty() {
-                    let _ = HooksExecutor::execute_post_workflow(
-                        &hooks.post_workflow,
-                        &self.workflow.name,
-                    )
-                    .await;
-                }
+        for hooks in self.workflow_hooks() {
+            if !hooks.post_workflow.is_empty() {
+                let _ =
+                    HooksExecutor::execute_post_workflow(&hooks.post_workflow, &self.workflow.name)
+                        .await;
             }
         }
 
         // If execution failed, run error hooks
         if let Err(ref e) = execution_result {
-            if let Some(workflows) = self.workflow.workflows.values().next() {
-                if let Some(hooks) = &workflows.hooks {
-                    if !hooks.on_error.is_empty() {
-                        let _ = HooksExecutor::execute_error(
-                            &hooks.on_error,
-                            &self.workflow.name,
-                            &e.to_string(),
-                        )
-                        .await;
-                    }
+            for hooks in self.workflow_hooks() {
+                if !hooks.on_error.is_empty() {
+                    let _ = HooksExecutor::execute_error(
+                        &hooks.on_error,
+                        &self.workflow.name,
+                        &e.to_string(),
+                    )
+                    .await;
                 }
             }
 
-            // Mark workflow as failed in state
+            // A cancelled run is paused so it can be resumed from its checkpoint,
+            // and so is one over a budget that pauses
+            let pause = match e {
+                Error::Cancelled => true,
+                Error::BudgetExceeded(_) => self
+                    .budget()
+                    .is_some_and(|budget| budget.on_exceeded == BudgetAction::Pause),
+                _ => false,
+            };
             if let Some(ref mut state) = self.state {
-                state.mark_failed();
+                if pause {
+                    state.mark_paused();
+                } else {
+                    state.mark_failed();
+                }
                 let _ = self.checkpoint_state();
             }
         } else {
@@ -740,6 +1370,15 @@ impl DSLExecutor {
             }
         }
 
+        self.events.emit(ExecutionEvent::WorkflowFinished {
+            workflow: self.workflow.name.clone(),
+            success: execution_result.is_ok(),
+            duration_ms: self
+                .workflow_start_time
+                .map_or(0, |start| start.elapsed().as_millis() as u64),
+            error: execution_result.as_ref().err().map(|e| e.to_string()),
+        });
+
         execution_result
     }
 
@@ -752,14 +1391,16 @@ impl DSLExecutor {
         if let Some(ref debugger) = self.debugger {
             let mut dbg = debugger.lock().await;
             dbg.start();
-            println!("🐛 Debug mode enabled");
+            self.events.progress(None, "🐛 Debug mode enabled");
         }
 
         // Get execution order
         let order = self.task_graph.topological_sort()?;
 
-        println!("Executing workflow: {}", self.workflow.name);
-        println!("Task execution order: {:?}", order);
+        self.events.emit(ExecutionEvent::WorkflowStarted {
+            workflow: self.workflow.name.clone(),
+            tasks: order.clone(),
+        });
 
         // Create initial snapshot if debugging
         if self.is_debug_mode() {
@@ -767,7 +1408,7 @@ impl DSLExecutor {
                 if let Some(ref debugger) = self.debugger {
                     let mut dbg = debugger.lock().await;
                     dbg.create_snapshot(state, "Workflow start".to_string());
-                    println!("📸 Created initial snapshot");
+                    self.events.progress(None, "📸 Created initial snapshot");
                 }
             }
         }
@@ -783,9 +1424,14 @@ impl DSLExecutor {
                     channels: notif_config.default_channels.clone(),
                     metadata: HashMap::new(),
                 };
-                if let Err(e) = self.notification_manager.send(&spec, &context).await {
-                    eprintln!("Warning: Failed to send workflow start notification: {}", e);
-                }
+                send_notification(
+                    &self.notification_manager,
+                    &self.events,
+                    None,
+                    &spec,
+                    &context,
+                )
+                .await;
             }
         }
 
@@ -795,189 +1441,231 @@ impl DSLExecutor {
         let state = Arc::new(Mutex::new(self.state.take()));
         let workflow_inputs = Arc::new(self.resolved_inputs.clone());
         let state_persistence = Arc::new(self.state_persistence.clone());
+        let workflow = Arc::new(self.workflow.clone());
+        let services = ExecutionServices {
+            mcp_clients: self.mcp_clients.clone(),
+            secrets: self.secrets.clone(),
+            secret_resolver: self.secret_resolver.clone(),
+            events: self.events.clone(),
+            state_persistence: self.state_persistence.clone(),
+            cancellation: self.cancellation.clone(),
+            tool_guards: Arc::new(self.tool_guards.clone()),
+            agent_options: Arc::new(self.agent_options.clone()),
+            message_bus: self.message_bus.clone(),
+            expressions: self.expressions.clone(),
+        };
         let workflow_name = Arc::new(self.workflow.name.clone());
 
-        // Track which tasks we've already processed
-        let mut processed = std::collections::HashSet::new();
+        // Errors from here on are recorded in `failure` rather than returned, so
+        // in-flight tasks are drained and the shared state is always restored
+        let mut failure: Option<Error> = None;
 
-        for task_id in order {
-            // Skip if already processed (as part of parallel group)
-            if processed.contains(&task_id) {
-                continue;
+        // Stages and tasks completed in a previous run are not executed again
+        // (resume functionality)
+        let mut reported_stages = HashSet::new();
+        if let Some(ref workflow_state) = *state.lock().await {
+            let mut graph = task_graph.lock().await;
+            for stage in self.stage_plan.stages() {
+                if workflow_state.get_stage_status(&stage.id) == Some(TaskStatus::Completed) {
+                    self.events.progress(
+                        None,
+                        format!("Skipping already completed stage: {}", stage.name),
+                    );
+                    for task_id in &stage.tasks {
+                        if let Err(e) = graph.update_task_status(task_id, TaskStatus::Completed) {
+                            failure.get_or_insert(e);
+                        }
+                    }
+                    reported_stages.insert(stage.id.clone());
+                }
             }
-
-            // Skip if task was already completed (resume functionality)
-            if let Some(ref workflow_state) = *state.lock().await {
-                if workflow_state.get_task_status(&task_id) == Some(TaskStatus::Completed) {
-                    println!("Skipping already completed task: {}", task_id);
-                    processed.insert(task_id.clone());
-                    continue;
+            for task_id in &order {
+                if workflow_state.get_task_status(task_id) == Some(TaskStatus::Completed) {
+                    self.events.progress(
+                        None,
+                        format!("Skipping already completed task: {}", task_id),
+                    );
+                    if let Err(e) = graph.update_task_status(task_id, TaskStatus::Completed) {
+                        failure.get_or_insert(e);
+                    }
                 }
             }
+        }
 
-            // Get parallel tasks
-            let parallel_tasks = {
-                let graph = task_graph.lock().await;
-                graph.get_parallel_tasks(&task_id)
-            };
-
-            if parallel_tasks.is_empty() {
-                // === DEBUG: Check breakpoints and pause if needed ===
-                if self.is_debug_mode() {
-                    // Check if should pause at this task
-                    if self.check_debug_pause(&task_id).await {
-                        println!("⏸️  Breakpoint hit at task: {}", task_id);
-
-                        // Display debugger status
-                        if let Some(ref debugger) = self.debugger {
-                            let dbg = debugger.lock().await;
-                            let status = dbg.status_summary();
-                            println!("{}", status);
-                        }
+        // Dispatch every task as soon as its dependencies are satisfied, bounded by
+        // the workflow and per-agent concurrency limits. Debug mode runs one task at
+        // a time so breakpoints and snapshots line up with task boundaries.
+        let mut limits = if self.is_debug_mode() {
+            ConcurrencyLimits::new(Some(1), &self.workflow.agents)
+        } else {
+            ConcurrencyLimits::new(self.workflow.max_concurrency, &self.workflow.agents)
+        };
+        let position: HashMap<&str, usize> = order
+            .iter()
+            .enumerate()
+            .map(|(index, id)| (id.as_str(), index))
+            .collect();
+        let mut running = tokio::task::JoinSet::new();
 
-                        // Wait for user to continue
-                        println!("⏸️  Execution paused. Waiting for continue...");
-                        self.debug_wait_for_continue().await;
-                        println!("▶️  Execution resumed");
-                    }
+        // Stages with nothing left to run are reported right away
+        if let Err(e) = self
+            .complete_finished_stages(&task_graph, &state, &mut reported_stages)
+            .await
+        {
+            failure.get_or_insert(e);
+        }
 
-                    // Create snapshot before task execution
-                    if let Some(ref workflow_state) = *state.lock().await {
-                        if let Some(ref debugger) = self.debugger {
-                            let mut dbg = debugger.lock().await;
-                            dbg.create_snapshot(
-                                workflow_state,
-                                format!("Before task: {}", task_id),
-                            );
-                            println!("📸 Snapshot created before task: {}", task_id);
-                        }
-                    }
-
-                    // Record task entry
-                    self.debug_enter_task(&task_id, None).await;
-                }
-
-                // Execute task sequentially
-                let task_result = self
-                    .execute_task_parallel(
-                        &task_id,
-                        agents.clone(),
-                        task_graph.clone(),
-                        state.clone(),
-                        workflow_inputs.clone(),
-                        state_persistence.clone(),
-                    )
-                    .await;
+        loop {
+            // Stop dispatching new work once a task has failed or the run is
+            // cancelled; in-flight tasks observe the token and wind down
+            if failure.is_none() && self.cancellation.is_cancelled() {
+                failure = Some(Error::Cancelled);
+            }
+            if failure.is_none() {
+                let ready = {
+                    let graph = task_graph.lock().await;
+                    let mut ready: Vec<(u32, String, Option<String>)> = graph
+                        .get_ready_tasks()
+                        .into_iter()
+                        .filter_map(|id| {
+                            let node = graph.get_task(&id)?;
+                            Some((node.spec.priority, id, node.spec.agent.clone()))
+                        })
+                        .collect();
+                    // Dispatch by priority, then topological order, so runs are deterministic
+                    ready.sort_by_key(|(priority, id, _)| {
+                        (*priority, position.get(id.as_str()).copied())
+                    });
+                    ready
+                };
 
-                // === DEBUG: Post-execution hooks ===
-                if self.is_debug_mode() {
-                    // Record task exit
-                    self.debug_exit_task().await;
-
-                    // Create snapshot after task execution
-                    if let Some(ref workflow_state) = *state.lock().await {
-                        if let Some(ref debugger) = self.debugger {
-                            let mut dbg = debugger.lock().await;
-                            let description = if task_result.is_ok() {
-                                format!("After task: {} (success)", task_id)
-                            } else {
-                                format!("After task: {} (failed)", task_id)
-                            };
-                            dbg.create_snapshot(workflow_state, description);
-                            println!("📸 Snapshot created after task: {}", task_id);
-                        }
+                for (_, task_id, agent) in ready {
+                    if limits.is_saturated() {
+                        break;
+                    }
+                    if !limits.try_acquire(agent.as_deref()) {
+                        continue;
                     }
-                }
 
-                // Propagate errors
-                task_result?;
-                processed.insert(task_id.clone());
-            } else {
-                // Execute tasks in parallel using tokio::spawn
-                println!(
-                    "Executing tasks in parallel: {} with {:?}",
-                    task_id, parallel_tasks
-                );
+                    // Claim the task so it is not dispatched twice
+                    let claimed = task_graph
+                        .lock()
+                        .await
+                        .update_task_status(&task_id, TaskStatus::Ready);
+                    if let Err(e) = claimed {
+                        limits.release(agent.as_deref());
+                        failure = Some(e);
+                        break;
+                    }
+                    self.update_stages_of_task(&task_id, TaskStatus::Running, &state)
+                        .await;
 
-                let mut handles = vec![];
+                    if self.is_debug_mode() {
+                        self.debug_before_task(&task_id, &state).await;
+                    }
 
-                // Spawn main task
-                {
-                    let task_id = task_id.clone();
                     let agents = agents.clone();
                     let graph = task_graph.clone();
                     let workflow_state = state.clone();
                     let inputs = workflow_inputs.clone();
                     let persistence = state_persistence.clone();
+                    let wf = workflow.clone();
+                    let task_services = services.clone();
                     let wf_name = workflow_name.clone();
                     let json_out = self.json_output;
 
-                    let handle = tokio::spawn(async move {
-                        execute_task_static(
-                            task_id,
+                    running.spawn(async move {
+                        let result = execute_task_static(
+                            task_id.clone(),
                             agents,
                             graph,
                             workflow_state,
                             inputs,
                             persistence,
+                            wf,
+                            task_services,
                             wf_name,
                             json_out,
                         )
-                        .await
+                        .await;
+                        (task_id, agent, result)
                     });
-                    handles.push(handle);
                 }
+            }
 
-                // Spawn parallel tasks
-                for parallel_id in &parallel_tasks {
-                    let status = {
-                        let graph = task_graph.lock().await;
-                        graph.get_task_status(parallel_id)
-                    };
+            // Wait for the next task to finish; done when nothing is in flight
+            let Some(joined) = running.join_next().await else {
+                break;
+            };
 
-                    if status == Some(TaskStatus::Pending) {
-                        let parallel_id = parallel_id.clone();
-                        let agents = agents.clone();
-                        let graph = task_graph.clone();
-                        let workflow_state = state.clone();
-                        let inputs = workflow_inputs.clone();
-                        let persistence = state_persistence.clone();
-                        let wf_name = workflow_name.clone();
-                        let json_out = self.json_output;
-
-                        let handle = tokio::spawn(async move {
-                            execute_task_static(
-                                parallel_id,
-                                agents,
-                                graph,
-                                workflow_state,
-                                inputs,
-                                persistence,
-                                wf_name,
-                                json_out,
-                            )
-                            .await
-                        });
-                        handles.push(handle);
+            match joined {
+                Ok((task_id, agent, result)) => {
+                    limits.release(agent.as_deref());
+
+                    if self.is_debug_mode() {
+                        self.debug_after_task(&task_id, result.is_ok(), &state)
+                            .await;
+                    }
+
+                    match result {
+                        Ok(()) => {
+                            // Tasks that return without a terminal status (e.g. loops)
+                            // still release their dependents
+                            {
+                                let mut graph = task_graph.lock().await;
+                                if !matches!(
+                                    graph.get_task_status(&task_id),
+                                    Some(TaskStatus::Completed | TaskStatus::Skipped)
+                                ) {
+                                    if let Err(e) =
+                                        graph.update_task_status(&task_id, TaskStatus::Completed)
+                                    {
+                                        failure.get_or_insert(e);
+                                    }
+                                }
+                            }
+
+                            if let Err(e) = self
+                                .complete_finished_stages(&task_graph, &state, &mut reported_stages)
+                                .await
+                            {
+                                failure.get_or_insert(e);
+                            }
+                        }
+                        Err(e) => {
+                            self.update_stages_of_task(&task_id, TaskStatus::Failed, &state)
+                                .await;
+                            failure.get_or_insert(e);
+                        }
                     }
-                }
 
-                // Wait for all parallel tasks to complete
-                for handle in handles {
-                    match handle.await {
-                        Ok(Ok(())) => {}
-                        Ok(Err(e)) => return Err(e),
-                        Err(e) => return Err(Error::InvalidInput(format!("Task panicked: {}", e))),
+                    // Stop dispatching once the task's usage exceeded the budget
+                    if failure.is_none() {
+                        failure = self.check_budget(&task_id, &state).await;
                     }
                 }
-
-                processed.insert(task_id.clone());
-                for parallel_id in parallel_tasks {
-                    processed.insert(parallel_id);
+                Err(e) => {
+                    failure.get_or_insert(Error::InvalidInput(format!("Task panicked: {}", e)));
                 }
             }
         }
 
+        // Anything still pending depends on a task that never completed
+        if failure.is_none() {
+            let graph = task_graph.lock().await;
+            let blocked: Vec<&str> = order
+                .iter()
+                .filter(|id| graph.get_task_status(id) == Some(TaskStatus::Pending))
+                .map(|id| id.as_str())
+                .collect();
+            if !blocked.is_empty() {
+                failure = Some(Error::InvalidInput(format!(
+                    "Tasks could not run because their dependencies did not complete: {}",
+                    blocked.join(", ")
+                )));
+            }
+        }
+
         // Restore agents, task graph, and state
         self.agents = Arc::try_unwrap(agents)
             .map_err(|_| Error::InvalidInput("Failed to unwrap agents".to_string()))?
@@ -988,14 +1676,19 @@ impl DSLExecutor {
         self.state = Arc::try_unwrap(state)
             .map_err(|_| Error::InvalidInput("Failed to unwrap state".to_string()))?
             .into_inner();
+        if let Some(ref mut state) = self.state {
+            state.messages = self.message_bus.log();
+        }
+
+        if let Some(e) = failure {
+            return Err(e);
+        }
 
         // Checkpoint state after execution
         if self.state.is_some() {
             let _ = self.checkpoint_state();
         }
 
-        println!("Workflow execution completed");
-
         // Send workflow completion notification if configured
         if let Some(notif_config) = &self.workflow.notifications {
             if notif_config.notify_on_workflow_completion
@@ -1011,12 +1704,14 @@ impl DSLExecutor {
                     channels: notif_config.default_channels.clone(),
                     metadata: HashMap::new(),
                 };
-                if let Err(e) = self.notification_manager.send(&spec, &context).await {
-                    eprintln!(
-                        "Warning: Failed to send workflow completion notification: {}",
-                        e
-                    );
-                }
+                send_notification(
+                    &self.notification_manager,
+                    &self.events,
+                    None,
+                    &spec,
+                    &context,
+                )
+                .await;
             }
         }
 
@@ -1027,20 +1722,22 @@ impl DSLExecutor {
                 if let Some(ref debugger) = self.debugger {
                     let mut dbg = debugger.lock().await;
                     dbg.create_snapshot(state, "Workflow completed".to_string());
-                    println!("📸 Created final snapshot");
+                    self.events.progress(None, "📸 Created final snapshot");
 
                     // Display final debug summary
-                    let status = dbg.status_summary();
-                    println!("\n🐛 Debug Session Summary:");
-                    println!("{}", status);
+                    self.events.progress(
+                        None,
+                        format!("\n🐛 Debug Session Summary:\n{}", dbg.status_summary()),
+                    );
 
                     // Display side effect summary
                     let side_effect_summary = dbg.side_effects.summary();
                     if !side_effect_summary.is_empty() {
-                        println!("\n📝 Side Effects Summary:");
+                        let mut summary = "\n📝 Side Effects Summary:".to_string();
                         for (effect_type, count) in side_effect_summary {
-                            println!("  {} x {}", count, effect_type);
+                            summary.push_str(&format!("\n  {} x {}", count, effect_type));
                         }
+                        self.events.progress(None, summary);
                     }
                 }
             }
@@ -1049,30 +1746,6 @@ impl DSLExecutor {
         Ok(())
     }
 
-    /// Execute a single task with shared state (for parallel execution)
-    async fn execute_task_parallel(
-        &self,
-        task_id: &str,
-        agents: Arc<Mutex<HashMap<String, PeriplonSDKClient>>>,
-        task_graph: Arc<Mutex<TaskGraph>>,
-        state: Arc<Mutex<Option<WorkflowState>>>,
-        workflow_inputs: Arc<HashMap<String, serde_json::Value>>,
-        state_persistence: Arc<Option<StatePersistence>>,
-    ) -> Result<()> {
-        let workflow_name = Arc::new(self.workflow.name.clone());
-        execute_task_static(
-            task_id.to_string(),
-            agents,
-            task_graph,
-            state,
-            workflow_inputs,
-            state_persistence,
-            workflow_name,
-            self.json_output,
-        )
-        .await
-    }
-
     /// Convert DSL AgentSpec to SDK AgentOptions
     ///
     /// # Arguments
@@ -1128,14 +1801,53 @@ impl DSLExecutor {
         // Determine create_cwd with cascading defaults
         let create_cwd = spec.create_cwd.or(workflow_create_cwd).unwrap_or(false);
 
+        // A provider profile picks the CLI and points it at the profile's
+        // endpoint through the CLI's environment
+        let mut provider = spec
+            .provider
+            .clone()
+            .unwrap_or_else(|| self.workflow.provider.clone());
+        let mut model = spec.model.clone();
+        let mut env = HashMap::new();
+        if let Some(name) = &spec.profile {
+            let profile = self.workflow.providers.get(name).ok_or_else(|| {
+                Error::InvalidInput(format!("Unknown provider profile '{}'", name))
+            })?;
+            provider = profile.provider.agent_cli().ok_or_else(|| {
+                Error::InvalidInput(format!(
+                    "Provider profile '{}' uses {:?}, which cannot run agents",
+                    name, profile.provider
+                ))
+            })?;
+            model = model.or_else(|| profile.model.clone());
+
+            let (endpoint_var, key_var) = match provider {
+                Provider::Claude => ("ANTHROPIC_BASE_URL", "ANTHROPIC_API_KEY"),
+                _ => ("OPENAI_BASE_URL", "OPENAI_API_KEY"),
+            };
+            let interpolate = |value: &String| {
+                var_context
+                    .interpolate(value)
+                    .unwrap_or_else(|_| value.clone())
+            };
+            if let Some(endpoint) = &profile.endpoint {
+                env.insert(endpoint_var.to_string(), interpolate(endpoint));
+            }
+            if let Some(api_key) = &profile.api_key {
+                env.insert(key_var.to_string(), interpolate(api_key));
+            }
+        }
+
         let options = AgentOptions {
+            provider: Some(provider),
             allowed_tools: spec.tools.clone(),
-            model: spec.model.clone(),
+            model,
             max_turns: spec.max_turns,
             permission_mode: Some(spec.permissions.mode.clone()),
             add_dirs,
             cwd,
             create_cwd,
+            env,
             ..Default::default()
         };
         Ok(options)
@@ -1143,14 +1855,17 @@ impl DSLExecutor {
 
     /// Shutdown the executor and disconnect all agents
     pub async fn shutdown(&mut self) -> Result<()> {
-        println!("Shutting down executor...");
+        self.events.progress(None, "Shutting down executor...");
 
-        for (name, agent) in &mut self.agents {
-            println!("Disconnecting agent: {}", name);
-            agent.disconnect().await?;
+        for (name, agent) in &self.agents {
+            self.events
+                .progress(None, format!("Disconnecting agent: {}", name));
+            agent.lock().await.disconnect().await?;
         }
 
-        println!("Executor shutdown complete");
+        self.mcp_clients.shutdown().await;
+
+        self.events.progress(None, "Executor shutdown complete");
         Ok(())
     }
 
@@ -1176,14 +1891,27 @@ impl DSLExecutor {
 #[allow(clippy::too_many_arguments)]
 async fn execute_task_static(
     task_id: String,
-    agents: Arc<Mutex<HashMap<String, PeriplonSDKClient>>>,
+    agents: Arc<Mutex<AgentClients>>,
     task_graph: Arc<Mutex<TaskGraph>>,
     state: Arc<Mutex<Option<WorkflowState>>>,
     workflow_inputs: Arc<HashMap<String, serde_json::Value>>,
     state_persistence: Arc<Option<StatePersistence>>,
+    workflow: Arc<DSLWorkflow>,
+    services: ExecutionServices,
     workflow_name: Arc<String>,
     json_output: bool,
 ) -> Result<()> {
+    let ctx = ExecutionContext {
+        workflow_inputs: &workflow_inputs,
+        agents: &agents,
+        task_graph: &task_graph,
+        state: &state,
+        workflow: &workflow,
+        services: &services,
+        workflow_name: &workflow_name,
+        json_output,
+    };
+
     // Get task spec and error recovery strategy
     let (spec, recovery_strategy) = {
         let graph = task_graph.lock().await;
@@ -1197,23 +1925,36 @@ async fn execute_task_static(
         (task_node.spec.clone(), strategy)
     };
 
-    println!("Executing task: {} - {}", task_id, spec.description);
+    let events = &services.events;
+    let started_at = Instant::now();
+    events.emit(ExecutionEvent::TaskStarted {
+        task_id: task_id.clone(),
+        description: spec.description.clone(),
+    });
 
     // Check if this is a loop task
     if let Some(ref loop_spec) = spec.loop_spec {
-        println!(
-            "Task '{}' has loop specification - delegating to loop executor",
-            task_id
+        events.progress(
+            Some(&task_id),
+            format!(
+                "Task '{}' has loop specification - delegating to loop executor",
+                task_id
+            ),
         );
-        let ctx = ExecutionContext {
-            workflow_inputs: &workflow_inputs,
-            agents: &agents,
-            task_graph: &task_graph,
-            state: &state,
-            workflow_name: &workflow_name,
-            json_output,
-        };
-        return execute_task_with_loop(&task_id, &spec, loop_spec, &ctx).await;
+        let result = execute_task_with_loop(&task_id, &spec, loop_spec, &ctx).await;
+        // Iterations failing on cancellation do not always stop the loop themselves
+        let result = services.check_cancelled().and(result);
+        events.emit(match result {
+            Ok(()) => ExecutionEvent::TaskSucceeded {
+                task_id: task_id.clone(),
+                duration_ms: started_at.elapsed().as_millis() as u64,
+            },
+            Err(ref e) => ExecutionEvent::TaskFailed {
+                task_id: task_id.clone(),
+                error: e.to_string(),
+            },
+        });
+        return result;
     }
 
     // Check condition if present
@@ -1222,11 +1963,42 @@ async fn execute_task_static(
             let graph = task_graph.lock().await;
             let workflow_state = state.lock().await;
             let state_ref = workflow_state.as_ref();
-            evaluate_condition(condition, &graph, state_ref)
+            evaluate_condition(
+                condition,
+                &graph,
+                state_ref,
+                ConditionVariables::new(&workflow_inputs, &services.expressions),
+            )
+        };
+
+        let condition_met = match condition_met {
+            Ok(met) => met,
+            Err(e) => {
+                let error = Error::InvalidInput(format!(
+                    "Task '{}' failed: condition could not be evaluated: {}",
+                    task_id, e
+                ));
+                {
+                    let mut graph = task_graph.lock().await;
+                    graph.update_task_status(&task_id, TaskStatus::Failed)?;
+                }
+                if let Some(ref mut workflow_state) = *state.lock().await {
+                    workflow_state.update_task_status(&task_id, TaskStatus::Failed);
+                    workflow_state.record_task_error(&task_id, &error.to_string());
+                }
+                events.emit(ExecutionEvent::TaskFailed {
+                    task_id: task_id.clone(),
+                    error: error.to_string(),
+                });
+                return Err(error);
+            }
         };
 
         if !condition_met {
-            println!("Task '{}' condition not met - skipping", task_id);
+            events.emit(ExecutionEvent::TaskSkipped {
+                task_id: task_id.clone(),
+                reason: "condition not met".to_string(),
+            });
 
             // Mark task as skipped
             {
@@ -1240,9 +2012,12 @@ async fn execute_task_static(
             return Ok(());
         }
 
-        println!(
-            "Task '{}' condition met - proceeding with execution",
-            task_id
+        events.progress(
+            Some(&task_id),
+            format!(
+                "Task '{}' condition met - proceeding with execution",
+                task_id
+            ),
         );
     }
 
@@ -1250,8 +2025,17 @@ async fn execute_task_static(
     let mut error_attempt = 0;
     // Track DoD retries separately
     let mut dod_attempt = 0;
-    // Store last DoD feedback for retry attempts
-    let mut last_dod_feedback: Option<String> = None;
+    // Track output schema retries separately
+    let mut schema_attempt = 0;
+    // Store last DoD or output schema feedback for retry attempts
+    let mut last_feedback: Option<String> = None;
+    // Agents are told the shape their result must have
+    let schema_instructions = match crate::dsl::output_schema::result_schema(&task_id, &spec) {
+        Some(schema) if spec.agent.is_some() => {
+            Some(crate::dsl::output_schema::schema_instructions(schema))
+        }
+        _ => None,
+    };
 
     loop {
         // Record attempt in state
@@ -1272,14 +2056,27 @@ async fn execute_task_static(
         for (key, value) in workflow_inputs.iter() {
             var_context.insert(&crate::dsl::variables::Scope::Workflow, key, value.clone());
         }
+        for (name, value) in services.secrets.values() {
+            var_context.insert(&crate::dsl::variables::Scope::Secret, &name, value.into());
+        }
+        if let Some(ref workflow_state) = *state.lock().await {
+            for (output_task_id, output) in &workflow_state.task_outputs {
+                var_context.insert_task_output(output_task_id, output.structured());
+            }
+        }
 
         // Interpolate task description
         let interpolated_description = var_context
             .interpolate(&spec.description)
             .unwrap_or_else(|_| spec.description.clone());
 
-        // Build task description with DoD feedback if this is a retry
-        let task_description = if let Some(ref feedback) = last_dod_feedback {
+        let interpolated_description = match schema_instructions {
+            Some(ref instructions) => format!("{}\n\n{}", interpolated_description, instructions),
+            None => interpolated_description,
+        };
+
+        // Build task description with DoD or schema feedback if this is a retry
+        let task_description = if let Some(ref feedback) = last_feedback {
             // Include the actual unmet criteria feedback
             format!("{}\n\n{}", interpolated_description, feedback)
         } else {
@@ -1291,85 +2088,193 @@ async fn execute_task_static(
             &task_id,
             &task_description,
             &spec,
-            &workflow_inputs,
-            &agents,
             error_attempt,
-            &state,
-            &workflow_name,
-            json_output,
+            last_feedback.as_deref(),
+            &ctx,
         )
         .await
         {
-            Ok(task_output) => {
+            Ok(mut task_output) => {
+                // Check the result and declared outputs against their schemas
+                let schema_errors = {
+                    let state_guard = state.lock().await;
+                    check_output_schemas(
+                        &task_id,
+                        &spec,
+                        &mut task_output,
+                        state_guard.as_ref(),
+                        &workflow_inputs,
+                    )
+                };
+
+                if !schema_errors.is_empty() {
+                    let feedback =
+                        crate::dsl::output_schema::format_schema_feedback(&schema_errors);
+                    events.progress(
+                        Some(&task_id),
+                        format!(
+                            "Output schema not met for task '{}':\n{}",
+                            task_id, feedback
+                        ),
+                    );
+
+                    schema_attempt += 1;
+                    let max_retries = spec
+                        .schema_retries
+                        .unwrap_or(crate::dsl::output_schema::DEFAULT_SCHEMA_RETRIES);
+
+                    if schema_attempt <= max_retries {
+                        events.emit(ExecutionEvent::TaskRetrying {
+                            task_id: task_id.clone(),
+                            attempt: schema_attempt,
+                            max_attempts: Some(max_retries),
+                            reason: RetryReason::OutputSchema,
+                            message: feedback.clone(),
+                            delay_secs: 0,
+                        });
+                        last_feedback = Some(feedback);
+                        continue;
+                    }
+
+                    events.progress(
+                        Some(&task_id),
+                        format!(
+                            "Task '{}' exhausted all schema retries ({}/{})",
+                            task_id, max_retries, max_retries
+                        ),
+                    );
+                    {
+                        let mut graph = task_graph.lock().await;
+                        graph.update_task_status(&task_id, TaskStatus::Failed)?;
+                    }
+                    if let Some(ref mut workflow_state) = *state.lock().await {
+                        workflow_state.update_task_status(&task_id, TaskStatus::Failed);
+                        workflow_state.record_task_error(
+                            &task_id,
+                            &format!(
+                                "Output did not match its schema after {} retries: {}",
+                                max_retries,
+                                schema_errors.join("; ")
+                            ),
+                        );
+                    }
+                    let error = Error::InvalidInput(format!(
+                        "Task '{}' failed: output did not match its schema after {} retries: {}",
+                        task_id,
+                        max_retries,
+                        schema_errors.join("; ")
+                    ));
+                    events.emit(ExecutionEvent::TaskFailed {
+                        task_id: task_id.clone(),
+                        error: error.to_string(),
+                    });
+                    return Err(error);
+                }
+
+                let output_text = task_output.as_ref().map(|result| result.text.clone());
+
                 // Task executed successfully - now check definition of done
                 if let Some(ref dod) = spec.definition_of_done {
-                    println!("Checking definition of done for task: {}", task_id);
+                    events.progress(
+                        Some(&task_id),
+                        format!("Checking definition of done for task: {}", task_id),
+                    );
+
+                    // Bypassing permissions would switch off an agent's tool policy
+                    let auto_elevate = dod.auto_elevate_permissions
+                        && !spec
+                            .agent
+                            .as_ref()
+                            .is_some_and(|agent| services.tool_guards.contains_key(agent));
 
                     // Use the variable context created earlier
                     let dod_results =
-                        check_definition_of_done(dod, task_output.as_deref(), &var_context).await;
+                        check_definition_of_done(dod, output_text.as_deref(), &var_context).await;
                     let all_met = dod_results.iter().all(|r| r.met);
 
-                    if !all_met {
+                    if all_met {
+                        events.emit(ExecutionEvent::DefinitionOfDoneChecked {
+                            task_id: task_id.clone(),
+                            met: true,
+                            feedback: None,
+                        });
+                    } else {
                         let mut unmet_feedback = format_unmet_criteria(&dod_results);
 
                         // Enhance feedback with permission hints
                         unmet_feedback = enhance_feedback_with_permission_hints(
                             unmet_feedback,
-                            task_output.as_deref().unwrap_or(""),
+                            output_text.as_deref().unwrap_or(""),
                             &dod_results,
-                            dod.auto_elevate_permissions,
+                            auto_elevate,
                         );
 
-                        println!("Definition of done not met for task '{}':", task_id);
-                        println!("{}", unmet_feedback);
+                        events.emit(ExecutionEvent::DefinitionOfDoneChecked {
+                            task_id: task_id.clone(),
+                            met: false,
+                            feedback: Some(unmet_feedback.clone()),
+                        });
 
                         dod_attempt += 1;
 
                         if dod_attempt <= dod.max_retries {
-                            println!(
-                                "Retrying task '{}' (DoD attempt {}/{})",
-                                task_id, dod_attempt, dod.max_retries
-                            );
+                            events.emit(ExecutionEvent::TaskRetrying {
+                                task_id: task_id.clone(),
+                                attempt: dod_attempt,
+                                max_attempts: Some(dod.max_retries),
+                                reason: RetryReason::DefinitionOfDone,
+                                message: unmet_feedback.clone(),
+                                delay_secs: 0,
+                            });
 
                             // Apply auto-elevation if configured and permission issue detected
-                            if dod.auto_elevate_permissions
+                            if auto_elevate
                                 && detect_permission_issue(
-                                    task_output.as_deref().unwrap_or(""),
+                                    output_text.as_deref().unwrap_or(""),
                                     &dod_results,
                                 )
                             {
-                                println!(
-                                    "  🔓 Auto-elevating permissions to 'bypassPermissions' for retry..."
+                                events.progress(
+                                    Some(&task_id),
+                                    "🔓 Auto-elevating permissions to 'bypassPermissions' for retry...",
                                 );
 
                                 // Actually update the agent's permission mode
                                 if let Some(ref agent_id) = spec.agent {
-                                    let mut agents_guard = agents.lock().await;
-                                    if let Some(agent) = agents_guard.get_mut(agent_id) {
-                                        if let Err(e) =
-                                            agent.set_permission_mode("bypassPermissions").await
+                                    let agent = agents.lock().await.get(agent_id).cloned();
+                                    if let Some(agent) = agent {
+                                        if let Err(e) = agent
+                                            .lock()
+                                            .await
+                                            .set_permission_mode("bypassPermissions")
+                                            .await
                                         {
-                                            eprintln!(
-                                                "Warning: Failed to elevate permissions: {}",
-                                                e
+                                            events.warning(
+                                                Some(&task_id),
+                                                format!("Failed to elevate permissions: {}", e),
                                             );
                                         } else {
-                                            println!("  ✓ Permissions elevated successfully");
+                                            events.progress(
+                                                Some(&task_id),
+                                                "✓ Permissions elevated successfully",
+                                            );
                                         }
                                     }
                                 }
                             }
 
                             // Store feedback for next iteration
-                            last_dod_feedback = Some(unmet_feedback);
+                            last_feedback = Some(unmet_feedback);
 
                             // Continue loop to retry with feedback
                             continue;
                         } else {
-                            println!(
-                                "Task '{}' exhausted all DoD retries ({}/{})",
-                                task_id, dod_attempt, dod.max_retries
+                            events.progress(
+                                Some(&task_id),
+                                format!(
+                                    "Task '{}' exhausted all DoD retries ({}/{})",
+                                    task_id, dod_attempt, dod.max_retries
+                                ),
                             );
 
                             if dod.fail_on_unmet {
@@ -1388,15 +2293,40 @@ async fn execute_task_static(
                                         ),
                                     );
                                 }
-                                return Err(Error::InvalidInput(format!(
+                                let error = Error::InvalidInput(format!(
                                     "Task '{}' failed: definition of done not met after {} retries",
                                     task_id, dod.max_retries
-                                )));
+                                ));
+                                events.emit(ExecutionEvent::TaskFailed {
+                                    task_id: task_id.clone(),
+                                    error: error.to_string(),
+                                });
+                                return Err(error);
                             }
                             // Otherwise fall through to mark as completed despite unmet DoD
                         }
-                    } else {
-                        println!("✓ Definition of done met for task: {}", task_id);
+                    }
+                }
+
+                // Send the result to the task's channel, failing the task
+                // when it is not a valid message
+                if let Some(ref result) = task_output {
+                    if let Err(e) =
+                        publish_result(&task_id, &spec, result, &services.message_bus).await
+                    {
+                        {
+                            let mut graph = task_graph.lock().await;
+                            graph.update_task_status(&task_id, TaskStatus::Failed)?;
+                        }
+                        if let Some(ref mut workflow_state) = *state.lock().await {
+                            workflow_state.update_task_status(&task_id, TaskStatus::Failed);
+                            workflow_state.record_task_error(&task_id, &e.to_string());
+                        }
+                        events.emit(ExecutionEvent::TaskFailed {
+                            task_id: task_id.clone(),
+                            error: e.to_string(),
+                        });
+                        return Err(e);
                     }
                 }
 
@@ -1409,8 +2339,8 @@ async fn execute_task_static(
                     workflow_state.update_task_status(&task_id, TaskStatus::Completed);
 
                     // Record task result for workflow context
-                    if let Some(ref output) = task_output {
-                        workflow_state.record_task_result(&task_id, output);
+                    if let Some(ref result) = task_output {
+                        workflow_state.record_task_result(&task_id, &result.text);
 
                         // Store task output with metadata for reference by other tasks
                         use crate::dsl::schema::TruncationStrategy;
@@ -1418,18 +2348,19 @@ async fn execute_task_static(
                         let task_output_obj = TaskOutput::new(
                             task_id.clone(),
                             OutputType::Combined,
-                            output.clone(),
-                            output.len(),
+                            result.text.clone(),
+                            result.text.len(),
                             false, // not truncated
                             TruncationStrategy::Tail,
-                        );
+                        )
+                        .with_value(result.value.clone());
                         workflow_state.store_task_output(task_output_obj);
                     }
                 }
 
                 // Write output to file if output directive is present
                 if let (Some(ref output_content), Some(ref output_path)) =
-                    (&task_output, &spec.output)
+                    (&output_text, &spec.output)
                 {
                     // Interpolate output path (supports workflow variables, task inputs, and task outputs)
                     let interpolated_path = {
@@ -1444,28 +2375,38 @@ async fn execute_task_static(
                     };
 
                     if let Err(e) = write_task_output_to_file(&interpolated_path, output_content) {
-                        eprintln!(
-                            "Warning: Failed to write task output to '{}': {}",
-                            interpolated_path, e
+                        events.warning(
+                            Some(&task_id),
+                            format!(
+                                "Failed to write task output to '{}': {}",
+                                interpolated_path, e
+                            ),
                         );
                     } else if !json_output {
-                        println!("✓ Output written to: {}", interpolated_path);
+                        events.progress(
+                            Some(&task_id),
+                            format!("✓ Output written to: {}", interpolated_path),
+                        );
                     }
                 }
 
                 // Checkpoint state after task completion
-                if let (Some(ref workflow_state), Some(ref persistence)) =
-                    (&*state.lock().await, &*state_persistence)
+                if let (Some(ref mut workflow_state), Some(ref persistence)) =
+                    (&mut *state.lock().await, &*state_persistence)
                 {
+                    workflow_state.messages = services.message_bus.log();
                     if let Err(e) = persistence.save_state(workflow_state) {
-                        eprintln!(
-                            "Warning: Failed to checkpoint state after task '{}': {}",
-                            task_id, e
+                        events.warning(
+                            Some(&task_id),
+                            format!("Failed to checkpoint state after task '{}': {}", task_id, e),
                         );
                     }
                 }
 
-                println!("Task completed: {}", task_id);
+                events.emit(ExecutionEvent::TaskSucceeded {
+                    task_id: task_id.clone(),
+                    duration_ms: started_at.elapsed().as_millis() as u64,
+                });
 
                 // Handle on_complete actions
                 if let Some(on_complete) = &spec.on_complete {
@@ -1477,20 +2418,33 @@ async fn execute_task_static(
                                 message.clone()
                             }
                         };
-                        println!("Notification: {}", message);
+                        events.emit(ExecutionEvent::NotificationSent {
+                            task_id: Some(task_id.clone()),
+                            message,
+                            error: None,
+                        });
                     }
                 }
 
                 return Ok(());
             }
+            Err(Error::Cancelled) => {
+                // Cancelled tasks are neither retried nor handed to a fallback.
+                // They stay pending in the checkpoint so a resumed run repeats them.
+                {
+                    let mut graph = task_graph.lock().await;
+                    graph.update_task_status(&task_id, TaskStatus::Failed)?;
+                }
+                if let Some(ref mut workflow_state) = *state.lock().await {
+                    workflow_state.update_task_status(&task_id, TaskStatus::Pending);
+                }
+                events.emit(ExecutionEvent::TaskFailed {
+                    task_id: task_id.clone(),
+                    error: Error::Cancelled.to_string(),
+                });
+                return Err(Error::Cancelled);
+            }
             Err(e) => {
-                println!(
-                    "Task '{}' failed (attempt {}): {}",
-                    task_id,
-                    error_attempt + 1,
-                    e
-                );
-
                 // Record error in state
                 if let Some(ref mut workflow_state) = *state.lock().await {
                     workflow_state.record_task_error(&task_id, &e.to_string());
@@ -1513,7 +2467,11 @@ async fn execute_task_static(
                     if let Some(fallback_agent) =
                         ErrorRecovery::get_fallback_agent(&recovery_strategy)
                     {
-                        println!("Attempting fallback with agent: {}", fallback_agent);
+                        events.emit(ExecutionEvent::TaskFallback {
+                            task_id: task_id.clone(),
+                            agent: fallback_agent.to_string(),
+                            error: e.to_string(),
+                        });
 
                         // Reset to Running status for fallback attempt
                         {
@@ -1531,12 +2489,17 @@ async fn execute_task_static(
                             &agents,
                             fallback_agent,
                             error_attempt,
-                            json_output,
+                            &services,
                         )
                         .await
                         {
-                            Ok(()) => {
-                                println!("Task completed with fallback agent: {}", task_id);
+                            Ok(usage) => {
+                                record_usage(&state, events, &task_id, Some(fallback_agent), usage)
+                                    .await;
+                                events.emit(ExecutionEvent::TaskSucceeded {
+                                    task_id: task_id.clone(),
+                                    duration_ms: started_at.elapsed().as_millis() as u64,
+                                });
 
                                 // Mark as completed
                                 {
@@ -1551,7 +2514,13 @@ async fn execute_task_static(
                                 return Ok(());
                             }
                             Err(fallback_err) => {
-                                println!("Fallback agent also failed: {}", fallback_err);
+                                events.emit(ExecutionEvent::TaskFailed {
+                                    task_id: task_id.clone(),
+                                    error: format!(
+                                        "Primary and fallback failed: {} / {}",
+                                        e, fallback_err
+                                    ),
+                                });
 
                                 // Mark as failed again
                                 {
@@ -1574,31 +2543,45 @@ async fn execute_task_static(
                         }
                     }
 
+                    events.emit(ExecutionEvent::TaskFailed {
+                        task_id: task_id.clone(),
+                        error: e.to_string(),
+                    });
                     return Err(e);
                 }
 
                 // Calculate retry delay with optional exponential backoff
                 let retry_delay = calculate_retry_delay(&recovery_strategy, error_attempt);
-                println!(
-                    "Retrying task '{}' (attempt {}) in {}s...",
-                    task_id,
-                    error_attempt + 1,
-                    retry_delay
-                );
-                tokio::time::sleep(tokio::time::Duration::from_secs(retry_delay)).await;
+                events.emit(ExecutionEvent::TaskRetrying {
+                    task_id: task_id.clone(),
+                    attempt: error_attempt,
+                    max_attempts: None,
+                    reason: RetryReason::Error,
+                    message: e.to_string(),
+                    delay_secs: retry_delay,
+                });
+                // The next attempt returns straight away if cancelled meanwhile
+                tokio::select! {
+                    _ = tokio::time::sleep(tokio::time::Duration::from_secs(retry_delay)) => {}
+                    _ = services.cancellation.cancelled() => {}
+                }
             }
         }
     }
 }
 
 /// Execute a script task
+#[allow(clippy::too_many_arguments)]
 async fn execute_script_task(
-    _task_id: &str,
+    task_id: &str,
     script_spec: &crate::dsl::schema::ScriptSpec,
     workflow_inputs: &HashMap<String, serde_json::Value>,
     task_inputs: &HashMap<String, serde_json::Value>,
+    workflow_state: Option<&WorkflowState>,
+    secrets: &SecretStore,
+    events: &ExecutionEvents,
     attempt: u32,
-) -> Result<Option<String>> {
+) -> Result<Option<TaskResult>> {
     use crate::dsl::schema::ScriptLanguage;
     use tokio::process::Command;
 
@@ -1616,8 +2599,12 @@ async fn execute_script_task(
         content.clone()
     } else if let Some(file_path) = &script_spec.file {
         // Interpolate variables in file path
-        let interpolated_file_path =
-            DSLExecutor::substitute_variables(file_path, workflow_inputs, task_inputs);
+        let interpolated_file_path = DSLExecutor::substitute_variables_with_state(
+            file_path,
+            workflow_inputs,
+            task_inputs,
+            workflow_state,
+        );
         tokio::fs::read_to_string(&interpolated_file_path)
             .await
             .map_err(|e| {
@@ -1632,26 +2619,41 @@ async fn execute_script_task(
         ));
     };
 
-    // Substitute workflow and task variables in script content
-    let script_content =
-        DSLExecutor::substitute_variables(&raw_script_content, workflow_inputs, task_inputs);
+    // Substitute workflow and task variables, then secrets, in script content
+    let script_content = secrets.substitute(&DSLExecutor::substitute_variables_with_state(
+        &raw_script_content,
+        workflow_inputs,
+        task_inputs,
+        workflow_state,
+    ))?;
 
     // Build command
     let mut cmd = Command::new(interpreter);
     cmd.args(&args);
     cmd.arg(&script_content);
+    // Timeouts and cancellation drop the running process
+    cmd.kill_on_drop(true);
 
     // Set working directory if specified (with variable interpolation)
     if let Some(working_dir) = &script_spec.working_dir {
-        let interpolated_working_dir =
-            DSLExecutor::substitute_variables(working_dir, workflow_inputs, task_inputs);
+        let interpolated_working_dir = DSLExecutor::substitute_variables_with_state(
+            working_dir,
+            workflow_inputs,
+            task_inputs,
+            workflow_state,
+        );
         cmd.current_dir(interpolated_working_dir);
     }
 
-    // Set environment variables (with variable interpolation)
+    // Set environment variables (with variable and secret interpolation)
     for (key, value) in &script_spec.env {
         let interpolated_value =
-            DSLExecutor::substitute_variables(value, workflow_inputs, task_inputs);
+            secrets.substitute(&DSLExecutor::substitute_variables_with_state(
+                value,
+                workflow_inputs,
+                task_inputs,
+                workflow_state,
+            ))?;
         cmd.env(key, interpolated_value);
     }
 
@@ -1659,17 +2661,14 @@ async fn execute_script_task(
     cmd.stdout(std::process::Stdio::piped());
     cmd.stderr(std::process::Stdio::piped());
 
+    let language = format!("{:?}", script_spec.language).to_lowercase();
     if attempt > 0 {
-        println!(
-            "  [Retry {}] Executing {} script...",
-            attempt,
-            format!("{:?}", script_spec.language).to_lowercase()
+        events.progress(
+            Some(task_id),
+            format!("[Retry {}] Executing {} script...", attempt, language),
         );
     } else {
-        println!(
-            "  Executing {} script...",
-            format!("{:?}", script_spec.language).to_lowercase()
-        );
+        events.progress(Some(task_id), format!("Executing {} script...", language));
     }
 
     // Execute with timeout if specified
@@ -1693,13 +2692,9 @@ async fn execute_script_task(
     let stdout = String::from_utf8_lossy(&output.stdout).to_string();
     let stderr = String::from_utf8_lossy(&output.stderr).to_string();
 
-    // Print output
-    if !stdout.is_empty() {
-        print!("{}", stdout);
-    }
-    if !stderr.is_empty() {
-        eprint!("{}", stderr);
-    }
+    // Show output
+    events.output(task_id, OutputStream::Stdout, secrets.redact(&stdout));
+    events.output(task_id, OutputStream::Stderr, secrets.redact(&stderr));
 
     // Check exit status
     if !output.status.success() {
@@ -1709,50 +2704,76 @@ async fn execute_script_task(
         )));
     }
 
-    // Return combined output
+    // Return combined output, with stdout as the structured value
     let combined_output = format!("{}{}", stdout, stderr);
     Ok(if combined_output.is_empty() {
         None
     } else {
-        Some(combined_output)
+        Some(TaskResult::new(combined_output, parse_output(&stdout)))
     })
 }
 
 /// Execute a command task
+#[allow(clippy::too_many_arguments)]
 async fn execute_command_task(
-    _task_id: &str,
+    task_id: &str,
     command_spec: &crate::dsl::schema::CommandSpec,
     workflow_inputs: &HashMap<String, serde_json::Value>,
     task_inputs: &HashMap<String, serde_json::Value>,
+    workflow_state: Option<&WorkflowState>,
+    secrets: &SecretStore,
+    events: &ExecutionEvents,
     attempt: u32,
-) -> Result<Option<String>> {
+) -> Result<Option<TaskResult>> {
     use tokio::process::Command;
 
     // Substitute variables in executable
-    let executable =
-        DSLExecutor::substitute_variables(&command_spec.executable, workflow_inputs, task_inputs);
+    let executable = DSLExecutor::substitute_variables_with_state(
+        &command_spec.executable,
+        workflow_inputs,
+        task_inputs,
+        workflow_state,
+    );
 
-    // Substitute variables in args
+    // Substitute variables and secrets in args
     let args: Vec<String> = command_spec
         .args
         .iter()
-        .map(|arg| DSLExecutor::substitute_variables(arg, workflow_inputs, task_inputs))
-        .collect();
+        .map(|arg| {
+            secrets.substitute(&DSLExecutor::substitute_variables_with_state(
+                arg,
+                workflow_inputs,
+                task_inputs,
+                workflow_state,
+            ))
+        })
+        .collect::<Result<_>>()?;
 
     // Build command
     let mut cmd = Command::new(&executable);
     cmd.args(&args);
+    // Timeouts and cancellation drop the running process
+    cmd.kill_on_drop(true);
 
     // Set working directory if specified
     if let Some(working_dir) = &command_spec.working_dir {
-        let working_dir =
-            DSLExecutor::substitute_variables(working_dir, workflow_inputs, task_inputs);
+        let working_dir = DSLExecutor::substitute_variables_with_state(
+            working_dir,
+            workflow_inputs,
+            task_inputs,
+            workflow_state,
+        );
         cmd.current_dir(working_dir);
     }
 
     // Set environment variables
     for (key, value) in &command_spec.env {
-        let value = DSLExecutor::substitute_variables(value, workflow_inputs, task_inputs);
+        let value = secrets.substitute(&DSLExecutor::substitute_variables_with_state(
+            value,
+            workflow_inputs,
+            task_inputs,
+            workflow_state,
+        ))?;
         cmd.env(key, value);
     }
 
@@ -1764,15 +2785,17 @@ async fn execute_command_task(
         cmd.stderr(std::process::Stdio::piped());
     }
 
+    let command_line = secrets.redact(&format!("{} {}", executable, args.join(" ")));
     if attempt > 0 {
-        println!(
-            "  [Retry {}] Executing command: {} {}",
-            attempt,
-            executable,
-            args.join(" ")
+        events.progress(
+            Some(task_id),
+            format!("[Retry {}] Executing command: {}", attempt, command_line),
         );
     } else {
-        println!("  Executing command: {} {}", executable, args.join(" "));
+        events.progress(
+            Some(task_id),
+            format!("Executing command: {}", command_line),
+        );
     }
 
     // Execute with timeout if specified
@@ -1804,13 +2827,9 @@ async fn execute_command_task(
         String::new()
     };
 
-    // Print output
-    if !stdout.is_empty() {
-        print!("{}", stdout);
-    }
-    if !stderr.is_empty() {
-        eprint!("{}", stderr);
-    }
+    // Show output
+    events.output(task_id, OutputStream::Stdout, secrets.redact(&stdout));
+    events.output(task_id, OutputStream::Stderr, secrets.redact(&stderr));
 
     // Check exit status
     if !output.status.success() {
@@ -1821,12 +2840,12 @@ async fn execute_command_task(
         )));
     }
 
-    // Return combined output
+    // Return combined output, with stdout as the structured value
     let combined_output = format!("{}{}", stdout, stderr);
     Ok(if combined_output.is_empty() {
         None
     } else {
-        Some(combined_output)
+        Some(TaskResult::new(combined_output, parse_output(&stdout)))
     })
 }
 
@@ -1854,25 +2873,36 @@ fn write_task_output_to_file(path: &str, content: &str) -> Result<()> {
 }
 
 /// Execute an LLM task (direct API call)
+#[allow(clippy::too_many_arguments)]
 async fn execute_llm_task(
-    _task_id: &str,
+    task_id: &str,
     llm_spec: &crate::dsl::schema::LlmSpec,
     workflow_inputs: &HashMap<String, serde_json::Value>,
     task_inputs: &HashMap<String, serde_json::Value>,
     workflow_state: Option<&crate::dsl::state::WorkflowState>,
+    secrets: &SecretStore,
+    events: &ExecutionEvents,
+    response_schema: Option<&serde_json::Value>,
+    tools: Option<&LlmTools<'_>>,
+    retry_feedback: Option<&str>,
     attempt: u32,
-) -> Result<Option<String>> {
+) -> Result<(Option<TaskResult>, Usage)> {
     use crate::adapters::secondary::HttpLlmClient;
     use crate::ports::secondary::{LlmClient, LlmRequest};
 
     // Substitute variables in prompt (including task outputs)
-    let prompt = DSLExecutor::substitute_variables_with_state(
+    let mut prompt = DSLExecutor::substitute_variables_with_state(
         &llm_spec.prompt,
         workflow_inputs,
         task_inputs,
         workflow_state,
     );
 
+    // Include feedback from the previous attempt on retries
+    if let Some(feedback) = retry_feedback {
+        prompt = format!("{}\n\n{}", prompt, feedback);
+    }
+
     // Substitute variables in system prompt if present
     let system_prompt = llm_spec.system_prompt.as_ref().map(|sp| {
         DSLExecutor::substitute_variables_with_state(
@@ -1883,35 +2913,49 @@ async fn execute_llm_task(
         )
     });
 
-    // Substitute variables in endpoint if present
-    let endpoint = llm_spec.endpoint.as_ref().map(|ep| {
-        DSLExecutor::substitute_variables_with_state(
-            ep,
-            workflow_inputs,
-            task_inputs,
-            workflow_state,
-        )
-    });
+    // Substitute variables and secrets in endpoint if present
+    let endpoint = llm_spec
+        .endpoint
+        .as_ref()
+        .map(|ep| {
+            secrets.substitute(&DSLExecutor::substitute_variables_with_state(
+                ep,
+                workflow_inputs,
+                task_inputs,
+                workflow_state,
+            ))
+        })
+        .transpose()?;
 
-    // Substitute variables in API key if present
-    let api_key = llm_spec.api_key.as_ref().map(|key| {
-        DSLExecutor::substitute_variables_with_state(
-            key,
-            workflow_inputs,
-            task_inputs,
-            workflow_state,
-        )
-    });
+    // Substitute variables and secrets in API key if present
+    let api_key = llm_spec
+        .api_key
+        .as_ref()
+        .map(|key| {
+            secrets.substitute(&DSLExecutor::substitute_variables_with_state(
+                key,
+                workflow_inputs,
+                task_inputs,
+                workflow_state,
+            ))
+        })
+        .transpose()?;
 
     if attempt > 0 {
-        println!(
-            "  [Retry {}] Executing LLM task with {:?} {}",
-            attempt, llm_spec.provider, llm_spec.model
+        events.progress(
+            Some(task_id),
+            format!(
+                "[Retry {}] Executing LLM task with {:?} {}",
+                attempt, llm_spec.provider, llm_spec.model
+            ),
         );
     } else {
-        println!(
-            "  Executing LLM task with {:?} {}",
-            llm_spec.provider, llm_spec.model
+        events.progress(
+            Some(task_id),
+            format!(
+                "Executing LLM task with {:?} {}",
+                llm_spec.provider, llm_spec.model
+            ),
         );
     }
 
@@ -1923,6 +2967,9 @@ async fn execute_llm_task(
         system_prompt,
         endpoint,
         api_key,
+        auth: llm_spec.auth,
+        auth_header: llm_spec.auth_header.clone(),
+        api_version: llm_spec.api_version.clone(),
         temperature: llm_spec.temperature,
         max_tokens: llm_spec.max_tokens,
         top_p: llm_spec.top_p,
@@ -1930,61 +2977,732 @@ async fn execute_llm_task(
         stop: llm_spec.stop.clone(),
         timeout_secs: llm_spec.timeout_secs,
         extra_params: llm_spec.extra_params.clone(),
+        response_schema: response_schema.cloned(),
+        messages: Vec::new(),
+        tools: tools
+            .map(|tools| tools.definitions().to_vec())
+            .unwrap_or_default(),
+        max_tool_iterations: llm_spec.max_tool_iterations,
     };
 
     // Create HTTP LLM client
     let client = HttpLlmClient::new();
 
-    // Execute LLM request
-    let response = client
-        .execute(request)
-        .await
-        .map_err(|e| Error::InvalidInput(format!("LLM execution failed: {}", e)))?;
+    // Execute LLM request, running the model's tool calls, or showing
+    // tokens as they arrive when streaming
+    let response = if let Some(tools) = tools {
+        let response = client
+            .execute_with_tools(request, tools)
+            .await
+            .map_err(|e| Error::InvalidInput(format!("LLM execution failed: {}", e)))?;
 
-    // Print response
-    println!("\n{}", response.content);
+        let content = secrets.redact(&response.content);
+        events.output(task_id, OutputStream::Stdout, format!("\n{}\n\n", content));
+        response
+    } else if llm_spec.stream {
+        use crate::ports::secondary::LlmStreamEvent;
 
-    // Print token usage if available
-    if let Some(usage) = &response.usage {
-        println!(
-            "\n  Token usage: {} input + {} output = {} total",
-            usage.prompt_tokens, usage.completion_tokens, usage.total_tokens
+        let mut stream = client
+            .execute_stream(request)
+            .await
+            .map_err(|e| Error::InvalidInput(format!("LLM execution failed: {}", e)))?;
+
+        events.output(task_id, OutputStream::Stdout, "\n");
+        let mut redactor = secrets.stream_redactor();
+        let mut response = None;
+        while let Some(event) = stream.next().await {
+            match event.map_err(|e| Error::InvalidInput(format!("LLM execution failed: {}", e)))? {
+                LlmStreamEvent::Delta(text) => {
+                    events.output(task_id, OutputStream::Stdout, redactor.push(&text))
+                }
+                LlmStreamEvent::Done(done) => response = Some(done),
+            }
+        }
+        events.output(
+            task_id,
+            OutputStream::Stdout,
+            format!("{}\n\n", redactor.finish()),
         );
-    }
-
-    // Print finish reason if available
-    if let Some(reason) = &response.finish_reason {
-        println!("  Finish reason: {}", reason);
-    }
 
-    println!(); // Add blank line for readability
+        response.ok_or_else(|| {
+            Error::InvalidInput("LLM execution failed: stream ended early".to_string())
+        })?
+    } else {
+        let response = client
+            .execute(request)
+            .await
+            .map_err(|e| Error::InvalidInput(format!("LLM execution failed: {}", e)))?;
+
+        // Show response
+        let content = secrets.redact(&response.content);
+        events.output(task_id, OutputStream::Stdout, format!("\n{}\n\n", content));
+        response
+    };
+
+    // Report token usage if available
+    if let Some(usage) = &response.usage {
+        events.progress(
+            Some(task_id),
+            format!(
+                "Token usage: {} input + {} output = {} total",
+                usage.prompt_tokens, usage.completion_tokens, usage.total_tokens
+            ),
+        );
+    }
+
+    // Report finish reason if available
+    if let Some(reason) = &response.finish_reason {
+        events.progress(Some(task_id), format!("Finish reason: {}", reason));
+    }
 
-    // Return content as output
-    Ok(Some(response.content))
+    // Return content as output; JSON responses stay navigable
+    let usage = response.usage.as_ref().map(Usage::from).unwrap_or_default();
+    Ok((Some(TaskResult::from_text(response.content)), usage))
 }
 
-/// Attempt to execute a task once
+/// Most characters of a response body quoted in the error of an HTTP task
+const MAX_HTTP_ERROR_BODY_CHARS: usize = 500;
+
+/// Execute an HTTP request task
+///
+/// The URL, headers, body and authentication values support workflow/task variables,
+/// task output references and `${secret.name}` references. The task output is a JSON
+/// document with the response `status`, `headers` and `body` (parsed as JSON when possible).
+/// Non-2xx responses are reported as errors so the task's `on_error` policy applies; the
+/// error quotes the start of the response body with secrets redacted.
+#[allow(clippy::too_many_arguments)]
+async fn execute_http_task(
+    task_id: &str,
+    http_spec: &crate::dsl::schema::HttpSpec,
+    workflow_inputs: &HashMap<String, serde_json::Value>,
+    task_inputs: &HashMap<String, serde_json::Value>,
+    workflow_state: Option<&crate::dsl::state::WorkflowState>,
+    secrets: &SecretStore,
+    events: &ExecutionEvents,
+    attempt: u32,
+) -> Result<Option<TaskResult>> {
+    use crate::dsl::schema::{HttpAuth, HttpMethod};
+
+    // Substitute workflow/task variables, task outputs, then secrets
+    let interpolate = |text: &str| -> Result<String> {
+        let substituted = DSLExecutor::substitute_variables_with_state(
+            text,
+            workflow_inputs,
+            task_inputs,
+            workflow_state,
+        );
+        secrets.substitute(&substituted)
+    };
+
+    let url = interpolate(&http_spec.url)?;
+    let method = match http_spec.method {
+        HttpMethod::Get => reqwest::Method::GET,
+        HttpMethod::Post => reqwest::Method::POST,
+        HttpMethod::Put => reqwest::Method::PUT,
+        HttpMethod::Patch => reqwest::Method::PATCH,
+        HttpMethod::Delete => reqwest::Method::DELETE,
+        HttpMethod::Head => reqwest::Method::HEAD,
+        HttpMethod::Options => reqwest::Method::OPTIONS,
+    };
+
+    // Build client honouring redirect and TLS settings
+    let mut client_builder = reqwest::Client::builder();
+    if !http_spec.follow_redirects {
+        client_builder = client_builder.redirect(reqwest::redirect::Policy::none());
+    }
+    if !http_spec.verify_tls {
+        client_builder = client_builder.danger_accept_invalid_certs(true);
+    }
+    if let Some(timeout_secs) = http_spec.timeout_secs {
+        client_builder = client_builder.timeout(std::time::Duration::from_secs(timeout_secs));
+    }
+    let client = client_builder
+        .build()
+        .map_err(|e| Error::InvalidInput(format!("Failed to build HTTP client: {}", e)))?;
+
+    let mut request = client.request(method.clone(), &url);
+
+    for (key, value) in &http_spec.headers {
+        request = request.header(key, interpolate(value)?);
+    }
+
+    if let Some(auth) = &http_spec.auth {
+        request = match auth {
+            HttpAuth::Bearer { token } => request.bearer_auth(interpolate(token)?),
+            HttpAuth::Basic { username, password } => {
+                request.basic_auth(interpolate(username)?, Some(interpolate(password)?))
+            }
+            HttpAuth::ApiKey { header, key } => request.header(header, interpolate(key)?),
+            HttpAuth::Custom { headers } => {
+                for (key, value) in headers {
+                    request = request.header(key, interpolate(value)?);
+                }
+                request
+            }
+        };
+    }
+
+    if let Some(body) = &http_spec.body {
+        request = request.body(interpolate(body)?);
+    }
+
+    let shown_url = secrets.redact(&url);
+    if attempt > 0 {
+        events.progress(
+            Some(task_id),
+            format!(
+                "[Retry {}] Executing HTTP request: {} {}",
+                attempt, method, shown_url
+            ),
+        );
+    } else {
+        events.progress(
+            Some(task_id),
+            format!("Executing HTTP request: {} {}", method, shown_url),
+        );
+    }
+
+    let response = request.send().await.map_err(|e| {
+        Error::InvalidInput(format!(
+            "HTTP request to '{}' failed: {}",
+            shown_url,
+            e.without_url()
+        ))
+    })?;
+
+    let status = response.status();
+    let headers: serde_json::Map<String, serde_json::Value> = response
+        .headers()
+        .iter()
+        .map(|(name, value)| {
+            (
+                name.to_string(),
+                serde_json::Value::String(String::from_utf8_lossy(value.as_bytes()).to_string()),
+            )
+        })
+        .collect();
+
+    let body_text = response.text().await.map_err(|e| {
+        Error::InvalidInput(format!(
+            "Failed to read response from '{}': {}",
+            shown_url,
+            e.without_url()
+        ))
+    })?;
+
+    events.progress(
+        Some(task_id),
+        format!("HTTP {} ({} bytes)", status, body_text.len()),
+    );
+
+    if !status.is_success() {
+        let mut message = format!(
+            "HTTP request to '{}' returned status: {}",
+            shown_url, status
+        );
+        let mut shown_body = secrets.redact(body_text.trim());
+        if !shown_body.is_empty() {
+            if let Some((index, _)) = shown_body.char_indices().nth(MAX_HTTP_ERROR_BODY_CHARS) {
+                shown_body.truncate(index);
+                shown_body.push_str("...");
+            }
+            message.push_str(&format!("\nResponse body: {}", shown_body));
+        }
+        return Err(Error::InvalidInput(message));
+    }
+
+    // Keep JSON bodies structured so downstream tasks can navigate them
+    let body = serde_json::from_str::<serde_json::Value>(&body_text)
+        .unwrap_or(serde_json::Value::String(body_text));
+
+    let output = serde_json::json!({
+        "status": status.as_u16(),
+        "headers": headers,
+        "body": body,
+    });
+
+    Ok(Some(TaskResult::new(output.to_string(), output)))
+}
+
+/// Interpolate every string inside a JSON value, at any depth
+fn interpolate_json_value(
+    value: &serde_json::Value,
+    interpolate: &dyn Fn(&str) -> Result<String>,
+) -> Result<serde_json::Value> {
+    Ok(match value {
+        serde_json::Value::String(s) => serde_json::Value::String(interpolate(s)?),
+        serde_json::Value::Array(items) => serde_json::Value::Array(
+            items
+                .iter()
+                .map(|item| interpolate_json_value(item, interpolate))
+                .collect::<Result<_>>()?,
+        ),
+        serde_json::Value::Object(map) => serde_json::Value::Object(
+            map.iter()
+                .map(|(k, v)| Ok((k.clone(), interpolate_json_value(v, interpolate)?)))
+                .collect::<Result<_>>()?,
+        ),
+        other => other.clone(),
+    })
+}
+
+/// Run a child workflow to completion with in-memory state tracking
+///
+/// Returns the execution result together with the child's final state. The
+/// child resolves its secrets with the parent's providers, reports its events
+/// to the parent's observers with task and agent IDs prefixed by `task_id/`,
+/// and is cancelled along with the parent. The future is boxed because child
+/// workflows may themselves contain subflow tasks.
+fn run_child_workflow(
+    task_id: &str,
+    workflow: DSLWorkflow,
+    secret_resolver: SecretResolver,
+    events: &Arc<ExecutionEvents>,
+    cancellation: CancellationToken,
+    json_output: bool,
+) -> futures::future::BoxFuture<'static, (Result<()>, Option<WorkflowState>)> {
+    let events = Arc::new(ExecutionEvents::scoped(events, task_id));
+    Box::pin(async move {
+        let mut executor = match DSLExecutor::with_events(workflow, events) {
+            Ok(executor) => executor,
+            Err(e) => return (Err(e), None),
+        };
+        executor.json_output = json_output;
+        executor.secret_resolver = secret_resolver;
+        executor.cancellation = cancellation;
+
+        if let Err(e) = executor.initialize().await {
+            let _ = executor.shutdown().await;
+            return (Err(e), None);
+        }
+
+        let result = executor.execute().await;
+        let _ = executor.shutdown().await;
+        (result, executor.state.take())
+    })
+}
+
+/// Execute a task that delegates to a child workflow
+///
+/// Covers `subflow`, `uses_workflow`, `uses` and `embed` tasks. The task's inputs
+/// are interpolated against the parent, mapped onto the child's declared inputs,
+/// and the child runs in its own executor under a namespaced name, its tasks and
+/// agents reported as `task_id/name`. Its final state is nested in the parent's
+/// state under the task id, and its declared outputs become the task output as a
+/// JSON object.
+async fn execute_child_workflow_task(
+    task_id: &str,
+    spec: &crate::dsl::schema::TaskSpec,
+    ctx: &ExecutionContext<'_>,
+    attempt: u32,
+) -> Result<Option<TaskResult>> {
+    use crate::dsl::subflow_executor::{
+        build_child_workflow, collect_child_outputs, map_child_inputs, resolve_child_workflow,
+    };
+
+    let provided_inputs = {
+        let state_guard = ctx.state.lock().await;
+        let interpolate = |text: &str| -> Result<String> {
+            let substituted = DSLExecutor::substitute_variables_with_state(
+                text,
+                ctx.workflow_inputs,
+                &HashMap::new(),
+                state_guard.as_ref(),
+            );
+            ctx.services.secrets.substitute(&substituted)
+        };
+        spec.inputs
+            .iter()
+            .map(|(k, v)| Ok((k.clone(), interpolate_json_value(v, &interpolate)?)))
+            .collect::<Result<HashMap<_, _>>>()?
+    };
+
+    let child = resolve_child_workflow(task_id, spec, &provided_inputs, ctx.workflow)
+        .await?
+        .ok_or_else(|| {
+            Error::InvalidInput(format!("Task '{}' does not reference a workflow", task_id))
+        })?;
+    let inputs = map_child_inputs(task_id, &child.inputs, &provided_inputs)?;
+    let workflow = build_child_workflow(ctx.workflow, &child, &inputs);
+
+    let events = &ctx.services.events;
+    if attempt > 0 {
+        events.progress(
+            Some(task_id),
+            format!(
+                "[Retry {}] Running {} '{}' ({} tasks)",
+                attempt,
+                child.kind,
+                child.name,
+                child.tasks.len()
+            ),
+        );
+    } else {
+        events.progress(
+            Some(task_id),
+            format!(
+                "Running {} '{}' ({} tasks)",
+                child.kind,
+                child.name,
+                child.tasks.len()
+            ),
+        );
+    }
+
+    let (result, child_state) = run_child_workflow(
+        task_id,
+        workflow,
+        ctx.services.secret_resolver.clone(),
+        &ctx.services.events,
+        ctx.services.cancellation.child_token(),
+        ctx.json_output,
+    )
+    .await;
+
+    if let (Some(ref mut parent_state), Some(child_state)) =
+        (&mut *ctx.state.lock().await, child_state.as_ref())
+    {
+        parent_state.store_subflow_state(task_id, child_state.clone());
+    }
+
+    result.map_err(|e| {
+        Error::InvalidInput(format!(
+            "{} '{}' failed in task '{}': {}",
+            child.kind, child.name, task_id, e
+        ))
+    })?;
+
+    let child_state = child_state.ok_or_else(|| {
+        Error::InvalidInput(format!("{} '{}' produced no state", child.kind, child.name))
+    })?;
+    let outputs = collect_child_outputs(&child, &child_state, &inputs)?;
+
+    Ok(Some(TaskResult::new(outputs.to_string(), outputs)))
+}
+
+/// Execute an MCP tool invocation task
+///
+/// String values in the tool parameters (at any depth) support the same variable,
+/// task output and `${secret.name}` references as HTTP tasks. The server is started
+/// on first use and shared with later tasks. Text results become the task output
+/// as-is; structured results are recorded as JSON. A tool result flagged as an
+/// error fails the task so its `on_error` policy applies.
 #[allow(clippy::too_many_arguments)]
+async fn execute_mcp_tool_task(
+    task_id: &str,
+    mcp_tool_spec: &crate::dsl::schema::McpToolSpec,
+    workflow_inputs: &HashMap<String, serde_json::Value>,
+    task_inputs: &HashMap<String, serde_json::Value>,
+    workflow_state: Option<&crate::dsl::state::WorkflowState>,
+    secrets: &SecretStore,
+    events: &ExecutionEvents,
+    mcp_clients: &McpClientPool,
+    attempt: u32,
+) -> Result<Option<TaskResult>> {
+    let interpolate = |text: &str| -> Result<String> {
+        let substituted = DSLExecutor::substitute_variables_with_state(
+            text,
+            workflow_inputs,
+            task_inputs,
+            workflow_state,
+        );
+        secrets.substitute(&substituted)
+    };
+
+    let arguments = mcp_tool_spec
+        .parameters
+        .iter()
+        .map(|(k, v)| Ok((k.clone(), interpolate_json_value(v, &interpolate)?)))
+        .collect::<Result<serde_json::Map<_, _>>>()?;
+
+    if attempt > 0 {
+        events.progress(
+            Some(task_id),
+            format!(
+                "[Retry {}] Calling MCP tool: {}/{}",
+                attempt, mcp_tool_spec.server, mcp_tool_spec.tool
+            ),
+        );
+    } else {
+        events.progress(
+            Some(task_id),
+            format!(
+                "Calling MCP tool: {}/{}",
+                mcp_tool_spec.server, mcp_tool_spec.tool
+            ),
+        );
+    }
+
+    let result = mcp_clients
+        .call_tool(
+            &mcp_tool_spec.server,
+            &mcp_tool_spec.tool,
+            serde_json::Value::Object(arguments),
+            mcp_tool_spec
+                .timeout_secs
+                .map(std::time::Duration::from_secs),
+        )
+        .await?;
+
+    let output = crate::dsl::mcp_clients::tool_result_to_output(&result);
+    if result.is_error {
+        return Err(Error::InvalidInput(format!(
+            "MCP tool '{}' on server '{}' failed: {}",
+            mcp_tool_spec.tool, mcp_tool_spec.server, output
+        )));
+    }
+
+    Ok(Some(TaskResult::from_text(output)))
+}
+
+/// Output of a single task execution
+///
+/// `text` is the readable output used for definition of done checks and
+/// `output` files; `value` is its structured form, referenced by other tasks
+/// as `${task.<id>.output.<path>}`.
+#[derive(Debug, Clone)]
+struct TaskResult {
+    text: String,
+    value: serde_json::Value,
+}
+
+impl TaskResult {
+    fn new(text: String, value: serde_json::Value) -> Self {
+        Self { text, value }
+    }
+
+    /// Result whose value is parsed from the text (JSON where possible)
+    fn from_text(text: String) -> Self {
+        let value = parse_output(&text);
+        Self { text, value }
+    }
+}
+
+/// Validate a task's result and declared outputs against their JSON Schemas
+///
+/// Returns the schema violations; violations of a named output are prefixed
+/// with its name. When a schema applies to the result and everything
+/// validates, the result's value is replaced by its JSON form.
+fn check_output_schemas(
+    task_id: &str,
+    spec: &crate::dsl::schema::TaskSpec,
+    result: &mut Option<TaskResult>,
+    workflow_state: Option<&WorkflowState>,
+    workflow_inputs: &HashMap<String, serde_json::Value>,
+) -> Vec<String> {
+    use crate::dsl::output_schema::{as_json, validate};
+    use crate::dsl::schema::OutputDataSource;
+
+    let result_value = result
+        .as_ref()
+        .map(|r| as_json(&r.value))
+        .unwrap_or(serde_json::Value::Null);
+    let mut result_checked = false;
+    let mut errors = Vec::new();
+    let mut check =
+        |schema: &serde_json::Value, value: &serde_json::Value, prefix: &str| match validate(
+            schema, value,
+        ) {
+            Ok(violations) => errors.extend(
+                violations
+                    .into_iter()
+                    .map(|violation| format!("{}{}", prefix, violation)),
+            ),
+            Err(e) => errors.push(format!("{}{}", prefix, e)),
+        };
+
+    if let Some(schema) = spec.llm.as_ref().and_then(|llm| llm.schema.as_ref()) {
+        check(schema, &result_value, "");
+        result_checked = true;
+    }
+
+    let mut names: Vec<&String> = spec.outputs.keys().collect();
+    names.sort();
+    for name in names {
+        let output = &spec.outputs[name];
+        let Some(schema) = &output.schema else {
+            continue;
+        };
+
+        let value = match &output.source {
+            OutputDataSource::TaskOutput { task } if task == task_id => {
+                result_checked = true;
+                result_value.clone()
+            }
+            OutputDataSource::TaskOutput { task } => workflow_state
+                .and_then(|state| state.get_task_output(task))
+                .map(|o| as_json(&o.structured()))
+                .unwrap_or(serde_json::Value::Null),
+            OutputDataSource::State { key } => workflow_state
+                .and_then(|state| state.get_metadata(key))
+                .cloned()
+                .unwrap_or(serde_json::Value::Null),
+            OutputDataSource::File { path } => {
+                let path = DSLExecutor::substitute_variables_with_state(
+                    path,
+                    workflow_inputs,
+                    &HashMap::new(),
+                    workflow_state,
+                );
+                std::fs::read_to_string(&path)
+                    .map(|content| parse_output(&content))
+                    .unwrap_or(serde_json::Value::Null)
+            }
+        };
+        check(schema, &value, &format!("output '{}': ", name));
+    }
+
+    if result_checked && errors.is_empty() {
+        if let Some(result) = result {
+            result.value = result_value;
+        }
+    }
+    errors
+}
+
+/// Runs tasks offered as tools to an `llm` task's model
+struct ContextTaskRunner<'a> {
+    ctx: &'a ExecutionContext<'a>,
+}
+
+#[async_trait::async_trait]
+impl TaskToolRunner for ContextTaskRunner<'_> {
+    async fn run_task(
+        &self,
+        task_id: &str,
+        arguments: serde_json::Map<String, serde_json::Value>,
+    ) -> Result<String> {
+        let mut spec = self
+            .ctx
+            .workflow
+            .tasks
+            .get(task_id)
+            .cloned()
+            .ok_or_else(|| {
+                Error::InvalidInput(format!("Tool task '{}' does not exist", task_id))
+            })?;
+        spec.inputs.extend(arguments);
+
+        // Boxed because the task may itself be an `llm` task with tools
+        let result = Box::pin(execute_task_attempt(
+            task_id,
+            &spec.description,
+            &spec,
+            0,
+            None,
+            self.ctx,
+        ))
+        .await?;
+        Ok(result.map(|result| result.text).unwrap_or_default())
+    }
+}
+
+/// Attempt to execute a task once
+///
+/// Secret values are redacted from the task's result and error, so they never
+/// reach the workflow state, its checkpoints or debugger snapshots.
 async fn execute_task_attempt(
+    task_id: &str,
+    task_description: &str,
+    spec: &crate::dsl::schema::TaskSpec,
+    attempt: u32,
+    retry_feedback: Option<&str>,
+    ctx: &ExecutionContext<'_>,
+) -> Result<Option<TaskResult>> {
+    ctx.services.check_cancelled()?;
+
+    let secrets = &ctx.services.secrets;
+    match run_task_attempt(
+        task_id,
+        task_description,
+        spec,
+        attempt,
+        retry_feedback,
+        ctx,
+    )
+    .await
+    {
+        Ok(result) => Ok(result.map(|result| TaskResult {
+            text: secrets.redact(&result.text),
+            value: secrets.redact_value(&result.value),
+        })),
+        Err(e) => Err(secrets.redact_error(e)),
+    }
+}
+
+/// A task's inputs, with the messages of each channel it reads as `messages.<channel>`
+fn message_inputs(
+    spec: &crate::dsl::schema::TaskSpec,
+    bus: &MessageBus,
+) -> HashMap<String, serde_json::Value> {
+    let mut inputs = spec.inputs.clone();
+    if let Some(messages) = &spec.messages {
+        for channel in &messages.read {
+            inputs.insert(
+                format!("messages.{}", channel),
+                message_tools::messages_json(&bus.transcript(channel)),
+            );
+        }
+    }
+    inputs
+}
+
+/// Send a task's result as the message its `messages.publish` declares
+async fn publish_result(
+    task_id: &str,
+    spec: &crate::dsl::schema::TaskSpec,
+    result: &TaskResult,
+    bus: &MessageBus,
+) -> Result<()> {
+    let Some(messages) = &spec.messages else {
+        return Ok(());
+    };
+    let Some(publish) = &messages.publish else {
+        return Ok(());
+    };
+    message_tools::send(
+        bus,
+        messages.participant_of(task_id, spec),
+        publish.channel.as_deref(),
+        publish.to.as_deref(),
+        &publish.message_type,
+        crate::dsl::output_schema::as_json(&result.value),
+    )
+    .await?;
+    Ok(())
+}
+
+/// Run a task once, dispatching on its kind
+async fn run_task_attempt(
     _task_id: &str,
     task_description: &str,
     _spec: &crate::dsl::schema::TaskSpec,
-    workflow_inputs: &HashMap<String, serde_json::Value>,
-    agents: &Arc<Mutex<HashMap<String, PeriplonSDKClient>>>,
     attempt: u32,
-    workflow_state: &Arc<Mutex<Option<crate::dsl::state::WorkflowState>>>,
-    workflow_name: &str,
-    json_output: bool,
-) -> Result<Option<String>> {
+    retry_feedback: Option<&str>,
+    ctx: &ExecutionContext<'_>,
+) -> Result<Option<TaskResult>> {
+    let workflow_inputs = ctx.workflow_inputs;
+    let agents = ctx.agents;
+    let workflow_state = ctx.state;
+    let workflow_name = ctx.workflow_name.as_str();
+    let task_inputs = message_inputs(_spec, &ctx.services.message_bus);
+
     // Check what type of task this is and execute accordingly
     if let Some(script_spec) = &_spec.script {
-        // Execute script task with variable substitution
-        return execute_script_task(
-            _task_id,
-            script_spec,
-            workflow_inputs,
-            &_spec.inputs,
-            attempt,
+        // Execute script task with variable and task output substitution
+        let state_snapshot = workflow_state.lock().await.clone();
+        return cancellable(
+            &ctx.services.cancellation,
+            execute_script_task(
+                _task_id,
+                script_spec,
+                workflow_inputs,
+                &task_inputs,
+                state_snapshot.as_ref(),
+                &ctx.services.secrets,
+                &ctx.services.events,
+                attempt,
+            ),
         )
         .await;
     }
@@ -1992,33 +3710,128 @@ async fn execute_task_attempt(
     // Check if this is a command task
     if let Some(command_spec) = &_spec.command {
         // Execute command task
-        return execute_command_task(
-            _task_id,
-            command_spec,
-            workflow_inputs,
-            &_spec.inputs,
-            attempt,
+        let state_snapshot = workflow_state.lock().await.clone();
+        return cancellable(
+            &ctx.services.cancellation,
+            execute_command_task(
+                _task_id,
+                command_spec,
+                workflow_inputs,
+                &task_inputs,
+                state_snapshot.as_ref(),
+                &ctx.services.secrets,
+                &ctx.services.events,
+                attempt,
+            ),
+        )
+        .await;
+    }
+
+    // Check if this is an HTTP task
+    if let Some(http_spec) = &_spec.http {
+        // Snapshot state so the lock isn't held for the duration of the request
+        let state_snapshot = workflow_state.lock().await.clone();
+        return cancellable(
+            &ctx.services.cancellation,
+            execute_http_task(
+                _task_id,
+                http_spec,
+                workflow_inputs,
+                &task_inputs,
+                state_snapshot.as_ref(),
+                &ctx.services.secrets,
+                &ctx.services.events,
+                attempt,
+            ),
         )
         .await;
     }
 
+    // Check if this is an MCP tool task
+    if let Some(mcp_tool_spec) = &_spec.mcp_tool {
+        let state_snapshot = workflow_state.lock().await.clone();
+        return cancellable(
+            &ctx.services.cancellation,
+            execute_mcp_tool_task(
+                _task_id,
+                mcp_tool_spec,
+                workflow_inputs,
+                &task_inputs,
+                state_snapshot.as_ref(),
+                &ctx.services.secrets,
+                &ctx.services.events,
+                &ctx.services.mcp_clients,
+                attempt,
+            ),
+        )
+        .await;
+    }
+
+    // Check if this task delegates to another workflow
+    if _spec.subflow.is_some()
+        || _spec.uses_workflow.is_some()
+        || _spec.uses.is_some()
+        || _spec.embed.is_some()
+    {
+        return execute_child_workflow_task(_task_id, _spec, ctx, attempt).await;
+    }
+
     // Check if this is an LLM task
     if let Some(llm_spec) = &_spec.llm {
-        // Execute LLM task with state for task output references
-        let state_ref = {
-            let state_guard = workflow_state.lock().await;
-            state_guard.as_ref().map(|s| s as *const _)
+        // Fill in the provider and connection settings from a profile
+        let resolved;
+        let llm_spec = match &llm_spec.profile {
+            Some(name) => {
+                let profile = ctx.workflow.providers.get(name).ok_or_else(|| {
+                    Error::InvalidInput(format!("Unknown provider profile '{}'", name))
+                })?;
+                resolved = llm_spec.with_profile(profile);
+                &resolved
+            }
+            None => llm_spec,
         };
-        let state_opt = unsafe { state_ref.map(|ptr| &*ptr) };
-        return execute_llm_task(
-            _task_id,
-            llm_spec,
-            workflow_inputs,
-            &_spec.inputs,
-            state_opt,
-            attempt,
+
+        // Build the tools the model may call
+        let runner = ContextTaskRunner { ctx };
+        let tools = if llm_spec.tools.is_empty() {
+            None
+        } else {
+            Some(
+                LlmTools::build(
+                    _task_id,
+                    &llm_spec.tools,
+                    ctx.workflow,
+                    &llm_spec.permissions,
+                    llm_spec.timeout_secs,
+                    &ctx.services.mcp_clients,
+                    &runner,
+                    &ctx.services.events,
+                )
+                .await?,
+            )
+        };
+
+        // Execute LLM task with state for task output references
+        let state_snapshot = workflow_state.lock().await.clone();
+        let (result, usage) = cancellable(
+            &ctx.services.cancellation,
+            execute_llm_task(
+                _task_id,
+                llm_spec,
+                workflow_inputs,
+                &task_inputs,
+                state_snapshot.as_ref(),
+                &ctx.services.secrets,
+                &ctx.services.events,
+                crate::dsl::output_schema::result_schema(_task_id, _spec),
+                tools.as_ref(),
+                retry_feedback,
+                attempt,
+            ),
         )
-        .await;
+        .await?;
+        record_usage(workflow_state, &ctx.services.events, _task_id, None, usage).await;
+        return Ok(result);
     }
 
     // Default to agent-based execution
@@ -2053,76 +3866,302 @@ async fn execute_task_attempt(
         task_description.to_string()
     };
 
-    // Execute task query
-    let mut agents_guard = agents.lock().await;
-    let agent = agents_guard
-        .get_mut(agent_name)
-        .ok_or_else(|| Error::InvalidInput(format!("Agent '{}' not found", agent_name)))?;
+    let reply = match &_spec.session {
+        // Tasks without a session share the agent's long-lived conversation
+        None => {
+            let mut agent = AgentLease::acquire(agents, ctx.services, agent_name).await?;
+            ctx.services.start_agent_task(agent_name, _task_id);
+            let reply = query_agent(
+                agent.client(),
+                &enhanced_description,
+                ctx.services,
+                _task_id,
+                agent_name,
+                attempt,
+            )
+            .await;
+            let shared = agent.is_shared();
+            agent.release().await;
+            let mut reply = reply?;
+            // A side session does not replace the agent's conversation
+            if !shared {
+                reply.session_id = None;
+            }
+            reply
+        }
+        Some(session) => {
+            let mut options = ctx
+                .services
+                .agent_options
+                .get(agent_name)
+                .cloned()
+                .ok_or_else(|| Error::InvalidInput(format!("Agent '{}' not found", agent_name)))?;
+            if let Some(ref state) = *workflow_state.lock().await {
+                apply_session(&mut options, session, agent_name, state);
+            }
 
-    agent.query(&enhanced_description).await?;
+            let mut agent = PeriplonSDKClient::new(options);
+            agent.connect(None).await?;
+            ctx.services.start_agent_task(agent_name, _task_id);
+            let reply = query_agent(
+                &mut agent,
+                &enhanced_description,
+                ctx.services,
+                _task_id,
+                agent_name,
+                attempt,
+            )
+            .await;
+            let _ = agent.disconnect().await;
+            reply?
+        }
+    };
 
-    // Process response and capture output
-    let stream = agent.receive_response()?;
-    futures::pin_mut!(stream);
-    let mut output = String::new();
-
-    while let Some(msg) = stream.next().await {
-        // Capture output for DoD checking
-        output.push_str(&format!("{:?}\n", msg));
-
-        // Log messages using formatter
-        if attempt > 0 {
-            let prefix_str = format!("Retry {}", attempt);
-            let formatted =
-                crate::dsl::message_formatter::format_message(&msg, json_output, Some(&prefix_str));
-            println!("{}", formatted);
-        } else {
-            let formatted = crate::dsl::message_formatter::format_message(&msg, json_output, None);
-            println!("{}", formatted);
+    record_usage(
+        workflow_state,
+        &ctx.services.events,
+        _task_id,
+        Some(agent_name),
+        reply.usage,
+    )
+    .await;
+
+    // Later tasks and resumed runs continue from this conversation
+    if let Some(ref session_id) = reply.session_id {
+        if let Some(ref mut state) = *workflow_state.lock().await {
+            let name = match &_spec.session {
+                Some(SessionSpec::Named(name)) => Some(name.as_str()),
+                _ => None,
+            };
+            state.record_session(agent_name, name, session_id);
         }
     }
 
-    Ok(if output.is_empty() {
+    let output = reply.output;
+    Ok(if output.trim().is_empty() {
         None
     } else {
-        Some(output)
+        Some(TaskResult::from_text(output))
+    })
+}
+
+/// Output of one agent query
+struct AgentReply {
+    /// Final result, or the assistant's text when there is none
+    output: String,
+    /// Conversation session the query ran in
+    session_id: Option<String>,
+    /// Tokens and cost reported in the result message
+    usage: Usage,
+}
+
+/// Send a task's prompt to an agent and collect its reply
+async fn query_agent(
+    agent: &mut PeriplonSDKClient,
+    prompt: &str,
+    services: &ExecutionServices,
+    task_id: &str,
+    agent_name: &str,
+    attempt: u32,
+) -> Result<AgentReply> {
+    agent.query(prompt).await?;
+
+    // Process response and capture output
+    let mut assistant_text = String::new();
+    let mut final_result = None;
+    let mut session_id = None;
+    let mut usage = Usage::default();
+
+    stream_agent_response(agent, &services.cancellation, |msg| {
+        // Capture the assistant's text and final result for DoD checking and later tasks
+        match &msg {
+            Message::Assistant(assistant) => {
+                for block in &assistant.message.content {
+                    if let ContentBlock::Text { text } = block {
+                        assistant_text.push_str(text);
+                        assistant_text.push('\n');
+                    }
+                }
+            }
+            Message::Result(result) => {
+                final_result = result.result.clone();
+                session_id = Some(result.session_id.clone());
+                usage = Usage::from_result(result);
+            }
+            _ => {}
+        }
+
+        services.events.emit(ExecutionEvent::AgentMessage {
+            task_id: task_id.to_string(),
+            agent: agent_name.to_string(),
+            attempt,
+            fallback: false,
+            message: services.secrets.redact_message(msg),
+        });
     })
+    .await?;
+
+    Ok(AgentReply {
+        output: final_result.unwrap_or(assistant_text),
+        session_id,
+        usage,
+    })
+}
+
+/// Point an agent's options at the conversation a task's `session:` selects
+///
+/// Sessions that do not exist yet start a fresh conversation.
+fn apply_session(
+    options: &mut AgentOptions,
+    session: &SessionSpec,
+    agent_name: &str,
+    state: &WorkflowState,
+) {
+    let resume = match session {
+        SessionSpec::New => None,
+        SessionSpec::Continue | SessionSpec::Fork => state.agent_session(agent_name),
+        SessionSpec::Named(name) => state.named_session(name),
+    };
+    options.resume = resume.map(str::to_string);
+    options.fork_session = *session == SessionSpec::Fork && options.resume.is_some();
+}
+
+/// Send a notification and report the outcome as an event
+async fn send_notification(
+    manager: &NotificationManager,
+    events: &ExecutionEvents,
+    task_id: Option<&str>,
+    spec: &crate::dsl::schema::NotificationSpec,
+    context: &NotificationContext,
+) {
+    let message = match spec {
+        crate::dsl::NotificationSpec::Simple(message)
+        | crate::dsl::NotificationSpec::Structured { message, .. } => message.clone(),
+    };
+    let error = manager
+        .send(spec, context)
+        .await
+        .err()
+        .map(|e| e.to_string());
+    events.emit(ExecutionEvent::NotificationSent {
+        task_id: task_id.map(str::to_string),
+        message,
+        error,
+    });
 }
 
 /// Execute a task with a specific agent (for fallback support)
 async fn execute_task_with_agent(
-    _task_id: &str,
+    task_id: &str,
     spec: &crate::dsl::schema::TaskSpec,
-    agents: &Arc<Mutex<HashMap<String, PeriplonSDKClient>>>,
+    agents: &Arc<Mutex<AgentClients>>,
     agent_name: &str,
     attempt: u32,
-    json_output: bool,
-) -> Result<()> {
+    services: &ExecutionServices,
+) -> Result<Usage> {
     // Execute task query with specified agent
-    let mut agents_guard = agents.lock().await;
-    let agent = agents_guard
-        .get_mut(agent_name)
-        .ok_or_else(|| Error::InvalidInput(format!("Agent '{}' not found", agent_name)))?;
+    let mut agent = AgentLease::acquire(agents, services, agent_name).await?;
+    services.start_agent_task(agent_name, task_id);
+    let result =
+        query_fallback_agent(task_id, spec, agent.client(), agent_name, attempt, services).await;
+    agent.release().await;
+    result
+}
+
+/// Send a task's prompt to its fallback agent and report the response
+async fn query_fallback_agent(
+    task_id: &str,
+    spec: &crate::dsl::schema::TaskSpec,
+    agent: &mut PeriplonSDKClient,
+    agent_name: &str,
+    attempt: u32,
+    services: &ExecutionServices,
+) -> Result<Usage> {
+    agent.query(&spec.description).await?;
+
+    // Process response
+    let mut usage = Usage::default();
+    stream_agent_response(agent, &services.cancellation, |msg| {
+        if let Message::Result(result) = &msg {
+            usage = Usage::from_result(result);
+        }
+        services.events.emit(ExecutionEvent::AgentMessage {
+            task_id: task_id.to_string(),
+            agent: agent_name.to_string(),
+            attempt,
+            fallback: true,
+            message: services.secrets.redact_message(msg),
+        });
+    })
+    .await?;
+    Ok(usage)
+}
+
+/// Add the usage of an agent or LLM call made by a task to the workflow state
+async fn record_usage(
+    state: &Mutex<Option<WorkflowState>>,
+    events: &ExecutionEvents,
+    task_id: &str,
+    agent: Option<&str>,
+    usage: Usage,
+) {
+    if usage.is_empty() {
+        return;
+    }
+    if let Some(ref mut state) = *state.lock().await {
+        state.record_usage(task_id, agent, &usage);
+    }
+    events.emit(ExecutionEvent::UsageRecorded {
+        task_id: task_id.to_string(),
+        agent: agent.map(str::to_string),
+        usage,
+    });
+}
 
-    agent.query(&spec.description).await?;
+/// How long an interrupted agent gets to finish its response
+const AGENT_INTERRUPT_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(5);
 
-    // Process response
+/// Pass each message of an agent's response to `on_message`
+///
+/// On cancellation the query is interrupted and the rest of the response is
+/// drained, so the agent session is ready for its next query.
+async fn stream_agent_response(
+    agent: &PeriplonSDKClient,
+    cancellation: &CancellationToken,
+    mut on_message: impl FnMut(Message),
+) -> Result<()> {
     let stream = agent.receive_response()?;
     futures::pin_mut!(stream);
-    while let Some(msg) = stream.next().await {
-        // Log messages using formatter
-        if attempt > 0 {
-            let prefix_str = "Fallback".to_string();
-            let formatted =
-                crate::dsl::message_formatter::format_message(&msg, json_output, Some(&prefix_str));
-            println!("{}", formatted);
-        } else {
-            let formatted = crate::dsl::message_formatter::format_message(&msg, json_output, None);
-            println!("{}", formatted);
+    loop {
+        tokio::select! {
+            msg = stream.next() => match msg {
+                Some(msg) => on_message(msg),
+                None => return Ok(()),
+            },
+            _ = cancellation.cancelled() => break,
         }
     }
 
-    Ok(())
+    let _ = tokio::time::timeout(AGENT_INTERRUPT_TIMEOUT, async {
+        let _ = agent.interrupt().await;
+        while stream.next().await.is_some() {}
+    })
+    .await;
+    Err(Error::Cancelled)
+}
+
+/// Run `future` unless the execution is cancelled first
+///
+/// The future is dropped on cancellation, which aborts in-flight requests and
+/// kills child processes spawned with `kill_on_drop`.
+async fn cancellable<T>(
+    cancellation: &CancellationToken,
+    future: impl std::future::Future<Output = Result<T>>,
+) -> Result<T> {
+    tokio::select! {
+        result = future => result,
+        _ = cancellation.cancelled() => Err(Error::Cancelled),
+    }
 }
 
 /// Calculate retry delay with optional exponential backoff
@@ -2174,6 +4213,7 @@ fn calculate_retry_delay(
 /// * `condition` - The condition to evaluate
 /// * `task_graph` - Current task graph state
 /// * `workflow_state` - Current workflow state (optional)
+/// * `variables` - Workflow inputs and loop variables for expression conditions
 ///
 /// # Returns
 ///
@@ -2182,30 +4222,49 @@ fn evaluate_condition(
     condition: &crate::dsl::schema::ConditionSpec,
     task_graph: &TaskGraph,
     workflow_state: Option<&WorkflowState>,
-) -> bool {
+    variables: ConditionVariables<'_>,
+) -> Result<bool> {
     use crate::dsl::schema::ConditionSpec;
 
-    match condition {
-        ConditionSpec::Single(cond) => evaluate_single_condition(cond, task_graph, workflow_state),
-        ConditionSpec::And { and } => and
-            .iter()
-            .all(|c| evaluate_condition(c, task_graph, workflow_state)),
-        ConditionSpec::Or { or } => or
-            .iter()
-            .any(|c| evaluate_condition(c, task_graph, workflow_state)),
-        ConditionSpec::Not { not } => !evaluate_condition(not, task_graph, workflow_state),
-    }
+    Ok(match condition {
+        ConditionSpec::Single(cond) => {
+            evaluate_single_condition(cond, task_graph, workflow_state, variables)?
+        }
+        ConditionSpec::And { and } => {
+            for c in and {
+                if !evaluate_condition(c, task_graph, workflow_state, variables)? {
+                    return Ok(false);
+                }
+            }
+            true
+        }
+        ConditionSpec::Or { or } => {
+            for c in or {
+                if evaluate_condition(c, task_graph, workflow_state, variables)? {
+                    return Ok(true);
+                }
+            }
+            false
+        }
+        ConditionSpec::Not { not } => {
+            !evaluate_condition(not, task_graph, workflow_state, variables)?
+        }
+    })
 }
 
 /// Evaluate a single condition
+///
+/// Expressions that cannot be evaluated, e.g. because a value has the wrong
+/// type, are errors rather than false.
 fn evaluate_single_condition(
     condition: &crate::dsl::schema::Condition,
     task_graph: &TaskGraph,
     workflow_state: Option<&WorkflowState>,
-) -> bool {
+    variables: ConditionVariables<'_>,
+) -> Result<bool> {
     use crate::dsl::schema::{Condition, TaskStatusCondition};
 
-    match condition {
+    Ok(match condition {
         Condition::TaskStatus { task, status } => {
             // Get task status from task graph
             let task_status = task_graph.get_task_status(task);
@@ -2234,9 +4293,26 @@ fn evaluate_single_condition(
                 false
             }
         }
+        Condition::Expression { expression } => {
+            let mut scope = ExpressionScope::from_run(task_graph, workflow_state);
+            if let Some(inputs) = variables.workflow_inputs {
+                scope = scope.with_inputs(inputs);
+            }
+            if let Some(loop_context) = variables.loop_context {
+                scope = scope.with_loop(loop_context);
+            }
+            match variables
+                .expressions
+                .and_then(|parsed| parsed.get(expression))
+            {
+                Some(parsed) => parsed.evaluate_bool(&scope)?,
+                // Loop variables may have been substituted into the text
+                None => Expression::parse(expression)?.evaluate_bool(&scope)?,
+            }
+        }
         Condition::Always => true,
         Condition::Never => false,
-    }
+    })
 }
 
 /// Check definition of done criteria
@@ -2998,10 +5074,13 @@ async fn execute_task_with_loop(
                 }
             }
 
-            println!(
-                "Executing ForEach loop for task '{}': {} items",
-                task_id,
-                items.len()
+            ctx.services.events.progress(
+                Some(task_id),
+                format!(
+                    "Executing ForEach loop for task '{}': {} items",
+                    task_id,
+                    items.len()
+                ),
             );
 
             if *parallel {
@@ -3023,7 +5102,10 @@ async fn execute_task_with_loop(
                 execute_foreach_sequential(task_id, spec, &items, iterator, ctx).await?;
             }
 
-            println!("ForEach loop completed for task '{}'", task_id);
+            ctx.services.events.progress(
+                Some(task_id),
+                format!("ForEach loop completed for task '{}'", task_id),
+            );
             Ok(())
         }
         LoopSpec::Repeat {
@@ -3040,9 +5122,12 @@ async fn execute_task_with_loop(
                 }
             }
 
-            println!(
-                "Executing Repeat loop for task '{}': {} iterations",
-                task_id, count
+            ctx.services.events.progress(
+                Some(task_id),
+                format!(
+                    "Executing Repeat loop for task '{}': {} iterations",
+                    task_id, count
+                ),
             );
 
             if *parallel {
@@ -3064,7 +5149,10 @@ async fn execute_task_with_loop(
                 execute_repeat_sequential(task_id, spec, *count, iterator.as_deref(), ctx).await?;
             }
 
-            println!("Repeat loop completed for task '{}'", task_id);
+            ctx.services.events.progress(
+                Some(task_id),
+                format!("Repeat loop completed for task '{}'", task_id),
+            );
             Ok(())
         }
         LoopSpec::While {
@@ -3081,9 +5169,12 @@ async fn execute_task_with_loop(
                 }
             }
 
-            println!(
-                "Executing While loop for task '{}': max {} iterations",
-                task_id, max_iterations
+            ctx.services.events.progress(
+                Some(task_id),
+                format!(
+                    "Executing While loop for task '{}': max {} iterations",
+                    task_id, max_iterations
+                ),
             );
 
             // Sequential execution
@@ -3098,7 +5189,10 @@ async fn execute_task_with_loop(
             )
             .await?;
 
-            println!("While loop completed for task '{}'", task_id);
+            ctx.services.events.progress(
+                Some(task_id),
+                format!("While loop completed for task '{}'", task_id),
+            );
             Ok(())
         }
         LoopSpec::RepeatUntil {
@@ -3116,11 +5210,14 @@ async fn execute_task_with_loop(
                 }
             }
 
-            println!(
-                "Executing RepeatUntil loop for task '{}': min {}, max {} iterations",
-                task_id,
-                min_iterations.unwrap_or(1),
-                max_iterations
+            ctx.services.events.progress(
+                Some(task_id),
+                format!(
+                    "Executing RepeatUntil loop for task '{}': min {}, max {} iterations",
+                    task_id,
+                    min_iterations.unwrap_or(1),
+                    max_iterations
+                ),
             );
 
             // Sequential execution
@@ -3136,7 +5233,10 @@ async fn execute_task_with_loop(
             )
             .await?;
 
-            println!("RepeatUntil loop completed for task '{}'", task_id);
+            ctx.services.events.progress(
+                Some(task_id),
+                format!("RepeatUntil loop completed for task '{}'", task_id),
+            );
             Ok(())
         }
     }
@@ -3150,26 +5250,26 @@ async fn execute_task_with_loop(
 /// NOTE: The `substituted_parent` should already have loop variables substituted
 /// via `substitute_task_variables`, so the subtasks within it will also have
 /// variables properly replaced.
-#[allow(clippy::too_many_arguments)]
 async fn execute_subtasks_in_loop_iteration(
     parent_task_id: &str,
     substituted_parent: &crate::dsl::schema::TaskSpec,
-    workflow_inputs: &HashMap<String, serde_json::Value>,
-    agents: &Arc<Mutex<HashMap<String, PeriplonSDKClient>>>,
-    task_graph: &Arc<Mutex<TaskGraph>>,
-    state: &Arc<Mutex<Option<WorkflowState>>>,
-    workflow_name: &Arc<String>,
-    json_output: bool,
-) -> Result<Option<String>> {
+    loop_context: &LoopContext,
+    ctx: &ExecutionContext<'_>,
+) -> Result<Option<TaskResult>> {
+    let task_graph = ctx.task_graph;
+    let state = ctx.state;
     // Execute each subtask in order
     // NOTE: subtasks are already substituted because they're part of substituted_parent
     for subtask_map in &substituted_parent.subtasks {
         for (subtask_name, subtask_spec) in subtask_map {
             let full_subtask_id = format!("{}.{}", parent_task_id, subtask_name);
 
-            println!(
-                "    Executing subtask: {} - {}",
-                full_subtask_id, subtask_spec.description
+            ctx.services.events.progress(
+                Some(&full_subtask_id),
+                format!(
+                    "Executing subtask: {} - {}",
+                    full_subtask_id, subtask_spec.description
+                ),
             );
 
             // Check if subtask has dependencies and if they're met
@@ -3196,9 +5296,9 @@ async fn execute_subtasks_in_loop_iteration(
                 };
 
                 if !deps_met {
-                    println!(
-                        "      Skipping subtask {} - dependencies not met",
-                        subtask_name
+                    ctx.services.events.progress(
+                        Some(&full_subtask_id),
+                        format!("Skipping subtask {} - dependencies not met", subtask_name),
                     );
                     continue;
                 }
@@ -3209,13 +5309,19 @@ async fn execute_subtasks_in_loop_iteration(
                 let condition_met = {
                     let graph = task_graph.lock().await;
                     let workflow_state = state.lock().await;
-                    evaluate_condition(condition, &graph, workflow_state.as_ref())
+                    evaluate_condition(
+                        condition,
+                        &graph,
+                        workflow_state.as_ref(),
+                        ConditionVariables::new(ctx.workflow_inputs, &ctx.services.expressions)
+                            .in_loop(loop_context),
+                    )?
                 };
 
                 if !condition_met {
-                    println!(
-                        "      Skipping subtask {} - condition not met",
-                        subtask_name
+                    ctx.services.events.progress(
+                        Some(&full_subtask_id),
+                        format!("Skipping subtask {} - condition not met", subtask_name),
                     );
                     continue;
                 }
@@ -3226,12 +5332,9 @@ async fn execute_subtasks_in_loop_iteration(
                 &full_subtask_id,
                 &subtask_spec.description,
                 subtask_spec,
-                workflow_inputs,
-                agents,
                 0,
-                state,
-                workflow_name,
-                json_output,
+                None,
+                ctx,
             )
             .await;
 
@@ -3240,12 +5343,18 @@ async fn execute_subtasks_in_loop_iteration(
                 Ok(_) => {
                     let mut graph = task_graph.lock().await;
                     let _ = graph.update_task_status(&full_subtask_id, TaskStatus::Completed);
-                    println!("      ✓ Subtask {} completed", subtask_name);
+                    ctx.services.events.progress(
+                        Some(&full_subtask_id),
+                        format!("✓ Subtask {} completed", subtask_name),
+                    );
                 }
                 Err(e) => {
                     let mut graph = task_graph.lock().await;
                     let _ = graph.update_task_status(&full_subtask_id, TaskStatus::Failed);
-                    println!("      ✗ Subtask {} failed: {}", subtask_name, e);
+                    ctx.services.events.progress(
+                        Some(&full_subtask_id),
+                        format!("✗ Subtask {} failed: {}", subtask_name, e),
+                    );
                     // If subtask fails and has no error recovery, fail the iteration
                     return Err(e);
                 }
@@ -3280,6 +5389,8 @@ async fn execute_foreach_sequential(
             .filter(|&interval| interval > 0);
 
         for (iteration, item) in items.iter().enumerate() {
+            ctx.services.check_cancelled()?;
+
             // Check if this iteration was already completed (resume capability)
             let already_completed = {
                 let state_guard = ctx.state.lock().await;
@@ -3291,41 +5402,55 @@ async fn execute_foreach_sequential(
             };
 
             if already_completed {
-                println!(
-                    "  Iteration {}: Already completed, skipping (resume)",
-                    iteration + 1
+                ctx.services.events.progress(
+                    Some(task_id),
+                    format!(
+                        "Iteration {}: Already completed, skipping (resume)",
+                        iteration + 1
+                    ),
                 );
                 continue;
             }
 
+            // Create loop context
+            let mut context = LoopContext::new(iteration);
+            context.set_variable(iterator.to_string(), item.clone());
+
             // Check continue condition BEFORE executing iteration
             if let Some(ref loop_control) = spec.loop_control {
                 if let Some(ref continue_cond) = loop_control.continue_condition {
                     let should_continue = {
                         let task_graph_guard = ctx.task_graph.lock().await;
                         let state_guard = ctx.state.lock().await;
-                        evaluate_condition(continue_cond, &task_graph_guard, state_guard.as_ref())
+                        evaluate_condition(
+                            continue_cond,
+                            &task_graph_guard,
+                            state_guard.as_ref(),
+                            ConditionVariables::new(ctx.workflow_inputs, &ctx.services.expressions)
+                                .in_loop(&context),
+                        )?
                     };
                     if should_continue {
-                        println!(
-                            "  Iteration {}: Skipping due to continue condition",
-                            iteration + 1
+                        ctx.services.events.progress(
+                            Some(task_id),
+                            format!(
+                                "Iteration {}: Skipping due to continue condition",
+                                iteration + 1
+                            ),
                         );
                         continue;
                     }
                 }
             }
 
-            println!(
-                "  Iteration {}/{}: Processing item: {:?}",
-                iteration + 1,
-                items.len(),
-                item
-            );
-
-            // Create loop context
-            let mut context = LoopContext::new(iteration);
-            context.set_variable(iterator.to_string(), item.clone());
+            ctx.services
+                .events
+                .emit(ExecutionEvent::LoopIterationStarted {
+                    task_id: task_id.to_string(),
+                    iteration,
+                    total: Some(items.len()),
+                    item: Some(item.clone()),
+                });
 
             // Substitute variables in task
             let substituted_task = substitute_task_variables(spec, &context);
@@ -3346,32 +5471,22 @@ async fn execute_foreach_sequential(
             // Execute task iteration
             // If the task has subtasks, execute them instead of the parent task
             let result = if !substituted_task.subtasks.is_empty() {
-                println!(
-                    "  Task has {} subtasks - executing within loop iteration",
-                    substituted_task.subtasks.len()
+                ctx.services.events.progress(
+                    Some(task_id),
+                    format!(
+                        "Task has {} subtasks - executing within loop iteration",
+                        substituted_task.subtasks.len()
+                    ),
                 );
-                execute_subtasks_in_loop_iteration(
-                    task_id,
-                    &substituted_task,
-                    ctx.workflow_inputs,
-                    ctx.agents,
-                    ctx.task_graph,
-                    ctx.state,
-                    ctx.workflow_name,
-                    ctx.json_output,
-                )
-                .await
+                execute_subtasks_in_loop_iteration(task_id, &substituted_task, &context, ctx).await
             } else {
                 execute_task_attempt(
                     &format!("{}[{}]", task_id, iteration),
                     &substituted_task.description,
                     &substituted_task,
-                    ctx.workflow_inputs,
-                    ctx.agents,
                     0,
-                    ctx.state,
-                    ctx.workflow_name,
-                    ctx.json_output,
+                    None,
+                    ctx,
                 )
                 .await
             };
@@ -3395,26 +5510,38 @@ async fn execute_foreach_sequential(
                                 .as_ref()
                                 .is_some_and(|lc| lc.collect_results)
                             {
-                                if let Some(output_str) = output {
-                                    workflow_state.store_loop_result(
-                                        task_id,
-                                        serde_json::Value::String(output_str),
-                                    );
+                                if let Some(result) = output {
+                                    workflow_state.store_loop_result(task_id, result.value);
                                 }
                             }
                         }
                     }
-                    println!("  Iteration {} completed successfully", iteration + 1);
+                    ctx.services
+                        .events
+                        .emit(ExecutionEvent::LoopIterationFinished {
+                            task_id: task_id.to_string(),
+                            iteration,
+                            error: None,
+                        });
 
                     // Save checkpoint if configured and interval reached
                     if let Some(interval) = checkpoint_interval {
                         if (iteration + 1) % interval == 0 {
-                            let state_guard = ctx.state.lock().await;
-                            if let Some(ref workflow_state) = *state_guard {
-                                if let Err(e) = workflow_state.save_checkpoint() {
-                                    println!("  Warning: Failed to save checkpoint: {}", e);
+                            let mut state_guard = ctx.state.lock().await;
+                            if let (Some(ref mut workflow_state), Some(ref persistence)) =
+                                (&mut *state_guard, &ctx.services.state_persistence)
+                            {
+                                workflow_state.messages = ctx.services.message_bus.log();
+                                if let Err(e) = persistence.save_state(workflow_state) {
+                                    ctx.services.events.warning(
+                                        Some(task_id),
+                                        format!("Failed to save checkpoint: {}", e),
+                                    );
                                 } else {
-                                    println!("  Checkpoint saved at iteration {}", iteration + 1);
+                                    ctx.services.events.progress(
+                                        Some(task_id),
+                                        format!("Checkpoint saved at iteration {}", iteration + 1),
+                                    );
                                 }
                             }
                         }
@@ -3433,6 +5560,13 @@ async fn execute_foreach_sequential(
                             );
                         }
                     }
+                    ctx.services
+                        .events
+                        .emit(ExecutionEvent::LoopIterationFinished {
+                            task_id: task_id.to_string(),
+                            iteration,
+                            error: Some(e.to_string()),
+                        });
 
                     // Check if we should break on error
                     let break_on_error = spec
@@ -3444,8 +5578,6 @@ async fn execute_foreach_sequential(
                     if break_on_error {
                         return Err(e);
                     }
-
-                    println!("  Iteration {} failed: {}", iteration + 1, e);
                 }
             }
 
@@ -3455,12 +5587,21 @@ async fn execute_foreach_sequential(
                     let should_break = {
                         let task_graph_guard = ctx.task_graph.lock().await;
                         let state_guard = ctx.state.lock().await;
-                        evaluate_condition(break_cond, &task_graph_guard, state_guard.as_ref())
+                        evaluate_condition(
+                            break_cond,
+                            &task_graph_guard,
+                            state_guard.as_ref(),
+                            ConditionVariables::new(ctx.workflow_inputs, &ctx.services.expressions)
+                                .in_loop(&context),
+                        )?
                     };
                     if should_break {
-                        println!(
-                            "  Breaking loop due to break condition at iteration {}",
-                            iteration + 1
+                        ctx.services.events.progress(
+                            Some(task_id),
+                            format!(
+                                "Breaking loop due to break condition at iteration {}",
+                                iteration + 1
+                            ),
                         );
                         break;
                     }
@@ -3476,7 +5617,9 @@ async fn execute_foreach_sequential(
         match tokio::time::timeout(timeout, loop_future).await {
             Ok(result) => result,
             Err(_) => {
-                println!("  Loop timed out after {:?}", timeout);
+                ctx.services
+                    .events
+                    .progress(Some(task_id), format!("Loop timed out after {:?}", timeout));
                 Err(Error::InvalidInput(format!(
                     "Loop '{}' timed out after {} seconds",
                     task_id,
@@ -3498,34 +5641,52 @@ async fn execute_repeat_sequential(
     ctx: &ExecutionContext<'_>,
 ) -> Result<()> {
     for iteration in 0..count {
+        ctx.services.check_cancelled()?;
+
+        // Create loop context
+        let mut context = LoopContext::new(iteration);
+        if let Some(iter_name) = iterator {
+            context.set_variable(
+                iter_name.to_string(),
+                serde_json::Value::Number(iteration.into()),
+            );
+        }
+
         // Check continue condition BEFORE executing iteration
         if let Some(ref loop_control) = spec.loop_control {
             if let Some(ref continue_cond) = loop_control.continue_condition {
                 let should_continue = {
                     let task_graph_guard = ctx.task_graph.lock().await;
                     let state_guard = ctx.state.lock().await;
-                    evaluate_condition(continue_cond, &task_graph_guard, state_guard.as_ref())
+                    evaluate_condition(
+                        continue_cond,
+                        &task_graph_guard,
+                        state_guard.as_ref(),
+                        ConditionVariables::new(ctx.workflow_inputs, &ctx.services.expressions)
+                            .in_loop(&context),
+                    )?
                 };
                 if should_continue {
-                    println!(
-                        "  Iteration {}: Skipping due to continue condition",
-                        iteration + 1
+                    ctx.services.events.progress(
+                        Some(task_id),
+                        format!(
+                            "Iteration {}: Skipping due to continue condition",
+                            iteration + 1
+                        ),
                     );
                     continue;
                 }
             }
         }
 
-        println!("  Iteration {}/{}", iteration + 1, count);
-
-        // Create loop context
-        let mut context = LoopContext::new(iteration);
-        if let Some(iter_name) = iterator {
-            context.set_variable(
-                iter_name.to_string(),
-                serde_json::Value::Number(iteration.into()),
-            );
-        }
+        ctx.services
+            .events
+            .emit(ExecutionEvent::LoopIterationStarted {
+                task_id: task_id.to_string(),
+                iteration,
+                total: Some(count),
+                item: None,
+            });
 
         // Substitute variables in task
         let substituted_task = substitute_task_variables(spec, &context);
@@ -3548,12 +5709,9 @@ async fn execute_repeat_sequential(
             &format!("{}[{}]", task_id, iteration),
             &substituted_task.description,
             &substituted_task,
-            ctx.workflow_inputs,
-            ctx.agents,
             0,
-            ctx.state,
-            ctx.workflow_name,
-            ctx.json_output,
+            None,
+            ctx,
         )
         .await
         {
@@ -3575,16 +5733,19 @@ async fn execute_repeat_sequential(
                             .as_ref()
                             .is_some_and(|lc| lc.collect_results)
                         {
-                            if let Some(output_str) = output {
-                                workflow_state.store_loop_result(
-                                    task_id,
-                                    serde_json::Value::String(output_str),
-                                );
+                            if let Some(result) = output {
+                                workflow_state.store_loop_result(task_id, result.value);
                             }
                         }
                     }
                 }
-                println!("  Iteration {} completed successfully", iteration + 1);
+                ctx.services
+                    .events
+                    .emit(ExecutionEvent::LoopIterationFinished {
+                        task_id: task_id.to_string(),
+                        iteration,
+                        error: None,
+                    });
             }
             Err(e) => {
                 // Update iteration status
@@ -3600,6 +5761,14 @@ async fn execute_repeat_sequential(
                     }
                 }
 
+                ctx.services
+                    .events
+                    .emit(ExecutionEvent::LoopIterationFinished {
+                        task_id: task_id.to_string(),
+                        iteration,
+                        error: Some(e.to_string()),
+                    });
+
                 // Check if we should break on error
                 let break_on_error = spec
                     .loop_control
@@ -3610,8 +5779,6 @@ async fn execute_repeat_sequential(
                 if break_on_error {
                     return Err(e);
                 }
-
-                println!("  Iteration {} failed: {}", iteration + 1, e);
             }
         }
 
@@ -3621,12 +5788,21 @@ async fn execute_repeat_sequential(
                 let should_break = {
                     let task_graph_guard = ctx.task_graph.lock().await;
                     let state_guard = ctx.state.lock().await;
-                    evaluate_condition(break_cond, &task_graph_guard, state_guard.as_ref())
+                    evaluate_condition(
+                        break_cond,
+                        &task_graph_guard,
+                        state_guard.as_ref(),
+                        ConditionVariables::new(ctx.workflow_inputs, &ctx.services.expressions)
+                            .in_loop(&context),
+                    )?
                 };
                 if should_break {
-                    println!(
-                        "  Breaking loop due to break condition at iteration {}",
-                        iteration + 1
+                    ctx.services.events.progress(
+                        Some(task_id),
+                        format!(
+                            "Breaking loop due to break condition at iteration {}",
+                            iteration + 1
+                        ),
                     );
                     break;
                 }
@@ -3652,43 +5828,61 @@ async fn execute_while_sequential(
     let mut iteration = 0;
 
     loop {
+        ctx.services.check_cancelled()?;
+
         // Check max iterations safety limit
         if iteration >= max_iterations {
-            println!(
-                "  While loop reached max iterations limit: {}",
-                max_iterations
+            ctx.services.events.progress(
+                Some(task_id),
+                format!(
+                    "While loop reached max iterations limit: {}",
+                    max_iterations
+                ),
             );
             break;
         }
 
+        // Create loop context
+        let mut context = LoopContext::new(iteration);
+        if let Some(iter_name) = iteration_variable {
+            context.set_variable(
+                iter_name.to_string(),
+                serde_json::Value::Number(iteration.into()),
+            );
+        }
+
         // Evaluate condition BEFORE executing iteration
         let condition_met = {
+            let task_graph_guard = ctx.task_graph.lock().await;
             let state_guard = ctx.state.lock().await;
-            let task_graph = TaskGraph::new(); // We need access to task_graph for condition evaluation
-            evaluate_condition(condition, &task_graph, state_guard.as_ref())
+            evaluate_condition(
+                condition,
+                &task_graph_guard,
+                state_guard.as_ref(),
+                ConditionVariables::new(ctx.workflow_inputs, &ctx.services.expressions)
+                    .in_loop(&context),
+            )?
         };
 
         if !condition_met {
-            println!(
-                "  While loop condition became false at iteration {}",
-                iteration
+            ctx.services.events.progress(
+                Some(task_id),
+                format!(
+                    "While loop condition became false at iteration {}",
+                    iteration
+                ),
             );
             break;
         }
 
-        println!(
-            "  Iteration {}: Condition is true, executing...",
-            iteration + 1
-        );
-
-        // Create loop context
-        let mut context = LoopContext::new(iteration);
-        if let Some(iter_name) = iteration_variable {
-            context.set_variable(
-                iter_name.to_string(),
-                serde_json::Value::Number(iteration.into()),
-            );
-        }
+        ctx.services
+            .events
+            .emit(ExecutionEvent::LoopIterationStarted {
+                task_id: task_id.to_string(),
+                iteration,
+                total: None,
+                item: None,
+            });
 
         // Substitute variables in task
         let substituted_task = substitute_task_variables(spec, &context);
@@ -3711,12 +5905,9 @@ async fn execute_while_sequential(
             &format!("{}[{}]", task_id, iteration),
             &substituted_task.description,
             &substituted_task,
-            ctx.workflow_inputs,
-            ctx.agents,
             0,
-            ctx.state,
-            ctx.workflow_name,
-            ctx.json_output,
+            None,
+            ctx,
         )
         .await
         {
@@ -3738,16 +5929,19 @@ async fn execute_while_sequential(
                             .as_ref()
                             .is_some_and(|lc| lc.collect_results)
                         {
-                            if let Some(output_str) = output {
-                                workflow_state.store_loop_result(
-                                    task_id,
-                                    serde_json::Value::String(output_str),
-                                );
+                            if let Some(result) = output {
+                                workflow_state.store_loop_result(task_id, result.value);
                             }
                         }
                     }
                 }
-                println!("  Iteration {} completed successfully", iteration + 1);
+                ctx.services
+                    .events
+                    .emit(ExecutionEvent::LoopIterationFinished {
+                        task_id: task_id.to_string(),
+                        iteration,
+                        error: None,
+                    });
             }
             Err(e) => {
                 // Update iteration status
@@ -3763,6 +5957,14 @@ async fn execute_while_sequential(
                     }
                 }
 
+                ctx.services
+                    .events
+                    .emit(ExecutionEvent::LoopIterationFinished {
+                        task_id: task_id.to_string(),
+                        iteration,
+                        error: Some(e.to_string()),
+                    });
+
                 // Check if we should break on error
                 let break_on_error = spec
                     .loop_control
@@ -3773,8 +5975,6 @@ async fn execute_while_sequential(
                 if break_on_error {
                     return Err(e);
                 }
-
-                println!("  Iteration {} failed: {}", iteration + 1, e);
             }
         }
 
@@ -3783,13 +5983,22 @@ async fn execute_while_sequential(
         // Apply delay if configured
         if let Some(delay_secs) = delay_between_secs {
             if delay_secs > 0 {
-                println!("  Waiting {} seconds before next iteration...", delay_secs);
-                tokio::time::sleep(tokio::time::Duration::from_secs(delay_secs)).await;
+                ctx.services.events.progress(
+                    Some(task_id),
+                    format!("Waiting {} seconds before next iteration...", delay_secs),
+                );
+                tokio::select! {
+                    _ = tokio::time::sleep(tokio::time::Duration::from_secs(delay_secs)) => {}
+                    _ = ctx.services.cancellation.cancelled() => {}
+                }
             }
         }
     }
 
-    println!("  While loop completed after {} iterations", iteration);
+    ctx.services.events.progress(
+        Some(task_id),
+        format!("While loop completed after {} iterations", iteration),
+    );
     Ok(())
 }
 
@@ -3812,16 +6021,28 @@ async fn execute_repeat_until_sequential(
     let mut iteration = 0;
 
     loop {
+        ctx.services.check_cancelled()?;
+
         // Check max iterations safety limit
         if iteration >= max_iterations {
-            println!(
-                "  RepeatUntil loop reached max iterations limit: {}",
-                max_iterations
+            ctx.services.events.progress(
+                Some(task_id),
+                format!(
+                    "RepeatUntil loop reached max iterations limit: {}",
+                    max_iterations
+                ),
             );
             break;
         }
 
-        println!("  Iteration {}", iteration + 1);
+        ctx.services
+            .events
+            .emit(ExecutionEvent::LoopIterationStarted {
+                task_id: task_id.to_string(),
+                iteration,
+                total: None,
+                item: None,
+            });
 
         // Create loop context
         let mut context = LoopContext::new(iteration);
@@ -3853,12 +6074,9 @@ async fn execute_repeat_until_sequential(
             &format!("{}[{}]", task_id, iteration),
             &substituted_task.description,
             &substituted_task,
-            ctx.workflow_inputs,
-            ctx.agents,
             0,
-            ctx.state,
-            ctx.workflow_name,
-            ctx.json_output,
+            None,
+            ctx,
         )
         .await
         {
@@ -3880,16 +6098,19 @@ async fn execute_repeat_until_sequential(
                             .as_ref()
                             .is_some_and(|lc| lc.collect_results)
                         {
-                            if let Some(output_str) = output {
-                                workflow_state.store_loop_result(
-                                    task_id,
-                                    serde_json::Value::String(output_str),
-                                );
+                            if let Some(result) = output {
+                                workflow_state.store_loop_result(task_id, result.value);
                             }
                         }
                     }
                 }
-                println!("  Iteration {} completed successfully", iteration + 1);
+                ctx.services
+                    .events
+                    .emit(ExecutionEvent::LoopIterationFinished {
+                        task_id: task_id.to_string(),
+                        iteration,
+                        error: None,
+                    });
             }
             Err(e) => {
                 // Update iteration status
@@ -3905,6 +6126,14 @@ async fn execute_repeat_until_sequential(
                     }
                 }
 
+                ctx.services
+                    .events
+                    .emit(ExecutionEvent::LoopIterationFinished {
+                        task_id: task_id.to_string(),
+                        iteration,
+                        error: Some(e.to_string()),
+                    });
+
                 // Check if we should break on error
                 let break_on_error = spec
                     .loop_control
@@ -3915,8 +6144,6 @@ async fn execute_repeat_until_sequential(
                 if break_on_error {
                     return Err(e);
                 }
-
-                println!("  Iteration {} failed: {}", iteration + 1, e);
             }
         }
 
@@ -3924,16 +6151,25 @@ async fn execute_repeat_until_sequential(
 
         // Evaluate condition AFTER executing iteration
         let condition_met = {
+            let task_graph_guard = ctx.task_graph.lock().await;
             let state_guard = ctx.state.lock().await;
-            let task_graph = TaskGraph::new(); // We need access to task_graph for condition evaluation
-            evaluate_condition(condition, &task_graph, state_guard.as_ref())
+            evaluate_condition(
+                condition,
+                &task_graph_guard,
+                state_guard.as_ref(),
+                ConditionVariables::new(ctx.workflow_inputs, &ctx.services.expressions)
+                    .in_loop(&context),
+            )?
         };
 
         // Stop if condition is met AND we've done minimum iterations
         if condition_met && iteration >= min {
-            println!(
-                "  RepeatUntil condition became true at iteration {}",
-                iteration
+            ctx.services.events.progress(
+                Some(task_id),
+                format!(
+                    "RepeatUntil condition became true at iteration {}",
+                    iteration
+                ),
             );
             break;
         }
@@ -3941,15 +6177,21 @@ async fn execute_repeat_until_sequential(
         // Apply delay if configured
         if let Some(delay_secs) = delay_between_secs {
             if delay_secs > 0 {
-                println!("  Waiting {} seconds before next iteration...", delay_secs);
-                tokio::time::sleep(tokio::time::Duration::from_secs(delay_secs)).await;
+                ctx.services.events.progress(
+                    Some(task_id),
+                    format!("Waiting {} seconds before next iteration...", delay_secs),
+                );
+                tokio::select! {
+                    _ = tokio::time::sleep(tokio::time::Duration::from_secs(delay_secs)) => {}
+                    _ = ctx.services.cancellation.cancelled() => {}
+                }
             }
         }
     }
 
-    println!(
-        "  RepeatUntil loop completed after {} iterations",
-        iteration
+    ctx.services.events.progress(
+        Some(task_id),
+        format!("RepeatUntil loop completed after {} iterations", iteration),
     );
     Ok(())
 }
@@ -3968,10 +6210,13 @@ async fn execute_foreach_parallel(
 ) -> Result<()> {
     use tokio::task::JoinSet;
 
-    println!(
-        "  Executing ForEach in parallel: {} items, max {} concurrent",
-        items.len(),
-        max_parallel
+    ctx.services.events.progress(
+        Some(task_id),
+        format!(
+            "Executing ForEach in parallel: {} items, max {} concurrent",
+            items.len(),
+            max_parallel
+        ),
     );
 
     // Clone items to owned Vec to avoid lifetime issues
@@ -3990,7 +6235,10 @@ async fn execute_foreach_parallel(
         let spec = spec.clone();
         let iterator = iterator.to_string();
         let agents = ctx.agents.clone();
+        let task_graph = ctx.task_graph.clone();
         let state = ctx.state.clone();
+        let workflow = ctx.workflow.clone();
+        let services = ctx.services.clone();
         let semaphore = semaphore.clone();
         let workflow_inputs_clone = workflow_inputs.clone();
         let workflow_name_clone = ctx.workflow_name.clone();
@@ -3999,13 +6247,14 @@ async fn execute_foreach_parallel(
         join_set.spawn(async move {
             // Acquire semaphore permit
             let _permit = semaphore.acquire().await.unwrap();
+            services.check_cancelled()?;
 
-            println!(
-                "  [Parallel] Iteration {}/{}: Processing item: {:?}",
-                iteration + 1,
-                total_items,
-                item
-            );
+            services.events.emit(ExecutionEvent::LoopIterationStarted {
+                task_id: task_id.clone(),
+                iteration,
+                total: Some(total_items),
+                item: Some(item.clone()),
+            });
 
             // Create loop context
             let mut context = LoopContext::new(iteration);
@@ -4028,16 +6277,23 @@ async fn execute_foreach_parallel(
             }
 
             // Execute task iteration
+            let task_ctx = ExecutionContext {
+                workflow_inputs: &workflow_inputs_clone,
+                agents: &agents,
+                task_graph: &task_graph,
+                state: &state,
+                workflow: &workflow,
+                services: &services,
+                workflow_name: &workflow_name_clone,
+                json_output,
+            };
             let result = execute_task_attempt(
                 &format!("{}[{}]", task_id, iteration),
                 &substituted_task.description,
                 &substituted_task,
-                &workflow_inputs_clone,
-                &agents,
                 0,
-                &state,
-                &workflow_name_clone,
-                json_output,
+                None,
+                &task_ctx,
             )
             .await;
 
@@ -4059,18 +6315,16 @@ async fn execute_foreach_parallel(
                             .as_ref()
                             .is_some_and(|lc| lc.collect_results)
                         {
-                            if let Some(output_str) = output {
-                                workflow_state.store_loop_result(
-                                    &task_id,
-                                    serde_json::Value::String(output_str),
-                                );
+                            if let Some(result) = output {
+                                workflow_state.store_loop_result(&task_id, result.value);
                             }
                         }
                     }
-                    println!(
-                        "  [Parallel] Iteration {} completed successfully",
-                        iteration + 1
-                    );
+                    services.events.emit(ExecutionEvent::LoopIterationFinished {
+                        task_id: task_id.clone(),
+                        iteration,
+                        error: None,
+                    });
                     Ok(())
                 }
                 Err(e) => {
@@ -4083,7 +6337,11 @@ async fn execute_foreach_parallel(
                             Some(item),
                         );
                     }
-                    println!("  [Parallel] Iteration {} failed: {}", iteration + 1, e);
+                    services.events.emit(ExecutionEvent::LoopIterationFinished {
+                        task_id: task_id.clone(),
+                        iteration,
+                        error: Some(e.to_string()),
+                    });
                     Err(e)
                 }
             }
@@ -4130,9 +6388,12 @@ async fn execute_repeat_parallel(
 ) -> Result<()> {
     use tokio::task::JoinSet;
 
-    println!(
-        "  Executing Repeat in parallel: {} iterations, max {} concurrent",
-        count, max_parallel
+    ctx.services.events.progress(
+        Some(task_id),
+        format!(
+            "Executing Repeat in parallel: {} iterations, max {} concurrent",
+            count, max_parallel
+        ),
     );
 
     // Create semaphore to limit concurrency
@@ -4147,7 +6408,10 @@ async fn execute_repeat_parallel(
         let spec = spec.clone();
         let iterator = iterator.map(|s| s.to_string());
         let agents = ctx.agents.clone();
+        let task_graph = ctx.task_graph.clone();
         let state = ctx.state.clone();
+        let workflow = ctx.workflow.clone();
+        let services = ctx.services.clone();
         let semaphore = semaphore.clone();
         let workflow_inputs_clone = workflow_inputs.clone();
         let workflow_name_clone = ctx.workflow_name.clone();
@@ -4156,8 +6420,14 @@ async fn execute_repeat_parallel(
         join_set.spawn(async move {
             // Acquire semaphore permit
             let _permit = semaphore.acquire().await.unwrap();
+            services.check_cancelled()?;
 
-            println!("  [Parallel] Iteration {}/{}", iteration + 1, count);
+            services.events.emit(ExecutionEvent::LoopIterationStarted {
+                task_id: task_id.clone(),
+                iteration,
+                total: Some(count),
+                item: None,
+            });
 
             // Create loop context
             let mut context = LoopContext::new(iteration);
@@ -4182,16 +6452,23 @@ async fn execute_repeat_parallel(
             }
 
             // Execute task iteration
+            let task_ctx = ExecutionContext {
+                workflow_inputs: &workflow_inputs_clone,
+                agents: &agents,
+                task_graph: &task_graph,
+                state: &state,
+                workflow: &workflow,
+                services: &services,
+                workflow_name: &workflow_name_clone,
+                json_output,
+            };
             let result = execute_task_attempt(
                 &format!("{}[{}]", task_id, iteration),
                 &substituted_task.description,
                 &substituted_task,
-                &workflow_inputs_clone,
-                &agents,
                 0,
-                &state,
-                &workflow_name_clone,
-                json_output,
+                None,
+                &task_ctx,
             )
             .await;
 
@@ -4213,18 +6490,16 @@ async fn execute_repeat_parallel(
                             .as_ref()
                             .is_some_and(|lc| lc.collect_results)
                         {
-                            if let Some(output_str) = output {
-                                workflow_state.store_loop_result(
-                                    &task_id,
-                                    serde_json::Value::String(output_str),
-                                );
+                            if let Some(result) = output {
+                                workflow_state.store_loop_result(&task_id, result.value);
                             }
                         }
                     }
-                    println!(
-                        "  [Parallel] Iteration {} completed successfully",
-                        iteration + 1
-                    );
+                    services.events.emit(ExecutionEvent::LoopIterationFinished {
+                        task_id: task_id.clone(),
+                        iteration,
+                        error: None,
+                    });
                     Ok(())
                 }
                 Err(e) => {
@@ -4237,7 +6512,11 @@ async fn execute_repeat_parallel(
                             Some(serde_json::Value::Number(iteration.into())),
                         );
                     }
-                    println!("  [Parallel] Iteration {} failed: {}", iteration + 1, e);
+                    services.events.emit(ExecutionEvent::LoopIterationFinished {
+                        task_id: task_id.clone(),
+                        iteration,
+                        error: Some(e.to_string()),
+                    });
                     Err(e)
                 }
             }
@@ -4299,6 +6578,8 @@ mod tests {
             imports: HashMap::new(),
             notifications: None,
             limits: None,
+            max_concurrency: None,
+            providers: HashMap::new(),
         };
 
         let executor = DSLExecutor::new(workflow).unwrap();
@@ -4307,6 +6588,67 @@ mod tests {
         assert_eq!(version, "1.0.0");
     }
 
+    #[test]
+    fn test_concurrency_limits() {
+        let agents: HashMap<String, AgentSpec> = HashMap::from([
+            (
+                "writer".to_string(),
+                serde_yaml::from_str("description: Writer\nmax_concurrency: 1").unwrap(),
+            ),
+            (
+                "reader".to_string(),
+                serde_yaml::from_str("description: Reader").unwrap(),
+            ),
+        ]);
+        let mut limits = ConcurrencyLimits::new(Some(3), &agents);
+
+        // The writer cap admits one task at a time
+        assert!(limits.try_acquire(Some("writer")));
+        assert!(!limits.try_acquire(Some("writer")));
+
+        // Uncapped agents and agent-less tasks fill the workflow-wide limit
+        assert!(limits.try_acquire(Some("reader")));
+        assert!(limits.try_acquire(None));
+        assert!(limits.is_saturated());
+        assert!(!limits.try_acquire(None));
+
+        limits.release(Some("writer"));
+        assert!(!limits.is_saturated());
+        assert!(limits.try_acquire(Some("writer")));
+    }
+
+    #[test]
+    fn test_apply_session() {
+        let mut state = WorkflowState::new("Test".to_string(), "1.0.0".to_string());
+        state.record_session("writer", None, "latest");
+        state.record_session("reviewer", Some("editorial"), "named");
+
+        let resolve = |session: SessionSpec| {
+            let mut options = AgentOptions::default();
+            apply_session(&mut options, &session, "writer", &state);
+            (options.resume, options.fork_session)
+        };
+
+        assert_eq!(resolve(SessionSpec::New), (None, false));
+        assert_eq!(
+            resolve(SessionSpec::Continue),
+            (Some("latest".to_string()), false)
+        );
+        assert_eq!(
+            resolve(SessionSpec::Fork),
+            (Some("latest".to_string()), true)
+        );
+        assert_eq!(
+            resolve(SessionSpec::Named("editorial".to_string())),
+            (Some("named".to_string()), false)
+        );
+        // Unknown sessions start fresh
+        assert_eq!(
+            resolve(SessionSpec::Named("other".to_string())),
+            (None, false)
+        );
+    }
+
     #[test]
     fn test_agent_spec_to_options() {
         let workflow = DSLWorkflow {
@@ -4330,6 +6672,8 @@ mod tests {
             imports: HashMap::new(),
             notifications: None,
             limits: None,
+            max_concurrency: None,
+            providers: HashMap::new(),
         };
 
         let executor = DSLExecutor::new(workflow).unwrap();
@@ -4349,6 +6693,8 @@ mod tests {
                 allowed_directories: vec!["./src".to_string(), "/tmp/test".to_string()],
             },
             max_turns: Some(10),
+            max_concurrency: None,
+            profile: None,
         };
 
         let var_context = crate::dsl::variables::VariableContext::new();
@@ -4389,6 +6735,8 @@ mod tests {
             imports: HashMap::new(),
             notifications: None,
             limits: None,
+            max_concurrency: None,
+            providers: HashMap::new(),
         };
 
         let executor = DSLExecutor::new(workflow).unwrap();
@@ -4405,6 +6753,8 @@ mod tests {
             tools: vec![],
             permissions: PermissionsSpec::default(),
             max_turns: None,
+            max_concurrency: None,
+            profile: None,
         };
 
         let var_context = crate::dsl::variables::VariableContext::new();
@@ -4437,6 +6787,8 @@ mod tests {
             imports: HashMap::new(),
             notifications: None,
             limits: None,
+            max_concurrency: None,
+            providers: HashMap::new(),
         };
 
         let executor = DSLExecutor::new(workflow).unwrap();
@@ -4453,6 +6805,8 @@ mod tests {
             tools: vec![],
             permissions: PermissionsSpec::default(),
             max_turns: None,
+            max_concurrency: None,
+            profile: None,
         };
 
         let var_context = crate::dsl::variables::VariableContext::new();
@@ -4485,6 +6839,8 @@ mod tests {
             imports: HashMap::new(),
             notifications: None,
             limits: None,
+            max_concurrency: None,
+            providers: HashMap::new(),
         };
 
         let executor = DSLExecutor::new(workflow).unwrap();
@@ -4501,6 +6857,8 @@ mod tests {
             tools: vec![],
             permissions: PermissionsSpec::default(),
             max_turns: None,
+            max_concurrency: None,
+            profile: None,
         };
 
         let var_context = crate::dsl::variables::VariableContext::new();
@@ -4534,6 +6892,8 @@ mod tests {
             imports: HashMap::new(),
             notifications: None,
             limits: None,
+            max_concurrency: None,
+            providers: HashMap::new(),
         };
 
         let executor = DSLExecutor::new(workflow).unwrap();
@@ -4550,6 +6910,8 @@ mod tests {
             tools: vec![],
             permissions: PermissionsSpec::default(),
             max_turns: None,
+            max_concurrency: None,
+            profile: None,
         };
 
         let var_context = crate::dsl::variables::VariableContext::new();
@@ -4583,6 +6945,8 @@ mod tests {
             imports: HashMap::new(),
             notifications: None,
             limits: None,
+            max_concurrency: None,
+            providers: HashMap::new(),
         };
 
         let executor = DSLExecutor::new(workflow).unwrap();
@@ -4599,6 +6963,8 @@ mod tests {
             tools: vec![],
             permissions: PermissionsSpec::default(),
             max_turns: None,
+            max_concurrency: None,
+            profile: None,
         };
 
         let var_context = crate::dsl::variables::VariableContext::new();
@@ -4631,6 +6997,8 @@ mod tests {
             imports: HashMap::new(),
             notifications: None,
             limits: None,
+            max_concurrency: None,
+            providers: HashMap::new(),
         };
 
         let executor = DSLExecutor::new(workflow).unwrap();
@@ -4647,6 +7015,8 @@ mod tests {
             tools: vec![],
             permissions: PermissionsSpec::default(),
             max_turns: None,
+            max_concurrency: None,
+            profile: None,
         };
 
         let var_context = crate::dsl::variables::VariableContext::new();
@@ -4680,6 +7050,8 @@ mod tests {
             imports: HashMap::new(),
             notifications: None,
             limits: None,
+            max_concurrency: None,
+            providers: HashMap::new(),
         };
 
         let executor = DSLExecutor::new(workflow).unwrap();
@@ -4696,6 +7068,8 @@ mod tests {
             tools: vec![],
             permissions: PermissionsSpec::default(),
             max_turns: None,
+            max_concurrency: None,
+            profile: None,
         };
 
         let var_context = crate::dsl::variables::VariableContext::new();
@@ -4706,13 +7080,54 @@ mod tests {
         assert!(!options.create_cwd);
     }
 
+    #[test]
+    fn test_agent_spec_to_options_with_provider_profile() {
+        let workflow = crate::dsl::parse_workflow(
+            r#"
+name: "Test"
+version: "1.0.0"
+providers:
+  local:
+    provider: openai_compatible
+    endpoint: "http://localhost:8000/v1"
+    api_key: "${workflow.key}"
+    model: "qwen2.5-coder"
+agents:
+  coder:
+    description: "Coder"
+    profile: local
+"#,
+        )
+        .unwrap();
+        let executor = DSLExecutor::new(workflow.clone()).unwrap();
+
+        let mut var_context = crate::dsl::variables::VariableContext::new();
+        var_context.insert(
+            &crate::dsl::variables::Scope::Workflow,
+            "key",
+            serde_json::json!("local-key"),
+        );
+        let options = executor
+            .agent_spec_to_options(&workflow.agents["coder"], None, None, &var_context)
+            .unwrap();
+
+        // OpenAI-compatible endpoints are reached through the Codex CLI
+        assert_eq!(options.provider, Some(Provider::Codex));
+        assert_eq!(options.model.as_deref(), Some("qwen2.5-coder"));
+        assert_eq!(options.env["OPENAI_BASE_URL"], "http://localhost:8000/v1");
+        assert_eq!(options.env["OPENAI_API_KEY"], "local-key");
+    }
+
     #[test]
     fn test_condition_always() {
         use crate::dsl::schema::{Condition, ConditionSpec};
 
         let task_graph = TaskGraph::new();
         let condition = ConditionSpec::Single(Condition::Always);
-        assert!(evaluate_condition(&condition, &task_graph, None));
+        assert!(
+            evaluate_condition(&condition, &task_graph, None, ConditionVariables::default())
+                .unwrap()
+        );
     }
 
     #[test]
@@ -4721,7 +7136,10 @@ mod tests {
 
         let task_graph = TaskGraph::new();
         let condition = ConditionSpec::Single(Condition::Never);
-        assert!(!evaluate_condition(&condition, &task_graph, None));
+        assert!(
+            !evaluate_condition(&condition, &task_graph, None, ConditionVariables::default())
+                .unwrap()
+        );
     }
 
     #[test]
@@ -4739,7 +7157,10 @@ mod tests {
             status: TaskStatusCondition::Completed,
         });
 
-        assert!(evaluate_condition(&condition, &task_graph, None));
+        assert!(
+            evaluate_condition(&condition, &task_graph, None, ConditionVariables::default())
+                .unwrap()
+        );
     }
 
     #[test]
@@ -4757,7 +7178,10 @@ mod tests {
             status: TaskStatusCondition::Failed,
         });
 
-        assert!(evaluate_condition(&condition, &task_graph, None));
+        assert!(
+            evaluate_condition(&condition, &task_graph, None, ConditionVariables::default())
+                .unwrap()
+        );
     }
 
     #[test]
@@ -4775,7 +7199,10 @@ mod tests {
             status: TaskStatusCondition::Skipped,
         });
 
-        assert!(evaluate_condition(&condition, &task_graph, None));
+        assert!(
+            evaluate_condition(&condition, &task_graph, None, ConditionVariables::default())
+                .unwrap()
+        );
     }
 
     #[test]
@@ -4794,8 +7221,10 @@ mod tests {
         assert!(evaluate_condition(
             &condition,
             &task_graph,
-            Some(&workflow_state)
-        ));
+            Some(&workflow_state),
+            ConditionVariables::default()
+        )
+        .unwrap());
     }
 
     #[test]
@@ -4813,8 +7242,10 @@ mod tests {
         assert!(evaluate_condition(
             &condition,
             &task_graph,
-            Some(&workflow_state)
-        ));
+            Some(&workflow_state),
+            ConditionVariables::default()
+        )
+        .unwrap());
 
         let condition_not_exists = ConditionSpec::Single(Condition::StateExists {
             key: "key2".to_string(),
@@ -4823,8 +7254,10 @@ mod tests {
         assert!(!evaluate_condition(
             &condition_not_exists,
             &task_graph,
-            Some(&workflow_state)
-        ));
+            Some(&workflow_state),
+            ConditionVariables::default()
+        )
+        .unwrap());
     }
 
     #[test]
@@ -4839,7 +7272,10 @@ mod tests {
             ],
         };
 
-        assert!(evaluate_condition(&condition, &task_graph, None));
+        assert!(
+            evaluate_condition(&condition, &task_graph, None, ConditionVariables::default())
+                .unwrap()
+        );
 
         let condition_with_never = ConditionSpec::And {
             and: vec![
@@ -4851,8 +7287,10 @@ mod tests {
         assert!(!evaluate_condition(
             &condition_with_never,
             &task_graph,
-            None
-        ));
+            None,
+            ConditionVariables::default()
+        )
+        .unwrap());
     }
 
     #[test]
@@ -4867,7 +7305,10 @@ mod tests {
             ],
         };
 
-        assert!(evaluate_condition(&condition, &task_graph, None));
+        assert!(
+            evaluate_condition(&condition, &task_graph, None, ConditionVariables::default())
+                .unwrap()
+        );
 
         let condition_all_never = ConditionSpec::Or {
             or: vec![
@@ -4876,7 +7317,13 @@ mod tests {
             ],
         };
 
-        assert!(!evaluate_condition(&condition_all_never, &task_graph, None));
+        assert!(!evaluate_condition(
+            &condition_all_never,
+            &task_graph,
+            None,
+            ConditionVariables::default()
+        )
+        .unwrap());
     }
 
     #[test]
@@ -4888,7 +7335,10 @@ mod tests {
             not: Box::new(ConditionSpec::Single(Condition::Never)),
         };
 
-        assert!(evaluate_condition(&condition, &task_graph, None));
+        assert!(
+            evaluate_condition(&condition, &task_graph, None, ConditionVariables::default())
+                .unwrap()
+        );
 
         let condition_not_always = ConditionSpec::Not {
             not: Box::new(ConditionSpec::Single(Condition::Always)),
@@ -4897,8 +7347,10 @@ mod tests {
         assert!(!evaluate_condition(
             &condition_not_always,
             &task_graph,
-            None
-        ));
+            None,
+            ConditionVariables::default()
+        )
+        .unwrap());
     }
 
     #[test]
@@ -4931,8 +7383,68 @@ mod tests {
         assert!(evaluate_condition(
             &condition,
             &task_graph,
-            Some(&workflow_state)
+            Some(&workflow_state),
+            ConditionVariables::default()
+        )
+        .unwrap());
+    }
+
+    #[test]
+    fn test_condition_expression() {
+        use crate::dsl::schema::{Condition, ConditionSpec, TaskSpec, TruncationStrategy};
+        use crate::dsl::state::{OutputType, TaskOutput};
+
+        let mut task_graph = TaskGraph::new();
+        task_graph.add_task("scan".to_string(), TaskSpec::default());
+        task_graph
+            .update_task_status("scan", TaskStatus::Completed)
+            .unwrap();
+
+        let mut workflow_state = WorkflowState::new("test".to_string(), "1.0.0".to_string());
+        let output = r#"{"findings": [{"severity": "high"}]}"#;
+        workflow_state.store_task_output(TaskOutput::new(
+            "scan".to_string(),
+            OutputType::Stdout,
+            output.to_string(),
+            output.len(),
+            false,
+            TruncationStrategy::Tail,
         ));
+
+        let mut inputs = HashMap::new();
+        inputs.insert("max_findings".to_string(), serde_json::json!(0));
+        let mut loop_context = LoopContext::new(1);
+        loop_context.set_variable("severity".to_string(), serde_json::json!("high"));
+
+        let condition = ConditionSpec::Single(Condition::Expression {
+            expression: "task.scan.status == 'completed' \
+                && len(task.scan.output.findings) > inputs.max_findings \
+                && task.scan.output.findings[0].severity == loop.severity"
+                .to_string(),
+        });
+
+        let expressions = ParsedExpressions::new();
+        let variables = ConditionVariables::new(&inputs, &expressions).in_loop(&loop_context);
+        assert!(
+            evaluate_condition(&condition, &task_graph, Some(&workflow_state), variables).unwrap()
+        );
+
+        // Loop variables are missing outside the loop
+        assert!(!evaluate_condition(
+            &condition,
+            &task_graph,
+            Some(&workflow_state),
+            ConditionVariables::new(&inputs, &expressions)
+        )
+        .unwrap());
+
+        // Runtime errors are reported, not taken as false
+        let condition = ConditionSpec::Single(Condition::Expression {
+            expression: "task.scan.output.findings > 1".to_string(),
+        });
+        let err = evaluate_condition(&condition, &task_graph, Some(&workflow_state), variables)
+            .unwrap_err();
+        assert!(err.to_string().contains("task.scan.output.findings > 1"));
     }
 
     #[tokio::test]
@@ -0,0 +1,1279 @@
+//! Condition Expressions
+//!
+//! This module implements the small, sandboxed expression language behind
+//! `type: expression` conditions. Expressions only read workflow data: they
+//! cannot run commands, touch files or loop, so they are safe to evaluate at
+//! any point of a run.
+//!
+//! ```text
+//! task.fetch.status == "completed" && len(task.fetch.output.items) > 0
+//! starts_with(inputs.branch, "release/") || loop.attempt >= 3
+//! matches(task.lint.output, "^ok") && !empty(state.approver)
+//! ```
+//!
+//! # Syntax
+//!
+//! - Literals: numbers, `"strings"` or `'strings'`, `true`, `false` and `null`
+//! - Logic: `&&`, `||`, `!`
+//! - Comparisons: `==`, `!=`, `<`, `<=`, `>`, `>=`
+//! - Arithmetic: `+`, `-`, `*`, `/`, `%` (`+` also concatenates strings)
+//! - Access: `.field`, `.0`, `[index]` and `["key"]` into JSON values; missing
+//!   values are `null`
+//! - Functions: `contains`, `starts_with`, `ends_with`, `matches` (regex),
+//!   `len` and `empty`
+//!
+//! # Variables
+//!
+//! - `task.<id>.status` - task status (`pending`, `running`, `completed`, ...)
+//! - `task.<id>.output` - task output, parsed as JSON when possible; use
+//!   `task["parent.child"]` for subtasks
+//! - `state.<key>` - workflow state metadata
+//! - `inputs.<name>` - workflow inputs
+//! - `loop.<name>` - loop variables, `iteration` - current loop iteration
+
+use crate::dsl::loop_context::LoopContext;
+use crate::dsl::state::{parse_output, WorkflowState};
+use crate::dsl::task_graph::TaskGraph;
+use crate::error::{Error, Result};
+use serde_json::{Map, Value};
+use std::cmp::Ordering;
+use std::collections::HashMap;
+
+/// Deepest nesting accepted by the parser
+const MAX_DEPTH: usize = 64;
+
+/// Size limit for compiled `matches` patterns
+const REGEX_SIZE_LIMIT: usize = 1 << 20;
+
+/// Variables an expression may start from
+const ROOTS: &[&str] = &["task", "state", "inputs", "loop", "iteration"];
+
+/// A parsed condition expression
+#[derive(Debug, Clone)]
+pub struct Expression {
+    source: String,
+    ast: Expr,
+}
+
+impl Expression {
+    /// Parse an expression
+    pub fn parse(source: &str) -> Result<Self> {
+        let tokens = tokenize(source).map_err(|e| invalid(source, e))?;
+        let mut parser = Parser {
+            tokens,
+            position: 0,
+            depth: 0,
+        };
+        let ast = parser.parse_root().map_err(|e| invalid(source, e))?;
+        Ok(Self {
+            source: source.to_string(),
+            ast,
+        })
+    }
+
+    /// Type-check the expression without evaluating it
+    ///
+    /// Rejects unknown variables and functions, wrong argument counts, invalid
+    /// regex literals and operations on values of the wrong type, as far as
+    /// types are known before the run.
+    pub fn check(&self) -> Result<()> {
+        let ty = type_of(&self.ast).map_err(|e| invalid(&self.source, e))?;
+        if !ty.accepts(Type::Bool) {
+            return Err(invalid(
+                &self.source,
+                format!("expression must be a boolean, found {}", ty.name()),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Ids of the tasks referenced through `task.<id>` or `task["<id>"]`
+    pub fn task_references(&self) -> Vec<String> {
+        let mut references = Vec::new();
+        collect_task_references(&self.ast, &mut references);
+        references
+    }
+
+    /// Evaluate the expression to a JSON value
+    pub fn evaluate(&self, scope: &ExpressionScope) -> Result<Value> {
+        eval(&self.ast, scope).map_err(|e| invalid(&self.source, e))
+    }
+
+    /// Evaluate the expression as a condition
+    pub fn evaluate_bool(&self, scope: &ExpressionScope) -> Result<bool> {
+        match self.evaluate(scope)? {
+            Value::Bool(value) => Ok(value),
+            other => Err(invalid(
+                &self.source,
+                format!("expected a boolean result, found {}", kind(&other)),
+            )),
+        }
+    }
+
+    /// Original expression text
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+}
+
+/// Values visible to an expression
+#[derive(Debug, Clone)]
+pub struct ExpressionScope {
+    tasks: Map<String, Value>,
+    state: Value,
+    inputs: Value,
+    loop_variables: Value,
+    iteration: Value,
+}
+
+impl Default for ExpressionScope {
+    fn default() -> Self {
+        Self {
+            tasks: Map::new(),
+            state: Value::Object(Map::new()),
+            inputs: Value::Object(Map::new()),
+            loop_variables: Value::Object(Map::new()),
+            iteration: Value::Null,
+        }
+    }
+}
+
+impl ExpressionScope {
+    /// Create an empty scope
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Scope with the task statuses of a graph and the outputs and metadata of
+    /// the workflow state
+    pub fn from_run(task_graph: &TaskGraph, workflow_state: Option<&WorkflowState>) -> Self {
+        let mut scope = Self::new();
+        for task_id in task_graph.get_all_tasks() {
+            let status = task_graph
+                .get_task_status(&task_id)
+                .map(|status| format!("{:?}", status).to_lowercase());
+            scope.set_task_field(&task_id, "status", status.map(Value::String));
+        }
+        if let Some(state) = workflow_state {
+            for (task_id, status) in &state.task_statuses {
+                if !scope.tasks.contains_key(task_id) {
+                    let status = format!("{:?}", status).to_lowercase();
+                    scope.set_task_field(task_id, "status", Some(Value::String(status)));
+                }
+            }
+            for (task_id, output) in &state.task_outputs {
+                scope.set_task_field(task_id, "output", Some(output.structured()));
+            }
+            scope.state = Value::Object(
+                state
+                    .metadata
+                    .iter()
+                    .map(|(key, value)| (key.clone(), value.clone()))
+                    .collect(),
+            );
+        }
+        scope
+    }
+
+    /// Add a task's status and output
+    pub fn with_task(mut self, task_id: &str, status: &str, output: Option<&str>) -> Self {
+        self.set_task_field(task_id, "status", Some(Value::String(status.to_string())));
+        self.set_task_field(task_id, "output", output.map(parse_output));
+        self
+    }
+
+    /// Set the workflow state metadata
+    pub fn with_state(mut self, metadata: &HashMap<String, Value>) -> Self {
+        self.state = Value::Object(
+            metadata
+                .iter()
+                .map(|(key, value)| (key.clone(), value.clone()))
+                .collect(),
+        );
+        self
+    }
+
+    /// Set the workflow inputs
+    pub fn with_inputs(mut self, inputs: &HashMap<String, Value>) -> Self {
+        self.inputs = Value::Object(
+            inputs
+                .iter()
+                .map(|(key, value)| (key.clone(), value.clone()))
+                .collect(),
+        );
+        self
+    }
+
+    /// Set the loop variables and iteration, including those of outer loops
+    pub fn with_loop(mut self, context: &LoopContext) -> Self {
+        let mut variables = Map::new();
+        let mut current = Some(context);
+        while let Some(context) = current {
+            for (name, value) in &context.variables {
+                // Inner loops shadow outer ones
+                if !variables.contains_key(name) {
+                    variables.insert(name.clone(), value.clone());
+                }
+            }
+            current = context.parent_context.as_deref();
+        }
+        self.loop_variables = Value::Object(variables);
+        self.iteration = Value::from(context.iteration);
+        self
+    }
+
+    fn set_task_field(&mut self, task_id: &str, field: &str, value: Option<Value>) {
+        let entry = self
+            .tasks
+            .entry(task_id.to_string())
+            .or_insert_with(|| Value::Object(Map::new()));
+        if let (Value::Object(task), Some(value)) = (entry, value) {
+            task.insert(field.to_string(), value);
+        }
+    }
+
+    fn root(&self, name: &str) -> Value {
+        match name {
+            "task" => Value::Object(self.tasks.clone()),
+            "state" => self.state.clone(),
+            "inputs" => self.inputs.clone(),
+            "loop" => self.loop_variables.clone(),
+            "iteration" => self.iteration.clone(),
+            _ => Value::Null,
+        }
+    }
+}
+
+fn invalid(source: &str, message: impl std::fmt::Display) -> Error {
+    Error::InvalidInput(format!("Invalid expression '{}': {}", source, message))
+}
+
+// ---------------------------------------------------------------------------
+// Tokenizer
+// ---------------------------------------------------------------------------
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Number(f64),
+    Str(String),
+    Ident(String),
+    Dot,
+    Comma,
+    LParen,
+    RParen,
+    LBracket,
+    RBracket,
+    Op(&'static str),
+}
+
+fn tokenize(source: &str) -> std::result::Result<Vec<Token>, String> {
+    let chars: Vec<char> = source.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        if c.is_whitespace() {
+            i += 1;
+            continue;
+        }
+
+        if c.is_ascii_digit() {
+            let start = i;
+            while i < chars.len() && chars[i].is_ascii_digit() {
+                i += 1;
+            }
+            // `items.0.name` is a path, not the number 0.0
+            let after_dot = tokens.last() == Some(&Token::Dot);
+            if !after_dot && i + 1 < chars.len() && chars[i] == '.' && chars[i + 1].is_ascii_digit()
+            {
+                i += 1;
+                while i < chars.len() && chars[i].is_ascii_digit() {
+                    i += 1;
+                }
+            }
+            let text: String = chars[start..i].iter().collect();
+            let number = text
+                .parse()
+                .map_err(|_| format!("invalid number '{}'", text))?;
+            tokens.push(Token::Number(number));
+            continue;
+        }
+
+        if c.is_alphabetic() || c == '_' {
+            let start = i;
+            while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_') {
+                i += 1;
+            }
+            tokens.push(Token::Ident(chars[start..i].iter().collect()));
+            continue;
+        }
+
+        if c == '"' || c == '\'' {
+            let quote = c;
+            let mut text = String::new();
+            i += 1;
+            loop {
+                let Some(&c) = chars.get(i) else {
+                    return Err("unterminated string".to_string());
+                };
+                i += 1;
+                if c == quote {
+                    break;
+                }
+                if c == '\\' {
+                    let escaped = chars
+                        .get(i)
+                        .ok_or_else(|| "unterminated string".to_string())?;
+                    i += 1;
+                    text.push(match escaped {
+                        'n' => '\n',
+                        't' => '\t',
+                        'r' => '\r',
+                        other => *other,
+                    });
+                } else {
+                    text.push(c);
+                }
+            }
+            tokens.push(Token::Str(text));
+            continue;
+        }
+
+        let next = chars.get(i + 1).copied();
+        let (token, width) = match (c, next) {
+            ('&', Some('&')) => (Token::Op("&&"), 2),
+            ('|', Some('|')) => (Token::Op("||"), 2),
+            ('=', Some('=')) => (Token::Op("=="), 2),
+            ('!', Some('=')) => (Token::Op("!="), 2),
+            ('<', Some('=')) => (Token::Op("<="), 2),
+            ('>', Some('=')) => (Token::Op(">="), 2),
+            ('<', _) => (Token::Op("<"), 1),
+            ('>', _) => (Token::Op(">"), 1),
+            ('!', _) => (Token::Op("!"), 1),
+            ('+', _) => (Token::Op("+"), 1),
+            ('-', _) => (Token::Op("-"), 1),
+            ('*', _) => (Token::Op("*"), 1),
+            ('/', _) => (Token::Op("/"), 1),
+            ('%', _) => (Token::Op("%"), 1),
+            ('.', _) => (Token::Dot, 1),
+            (',', _) => (Token::Comma, 1),
+            ('(', _) => (Token::LParen, 1),
+            (')', _) => (Token::RParen, 1),
+            ('[', _) => (Token::LBracket, 1),
+            (']', _) => (Token::RBracket, 1),
+            ('=', _) => return Err("unexpected '=' (use '==' to compare)".to_string()),
+            _ => return Err(format!("unexpected character '{}'", c)),
+        };
+        tokens.push(token);
+        i += width;
+    }
+
+    Ok(tokens)
+}
+
+// ---------------------------------------------------------------------------
+// Parser
+// ---------------------------------------------------------------------------
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum BinaryOp {
+    And,
+    Or,
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Rem,
+}
+
+impl BinaryOp {
+    fn symbol(self) -> &'static str {
+        match self {
+            BinaryOp::And => "&&",
+            BinaryOp::Or => "||",
+            BinaryOp::Eq => "==",
+            BinaryOp::Ne => "!=",
+            BinaryOp::Lt => "<",
+            BinaryOp::Le => "<=",
+            BinaryOp::Gt => ">",
+            BinaryOp::Ge => ">=",
+            BinaryOp::Add => "+",
+            BinaryOp::Sub => "-",
+            BinaryOp::Mul => "*",
+            BinaryOp::Div => "/",
+            BinaryOp::Rem => "%",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Function {
+    Contains,
+    StartsWith,
+    EndsWith,
+    Matches,
+    Len,
+    Empty,
+}
+
+impl Function {
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "contains" => Some(Function::Contains),
+            "starts_with" => Some(Function::StartsWith),
+            "ends_with" => Some(Function::EndsWith),
+            "matches" => Some(Function::Matches),
+            "len" => Some(Function::Len),
+            "empty" => Some(Function::Empty),
+            _ => None,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Function::Contains => "contains",
+            Function::StartsWith => "starts_with",
+            Function::EndsWith => "ends_with",
+            Function::Matches => "matches",
+            Function::Len => "len",
+            Function::Empty => "empty",
+        }
+    }
+
+    fn arity(self) -> usize {
+        match self {
+            Function::Len | Function::Empty => 1,
+            _ => 2,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Expr {
+    Literal(Value),
+    Variable(String),
+    Field(Box<Expr>, String),
+    Index(Box<Expr>, Box<Expr>),
+    Not(Box<Expr>),
+    Neg(Box<Expr>),
+    Binary(BinaryOp, Box<Expr>, Box<Expr>),
+    Call(Function, Vec<Expr>),
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    position: usize,
+    depth: usize,
+}
+
+type ParseResult = std::result::Result<Expr, String>;
+
+impl Parser {
+    fn parse_root(&mut self) -> ParseResult {
+        if self.tokens.is_empty() {
+            return Err("expression is empty".to_string());
+        }
+        let expr = self.parse_or()?;
+        match self.peek() {
+            None => Ok(expr),
+            Some(token) => Err(format!("unexpected {}", describe(token))),
+        }
+    }
+
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.position).cloned();
+        self.position += 1;
+        token
+    }
+
+    fn eat_op(&mut self, ops: &[&'static str]) -> Option<&'static str> {
+        match self.peek() {
+            Some(Token::Op(op)) if ops.contains(op) => {
+                let op = *op;
+                self.position += 1;
+                Some(op)
+            }
+            _ => None,
+        }
+    }
+
+    fn expect(&mut self, expected: Token) -> std::result::Result<(), String> {
+        match self.next() {
+            Some(token) if token == expected => Ok(()),
+            Some(token) => Err(format!(
+                "expected {}, found {}",
+                describe(&expected),
+                describe(&token)
+            )),
+            None => Err(format!(
+                "expected {}, found end of input",
+                describe(&expected)
+            )),
+        }
+    }
+
+    fn descend(&mut self) -> std::result::Result<(), String> {
+        self.depth += 1;
+        if self.depth > MAX_DEPTH {
+            return Err("expression is nested too deeply".to_string());
+        }
+        Ok(())
+    }
+
+    fn parse_or(&mut self) -> ParseResult {
+        let mut left = self.parse_and()?;
+        while self.eat_op(&["||"]).is_some() {
+            let right = self.parse_and()?;
+            left = Expr::Binary(BinaryOp::Or, Box::new(left), Box::new(right));
+        }
+        Ok(left)
+    }
+
+    fn parse_and(&mut self) -> ParseResult {
+        let mut left = self.parse_comparison()?;
+        while self.eat_op(&["&&"]).is_some() {
+            let right = self.parse_comparison()?;
+            left = Expr::Binary(BinaryOp::And, Box::new(left), Box::new(right));
+        }
+        Ok(left)
+    }
+
+    fn parse_comparison(&mut self) -> ParseResult {
+        let left = self.parse_additive()?;
+        let Some(op) = self.eat_op(&["==", "!=", "<", "<=", ">", ">="]) else {
+            return Ok(left);
+        };
+        let op = match op {
+            "==" => BinaryOp::Eq,
+            "!=" => BinaryOp::Ne,
+            "<" => BinaryOp::Lt,
+            "<=" => BinaryOp::Le,
+            ">" => BinaryOp::Gt,
+            _ => BinaryOp::Ge,
+        };
+        let right = self.parse_additive()?;
+        if let Some(Token::Op(next @ ("==" | "!=" | "<" | "<=" | ">" | ">="))) = self.peek() {
+            return Err(format!(
+                "comparisons cannot be chained; use '&&' before '{}'",
+                next
+            ));
+        }
+        Ok(Expr::Binary(op, Box::new(left), Box::new(right)))
+    }
+
+    fn parse_additive(&mut self) -> ParseResult {
+        let mut left = self.parse_multiplicative()?;
+        while let Some(op) = self.eat_op(&["+", "-"]) {
+            let op = if op == "+" {
+                BinaryOp::Add
+            } else {
+                BinaryOp::Sub
+            };
+            let right = self.parse_multiplicative()?;
+            left = Expr::Binary(op, Box::new(left), Box::new(right));
+        }
+        Ok(left)
+    }
+
+    fn parse_multiplicative(&mut self) -> ParseResult {
+        let mut left = self.parse_unary()?;
+        while let Some(op) = self.eat_op(&["*", "/", "%"]) {
+            let op = match op {
+                "*" => BinaryOp::Mul,
+                "/" => BinaryOp::Div,
+                _ => BinaryOp::Rem,
+            };
+            let right = self.parse_unary()?;
+            left = Expr::Binary(op, Box::new(left), Box::new(right));
+        }
+        Ok(left)
+    }
+
+    fn parse_unary(&mut self) -> ParseResult {
+        self.descend()?;
+        let expr = if self.eat_op(&["!"]).is_some() {
+            Expr::Not(Box::new(self.parse_unary()?))
+        } else if self.eat_op(&["-"]).is_some() {
+            Expr::Neg(Box::new(self.parse_unary()?))
+        } else {
+            self.parse_postfix()?
+        };
+        self.depth -= 1;
+        Ok(expr)
+    }
+
+    fn parse_postfix(&mut self) -> ParseResult {
+        let mut expr = self.parse_primary()?;
+        loop {
+            match self.peek() {
+                Some(Token::Dot) => {
+                    self.position += 1;
+                    expr = match self.next() {
+                        Some(Token::Ident(name)) => Expr::Field(Box::new(expr), name),
+                        Some(Token::Number(index)) if index.fract() == 0.0 => Expr::Index(
+                            Box::new(expr),
+                            Box::new(Expr::Literal(Value::from(index as u64))),
+                        ),
+                        Some(token) => {
+                            return Err(format!(
+                                "expected a field name after '.', found {}",
+                                describe(&token)
+                            ))
+                        }
+                        None => return Err("expected a field name after '.'".to_string()),
+                    };
+                }
+                Some(Token::LBracket) => {
+                    self.position += 1;
+                    let index = self.parse_or()?;
+                    self.expect(Token::RBracket)?;
+                    expr = Expr::Index(Box::new(expr), Box::new(index));
+                }
+                _ => return Ok(expr),
+            }
+        }
+    }
+
+    fn parse_primary(&mut self) -> ParseResult {
+        match self.next() {
+            Some(Token::Number(number)) => Ok(Expr::Literal(number_value(number))),
+            Some(Token::Str(text)) => Ok(Expr::Literal(Value::String(text))),
+            Some(Token::LParen) => {
+                let expr = self.parse_or()?;
+                self.expect(Token::RParen)?;
+                Ok(expr)
+            }
+            Some(Token::Ident(name)) => match name.as_str() {
+                "true" => Ok(Expr::Literal(Value::Bool(true))),
+                "false" => Ok(Expr::Literal(Value::Bool(false))),
+                "null" => Ok(Expr::Literal(Value::Null)),
+                _ if self.peek() == Some(&Token::LParen) => {
+                    self.position += 1;
+                    let function = Function::from_name(&name)
+                        .ok_or_else(|| format!("unknown function '{}'", name))?;
+                    let mut args = Vec::new();
+                    if self.peek() != Some(&Token::RParen) {
+                        loop {
+                            args.push(self.parse_or()?);
+                            if self.peek() == Some(&Token::Comma) {
+                                self.position += 1;
+                            } else {
+                                break;
+                            }
+                        }
+                    }
+                    self.expect(Token::RParen)?;
+                    Ok(Expr::Call(function, args))
+                }
+                _ => Ok(Expr::Variable(name)),
+            },
+            Some(token) => Err(format!("unexpected {}", describe(&token))),
+            None => Err("unexpected end of input".to_string()),
+        }
+    }
+}
+
+fn describe(token: &Token) -> String {
+    match token {
+        Token::Number(number) => format!("number {}", number),
+        Token::Str(text) => format!("string \"{}\"", text),
+        Token::Ident(name) => format!("'{}'", name),
+        Token::Dot => "'.'".to_string(),
+        Token::Comma => "','".to_string(),
+        Token::LParen => "'('".to_string(),
+        Token::RParen => "')'".to_string(),
+        Token::LBracket => "'['".to_string(),
+        Token::RBracket => "']'".to_string(),
+        Token::Op(op) => format!("'{}'", op),
+    }
+}
+
+fn collect_task_references(expr: &Expr, references: &mut Vec<String>) {
+    match expr {
+        Expr::Field(base, name) if matches!(**base, Expr::Variable(ref root) if root == "task") => {
+            references.push(name.clone());
+        }
+        Expr::Index(base, index) if matches!(**base, Expr::Variable(ref root) if root == "task") => {
+            if let Expr::Literal(Value::String(name)) = &**index {
+                references.push(name.clone());
+            } else {
+                collect_task_references(index, references);
+            }
+        }
+        Expr::Literal(_) | Expr::Variable(_) => {}
+        Expr::Field(base, _) | Expr::Not(base) | Expr::Neg(base) => {
+            collect_task_references(base, references)
+        }
+        Expr::Index(base, index) | Expr::Binary(_, base, index) => {
+            collect_task_references(base, references);
+            collect_task_references(index, references);
+        }
+        Expr::Call(_, args) => {
+            for arg in args {
+                collect_task_references(arg, references);
+            }
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Type checking
+// ---------------------------------------------------------------------------
+
+/// Static type of a sub-expression; `Any` for values only known at run time
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Type {
+    Bool,
+    Number,
+    String,
+    Null,
+    Any,
+}
+
+impl Type {
+    fn of(value: &Value) -> Self {
+        match value {
+            Value::Bool(_) => Type::Bool,
+            Value::Number(_) => Type::Number,
+            Value::String(_) => Type::String,
+            Value::Null => Type::Null,
+            _ => Type::Any,
+        }
+    }
+
+    fn accepts(self, expected: Type) -> bool {
+        self == expected || self == Type::Any
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Type::Bool => "boolean",
+            Type::Number => "number",
+            Type::String => "string",
+            Type::Null => "null",
+            Type::Any => "value",
+        }
+    }
+}
+
+type CheckResult = std::result::Result<Type, String>;
+
+fn type_of(expr: &Expr) -> CheckResult {
+    match expr {
+        Expr::Literal(value) => Ok(Type::of(value)),
+        Expr::Variable(name) => match name.as_str() {
+            "iteration" => Ok(Type::Number),
+            _ if ROOTS.contains(&name.as_str()) => Ok(Type::Any),
+            _ => Err(format!(
+                "unknown variable '{}' (expected one of: {})",
+                name,
+                ROOTS.join(", ")
+            )),
+        },
+        Expr::Field(base, name) => {
+            let base = type_of(base)?;
+            if base != Type::Any {
+                return Err(format!(
+                    "cannot access field '{}' of a {}",
+                    name,
+                    base.name()
+                ));
+            }
+            Ok(Type::Any)
+        }
+        Expr::Index(base, index) => {
+            let base = type_of(base)?;
+            let index = type_of(index)?;
+            if base != Type::Any {
+                return Err(format!("cannot index into a {}", base.name()));
+            }
+            if !index.accepts(Type::Number) && !index.accepts(Type::String) {
+                return Err(format!("cannot index with a {}", index.name()));
+            }
+            Ok(Type::Any)
+        }
+        Expr::Not(operand) => {
+            let operand = type_of(operand)?;
+            if !operand.accepts(Type::Bool) {
+                return Err(format!("'!' expects a boolean, found {}", operand.name()));
+            }
+            Ok(Type::Bool)
+        }
+        Expr::Neg(operand) => {
+            let operand = type_of(operand)?;
+            if !operand.accepts(Type::Number) {
+                return Err(format!("'-' expects a number, found {}", operand.name()));
+            }
+            Ok(Type::Number)
+        }
+        Expr::Binary(op, left, right) => {
+            let left = type_of(left)?;
+            let right = type_of(right)?;
+            let mismatch = || {
+                format!(
+                    "'{}' cannot be applied to {} and {}",
+                    op.symbol(),
+                    left.name(),
+                    right.name()
+                )
+            };
+            match op {
+                BinaryOp::And | BinaryOp::Or => {
+                    if !left.accepts(Type::Bool) || !right.accepts(Type::Bool) {
+                        return Err(mismatch());
+                    }
+                    Ok(Type::Bool)
+                }
+                BinaryOp::Eq | BinaryOp::Ne => Ok(Type::Bool),
+                BinaryOp::Lt | BinaryOp::Le | BinaryOp::Gt | BinaryOp::Ge => {
+                    let comparable = [Type::Number, Type::String]
+                        .iter()
+                        .any(|ty| left.accepts(*ty) && right.accepts(*ty));
+                    if !comparable {
+                        return Err(mismatch());
+                    }
+                    Ok(Type::Bool)
+                }
+                BinaryOp::Add => match (left, right) {
+                    (Type::Number, Type::Number) => Ok(Type::Number),
+                    (Type::String, Type::String) => Ok(Type::String),
+                    (Type::Any, Type::Number | Type::String | Type::Any) => Ok(Type::Any),
+                    (Type::Number | Type::String, Type::Any) => Ok(Type::Any),
+                    _ => Err(mismatch()),
+                },
+                BinaryOp::Sub | BinaryOp::Mul | BinaryOp::Div | BinaryOp::Rem => {
+                    if !left.accepts(Type::Number) || !right.accepts(Type::Number) {
+                        return Err(mismatch());
+                    }
+                    Ok(Type::Number)
+                }
+            }
+        }
+        Expr::Call(function, args) => {
+            if args.len() != function.arity() {
+                return Err(format!(
+                    "{}() takes {} argument{}, found {}",
+                    function.name(),
+                    function.arity(),
+                    if function.arity() == 1 { "" } else { "s" },
+                    args.len()
+                ));
+            }
+            let types = args
+                .iter()
+                .map(type_of)
+                .collect::<std::result::Result<Vec<_>, _>>()?;
+            let expect_text = |position: usize| {
+                let ty = types[position];
+                if ty.accepts(Type::String) || ty == Type::Null {
+                    Ok(())
+                } else {
+                    Err(format!(
+                        "{}() expects a string, found {}",
+                        function.name(),
+                        ty.name()
+                    ))
+                }
+            };
+            match function {
+                Function::Contains => {
+                    expect_text(0).map_err(|_| {
+                        format!(
+                            "contains() expects a string, list or object, found {}",
+                            types[0].name()
+                        )
+                    })?;
+                    Ok(Type::Bool)
+                }
+                Function::StartsWith | Function::EndsWith => {
+                    expect_text(0)?;
+                    expect_text(1)?;
+                    Ok(Type::Bool)
+                }
+                Function::Matches => {
+                    expect_text(0)?;
+                    expect_text(1)?;
+                    if let Expr::Literal(Value::String(pattern)) = &args[1] {
+                        compile_regex(pattern)?;
+                    }
+                    Ok(Type::Bool)
+                }
+                Function::Len => {
+                    expect_text(0).map_err(|_| {
+                        format!(
+                            "len() expects a string, list or object, found {}",
+                            types[0].name()
+                        )
+                    })?;
+                    Ok(Type::Number)
+                }
+                Function::Empty => Ok(Type::Bool),
+            }
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Evaluation
+// ---------------------------------------------------------------------------
+
+type EvalResult = std::result::Result<Value, String>;
+
+fn eval(expr: &Expr, scope: &ExpressionScope) -> EvalResult {
+    match expr {
+        Expr::Literal(value) => Ok(value.clone()),
+        Expr::Variable(name) => {
+            if !ROOTS.contains(&name.as_str()) {
+                return Err(format!("unknown variable '{}'", name));
+            }
+            Ok(scope.root(name))
+        }
+        Expr::Field(base, name) => Ok(match eval(base, scope)? {
+            Value::Object(mut map) => map.remove(name).unwrap_or(Value::Null),
+            _ => Value::Null,
+        }),
+        Expr::Index(base, index) => {
+            let base = eval(base, scope)?;
+            let index = eval(index, scope)?;
+            Ok(match (base, index) {
+                (Value::Array(mut items), Value::Number(n)) => match n.as_u64() {
+                    Some(i) if (i as usize) < items.len() => items.swap_remove(i as usize),
+                    _ => Value::Null,
+                },
+                (Value::Object(mut map), Value::String(key)) => {
+                    map.remove(&key).unwrap_or(Value::Null)
+                }
+                _ => Value::Null,
+            })
+        }
+        Expr::Not(operand) => Ok(Value::Bool(!as_bool(&eval(operand, scope)?, "!")?)),
+        Expr::Neg(operand) => {
+            let value = eval(operand, scope)?;
+            let number = as_number(&value, "-")?;
+            Ok(number_value(-number))
+        }
+        Expr::Binary(BinaryOp::And, left, right) => {
+            if !as_bool(&eval(left, scope)?, "&&")? {
+                return Ok(Value::Bool(false));
+            }
+            Ok(Value::Bool(as_bool(&eval(right, scope)?, "&&")?))
+        }
+        Expr::Binary(BinaryOp::Or, left, right) => {
+            if as_bool(&eval(left, scope)?, "||")? {
+                return Ok(Value::Bool(true));
+            }
+            Ok(Value::Bool(as_bool(&eval(right, scope)?, "||")?))
+        }
+        Expr::Binary(op, left, right) => {
+            let left = eval(left, scope)?;
+            let right = eval(right, scope)?;
+            binary(*op, &left, &right)
+        }
+        Expr::Call(function, args) => {
+            let args = args
+                .iter()
+                .map(|arg| eval(arg, scope))
+                .collect::<std::result::Result<Vec<_>, _>>()?;
+            call(*function, &args)
+        }
+    }
+}
+
+fn binary(op: BinaryOp, left: &Value, right: &Value) -> EvalResult {
+    match op {
+        BinaryOp::Eq => Ok(Value::Bool(values_equal(left, right))),
+        BinaryOp::Ne => Ok(Value::Bool(!values_equal(left, right))),
+        BinaryOp::Lt | BinaryOp::Le | BinaryOp::Gt | BinaryOp::Ge => {
+            let ordering = match (left, right) {
+                (Value::Number(_), Value::Number(_)) => {
+                    let (a, b) = (
+                        as_number(left, op.symbol())?,
+                        as_number(right, op.symbol())?,
+                    );
+                    a.partial_cmp(&b)
+                }
+                (Value::String(a), Value::String(b)) => Some(a.cmp(b)),
+                _ => None,
+            };
+            let ordering = ordering
+                .ok_or_else(|| format!("cannot compare {} with {}", kind(left), kind(right)))?;
+            Ok(Value::Bool(match op {
+                BinaryOp::Lt => ordering == Ordering::Less,
+                BinaryOp::Le => ordering != Ordering::Greater,
+                BinaryOp::Gt => ordering == Ordering::Greater,
+                _ => ordering != Ordering::Less,
+            }))
+        }
+        BinaryOp::Add => match (left, right) {
+            (Value::String(a), Value::String(b)) => Ok(Value::String(format!("{}{}", a, b))),
+            _ => Ok(number_value(as_number(left, "+")? + as_number(right, "+")?)),
+        },
+        BinaryOp::Sub | BinaryOp::Mul | BinaryOp::Div | BinaryOp::Rem => {
+            let a = as_number(left, op.symbol())?;
+            let b = as_number(right, op.symbol())?;
+            if matches!(op, BinaryOp::Div | BinaryOp::Rem) && b == 0.0 {
+                return Err("division by zero".to_string());
+            }
+            Ok(number_value(match op {
+                BinaryOp::Sub => a - b,
+                BinaryOp::Mul => a * b,
+                BinaryOp::Div => a / b,
+                _ => a % b,
+            }))
+        }
+        BinaryOp::And | BinaryOp::Or => unreachable!("logical operators short-circuit in eval"),
+    }
+}
+
+fn call(function: Function, args: &[Value]) -> EvalResult {
+    let result = match (function, args) {
+        (Function::Contains, [Value::String(text), Value::String(needle)]) => {
+            Value::Bool(text.contains(needle.as_str()))
+        }
+        (Function::Contains, [Value::Array(items), needle]) => {
+            Value::Bool(items.iter().any(|item| values_equal(item, needle)))
+        }
+        (Function::Contains, [Value::Object(map), Value::String(key)]) => {
+            Value::Bool(map.contains_key(key))
+        }
+        (Function::StartsWith, [Value::String(text), Value::String(prefix)]) => {
+            Value::Bool(text.starts_with(prefix.as_str()))
+        }
+        (Function::EndsWith, [Value::String(text), Value::String(suffix)]) => {
+            Value::Bool(text.ends_with(suffix.as_str()))
+        }
+        (Function::Matches, [Value::String(text), Value::String(pattern)]) => {
+            Value::Bool(compile_regex(pattern)?.is_match(text))
+        }
+        (
+            Function::Contains | Function::StartsWith | Function::EndsWith | Function::Matches,
+            [Value::Null, _],
+        ) => Value::Bool(false),
+        (Function::Len, [Value::String(text)]) => Value::from(text.chars().count()),
+        (Function::Len, [Value::Array(items)]) => Value::from(items.len()),
+        (Function::Len, [Value::Object(map)]) => Value::from(map.len()),
+        (Function::Len, [Value::Null]) => Value::from(0),
+        (Function::Empty, [value]) => Value::Bool(match value {
+            Value::Null => true,
+            Value::String(text) => text.is_empty(),
+            Value::Array(items) => items.is_empty(),
+            Value::Object(map) => map.is_empty(),
+            _ => false,
+        }),
+        _ => {
+            let kinds: Vec<&str> = args.iter().map(kind).collect();
+            return Err(format!(
+                "{}() cannot be applied to {}",
+                function.name(),
+                kinds.join(" and ")
+            ));
+        }
+    };
+    Ok(result)
+}
+
+fn compile_regex(pattern: &str) -> std::result::Result<regex::Regex, String> {
+    regex::RegexBuilder::new(pattern)
+        .size_limit(REGEX_SIZE_LIMIT)
+        .build()
+        .map_err(|e| format!("invalid regex '{}': {}", pattern, e))
+}
+
+fn values_equal(left: &Value, right: &Value) -> bool {
+    match (left, right) {
+        (Value::Number(a), Value::Number(b)) => a.as_f64() == b.as_f64(),
+        _ => left == right,
+    }
+}
+
+fn as_bool(value: &Value, op: &str) -> std::result::Result<bool, String> {
+    match value {
+        Value::Bool(value) => Ok(*value),
+        other => Err(format!("'{}' expects a boolean, found {}", op, kind(other))),
+    }
+}
+
+fn as_number(value: &Value, op: &str) -> std::result::Result<f64, String> {
+    value
+        .as_f64()
+        .ok_or_else(|| format!("'{}' expects a number, found {}", op, kind(value)))
+}
+
+/// JSON number, as an integer when the value is whole
+fn number_value(number: f64) -> Value {
+    if number.fract() == 0.0 && number.abs() < i64::MAX as f64 {
+        Value::from(number as i64)
+    } else {
+        serde_json::Number::from_f64(number)
+            .map(Value::Number)
+            .unwrap_or(Value::Null)
+    }
+}
+
+fn kind(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "list",
+        Value::Object(_) => "object",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn scope() -> ExpressionScope {
+        let mut inputs = HashMap::new();
+        inputs.insert("branch".to_string(), json!("release/1.2"));
+        inputs.insert("retries".to_string(), json!(3));
+        let mut metadata = HashMap::new();
+        metadata.insert("approver".to_string(), json!("ops"));
+
+        let mut context = LoopContext::new(2);
+        context.set_variable("item".to_string(), json!({"name": "api", "size": 12}));
+
+        ExpressionScope::new()
+            .with_task(
+                "fetch",
+                "completed",
+                Some(r#"{"items": [1, 2, 3], "ok": true}"#),
+            )
+            .with_task("lint", "failed", Some("error: unused import\n"))
+            .with_task("build.compile", "completed", None)
+            .with_inputs(&inputs)
+            .with_state(&metadata)
+            .with_loop(&context)
+    }
+
+    fn eval_bool(source: &str) -> bool {
+        let expression = Expression::parse(source).unwrap();
+        expression.check().unwrap();
+        expression.evaluate_bool(&scope()).unwrap()
+    }
+
+    fn check_error(source: &str) -> String {
+        Expression::parse(source)
+            .and_then(|expression| expression.check())
+            .unwrap_err()
+            .to_string()
+    }
+
+    #[test]
+    fn test_comparisons_and_logic() {
+        assert!(eval_bool("1 < 2 && 2 <= 2 && 3 > 2 && 3 >= 3"));
+        assert!(eval_bool("1 != 2 || false"));
+        assert!(eval_bool("!(1 == 2)"));
+        assert!(eval_bool("'abc' < 'abd'"));
+        assert!(eval_bool("1 == 1.0"));
+        assert!(!eval_bool("null == false"));
+    }
+
+    #[test]
+    fn test_arithmetic() {
+        assert!(eval_bool("1 + 2 * 3 == 7"));
+        assert!(eval_bool("(1 + 2) * 3 == 9"));
+        assert!(eval_bool("7 % 4 == 3 && 7 / 2 == 3.5"));
+        assert!(eval_bool("-inputs.retries + 5 == 2"));
+        assert!(eval_bool("'a' + 'b' == 'ab'"));
+    }
+
+    #[test]
+    fn test_task_paths() {
+        assert!(eval_bool("task.fetch.status == 'completed'"));
+        assert!(eval_bool("task.fetch.output.ok"));
+        assert!(eval_bool("task.fetch.output.items[1] == 2"));
+        assert!(eval_bool("task.fetch.output.items.2 == 3"));
+        assert!(eval_bool("len(task.fetch.output.items) == 3"));
+        assert!(eval_bool("task.lint.output == 'error: unused import'"));
+        assert!(eval_bool("task['build.compile'].status == 'completed'"));
+        assert!(eval_bool("task.missing.output == null"));
+    }
+
+    #[test]
+    fn test_string_functions() {
+        assert!(eval_bool("starts_with(inputs.branch, 'release/')"));
+        assert!(eval_bool("ends_with(inputs.branch, '.2')"));
+        assert!(eval_bool("contains(task.lint.output, 'unused')"));
+        assert!(eval_bool("contains(task.fetch.output.items, 2)"));
+        assert!(eval_bool(
+            "matches(inputs.branch, '^release/[0-9]+\\\\.[0-9]+$')"
+        ));
+        assert!(!eval_bool("contains(task.missing.output, 'x')"));
+    }
+
+    #[test]
+    fn test_length_and_empty() {
+        assert!(eval_bool("len('héllo') == 5"));
+        assert!(eval_bool("empty(task.missing.output)"));
+        assert!(eval_bool("empty('') && !empty(state.approver)"));
+        assert!(eval_bool("len(task.missing.output) == 0"));
+    }
+
+    #[test]
+    fn test_loop_variables() {
+        assert!(eval_bool("iteration == 2"));
+        assert!(eval_bool("loop.item.name == 'api' && loop.item.size > 10"));
+    }
+
+    #[test]
+    fn test_task_references() {
+        let expression =
+            Expression::parse("task.a.status == 'completed' && len(task['b.c'].output) > 0")
+                .unwrap();
+        assert_eq!(expression.task_references(), vec!["a", "b.c"]);
+    }
+
+    #[test]
+    fn test_syntax_errors() {
+        assert!(Expression::parse("").is_err());
+        assert!(Expression::parse("1 +").is_err());
+        assert!(Expression::parse("a = 1").is_err());
+        assert!(Expression::parse("'open").is_err());
+        assert!(Expression::parse("1 < 2 < 3").is_err());
+        assert!(Expression::parse("nope(1)").is_err());
+        let deep = format!("{}true", "!".repeat(MAX_DEPTH + 1));
+        assert!(Expression::parse(&deep).is_err());
+    }
+
+    #[test]
+    fn test_type_errors() {
+        assert!(check_error("1 + 2").contains("must be a boolean"));
+        assert!(check_error("'a' < 1").contains("cannot be applied"));
+        assert!(check_error("1 && true").contains("cannot be applied"));
+        assert!(check_error("'a' - 1 == 0").contains("cannot be applied"));
+        assert!(check_error("len(1) == 0").contains("len() expects"));
+        assert!(check_error("starts_with(1, 'a')").contains("expects a string"));
+        assert!(check_error("contains('a')").contains("takes 2 arguments"));
+        assert!(check_error("matches(inputs.x, '(')").contains("invalid regex"));
+        assert!(check_error("outputs.x == 1").contains("unknown variable 'outputs'"));
+        assert!(check_error("'a'.b == 1").contains("cannot access field"));
+    }
+
+    #[test]
+    fn test_runtime_errors() {
+        let expression = Expression::parse("task.fetch.output.items > 1").unwrap();
+        expression.check().unwrap();
+        assert!(expression.evaluate_bool(&scope()).is_err());
+
+        let expression = Expression::parse("inputs.retries / 0 == 1").unwrap();
+        assert!(expression.evaluate_bool(&scope()).is_err());
+
+        let expression = Expression::parse("inputs.branch").unwrap();
+        assert!(expression.evaluate_bool(&scope()).is_err());
+    }
+}
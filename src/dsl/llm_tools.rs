@@ -0,0 +1,559 @@
+//! Tools for `llm` Tasks
+//!
+//! This module builds the tools an `llm` task offers its model from the task's
+//! `tools:` list and runs the calls the model makes: tools of the workflow's MCP
+//! servers through the [`McpClientPool`], other tasks through a
+//! [`TaskToolRunner`], and the built-in file and shell tools within the limits
+//! of the task's `permissions`.
+//!
+//! | Mode | Built-in tools |
+//! |------|----------------|
+//! | `default`, `plan` | `read_file`, `list_directory` |
+//! | `acceptEdits` | also `write_file` |
+//! | `bypassPermissions` | also `shell`, and paths outside `allowed_directories` |
+
+use crate::dsl::events::ExecutionEvents;
+use crate::dsl::mcp_clients::{tool_result_to_output, McpClientPool};
+use crate::dsl::schema::{BuiltinTool, DSLWorkflow, LlmToolSpec, PermissionsSpec};
+use crate::dsl::tool_policy::resolve;
+use crate::error::{Error, Result};
+use crate::ports::secondary::{LlmToolCall, LlmToolDefinition, LlmToolHandler, LlmToolOutput};
+use async_trait::async_trait;
+use serde_json::{json, Map, Value};
+use std::collections::{HashMap, HashSet};
+use std::path::{Component, Path, PathBuf};
+use std::time::Duration;
+
+/// Longest tool output sent back to the model, in characters
+const MAX_TOOL_OUTPUT_CHARS: usize = 50_000;
+
+/// Longest a `shell` command may run when the task sets no `timeout_secs`
+const DEFAULT_SHELL_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// Runs workflow tasks on behalf of a model
+#[async_trait]
+pub trait TaskToolRunner: Send + Sync {
+    /// Run a task with the given arguments merged into its inputs, returning its output
+    async fn run_task(&self, task_id: &str, arguments: Map<String, Value>) -> Result<String>;
+}
+
+/// IDs of the tasks offered as tools by `llm` tasks
+///
+/// These tasks are not scheduled on their own; they run when a model calls them.
+pub fn tool_task_ids(workflow: &DSLWorkflow) -> HashSet<String> {
+    workflow
+        .tasks
+        .values()
+        .filter_map(|spec| spec.llm.as_ref())
+        .flat_map(|llm| &llm.tools)
+        .filter_map(|tool| match tool {
+            LlmToolSpec::Task { task, .. } => Some(task.clone()),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Check that a built-in tool is allowed by the permission mode
+pub fn check_builtin(
+    tool: BuiltinTool,
+    permissions: &PermissionsSpec,
+) -> std::result::Result<(), String> {
+    let mode = permissions.mode.as_str();
+    let allowed = match tool {
+        BuiltinTool::ReadFile | BuiltinTool::ListDirectory => true,
+        BuiltinTool::WriteFile => matches!(mode, "acceptEdits" | "bypassPermissions"),
+        BuiltinTool::Shell => mode == "bypassPermissions",
+    };
+    if allowed {
+        return Ok(());
+    }
+
+    let required = match tool {
+        BuiltinTool::Shell => "'bypassPermissions'",
+        _ => "'acceptEdits' or 'bypassPermissions'",
+    };
+    Err(format!(
+        "built-in tool '{}' requires permissions mode {} (current: '{}')",
+        builtin_name(tool),
+        required,
+        mode
+    ))
+}
+
+/// Name of a built-in tool as offered to the model
+pub fn builtin_name(tool: BuiltinTool) -> &'static str {
+    match tool {
+        BuiltinTool::ReadFile => "read_file",
+        BuiltinTool::ListDirectory => "list_directory",
+        BuiltinTool::WriteFile => "write_file",
+        BuiltinTool::Shell => "shell",
+    }
+}
+
+fn builtin_definition(tool: BuiltinTool) -> LlmToolDefinition {
+    let (description, parameters) = match tool {
+        BuiltinTool::ReadFile => (
+            "Read a text file",
+            json!({
+                "type": "object",
+                "properties": {"path": {"type": "string", "description": "File path"}},
+                "required": ["path"]
+            }),
+        ),
+        BuiltinTool::ListDirectory => (
+            "List the entries of a directory (directories end with '/')",
+            json!({
+                "type": "object",
+                "properties": {"path": {"type": "string", "description": "Directory path (default: '.')"}}
+            }),
+        ),
+        BuiltinTool::WriteFile => (
+            "Create or overwrite a text file",
+            json!({
+                "type": "object",
+                "properties": {
+                    "path": {"type": "string", "description": "File path"},
+                    "content": {"type": "string", "description": "New file content"}
+                },
+                "required": ["path", "content"]
+            }),
+        ),
+        BuiltinTool::Shell => (
+            "Run a shell command and return its output",
+            json!({
+                "type": "object",
+                "properties": {"command": {"type": "string", "description": "Command line for sh -c"}},
+                "required": ["command"]
+            }),
+        ),
+    };
+
+    LlmToolDefinition {
+        name: builtin_name(tool).to_string(),
+        description: description.to_string(),
+        parameters,
+    }
+}
+
+/// Argument schema derived from a task's inputs and their default values
+fn task_parameters(inputs: &HashMap<String, Value>) -> Value {
+    let properties: Map<String, Value> = inputs
+        .iter()
+        .map(|(name, default)| {
+            let schema = match default {
+                Value::Bool(_) => json!({"type": "boolean"}),
+                Value::Number(n) if n.is_i64() || n.is_u64() => json!({"type": "integer"}),
+                Value::Number(_) => json!({"type": "number"}),
+                Value::String(_) => json!({"type": "string"}),
+                Value::Array(_) => json!({"type": "array"}),
+                Value::Object(_) => json!({"type": "object"}),
+                Value::Null => json!({}),
+            };
+            (name.clone(), schema)
+        })
+        .collect();
+    json!({"type": "object", "properties": properties})
+}
+
+/// Where the tools of a call are routed
+enum ToolRoute {
+    Mcp { server: String, tool: String },
+    Task { task: String },
+    Builtin(BuiltinTool),
+}
+
+/// Paths the built-in tools may touch
+struct Sandbox {
+    base_dir: PathBuf,
+    allowed: Vec<PathBuf>,
+    unrestricted: bool,
+}
+
+impl Sandbox {
+    fn new(base_dir: PathBuf, permissions: &PermissionsSpec) -> Self {
+        let allowed = if permissions.allowed_directories.is_empty() {
+            vec![resolve(&base_dir)]
+        } else {
+            permissions
+                .allowed_directories
+                .iter()
+                .map(|dir| resolve(&base_dir.join(dir)))
+                .collect()
+        };
+        Self {
+            base_dir,
+            allowed,
+            unrestricted: permissions.mode == "bypassPermissions",
+        }
+    }
+
+    /// Resolve a path relative to the base directory, checking it is allowed
+    ///
+    /// Symlinks are followed first, so a link inside an allowed directory
+    /// cannot lead out of it.
+    fn resolve(&self, path: &str) -> std::result::Result<PathBuf, String> {
+        let resolved = resolve(&self.base_dir.join(path));
+        if self.unrestricted || self.allowed.iter().any(|dir| resolved.starts_with(dir)) {
+            Ok(resolved)
+        } else {
+            Err(format!(
+                "Access to '{}' is denied: outside the allowed directories",
+                path
+            ))
+        }
+    }
+}
+
+/// Resolve `.` and `..` components without touching the filesystem
+pub(crate) fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            other => normalized.push(other),
+        }
+    }
+    normalized
+}
+
+/// The tools offered to the model of one `llm` task
+pub struct LlmTools<'a> {
+    definitions: Vec<LlmToolDefinition>,
+    routes: HashMap<String, ToolRoute>,
+    mcp_clients: &'a McpClientPool,
+    tasks: &'a dyn TaskToolRunner,
+    sandbox: Sandbox,
+    /// Longest a `shell` command may run
+    shell_timeout: Duration,
+    /// The `llm` task, and where its tool calls are reported
+    task_id: &'a str,
+    events: &'a ExecutionEvents,
+}
+
+impl<'a> LlmTools<'a> {
+    /// Build the tools declared by an `llm` task
+    ///
+    /// MCP servers are connected to list their tools. Built-in tools not
+    /// permitted by `permissions` and duplicate tool names are rejected.
+    /// `shell` commands are stopped after `timeout_secs`, or two minutes when
+    /// unset. Each tool call is reported to `events` as progress of `task_id`.
+    #[allow(clippy::too_many_arguments)]
+    pub async fn build(
+        task_id: &'a str,
+        specs: &[LlmToolSpec],
+        workflow: &DSLWorkflow,
+        permissions: &PermissionsSpec,
+        timeout_secs: Option<u64>,
+        mcp_clients: &'a McpClientPool,
+        tasks: &'a dyn TaskToolRunner,
+        events: &'a ExecutionEvents,
+    ) -> Result<Self> {
+        let mut tools = Vec::new();
+
+        for spec in specs {
+            match spec {
+                LlmToolSpec::Mcp {
+                    server,
+                    tools: names,
+                } => {
+                    let available = mcp_clients.list_tools(server).await?;
+                    for name in names {
+                        if !available.iter().any(|tool| &tool.name == name) {
+                            return Err(Error::InvalidInput(format!(
+                                "MCP server '{}' does not provide tool '{}'",
+                                server, name
+                            )));
+                        }
+                    }
+                    for tool in available {
+                        if names.is_empty() || names.contains(&tool.name) {
+                            let route = ToolRoute::Mcp {
+                                server: server.clone(),
+                                tool: tool.name.clone(),
+                            };
+                            tools.push((
+                                LlmToolDefinition {
+                                    name: tool.name,
+                                    description: tool.description,
+                                    parameters: tool.input_schema,
+                                },
+                                route,
+                            ));
+                        }
+                    }
+                }
+                LlmToolSpec::Task {
+                    task,
+                    name,
+                    description,
+                    parameters,
+                } => {
+                    let task_spec = workflow.tasks.get(task).ok_or_else(|| {
+                        Error::InvalidInput(format!("Tool task '{}' does not exist", task))
+                    })?;
+                    tools.push((
+                        LlmToolDefinition {
+                            name: name.clone().unwrap_or_else(|| task.clone()),
+                            description: description
+                                .clone()
+                                .unwrap_or_else(|| task_spec.description.clone()),
+                            parameters: parameters
+                                .clone()
+                                .unwrap_or_else(|| task_parameters(&task_spec.inputs)),
+                        },
+                        ToolRoute::Task { task: task.clone() },
+                    ));
+                }
+                LlmToolSpec::Builtin { name } => {
+                    check_builtin(*name, permissions).map_err(Error::InvalidInput)?;
+                    tools.push((builtin_definition(*name), ToolRoute::Builtin(*name)));
+                }
+            }
+        }
+
+        let mut definitions = Vec::with_capacity(tools.len());
+        let mut routes = HashMap::new();
+        for (definition, route) in tools {
+            if routes.insert(definition.name.clone(), route).is_some() {
+                return Err(Error::InvalidInput(format!(
+                    "Tool '{}' is offered more than once",
+                    definition.name
+                )));
+            }
+            definitions.push(definition);
+        }
+
+        let mut base_dir = std::env::current_dir()?;
+        if let Some(cwd) = &workflow.cwd {
+            base_dir.push(cwd);
+        }
+
+        Ok(Self {
+            definitions,
+            routes,
+            mcp_clients,
+            tasks,
+            sandbox: Sandbox::new(normalize(&base_dir), permissions),
+            shell_timeout: timeout_secs.map_or(DEFAULT_SHELL_TIMEOUT, Duration::from_secs),
+            task_id,
+            events,
+        })
+    }
+
+    /// Definitions to send to the model
+    pub fn definitions(&self) -> &[LlmToolDefinition] {
+        &self.definitions
+    }
+
+    async fn run_builtin(
+        &self,
+        tool: BuiltinTool,
+        arguments: &Value,
+    ) -> std::result::Result<String, String> {
+        let argument = |name: &str| -> std::result::Result<&str, String> {
+            arguments[name]
+                .as_str()
+                .ok_or_else(|| format!("Missing string argument '{}'", name))
+        };
+
+        match tool {
+            BuiltinTool::ReadFile => {
+                let path = self.sandbox.resolve(argument("path")?)?;
+                tokio::fs::read_to_string(&path)
+                    .await
+                    .map_err(|e| format!("Cannot read '{}': {}", path.display(), e))
+            }
+            BuiltinTool::ListDirectory => {
+                let path = self
+                    .sandbox
+                    .resolve(arguments["path"].as_str().unwrap_or("."))?;
+                let mut entries = tokio::fs::read_dir(&path)
+                    .await
+                    .map_err(|e| format!("Cannot list '{}': {}", path.display(), e))?;
+                let mut names = Vec::new();
+                while let Ok(Some(entry)) = entries.next_entry().await {
+                    let mut name = entry.file_name().to_string_lossy().to_string();
+                    if entry.file_type().await.map(|t| t.is_dir()).unwrap_or(false) {
+                        name.push('/');
+                    }
+                    names.push(name);
+                }
+                names.sort();
+                Ok(names.join("\n"))
+            }
+            BuiltinTool::WriteFile => {
+                let path = self.sandbox.resolve(argument("path")?)?;
+                let content = argument("content")?;
+                if let Some(parent) = path.parent() {
+                    tokio::fs::create_dir_all(parent)
+                        .await
+                        .map_err(|e| format!("Cannot create '{}': {}", parent.display(), e))?;
+                }
+                tokio::fs::write(&path, content)
+                    .await
+                    .map_err(|e| format!("Cannot write '{}': {}", path.display(), e))?;
+                Ok(format!(
+                    "Wrote {} bytes to {}",
+                    content.len(),
+                    path.display()
+                ))
+            }
+            BuiltinTool::Shell => {
+                let command = tokio::process::Command::new("sh")
+                    .arg("-c")
+                    .arg(argument("command")?)
+                    .current_dir(&self.sandbox.base_dir)
+                    .kill_on_drop(true)
+                    .output();
+                let output = tokio::time::timeout(self.shell_timeout, command)
+                    .await
+                    .map_err(|_| {
+                        format!(
+                            "Command timed out after {} seconds",
+                            self.shell_timeout.as_secs()
+                        )
+                    })?
+                    .map_err(|e| format!("Cannot run command: {}", e))?;
+                let mut text = String::from_utf8_lossy(&output.stdout).to_string();
+                let stderr = String::from_utf8_lossy(&output.stderr);
+                if !stderr.is_empty() {
+                    text.push_str(&format!("\nstderr:\n{}", stderr));
+                }
+                if output.status.success() {
+                    Ok(text)
+                } else {
+                    Err(format!("Command failed ({}):\n{}", output.status, text))
+                }
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl LlmToolHandler for LlmTools<'_> {
+    async fn call_tool(&self, call: &LlmToolCall) -> LlmToolOutput {
+        self.events
+            .progress(Some(self.task_id), format!("Calling tool: {}", call.name));
+
+        let result = match self.routes.get(&call.name) {
+            None => Err(format!("Unknown tool '{}'", call.name)),
+            Some(ToolRoute::Mcp { server, tool }) => self
+                .mcp_clients
+                .call_tool(server, tool, call.arguments.clone(), None)
+                .await
+                .map_err(|e| e.to_string())
+                .and_then(|result| {
+                    let output = tool_result_to_output(&result);
+                    if result.is_error {
+                        Err(output)
+                    } else {
+                        Ok(output)
+                    }
+                }),
+            Some(ToolRoute::Task { task }) => match &call.arguments {
+                Value::Object(arguments) => self
+                    .tasks
+                    .run_task(task, arguments.clone())
+                    .await
+                    .map_err(|e| e.to_string()),
+                Value::Null => self
+                    .tasks
+                    .run_task(task, Map::new())
+                    .await
+                    .map_err(|e| e.to_string()),
+                other => Err(format!("Arguments must be an object, got: {}", other)),
+            },
+            Some(ToolRoute::Builtin(tool)) => self.run_builtin(*tool, &call.arguments).await,
+        };
+
+        match result {
+            Ok(content) => LlmToolOutput::ok(truncate(content)),
+            Err(error) => LlmToolOutput::error(truncate(error)),
+        }
+    }
+}
+
+fn truncate(mut text: String) -> String {
+    if let Some((index, _)) = text.char_indices().nth(MAX_TOOL_OUTPUT_CHARS) {
+        text.truncate(index);
+        text.push_str("\n[output truncated]");
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn permissions(mode: &str, allowed: &[&str]) -> PermissionsSpec {
+        PermissionsSpec {
+            mode: mode.to_string(),
+            allowed_directories: allowed.iter().map(|d| d.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn test_check_builtin_by_mode() {
+        let default = PermissionsSpec::default();
+        assert!(check_builtin(BuiltinTool::ReadFile, &default).is_ok());
+        assert!(check_builtin(BuiltinTool::WriteFile, &default).is_err());
+
+        let edits = permissions("acceptEdits", &[]);
+        assert!(check_builtin(BuiltinTool::WriteFile, &edits).is_ok());
+        let err = check_builtin(BuiltinTool::Shell, &edits).unwrap_err();
+        assert!(err.contains("requires permissions mode 'bypassPermissions'"));
+
+        assert!(check_builtin(BuiltinTool::Shell, &permissions("bypassPermissions", &[])).is_ok());
+    }
+
+    #[test]
+    fn test_sandbox_confines_paths() {
+        let sandbox = Sandbox::new(PathBuf::from("/work"), &permissions("default", &["data"]));
+        assert_eq!(
+            sandbox.resolve("data/./a.txt").unwrap(),
+            PathBuf::from("/work/data/a.txt")
+        );
+        assert!(sandbox.resolve("data/../secret.txt").is_err());
+        assert!(sandbox.resolve("/etc/passwd").is_err());
+
+        let open = Sandbox::new(
+            PathBuf::from("/work"),
+            &permissions("bypassPermissions", &[]),
+        );
+        assert!(open.resolve("/etc/passwd").is_ok());
+    }
+
+    #[test]
+    fn test_symlinks_out_of_allowed_directories() {
+        let dir = tempfile::tempdir().unwrap();
+        let outside = tempfile::tempdir().unwrap();
+        std::fs::create_dir(dir.path().join("data")).unwrap();
+        std::os::unix::fs::symlink(outside.path(), dir.path().join("data/escape")).unwrap();
+
+        let sandbox = Sandbox::new(
+            dir.path().to_path_buf(),
+            &permissions("acceptEdits", &["data"]),
+        );
+        assert!(sandbox.resolve("data/a.txt").is_ok());
+        assert!(sandbox.resolve("data/new/a.txt").is_ok());
+        assert!(sandbox
+            .resolve("data/escape/a.txt")
+            .unwrap_err()
+            .contains("outside the allowed directories"));
+        assert!(sandbox.resolve("data/escape/../a.txt").is_err());
+    }
+
+    #[test]
+    fn test_task_parameters_from_inputs() {
+        let inputs = HashMap::from([
+            ("city".to_string(), json!("Paris")),
+            ("days".to_string(), json!(3)),
+        ]);
+        let schema = task_parameters(&inputs);
+        assert_eq!(schema["properties"]["city"]["type"], json!("string"));
+        assert_eq!(schema["properties"]["days"]["type"], json!("integer"));
+    }
+}
@@ -266,6 +266,10 @@ fn substitute_single_condition(
         Condition::StateExists { key } => Condition::StateExists {
             key: context.substitute_variables(key),
         },
+        // Loop variables are read through `loop.<name>` inside expressions
+        Condition::Expression { expression } => Condition::Expression {
+            expression: expression.clone(),
+        },
         Condition::Always => Condition::Always,
         Condition::Never => Condition::Never,
     }
@@ -500,6 +504,9 @@ mod tests {
                 system_prompt: Some("You are assistant number {{count}}".to_string()),
                 endpoint: None,
                 api_key: None,
+                auth: None,
+                auth_header: None,
+                api_version: None,
                 temperature: None,
                 max_tokens: None,
                 top_p: None,
@@ -508,6 +515,11 @@ mod tests {
                 timeout_secs: None,
                 extra_params: Default::default(),
                 stream: false,
+                schema: None,
+                tools: vec![],
+                max_tool_iterations: None,
+                permissions: Default::default(),
+                profile: None,
             }),
             ..Default::default()
         };
@@ -0,0 +1,330 @@
+//! MCP Client Pool for Workflow Execution
+//!
+//! This module manages connections to the MCP servers declared in a workflow's
+//! `mcp_servers` section. Servers are started (or connected to) lazily the first
+//! time a task references them and are shared by all tasks of the workflow.
+//! Each server connects independently, so a slow or hung server does not hold
+//! up tasks using other servers.
+
+use crate::adapters::secondary::{HttpMcpClient, SseMcpClient, StdioMcpClient};
+use crate::dsl::events::ExecutionEvents;
+use crate::dsl::schema::McpServerSpec;
+use crate::dsl::secrets::SecretStore;
+use crate::error::{Error, Result};
+use crate::ports::secondary::{McpServer, ToolDefinition, ToolResult};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{Mutex, OnceCell};
+
+/// Time allowed for starting a server and its handshake when the task sets no timeout
+const DEFAULT_CONNECT_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// A connected server together with the tools it advertised during the handshake
+struct ConnectedServer {
+    client: Arc<dyn McpServer>,
+    tools: Vec<ToolDefinition>,
+}
+
+/// Pool of MCP server connections shared across a workflow run
+pub struct McpClientPool {
+    specs: HashMap<String, McpServerSpec>,
+    secrets: Arc<SecretStore>,
+    events: Arc<ExecutionEvents>,
+    servers: Mutex<HashMap<String, Arc<OnceCell<Arc<ConnectedServer>>>>>,
+}
+
+impl McpClientPool {
+    /// Create a pool for the given server declarations
+    ///
+    /// `${secret.name}` references in server environment variables, URLs and
+    /// headers are substituted from `secrets` when the server is first used.
+    /// Connections are reported to `events`.
+    pub fn new(
+        specs: HashMap<String, McpServerSpec>,
+        secrets: Arc<SecretStore>,
+        events: Arc<ExecutionEvents>,
+    ) -> Self {
+        Self {
+            specs,
+            secrets,
+            events,
+            servers: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Get the connection to a server, starting it on first use
+    ///
+    /// Starting the server and the handshake must finish within `timeout`
+    /// (or [`DEFAULT_CONNECT_TIMEOUT`]). A failed or timed out connection is
+    /// retried by the next caller.
+    async fn connect(
+        &self,
+        server: &str,
+        timeout: Option<Duration>,
+    ) -> Result<Arc<ConnectedServer>> {
+        let spec = self.specs.get(server).ok_or_else(|| {
+            Error::InvalidInput(format!(
+                "MCP server '{}' is not defined in workflow mcp_servers",
+                server
+            ))
+        })?;
+
+        // Only the cell lookup holds the pool lock; the connection itself is
+        // serialized per server by its cell
+        let cell = self
+            .servers
+            .lock()
+            .await
+            .entry(server.to_string())
+            .or_default()
+            .clone();
+
+        let timeout = timeout.unwrap_or(DEFAULT_CONNECT_TIMEOUT);
+        let init = cell.get_or_try_init(|| async {
+            self.events
+                .progress(None, format!("Connecting to MCP server: {}", server));
+            let client = self.create_client(server, spec).await?;
+            let tools = client.list_tools().await?;
+            Ok::<_, Error>(Arc::new(ConnectedServer { client, tools }))
+        });
+        let connected = tokio::time::timeout(timeout, init).await.map_err(|_| {
+            Error::InvalidInput(format!(
+                "MCP server '{}' did not finish connecting within {}s",
+                server,
+                timeout.as_secs()
+            ))
+        })??;
+        Ok(connected.clone())
+    }
+
+    async fn create_client(&self, name: &str, spec: &McpServerSpec) -> Result<Arc<dyn McpServer>> {
+        let resolve_map = |map: &HashMap<String, String>| -> Result<HashMap<String, String>> {
+            map.iter()
+                .map(|(k, v)| Ok((k.clone(), self.secrets.substitute(v)?)))
+                .collect()
+        };
+
+        match spec.server_type.as_str() {
+            "stdio" => {
+                let command = spec.command.as_deref().ok_or_else(|| {
+                    Error::InvalidInput(format!(
+                        "MCP server '{}' of type 'stdio' requires a command",
+                        name
+                    ))
+                })?;
+                let env = resolve_map(&spec.env)?;
+                let client = StdioMcpClient::connect(name, command, &spec.args, &env).await?;
+                Ok(Arc::new(client))
+            }
+            server_type @ ("http" | "sse") => {
+                let url = spec.url.as_deref().ok_or_else(|| {
+                    Error::InvalidInput(format!(
+                        "MCP server '{}' of type '{}' requires a url",
+                        name, server_type
+                    ))
+                })?;
+                let url = self.secrets.substitute(url)?;
+                let headers = resolve_map(&spec.headers)?;
+                if server_type == "sse" {
+                    Ok(Arc::new(SseMcpClient::connect(name, &url, &headers).await?))
+                } else {
+                    Ok(Arc::new(
+                        HttpMcpClient::connect(name, &url, &headers).await?,
+                    ))
+                }
+            }
+            other => Err(Error::InvalidInput(format!(
+                "MCP server '{}' has unsupported type '{}' (expected stdio, http or sse)",
+                name, other
+            ))),
+        }
+    }
+
+    /// List the tools advertised by a server
+    pub async fn list_tools(&self, server: &str) -> Result<Vec<ToolDefinition>> {
+        Ok(self.connect(server, None).await?.tools.clone())
+    }
+
+    /// Invoke a tool on a server, optionally bounded by a timeout
+    ///
+    /// The timeout applies to connecting to the server and to the call
+    /// separately.
+    pub async fn call_tool(
+        &self,
+        server: &str,
+        tool: &str,
+        arguments: serde_json::Value,
+        timeout: Option<Duration>,
+    ) -> Result<ToolResult> {
+        let connected = self.connect(server, timeout).await?;
+
+        if !connected.tools.iter().any(|t| t.name == tool) {
+            let available: Vec<&str> = connected.tools.iter().map(|t| t.name.as_str()).collect();
+            return Err(Error::InvalidInput(format!(
+                "MCP server '{}' does not provide tool '{}' (available: {})",
+                server,
+                tool,
+                available.join(", ")
+            )));
+        }
+
+        let call = connected.client.call_tool(tool, arguments);
+        match timeout {
+            Some(duration) => tokio::time::timeout(duration, call).await.map_err(|_| {
+                Error::InvalidInput(format!(
+                    "MCP tool '{}' on server '{}' timed out after {}s",
+                    tool,
+                    server,
+                    duration.as_secs()
+                ))
+            })?,
+            None => call.await,
+        }
+    }
+
+    /// Drop all connections, terminating any stdio servers
+    pub async fn shutdown(&self) {
+        self.servers.lock().await.clear();
+    }
+}
+
+/// Render a tool result as task output text
+///
+/// Content block arrays are flattened to their text; structured results are
+/// serialized as JSON so later tasks can navigate them.
+pub fn tool_result_to_output(result: &ToolResult) -> String {
+    if let Some(blocks) = result.content.as_array() {
+        let texts: Vec<&str> = blocks
+            .iter()
+            .filter_map(|block| block.get("text").and_then(|t| t.as_str()))
+            .collect();
+        if texts.len() == blocks.len() {
+            return texts.join("\n");
+        }
+    }
+    match &result.content {
+        serde_json::Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dsl::events::ExecutionEvent;
+    use serde_json::json;
+
+    #[test]
+    fn test_tool_result_to_output_text_blocks() {
+        let result = ToolResult {
+            content: json!([
+                {"type": "text", "text": "line one"},
+                {"type": "text", "text": "line two"}
+            ]),
+            is_error: false,
+        };
+        assert_eq!(tool_result_to_output(&result), "line one\nline two");
+    }
+
+    #[test]
+    fn test_tool_result_to_output_structured() {
+        let result = ToolResult {
+            content: json!({"count": 3}),
+            is_error: false,
+        };
+        assert_eq!(tool_result_to_output(&result), r#"{"count":3}"#);
+    }
+
+    #[tokio::test]
+    async fn test_unknown_server() {
+        let pool = McpClientPool::new(
+            HashMap::new(),
+            Arc::new(SecretStore::new()),
+            Arc::new(ExecutionEvents::new()),
+        );
+        let err = pool
+            .call_tool("missing", "tool", json!({}), None)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("not defined"));
+    }
+
+    fn stdio_spec(command: &str, args: &[&str]) -> McpServerSpec {
+        McpServerSpec {
+            server_type: "stdio".to_string(),
+            command: Some(command.to_string()),
+            args: args.iter().map(|arg| arg.to_string()).collect(),
+            env: HashMap::new(),
+            url: None,
+            headers: HashMap::new(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_connect_timeout_does_not_block_other_servers() {
+        // Neither server ever answers the initialize request
+        let specs = HashMap::from([
+            ("slow".to_string(), stdio_spec("sleep", &["30"])),
+            ("hung".to_string(), stdio_spec("sleep", &["30"])),
+        ]);
+        let events = Arc::new(ExecutionEvents::new());
+        events.console().set_enabled(false);
+        let pool = Arc::new(McpClientPool::new(
+            specs,
+            Arc::new(SecretStore::new()),
+            events,
+        ));
+
+        let slow_pool = pool.clone();
+        let slow = tokio::spawn(async move {
+            slow_pool
+                .call_tool("slow", "tool", json!({}), Some(Duration::from_secs(5)))
+                .await
+        });
+        tokio::time::sleep(Duration::from_millis(100)).await;
+
+        let started = std::time::Instant::now();
+        let err = pool
+            .call_tool("hung", "tool", json!({}), Some(Duration::from_secs(1)))
+            .await
+            .unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("did not finish connecting within 1s"));
+        assert!(started.elapsed() < Duration::from_secs(4));
+
+        assert!(slow.await.unwrap().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_unsupported_server_type() {
+        let spec = McpServerSpec {
+            server_type: "websocket".to_string(),
+            command: None,
+            args: Vec::new(),
+            env: HashMap::new(),
+            url: Some("ws://localhost:9".to_string()),
+            headers: HashMap::new(),
+        };
+        let events = Arc::new(ExecutionEvents::new());
+        events.console().set_enabled(false);
+        let mut rx = events.channel();
+        let pool = McpClientPool::new(
+            HashMap::from([("socket".to_string(), spec)]),
+            Arc::new(SecretStore::new()),
+            events,
+        );
+        let err = pool.list_tools("socket").await.unwrap_err();
+        assert!(err.to_string().contains("expected stdio, http or sse"));
+
+        // The attempt is reported as an event rather than printed
+        match rx.try_recv().unwrap() {
+            ExecutionEvent::Progress { task_id, message } => {
+                assert_eq!(task_id, None);
+                assert_eq!(message, "Connecting to MCP server: socket");
+            }
+            other => panic!("unexpected event {:?}", other),
+        }
+    }
+}
@@ -2,6 +2,10 @@
 //!
 //! This module provides a publish-subscribe message bus that enables agents to
 //! communicate and coordinate during workflow execution.
+//!
+//! Every message sent is also kept in a `MessageLog`, so participants can read
+//! a channel's messages after they were sent, and the log can be saved in the
+//! workflow state and restored when the workflow is resumed.
 
 use crate::error::{Error, Result};
 use serde::{Deserialize, Serialize};
@@ -92,12 +96,41 @@ impl Channel {
     }
 }
 
+/// Messages sent on the bus, and how far each participant has read them
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct MessageLog {
+    /// Messages of each channel, and of each agent's direct messages under
+    /// `@<agent>`, in the order they were sent
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub transcripts: HashMap<String, Vec<AgentMessage>>,
+    /// Number of messages of a transcript a participant has read, keyed by
+    /// `<participant>/<transcript>`
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub cursors: HashMap<String, usize>,
+}
+
+impl MessageLog {
+    /// Check whether no message has been sent
+    pub fn is_empty(&self) -> bool {
+        self.transcripts.is_empty()
+    }
+}
+
+/// Name of the transcript holding an agent's direct messages
+pub fn direct_transcript(agent_name: &str) -> String {
+    format!("@{}", agent_name)
+}
+
 /// Message bus for inter-agent communication
 pub struct MessageBus {
     /// Communication channels indexed by name
     channels: Arc<RwLock<HashMap<String, Channel>>>,
     /// Direct message queues for each agent
     direct_messages: Arc<RwLock<HashMap<String, broadcast::Sender<AgentMessage>>>>,
+    /// JSON schemas of the declared message types
+    message_types: Arc<std::sync::RwLock<HashMap<String, serde_json::Value>>>,
+    /// Every message sent, for participants reading them later
+    log: Arc<std::sync::Mutex<MessageLog>>,
 }
 
 impl MessageBus {
@@ -106,6 +139,43 @@ impl MessageBus {
         Self {
             channels: Arc::new(RwLock::new(HashMap::new())),
             direct_messages: Arc::new(RwLock::new(HashMap::new())),
+            message_types: Arc::new(std::sync::RwLock::new(HashMap::new())),
+            log: Arc::new(std::sync::Mutex::new(MessageLog::default())),
+        }
+    }
+
+    /// Declare a message type whose payloads must match a JSON schema
+    ///
+    /// Once any type is declared, messages of undeclared types are rejected.
+    pub fn declare_message_type(&self, name: String, schema: serde_json::Value) {
+        self.message_types.write().unwrap().insert(name, schema);
+    }
+
+    /// Check a message's payload against the schema of its type
+    pub fn check_message(&self, message: &AgentMessage) -> Result<()> {
+        let message_types = self.message_types.read().unwrap();
+        if message_types.is_empty() {
+            return Ok(());
+        }
+        let schema = message_types.get(&message.message_type).ok_or_else(|| {
+            Error::InvalidInput(format!("Unknown message type '{}'", message.message_type))
+        })?;
+
+        let errors =
+            crate::dsl::output_schema::validate(schema, &message.payload).map_err(|e| {
+                Error::InvalidInput(format!(
+                    "Message type '{}' has an invalid schema: {}",
+                    message.message_type, e
+                ))
+            })?;
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(Error::InvalidInput(format!(
+                "Message of type '{}' does not match its schema: {}",
+                message.message_type,
+                errors.join("; ")
+            )))
         }
     }
 
@@ -161,8 +231,13 @@ impl MessageBus {
                 message.from, channel_name
             )));
         }
+        self.check_message(&message)?;
 
-        channel.send(message)
+        // The message is kept in the log, so it is delivered even when no
+        // participant is subscribed right now
+        self.append(channel_name.to_string(), message.clone());
+        let _ = channel.send(message);
+        Ok(())
     }
 
     /// Send a direct message to an agent
@@ -171,14 +246,73 @@ impl MessageBus {
         let sender = direct_messages
             .get(&message.to)
             .ok_or_else(|| Error::InvalidInput(format!("Agent '{}' not registered", message.to)))?;
+        self.check_message(&message)?;
 
-        sender
-            .send(message)
-            .map_err(|e| Error::InvalidInput(format!("Failed to send direct message: {}", e)))?;
-
+        self.append(direct_transcript(&message.to), message.clone());
+        let _ = sender.send(message);
         Ok(())
     }
 
+    /// Read the messages a participant has not read yet
+    ///
+    /// Reads the given channel, or the participant's direct messages when
+    /// `channel` is `None`. Messages the participant sent itself are skipped.
+    pub async fn read(&self, reader: &str, channel: Option<&str>) -> Result<Vec<AgentMessage>> {
+        let transcript = match channel {
+            Some(channel_name) => {
+                let channels = self.channels.read().await;
+                let channel = channels.get(channel_name).ok_or_else(|| {
+                    Error::InvalidInput(format!("Channel '{}' not found", channel_name))
+                })?;
+                if !channel.has_participant(reader) {
+                    return Err(Error::InvalidInput(format!(
+                        "Agent '{}' is not a participant in channel '{}'",
+                        reader, channel_name
+                    )));
+                }
+                channel_name.to_string()
+            }
+            None => direct_transcript(reader),
+        };
+
+        let mut log = self.log.lock().unwrap();
+        let messages = log
+            .transcripts
+            .get(&transcript)
+            .cloned()
+            .unwrap_or_default();
+        let cursor = log
+            .cursors
+            .insert(format!("{}/{}", reader, transcript), messages.len())
+            .unwrap_or(0);
+        Ok(messages
+            .into_iter()
+            .skip(cursor)
+            .filter(|message| message.from != reader)
+            .collect())
+    }
+
+    /// All messages of a channel, or of an agent's direct messages (`@<agent>`)
+    pub fn transcript(&self, name: &str) -> Vec<AgentMessage> {
+        let log = self.log.lock().unwrap();
+        log.transcripts.get(name).cloned().unwrap_or_default()
+    }
+
+    /// Snapshot of every message sent and the participants' read positions
+    pub fn log(&self) -> MessageLog {
+        self.log.lock().unwrap().clone()
+    }
+
+    /// Replace the log, such as with one saved in a resumed workflow's state
+    pub fn restore(&self, log: MessageLog) {
+        *self.log.lock().unwrap() = log;
+    }
+
+    fn append(&self, transcript: String, message: AgentMessage) {
+        let mut log = self.log.lock().unwrap();
+        log.transcripts.entry(transcript).or_default().push(message);
+    }
+
     /// Subscribe to a channel
     pub async fn subscribe_to_channel(
         &self,
@@ -372,4 +506,105 @@ mod tests {
         let result = bus.send_to_channel("private_channel", message).await;
         assert!(result.is_err());
     }
+
+    #[tokio::test]
+    async fn test_read_unread_messages() {
+        let bus = MessageBus::new();
+        bus.create_channel(
+            "updates".to_string(),
+            "Updates".to_string(),
+            vec!["agent1".to_string(), "agent2".to_string()],
+            "json".to_string(),
+        )
+        .await
+        .unwrap();
+
+        // Sending without subscribers keeps the message in the log
+        for n in 1..=2 {
+            let message = AgentMessage::new(
+                "agent1".to_string(),
+                "updates".to_string(),
+                "status".to_string(),
+                serde_json::json!({ "step": n }),
+            );
+            bus.send_to_channel("updates", message).await.unwrap();
+        }
+
+        let read = bus.read("agent2", Some("updates")).await.unwrap();
+        assert_eq!(read.len(), 2);
+        assert!(bus
+            .read("agent2", Some("updates"))
+            .await
+            .unwrap()
+            .is_empty());
+        // Senders do not read their own messages
+        assert!(bus
+            .read("agent1", Some("updates"))
+            .await
+            .unwrap()
+            .is_empty());
+        assert!(bus.read("agent3", Some("updates")).await.is_err());
+
+        // A restored log keeps the read positions
+        let restored = MessageBus::new();
+        restored
+            .create_channel(
+                "updates".to_string(),
+                "Updates".to_string(),
+                vec!["agent1".to_string(), "agent2".to_string()],
+                "json".to_string(),
+            )
+            .await
+            .unwrap();
+        restored.restore(bus.log());
+        assert_eq!(restored.transcript("updates").len(), 2);
+        assert!(restored
+            .read("agent2", Some("updates"))
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_message_type_schema() {
+        let bus = MessageBus::new();
+        bus.register_agent("agent2".to_string()).await.unwrap();
+        bus.declare_message_type(
+            "status".to_string(),
+            serde_json::json!({
+                "type": "object",
+                "required": ["done"],
+                "properties": { "done": { "type": "boolean" } }
+            }),
+        );
+
+        let message = |message_type: &str, payload| {
+            AgentMessage::new(
+                "agent1".to_string(),
+                "agent2".to_string(),
+                message_type.to_string(),
+                payload,
+            )
+        };
+        bus.send_direct(message("status", serde_json::json!({ "done": true })))
+            .await
+            .unwrap();
+
+        let error = bus
+            .send_direct(message("status", serde_json::json!({ "done": "yes" })))
+            .await
+            .unwrap_err();
+        assert!(error
+            .to_string()
+            .contains("Message of type 'status' does not match its schema"));
+        let error = bus
+            .send_direct(message("other", serde_json::json!({})))
+            .await
+            .unwrap_err();
+        assert!(error.to_string().contains("Unknown message type 'other'"));
+
+        let read = bus.read("agent2", None).await.unwrap();
+        assert_eq!(read.len(), 1);
+        assert_eq!(read[0].payload["done"], true);
+    }
 }
@@ -0,0 +1,168 @@
+//! Message Tools for Agents
+//!
+//! This module offers the workflow's [`MessageBus`] to agents as the tools of an
+//! in-process MCP server, and holds the helpers tasks use to send and read
+//! messages.
+//!
+//! | Tool | Arguments |
+//! |------|-----------|
+//! | `send_message` | `channel` or `to`, `message_type`, `payload` |
+//! | `read_messages` | `channel`, or none for the agent's direct messages |
+
+use crate::adapters::secondary::SdkMcpServer;
+use crate::dsl::message_bus::{AgentMessage, MessageBus};
+use crate::error::{Error, Result};
+use crate::options::AgentOptions;
+use crate::ports::secondary::ToolResult;
+use schemars::JsonSchema;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::sync::Arc;
+
+/// Name of the MCP server offering the message tools
+pub const SERVER_NAME: &str = "workflow_messages";
+
+/// Names of the message tools as the CLI sees them
+pub fn tool_names() -> Vec<String> {
+    ["send_message", "read_messages"]
+        .iter()
+        .map(|tool| format!("mcp__{}__{}", SERVER_NAME, tool))
+        .collect()
+}
+
+#[derive(Deserialize, JsonSchema)]
+struct SendMessageArgs {
+    /// Channel to send the message to
+    #[serde(default)]
+    channel: Option<String>,
+    /// Agent to send a direct message to, instead of a channel
+    #[serde(default)]
+    to: Option<String>,
+    /// Type of the message
+    message_type: String,
+    /// Content of the message, matching the schema of its type
+    payload: Value,
+}
+
+#[derive(Deserialize, JsonSchema)]
+struct ReadMessagesArgs {
+    /// Channel to read; your direct messages are read when omitted
+    #[serde(default)]
+    channel: Option<String>,
+}
+
+/// Offer the message tools to an agent
+///
+/// Messages the agent sends are from `agent`, and it reads the channels it
+/// participates in. The tools are added to the agent's allowed tools.
+pub fn attach(options: &mut AgentOptions, agent: &str, bus: Arc<MessageBus>) {
+    let sender = agent.to_string();
+    let send_bus = Arc::clone(&bus);
+    let reader = agent.to_string();
+
+    let server = SdkMcpServer::builder(SERVER_NAME)
+        .tool(
+            "send_message",
+            "Send a message to a workflow channel, or directly to another agent",
+            move |args: SendMessageArgs| {
+                let bus = Arc::clone(&send_bus);
+                let from = sender.clone();
+                async move {
+                    let result = send(
+                        &bus,
+                        &from,
+                        args.channel.as_deref(),
+                        args.to.as_deref(),
+                        &args.message_type,
+                        args.payload,
+                    )
+                    .await;
+                    Ok(match result {
+                        Ok(target) => ToolResult::text(format!("Message sent to {}", target)),
+                        Err(e) => ToolResult::error(e.to_string()),
+                    })
+                }
+            },
+        )
+        .tool(
+            "read_messages",
+            "Read the messages of a workflow channel, or your direct messages, that you have not read yet",
+            move |args: ReadMessagesArgs| {
+                let bus = Arc::clone(&bus);
+                let reader = reader.clone();
+                async move {
+                    Ok(match bus.read(&reader, args.channel.as_deref()).await {
+                        Ok(messages) if messages.is_empty() => ToolResult::text("No new messages"),
+                        Ok(messages) => ToolResult::text(
+                            serde_json::to_string_pretty(&messages_json(&messages))
+                                .unwrap_or_default(),
+                        ),
+                        Err(e) => ToolResult::error(e.to_string()),
+                    })
+                }
+            },
+        )
+        .build();
+
+    options.add_sdk_mcp_server(Arc::new(server));
+    for tool in tool_names() {
+        if !options.allowed_tools.contains(&tool) {
+            options.allowed_tools.push(tool);
+        }
+    }
+}
+
+/// Send a message to a channel, or directly to an agent
+///
+/// Exactly one of `channel` and `to` must be given. Returns a description of
+/// where the message went.
+pub async fn send(
+    bus: &MessageBus,
+    from: &str,
+    channel: Option<&str>,
+    to: Option<&str>,
+    message_type: &str,
+    payload: Value,
+) -> Result<String> {
+    match (channel, to) {
+        (Some(channel), None) => {
+            let message = AgentMessage::new(
+                from.to_string(),
+                channel.to_string(),
+                message_type.to_string(),
+                payload,
+            );
+            bus.send_to_channel(channel, message).await?;
+            Ok(format!("channel '{}'", channel))
+        }
+        (None, Some(to)) => {
+            let message = AgentMessage::new(
+                from.to_string(),
+                to.to_string(),
+                message_type.to_string(),
+                payload,
+            );
+            bus.send_direct(message).await?;
+            Ok(format!("agent '{}'", to))
+        }
+        _ => Err(Error::InvalidInput(
+            "A message needs either a channel or a recipient agent".to_string(),
+        )),
+    }
+}
+
+/// Messages as JSON, as handed to agents and tasks
+pub fn messages_json(messages: &[AgentMessage]) -> Value {
+    Value::Array(
+        messages
+            .iter()
+            .map(|message| {
+                json!({
+                    "from": message.from,
+                    "message_type": message.message_type,
+                    "payload": message.payload,
+                })
+            })
+            .collect(),
+    )
+}
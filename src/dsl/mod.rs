@@ -39,29 +39,42 @@ pub mod debug_ai;
 #[cfg(feature = "tui")]
 pub mod debug_tui;
 pub mod debugger;
+pub mod events;
 pub mod executor;
+pub mod expression;
 pub mod fetcher;
 pub mod hooks;
+pub mod llm_tools;
 pub mod loop_context;
+pub mod mcp_clients;
 pub mod message_bus;
 pub mod message_formatter;
+pub mod message_tools;
 pub mod nl_generator;
 pub mod notifications;
+pub mod output_schema;
 pub mod parser;
 pub mod predefined_tasks;
 pub mod repl;
 pub mod schema;
+pub mod secrets;
+pub mod stages;
 pub mod state;
+pub mod subflow_executor;
 pub mod task_graph;
 pub mod template;
+pub mod tool_policy;
 pub mod truncation;
 pub mod validator;
 pub mod variables;
 
+pub use events::{ExecutionEvent, ExecutionEvents, ExecutionObserver};
 pub use executor::DSLExecutor;
+pub use expression::{Expression, ExpressionScope};
 pub use fetcher::{fetch_subflow, SubflowCache};
 pub use loop_context::{substitute_task_variables, LoopContext};
-pub use message_bus::{AgentMessage, Channel, MessageBus};
+pub use mcp_clients::McpClientPool;
+pub use message_bus::{AgentMessage, Channel, MessageBus, MessageLog};
 pub use nl_generator::{generate_and_save, generate_from_nl};
 pub use notifications::{
     ConsoleSender, DiscordSender, ElevenLabsSender, EmailSender, FileSender, NotificationContext,
@@ -73,19 +86,21 @@ pub use parser::{
     serialize_workflow, write_workflow_file,
 };
 pub use schema::{
-    AgentSpec, CleanupStrategy, CollectionSource, CommandSpec, Condition, ConditionSpec,
-    ContextConfig, ContextMode, CriterionResult, DSLWorkflow, DefinitionOfDone, DiscordEmbed,
-    DiscordField, DoneCriterion, ExecutionMode, FileFormat, FileNotificationFormat, HttpAuth,
-    HttpMethod, HttpSpec, InputSpec, LimitsConfig, LoopControl, LoopSpec, McpToolSpec,
-    NotificationChannel, NotificationDefaults, NotificationPriority, NotificationSpec,
-    OutputDataSource, OutputSource, OutputSpec, PagerDutyAction, PagerDutySeverity,
-    PermissionsSpec, RetryConfig, ScriptLanguage, ScriptSpec, SecretSource, SecretSpec,
-    SlackAttachment, SlackField, SlackMethod, SmtpConfig, StageSpec, SubflowSource, SubflowSpec,
-    TaskSpec, TaskStatusCondition, TeamsFact, TelegramParseMode, ToolsConfig, TruncationStrategy,
-    WorkflowSpec,
+    AgentSpec, BudgetAction, BudgetConfig, CleanupStrategy, CollectionSource, CommandSpec,
+    Condition, ConditionSpec, ContextConfig, ContextMode, CriterionResult, DSLWorkflow,
+    DefinitionOfDone, DiscordEmbed, DiscordField, DoneCriterion, ExecutionMode, FileFormat,
+    FileNotificationFormat, HttpAuth, HttpMethod, HttpSpec, InputSpec, LimitsConfig, LoopControl,
+    LoopSpec, McpToolSpec, NotificationChannel, NotificationDefaults, NotificationPriority,
+    NotificationSpec, OutputDataSource, OutputSource, OutputSpec, PagerDutyAction,
+    PagerDutySeverity, PermissionsSpec, PublishSpec, RetryConfig, ScriptLanguage, ScriptSpec,
+    SecretSource, SecretSpec, SessionSpec, SlackAttachment, SlackField, SlackMethod, SmtpConfig,
+    StageSpec, SubflowSource, SubflowSpec, TaskMessagesSpec, TaskSpec, TaskStatusCondition,
+    TeamsFact, TelegramParseMode, ToolsConfig, TruncationStrategy, WorkflowSpec,
 };
+pub use secrets::{SecretProvider, SecretResolver, SecretStore};
+pub use stages::{PlannedStage, StagePlan};
 pub use state::{
-    ContextMetrics, LoopState, OutputType, StatePersistence, TaskOutput, WorkflowState,
+    ContextMetrics, LoopState, OutputType, StatePersistence, TaskOutput, Usage, WorkflowState,
     WorkflowStatus,
 };
 pub use task_graph::{TaskGraph, TaskStatus};
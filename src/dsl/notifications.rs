@@ -193,12 +193,29 @@ impl NotificationContext {
         if result.contains("${") {
             return Err(NotificationError::InterpolationError(format!(
                 "Unresolved variables in template: {}",
-                result
+                crate::dsl::secrets::redact_secrets(
+                    &result,
+                    self.secrets.values().map(String::as_str)
+                )
             )));
         }
 
         Ok(result)
     }
+
+    /// Interpolate variables in text that is displayed or delivered to readers
+    ///
+    /// Like [`interpolate`](Self::interpolate), but secret values are replaced
+    /// with `[REDACTED]`, whether they come from a `${secret.name}` reference or
+    /// from another variable. Use [`interpolate`](Self::interpolate) for
+    /// credentials, tokens and URLs.
+    pub fn interpolate_message(&self, template: &str) -> NotificationResult<String> {
+        let result = self.interpolate(template)?;
+        Ok(crate::dsl::secrets::redact_secrets(
+            &result,
+            self.secrets.values().map(String::as_str),
+        ))
+    }
 }
 
 // ============================================================================
@@ -355,10 +372,12 @@ impl NotificationSender for NtfySender {
                 markdown,
                 auth_token,
             } => {
-                let interpolated_message = context.interpolate(message)?;
+                let interpolated_message = context.interpolate_message(message)?;
                 let interpolated_topic = context.interpolate(topic)?;
-                let interpolated_title =
-                    title.as_ref().map(|t| context.interpolate(t)).transpose()?;
+                let interpolated_title = title
+                    .as_ref()
+                    .map(|t| context.interpolate_message(t))
+                    .transpose()?;
                 let interpolated_click = click_url
                     .as_ref()
                     .map(|u| context.interpolate(u))
@@ -498,7 +517,7 @@ impl NotificationSender for SlackSender {
                 method,
                 attachments,
             } => {
-                let interpolated_message = context.interpolate(message)?;
+                let interpolated_message = context.interpolate_message(message)?;
                 let interpolated_credential = context.interpolate(credential)?;
 
                 match method {
@@ -649,7 +668,7 @@ impl NotificationSender for DiscordSender {
                 tts,
                 embed,
             } => {
-                let interpolated_message = context.interpolate(message)?;
+                let interpolated_message = context.interpolate_message(message)?;
                 let interpolated_webhook = context.interpolate(webhook_url)?;
 
                 self.send_webhook(
@@ -703,7 +722,7 @@ impl NotificationSender for ConsoleSender {
     ) -> NotificationResult<()> {
         match channel {
             NotificationChannel::Console { colored, timestamp } => {
-                let interpolated_message = context.interpolate(message)?;
+                let interpolated_message = context.interpolate_message(message)?;
 
                 let output = if *timestamp {
                     format!(
@@ -777,7 +796,7 @@ impl NotificationSender for FileSender {
                 use tokio::fs::OpenOptions;
                 use tokio::io::AsyncWriteExt;
 
-                let interpolated_message = context.interpolate(message)?;
+                let interpolated_message = context.interpolate_message(message)?;
                 let interpolated_path = context.interpolate(path)?;
 
                 let content = match format {
@@ -1219,6 +1238,18 @@ mod tests {
         assert_eq!(result, "Key: secret123");
     }
 
+    #[test]
+    fn test_context_interpolate_message_redacts_secrets() {
+        let context = NotificationContext::new()
+            .with_secret("api_key", "secret123")
+            .with_metadata("error", "401 for key secret123");
+
+        let result = context
+            .interpolate_message("Key ${secret.api_key}: ${metadata.error}")
+            .unwrap();
+        assert_eq!(result, "Key [REDACTED]: 401 for key [REDACTED]");
+    }
+
     #[test]
     fn test_context_interpolation_metadata() {
         let context = NotificationContext::new().with_metadata("status", "success");
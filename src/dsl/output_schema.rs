@@ -0,0 +1,192 @@
+//! Typed Task Outputs
+//!
+//! This module validates task results against the JSON Schemas declared on
+//! `llm.schema` and on a task's `outputs:`. A schema on an output whose source
+//! is the task's own `task_output` describes the task's result; the executor
+//! passes it to the model (natively where the provider supports structured
+//! output, as instructions otherwise) and feeds validation errors back as
+//! retry feedback until the task's `schema_retries` are exhausted.
+
+use crate::dsl::schema::{OutputDataSource, TaskSpec};
+use serde_json::Value;
+
+/// Retries granted to a task whose result does not match its schema
+pub const DEFAULT_SCHEMA_RETRIES: u32 = 2;
+
+/// Schema the result of a task must satisfy, if it declares one
+///
+/// `llm.schema` takes precedence over a schema on an output read from the
+/// task's own result.
+pub fn result_schema<'a>(task_id: &str, spec: &'a TaskSpec) -> Option<&'a Value> {
+    if let Some(schema) = spec.llm.as_ref().and_then(|llm| llm.schema.as_ref()) {
+        return Some(schema);
+    }
+
+    let mut names: Vec<&String> = spec.outputs.keys().collect();
+    names.sort();
+    names.into_iter().find_map(|name| {
+        let output = &spec.outputs[name];
+        match &output.source {
+            OutputDataSource::TaskOutput { task } if task == task_id => output.schema.as_ref(),
+            _ => None,
+        }
+    })
+}
+
+/// Check that a schema is itself a valid JSON Schema
+pub fn check_schema(schema: &Value) -> Result<(), String> {
+    compile(schema).map(|_| ())
+}
+
+/// Validate a value against a schema
+///
+/// Returns one message per violation, prefixed with the location of the
+/// offending value (`/items/0/id`) when it is not the root.
+pub fn validate(schema: &Value, value: &Value) -> Result<Vec<String>, String> {
+    let validator = compile(schema)?;
+
+    Ok(validator
+        .iter_errors(value)
+        .map(|error| {
+            let path = error.instance_path.to_string();
+            if path.is_empty() {
+                error.to_string()
+            } else {
+                format!("{}: {}", path, error)
+            }
+        })
+        .collect())
+}
+
+fn compile(schema: &Value) -> Result<jsonschema::Validator, String> {
+    jsonschema::validator_for(schema).map_err(|e| format!("Invalid JSON Schema: {}", e))
+}
+
+/// Interpret a result as JSON for validation
+///
+/// Text results are parsed as JSON, accepting a single fenced code block
+/// (```` ```json ... ``` ````) as models often wrap structured answers in one.
+/// Text that is not JSON is returned unchanged.
+pub fn as_json(value: &Value) -> Value {
+    let Value::String(text) = value else {
+        return value.clone();
+    };
+
+    let text = text.trim();
+    let unfenced = text
+        .strip_prefix("```")
+        .and_then(|rest| rest.strip_suffix("```"))
+        .map(|body| {
+            // Drop the language tag on the opening fence
+            let body = body.trim_start_matches(|c: char| c.is_ascii_alphanumeric());
+            body.trim()
+        })
+        .unwrap_or(text);
+
+    serde_json::from_str(unfenced).unwrap_or_else(|_| value.clone())
+}
+
+/// Instructions asking a model to answer with JSON matching a schema
+pub fn schema_instructions(schema: &Value) -> String {
+    format!(
+        "Respond with only a JSON value that conforms to this JSON Schema:\n{}",
+        serde_json::to_string_pretty(schema).unwrap_or_else(|_| schema.to_string())
+    )
+}
+
+/// Retry feedback listing the schema violations of a result
+pub fn format_schema_feedback(errors: &[String]) -> String {
+    let mut feedback = String::from(
+        "Your previous output did not match the required JSON Schema. Fix these errors:\n",
+    );
+    for error in errors {
+        feedback.push_str(&format!("  - {}\n", error));
+    }
+    feedback
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dsl::schema::OutputSpec;
+    use serde_json::json;
+
+    fn person_schema() -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "name": {"type": "string"},
+                "age": {"type": "integer", "minimum": 0}
+            },
+            "required": ["name", "age"]
+        })
+    }
+
+    #[test]
+    fn test_validate_reports_each_violation() {
+        let schema = person_schema();
+
+        assert!(validate(&schema, &json!({"name": "Ada", "age": 36}))
+            .unwrap()
+            .is_empty());
+
+        let errors = validate(&schema, &json!({"age": -1})).unwrap();
+        assert_eq!(errors.len(), 2);
+        assert!(errors
+            .iter()
+            .any(|e| e.contains("\"name\" is a required property")));
+        assert!(errors.iter().any(|e| e.starts_with("/age:")));
+    }
+
+    #[test]
+    fn test_check_schema_rejects_invalid_schema() {
+        assert!(check_schema(&person_schema()).is_ok());
+        assert!(check_schema(&json!({"type": "not-a-type"})).is_err());
+    }
+
+    #[test]
+    fn test_as_json_parses_fenced_blocks() {
+        assert_eq!(
+            as_json(&json!("```json\n{\"name\": \"Ada\"}\n```")),
+            json!({"name": "Ada"})
+        );
+        assert_eq!(as_json(&json!(" [1, 2] ")), json!([1, 2]));
+        assert_eq!(as_json(&json!("plain text")), json!("plain text"));
+        assert_eq!(as_json(&json!({"a": 1})), json!({"a": 1}));
+    }
+
+    #[test]
+    fn test_result_schema_from_own_output() {
+        let mut spec = TaskSpec::default();
+        spec.outputs.insert(
+            "other".to_string(),
+            OutputSpec {
+                source: OutputDataSource::TaskOutput {
+                    task: "upstream".to_string(),
+                },
+                description: None,
+                schema: Some(json!({"type": "string"})),
+            },
+        );
+        assert!(result_schema("extract", &spec).is_none());
+
+        spec.outputs.insert(
+            "person".to_string(),
+            OutputSpec {
+                source: OutputDataSource::TaskOutput {
+                    task: "extract".to_string(),
+                },
+                description: None,
+                schema: Some(person_schema()),
+            },
+        );
+        assert_eq!(result_schema("extract", &spec), Some(&person_schema()));
+    }
+
+    #[test]
+    fn test_schema_feedback_lists_errors() {
+        let feedback = format_schema_feedback(&["/age: -1 is less than the minimum of 0".into()]);
+        assert!(feedback.contains("did not match the required JSON Schema"));
+        assert!(feedback.contains("  - /age: -1 is less than the minimum of 0"));
+    }
+}
@@ -479,6 +479,8 @@ tasks:
             imports: HashMap::new(),
             notifications: None,
             limits: None,
+            max_concurrency: None,
+            providers: HashMap::new(),
         };
 
         // Add import
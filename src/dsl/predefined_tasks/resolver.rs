@@ -304,6 +304,8 @@ fn create_agent_from_template(
         tools: template.tools.clone(),
         permissions: template.permissions.clone(),
         max_turns: template.max_turns,
+        max_concurrency: None,
+        profile: None,
     })
 }
 
@@ -201,6 +201,9 @@ pub enum InspectTarget {
 
     /// Inspect current execution position
     Position,
+
+    /// Inspect the messages of a channel, or of all channels
+    Messages(Option<String>),
 }
 
 impl ReplCommand {
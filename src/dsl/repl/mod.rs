@@ -718,6 +718,7 @@ impl ReplSession {
                 "inspect task_id        # Inspect task details".to_string(),
                 "inspect state          # Inspect workflow state".to_string(),
                 "inspect effects        # Inspect side effects".to_string(),
+                "inspect messages       # Inspect channel transcripts".to_string(),
             ],
             "goto" | "back" | "forward" => {
                 vec![format!("{} 3                   # Jump to position 3", name)]
@@ -1000,6 +1001,34 @@ impl ReplSession {
                     println!("  Steps: {}", pos.step_count);
                 }
             }
+
+            InspectTarget::Messages(channel) => {
+                if let Some(inspector) = executor.inspector() {
+                    let transcripts = match channel {
+                        Some(ref name) => {
+                            inspector.inspect_channel(name).await.into_iter().collect()
+                        }
+                        None => inspector.channel_transcripts().await,
+                    };
+
+                    if transcripts.is_empty() {
+                        println!("No messages sent");
+                    }
+                    for transcript in &transcripts {
+                        println!(
+                            "Channel: {} ({})",
+                            transcript.name,
+                            transcript.messages.len()
+                        );
+                        for message in &transcript.messages {
+                            println!(
+                                "  {} [{}]: {}",
+                                message.from, message.message_type, message.payload
+                            );
+                        }
+                    }
+                }
+            }
         }
 
         Ok(())
@@ -345,7 +345,7 @@ fn parse_task_status(status_str: &str) -> Result<TaskStatus> {
 fn parse_inspect_command(args: &[&str]) -> Result<ReplCommand> {
     if args.is_empty() {
         return Err(Error::InvalidInput(
-            "Usage: inspect <task|var|state|effects|position>".to_string(),
+            "Usage: inspect <task|var|state|effects|position|messages>".to_string(),
         ));
     }
 
@@ -353,6 +353,7 @@ fn parse_inspect_command(args: &[&str]) -> Result<ReplCommand> {
         "state" => InspectTarget::State,
         "effects" | "side" | "sideeffects" => InspectTarget::SideEffects,
         "position" | "pos" => InspectTarget::Position,
+        "messages" | "msgs" => InspectTarget::Messages(args.get(1).map(|s| s.to_string())),
         "task" => {
             if args.len() < 2 {
                 return Err(Error::InvalidInput(
@@ -516,6 +517,14 @@ mod tests {
                 target: InspectTarget::State
             }
         ));
+
+        let cmd = parse_command("inspect messages updates").unwrap();
+        assert!(matches!(
+            cmd,
+            ReplCommand::Inspect {
+                target: InspectTarget::Messages(Some(ref channel))
+            } if channel == "updates"
+        ));
     }
 
     #[test]
@@ -3,7 +3,7 @@
 //! This module defines the type structures for the DSL, including agents, tasks,
 //! workflows, tools, and communication protocols.
 
-use crate::domain::Provider;
+use crate::domain::{AuthStyle, Provider};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
@@ -26,6 +26,9 @@ pub struct DSLWorkflow {
     /// Default model for the workflow (can be overridden per agent)
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub model: Option<String>,
+    /// Named provider profiles, referenced by agents and `llm` tasks with `profile`
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub providers: HashMap<String, ProviderProfile>,
     /// Working directory for all agents (can be overridden per agent)
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub cwd: Option<String>,
@@ -71,6 +74,9 @@ pub struct DSLWorkflow {
     /// Stdio and context management limits
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub limits: Option<LimitsConfig>,
+    /// Maximum number of tasks running at the same time (unbounded when unset)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_concurrency: Option<usize>,
 }
 
 /// Agent specification
@@ -84,6 +90,9 @@ pub struct AgentSpec {
     /// Model to use (e.g., "claude-sonnet-4-5" or "gpt-5-codex")
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub model: Option<String>,
+    /// Provider profile from the workflow's `providers` (overrides `provider`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub profile: Option<String>,
     /// System prompt
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub system_prompt: Option<String>,
@@ -108,6 +117,35 @@ pub struct AgentSpec {
     /// Maximum number of turns
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub max_turns: Option<u32>,
+    /// Maximum number of this agent's tasks running at the same time
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_concurrency: Option<usize>,
+}
+
+/// A named provider configuration, shared by the agents and `llm` tasks that
+/// reference it
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ProviderProfile {
+    /// Provider kind (e.g., openai_compatible, azure_openai, ollama, claude)
+    pub provider: Provider,
+    /// API endpoint URL (required for openai_compatible and azure_openai)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub endpoint: Option<String>,
+    /// API key (can reference secrets via ${secret.name})
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub api_key: Option<String>,
+    /// How the API key is sent: bearer, api_key or none (default depends on provider)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub auth: Option<AuthStyle>,
+    /// Header carrying the key when `auth` is api_key (default: api-key)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub auth_header: Option<String>,
+    /// Azure OpenAI API version
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub api_version: Option<String>,
+    /// Default model (the deployment name for azure_openai)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
 }
 
 /// Permission specification
@@ -196,6 +234,23 @@ pub enum ConditionSpec {
     },
 }
 
+impl ConditionSpec {
+    /// Source text of every `expression` condition, at any depth
+    pub fn expressions(&self) -> Vec<&str> {
+        match self {
+            ConditionSpec::Single(Condition::Expression { expression }) => vec![expression],
+            ConditionSpec::Single(_) => Vec::new(),
+            ConditionSpec::And { and: conditions } | ConditionSpec::Or { or: conditions } => {
+                conditions
+                    .iter()
+                    .flat_map(ConditionSpec::expressions)
+                    .collect()
+            }
+            ConditionSpec::Not { not } => not.expressions(),
+        }
+    }
+}
+
 /// Individual condition
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "type", rename_all = "snake_case")]
@@ -212,6 +267,8 @@ pub enum Condition {
     },
     /// Check if a state variable exists
     StateExists { key: String },
+    /// Evaluate an expression over task outputs, state, inputs and loop variables
+    Expression { expression: String },
     /// Always true (useful for testing)
     Always,
     /// Always false (skip task)
@@ -318,12 +375,60 @@ pub struct TaskSpec {
     /// Inject workflow execution context (completed tasks, results, etc.) into agent-based tasks
     #[serde(default, skip_serializing_if = "is_false")]
     pub inject_context: bool,
+    /// Conversation an agent-based task runs in (defaults to the agent's shared session)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub session: Option<SessionSpec>,
+    /// Channels the task reads, and where it sends its result
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub messages: Option<TaskMessagesSpec>,
     /// Task-level limits override (overrides workflow-level limits)
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub limits: Option<LimitsConfig>,
     /// Context injection control for this specific task
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub context: Option<ContextConfig>,
+    /// Retries when the task result does not match its output schema (default: 2)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub schema_retries: Option<u32>,
+}
+
+/// Conversation an agent-based task runs in
+///
+/// Written as `new`, `continue`, `fork`, or any other string naming a session
+/// shared by the tasks that use the name.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(from = "String", into = "String")]
+pub enum SessionSpec {
+    /// Start a fresh conversation
+    New,
+    /// Continue the agent's most recent conversation
+    Continue,
+    /// Branch off the agent's most recent conversation, leaving it unchanged
+    Fork,
+    /// Continue the conversation of the same name, starting it if needed
+    Named(String),
+}
+
+impl From<String> for SessionSpec {
+    fn from(value: String) -> Self {
+        match value.as_str() {
+            "new" => Self::New,
+            "continue" => Self::Continue,
+            "fork" => Self::Fork,
+            _ => Self::Named(value),
+        }
+    }
+}
+
+impl From<SessionSpec> for String {
+    fn from(spec: SessionSpec) -> Self {
+        match spec {
+            SessionSpec::New => "new".to_string(),
+            SessionSpec::Continue => "continue".to_string(),
+            SessionSpec::Fork => "fork".to_string(),
+            SessionSpec::Named(name) => name,
+        }
+    }
 }
 
 impl TaskSpec {
@@ -338,6 +443,22 @@ impl TaskSpec {
         }
     }
 
+    /// Conditions of the task itself: its `condition`, loop controls and
+    /// `while`/`repeat_until` loop (not those of its subtasks)
+    pub fn conditions(&self) -> Vec<&ConditionSpec> {
+        let mut conditions: Vec<&ConditionSpec> = self.condition.iter().collect();
+        if let Some(loop_control) = &self.loop_control {
+            conditions.extend(&loop_control.break_condition);
+            conditions.extend(&loop_control.continue_condition);
+        }
+        if let Some(LoopSpec::While { condition, .. } | LoopSpec::RepeatUntil { condition, .. }) =
+            &self.loop_spec
+        {
+            conditions.push(condition);
+        }
+        conditions
+    }
+
     /// Check if this task uses any execution type
     pub fn has_execution_type(&self) -> bool {
         self.agent.is_some()
@@ -763,6 +884,47 @@ pub struct MessageTypeSpec {
     pub schema: serde_json::Value,
 }
 
+/// Message bus access of a task
+///
+/// Agents read and send messages with their `send_message` and
+/// `read_messages` tools; other tasks declare them here.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TaskMessagesSpec {
+    /// Participant the task reads and sends as (defaults to the task's agent,
+    /// or else the task ID)
+    #[serde(default, rename = "as", skip_serializing_if = "Option::is_none")]
+    pub participant: Option<String>,
+    /// Channels whose messages are passed in the `messages.<channel>` input
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub read: Vec<String>,
+    /// Message sent with the task's result once it succeeds
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub publish: Option<PublishSpec>,
+}
+
+impl TaskMessagesSpec {
+    /// Participant a task reads and sends as
+    pub fn participant_of<'a>(&'a self, task_id: &'a str, spec: &'a TaskSpec) -> &'a str {
+        self.participant
+            .as_deref()
+            .or(spec.agent.as_deref())
+            .unwrap_or(task_id)
+    }
+}
+
+/// Where a task publishes its result
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PublishSpec {
+    /// Channel to send to
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub channel: Option<String>,
+    /// Agent to send a direct message to, instead of a channel
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub to: Option<String>,
+    /// Type of the message, checked against its declared schema
+    pub message_type: String,
+}
+
 /// MCP server specification
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct McpServerSpec {
@@ -1034,6 +1196,9 @@ pub struct OutputSpec {
     /// Description of the output
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub description: Option<String>,
+    /// JSON Schema the output value must satisfy
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub schema: Option<serde_json::Value>,
 }
 
 /// Output data source
@@ -1086,6 +1251,51 @@ pub enum SecretSource {
         /// Secret value
         value: String,
     },
+    /// Output of an external command (e.g. `pass`, `op read`, `vault kv get`)
+    Command {
+        /// Executable to run
+        command: String,
+        /// Command arguments
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        args: Vec<String>,
+    },
+    /// Entry of an encrypted local secrets file
+    Encrypted {
+        /// Path to the encrypted secrets file
+        path: String,
+        /// Entry to read (defaults to the secret's name)
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        key: Option<String>,
+        /// Environment variable holding the passphrase
+        /// (defaults to `PERIPLON_SECRETS_PASSPHRASE`)
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        passphrase_env: Option<String>,
+    },
+    /// Custom provider registered with the executor
+    Provider {
+        /// Name the provider was registered under
+        provider: String,
+        /// Key to look up (defaults to the secret's name)
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        key: Option<String>,
+        /// Provider-specific options
+        #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+        options: HashMap<String, String>,
+    },
+}
+
+impl SecretSource {
+    /// Name of the provider that resolves this source
+    pub fn provider_name(&self) -> &str {
+        match self {
+            SecretSource::Env { .. } => "env",
+            SecretSource::File { .. } => "file",
+            SecretSource::Value { .. } => "value",
+            SecretSource::Command { .. } => "command",
+            SecretSource::Encrypted { .. } => "encrypted",
+            SecretSource::Provider { provider, .. } => provider,
+        }
+    }
 }
 
 /// Script execution specification
@@ -1243,9 +1453,15 @@ pub struct McpToolSpec {
 /// LLM invocation specification for direct API calls
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct LlmSpec {
-    /// LLM provider (ollama, openai, anthropic, google)
+    /// LLM provider (ollama, openai, anthropic, google, openai_compatible, azure_openai)
+    #[serde(default)]
     pub provider: Provider,
+    /// Provider profile from the workflow's `providers`, supplying the provider
+    /// and any connection settings or model left unset here
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub profile: Option<String>,
     /// Model name (e.g., "gpt-4o", "claude-3-5-sonnet-20241022", "llama3.3")
+    #[serde(default, skip_serializing_if = "String::is_empty")]
     pub model: String,
     /// User prompt/query (supports variable interpolation)
     pub prompt: String,
@@ -1259,6 +1475,15 @@ pub struct LlmSpec {
     /// If not provided, will try to use environment variable for the provider
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub api_key: Option<String>,
+    /// How the API key is sent: bearer, api_key or none (default depends on provider)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub auth: Option<AuthStyle>,
+    /// Header carrying the key when `auth` is api_key (default: api-key)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub auth_header: Option<String>,
+    /// Azure OpenAI API version
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub api_version: Option<String>,
     /// Temperature for sampling (0.0 to 2.0, default depends on provider)
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub temperature: Option<f64>,
@@ -1283,6 +1508,85 @@ pub struct LlmSpec {
     /// Whether to stream the response (default: false)
     #[serde(default, skip_serializing_if = "is_false")]
     pub stream: bool,
+    /// JSON Schema the response must satisfy (uses the provider's structured output mode)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub schema: Option<serde_json::Value>,
+    /// Tools the model may call while answering
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tools: Vec<LlmToolSpec>,
+    /// Maximum tool-calling rounds (default: 10)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_tool_iterations: Option<u32>,
+    /// Permissions for the built-in file and shell tools
+    #[serde(default, skip_serializing_if = "is_default_permissions")]
+    pub permissions: PermissionsSpec,
+}
+
+impl LlmSpec {
+    /// The spec with its provider taken from a profile, and the connection
+    /// settings and model it leaves unset filled in from the profile
+    pub fn with_profile(&self, profile: &ProviderProfile) -> LlmSpec {
+        let mut spec = self.clone();
+        spec.provider = profile.provider.clone();
+        if spec.model.is_empty() {
+            spec.model = profile.model.clone().unwrap_or_default();
+        }
+        spec.endpoint = spec.endpoint.or_else(|| profile.endpoint.clone());
+        spec.api_key = spec.api_key.or_else(|| profile.api_key.clone());
+        spec.auth = spec.auth.or(profile.auth);
+        spec.auth_header = spec.auth_header.or_else(|| profile.auth_header.clone());
+        spec.api_version = spec.api_version.or_else(|| profile.api_version.clone());
+        spec
+    }
+}
+
+/// A tool offered to the model of an `llm` task
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum LlmToolSpec {
+    /// Tools of a server declared in `mcp_servers`
+    Mcp {
+        /// MCP server name
+        server: String,
+        /// Tools to expose (all tools of the server when empty)
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        tools: Vec<String>,
+    },
+    /// Another task, run with the model's arguments as its inputs
+    ///
+    /// Tasks used as tools only run when the model calls them.
+    Task {
+        /// Task ID
+        task: String,
+        /// Tool name (defaults to the task ID)
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        name: Option<String>,
+        /// Tool description (defaults to the task description)
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        description: Option<String>,
+        /// JSON Schema of the arguments (defaults to the task's inputs)
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        parameters: Option<serde_json::Value>,
+    },
+    /// A built-in tool
+    Builtin {
+        /// Which tool
+        name: BuiltinTool,
+    },
+}
+
+/// Built-in tools for `llm` tasks, gated by the task's `permissions`
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum BuiltinTool {
+    /// Read a text file
+    ReadFile,
+    /// List the entries of a directory
+    ListDirectory,
+    /// Create or overwrite a file (requires `acceptEdits` or `bypassPermissions`)
+    WriteFile,
+    /// Run a shell command (requires `bypassPermissions`)
+    Shell,
 }
 
 // ============================================================================
@@ -1775,6 +2079,10 @@ pub struct LimitsConfig {
     /// Cleanup strategy for context pruning
     #[serde(default = "default_cleanup_strategy")]
     pub cleanup_strategy: CleanupStrategy,
+
+    /// Token and cost limits of agent and LLM calls
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub budget: Option<BudgetConfig>,
 }
 
 impl Default for LimitsConfig {
@@ -1790,6 +2098,7 @@ impl Default for LimitsConfig {
             external_storage_dir: ".workflow_state/task_outputs".to_string(),
             compress_external: true,
             cleanup_strategy: CleanupStrategy::MostRecent { keep_count: 20 },
+            budget: None,
         }
     }
 }
@@ -1826,6 +2135,45 @@ fn default_cleanup_strategy() -> CleanupStrategy {
     CleanupStrategy::MostRecent { keep_count: 20 }
 }
 
+/// Token and cost limits of a workflow run
+///
+/// Usage is reported by agents and LLM providers once a call completes, so
+/// limits are checked after each task and a run can overshoot them by the
+/// usage of the tasks running at the time.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct BudgetConfig {
+    /// Maximum tokens of one task, over all its attempts and iterations
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_task_tokens: Option<u64>,
+
+    /// Maximum cost in USD of one task
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_task_usd: Option<f64>,
+
+    /// Maximum tokens of the whole workflow, including its subflows
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_workflow_tokens: Option<u64>,
+
+    /// Maximum cost in USD of the whole workflow
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_workflow_usd: Option<f64>,
+
+    /// What happens to the workflow when a limit is exceeded
+    #[serde(default)]
+    pub on_exceeded: BudgetAction,
+}
+
+/// Action taken when a budget limit is exceeded
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum BudgetAction {
+    /// Fail the workflow
+    #[default]
+    Abort,
+    /// Pause the workflow so it can be resumed, e.g. with a raised budget
+    Pause,
+}
+
 /// Truncation strategy for output capture
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 #[serde(rename_all = "snake_case")]
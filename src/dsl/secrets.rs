@@ -0,0 +1,731 @@
+//! Workflow Secrets
+//!
+//! This module resolves the secrets declared in a workflow's `secrets:` section
+//! before execution starts and keeps their values out of everything the
+//! executor records.
+//!
+//! Each [`SecretSource`] is resolved by the [`SecretProvider`] registered under
+//! its type in a [`SecretResolver`]:
+//!
+//! | `type` | Provider |
+//! |--------|----------|
+//! | `env` | Environment variable |
+//! | `file` | File contents, without the trailing newline |
+//! | `value` | Inline value |
+//! | `command` | Standard output of a command, without the trailing newline |
+//! | `encrypted` | Entry of a local secrets file encrypted with [`encrypt_secrets`] |
+//! | `provider` | Custom provider registered with [`SecretResolver::register`] |
+//!
+//! The resolved values live in a [`SecretStore`], which substitutes
+//! `${secret.name}` references and replaces the values with [`REDACTED`] in
+//! task outputs, errors, notification messages and agent messages.
+
+use crate::domain::{Message, SystemMessage};
+use crate::dsl::schema::{SecretSource, SecretSpec};
+use crate::error::{Error, Result};
+use aes_gcm::aead::Aead;
+use aes_gcm::{Aes256Gcm, KeyInit, Nonce};
+use async_trait::async_trait;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use rand::RngCore;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, RwLock};
+
+/// Replacement for secret values in recorded output
+pub const REDACTED: &str = "[REDACTED]";
+
+/// Shortest secret value that is redacted
+///
+/// Shorter values would mangle unrelated output.
+pub const MIN_REDACTED_LEN: usize = 4;
+
+/// Environment variable holding the passphrase of encrypted secrets files
+pub const DEFAULT_PASSPHRASE_ENV: &str = "PERIPLON_SECRETS_PASSPHRASE";
+
+/// PBKDF2 rounds used when encrypting a secrets file
+const KDF_ITERATIONS: u32 = 100_000;
+
+/// Resolves secret values from one kind of source
+#[async_trait]
+pub trait SecretProvider: Send + Sync {
+    /// Resolve the value of the secret `name` declared with `source`
+    async fn resolve(&self, name: &str, source: &SecretSource) -> Result<String>;
+}
+
+/// Error for a source handed to the wrong provider
+fn unsupported_source(provider: &str, source: &SecretSource) -> Error {
+    Error::InvalidInput(format!(
+        "Secret provider '{}' cannot resolve '{}' sources",
+        provider,
+        source.provider_name()
+    ))
+}
+
+/// Reads secrets from environment variables
+pub struct EnvSecretProvider;
+
+#[async_trait]
+impl SecretProvider for EnvSecretProvider {
+    async fn resolve(&self, name: &str, source: &SecretSource) -> Result<String> {
+        let SecretSource::Env { var } = source else {
+            return Err(unsupported_source("env", source));
+        };
+        std::env::var(var).map_err(|_| {
+            Error::InvalidInput(format!(
+                "Secret '{}' references environment variable '{}' which is not set",
+                name, var
+            ))
+        })
+    }
+}
+
+/// Reads secrets from files
+pub struct FileSecretProvider;
+
+#[async_trait]
+impl SecretProvider for FileSecretProvider {
+    async fn resolve(&self, name: &str, source: &SecretSource) -> Result<String> {
+        let SecretSource::File { path } = source else {
+            return Err(unsupported_source("file", source));
+        };
+        let path = shellexpand::tilde(path);
+        tokio::fs::read_to_string(path.as_ref())
+            .await
+            .map(|content| content.trim_end().to_string())
+            .map_err(|e| {
+                Error::InvalidInput(format!(
+                    "Failed to read secret '{}' from '{}': {}",
+                    name, path, e
+                ))
+            })
+    }
+}
+
+/// Returns inline secret values
+pub struct ValueSecretProvider;
+
+#[async_trait]
+impl SecretProvider for ValueSecretProvider {
+    async fn resolve(&self, _name: &str, source: &SecretSource) -> Result<String> {
+        match source {
+            SecretSource::Value { value } => Ok(value.clone()),
+            _ => Err(unsupported_source("value", source)),
+        }
+    }
+}
+
+/// Runs a command and returns its standard output
+///
+/// Works with password managers and vaults that have a CLI, such as
+/// `pass show`, `op read` or `vault kv get -field=...`.
+pub struct CommandSecretProvider;
+
+#[async_trait]
+impl SecretProvider for CommandSecretProvider {
+    async fn resolve(&self, name: &str, source: &SecretSource) -> Result<String> {
+        let SecretSource::Command { command, args } = source else {
+            return Err(unsupported_source("command", source));
+        };
+        let output = tokio::process::Command::new(command)
+            .args(args)
+            .stdin(std::process::Stdio::null())
+            .output()
+            .await
+            .map_err(|e| {
+                Error::InvalidInput(format!(
+                    "Failed to run command '{}' for secret '{}': {}",
+                    command, name, e
+                ))
+            })?;
+
+        if !output.status.success() {
+            return Err(Error::InvalidInput(format!(
+                "Command '{}' for secret '{}' failed with exit code {:?}: {}",
+                command,
+                name,
+                output.status.code(),
+                String::from_utf8_lossy(&output.stderr).trim()
+            )));
+        }
+
+        Ok(String::from_utf8_lossy(&output.stdout)
+            .trim_end()
+            .to_string())
+    }
+}
+
+/// Reads entries of encrypted secrets files
+///
+/// Each file is decrypted once and its entries are cached for later secrets.
+#[derive(Default)]
+pub struct EncryptedSecretProvider {
+    files: Mutex<HashMap<String, Arc<HashMap<String, String>>>>,
+}
+
+impl EncryptedSecretProvider {
+    /// Create a provider with an empty cache
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl SecretProvider for EncryptedSecretProvider {
+    async fn resolve(&self, name: &str, source: &SecretSource) -> Result<String> {
+        let SecretSource::Encrypted {
+            path,
+            key,
+            passphrase_env,
+        } = source
+        else {
+            return Err(unsupported_source("encrypted", source));
+        };
+
+        let cached = self.files.lock().unwrap().get(path).cloned();
+        let entries = match cached {
+            Some(entries) => entries,
+            None => {
+                let passphrase_env = passphrase_env.as_deref().unwrap_or(DEFAULT_PASSPHRASE_ENV);
+                let passphrase = std::env::var(passphrase_env).map_err(|_| {
+                    Error::InvalidInput(format!(
+                        "Secret '{}' needs the passphrase of '{}' in environment variable '{}', which is not set",
+                        name, path, passphrase_env
+                    ))
+                })?;
+                let content = tokio::fs::read_to_string(shellexpand::tilde(path).as_ref())
+                    .await
+                    .map_err(|e| {
+                        Error::InvalidInput(format!(
+                            "Failed to read secrets file '{}': {}",
+                            path, e
+                        ))
+                    })?;
+                let entries = Arc::new(decrypt_entries(&content, &passphrase).map_err(|e| {
+                    Error::InvalidInput(format!("Failed to decrypt secrets file '{}': {}", path, e))
+                })?);
+                self.files
+                    .lock()
+                    .unwrap()
+                    .insert(path.clone(), entries.clone());
+                entries
+            }
+        };
+
+        let key = key.as_deref().unwrap_or(name);
+        entries.get(key).cloned().ok_or_else(|| {
+            Error::InvalidInput(format!(
+                "Secrets file '{}' has no entry '{}' (needed by secret '{}')",
+                path, key, name
+            ))
+        })
+    }
+}
+
+/// On-disk format of an encrypted secrets file
+#[derive(Serialize, Deserialize)]
+struct EncryptedSecretsFile {
+    version: u32,
+    iterations: u32,
+    salt: String,
+    nonce: String,
+    ciphertext: String,
+}
+
+/// Derive the AES-256 key for a passphrase with PBKDF2-HMAC-SHA256
+fn derive_key(passphrase: &str, salt: &[u8], iterations: u32) -> Aes256Gcm {
+    let key =
+        pbkdf2::pbkdf2_hmac_array::<sha2::Sha256, 32>(passphrase.as_bytes(), salt, iterations);
+    Aes256Gcm::new(&key.into())
+}
+
+/// Encrypt secrets into the format read by `encrypted` secret sources
+///
+/// The entries are serialized as a JSON object and encrypted with AES-256-GCM
+/// under a key derived from `passphrase`.
+pub fn encrypt_secrets(secrets: &HashMap<String, String>, passphrase: &str) -> Result<String> {
+    let mut salt = [0u8; 16];
+    let mut nonce = [0u8; 12];
+    rand::thread_rng().fill_bytes(&mut salt);
+    rand::thread_rng().fill_bytes(&mut nonce);
+
+    let plaintext = serde_json::to_vec(secrets)?;
+    let ciphertext = derive_key(passphrase, &salt, KDF_ITERATIONS)
+        .encrypt(&Nonce::from(nonce), plaintext.as_slice())
+        .map_err(|_| Error::InvalidInput("Failed to encrypt secrets".to_string()))?;
+
+    let file = EncryptedSecretsFile {
+        version: 1,
+        iterations: KDF_ITERATIONS,
+        salt: BASE64.encode(salt),
+        nonce: BASE64.encode(nonce),
+        ciphertext: BASE64.encode(ciphertext),
+    };
+    Ok(serde_json::to_string_pretty(&file)?)
+}
+
+/// Decrypt a secrets file written by [`encrypt_secrets`]
+pub fn decrypt_secrets(content: &str, passphrase: &str) -> Result<HashMap<String, String>> {
+    decrypt_entries(content, passphrase)
+        .map_err(|e| Error::InvalidInput(format!("Failed to decrypt secrets: {}", e)))
+}
+
+fn decrypt_entries(
+    content: &str,
+    passphrase: &str,
+) -> std::result::Result<HashMap<String, String>, String> {
+    let file: EncryptedSecretsFile = serde_json::from_str(content)
+        .map_err(|e| format!("not an encrypted secrets file ({})", e))?;
+    if file.version != 1 {
+        return Err(format!("unsupported file version {}", file.version));
+    }
+
+    let decode = |field: &str, value: &str| {
+        BASE64
+            .decode(value)
+            .map_err(|e| format!("invalid {} ({})", field, e))
+    };
+    let salt = decode("salt", &file.salt)?;
+    let nonce: [u8; 12] = decode("nonce", &file.nonce)?
+        .try_into()
+        .map_err(|_| "invalid nonce length".to_string())?;
+    let ciphertext = decode("ciphertext", &file.ciphertext)?;
+
+    let plaintext = derive_key(passphrase, &salt, file.iterations)
+        .decrypt(&Nonce::from(nonce), ciphertext.as_slice())
+        .map_err(|_| "wrong passphrase or corrupted file".to_string())?;
+    serde_json::from_slice(&plaintext).map_err(|e| format!("invalid secrets content ({})", e))
+}
+
+/// Registry of secret providers by source type
+#[derive(Clone)]
+pub struct SecretResolver {
+    providers: HashMap<String, Arc<dyn SecretProvider>>,
+}
+
+impl Default for SecretResolver {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SecretResolver {
+    /// Create a resolver with the built-in providers
+    pub fn new() -> Self {
+        let mut resolver = Self {
+            providers: HashMap::new(),
+        };
+        resolver.register("env", Arc::new(EnvSecretProvider));
+        resolver.register("file", Arc::new(FileSecretProvider));
+        resolver.register("value", Arc::new(ValueSecretProvider));
+        resolver.register("command", Arc::new(CommandSecretProvider));
+        resolver.register("encrypted", Arc::new(EncryptedSecretProvider::new()));
+        resolver
+    }
+
+    /// Register a provider, replacing any provider with the same name
+    ///
+    /// Custom providers are used by `provider` sources naming them.
+    pub fn register(&mut self, name: impl Into<String>, provider: Arc<dyn SecretProvider>) {
+        self.providers.insert(name.into(), provider);
+    }
+
+    /// Resolve one secret
+    pub async fn resolve(&self, name: &str, spec: &SecretSpec) -> Result<String> {
+        let provider_name = spec.source.provider_name();
+        let provider = self.providers.get(provider_name).ok_or_else(|| {
+            Error::InvalidInput(format!(
+                "Secret '{}' uses unknown provider '{}'",
+                name, provider_name
+            ))
+        })?;
+        provider.resolve(name, &spec.source).await
+    }
+
+    /// Resolve every declared secret
+    pub async fn resolve_all(
+        &self,
+        secrets: &HashMap<String, SecretSpec>,
+    ) -> Result<HashMap<String, String>> {
+        let mut values = HashMap::new();
+        for (name, spec) in secrets {
+            values.insert(name.clone(), self.resolve(name, spec).await?);
+        }
+        Ok(values)
+    }
+}
+
+/// Replace each of `values` in `text` with [`REDACTED`]
+///
+/// Longer values are replaced first so that a secret containing another is
+/// fully redacted. Values shorter than [`MIN_REDACTED_LEN`] are left alone.
+pub fn redact_secrets<'a>(text: &str, values: impl IntoIterator<Item = &'a str>) -> String {
+    let mut values: Vec<&str> = values
+        .into_iter()
+        .filter(|value| value.len() >= MIN_REDACTED_LEN)
+        .collect();
+    values.sort_by_key(|value| std::cmp::Reverse(value.len()));
+
+    let mut result = text.to_string();
+    for value in values {
+        if result.contains(value) {
+            result = result.replace(value, REDACTED);
+        }
+    }
+    result
+}
+
+/// Resolved secret values of a workflow run
+///
+/// Shared by the executor and the services that need the values, such as the
+/// MCP client pool. The values are loaded when the executor initializes.
+#[derive(Default)]
+pub struct SecretStore {
+    values: RwLock<HashMap<String, String>>,
+}
+
+impl std::fmt::Debug for SecretStore {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut names: Vec<String> = self.values.read().unwrap().keys().cloned().collect();
+        names.sort();
+        f.debug_struct("SecretStore")
+            .field("names", &names)
+            .finish()
+    }
+}
+
+impl SecretStore {
+    /// Create an empty store
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a store holding the given values
+    pub fn with_values(values: HashMap<String, String>) -> Self {
+        Self {
+            values: RwLock::new(values),
+        }
+    }
+
+    /// Replace the stored values
+    pub fn load(&self, values: HashMap<String, String>) {
+        *self.values.write().unwrap() = values;
+    }
+
+    /// Value of a secret
+    pub fn get(&self, name: &str) -> Option<String> {
+        self.values.read().unwrap().get(name).cloned()
+    }
+
+    /// All secret values by name
+    pub fn values(&self) -> HashMap<String, String> {
+        self.values.read().unwrap().clone()
+    }
+
+    /// Whether no secrets are stored
+    pub fn is_empty(&self) -> bool {
+        self.values.read().unwrap().is_empty()
+    }
+
+    /// Replace `${secret.name}` references with their values
+    ///
+    /// Fails when a referenced secret is not declared by the workflow.
+    pub fn substitute(&self, text: &str) -> Result<String> {
+        if !text.contains("${secret.") {
+            return Ok(text.to_string());
+        }
+
+        let re = Regex::new(r"\$\{secret\.([a-zA-Z0-9_-]+)\}")
+            .map_err(|e| Error::InvalidInput(format!("Invalid secret pattern: {}", e)))?;
+        let values = self.values.read().unwrap();
+
+        let mut result = String::with_capacity(text.len());
+        let mut last_match = 0;
+        for caps in re.captures_iter(text) {
+            let full_match = caps.get(0).unwrap();
+            let name = &caps[1];
+            let value = values.get(name).ok_or_else(|| {
+                Error::InvalidInput(format!("Secret '{}' is not defined in workflow", name))
+            })?;
+            result.push_str(&text[last_match..full_match.start()]);
+            result.push_str(value);
+            last_match = full_match.end();
+        }
+        result.push_str(&text[last_match..]);
+
+        Ok(result)
+    }
+
+    /// Replace secret values in `text` with [`REDACTED`]
+    pub fn redact(&self, text: &str) -> String {
+        let values = self.values.read().unwrap();
+        if values.is_empty() {
+            return text.to_string();
+        }
+        redact_secrets(text, values.values().map(String::as_str))
+    }
+
+    /// Redact secret values in every string of a JSON value
+    pub fn redact_value(&self, value: &Value) -> Value {
+        match value {
+            Value::String(text) => Value::String(self.redact(text)),
+            Value::Array(items) => {
+                Value::Array(items.iter().map(|item| self.redact_value(item)).collect())
+            }
+            Value::Object(map) => Value::Object(
+                map.iter()
+                    .map(|(key, item)| (key.clone(), self.redact_value(item)))
+                    .collect(),
+            ),
+            other => other.clone(),
+        }
+    }
+
+    /// Redact secret values in an agent message
+    ///
+    /// Covers every string of the message, including tool inputs and results.
+    pub fn redact_message(&self, message: Message) -> Message {
+        if self.is_empty() {
+            return message;
+        }
+        let Ok(value) = serde_json::to_value(&message) else {
+            return message;
+        };
+        let redacted = self.redact_value(&value);
+        if redacted == value {
+            return message;
+        }
+        serde_json::from_value(redacted.clone()).unwrap_or_else(|_| {
+            Message::System(SystemMessage {
+                subtype: "redacted".to_string(),
+                data: redacted,
+            })
+        })
+    }
+
+    /// Redact secret values in an error message
+    ///
+    /// Errors without secret values are returned unchanged.
+    pub fn redact_error(&self, error: Error) -> Error {
+        let message = error.to_string();
+        let redacted = self.redact(&message);
+        if redacted == message {
+            error
+        } else {
+            Error::InvalidInput(redacted)
+        }
+    }
+
+    /// Redact text that arrives in pieces, such as streamed LLM tokens
+    pub fn stream_redactor(&self) -> StreamRedactor<'_> {
+        StreamRedactor {
+            store: self,
+            pending: String::new(),
+        }
+    }
+}
+
+/// Redacts secret values from text arriving in pieces
+///
+/// A secret may be split across pieces, so the end of the text seen so far is
+/// held back until it can no longer be the start of a secret.
+pub struct StreamRedactor<'a> {
+    store: &'a SecretStore,
+    pending: String,
+}
+
+impl StreamRedactor<'_> {
+    /// Add a piece and return the redacted text that is safe to show
+    pub fn push(&mut self, text: &str) -> String {
+        self.pending.push_str(text);
+
+        let values = self.store.values.read().unwrap();
+        let values: Vec<&str> = values
+            .values()
+            .map(String::as_str)
+            .filter(|value| value.len() >= MIN_REDACTED_LEN)
+            .collect();
+        let Some(longest) = values.iter().map(|value| value.len()).max() else {
+            return std::mem::take(&mut self.pending);
+        };
+
+        // Hold back what may be an incomplete secret, and any secret
+        // crossing the cut
+        let mut cut = self.pending.len().saturating_sub(longest - 1);
+        loop {
+            let crossing = values
+                .iter()
+                .flat_map(|value| {
+                    self.pending
+                        .match_indices(value)
+                        .map(|(start, value)| (start, start + value.len()))
+                })
+                .filter(|(start, end)| *start < cut && *end > cut)
+                .map(|(start, _)| start)
+                .min();
+            match crossing {
+                Some(start) => cut = start,
+                None => break,
+            }
+        }
+        while !self.pending.is_char_boundary(cut) {
+            cut -= 1;
+        }
+
+        let shown: String = self.pending.drain(..cut).collect();
+        redact_secrets(&shown, values)
+    }
+
+    /// Return the redacted rest of the text
+    pub fn finish(self) -> String {
+        self.store.redact(&self.pending)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_substitute_and_redact() {
+        let store = SecretStore::with_values(HashMap::from([
+            ("token".to_string(), "abcd1234".to_string()),
+            ("pin".to_string(), "42".to_string()),
+        ]));
+
+        assert_eq!(
+            store.substitute("Bearer ${secret.token}").unwrap(),
+            "Bearer abcd1234"
+        );
+        let err = store.substitute("${secret.missing}").unwrap_err();
+        assert!(err.to_string().contains("Secret 'missing' is not defined"));
+
+        assert_eq!(
+            store.redact("token=abcd1234, pin=42"),
+            "token=[REDACTED], pin=42"
+        );
+        assert_eq!(
+            store.redact_value(&serde_json::json!({"headers": ["abcd1234"], "n": 1})),
+            serde_json::json!({"headers": ["[REDACTED]"], "n": 1})
+        );
+    }
+
+    #[test]
+    fn test_stream_redactor_catches_split_secrets() {
+        let store = SecretStore::with_values(HashMap::from([
+            ("token".to_string(), "abcd1234".to_string()),
+            ("key".to_string(), "1234wxyz".to_string()),
+        ]));
+
+        let mut redactor = store.stream_redactor();
+        let mut shown = String::new();
+        for piece in ["Bearer ab", "cd12", "34 and 12", "34w", "xyz", " done"] {
+            shown.push_str(&redactor.push(piece));
+        }
+        shown.push_str(&redactor.finish());
+        assert_eq!(shown, "Bearer [REDACTED] and [REDACTED] done");
+
+        // Without secrets pieces are shown as they arrive
+        let empty = SecretStore::new();
+        let mut redactor = empty.stream_redactor();
+        assert_eq!(redactor.push("Bearer ab"), "Bearer ab");
+        assert_eq!(redactor.finish(), "");
+    }
+
+    #[test]
+    fn test_redact_agent_message() {
+        let store = SecretStore::with_values(HashMap::from([(
+            "token".to_string(),
+            "abcd1234".to_string(),
+        )]));
+
+        // An agent echoing a secret in its text and in a tool call
+        let message: Message = serde_json::from_value(serde_json::json!({
+            "type": "assistant",
+            "message": {
+                "model": "claude-sonnet-4-5",
+                "content": [
+                    {"type": "text", "text": "The token is abcd1234"},
+                    {"type": "tool_use", "id": "t1", "name": "Bash",
+                     "input": {"command": "curl -H 'Authorization: abcd1234' example.com"}}
+                ]
+            }
+        }))
+        .unwrap();
+
+        let redacted = store.redact_message(message);
+        let json = serde_json::to_string(&redacted).unwrap();
+        assert!(!json.contains("abcd1234"));
+        assert_eq!(
+            redacted.display_text().unwrap(),
+            "The token is [REDACTED]\nUsing tool: Bash"
+        );
+    }
+
+    #[test]
+    fn test_encrypted_round_trip() {
+        let secrets = HashMap::from([("api_key".to_string(), "sk-test".to_string())]);
+        let content = encrypt_secrets(&secrets, "correct horse").unwrap();
+        assert!(!content.contains("sk-test"));
+
+        assert_eq!(decrypt_secrets(&content, "correct horse").unwrap(), secrets);
+        let err = decrypt_secrets(&content, "wrong").unwrap_err();
+        assert!(err.to_string().contains("wrong passphrase"));
+    }
+
+    #[tokio::test]
+    async fn test_resolver_uses_registered_providers() {
+        struct Vault;
+
+        #[async_trait]
+        impl SecretProvider for Vault {
+            async fn resolve(&self, name: &str, source: &SecretSource) -> Result<String> {
+                match source {
+                    SecretSource::Provider { key, .. } => {
+                        Ok(format!("vault:{}", key.as_deref().unwrap_or(name)))
+                    }
+                    _ => Err(unsupported_source("vault", source)),
+                }
+            }
+        }
+
+        let mut resolver = SecretResolver::new();
+        resolver.register("vault", Arc::new(Vault));
+        let spec = |source| SecretSpec {
+            source,
+            description: None,
+        };
+
+        let secrets = HashMap::from([
+            (
+                "db".to_string(),
+                spec(SecretSource::Provider {
+                    provider: "vault".to_string(),
+                    key: Some("prod/db".to_string()),
+                    options: HashMap::new(),
+                }),
+            ),
+            (
+                "greeting".to_string(),
+                spec(SecretSource::Command {
+                    command: "echo".to_string(),
+                    args: vec!["hello".to_string()],
+                }),
+            ),
+        ]);
+        let values = resolver.resolve_all(&secrets).await.unwrap();
+        assert_eq!(values["db"], "vault:prod/db");
+        assert_eq!(values["greeting"], "hello");
+
+        let unknown = spec(SecretSource::Provider {
+            provider: "nope".to_string(),
+            key: None,
+            options: HashMap::new(),
+        });
+        let err = resolver.resolve("x", &unknown).await.unwrap_err();
+        assert!(err.to_string().contains("unknown provider 'nope'"));
+    }
+}
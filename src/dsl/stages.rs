@@ -0,0 +1,432 @@
+//! Workflow Stage Orchestration
+//!
+//! This module turns the stages declared under `workflows:` into ordering
+//! constraints on the task graph. A stage starts once the stages it depends on
+//! have finished, tasks of a sequential stage run one after another and tasks
+//! of a parallel stage run concurrently. The executor reports a stage (and runs
+//! its `on_stage_complete` hooks) once all of its tasks have finished.
+
+use crate::dsl::schema::{DSLWorkflow, ExecutionMode, TaskSpec};
+use crate::dsl::task_graph::{TaskGraph, TaskStatus};
+use crate::error::{Error, Result};
+use std::collections::{HashMap, HashSet};
+
+/// A stage of a `workflows:` entry, resolved against the task graph
+#[derive(Debug, Clone)]
+pub struct PlannedStage {
+    /// Stage key (`<workflow>.<stage>`) used to track progress in workflow state
+    pub id: String,
+    /// Name of the `workflows:` entry declaring the stage
+    pub workflow: String,
+    /// Stage name
+    pub name: String,
+    /// Keys of the stages this stage waits for
+    pub depends_on: Vec<String>,
+    /// How the stage's tasks are run
+    pub mode: ExecutionMode,
+    /// Task graph ids of the stage's tasks, in declaration order
+    pub tasks: Vec<String>,
+}
+
+/// Stages of every `workflows:` entry, in execution order
+#[derive(Debug, Clone, Default)]
+pub struct StagePlan {
+    stages: Vec<PlannedStage>,
+}
+
+impl StagePlan {
+    /// Build the plan for a workflow's stages
+    ///
+    /// Entries are processed in name order and the stages of each entry are
+    /// ordered by their `depends_on`. Task names are those declared in the
+    /// stage; call [`StagePlan::apply_to_graph`] to resolve them.
+    pub fn from_workflow(workflow: &DSLWorkflow) -> Result<Self> {
+        let mut names: Vec<&String> = workflow.workflows.keys().collect();
+        names.sort();
+
+        let mut stages = Vec::new();
+        for workflow_name in names {
+            let spec = &workflow.workflows[workflow_name];
+            let declared: HashMap<&str, _> = spec
+                .steps
+                .iter()
+                .map(|stage| (stage.stage.as_str(), stage))
+                .collect();
+
+            // Kahn's algorithm over the stage dependencies, keeping declaration order.
+            // Dependencies on undeclared stages are reported by the validator.
+            let mut remaining: Vec<_> = spec.steps.iter().collect();
+            let mut placed: HashSet<&str> = HashSet::new();
+            while !remaining.is_empty() {
+                let position = remaining.iter().position(|stage| {
+                    stage.depends_on.iter().all(|dep| {
+                        placed.contains(dep.as_str()) || !declared.contains_key(dep.as_str())
+                    })
+                });
+                let Some(position) = position else {
+                    let blocked: Vec<&str> =
+                        remaining.iter().map(|stage| stage.stage.as_str()).collect();
+                    return Err(Error::InvalidInput(format!(
+                        "Workflow '{}' has cyclic stage dependencies: {}",
+                        workflow_name,
+                        blocked.join(", ")
+                    )));
+                };
+                let stage = remaining.remove(position);
+                placed.insert(stage.stage.as_str());
+
+                stages.push(PlannedStage {
+                    id: stage_key(workflow_name, &stage.stage),
+                    workflow: workflow_name.clone(),
+                    name: stage.stage.clone(),
+                    depends_on: stage
+                        .depends_on
+                        .iter()
+                        .filter(|dep| declared.contains_key(dep.as_str()))
+                        .map(|dep| stage_key(workflow_name, dep))
+                        .collect(),
+                    mode: stage.mode.clone(),
+                    tasks: stage
+                        .tasks
+                        .iter()
+                        .flat_map(|entry| entry.keys().cloned())
+                        .collect(),
+                });
+            }
+        }
+
+        Ok(Self { stages })
+    }
+
+    /// Whether the workflow declares any stages
+    pub fn is_empty(&self) -> bool {
+        self.stages.is_empty()
+    }
+
+    /// Stages in execution order
+    pub fn stages(&self) -> &[PlannedStage] {
+        &self.stages
+    }
+
+    /// Stages that contain the given task
+    pub fn stages_of_task<'a>(
+        &'a self,
+        task_id: &'a str,
+    ) -> impl Iterator<Item = &'a PlannedStage> + 'a {
+        self.stages
+            .iter()
+            .filter(move |stage| stage.tasks.iter().any(|id| id == task_id))
+    }
+
+    /// Resolve stage tasks to graph ids and add the stage ordering to the graph
+    ///
+    /// A stage task naming a parent task stands for all of its subtasks. Every
+    /// task of a stage depends on the tasks of the stages it depends on, and in
+    /// a sequential stage each task also depends on the one declared before it.
+    pub fn apply_to_graph(&mut self, graph: &mut TaskGraph) -> Result<()> {
+        let all_tasks = graph.get_all_tasks();
+
+        // Resolve each declared task name to the graph tasks it stands for
+        let mut groups: HashMap<String, Vec<Vec<String>>> = HashMap::new();
+        for stage in &mut self.stages {
+            let mut stage_groups = Vec::new();
+            for name in &stage.tasks {
+                let ids = if graph.get_task(name).is_some() {
+                    vec![name.clone()]
+                } else {
+                    let prefix = format!("{}.", name);
+                    let mut ids: Vec<String> = all_tasks
+                        .iter()
+                        .filter(|id| id.starts_with(&prefix))
+                        .cloned()
+                        .collect();
+                    ids.sort();
+                    ids
+                };
+                if ids.is_empty() {
+                    return Err(Error::InvalidInput(format!(
+                        "Stage '{}' of workflow '{}' references unknown task '{}'",
+                        stage.name, stage.workflow, name
+                    )));
+                }
+                stage_groups.push(ids);
+            }
+            stage.tasks = stage_groups.iter().flatten().cloned().collect();
+            groups.insert(stage.id.clone(), stage_groups);
+        }
+
+        let stage_tasks: HashMap<&str, &Vec<String>> = self
+            .stages
+            .iter()
+            .map(|stage| (stage.id.as_str(), &stage.tasks))
+            .collect();
+
+        for stage in &self.stages {
+            let prerequisites: Vec<String> = stage
+                .depends_on
+                .iter()
+                .filter_map(|dep| stage_tasks.get(dep.as_str()))
+                .flat_map(|tasks| tasks.iter().cloned())
+                .collect();
+
+            let mut previous: Vec<String> = Vec::new();
+            for group in &groups[&stage.id] {
+                for task_id in group {
+                    let mut added = prerequisites.clone();
+                    if stage.mode == ExecutionMode::Sequential {
+                        added.extend(previous.iter().cloned());
+                    }
+                    add_dependencies(graph, task_id, added)?;
+                }
+                previous = group.clone();
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Stages whose tasks have all finished and whose prerequisites are reported
+    ///
+    /// `reported` holds the keys of stages already handled; stages are returned
+    /// in execution order, so a stage may follow one it depends on.
+    pub fn finished_stages(
+        &self,
+        graph: &TaskGraph,
+        reported: &HashSet<String>,
+    ) -> Vec<PlannedStage> {
+        let mut done: HashSet<&str> = reported.iter().map(|id| id.as_str()).collect();
+        let mut finished = Vec::new();
+        for stage in &self.stages {
+            if done.contains(stage.id.as_str()) {
+                continue;
+            }
+            let prerequisites_done = stage
+                .depends_on
+                .iter()
+                .all(|dep| done.contains(dep.as_str()));
+            let tasks_done = stage.tasks.iter().all(|id| {
+                matches!(
+                    graph.get_task_status(id),
+                    Some(TaskStatus::Completed | TaskStatus::Skipped)
+                )
+            });
+            if prerequisites_done && tasks_done {
+                done.insert(stage.id.as_str());
+                finished.push(stage.clone());
+            }
+        }
+        finished
+    }
+}
+
+/// Tasks defined inline in stages rather than under `tasks:`
+///
+/// A stage task whose name matches a top-level task refers to it. Other stage
+/// tasks are defined by the stage itself; when the stage involves exactly one
+/// agent, tasks without an agent or execution type run on that agent.
+pub fn inline_stage_tasks(workflow: &DSLWorkflow) -> Vec<(String, TaskSpec)> {
+    let mut names: Vec<&String> = workflow.workflows.keys().collect();
+    names.sort();
+
+    let mut inline: Vec<(String, TaskSpec)> = Vec::new();
+    for workflow_name in names {
+        for stage in &workflow.workflows[workflow_name].steps {
+            for (task_name, spec) in stage.tasks.iter().flatten() {
+                if workflow.tasks.contains_key(task_name)
+                    || inline.iter().any(|(name, _)| name == task_name)
+                {
+                    continue;
+                }
+                let mut spec = spec.clone();
+                if let [agent] = stage.agents.as_slice() {
+                    if spec.agent.is_none() && !has_execution_type(&spec) {
+                        spec.agent = Some(agent.clone());
+                    }
+                }
+                inline.push((task_name.clone(), spec));
+            }
+        }
+    }
+    inline
+}
+
+/// Key identifying a stage in workflow state
+fn stage_key(workflow: &str, stage: &str) -> String {
+    format!("{}.{}", workflow, stage)
+}
+
+fn has_execution_type(spec: &TaskSpec) -> bool {
+    spec.script.is_some()
+        || spec.command.is_some()
+        || spec.http.is_some()
+        || spec.mcp_tool.is_some()
+        || spec.llm.is_some()
+        || spec.subflow.is_some()
+        || spec.uses_workflow.is_some()
+        || spec.uses.is_some()
+        || spec.embed.is_some()
+}
+
+/// Add dependencies to a graph task, keeping the ones it already has
+fn add_dependencies(graph: &mut TaskGraph, task_id: &str, added: Vec<String>) -> Result<()> {
+    let mut dependencies = graph
+        .get_task(task_id)
+        .map(|node| node.dependencies.clone())
+        .unwrap_or_default();
+    let before = dependencies.len();
+    for dep in added {
+        if dep != task_id && !dependencies.contains(&dep) {
+            dependencies.push(dep);
+        }
+    }
+    if dependencies.len() == before {
+        return Ok(());
+    }
+    graph.update_task_dependencies(task_id, dependencies)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dsl::parser::parse_workflow;
+
+    const STAGED: &str = r#"
+name: "Staged"
+version: "1.0.0"
+tasks:
+  build:
+    description: "Build"
+    command:
+      executable: "true"
+  test:
+    description: "Test"
+    command:
+      executable: "true"
+workflows:
+  pipeline:
+    description: "Pipeline"
+    steps:
+      - stage: deploy
+        depends_on: [verify]
+        mode: parallel
+        agents: []
+        tasks:
+          - ship_a:
+              description: "Ship A"
+              command:
+                executable: "true"
+          - ship_b:
+              description: "Ship B"
+              command:
+                executable: "true"
+      - stage: verify
+        agents: []
+        tasks:
+          - build:
+              description: "Build"
+          - test:
+              description: "Test"
+"#;
+
+    fn graph_for(workflow: &DSLWorkflow) -> TaskGraph {
+        let mut graph = TaskGraph::new();
+        for (name, spec) in &workflow.tasks {
+            graph.add_task(name.clone(), spec.clone());
+        }
+        for (name, spec) in inline_stage_tasks(workflow) {
+            graph.add_task(name, spec);
+        }
+        graph
+    }
+
+    #[test]
+    fn test_stages_ordered_by_dependencies() {
+        let workflow = parse_workflow(STAGED).unwrap();
+        let plan = StagePlan::from_workflow(&workflow).unwrap();
+        let ids: Vec<&str> = plan.stages().iter().map(|s| s.id.as_str()).collect();
+        assert_eq!(ids, vec!["pipeline.verify", "pipeline.deploy"]);
+        assert_eq!(plan.stages()[1].depends_on, vec!["pipeline.verify"]);
+    }
+
+    #[test]
+    fn test_inline_stage_tasks() {
+        let workflow = parse_workflow(STAGED).unwrap();
+        let names: Vec<String> = inline_stage_tasks(&workflow)
+            .into_iter()
+            .map(|(name, _)| name)
+            .collect();
+        assert_eq!(names, vec!["ship_a", "ship_b"]);
+    }
+
+    #[test]
+    fn test_apply_to_graph_adds_stage_ordering() {
+        let workflow = parse_workflow(STAGED).unwrap();
+        let mut graph = graph_for(&workflow);
+        let mut plan = StagePlan::from_workflow(&workflow).unwrap();
+        plan.apply_to_graph(&mut graph).unwrap();
+
+        // Sequential stage chains its tasks
+        assert_eq!(graph.get_task("test").unwrap().dependencies, vec!["build"]);
+        // Parallel stage tasks only wait for the previous stage
+        let mut deps = graph.get_task("ship_b").unwrap().dependencies.clone();
+        deps.sort();
+        assert_eq!(deps, vec!["build", "test"]);
+
+        assert_eq!(graph.get_ready_tasks(), vec!["build"]);
+    }
+
+    #[test]
+    fn test_finished_stages() {
+        let workflow = parse_workflow(STAGED).unwrap();
+        let mut graph = graph_for(&workflow);
+        let mut plan = StagePlan::from_workflow(&workflow).unwrap();
+        plan.apply_to_graph(&mut graph).unwrap();
+
+        let mut reported = HashSet::new();
+        assert!(plan.finished_stages(&graph, &reported).is_empty());
+
+        graph
+            .update_task_status("build", TaskStatus::Completed)
+            .unwrap();
+        graph
+            .update_task_status("test", TaskStatus::Skipped)
+            .unwrap();
+        let finished = plan.finished_stages(&graph, &reported);
+        assert_eq!(finished.len(), 1);
+        assert_eq!(finished[0].name, "verify");
+
+        reported.insert(finished[0].id.clone());
+        graph
+            .update_task_status("ship_a", TaskStatus::Completed)
+            .unwrap();
+        graph
+            .update_task_status("ship_b", TaskStatus::Completed)
+            .unwrap();
+        let finished = plan.finished_stages(&graph, &reported);
+        assert_eq!(finished[0].name, "deploy");
+    }
+
+    #[test]
+    fn test_cyclic_stages_rejected() {
+        let yaml = STAGED.replace(
+            "      - stage: verify\n",
+            "      - stage: verify\n        depends_on: [deploy]\n",
+        );
+        let workflow = parse_workflow(&yaml).unwrap();
+        let err = StagePlan::from_workflow(&workflow).unwrap_err();
+        assert!(err.to_string().contains("cyclic"));
+    }
+
+    #[test]
+    fn test_unknown_stage_task_rejected() {
+        let workflow = parse_workflow(STAGED).unwrap();
+        // Graph without the top-level tasks the stage refers to
+        let mut graph = TaskGraph::new();
+        for (name, spec) in inline_stage_tasks(&workflow) {
+            graph.add_task(name, spec);
+        }
+        let mut plan = StagePlan::from_workflow(&workflow).unwrap();
+        let err = plan.apply_to_graph(&mut graph).unwrap_err();
+        assert!(err.to_string().contains("unknown task 'build'"));
+    }
+}
@@ -3,9 +3,12 @@
 //! This module provides state management for workflows, enabling checkpointing,
 //! resuming interrupted workflows, and tracking execution progress.
 
-use crate::dsl::schema::{CleanupStrategy, TruncationStrategy};
+use crate::domain::ResultMessage;
+use crate::dsl::message_bus::MessageLog;
+use crate::dsl::schema::{BudgetConfig, CleanupStrategy, TruncationStrategy};
 use crate::dsl::task_graph::TaskStatus;
 use crate::error::{Error, Result};
+use crate::ports::secondary::TokenUsage;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
@@ -51,6 +54,76 @@ impl LoopState {
     }
 }
 
+/// Tokens and cost consumed by agent and LLM calls
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Usage {
+    /// Input tokens, excluding prompt cache reads and writes
+    pub input_tokens: u64,
+    /// Output tokens
+    pub output_tokens: u64,
+    /// Input tokens written to the prompt cache
+    pub cache_creation_input_tokens: u64,
+    /// Input tokens read from the prompt cache
+    pub cache_read_input_tokens: u64,
+    /// Cost in USD, when the provider reports it
+    pub cost_usd: f64,
+}
+
+impl Usage {
+    /// Usage reported in the result message of an agent query
+    pub fn from_result(result: &ResultMessage) -> Self {
+        let count = |key: &str| {
+            result
+                .usage
+                .as_ref()
+                .and_then(|usage| usage.get(key))
+                .and_then(serde_json::Value::as_u64)
+                .unwrap_or(0)
+        };
+        Self {
+            input_tokens: count("input_tokens"),
+            output_tokens: count("output_tokens"),
+            cache_creation_input_tokens: count("cache_creation_input_tokens"),
+            cache_read_input_tokens: count("cache_read_input_tokens"),
+            cost_usd: result.total_cost_usd.unwrap_or(0.0),
+        }
+    }
+
+    /// Tokens of all kinds
+    pub fn total_tokens(&self) -> u64 {
+        self.input_tokens
+            + self.output_tokens
+            + self.cache_creation_input_tokens
+            + self.cache_read_input_tokens
+    }
+
+    /// Whether nothing was consumed
+    pub fn is_empty(&self) -> bool {
+        self.total_tokens() == 0 && self.cost_usd == 0.0
+    }
+
+    /// Add another usage to this one
+    pub fn add(&mut self, other: &Usage) {
+        self.input_tokens += other.input_tokens;
+        self.output_tokens += other.output_tokens;
+        self.cache_creation_input_tokens += other.cache_creation_input_tokens;
+        self.cache_read_input_tokens += other.cache_read_input_tokens;
+        self.cost_usd += other.cost_usd;
+    }
+}
+
+impl From<&TokenUsage> for Usage {
+    /// Usage reported by an LLM provider, which does not include a cost
+    fn from(usage: &TokenUsage) -> Self {
+        Self {
+            input_tokens: usage.prompt_tokens as u64,
+            output_tokens: usage.completion_tokens as u64,
+            ..Default::default()
+        }
+    }
+}
+
 /// Task output with metadata for context management
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TaskOutput {
@@ -74,6 +147,15 @@ pub struct TaskOutput {
     pub last_accessed: SystemTime,
     /// Tasks that depend on this output
     pub depended_by: Vec<String>,
+    /// Structured result referenced as `${task.<id>.output}` (JSON when the task produced it)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub value: Option<serde_json::Value>,
+}
+
+/// Parse task output text as JSON, falling back to the trimmed text
+pub fn parse_output(text: &str) -> serde_json::Value {
+    let text = text.trim();
+    serde_json::from_str(text).unwrap_or_else(|_| serde_json::Value::String(text.to_string()))
 }
 
 /// Output type classification
@@ -112,6 +194,24 @@ impl TaskOutput {
             relevance_score: 0.0,
             last_accessed: SystemTime::now(),
             depended_by: Vec::new(),
+            value: None,
+        }
+    }
+
+    /// Attach the structured result of the task
+    pub fn with_value(mut self, value: serde_json::Value) -> Self {
+        self.value = Some(value);
+        self
+    }
+
+    /// Structured result of the task
+    ///
+    /// Outputs recorded without a structured value (e.g. by older versions) are
+    /// parsed from their content: JSON when it parses, the trimmed text otherwise.
+    pub fn structured(&self) -> serde_json::Value {
+        match &self.value {
+            Some(value) => value.clone(),
+            None => parse_output(&self.content),
         }
     }
 
@@ -202,6 +302,43 @@ pub struct WorkflowState {
     /// Loop results collected from iterations
     #[serde(default, skip_serializing_if = "HashMap::is_empty")]
     pub loop_results: HashMap<String, Vec<serde_json::Value>>,
+    /// States of child workflows run by subflow tasks (keyed by parent task id)
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub subflow_states: HashMap<String, WorkflowState>,
+    /// Stage statuses for `workflows:` stages (keyed by `<workflow>.<stage>`)
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub stage_statuses: HashMap<String, TaskStatus>,
+    /// Most recent conversation session ID of each agent
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub agent_sessions: HashMap<String, String>,
+    /// Session IDs of the named sessions of `session:` task options
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub named_sessions: HashMap<String, String>,
+    /// Tokens and cost of each task, over all its attempts and iterations
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub task_usage: HashMap<String, Usage>,
+    /// Tokens and cost of each agent
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub agent_usage: HashMap<String, Usage>,
+    /// Messages sent on the workflow's channels and to its agents
+    #[serde(default, skip_serializing_if = "MessageLog::is_empty")]
+    pub messages: MessageLog,
+}
+
+fn exceeded_tokens(scope: &str, tokens: u64, limit: Option<u64>) -> Option<String> {
+    let limit = limit.filter(|limit| tokens > *limit)?;
+    Some(format!(
+        "{} used {} tokens, over its budget of {}",
+        scope, tokens, limit
+    ))
+}
+
+fn exceeded_cost(scope: &str, cost: f64, limit: Option<f64>) -> Option<String> {
+    let limit = limit.filter(|limit| cost > *limit)?;
+    Some(format!(
+        "{} cost ${:.4}, over its budget of ${:.4}",
+        scope, cost, limit
+    ))
 }
 
 /// Overall workflow execution status
@@ -215,6 +352,8 @@ pub enum WorkflowStatus {
     Failed,
     /// Workflow was paused/checkpointed
     Paused,
+    /// Workflow was stopped for good and cannot be resumed
+    Cancelled,
 }
 
 impl WorkflowState {
@@ -237,6 +376,13 @@ impl WorkflowState {
             metadata: HashMap::new(),
             loop_states: HashMap::new(),
             loop_results: HashMap::new(),
+            subflow_states: HashMap::new(),
+            stage_statuses: HashMap::new(),
+            agent_sessions: HashMap::new(),
+            named_sessions: HashMap::new(),
+            task_usage: HashMap::new(),
+            agent_usage: HashMap::new(),
+            messages: MessageLog::default(),
         }
     }
 
@@ -251,10 +397,8 @@ impl WorkflowState {
         self.task_statuses.insert(task_id.to_string(), status);
 
         match status {
-            TaskStatus::Running => {
-                if !self.task_start_times.contains_key(task_id) {
-                    self.task_start_times.insert(task_id.to_string(), now);
-                }
+            TaskStatus::Running if !self.task_start_times.contains_key(task_id) => {
+                self.task_start_times.insert(task_id.to_string(), now);
             }
             TaskStatus::Completed | TaskStatus::Failed => {
                 self.task_end_times.insert(task_id.to_string(), now);
@@ -376,6 +520,13 @@ impl WorkflowState {
         self.checkpoint_at = SystemTime::now();
     }
 
+    /// Mark workflow as cancelled
+    pub fn mark_cancelled(&mut self) {
+        self.status = WorkflowStatus::Cancelled;
+        self.ended_at = Some(SystemTime::now());
+        self.checkpoint_at = SystemTime::now();
+    }
+
     /// Get task status
     pub fn get_task_status(&self, task_id: &str) -> Option<TaskStatus> {
         self.task_statuses.get(task_id).copied()
@@ -553,6 +704,116 @@ impl WorkflowState {
         self.loop_results.get(task_id)
     }
 
+    /// Store the state of a child workflow run by a subflow task
+    pub fn store_subflow_state(&mut self, task_id: &str, state: WorkflowState) {
+        self.subflow_states.insert(task_id.to_string(), state);
+        self.checkpoint_at = SystemTime::now();
+    }
+
+    /// Get the state of the child workflow run by a subflow task
+    pub fn get_subflow_state(&self, task_id: &str) -> Option<&WorkflowState> {
+        self.subflow_states.get(task_id)
+    }
+
+    /// Update the status of a workflow stage
+    pub fn update_stage_status(&mut self, stage_id: &str, status: TaskStatus) {
+        self.stage_statuses.insert(stage_id.to_string(), status);
+        self.checkpoint_at = SystemTime::now();
+    }
+
+    /// Get the status of a workflow stage
+    pub fn get_stage_status(&self, stage_id: &str) -> Option<TaskStatus> {
+        self.stage_statuses.get(stage_id).copied()
+    }
+
+    /// Record the conversation session an agent's task ran in
+    pub fn record_session(&mut self, agent: &str, name: Option<&str>, session_id: &str) {
+        self.agent_sessions
+            .insert(agent.to_string(), session_id.to_string());
+        if let Some(name) = name {
+            self.named_sessions
+                .insert(name.to_string(), session_id.to_string());
+        }
+        self.checkpoint_at = SystemTime::now();
+    }
+
+    /// Most recent conversation session of an agent
+    pub fn agent_session(&self, agent: &str) -> Option<&str> {
+        self.agent_sessions.get(agent).map(String::as_str)
+    }
+
+    /// Session ID of a named session
+    pub fn named_session(&self, name: &str) -> Option<&str> {
+        self.named_sessions.get(name).map(String::as_str)
+    }
+
+    /// Add the usage of an agent or LLM call made by a task
+    pub fn record_usage(&mut self, task_id: &str, agent: Option<&str>, usage: &Usage) {
+        self.task_usage
+            .entry(task_id.to_string())
+            .or_default()
+            .add(usage);
+        if let Some(agent) = agent {
+            self.agent_usage
+                .entry(agent.to_string())
+                .or_default()
+                .add(usage);
+        }
+        self.checkpoint_at = SystemTime::now();
+    }
+
+    /// Usage of the whole workflow, including its subflows
+    pub fn total_usage(&self) -> Usage {
+        let mut total = Usage::default();
+        for usage in self.task_usage.values() {
+            total.add(usage);
+        }
+        for subflow in self.subflow_states.values() {
+            total.add(&subflow.total_usage());
+        }
+        total
+    }
+
+    /// Usage per task, including the tasks of subflows as `subflow_task/task`
+    pub fn usage_by_task(&self) -> HashMap<String, Usage> {
+        let mut usage = self.task_usage.clone();
+        for (scope, subflow) in &self.subflow_states {
+            for (task_id, task) in subflow.usage_by_task() {
+                usage.insert(format!("{}/{}", scope, task_id), task);
+            }
+        }
+        usage
+    }
+
+    /// Usage per agent, including the agents of subflows as `subflow_task/agent`
+    pub fn usage_by_agent(&self) -> HashMap<String, Usage> {
+        let mut usage = self.agent_usage.clone();
+        for (scope, subflow) in &self.subflow_states {
+            for (agent, agent_usage) in subflow.usage_by_agent() {
+                usage.insert(format!("{}/{}", scope, agent), agent_usage);
+            }
+        }
+        usage
+    }
+
+    /// Describe the first limit of `budget` exceeded after `task_id` ran
+    pub fn check_budget(&self, budget: &BudgetConfig, task_id: &str) -> Option<String> {
+        let task = self.task_usage.get(task_id).copied().unwrap_or_default();
+        let workflow = self.total_usage();
+        let task_scope = format!("Task '{}'", task_id);
+
+        exceeded_tokens(&task_scope, task.total_tokens(), budget.max_task_tokens)
+            .or_else(|| exceeded_cost(&task_scope, task.cost_usd, budget.max_task_usd))
+            .or_else(|| {
+                exceeded_tokens(
+                    "Workflow",
+                    workflow.total_tokens(),
+                    budget.max_workflow_tokens,
+                )
+            })
+            .or_else(|| exceeded_cost("Workflow", workflow.cost_usd, budget.max_workflow_usd))
+    }
+
     /// Get loop state for a task
     pub fn get_loop_state(&self, task_id: &str) -> Option<&LoopState> {
         self.loop_states.get(task_id)
@@ -822,7 +1083,6 @@ impl StatePersistence {
             .flush()
             .map_err(|e| Error::InvalidInput(format!("Failed to flush state file: {}", e)))?;
 
-        println!("Saved workflow state to: {}", file_path.display());
         Ok(())
     }
 
@@ -856,7 +1116,6 @@ impl StatePersistence {
             Error::InvalidInput(format!("Failed to deserialize workflow state: {}", e))
         })?;
 
-        println!("Loaded workflow state from: {}", file_path.display());
         Ok(state)
     }
 
@@ -872,7 +1131,6 @@ impl StatePersistence {
         if file_path.exists() {
             fs::remove_file(&file_path)
                 .map_err(|e| Error::InvalidInput(format!("Failed to delete state file: {}", e)))?;
-            println!("Deleted state file: {}", file_path.display());
         }
 
         Ok(())
@@ -973,6 +1231,13 @@ mod tests {
         assert_eq!(state.status, WorkflowStatus::Completed);
         assert!(!state.can_resume());
         assert!(state.ended_at.is_some());
+
+        let mut state = WorkflowState::new("test".to_string(), "1.0.0".to_string());
+        state.mark_paused();
+        state.mark_cancelled();
+        assert_eq!(state.status, WorkflowStatus::Cancelled);
+        assert!(!state.can_resume());
+        assert!(state.ended_at.is_some());
     }
 
     #[test]
@@ -1122,4 +1387,50 @@ mod tests {
         assert!(context.contains("task1"));
         assert!(context.contains("First task done"));
     }
+
+    #[test]
+    fn test_record_usage_and_check_budget() {
+        let mut state = WorkflowState::new("test_workflow".to_string(), "1.0.0".to_string());
+        let call = Usage {
+            input_tokens: 100,
+            output_tokens: 50,
+            cost_usd: 0.01,
+            ..Default::default()
+        };
+        state.record_usage("draft", Some("writer"), &call);
+        state.record_usage("draft", Some("writer"), &call);
+        state.record_usage("summarize", None, &call);
+
+        let mut child = WorkflowState::new("child".to_string(), "1.0.0".to_string());
+        child.record_usage("draft", Some("writer"), &call);
+        state.subflow_states.insert("sub".to_string(), child);
+
+        assert_eq!(state.task_usage["draft"].total_tokens(), 300);
+        assert_eq!(state.agent_usage["writer"].output_tokens, 100);
+        assert!(!state.agent_usage.contains_key("summarize"));
+        assert_eq!(state.total_usage().total_tokens(), 600);
+
+        // Subflow entries with the same names stay apart
+        assert_eq!(state.usage_by_task()["draft"].total_tokens(), 300);
+        assert_eq!(state.usage_by_task()["sub/draft"].total_tokens(), 150);
+        assert_eq!(state.usage_by_agent()["writer"].output_tokens, 100);
+        assert_eq!(state.usage_by_agent()["sub/writer"].output_tokens, 50);
+
+        let budget = BudgetConfig {
+            max_task_tokens: Some(300),
+            max_workflow_usd: Some(0.055),
+            ..Default::default()
+        };
+        assert_eq!(state.check_budget(&budget, "draft"), None);
+
+        let costly = Usage {
+            cost_usd: 0.02,
+            ..call
+        };
+        state.record_usage("summarize", None, &costly);
+        assert_eq!(
+            state.check_budget(&budget, "summarize").as_deref(),
+            Some("Workflow cost $0.0600, over its budget of $0.0550")
+        );
+    }
 }
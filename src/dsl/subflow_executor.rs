@@ -0,0 +1,560 @@
+//! Subflow Execution
+//!
+//! This module turns tasks that delegate to another workflow into a standalone
+//! child workflow that the executor can run to completion:
+//!
+//! - `subflow`: an inline subflow, or one fetched from a file/git/http source
+//! - `uses_workflow`: a prebuilt workflow from an imported task group
+//! - `uses` / `embed`: a predefined task, run with its templated agent
+//!
+//! It also maps the parent task's `inputs` onto the child's declared inputs
+//! (with type checking and defaults) and collects the child's declared outputs
+//! from its final state.
+
+use crate::dsl::executor::DSLExecutor;
+use crate::dsl::fetcher::{fetch_subflow, SubflowCache};
+use crate::dsl::predefined_tasks::groups::{NamespaceResolver, TaskGroupLoader};
+use crate::dsl::predefined_tasks::TaskResolver;
+use crate::dsl::schema::{
+    AgentSpec, DSLWorkflow, InputSpec, OutputDataSource, OutputSpec, TaskSpec,
+};
+use crate::dsl::state::{parse_output, WorkflowState};
+use crate::error::{Error, Result};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::path::Path;
+
+/// How a child workflow declares its outputs
+#[derive(Debug, Clone)]
+pub enum ChildOutputs {
+    /// Output specifications (subflows and predefined tasks)
+    Specs(HashMap<String, OutputSpec>),
+    /// Output templates such as `${task.upload.output}` (task group workflows)
+    Templates(HashMap<String, String>),
+}
+
+/// A workflow to run on behalf of a parent task
+#[derive(Debug, Clone)]
+pub struct ChildWorkflow {
+    /// Kind of delegation (`subflow`, `uses_workflow`, `uses` or `embed`)
+    pub kind: &'static str,
+    /// Namespaced name of the child run (`<parent workflow>/<reference>`)
+    pub name: String,
+    /// Agents available to the child's tasks
+    pub agents: HashMap<String, AgentSpec>,
+    /// Tasks of the child workflow
+    pub tasks: HashMap<String, TaskSpec>,
+    /// Declared inputs
+    pub inputs: HashMap<String, InputSpec>,
+    /// Declared outputs
+    pub outputs: ChildOutputs,
+}
+
+/// Resolve the child workflow a task delegates to
+///
+/// Returns `None` when the task does not use `subflow`, `uses_workflow`, `uses`
+/// or `embed`. `inputs` are the parent task's inputs after interpolation; they
+/// are needed up front by predefined tasks, whose templates are rendered at
+/// resolution time.
+pub async fn resolve_child_workflow(
+    task_id: &str,
+    spec: &TaskSpec,
+    inputs: &HashMap<String, Value>,
+    parent: &DSLWorkflow,
+) -> Result<Option<ChildWorkflow>> {
+    if let Some(subflow_id) = &spec.subflow {
+        return resolve_subflow(task_id, subflow_id, parent).await.map(Some);
+    }
+
+    if let Some(reference) = &spec.uses_workflow {
+        return resolve_group_workflow(task_id, reference, parent).map(Some);
+    }
+
+    if let Some(reference) = &spec.uses {
+        return resolve_predefined_task(task_id, "uses", reference, spec, inputs, None, parent)
+            .map(Some);
+    }
+
+    if let Some(reference) = &spec.embed {
+        return resolve_predefined_task(
+            task_id,
+            "embed",
+            reference,
+            spec,
+            inputs,
+            spec.overrides.as_ref(),
+            parent,
+        )
+        .map(Some);
+    }
+
+    Ok(None)
+}
+
+/// Resolve an inline or external subflow
+async fn resolve_subflow(
+    task_id: &str,
+    subflow_id: &str,
+    parent: &DSLWorkflow,
+) -> Result<ChildWorkflow> {
+    let subflow = parent.subflows.get(subflow_id).ok_or_else(|| {
+        Error::InvalidInput(format!(
+            "Task '{}' references non-existent subflow '{}'",
+            task_id, subflow_id
+        ))
+    })?;
+
+    let mut agents = subflow.agents.clone();
+    let mut tasks = subflow.tasks.clone();
+    let mut inputs = subflow.inputs.clone();
+    let mut outputs = subflow.outputs.clone();
+
+    // External subflows that weren't resolved at parse time are fetched now
+    if let (Some(source), true) = (&subflow.source, subflow.tasks.is_empty()) {
+        let base_path = parent.cwd.as_deref().map(Path::new);
+        let fetched = fetch_subflow(source, base_path, &SubflowCache::new()).await?;
+        agents = fetched.agents;
+        tasks = fetched.tasks;
+        if inputs.is_empty() {
+            inputs = fetched.inputs;
+        }
+        if outputs.is_empty() {
+            outputs = fetched.outputs;
+        }
+    }
+
+    Ok(ChildWorkflow {
+        kind: "subflow",
+        name: format!("{}/{}", parent.name, subflow_id),
+        agents,
+        tasks,
+        inputs,
+        outputs: ChildOutputs::Specs(outputs),
+    })
+}
+
+/// Resolve a `namespace:workflow` reference against the workflow's imports
+fn resolve_group_workflow(
+    task_id: &str,
+    reference: &str,
+    parent: &DSLWorkflow,
+) -> Result<ChildWorkflow> {
+    let resolver_error = |e: crate::dsl::predefined_tasks::groups::ResolverError| {
+        Error::InvalidInput(format!(
+            "Task '{}' failed to resolve workflow '{}': {}",
+            task_id, reference, e
+        ))
+    };
+
+    let mut loader = TaskGroupLoader::new();
+    let resolver =
+        NamespaceResolver::from_imports(&parent.imports, &mut loader).map_err(resolver_error)?;
+    let prebuilt = resolver
+        .resolve_workflow_reference(reference)
+        .map_err(resolver_error)?;
+
+    let tasks: HashMap<String, TaskSpec> = if prebuilt.tasks.is_null() {
+        HashMap::new()
+    } else {
+        serde_yaml::from_value(prebuilt.tasks.clone()).map_err(|e| {
+            Error::InvalidInput(format!(
+                "Workflow '{}' has invalid task definitions: {}",
+                reference, e
+            ))
+        })?
+    };
+
+    Ok(ChildWorkflow {
+        kind: "uses_workflow",
+        name: format!("{}/{}", parent.name, reference),
+        agents: HashMap::new(),
+        tasks,
+        inputs: prebuilt.inputs.clone(),
+        outputs: ChildOutputs::Templates(prebuilt.outputs.clone()),
+    })
+}
+
+/// Resolve a predefined task into a single-task child workflow
+fn resolve_predefined_task(
+    task_id: &str,
+    kind: &'static str,
+    reference: &str,
+    spec: &TaskSpec,
+    inputs: &HashMap<String, Value>,
+    overrides: Option<&serde_yaml::Value>,
+    parent: &DSLWorkflow,
+) -> Result<ChildWorkflow> {
+    let (agent_id, agent_spec, mut task_spec) = TaskResolver::new()
+        .resolve(reference, inputs, &spec.outputs)
+        .map_err(|e| {
+            Error::InvalidInput(format!(
+                "Task '{}' failed to resolve '{}': {}",
+                task_id, reference, e
+            ))
+        })?;
+
+    task_spec.agent = Some(agent_id.clone());
+    if let Some(overrides) = overrides {
+        task_spec = apply_overrides(task_spec, overrides)?;
+    }
+
+    let outputs = task_spec.outputs.clone();
+    Ok(ChildWorkflow {
+        kind,
+        name: format!("{}/{}", parent.name, reference),
+        agents: HashMap::from([(agent_id, agent_spec)]),
+        tasks: HashMap::from([(task_id.to_string(), task_spec)]),
+        inputs: HashMap::new(),
+        outputs: ChildOutputs::Specs(outputs),
+    })
+}
+
+/// Deep-merge embed overrides into a task definition
+fn apply_overrides(task: TaskSpec, overrides: &serde_yaml::Value) -> Result<TaskSpec> {
+    fn merge(base: &mut serde_yaml::Value, overlay: &serde_yaml::Value) {
+        match (base, overlay) {
+            (serde_yaml::Value::Mapping(base_map), serde_yaml::Value::Mapping(overlay_map)) => {
+                for (key, value) in overlay_map {
+                    match base_map.get_mut(key) {
+                        Some(existing) => merge(existing, value),
+                        None => {
+                            base_map.insert(key.clone(), value.clone());
+                        }
+                    }
+                }
+            }
+            (base, overlay) => *base = overlay.clone(),
+        }
+    }
+
+    let mut value = serde_yaml::to_value(&task)?;
+    merge(&mut value, overrides);
+    Ok(serde_yaml::from_value(value)?)
+}
+
+/// Name of a JSON value's type as used in input declarations
+fn json_type_name(value: &Value) -> &'static str {
+    match value {
+        Value::String(_) => "string",
+        Value::Number(_) => "number",
+        Value::Bool(_) => "boolean",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+        Value::Null => "null",
+    }
+}
+
+/// Check a value against a declared input type, coercing interpolated strings
+///
+/// Values interpolated from `${...}` references are always strings, so a string
+/// is parsed as JSON when a non-string type is declared.
+fn coerce_input(value: Value, declared_type: &str) -> std::result::Result<Value, &'static str> {
+    let matches = |v: &Value| match declared_type {
+        "string" | "secret" => v.is_string(),
+        "number" => v.is_number(),
+        "integer" => v.is_i64() || v.is_u64(),
+        "boolean" => v.is_boolean(),
+        "array" => v.is_array(),
+        "object" => v.is_object(),
+        _ => true,
+    };
+
+    if matches(&value) {
+        return Ok(value);
+    }
+
+    if let Value::String(s) = &value {
+        if let Ok(parsed) = serde_json::from_str::<Value>(s) {
+            if matches(&parsed) {
+                return Ok(parsed);
+            }
+        }
+    }
+
+    Err(json_type_name(&value))
+}
+
+/// Map a parent task's inputs onto a child workflow's declared inputs
+///
+/// Declared inputs are type checked and fall back to their defaults; missing
+/// required inputs are an error. Inputs the child doesn't declare are passed
+/// through unchanged.
+pub fn map_child_inputs(
+    task_id: &str,
+    declared: &HashMap<String, InputSpec>,
+    provided: &HashMap<String, Value>,
+) -> Result<HashMap<String, Value>> {
+    let mut mapped: HashMap<String, Value> = provided
+        .iter()
+        .filter(|(name, _)| !declared.contains_key(*name))
+        .map(|(name, value)| (name.clone(), value.clone()))
+        .collect();
+
+    for (name, input_spec) in declared {
+        match provided.get(name) {
+            Some(value) => {
+                let value =
+                    coerce_input(value.clone(), &input_spec.param_type).map_err(|actual| {
+                        Error::InvalidInput(format!(
+                            "Task '{}': input '{}' expects type '{}' but got '{}'",
+                            task_id, name, input_spec.param_type, actual
+                        ))
+                    })?;
+                mapped.insert(name.clone(), value);
+            }
+            None => {
+                if let Some(default) = &input_spec.default {
+                    mapped.insert(name.clone(), default.clone());
+                } else if input_spec.required {
+                    return Err(Error::InvalidInput(format!(
+                        "Task '{}': missing required input '{}'",
+                        task_id, name
+                    )));
+                }
+            }
+        }
+    }
+
+    Ok(mapped)
+}
+
+/// Build the workflow definition for a child run
+///
+/// The child inherits the parent's provider, model, working directory, secrets,
+/// MCP servers, tool configuration, limits, concurrency limit, subflows and
+/// imports. Mapped input values become the defaults of the child's inputs so
+/// they resolve as `${workflow.<name>}` inside the child.
+pub fn build_child_workflow(
+    parent: &DSLWorkflow,
+    child: &ChildWorkflow,
+    inputs: &HashMap<String, Value>,
+) -> DSLWorkflow {
+    let child_inputs = inputs
+        .iter()
+        .map(|(name, value)| {
+            let declared = child.inputs.get(name);
+            (
+                name.clone(),
+                InputSpec {
+                    param_type: declared
+                        .map(|d| d.param_type.clone())
+                        .unwrap_or_else(|| json_type_name(value).to_string()),
+                    required: false,
+                    default: Some(value.clone()),
+                    description: declared.and_then(|d| d.description.clone()),
+                },
+            )
+        })
+        .collect();
+
+    DSLWorkflow {
+        name: child.name.clone(),
+        version: parent.version.clone(),
+        dsl_version: parent.dsl_version.clone(),
+        provider: parent.provider.clone(),
+        model: parent.model.clone(),
+        providers: parent.providers.clone(),
+        cwd: parent.cwd.clone(),
+        create_cwd: parent.create_cwd,
+        secrets: parent.secrets.clone(),
+        inputs: child_inputs,
+        outputs: HashMap::new(),
+        agents: child.agents.clone(),
+        tasks: child.tasks.clone(),
+        workflows: HashMap::new(),
+        tools: parent.tools.clone(),
+        communication: None,
+        mcp_servers: parent.mcp_servers.clone(),
+        subflows: parent.subflows.clone(),
+        imports: parent.imports.clone(),
+        notifications: None,
+        limits: parent.limits.clone(),
+        max_concurrency: parent.max_concurrency,
+    }
+}
+
+/// Collect the declared outputs of a finished child run
+///
+/// Without declared outputs, every task output of the child is returned keyed
+/// by task id.
+pub fn collect_child_outputs(
+    child: &ChildWorkflow,
+    state: &WorkflowState,
+    inputs: &HashMap<String, Value>,
+) -> Result<Value> {
+    let mut collected = serde_json::Map::new();
+
+    match &child.outputs {
+        ChildOutputs::Specs(specs) if specs.is_empty() => {
+            for (task_id, output) in &state.task_outputs {
+                collected.insert(task_id.clone(), output.structured());
+            }
+        }
+        ChildOutputs::Specs(specs) => {
+            for (name, spec) in specs {
+                let value = match &spec.source {
+                    OutputDataSource::TaskOutput { task } => state
+                        .get_task_output(task)
+                        .map(|o| o.structured())
+                        .unwrap_or(Value::Null),
+                    OutputDataSource::State { key } => {
+                        state.get_metadata(key).cloned().unwrap_or(Value::Null)
+                    }
+                    OutputDataSource::File { path } => {
+                        let content = std::fs::read_to_string(path).map_err(|e| {
+                            Error::InvalidInput(format!(
+                                "Failed to read output '{}' from '{}': {}",
+                                name, path, e
+                            ))
+                        })?;
+                        parse_output(&content)
+                    }
+                };
+                collected.insert(name.clone(), value);
+            }
+        }
+        ChildOutputs::Templates(templates) => {
+            for (name, template) in templates {
+                let rendered = DSLExecutor::substitute_variables_with_state(
+                    template,
+                    inputs,
+                    &HashMap::new(),
+                    Some(state),
+                );
+                collected.insert(name.clone(), parse_output(&rendered));
+            }
+        }
+    }
+
+    Ok(Value::Object(collected))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dsl::parse_workflow;
+    use crate::dsl::schema::TruncationStrategy;
+    use crate::dsl::state::{OutputType, TaskOutput};
+    use serde_json::json;
+
+    fn input(param_type: &str, required: bool, default: Option<Value>) -> InputSpec {
+        InputSpec {
+            param_type: param_type.to_string(),
+            required,
+            default,
+            description: None,
+        }
+    }
+
+    #[test]
+    fn test_map_child_inputs_defaults_and_coercion() {
+        let declared = HashMap::from([
+            ("name".to_string(), input("string", true, None)),
+            ("count".to_string(), input("number", false, Some(json!(1)))),
+            ("verbose".to_string(), input("boolean", false, None)),
+        ]);
+        let provided = HashMap::from([
+            ("name".to_string(), json!("report")),
+            ("verbose".to_string(), json!("true")),
+            ("extra".to_string(), json!([1, 2])),
+        ]);
+
+        let mapped = map_child_inputs("t", &declared, &provided).unwrap();
+        assert_eq!(mapped["name"], json!("report"));
+        assert_eq!(mapped["count"], json!(1));
+        assert_eq!(mapped["verbose"], json!(true));
+        assert_eq!(mapped["extra"], json!([1, 2]));
+    }
+
+    #[test]
+    fn test_map_child_inputs_errors() {
+        let declared = HashMap::from([("count".to_string(), input("number", true, None))]);
+
+        let err = map_child_inputs("t", &declared, &HashMap::new()).unwrap_err();
+        assert!(err.to_string().contains("missing required input 'count'"));
+
+        let provided = HashMap::from([("count".to_string(), json!("many"))]);
+        let err = map_child_inputs("t", &declared, &provided).unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("expects type 'number' but got 'string'"));
+    }
+
+    #[tokio::test]
+    async fn test_resolve_inline_subflow() {
+        let parent = parse_workflow(
+            r#"
+name: "parent"
+version: "1.0.0"
+secrets:
+  token:
+    source:
+      type: value
+      value: "abc"
+subflows:
+  report:
+    inputs:
+      title:
+        type: string
+        required: true
+    outputs:
+      body:
+        source:
+          type: task_output
+          task: render
+    tasks:
+      render:
+        description: "Render"
+        command:
+          executable: "echo"
+          args: ["${workflow.title}"]
+tasks:
+  make_report:
+    description: "Make a report"
+    subflow: report
+"#,
+        )
+        .unwrap();
+
+        let spec = &parent.tasks["make_report"];
+        let child = resolve_child_workflow("make_report", spec, &HashMap::new(), &parent)
+            .await
+            .unwrap()
+            .expect("subflow task");
+        assert_eq!(child.kind, "subflow");
+        assert_eq!(child.name, "parent/report");
+        assert!(child.tasks.contains_key("render"));
+
+        let inputs = HashMap::from([("title".to_string(), json!("Q3"))]);
+        let workflow = build_child_workflow(&parent, &child, &inputs);
+        assert_eq!(workflow.inputs["title"].default, Some(json!("Q3")));
+        assert!(workflow.secrets.contains_key("token"));
+
+        let mut state = WorkflowState::new(workflow.name.clone(), workflow.version.clone());
+        state.store_task_output(TaskOutput::new(
+            "render".to_string(),
+            OutputType::Stdout,
+            "Q3".to_string(),
+            2,
+            false,
+            TruncationStrategy::Tail,
+        ));
+        let outputs = collect_child_outputs(&child, &state, &inputs).unwrap();
+        assert_eq!(outputs, json!({"body": "Q3"}));
+    }
+
+    #[test]
+    fn test_apply_overrides() {
+        let task = TaskSpec {
+            description: "Upload".to_string(),
+            agent: Some("uploader".to_string()),
+            ..Default::default()
+        };
+        let overrides: serde_yaml::Value =
+            serde_yaml::from_str("description: \"Upload quietly\"\npriority: 2").unwrap();
+        let merged = apply_overrides(task, &overrides).unwrap();
+        assert_eq!(merged.description, "Upload quietly");
+        assert_eq!(merged.priority, 2);
+        assert_eq!(merged.agent.as_deref(), Some("uploader"));
+    }
+}
@@ -211,6 +211,19 @@ pub fn generate_template() -> String {
     writeln!(&mut template, "# create_cwd: true").unwrap();
     writeln!(&mut template).unwrap();
 
+    writeln!(
+        &mut template,
+        "# (optional) Maximum number of tasks running at the same time"
+    )
+    .unwrap();
+    writeln!(
+        &mut template,
+        "# Tasks start as soon as their dependencies complete (default: unbounded)"
+    )
+    .unwrap();
+    writeln!(&mut template, "# max_concurrency: 8").unwrap();
+    writeln!(&mut template).unwrap();
+
     writeln!(
         &mut template,
         "# (optional) Workflow-level limits for output truncation and context management"
@@ -257,6 +270,23 @@ pub fn generate_template() -> String {
         "#     keep_count: 20                 # Number of task outputs to retain"
     )
     .unwrap();
+    writeln!(&mut template, "#   # Token and Cost Budget").unwrap();
+    writeln!(&mut template, "#   budget:").unwrap();
+    writeln!(
+        &mut template,
+        "#     max_task_tokens: 200000        # Per task, over all attempts"
+    )
+    .unwrap();
+    writeln!(
+        &mut template,
+        "#     max_workflow_usd: 5.0          # Whole workflow, including subflows"
+    )
+    .unwrap();
+    writeln!(
+        &mut template,
+        "#     on_exceeded: abort             # abort|pause (default: abort)"
+    )
+    .unwrap();
     writeln!(&mut template).unwrap();
 
     writeln!(
@@ -298,7 +328,7 @@ pub fn generate_template() -> String {
     writeln!(&mut template, "#     source:").unwrap();
     writeln!(
         &mut template,
-        "#       type: env                      # env, file, or value"
+        "#       type: env                      # env, file, value, command, encrypted or provider"
     )
     .unwrap();
     writeln!(
@@ -329,6 +359,38 @@ pub fn generate_template() -> String {
         "#     description: \"Signing key for tokens\""
     )
     .unwrap();
+    writeln!(&mut template, "#   deploy_key:").unwrap();
+    writeln!(&mut template, "#     source:").unwrap();
+    writeln!(&mut template, "#       type: command").unwrap();
+    writeln!(
+        &mut template,
+        "#       command: \"pass\"                # For command source: trimmed stdout"
+    )
+    .unwrap();
+    writeln!(&mut template, "#       args: [\"show\", \"ci/deploy\"]").unwrap();
+    writeln!(&mut template, "#   smtp_password:").unwrap();
+    writeln!(&mut template, "#     source:").unwrap();
+    writeln!(&mut template, "#       type: encrypted").unwrap();
+    writeln!(
+        &mut template,
+        "#       path: \".secrets/vault.enc\"     # For encrypted source: written by `periplon-executor secrets encrypt`"
+    )
+    .unwrap();
+    writeln!(
+        &mut template,
+        "#       key: \"smtp\"                    # Entry name (default: the secret's name)"
+    )
+    .unwrap();
+    writeln!(
+        &mut template,
+        "#       passphrase_env: \"VAULT_PASS\"   # Default: PERIPLON_SECRETS_PASSPHRASE"
+    )
+    .unwrap();
+    writeln!(
+        &mut template,
+        "# Resolved secret values are redacted from task outputs, state and notifications"
+    )
+    .unwrap();
     writeln!(&mut template).unwrap();
 
     // Variables section
@@ -586,6 +648,14 @@ pub fn generate_template() -> String {
     writeln!(&mut template, "    # max_turns: 10").unwrap();
     writeln!(&mut template).unwrap();
 
+    writeln!(
+        &mut template,
+        "    # (optional) Maximum number of this agent's tasks running at the same time"
+    )
+    .unwrap();
+    writeln!(&mut template, "    # max_concurrency: 2").unwrap();
+    writeln!(&mut template).unwrap();
+
     // Add processor_agent for the hierarchical tasks example
     writeln!(&mut template, "  # Additional agent for processing tasks").unwrap();
     writeln!(&mut template, "  processor_agent:").unwrap();
@@ -1007,7 +1077,12 @@ pub fn generate_template() -> String {
     writeln!(&mut template, "    llm:").unwrap();
     writeln!(
         &mut template,
-        "      provider: ollama  # ollama, openai, anthropic, google"
+        "      provider: ollama  # ollama, openai, anthropic, google, openai_compatible, azure_openai"
+    )
+    .unwrap();
+    writeln!(
+        &mut template,
+        "      # profile: local_vllm  # Optional profile from `providers:` (sets provider, endpoint, key and model)"
     )
     .unwrap();
     writeln!(&mut template, "      model: \"olmo2:13b\"  # Model name").unwrap();
@@ -1045,6 +1120,46 @@ pub fn generate_template() -> String {
     writeln!(&mut template, "      # top_k: 50  # Top-k sampling").unwrap();
     writeln!(&mut template, "      # stop: [\"END\"]  # Stop sequences").unwrap();
     writeln!(&mut template, "      timeout_secs: 30  # Request timeout").unwrap();
+    writeln!(
+        &mut template,
+        "      # stream: true  # Print tokens as they are generated"
+    )
+    .unwrap();
+    writeln!(
+        &mut template,
+        "      # tools:  # Tools the model may call until it answers"
+    )
+    .unwrap();
+    writeln!(
+        &mut template,
+        "      #   - {{type: mcp, server: filesystem, tools: [read_file]}}  # Tools of an MCP server (all when omitted)"
+    )
+    .unwrap();
+    writeln!(
+        &mut template,
+        "      #   - {{type: task, task: lookup_user}}  # Another task, run with the model's arguments as inputs"
+    )
+    .unwrap();
+    writeln!(
+        &mut template,
+        "      #   - {{type: builtin, name: read_file}}  # read_file, list_directory, write_file, shell"
+    )
+    .unwrap();
+    writeln!(
+        &mut template,
+        "      # max_tool_iterations: 10  # Tool-calling rounds before giving up"
+    )
+    .unwrap();
+    writeln!(
+        &mut template,
+        "      # permissions: {{mode: acceptEdits, allowed_directories: [./out]}}  # Gates write_file (acceptEdits) and shell (bypassPermissions)"
+    )
+    .unwrap();
+    writeln!(
+        &mut template,
+        "      # schema: {{type: object, required: [summary]}}  # JSON Schema the response must match"
+    )
+    .unwrap();
     writeln!(&mut template, "    # Provider details:").unwrap();
     writeln!(
         &mut template,
@@ -1244,7 +1359,7 @@ pub fn generate_template() -> String {
     writeln!(&mut template, "    # condition:").unwrap();
     writeln!(
         &mut template,
-        "    #   type: state_equals  # or state_exists, task_status, always, never"
+        "    #   type: state_equals  # or state_exists, task_status, expression, always, never"
     )
     .unwrap();
     writeln!(&mut template, "    #   key: \"environment\"").unwrap();
@@ -1264,6 +1379,18 @@ pub fn generate_template() -> String {
     writeln!(&mut template, "    #     - type: always").unwrap();
     writeln!(&mut template, "    #     - type: state_exists").unwrap();
     writeln!(&mut template, "    #       key: \"override\"").unwrap();
+    writeln!(
+        &mut template,
+        "    # Expression condition (task outputs, state, inputs and loop variables):"
+    )
+    .unwrap();
+    writeln!(&mut template, "    # condition:").unwrap();
+    writeln!(&mut template, "    #   type: expression").unwrap();
+    writeln!(
+        &mut template,
+        "    #   expression: \"task.scan.output.issues < 5 && starts_with(inputs.branch, 'release/')\""
+    )
+    .unwrap();
     writeln!(&mut template, "    # Negated condition (NOT):").unwrap();
     writeln!(&mut template, "    # condition:").unwrap();
     writeln!(&mut template, "    #   not:").unwrap();
@@ -1365,6 +1492,16 @@ pub fn generate_template() -> String {
         "    #     description: \"Research findings\""
     )
     .unwrap();
+    writeln!(
+        &mut template,
+        "    #     schema: {{type: object}}  # Optional JSON Schema the output must satisfy"
+    )
+    .unwrap();
+    writeln!(
+        &mut template,
+        "    # schema_retries: 2  # Retries when the result does not match its schema (default: 2)"
+    )
+    .unwrap();
     writeln!(&mut template).unwrap();
     writeln!(
         &mut template,
@@ -1382,6 +1519,17 @@ pub fn generate_template() -> String {
     )
     .unwrap();
     writeln!(&mut template).unwrap();
+    writeln!(
+        &mut template,
+        "    # (optional) Conversation the agent runs in: new, continue, fork, or a session name"
+    )
+    .unwrap();
+    writeln!(
+        &mut template,
+        "    # session: continue  # Default: the agent's shared conversation"
+    )
+    .unwrap();
+    writeln!(&mut template).unwrap();
 
     writeln!(
         &mut template,
@@ -2191,6 +2339,46 @@ pub fn generate_template() -> String {
     writeln!(&mut template, "#     url: \"https://api.example.com/mcp\"").unwrap();
     writeln!(&mut template, "#     headers:").unwrap();
     writeln!(&mut template, "#       Authorization: \"Bearer token123\"").unwrap();
+    writeln!(&mut template).unwrap();
+
+    // Provider profiles section
+    writeln!(
+        &mut template,
+        "# (optional) Named provider profiles, used by agents and llm tasks with `profile:`"
+    )
+    .unwrap();
+    writeln!(&mut template, "# providers:").unwrap();
+    writeln!(&mut template, "#   local_vllm:").unwrap();
+    writeln!(
+        &mut template,
+        "#     provider: openai_compatible    # Also vLLM, LM Studio, llama.cpp"
+    )
+    .unwrap();
+    writeln!(
+        &mut template,
+        "#     endpoint: \"http://localhost:8000/v1\""
+    )
+    .unwrap();
+    writeln!(&mut template, "#     model: \"Qwen/Qwen2.5-7B-Instruct\"").unwrap();
+    writeln!(
+        &mut template,
+        "#     auth: none                     # bearer|api_key|none"
+    )
+    .unwrap();
+    writeln!(&mut template, "#   azure:").unwrap();
+    writeln!(&mut template, "#     provider: azure_openai").unwrap();
+    writeln!(
+        &mut template,
+        "#     endpoint: \"https://my-resource.openai.azure.com\""
+    )
+    .unwrap();
+    writeln!(&mut template, "#     api_key: \"${{secret.azure_key}}\"").unwrap();
+    writeln!(&mut template, "#     api_version: \"2024-10-21\"").unwrap();
+    writeln!(
+        &mut template,
+        "#     model: \"gpt-4o-prod\"            # Deployment name"
+    )
+    .unwrap();
 
     template
 }
@@ -2359,6 +2547,11 @@ pub fn generate_nl_to_dsl_prompt() -> String {
     )
     .unwrap();
     writeln!(&mut prompt, "- `mcp_servers`: MCP server configurations").unwrap();
+    writeln!(
+        &mut prompt,
+        "- `providers`: Named provider profiles (provider, endpoint, api_key, auth, model) referenced with `profile`"
+    )
+    .unwrap();
     writeln!(
         &mut prompt,
         "- `subflows`: Map of reusable subflow definitions (can be inline or external)"
@@ -2527,7 +2720,11 @@ pub fn generate_nl_to_dsl_prompt() -> String {
     writeln!(&mut prompt, "secrets:").unwrap();
     writeln!(&mut prompt, "  api_token:").unwrap();
     writeln!(&mut prompt, "    source:").unwrap();
-    writeln!(&mut prompt, "      type: env  # env, file, or value").unwrap();
+    writeln!(
+        &mut prompt,
+        "      type: env  # env, file, value, command, encrypted or provider"
+    )
+    .unwrap();
     writeln!(&mut prompt, "      var: \"API_TOKEN\"  # For env source").unwrap();
     writeln!(&mut prompt, "    description: \"API token\"").unwrap();
     writeln!(&mut prompt, "```").unwrap();
@@ -2763,6 +2960,11 @@ pub fn generate_nl_to_dsl_prompt() -> String {
     writeln!(&mut prompt, "      allowed_directories:  # optional list").unwrap();
     writeln!(&mut prompt, "        - \"/allowed/path\"").unwrap();
     writeln!(&mut prompt, "    max_turns: 10  # optional").unwrap();
+    writeln!(
+        &mut prompt,
+        "    max_concurrency: 2  # optional, max tasks of this agent running at once"
+    )
+    .unwrap();
     writeln!(&mut prompt, "```").unwrap();
     writeln!(&mut prompt).unwrap();
     writeln!(&mut prompt, "## Task Schema").unwrap();
@@ -2918,7 +3120,12 @@ pub fn generate_nl_to_dsl_prompt() -> String {
     writeln!(&mut prompt, "    # llm:").unwrap();
     writeln!(
         &mut prompt,
-        "#       provider: ollama  # ollama, openai, anthropic, google"
+        "#       provider: ollama  # ollama, openai, anthropic, google, openai_compatible, azure_openai"
+    )
+    .unwrap();
+    writeln!(
+        &mut prompt,
+        "#       profile: local_vllm  # Optional profile from `providers:` instead of provider settings"
     )
     .unwrap();
     writeln!(&mut prompt, "#       model: \"olmo2:13b\"  # Model name").unwrap();
@@ -3071,10 +3278,15 @@ pub fn generate_nl_to_dsl_prompt() -> String {
         "    condition:  # optional - conditional execution"
     )
     .unwrap();
-    writeln!(&mut prompt, "      # Single condition (choose type: state_equals, state_exists, task_status, always, never):").unwrap();
+    writeln!(&mut prompt, "      # Single condition (choose type: state_equals, state_exists, task_status, expression, always, never):").unwrap();
     writeln!(&mut prompt, "      type: state_equals").unwrap();
     writeln!(&mut prompt, "      key: \"environment\"").unwrap();
     writeln!(&mut prompt, "      value: \"production\"").unwrap();
+    writeln!(
+        &mut prompt,
+        "      # expression conditions: type: expression, expression: \"task.<id>.output.<field> >= 3 && contains(inputs.<name>, 'x')\""
+    )
+    .unwrap();
     writeln!(&mut prompt, "      # OR use combined conditions:").unwrap();
     writeln!(
         &mut prompt,
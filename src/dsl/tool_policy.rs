@@ -0,0 +1,743 @@
+//! Tool Policy
+//!
+//! This module enforces a workflow's `tools:` configuration and the agents'
+//! `allowed_directories` on the tool calls agents make. Each agent gets a
+//! [`ToolGuard`] whose [`CanUseToolCallback`] the CLI consults before running a
+//! tool:
+//!
+//! | Check | Applies to |
+//! |-------|------------|
+//! | `disallowed`, then `allowed` | every tool |
+//! | the agent's `tools`, when set | every tool |
+//! | `allowed_commands` | `Bash` |
+//! | `allowed_directories` | `Write`, `Edit`, `MultiEdit`, `NotebookEdit`, and `Bash` output redirections |
+//! | `allowed_extensions`, `max_file_size` | the same file tools |
+//! | `rate_limit` (calls per minute) | every tool |
+//! | `timeout` (caps the call's `timeout`) | `Bash` |
+//!
+//! Denied calls are reported as [`ExecutionEvent::ToolDenied`] events and,
+//! when debugging, recorded as side effects of the task.
+
+use crate::domain::{PermissionResult, ToolPermissionContext};
+use crate::dsl::debugger::side_effects::ToolDenialCompensation;
+use crate::dsl::debugger::{DebuggerState, SideEffectType};
+use crate::dsl::events::{ExecutionEvent, ExecutionEvents};
+use crate::dsl::llm_tools::normalize;
+use crate::dsl::schema::{ToolConstraints, ToolsConfig};
+use crate::options::{AgentOptions, CanUseToolCallback};
+use serde_json::Value;
+use std::collections::{HashMap, VecDeque};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
+
+/// Built-in tools that write files, with the input holding the path
+const FILE_TOOLS: &[(&str, &str)] = &[
+    ("Write", "file_path"),
+    ("Edit", "file_path"),
+    ("MultiEdit", "file_path"),
+    ("NotebookEdit", "notebook_path"),
+];
+
+/// Window `rate_limit` counts calls in
+const RATE_LIMIT_WINDOW: Duration = Duration::from_secs(60);
+
+/// The workflow-wide part of the policy, shared by all agents
+///
+/// Rate limits count the calls of all agents together.
+pub struct ToolPolicy {
+    config: ToolsConfig,
+    calls: std::sync::Mutex<HashMap<String, VecDeque<Instant>>>,
+}
+
+impl ToolPolicy {
+    pub fn new(config: ToolsConfig) -> Self {
+        Self {
+            config,
+            calls: std::sync::Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Check a call, returning its input with constraints applied
+    fn check(&self, tool: &str, input: &Value, files: &FileScope) -> Result<Value, String> {
+        if self
+            .config
+            .disallowed
+            .iter()
+            .any(|pattern| tool_matches(pattern, tool))
+        {
+            return Err(format!("{} is disallowed by the workflow", tool));
+        }
+        if !self.config.allowed.is_empty()
+            && !self
+                .config
+                .allowed
+                .iter()
+                .any(|pattern| tool_matches(pattern, tool))
+        {
+            return Err(format!("{} is not in the workflow's allowed tools", tool));
+        }
+
+        let constraints = self.config.constraints.get(tool);
+        let mut input = input.clone();
+        if tool == "Bash" {
+            if let Some(constraints) = constraints {
+                check_command(&input, constraints)?;
+                cap_timeout(&mut input, constraints);
+            }
+            let commands_restricted = constraints.is_some_and(|c| !c.allowed_commands.is_empty());
+            if commands_restricted || files.is_restricted() {
+                check_redirections(&input, files)?;
+            }
+        }
+        if let Some(path_key) = file_path_key(tool) {
+            files.check(&input, path_key)?;
+            if let Some(constraints) = constraints {
+                check_file_constraints(tool, &input, path_key, constraints)?;
+            }
+        }
+
+        if let Some(limit) = constraints.and_then(|c| c.rate_limit) {
+            self.take_rate_slot(tool, limit, Instant::now())?;
+        }
+        Ok(input)
+    }
+
+    /// Count a call against the tool's rate limit, failing when it is used up
+    fn take_rate_slot(&self, tool: &str, limit: u32, now: Instant) -> Result<(), String> {
+        let mut calls = self.calls.lock().unwrap();
+        let window = calls.entry(tool.to_string()).or_default();
+        while window
+            .front()
+            .is_some_and(|&at| now.duration_since(at) >= RATE_LIMIT_WINDOW)
+        {
+            window.pop_front();
+        }
+        if window.len() >= limit as usize {
+            return Err(format!(
+                "{} exceeded its rate limit of {} calls per minute",
+                tool, limit
+            ));
+        }
+        window.push_back(now);
+        Ok(())
+    }
+}
+
+/// Directories an agent may write in
+pub struct FileScope {
+    base_dir: PathBuf,
+    allowed: Vec<PathBuf>,
+}
+
+impl FileScope {
+    /// Resolve `allowed_directories` against the agent's working directory
+    ///
+    /// With no allowed directories every path is allowed.
+    pub fn new(base_dir: PathBuf, allowed_directories: &[String]) -> Self {
+        let allowed = allowed_directories
+            .iter()
+            .map(|dir| resolve(&base_dir.join(dir)))
+            .collect();
+        Self { base_dir, allowed }
+    }
+
+    fn is_restricted(&self) -> bool {
+        !self.allowed.is_empty()
+    }
+
+    fn check(&self, input: &Value, path_key: &str) -> Result<(), String> {
+        if !self.is_restricted() {
+            return Ok(());
+        }
+        self.check_path(input_path(input, path_key)?)
+    }
+
+    fn check_path(&self, path: &str) -> Result<(), String> {
+        let resolved = resolve(&self.base_dir.join(path));
+        if self.allowed.iter().any(|dir| resolved.starts_with(dir)) {
+            Ok(())
+        } else {
+            Err(format!("{} is outside the allowed directories", path))
+        }
+    }
+}
+
+/// Resolve symlinks in the existing part of `path`, and `.` and `..` in the rest
+///
+/// Symlinks must be followed before checking containment, or a link inside
+/// an allowed directory would lead out of it.
+pub(crate) fn resolve(path: &Path) -> PathBuf {
+    let mut existing = path;
+    let mut missing = Vec::new();
+    loop {
+        if let Ok(canonical) = existing.canonicalize() {
+            let full = missing
+                .iter()
+                .rev()
+                .fold(canonical, |full, name| full.join(name));
+            return normalize(&full);
+        }
+        match (existing.file_name(), existing.parent()) {
+            (Some(name), Some(parent)) => {
+                missing.push(name);
+                existing = parent;
+            }
+            _ => return normalize(path),
+        }
+    }
+}
+
+/// Enforces the tool policy for one agent
+pub struct ToolGuard {
+    agent: String,
+    agent_tools: Vec<String>,
+    policy: Arc<ToolPolicy>,
+    files: FileScope,
+    task_id: std::sync::Mutex<String>,
+    events: Arc<ExecutionEvents>,
+    debugger: Option<Arc<Mutex<DebuggerState>>>,
+}
+
+impl ToolGuard {
+    pub fn new(
+        agent: String,
+        agent_tools: Vec<String>,
+        policy: Arc<ToolPolicy>,
+        files: FileScope,
+        events: Arc<ExecutionEvents>,
+        debugger: Option<Arc<Mutex<DebuggerState>>>,
+    ) -> Self {
+        Self {
+            agent,
+            agent_tools,
+            policy,
+            files,
+            task_id: std::sync::Mutex::new(String::new()),
+            events,
+            debugger,
+        }
+    }
+
+    /// Attribute the agent's following tool calls to a task
+    pub fn set_task(&self, task_id: &str) {
+        *self.task_id.lock().unwrap() = task_id.to_string();
+    }
+
+    /// Whether calls to `tool` must be checked rather than pre-approved
+    pub fn checks(&self, tool: &str) -> bool {
+        let config = &self.policy.config;
+        config.constraints.contains_key(tool)
+            || !config.allowed.is_empty()
+            || (self.files.is_restricted() && (file_path_key(tool).is_some() || tool == "Bash"))
+    }
+
+    /// Route the agent's tool calls through this guard
+    ///
+    /// The CLI does not consult the permission callback for tools passed as
+    /// allowed tools, so checked tools are left out of them. Disallowed tools
+    /// are also passed to the CLI, which then does not offer them at all.
+    pub fn apply_to(self: &Arc<Self>, options: &mut AgentOptions) {
+        options.allowed_tools.retain(|tool| !self.checks(tool));
+        for tool in &self.policy.config.disallowed {
+            if !options.disallowed_tools.contains(tool) {
+                options.disallowed_tools.push(tool.clone());
+            }
+        }
+        options.can_use_tool = Some(self.callback());
+        options.permission_prompt_tool_name = Some("stdio".to_string());
+    }
+
+    /// Decide on a tool call, reporting denials
+    pub async fn check(&self, tool: &str, input: Value) -> PermissionResult {
+        // Tools left out of the allowed tools for checking stay limited to the agent's
+        let decision = if !self.agent_tools.is_empty()
+            && !self
+                .agent_tools
+                .iter()
+                .any(|pattern| tool_matches(pattern, tool))
+        {
+            Err(format!(
+                "{} is not among the tools of agent '{}'",
+                tool, self.agent
+            ))
+        } else {
+            self.policy.check(tool, &input, &self.files)
+        };
+
+        match decision {
+            Ok(updated_input) => PermissionResult::Allow {
+                updated_input: Some(updated_input),
+                updated_permissions: None,
+            },
+            Err(reason) => {
+                self.report_denial(tool, input, &reason).await;
+                PermissionResult::Deny {
+                    message: reason,
+                    interrupt: false,
+                }
+            }
+        }
+    }
+
+    /// The permission callback to install on the agent
+    pub fn callback(self: &Arc<Self>) -> CanUseToolCallback {
+        let guard = Arc::clone(self);
+        Arc::new(
+            move |tool: String, input: Value, _context: ToolPermissionContext| {
+                let guard = Arc::clone(&guard);
+                Box::pin(async move { guard.check(&tool, input).await })
+            },
+        )
+    }
+
+    async fn report_denial(&self, tool: &str, input: Value, reason: &str) {
+        let task_id = self.task_id.lock().unwrap().clone();
+        self.events.emit(ExecutionEvent::ToolDenied {
+            task_id: task_id.clone(),
+            agent: self.agent.clone(),
+            tool: tool.to_string(),
+            reason: reason.to_string(),
+        });
+
+        if let Some(ref debugger) = self.debugger {
+            debugger.lock().await.side_effects.record(
+                task_id,
+                SideEffectType::ToolDenied {
+                    agent: self.agent.clone(),
+                    tool_name: tool.to_string(),
+                    input,
+                    reason: reason.to_string(),
+                },
+                Arc::new(ToolDenialCompensation {
+                    tool_name: tool.to_string(),
+                }),
+            );
+        }
+    }
+}
+
+/// Match a tool name against an exact name or a `prefix*` pattern
+fn tool_matches(pattern: &str, tool: &str) -> bool {
+    match pattern.strip_suffix('*') {
+        Some(prefix) => tool.starts_with(prefix),
+        None => pattern == tool,
+    }
+}
+
+fn file_path_key(tool: &str) -> Option<&'static str> {
+    FILE_TOOLS
+        .iter()
+        .find(|(name, _)| *name == tool)
+        .map(|(_, key)| *key)
+}
+
+fn input_path<'a>(input: &'a Value, path_key: &str) -> Result<&'a str, String> {
+    input
+        .get(path_key)
+        .and_then(Value::as_str)
+        .ok_or_else(|| format!("Missing '{}' in tool input", path_key))
+}
+
+/// Check every command of a Bash call against `allowed_commands`
+///
+/// Commands chained with `;`, `&&`, `||`, `|` or `&` are checked one by one.
+/// Command substitution is refused since its commands cannot be checked.
+fn check_command(input: &Value, constraints: &ToolConstraints) -> Result<(), String> {
+    if constraints.allowed_commands.is_empty() {
+        return Ok(());
+    }
+    let command = input_path(input, "command")?;
+    if command.contains("$(") || command.contains('`') || command.contains("<(") {
+        return Err("Command substitution is not allowed".to_string());
+    }
+
+    for segment in command_segments(command) {
+        let words: Vec<&str> = segment
+            .split_whitespace()
+            .skip_while(|word| is_env_assignment(word))
+            .collect();
+        if words.is_empty() {
+            continue;
+        }
+        let invocation = words.join(" ");
+        let allowed = constraints.allowed_commands.iter().any(|allowed| {
+            invocation == *allowed || invocation.starts_with(&format!("{} ", allowed))
+        });
+        if !allowed {
+            return Err(format!("Command '{}' is not allowed", invocation));
+        }
+    }
+    Ok(())
+}
+
+/// Check the files a Bash call redirects output to
+///
+/// Targets must be in the allowed directories, when they are set; otherwise
+/// only `/dev/null` is allowed. Targets with expansions are refused since
+/// where they lead cannot be checked.
+fn check_redirections(input: &Value, files: &FileScope) -> Result<(), String> {
+    let command = input_path(input, "command")?;
+    for target in redirection_targets(command) {
+        if target == "/dev/null" {
+            continue;
+        }
+        if target.is_empty()
+            || target.contains(['$', '`', '*', '?', '[', '{'])
+            || target.starts_with('~')
+        {
+            return Err(format!("Redirection to '{}' cannot be checked", target));
+        }
+        if !files.is_restricted() {
+            return Err(format!("Output redirection to {} is not allowed", target));
+        }
+        files.check_path(&target)?;
+    }
+    Ok(())
+}
+
+/// Files written by the output redirections of a command line
+///
+/// Covers `>`, `>>`, `>|`, `&>`, `N>` and `<>`; `>&N` duplicates a descriptor
+/// and writes no file. Quoted text is skipped, and quotes are removed from
+/// the targets.
+fn redirection_targets(command: &str) -> Vec<String> {
+    let chars: Vec<char> = command.chars().collect();
+    let mut targets = Vec::new();
+    let mut quote = None;
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        if let Some(open) = quote {
+            if c == open {
+                quote = None;
+            } else if c == '\\' && open == '"' {
+                i += 1;
+            }
+            i += 1;
+            continue;
+        }
+        match c {
+            '\'' | '"' => quote = Some(c),
+            '\\' => i += 1,
+            '>' => {
+                i += 1;
+                if matches!(chars.get(i), Some('>' | '|')) {
+                    i += 1;
+                }
+                let duplicates = chars.get(i) == Some(&'&');
+                if duplicates {
+                    i += 1;
+                }
+                while chars.get(i).is_some_and(|c| c.is_whitespace()) {
+                    i += 1;
+                }
+                let (target, end) = shell_word(&chars, i);
+                i = end;
+                if duplicates && (target == "-" || target.chars().all(|c| c.is_ascii_digit())) {
+                    continue;
+                }
+                targets.push(target);
+                continue;
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    targets
+}
+
+/// Read the shell word starting at `start`, returning it unquoted and its end
+fn shell_word(chars: &[char], start: usize) -> (String, usize) {
+    let mut word = String::new();
+    let mut quote = None;
+    let mut i = start;
+    while let Some(&c) = chars.get(i) {
+        match quote {
+            Some(open) if c == open => quote = None,
+            Some(_) => word.push(c),
+            None if c == '\'' || c == '"' => quote = Some(c),
+            None if c.is_whitespace() || ";&|<>()".contains(c) => break,
+            None => word.push(c),
+        }
+        i += 1;
+    }
+    (word, i)
+}
+
+/// Split a command line at `;`, `&&`, `||`, `|`, `&` and newlines
+///
+/// `&` in redirections such as `2>&1` and `&>` does not separate commands.
+fn command_segments(command: &str) -> Vec<&str> {
+    let bytes = command.as_bytes();
+    let mut segments = Vec::new();
+    let mut start = 0;
+    for (i, &byte) in bytes.iter().enumerate() {
+        let separates = match byte {
+            b';' | b'\n' | b'|' => true,
+            b'&' => {
+                let redirect_before = i > 0 && matches!(bytes[i - 1], b'>' | b'<');
+                let redirect_after = bytes.get(i + 1) == Some(&b'>');
+                !redirect_before && !redirect_after
+            }
+            _ => false,
+        };
+        if separates {
+            segments.push(&command[start..i]);
+            start = i + 1;
+        }
+    }
+    segments.push(&command[start..]);
+    segments
+}
+
+fn is_env_assignment(word: &str) -> bool {
+    word.split_once('=').is_some_and(|(name, _)| {
+        !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+    })
+}
+
+/// Lower a Bash call's `timeout` to the constraint's
+fn cap_timeout(input: &mut Value, constraints: &ToolConstraints) {
+    let (Some(limit), Some(fields)) = (constraints.timeout, input.as_object_mut()) else {
+        return;
+    };
+    let timeout = fields
+        .get("timeout")
+        .and_then(Value::as_u64)
+        .map_or(limit, |requested| requested.min(limit));
+    fields.insert("timeout".to_string(), timeout.into());
+}
+
+fn check_file_constraints(
+    tool: &str,
+    input: &Value,
+    path_key: &str,
+    constraints: &ToolConstraints,
+) -> Result<(), String> {
+    let path = input_path(input, path_key)?;
+    if !constraints.allowed_extensions.is_empty() {
+        let extension = Path::new(path)
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .unwrap_or("");
+        let allowed = constraints.allowed_extensions.iter().any(|allowed| {
+            allowed
+                .trim_start_matches('.')
+                .eq_ignore_ascii_case(extension)
+        });
+        if !allowed {
+            return Err(format!("{} does not have an allowed extension", path));
+        }
+    }
+
+    if let Some(max_size) = constraints.max_file_size {
+        let size = written_size(tool, input);
+        if size > max_size {
+            return Err(format!(
+                "{} bytes written to {} exceed the limit of {} bytes",
+                size, path, max_size
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Bytes of new content in a file tool call
+fn written_size(tool: &str, input: &Value) -> u64 {
+    let len = |value: Option<&Value>| value.and_then(Value::as_str).map_or(0, str::len) as u64;
+    match tool {
+        "Write" => len(input.get("content")),
+        "Edit" => len(input.get("new_string")),
+        "MultiEdit" => input
+            .get("edits")
+            .and_then(Value::as_array)
+            .map_or(0, |edits| {
+                edits.iter().map(|edit| len(edit.get("new_string"))).sum()
+            }),
+        "NotebookEdit" => len(input.get("new_source")),
+        _ => 0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn policy(yaml: &str) -> ToolPolicy {
+        ToolPolicy::new(serde_yaml::from_str(yaml).unwrap())
+    }
+
+    fn unrestricted() -> FileScope {
+        FileScope::new(PathBuf::from("/work"), &[])
+    }
+
+    #[test]
+    fn test_allowed_and_disallowed_tools() {
+        let policy = policy("allowed: [Read, Bash, 'mcp__docs__*']\ndisallowed: [Bash]");
+        let files = unrestricted();
+
+        assert!(policy.check("Read", &json!({}), &files).is_ok());
+        assert!(policy
+            .check("mcp__docs__search", &json!({}), &files)
+            .is_ok());
+        assert!(policy
+            .check("Bash", &json!({}), &files)
+            .unwrap_err()
+            .contains("disallowed"));
+        assert!(policy
+            .check("Write", &json!({}), &files)
+            .unwrap_err()
+            .contains("not in the workflow's allowed tools"));
+    }
+
+    #[test]
+    fn test_allowed_commands() {
+        let policy =
+            policy("constraints:\n  Bash:\n    allowed_commands: [ls, cat, 'git status', cargo]");
+        let files = unrestricted();
+        let run = |command: &str| policy.check("Bash", &json!({ "command": command }), &files);
+
+        assert!(run("ls -la").is_ok());
+        assert!(run("git status && RUST_LOG=debug cargo test 2>&1 | cat -n").is_ok());
+        assert!(run("ls &> /dev/null").is_ok());
+        assert!(run("git push").is_err());
+        assert!(run("ls; rm -rf /").is_err());
+        assert!(run("lsof").is_err());
+        assert!(run("ls $(rm -rf /)").is_err());
+    }
+
+    #[test]
+    fn test_redirections() {
+        let policy = policy("constraints:\n  Bash:\n    allowed_commands: [ls, cat, echo]");
+        let unrestricted = unrestricted();
+        let run = |command: &str, files: &FileScope| {
+            policy.check("Bash", &json!({ "command": command }), files)
+        };
+
+        // Allowed commands may not write files through redirections
+        assert!(run("ls > /etc/x", &unrestricted)
+            .unwrap_err()
+            .contains("not allowed"));
+        assert!(run("cat a >> ../b", &unrestricted).is_err());
+        assert!(run("ls 2>/dev/null", &unrestricted).is_ok());
+        assert!(run("ls 2>&1 >&2", &unrestricted).is_ok());
+        assert!(run("echo 'a > b' \"c>d\"", &unrestricted).is_ok());
+
+        // With allowed directories, targets inside them are fine
+        let docs = FileScope::new(PathBuf::from("/work"), &["docs".to_string()]);
+        assert!(run("ls > docs/listing.txt", &docs).is_ok());
+        assert!(run("echo hi >'docs/a b.txt'", &docs).is_ok());
+        assert!(run("cat a >> ../b", &docs)
+            .unwrap_err()
+            .contains("outside the allowed directories"));
+        assert!(run("ls &>/work/src/x", &docs).is_err());
+        assert!(run("ls > $HOME/x", &docs)
+            .unwrap_err()
+            .contains("cannot be checked"));
+
+        // Allowed directories alone also apply to redirections
+        let no_constraints = ToolPolicy::new(Default::default());
+        assert!(no_constraints
+            .check("Bash", &json!({"command": "rm -rf build > /etc/x"}), &docs)
+            .is_err());
+        assert!(no_constraints
+            .check("Bash", &json!({"command": "cargo build"}), &docs)
+            .is_ok());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_symlinks_out_of_allowed_directories() {
+        let dir = tempfile::tempdir().unwrap();
+        let outside = tempfile::tempdir().unwrap();
+        std::fs::create_dir(dir.path().join("docs")).unwrap();
+        std::os::unix::fs::symlink(outside.path(), dir.path().join("docs/escape")).unwrap();
+
+        let files = FileScope::new(dir.path().to_path_buf(), &["docs".to_string()]);
+        assert!(files.check_path("docs/notes.md").is_ok());
+        assert!(files.check_path("docs/new/notes.md").is_ok());
+        assert!(files
+            .check_path("docs/escape/notes.md")
+            .unwrap_err()
+            .contains("outside the allowed directories"));
+        assert!(files.check_path("docs/escape/../notes.md").is_err());
+    }
+
+    #[test]
+    fn test_timeout_is_capped() {
+        let policy = policy("constraints:\n  Bash:\n    timeout: 5000");
+        let files = unrestricted();
+
+        let input = policy
+            .check(
+                "Bash",
+                &json!({"command": "sleep 1", "timeout": 60000}),
+                &files,
+            )
+            .unwrap();
+        assert_eq!(input["timeout"], 5000);
+        let input = policy
+            .check("Bash", &json!({"command": "sleep 1"}), &files)
+            .unwrap();
+        assert_eq!(input["timeout"], 5000);
+    }
+
+    #[test]
+    fn test_file_constraints() {
+        let policy = policy(
+            "constraints:\n  Write:\n    allowed_extensions: ['.md', txt]\n    max_file_size: 10",
+        );
+        let files = FileScope::new(PathBuf::from("/work"), &["docs".to_string()]);
+        let write = |path: &str, content: &str| {
+            policy.check(
+                "Write",
+                &json!({"file_path": path, "content": content}),
+                &files,
+            )
+        };
+
+        assert!(write("docs/notes.md", "short").is_ok());
+        assert!(write("/work/docs/a/b.TXT", "short").is_ok());
+        assert!(write("docs/../src/main.md", "short")
+            .unwrap_err()
+            .contains("outside the allowed directories"));
+        assert!(write("docs/main.rs", "short")
+            .unwrap_err()
+            .contains("allowed extension"));
+        assert!(write("docs/notes.md", "far too long")
+            .unwrap_err()
+            .contains("exceed the limit"));
+
+        // Edits are checked against directories but not Write's constraints
+        assert!(policy
+            .check(
+                "Edit",
+                &json!({"file_path": "/etc/passwd", "old_string": "a", "new_string": "b"}),
+                &files
+            )
+            .is_err());
+        assert!(policy
+            .check(
+                "Edit",
+                &json!({"file_path": "docs/x.rs", "old_string": "a", "new_string": "b"}),
+                &files
+            )
+            .is_ok());
+    }
+
+    #[test]
+    fn test_rate_limit_window() {
+        let policy = policy("constraints:\n  WebSearch:\n    rate_limit: 2");
+        let start = Instant::now();
+
+        assert!(policy.take_rate_slot("WebSearch", 2, start).is_ok());
+        assert!(policy.take_rate_slot("WebSearch", 2, start).is_ok());
+        assert!(policy.take_rate_slot("WebSearch", 2, start).is_err());
+        assert!(policy
+            .take_rate_slot("WebSearch", 2, start + RATE_LIMIT_WINDOW)
+            .is_ok());
+    }
+}
@@ -4,7 +4,12 @@
 //! including checking for circular dependencies, valid agent references, tool availability,
 //! and variable reference validation.
 
-use crate::dsl::schema::{CollectionSource, DSLWorkflow, LoopSpec, TaskSpec};
+use crate::dsl::expression::Expression;
+use crate::dsl::output_schema::check_schema;
+use crate::dsl::schema::{
+    CollectionSource, Condition, ConditionSpec, DSLWorkflow, LlmToolSpec, LoopSpec, TaskSpec,
+};
+use crate::dsl::stages::StagePlan;
 use crate::dsl::variables::extract_variable_references;
 use crate::error::{Error, Result};
 use std::collections::{HashMap, HashSet};
@@ -98,15 +103,42 @@ pub fn validate_workflow(workflow: &DSLWorkflow) -> Result<()> {
     // Validate tool references
     validate_tool_references(workflow, &mut errors);
 
+    // Validate MCP server references
+    validate_mcp_server_references(workflow, &mut errors);
+
     // Validate permission modes
     validate_permission_modes(workflow, &mut errors);
 
+    // Validate the tools offered to LLM tasks
+    validate_llm_tools(workflow, &mut errors);
+
+    // Validate provider profiles and their references
+    validate_providers(workflow, &mut errors);
+
+    // Validate concurrency limits
+    validate_concurrency_limits(workflow, &mut errors);
+
+    // Validate task sessions
+    validate_sessions(workflow, &mut errors);
+
+    // Validate token and cost budgets
+    validate_budget(workflow, &mut errors);
+
+    // Validate message types and the channels tasks use
+    validate_communication(workflow, &mut errors);
+
     // Validate workflow stages
     validate_workflow_stages(workflow, &mut errors);
 
     // Validate loop specifications
     validate_loop_specs(workflow, &mut errors);
 
+    // Type-check condition expressions
+    validate_condition_expressions(workflow, &mut errors);
+
+    // Validate output JSON Schemas
+    validate_output_schemas(workflow, &mut errors);
+
     // Validate subflow references
     validate_subflow_references(workflow, &mut errors);
 
@@ -164,6 +196,54 @@ fn validate_subtask_agent_references(
     }
 }
 
+/// Validate that MCP tool tasks reference declared MCP servers
+fn validate_mcp_server_references(workflow: &DSLWorkflow, errors: &mut ValidationErrors) {
+    for (server_name, server_spec) in &workflow.mcp_servers {
+        match server_spec.server_type.as_str() {
+            "stdio" if server_spec.command.is_none() => errors.add_error(format!(
+                "MCP server '{}' of type 'stdio' must specify a command",
+                server_name
+            )),
+            "http" | "sse" if server_spec.url.is_none() => errors.add_error(format!(
+                "MCP server '{}' of type '{}' must specify a url",
+                server_name, server_spec.server_type
+            )),
+            "stdio" | "http" | "sse" => {}
+            other => errors.add_error(format!(
+                "MCP server '{}' has invalid type '{}'. Valid types: stdio, http, sse",
+                server_name, other
+            )),
+        }
+    }
+
+    for (task_name, task_spec) in &workflow.tasks {
+        validate_task_mcp_server_reference(task_name, task_spec, workflow, errors);
+    }
+}
+
+/// Validate the MCP server reference of a task and its subtasks recursively
+fn validate_task_mcp_server_reference(
+    task_name: &str,
+    task_spec: &TaskSpec,
+    workflow: &DSLWorkflow,
+    errors: &mut ValidationErrors,
+) {
+    if let Some(mcp_tool) = &task_spec.mcp_tool {
+        if !workflow.mcp_servers.contains_key(&mcp_tool.server) {
+            errors.add_error(format!(
+                "Task '{}' references non-existent MCP server '{}'",
+                task_name, mcp_tool.server
+            ));
+        }
+    }
+
+    for subtask_map in &task_spec.subtasks {
+        for (subtask_name, subtask_spec) in subtask_map {
+            validate_task_mcp_server_reference(subtask_name, subtask_spec, workflow, errors);
+        }
+    }
+}
+
 /// Validate that all task dependencies exist
 fn validate_task_dependencies(workflow: &DSLWorkflow, errors: &mut ValidationErrors) {
     let task_names: HashSet<_> = workflow.tasks.keys().cloned().collect();
@@ -257,6 +337,10 @@ fn validate_tool_references(workflow: &DSLWorkflow, errors: &mut ValidationError
 }
 
 /// Validate that permission modes are valid
+///
+/// Agents under a tool policy (a workflow `tools:` section or their own
+/// `allowed_directories`) cannot bypass permissions, since the CLI then
+/// skips the checks that enforce the policy.
 fn validate_permission_modes(workflow: &DSLWorkflow, errors: &mut ValidationErrors) {
     const VALID_MODES: &[&str] = &["default", "acceptEdits", "plan", "bypassPermissions"];
 
@@ -270,6 +354,281 @@ fn validate_permission_modes(workflow: &DSLWorkflow, errors: &mut ValidationErro
                 VALID_MODES.join(", ")
             ));
         }
+
+        let guarded =
+            workflow.tools.is_some() || !agent_spec.permissions.allowed_directories.is_empty();
+        if mode == "bypassPermissions" && guarded {
+            errors.add_error(format!(
+                "Agent '{}' cannot use permission mode 'bypassPermissions' with a tool policy or allowed_directories, which it would skip; use 'acceptEdits' instead",
+                agent_name
+            ));
+        }
+    }
+}
+
+/// Validate provider profiles and the agents and `llm` tasks using them
+///
+/// OpenAI-compatible and Azure providers have no default endpoint or model
+/// list, so any model name is accepted but the endpoint and model must be
+/// configured. Agents run through a CLI, so their profiles must use a
+/// provider the Claude or Codex CLI can reach.
+fn validate_providers(workflow: &DSLWorkflow, errors: &mut ValidationErrors) {
+    for (name, profile) in &workflow.providers {
+        if profile.provider.is_api_based()
+            && profile.endpoint.is_none()
+            && profile.provider.default_endpoint().is_none()
+        {
+            errors.add_error(format!("Provider profile '{}' requires an endpoint", name));
+        }
+    }
+
+    for (agent_name, agent) in &workflow.agents {
+        let Some(name) = &agent.profile else {
+            continue;
+        };
+        match workflow.providers.get(name) {
+            None => errors.add_error(format!(
+                "Agent '{}' references unknown provider profile '{}'",
+                agent_name, name
+            )),
+            Some(profile) if profile.provider.agent_cli().is_none() => errors.add_error(format!(
+                "Agent '{}' uses provider profile '{}', but {:?} cannot run agents",
+                agent_name, name, profile.provider
+            )),
+            Some(_) => {}
+        }
+    }
+
+    for (task_name, task_spec) in &workflow.tasks {
+        let Some(llm) = &task_spec.llm else {
+            continue;
+        };
+        let llm = match &llm.profile {
+            Some(name) => match workflow.providers.get(name) {
+                Some(profile) => llm.with_profile(profile),
+                None => {
+                    errors.add_error(format!(
+                        "Task '{}' references unknown provider profile '{}'",
+                        task_name, name
+                    ));
+                    continue;
+                }
+            },
+            None => llm.clone(),
+        };
+
+        if !llm.provider.is_api_based() {
+            errors.add_error(format!(
+                "Task '{}' llm provider {:?} does not support direct API calls",
+                task_name, llm.provider
+            ));
+        } else if llm.profile.is_none()
+            && llm.endpoint.is_none()
+            && llm.provider.default_endpoint().is_none()
+        {
+            errors.add_error(format!(
+                "Task '{}' llm provider {:?} requires an endpoint",
+                task_name, llm.provider
+            ));
+        }
+        if llm.model.is_empty() {
+            errors.add_error(format!("Task '{}' llm has no model", task_name));
+        }
+    }
+}
+
+/// Validate the tools of `llm` tasks
+///
+/// Tool servers and tasks must exist, built-in tools must be allowed by the
+/// task's permission mode, and tasks offered as tools (which only run when a
+/// model calls them) cannot be dependencies of other tasks.
+fn validate_llm_tools(workflow: &DSLWorkflow, errors: &mut ValidationErrors) {
+    const VALID_MODES: &[&str] = &["default", "acceptEdits", "plan", "bypassPermissions"];
+    let tool_tasks = crate::dsl::llm_tools::tool_task_ids(workflow);
+
+    for (task_name, task_spec) in &workflow.tasks {
+        for dep in &task_spec.depends_on {
+            if tool_tasks.contains(dep) {
+                errors.add_error(format!(
+                    "Task '{}' depends on '{}', which is offered as an LLM tool and only runs when called",
+                    task_name, dep
+                ));
+            }
+        }
+
+        let Some(llm) = &task_spec.llm else {
+            continue;
+        };
+        if !VALID_MODES.contains(&llm.permissions.mode.as_str()) {
+            errors.add_error(format!(
+                "Task '{}' has invalid permission mode '{}'. Valid modes: {}",
+                task_name,
+                llm.permissions.mode,
+                VALID_MODES.join(", ")
+            ));
+        }
+
+        for tool in &llm.tools {
+            match tool {
+                LlmToolSpec::Mcp { server, .. } => {
+                    if !workflow.mcp_servers.contains_key(server) {
+                        errors.add_error(format!(
+                            "Task '{}' offers tools of non-existent MCP server '{}'",
+                            task_name, server
+                        ));
+                    }
+                }
+                LlmToolSpec::Task { task, .. } => {
+                    if task == task_name {
+                        errors.add_error(format!(
+                            "Task '{}' cannot offer itself as a tool",
+                            task_name
+                        ));
+                    } else if !workflow.tasks.contains_key(task) {
+                        errors.add_error(format!(
+                            "Task '{}' offers non-existent task '{}' as a tool",
+                            task_name, task
+                        ));
+                    }
+                }
+                LlmToolSpec::Builtin { name } => {
+                    if let Err(e) = crate::dsl::llm_tools::check_builtin(*name, &llm.permissions) {
+                        errors.add_error(format!("Task '{}': {}", task_name, e));
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Validate that workflow and agent concurrency limits allow at least one task
+fn validate_concurrency_limits(workflow: &DSLWorkflow, errors: &mut ValidationErrors) {
+    if workflow.max_concurrency == Some(0) {
+        errors.add_error("Workflow max_concurrency must be at least 1".to_string());
+    }
+
+    for (agent_name, agent_spec) in &workflow.agents {
+        if agent_spec.max_concurrency == Some(0) {
+            errors.add_error(format!(
+                "Agent '{}' has max_concurrency 0; it must be at least 1",
+                agent_name
+            ));
+        }
+    }
+}
+
+/// Validate that only agent-based tasks choose a session
+fn validate_sessions(workflow: &DSLWorkflow, errors: &mut ValidationErrors) {
+    for (task_name, task_spec) in &workflow.tasks {
+        if task_spec.session.is_none() {
+            continue;
+        }
+        let runs_agent = task_spec.agent.is_some()
+            && task_spec.script.is_none()
+            && task_spec.command.is_none()
+            && task_spec.http.is_none()
+            && task_spec.mcp_tool.is_none()
+            && task_spec.llm.is_none();
+        if !runs_agent {
+            errors.add_error(format!(
+                "Task '{}' sets a session but does not run an agent",
+                task_name
+            ));
+        }
+    }
+}
+
+/// Validate that budget cost limits are positive amounts
+fn validate_budget(workflow: &DSLWorkflow, errors: &mut ValidationErrors) {
+    let Some(budget) = workflow
+        .limits
+        .as_ref()
+        .and_then(|limits| limits.budget.as_ref())
+    else {
+        return;
+    };
+
+    for (name, limit) in [
+        ("max_task_usd", budget.max_task_usd),
+        ("max_workflow_usd", budget.max_workflow_usd),
+    ] {
+        if limit.is_some_and(|usd| !usd.is_finite() || usd <= 0.0) {
+            errors.add_error(format!("Budget {} must be a positive amount", name));
+        }
+    }
+}
+
+/// Validate message type schemas and the channels tasks read and publish to
+fn validate_communication(workflow: &DSLWorkflow, errors: &mut ValidationErrors) {
+    if let Some(communication) = &workflow.communication {
+        for (type_name, type_spec) in &communication.message_types {
+            if let Err(e) = check_schema(&type_spec.schema) {
+                errors.add_error(format!(
+                    "Message type '{}' has an invalid schema: {}",
+                    type_name, e
+                ));
+            }
+        }
+    }
+
+    for (task_name, task_spec) in &workflow.tasks {
+        let Some(messages) = &task_spec.messages else {
+            continue;
+        };
+        let Some(communication) = &workflow.communication else {
+            errors.add_error(format!(
+                "Task '{}' uses messages, but the workflow has no communication channels",
+                task_name
+            ));
+            continue;
+        };
+
+        let participant = messages.participant_of(task_name, task_spec);
+        let mut check_channel = |channel: &str| match communication.channels.get(channel) {
+            None => errors.add_error(format!(
+                "Task '{}' references unknown channel '{}'",
+                task_name, channel
+            )),
+            Some(spec) if !spec.participants.iter().any(|p| p == participant) => {
+                errors.add_error(format!(
+                    "Task '{}' uses channel '{}' as '{}', which is not a participant",
+                    task_name, channel, participant
+                ))
+            }
+            Some(_) => {}
+        };
+        for channel in &messages.read {
+            check_channel(channel);
+        }
+
+        let Some(publish) = &messages.publish else {
+            continue;
+        };
+        match (&publish.channel, &publish.to) {
+            (Some(channel), None) => check_channel(channel),
+            (None, Some(to)) => {
+                if !workflow.agents.contains_key(to) {
+                    errors.add_error(format!(
+                        "Task '{}' publishes to unknown agent '{}'",
+                        task_name, to
+                    ));
+                }
+            }
+            _ => errors.add_error(format!(
+                "Task '{}' must publish to either a channel or an agent",
+                task_name
+            )),
+        }
+        if !communication.message_types.is_empty()
+            && !communication
+                .message_types
+                .contains_key(&publish.message_type)
+        {
+            errors.add_error(format!(
+                "Task '{}' publishes unknown message type '{}'",
+                task_name, publish.message_type
+            ));
+        }
     }
 }
 
@@ -304,6 +663,11 @@ fn validate_workflow_stages(workflow: &DSLWorkflow, errors: &mut ValidationError
             }
         }
     }
+
+    // Stages must be orderable by their dependencies
+    if let Err(Error::InvalidInput(message)) = StagePlan::from_workflow(workflow) {
+        errors.add_error(message);
+    }
 }
 
 /// Validate loop specifications in tasks
@@ -443,6 +807,155 @@ fn validate_loop_spec(task_name: &str, loop_spec: &LoopSpec, errors: &mut Valida
     }
 }
 
+/// Type-check the expressions of task conditions, loop controls and
+/// `while`/`repeat_until` loops
+fn validate_condition_expressions(workflow: &DSLWorkflow, errors: &mut ValidationErrors) {
+    let mut task_ids = HashSet::new();
+    collect_task_ids("", &workflow.tasks, &mut task_ids);
+    for workflow_spec in workflow.workflows.values() {
+        for stage in &workflow_spec.steps {
+            for stage_tasks in &stage.tasks {
+                collect_task_ids("", stage_tasks, &mut task_ids);
+            }
+        }
+    }
+
+    for (task_name, task_spec) in &workflow.tasks {
+        validate_task_expressions(task_name, task_spec, &task_ids, errors);
+    }
+}
+
+/// Validate the JSON Schemas declared on outputs and LLM tasks
+fn validate_output_schemas(workflow: &DSLWorkflow, errors: &mut ValidationErrors) {
+    for (name, output) in &workflow.outputs {
+        if let Some(schema) = &output.schema {
+            if let Err(e) = check_schema(schema) {
+                errors.add_error(format!("Workflow output '{}': {}", name, e));
+            }
+        }
+    }
+
+    for (task_name, task_spec) in &workflow.tasks {
+        validate_task_output_schemas(task_name, task_spec, errors);
+    }
+}
+
+/// Validate the output schemas of a task and its subtasks
+fn validate_task_output_schemas(
+    task_name: &str,
+    task_spec: &TaskSpec,
+    errors: &mut ValidationErrors,
+) {
+    if let Some(schema) = task_spec.llm.as_ref().and_then(|llm| llm.schema.as_ref()) {
+        if let Err(e) = check_schema(schema) {
+            errors.add_error(format!("Task '{}' llm schema: {}", task_name, e));
+        }
+    }
+    for (output_name, output) in &task_spec.outputs {
+        if let Some(schema) = &output.schema {
+            if let Err(e) = check_schema(schema) {
+                errors.add_error(format!(
+                    "Task '{}' output '{}': {}",
+                    task_name, output_name, e
+                ));
+            }
+        }
+    }
+
+    for subtask_map in &task_spec.subtasks {
+        for (subtask_name, subtask_spec) in subtask_map {
+            validate_task_output_schemas(
+                &format!("{}.{}", task_name, subtask_name),
+                subtask_spec,
+                errors,
+            );
+        }
+    }
+}
+
+/// Collect task ids, including `parent.child` ids of subtasks
+fn collect_task_ids(
+    prefix: &str,
+    tasks: &HashMap<String, TaskSpec>,
+    task_ids: &mut HashSet<String>,
+) {
+    for (name, spec) in tasks {
+        let id = if prefix.is_empty() {
+            name.clone()
+        } else {
+            format!("{}.{}", prefix, name)
+        };
+        task_ids.insert(name.clone());
+        for subtasks in &spec.subtasks {
+            collect_task_ids(&id, subtasks, task_ids);
+        }
+        task_ids.insert(id);
+    }
+}
+
+/// Type-check the condition expressions of a task and its subtasks
+fn validate_task_expressions(
+    task_name: &str,
+    task_spec: &TaskSpec,
+    task_ids: &HashSet<String>,
+    errors: &mut ValidationErrors,
+) {
+    for condition in task_spec.conditions() {
+        validate_condition_spec(task_name, condition, task_ids, errors);
+    }
+
+    for subtask_map in &task_spec.subtasks {
+        for (subtask_name, subtask_spec) in subtask_map {
+            validate_task_expressions(
+                &format!("{}.{}", task_name, subtask_name),
+                subtask_spec,
+                task_ids,
+                errors,
+            );
+        }
+    }
+}
+
+/// Type-check every expression in a condition
+fn validate_condition_spec(
+    task_name: &str,
+    condition: &ConditionSpec,
+    task_ids: &HashSet<String>,
+    errors: &mut ValidationErrors,
+) {
+    match condition {
+        ConditionSpec::Single(Condition::Expression { expression }) => {
+            let checked = Expression::parse(expression).and_then(|parsed| {
+                parsed.check()?;
+                Ok(parsed)
+            });
+            match checked {
+                Ok(parsed) => {
+                    for task in parsed.task_references() {
+                        if !task_ids.contains(&task) {
+                            errors.add_error(format!(
+                                "Task '{}': condition expression references non-existent task '{}'",
+                                task_name, task
+                            ));
+                        }
+                    }
+                }
+                Err(Error::InvalidInput(message)) => {
+                    errors.add_error(format!("Task '{}': {}", task_name, message));
+                }
+                Err(e) => errors.add_error(format!("Task '{}': {}", task_name, e)),
+            }
+        }
+        ConditionSpec::Single(_) => {}
+        ConditionSpec::And { and: conditions } | ConditionSpec::Or { or: conditions } => {
+            for condition in conditions {
+                validate_condition_spec(task_name, condition, task_ids, errors);
+            }
+        }
+        ConditionSpec::Not { not } => validate_condition_spec(task_name, not, task_ids, errors),
+    }
+}
+
 /// Validate a collection source
 fn validate_collection_source(
     task_name: &str,
@@ -773,6 +1286,11 @@ fn validate_variable_references(workflow: &DSLWorkflow, errors: &mut ValidationE
         defined_vars.insert(format!("workflow.{}", var_name));
     }
 
+    // Task outputs, referenced as ${task.<id>.output} or a path inside it
+    for task_id in workflow.tasks.keys() {
+        defined_vars.insert(format!("task.{}.output", task_id));
+    }
+
     // Agent-level variables
     for (agent_id, agent_spec) in &workflow.agents {
         for var_name in agent_spec.inputs.keys() {
@@ -866,9 +1384,12 @@ fn validate_string_variable_refs(
     let refs = extract_variable_references(text);
 
     for var_ref in refs {
-        // Check qualified references (scope.var)
+        // Check qualified references (scope.var), ignoring any path into the value
         if var_ref.contains('.') {
-            if !defined_vars.contains(&var_ref) {
+            let segments: Vec<&str> = var_ref.split(['.', '[']).collect();
+            let is_defined = (2..=segments.len().min(3))
+                .any(|len| defined_vars.contains(&segments[..len].join(".")));
+            if !is_defined {
                 // Check if it's a valid scope prefix
                 let scope = var_ref.split('.').next().unwrap();
                 match scope {
@@ -1756,6 +2277,8 @@ mod tests {
             imports: HashMap::new(),
             notifications: None,
             limits: None,
+            max_concurrency: None,
+            providers: HashMap::new(),
         }
     }
 
@@ -1788,6 +2311,8 @@ mod tests {
             imports: HashMap::new(),
             notifications: None,
             limits: None,
+            max_concurrency: None,
+            providers: HashMap::new(),
         };
 
         let task = TaskSpec {
@@ -1829,6 +2354,8 @@ mod tests {
             imports: HashMap::new(),
             notifications: None,
             limits: None,
+            max_concurrency: None,
+            providers: HashMap::new(),
         };
 
         let task1 = TaskSpec {
@@ -1877,6 +2404,8 @@ mod tests {
             imports: HashMap::new(),
             notifications: None,
             limits: None,
+            max_concurrency: None,
+            providers: HashMap::new(),
         };
 
         let agent = AgentSpec {
@@ -1891,6 +2420,8 @@ mod tests {
             tools: vec!["InvalidTool".to_string()],
             permissions: PermissionsSpec::default(),
             max_turns: None,
+            max_concurrency: None,
+            profile: None,
         };
 
         workflow.agents.insert("agent1".to_string(), agent);
@@ -1900,6 +2431,167 @@ mod tests {
         assert!(result.unwrap_err().to_string().contains("invalid tool"));
     }
 
+    #[test]
+    fn test_validate_mcp_server_references() {
+        use crate::dsl::schema::{McpServerSpec, McpToolSpec};
+
+        let mut workflow = create_test_workflow();
+        workflow.mcp_servers.insert(
+            "files".to_string(),
+            McpServerSpec {
+                server_type: "stdio".to_string(),
+                command: Some("mcp-files".to_string()),
+                args: vec![],
+                env: HashMap::new(),
+                url: None,
+                headers: HashMap::new(),
+            },
+        );
+        workflow.tasks.insert(
+            "read".to_string(),
+            TaskSpec {
+                description: "Read a file".to_string(),
+                mcp_tool: Some(McpToolSpec {
+                    server: "files".to_string(),
+                    tool: "read_file".to_string(),
+                    parameters: HashMap::new(),
+                    timeout_secs: None,
+                }),
+                ..Default::default()
+            },
+        );
+        assert!(validate_workflow(&workflow).is_ok());
+
+        workflow
+            .tasks
+            .get_mut("read")
+            .unwrap()
+            .mcp_tool
+            .as_mut()
+            .unwrap()
+            .server = "missing".to_string();
+        let err = validate_workflow(&workflow).unwrap_err().to_string();
+        assert!(err.contains("non-existent MCP server 'missing'"));
+    }
+
+    #[test]
+    fn test_validate_concurrency_limits() {
+        let mut workflow = create_test_workflow();
+        workflow.max_concurrency = Some(4);
+        assert!(validate_workflow(&workflow).is_ok());
+
+        workflow.max_concurrency = Some(0);
+        let err = validate_workflow(&workflow).unwrap_err().to_string();
+        assert!(err.contains("Workflow max_concurrency must be at least 1"));
+
+        workflow.max_concurrency = None;
+        workflow.agents.insert(
+            "agent1".to_string(),
+            AgentSpec {
+                provider: None,
+                description: "Test agent".to_string(),
+                model: None,
+                system_prompt: None,
+                cwd: None,
+                create_cwd: None,
+                inputs: HashMap::new(),
+                outputs: HashMap::new(),
+                tools: vec![],
+                permissions: PermissionsSpec::default(),
+                max_turns: None,
+                max_concurrency: Some(0),
+                profile: None,
+            },
+        );
+        let err = validate_workflow(&workflow).unwrap_err().to_string();
+        assert!(err.contains("max_concurrency 0"));
+    }
+
+    #[test]
+    fn test_validate_condition_expressions() {
+        let workflow_with = |condition: &str| {
+            let yaml = format!(
+                r#"
+name: "Expressions"
+version: "1.0.0"
+tasks:
+  check:
+    description: "Check"
+    script:
+      language: bash
+      content: "true"
+  deploy:
+    description: "Deploy"
+    script:
+      language: bash
+      content: "true"
+{}
+"#,
+                condition
+            );
+            crate::dsl::parser::parse_workflow(&yaml).unwrap()
+        };
+        let condition = |expression: &str| {
+            format!(
+                "    condition:\n      type: expression\n      expression: \"{}\"",
+                expression
+            )
+        };
+
+        let workflow = workflow_with(&condition(
+            "task.check.output.ok && len(inputs.targets) > 0",
+        ));
+        assert!(validate_workflow(&workflow).is_ok());
+
+        let workflow = workflow_with(&condition("'ready' < 3"));
+        let err = validate_workflow(&workflow).unwrap_err().to_string();
+        assert!(err.contains("Task 'deploy': Invalid expression"));
+
+        let workflow = workflow_with(&condition("task.missing.status == 'completed'"));
+        let err = validate_workflow(&workflow).unwrap_err().to_string();
+        assert!(err.contains("references non-existent task 'missing'"));
+
+        let workflow = workflow_with(
+            "    loop:\n      type: while\n      condition:\n        type: expression\n        expression: \"loop.remaining +\"\n      max_iterations: 3",
+        );
+        let err = validate_workflow(&workflow).unwrap_err().to_string();
+        assert!(err.contains("Invalid expression 'loop.remaining +'"));
+    }
+
+    #[test]
+    fn test_validate_output_schemas() {
+        let workflow_with = |schema_type: &str| {
+            let yaml = format!(
+                r#"
+name: "Schemas"
+version: "1.0.0"
+tasks:
+  extract:
+    description: "Extract"
+    script:
+      language: bash
+      content: "echo '{{}}'"
+    outputs:
+      person:
+        source:
+          type: task_output
+          task: extract
+        schema:
+          type: {}
+"#,
+                schema_type
+            );
+            crate::dsl::parser::parse_workflow(&yaml).unwrap()
+        };
+
+        assert!(validate_workflow(&workflow_with("object")).is_ok());
+
+        let err = validate_workflow(&workflow_with("thing"))
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("Task 'extract' output 'person': Invalid JSON Schema"));
+    }
+
     #[test]
     fn test_validate_invalid_permission_mode() {
         let mut workflow = DSLWorkflow {
@@ -1923,6 +2615,8 @@ mod tests {
             imports: HashMap::new(),
             notifications: None,
             limits: None,
+            max_concurrency: None,
+            providers: HashMap::new(),
         };
 
         let agent = AgentSpec {
@@ -1940,6 +2634,8 @@ mod tests {
                 allowed_directories: vec![],
             },
             max_turns: None,
+            max_concurrency: None,
+            profile: None,
         };
 
         workflow.agents.insert("agent1".to_string(), agent);
@@ -1950,6 +2646,16 @@ mod tests {
             .unwrap_err()
             .to_string()
             .contains("invalid permission mode"));
+
+        // Bypassing permissions would skip the agent's allowed directories
+        let permissions = &mut workflow.agents.get_mut("agent1").unwrap().permissions;
+        permissions.mode = "bypassPermissions".to_string();
+        assert!(validate_workflow(&workflow).is_ok());
+
+        let permissions = &mut workflow.agents.get_mut("agent1").unwrap().permissions;
+        permissions.allowed_directories = vec!["src".to_string()];
+        let err = validate_workflow(&workflow).unwrap_err().to_string();
+        assert!(err.contains("cannot use permission mode 'bypassPermissions'"));
     }
 
     #[test]
@@ -1977,6 +2683,8 @@ mod tests {
             imports: HashMap::new(),
             notifications: None,
             limits: None,
+            max_concurrency: None,
+            providers: HashMap::new(),
         };
 
         let task = TaskSpec {
@@ -2025,6 +2733,8 @@ mod tests {
             imports: HashMap::new(),
             notifications: None,
             limits: None,
+            max_concurrency: None,
+            providers: HashMap::new(),
         };
 
         let task = TaskSpec {
@@ -2073,6 +2783,8 @@ mod tests {
             imports: HashMap::new(),
             notifications: None,
             limits: None,
+            max_concurrency: None,
+            providers: HashMap::new(),
         };
 
         let task = TaskSpec {
@@ -2125,6 +2837,8 @@ mod tests {
             imports: HashMap::new(),
             notifications: None,
             limits: None,
+            max_concurrency: None,
+            providers: HashMap::new(),
         };
 
         let task = TaskSpec {
@@ -2434,6 +3148,8 @@ mod tests {
                 tools: vec![],
                 permissions: PermissionsSpec::default(),
                 max_turns: None,
+                max_concurrency: None,
+                profile: None,
             },
         );
 
@@ -2518,6 +3234,8 @@ mod tests {
                 tools: vec![],
                 permissions: PermissionsSpec::default(),
                 max_turns: None,
+                max_concurrency: None,
+                profile: None,
             },
         );
 
@@ -2713,6 +3431,8 @@ mod tests {
                 tools: vec![],
                 permissions: PermissionsSpec::default(),
                 max_turns: None,
+                max_concurrency: None,
+                profile: None,
             },
         );
 
@@ -2805,6 +3525,8 @@ mod tests {
                 tools: vec![],
                 permissions: PermissionsSpec::default(),
                 max_turns: None,
+                max_concurrency: None,
+                profile: None,
             },
         );
 
@@ -2910,6 +3632,8 @@ mod tests {
                 tools: vec![],
                 permissions: PermissionsSpec::default(),
                 max_turns: None,
+                max_concurrency: None,
+                profile: None,
             },
         );
 
@@ -3070,6 +3794,8 @@ mod tests {
                 tools: vec![],
                 permissions: PermissionsSpec::default(),
                 max_turns: None,
+                max_concurrency: None,
+                profile: None,
             },
         );
 
@@ -3365,6 +4091,8 @@ mod tests {
                 tools: vec![],
                 permissions: PermissionsSpec::default(),
                 max_turns: None,
+                max_concurrency: None,
+                profile: None,
             },
         );
 
@@ -3418,6 +4146,8 @@ mod tests {
                 tools: vec![],
                 permissions: PermissionsSpec::default(),
                 max_turns: None,
+                max_concurrency: None,
+                profile: None,
             },
         );
 
@@ -3470,6 +4200,8 @@ mod tests {
                 tools: vec![],
                 permissions: PermissionsSpec::default(),
                 max_turns: None,
+                max_concurrency: None,
+                profile: None,
             },
         );
 
@@ -3486,6 +4218,8 @@ mod tests {
             tools: vec![],
             permissions: PermissionsSpec::default(),
             max_turns: None,
+            max_concurrency: None,
+            profile: None,
         };
 
         let mut subflow_agents = HashMap::new();
@@ -3565,6 +4299,8 @@ mod tests {
                 tools: vec![],
                 permissions: PermissionsSpec::default(),
                 max_turns: None,
+                max_concurrency: None,
+                profile: None,
             },
         );
 
@@ -3701,6 +4437,8 @@ mod tests {
             tools: vec![],
             permissions: PermissionsSpec::default(),
             max_turns: None,
+            max_concurrency: None,
+            profile: None,
         };
 
         let mut subflow_agents = HashMap::new();
@@ -3,6 +3,7 @@
 //! This module provides variable management for the DSL, including:
 //! - Variable context management across workflow/agent/task scopes
 //! - Variable interpolation in strings using ${scope.var} or ${var} syntax
+//! - Nested access into JSON values, e.g. ${task.fetch.output.items[0].id}
 //! - Type-safe variable resolution
 //! - Scope hierarchy and variable shadowing
 
@@ -13,16 +14,68 @@ use std::collections::HashMap;
 use std::sync::OnceLock;
 
 /// Regular expression for matching variable references in strings
-/// Matches: ${scope.variable_name} or ${variable_name}
+/// Matches: ${scope.variable_name}, ${variable_name} or ${scope.name.path[0].field}
 static VAR_REGEX: OnceLock<Regex> = OnceLock::new();
 
 fn get_var_regex() -> &'static Regex {
     VAR_REGEX.get_or_init(|| {
-        Regex::new(r"\$\{([a-zA-Z_][a-zA-Z0-9_]*(?:\.[a-zA-Z_][a-zA-Z0-9_]*)?)\}")
+        Regex::new(r"\$\{([a-zA-Z_][a-zA-Z0-9_]*(?:\.[a-zA-Z0-9_-]+|\[[0-9]+\])*)\}")
             .expect("Invalid variable regex")
     })
 }
 
+/// A step in a variable reference: `.name` or `[index]`
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Segment {
+    Key(String),
+    Index(usize),
+}
+
+/// Split a reference such as `task.fetch.output.items[0].id` into its segments
+fn parse_reference(reference: &str) -> Result<Vec<Segment>> {
+    let invalid = || Error::InvalidInput(format!("Invalid variable reference '{}'", reference));
+    let mut segments = Vec::new();
+    for part in reference.split('.') {
+        let (key, mut indexes) = match part.find('[') {
+            Some(pos) => (&part[..pos], &part[pos..]),
+            None => (part, ""),
+        };
+        if !key.is_empty() {
+            segments.push(Segment::Key(key.to_string()));
+        } else if indexes.is_empty() {
+            return Err(invalid());
+        }
+        while let Some(rest) = indexes.strip_prefix('[') {
+            let end = rest.find(']').ok_or_else(invalid)?;
+            let index = rest[..end].parse().map_err(|_| invalid())?;
+            segments.push(Segment::Index(index));
+            indexes = &rest[end + 1..];
+        }
+        if !indexes.is_empty() {
+            return Err(invalid());
+        }
+    }
+    Ok(segments)
+}
+
+/// Follow a path of segments into a JSON value
+fn lookup_path<'a>(value: &'a Value, path: &[Segment]) -> Option<&'a Value> {
+    path.iter()
+        .try_fold(value, |current, segment| match segment {
+            Segment::Key(key) => current.get(key.as_str()),
+            Segment::Index(index) => current.get(*index),
+        })
+}
+
+/// Resolve a `.field` / `[index]` path (e.g. `.items[0].id`) inside a JSON value
+pub fn resolve_path<'a>(value: &'a Value, path: &str) -> Option<&'a Value> {
+    let path = path.strip_prefix('.').unwrap_or(path);
+    if path.is_empty() {
+        return Some(value);
+    }
+    lookup_path(value, &parse_reference(path).ok()?)
+}
+
 /// Variable scope identifier
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum Scope {
@@ -129,6 +182,10 @@ impl VariableContext {
 
     /// Resolve a variable reference (e.g., "workflow.var", "secret.name", or "var")
     ///
+    /// Qualified references may continue into the variable's JSON value with
+    /// `.field` and `[index]` steps. `task.<id>.<name>` refers to a variable of
+    /// another task, such as `${task.fetch.output.items[0].id}`.
+    ///
     /// # Arguments
     ///
     /// * `reference` - The variable reference (with or without scope prefix)
@@ -137,74 +194,97 @@ impl VariableContext {
     ///
     /// The variable value if found
     pub fn resolve(&self, reference: &str) -> Result<&Value> {
-        if let Some((scope_prefix, var_name)) = reference.split_once('.') {
-            // Qualified reference: scope.variable
-            let scope = match scope_prefix {
-                "workflow" => Scope::Workflow,
-                "secret" => Scope::Secret,
-                "agent" => {
-                    if let Some(Scope::Agent(name)) = &self.current_scope {
-                        Scope::Agent(name.clone())
-                    } else {
-                        return Err(Error::InvalidInput(format!(
-                            "Cannot resolve agent variable '{}' outside agent scope",
-                            reference
-                        )));
+        let segments = parse_reference(reference)?;
+        let not_found =
+            || Error::InvalidInput(format!("Variable '{}' not found in context", reference));
+
+        match segments.as_slice() {
+            [Segment::Key(scope_prefix), Segment::Key(var_name), path @ ..] => {
+                // Qualified reference: scope.variable[.path]
+                let scope = match scope_prefix.as_str() {
+                    "workflow" => Scope::Workflow,
+                    "secret" => Scope::Secret,
+                    "agent" => {
+                        if let Some(Scope::Agent(name)) = &self.current_scope {
+                            Scope::Agent(name.clone())
+                        } else {
+                            return Err(Error::InvalidInput(format!(
+                                "Cannot resolve agent variable '{}' outside agent scope",
+                                reference
+                            )));
+                        }
                     }
-                }
-                "task" => {
-                    if let Some(Scope::Task(name)) = &self.current_scope {
-                        Scope::Task(name.clone())
-                    } else {
-                        return Err(Error::InvalidInput(format!(
-                            "Cannot resolve task variable '{}' outside task scope",
-                            reference
-                        )));
+                    "task" => {
+                        // Another task's variable: task.<id>.<name>[.path]
+                        if let [Segment::Key(name), path @ ..] = path {
+                            let other = Scope::Task(var_name.clone());
+                            let shadowed = matches!(
+                                &self.current_scope,
+                                Some(current @ Scope::Task(_)) if self.contains(current, var_name)
+                            );
+                            if !shadowed && self.contains(&other, name) {
+                                return self
+                                    .get(Some(&other), name)
+                                    .and_then(|value| lookup_path(value, path))
+                                    .ok_or_else(not_found);
+                            }
+                        }
+                        if let Some(Scope::Task(name)) = &self.current_scope {
+                            Scope::Task(name.clone())
+                        } else {
+                            return Err(Error::InvalidInput(format!(
+                                "Cannot resolve task variable '{}' outside task scope",
+                                reference
+                            )));
+                        }
                     }
-                }
-                "subflow" => {
-                    if let Some(Scope::Subflow(name)) = &self.current_scope {
-                        Scope::Subflow(name.clone())
-                    } else {
-                        return Err(Error::InvalidInput(format!(
-                            "Cannot resolve subflow variable '{}' outside subflow scope",
-                            reference
-                        )));
+                    "subflow" => {
+                        if let Some(Scope::Subflow(name)) = &self.current_scope {
+                            Scope::Subflow(name.clone())
+                        } else {
+                            return Err(Error::InvalidInput(format!(
+                                "Cannot resolve subflow variable '{}' outside subflow scope",
+                                reference
+                            )));
+                        }
                     }
-                }
-                "loop" => {
-                    if let Some(Scope::Loop(name)) = &self.current_scope {
-                        Scope::Loop(name.clone())
-                    } else {
+                    "loop" => {
+                        if let Some(Scope::Loop(name)) = &self.current_scope {
+                            Scope::Loop(name.clone())
+                        } else {
+                            return Err(Error::InvalidInput(format!(
+                                "Cannot resolve loop variable '{}' outside loop scope",
+                                reference
+                            )));
+                        }
+                    }
+                    _ => {
                         return Err(Error::InvalidInput(format!(
-                            "Cannot resolve loop variable '{}' outside loop scope",
-                            reference
-                        )));
+                            "Unknown scope prefix '{}' in variable reference '{}'",
+                            scope_prefix, reference
+                        )))
                     }
-                }
-                _ => {
-                    return Err(Error::InvalidInput(format!(
-                        "Unknown scope prefix '{}' in variable reference '{}'",
-                        scope_prefix, reference
-                    )))
-                }
-            };
+                };
 
-            self.get(Some(&scope), var_name).ok_or_else(|| {
-                Error::InvalidInput(format!("Variable '{}' not found in context", reference))
-            })
-        } else {
-            // Unqualified reference: try current scope, then workflow scope, but not secrets
-            if let Some(current) = &self.current_scope {
-                if let Some(value) = self.get(Some(current), reference) {
-                    return Ok(value);
-                }
+                self.get(Some(&scope), var_name)
+                    .and_then(|value| lookup_path(value, path))
+                    .ok_or_else(not_found)
             }
+            [Segment::Key(name)] => {
+                // Unqualified reference: try current scope, then workflow scope, but not secrets
+                if let Some(current) = &self.current_scope {
+                    if let Some(value) = self.get(Some(current), name) {
+                        return Ok(value);
+                    }
+                }
 
-            // Fall back to workflow scope (secrets must be explicitly qualified)
-            self.get(Some(&Scope::Workflow), reference).ok_or_else(|| {
-                Error::InvalidInput(format!("Variable '{}' not found in context", reference))
-            })
+                // Fall back to workflow scope (secrets must be explicitly qualified)
+                self.get(Some(&Scope::Workflow), name).ok_or_else(not_found)
+            }
+            _ => Err(Error::InvalidInput(format!(
+                "Invalid variable reference '{}'",
+                reference
+            ))),
         }
     }
 
@@ -244,6 +324,11 @@ impl VariableContext {
         Ok(result)
     }
 
+    /// Record a task's structured output for `${task.<id>.output...}` references
+    pub fn insert_task_output(&mut self, task_id: &str, output: Value) {
+        self.insert(&Scope::Task(task_id.to_string()), "output", output);
+    }
+
     /// Check if a variable exists in the context
     pub fn contains(&self, scope: &Scope, name: &str) -> bool {
         let key = Self::make_key(scope, name);
@@ -310,7 +395,7 @@ impl Default for VariableContext {
 }
 
 /// Convert a JSON value to a string for interpolation
-fn value_to_string(value: &Value) -> String {
+pub(crate) fn value_to_string(value: &Value) -> String {
     match value {
         Value::String(s) => s.clone(),
         Value::Number(n) => n.to_string(),
@@ -540,4 +625,53 @@ mod tests {
         assert_eq!(agent_vars.len(), 1);
         assert_eq!(agent_vars.get("var3"), Some(&json!("value3")));
     }
+
+    #[test]
+    fn test_nested_task_output_reference() {
+        let mut ctx = VariableContext::new();
+        ctx.insert_task_output(
+            "fetch",
+            json!({"items": [{"id": 42, "tags": ["a", "b"]}], "total": 1}),
+        );
+
+        assert_eq!(
+            ctx.interpolate("id=${task.fetch.output.items[0].id}")
+                .unwrap(),
+            "id=42"
+        );
+        assert_eq!(
+            ctx.interpolate("${task.fetch.output.items[0].tags[1]}")
+                .unwrap(),
+            "b"
+        );
+        assert_eq!(ctx.resolve("task.fetch.output.total").unwrap(), &json!(1));
+        assert!(ctx.resolve("task.fetch.output.items[3].id").is_err());
+    }
+
+    #[test]
+    fn test_task_output_reference_from_other_task_scope() {
+        let mut ctx = VariableContext::new();
+        ctx.insert_task_output("fetch", json!({"name": "report"}));
+        ctx.set_current_scope(Scope::Task("publish".into()));
+        ctx.insert(&Scope::Task("publish".into()), "title", json!("Weekly"));
+
+        assert_eq!(
+            ctx.interpolate("${task.title}: ${task.fetch.output.name}")
+                .unwrap(),
+            "Weekly: report"
+        );
+    }
+
+    #[test]
+    fn test_resolve_path() {
+        let value = json!({"body": {"items": [{"id": "x1"}]}});
+
+        assert_eq!(
+            resolve_path(&value, ".body.items[0].id"),
+            Some(&json!("x1"))
+        );
+        assert_eq!(resolve_path(&value, ""), Some(&value));
+        assert_eq!(resolve_path(&value, ".body.missing"), None);
+        assert_eq!(resolve_path(&value, ".body.items[x]"), None);
+    }
 }
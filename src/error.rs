@@ -53,6 +53,12 @@ pub enum Error {
     #[error("Invalid input: {0}")]
     InvalidInput(String),
 
+    #[error("Execution cancelled")]
+    Cancelled,
+
+    #[error("Budget exceeded: {0}")]
+    BudgetExceeded(String),
+
     // Predefined Tasks errors
     #[error("Task '{name}' not found in source '{source_name}'")]
     TaskNotFound {
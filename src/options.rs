@@ -1,11 +1,13 @@
 use crate::domain::{
     HookEvent, HookMatcher, PermissionMode, PermissionResult, Provider, ToolPermissionContext,
 };
+use crate::ports::secondary::McpServer;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::future::Future;
 use std::path::PathBuf;
 use std::pin::Pin;
+use std::sync::Arc;
 
 pub type SettingSource = String;
 
@@ -24,6 +26,7 @@ pub struct AgentOptions {
 
     // MCP Servers
     pub mcp_servers: HashMap<String, McpServerConfig>,
+    pub sdk_mcp_servers: SdkMcpServers,
 
     // Advanced options
     pub continue_conversation: bool,
@@ -105,6 +108,24 @@ pub type CanUseToolCallback = std::sync::Arc<
 
 pub type StderrCallback = std::sync::Arc<dyn Fn(String) + Send + Sync>;
 
+/// In-process MCP servers by name
+pub type SdkMcpServers = HashMap<String, Arc<dyn McpServer>>;
+
+impl AgentOptions {
+    /// Offer the tools of an in-process MCP server to the CLI
+    ///
+    /// The server is declared as an `sdk` server under its name, and the CLI's
+    /// MCP requests reach it through the control protocol, so no separate
+    /// process is started. Its tools are named `mcp__<server>__<tool>`.
+    /// In-process servers need the streaming `PeriplonSDKClient`.
+    pub fn add_sdk_mcp_server(&mut self, server: Arc<dyn McpServer>) {
+        let name = server.name().to_string();
+        self.mcp_servers
+            .insert(name.clone(), McpServerConfig::Sdk { name: name.clone() });
+        self.sdk_mcp_servers.insert(name, server);
+    }
+}
+
 // Manual Debug implementation for AgentOptions since callbacks don't implement Debug
 impl std::fmt::Debug for AgentOptions {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
@@ -120,6 +141,10 @@ impl std::fmt::Debug for AgentOptions {
             .field("create_cwd", &self.create_cwd)
             .field("cli_path", &self.cli_path)
             .field("mcp_servers", &self.mcp_servers)
+            .field(
+                "sdk_mcp_servers",
+                &self.sdk_mcp_servers.keys().collect::<Vec<_>>(),
+            )
             .field("continue_conversation", &self.continue_conversation)
             .field("resume", &self.resume)
             .field(
@@ -1,12 +1,61 @@
 //! LLM Client Port
 //!
 //! Secondary port for direct LLM API interactions. This port defines the
-//! interface for calling LLM providers (Ollama, OpenAI, Anthropic, Google)
-//! without going through CLI subprocesses.
+//! interface for calling LLM providers (Ollama, OpenAI, Anthropic, Google,
+//! Azure OpenAI and OpenAI-compatible servers) without going through CLI
+//! subprocesses.
 
-use crate::domain::Provider;
+use crate::domain::{AuthStyle, Provider};
 use async_trait::async_trait;
+use futures::Stream;
 use std::collections::HashMap;
+use std::pin::Pin;
+
+/// Role of a message in a conversation
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LlmRole {
+    /// Instructions for the model
+    System,
+    /// Input from the user
+    User,
+    /// A previous answer of the model
+    Assistant,
+}
+
+/// A message of a conversation
+#[derive(Debug, Clone, PartialEq)]
+pub struct LlmMessage {
+    /// Who the message is from
+    pub role: LlmRole,
+    /// Message text
+    pub content: String,
+}
+
+impl LlmMessage {
+    /// Create a system message
+    pub fn system(content: impl Into<String>) -> Self {
+        Self {
+            role: LlmRole::System,
+            content: content.into(),
+        }
+    }
+
+    /// Create a user message
+    pub fn user(content: impl Into<String>) -> Self {
+        Self {
+            role: LlmRole::User,
+            content: content.into(),
+        }
+    }
+
+    /// Create an assistant message
+    pub fn assistant(content: impl Into<String>) -> Self {
+        Self {
+            role: LlmRole::Assistant,
+            content: content.into(),
+        }
+    }
+}
 
 /// LLM request configuration
 #[derive(Debug, Clone)]
@@ -23,6 +72,12 @@ pub struct LlmRequest {
     pub endpoint: Option<String>,
     /// API key (optional, will try environment variable if not provided)
     pub api_key: Option<String>,
+    /// How the API key is sent (optional, uses the provider default if not specified)
+    pub auth: Option<AuthStyle>,
+    /// Header carrying the key with [`AuthStyle::ApiKey`] (default: `api-key`)
+    pub auth_header: Option<String>,
+    /// Azure OpenAI API version (`api-version` query parameter)
+    pub api_version: Option<String>,
     /// Temperature for sampling
     pub temperature: Option<f64>,
     /// Maximum tokens to generate
@@ -37,6 +92,89 @@ pub struct LlmRequest {
     pub timeout_secs: Option<u64>,
     /// Additional provider-specific parameters
     pub extra_params: HashMap<String, serde_json::Value>,
+    /// JSON Schema the response must conform to (structured output)
+    pub response_schema: Option<serde_json::Value>,
+    /// Earlier turns of the conversation, sent between the system prompt and `prompt`
+    pub messages: Vec<LlmMessage>,
+    /// Tools the model may call (see [`LlmClient::execute_with_tools`])
+    pub tools: Vec<LlmToolDefinition>,
+    /// Maximum number of tool-calling rounds before giving up
+    pub max_tool_iterations: Option<u32>,
+}
+
+impl LlmRequest {
+    /// The full conversation: system prompt, earlier turns, then `prompt` as
+    /// the latest user message (omitted when empty)
+    pub fn conversation(&self) -> Vec<LlmMessage> {
+        let mut conversation = Vec::with_capacity(self.messages.len() + 2);
+        if let Some(system) = &self.system_prompt {
+            conversation.push(LlmMessage::system(system.clone()));
+        }
+        conversation.extend(self.messages.iter().cloned());
+        if !self.prompt.is_empty() {
+            conversation.push(LlmMessage::user(self.prompt.clone()));
+        }
+        conversation
+    }
+}
+
+/// Default limit on tool-calling rounds per request
+pub const DEFAULT_MAX_TOOL_ITERATIONS: u32 = 10;
+
+/// A tool offered to the model
+#[derive(Debug, Clone, PartialEq)]
+pub struct LlmToolDefinition {
+    /// Tool name, unique within a request
+    pub name: String,
+    /// What the tool does, shown to the model
+    pub description: String,
+    /// JSON Schema of the tool's arguments
+    pub parameters: serde_json::Value,
+}
+
+/// A tool invocation requested by the model
+#[derive(Debug, Clone, PartialEq)]
+pub struct LlmToolCall {
+    /// Provider-assigned call id (the tool name when the provider has none)
+    pub id: String,
+    /// Name of the tool to call
+    pub name: String,
+    /// Arguments chosen by the model
+    pub arguments: serde_json::Value,
+}
+
+/// Result of a tool call, sent back to the model
+#[derive(Debug, Clone, PartialEq)]
+pub struct LlmToolOutput {
+    /// Output text
+    pub content: String,
+    /// Whether the call failed; the model sees the error and may recover
+    pub is_error: bool,
+}
+
+impl LlmToolOutput {
+    /// Successful output
+    pub fn ok(content: impl Into<String>) -> Self {
+        Self {
+            content: content.into(),
+            is_error: false,
+        }
+    }
+
+    /// Failed output
+    pub fn error(content: impl Into<String>) -> Self {
+        Self {
+            content: content.into(),
+            is_error: true,
+        }
+    }
+}
+
+/// Executes the tool calls made by a model
+#[async_trait]
+pub trait LlmToolHandler: Send + Sync {
+    /// Run a tool call and return its output
+    async fn call_tool(&self, call: &LlmToolCall) -> LlmToolOutput;
 }
 
 /// LLM response
@@ -56,6 +194,18 @@ pub struct LlmResponse {
     pub raw_response: Option<serde_json::Value>,
 }
 
+/// Event of a streamed LLM response
+#[derive(Debug, Clone)]
+pub enum LlmStreamEvent {
+    /// A chunk of generated text
+    Delta(String),
+    /// The response is complete; `content` holds the concatenated deltas
+    Done(LlmResponse),
+}
+
+/// Stream of LLM response events, ending with [`LlmStreamEvent::Done`]
+pub type LlmStream = Pin<Box<dyn Stream<Item = Result<LlmStreamEvent, LlmError>> + Send>>;
+
 /// Token usage information
 #[derive(Debug, Clone)]
 pub struct TokenUsage {
@@ -109,6 +259,40 @@ pub trait LlmClient: Send + Sync {
     /// Execute an LLM request
     async fn execute(&self, request: LlmRequest) -> Result<LlmResponse, LlmError>;
 
+    /// Execute an LLM request, streaming the response as it is generated
+    ///
+    /// The default implementation waits for [`LlmClient::execute`] and emits the
+    /// whole response as a single delta.
+    async fn execute_stream(&self, request: LlmRequest) -> Result<LlmStream, LlmError> {
+        let response = self.execute(request).await?;
+        let events = vec![
+            Ok(LlmStreamEvent::Delta(response.content.clone())),
+            Ok(LlmStreamEvent::Done(response)),
+        ];
+        Ok(Box::pin(futures::stream::iter(events)))
+    }
+
+    /// Execute an LLM request, running the tool calls the model makes
+    ///
+    /// Tool calls are passed to `handler` and their outputs sent back to the
+    /// model until it answers without calling a tool, or until
+    /// `max_tool_iterations` rounds have passed. The returned usage covers all
+    /// rounds. The default implementation only supports requests without tools.
+    async fn execute_with_tools(
+        &self,
+        request: LlmRequest,
+        handler: &dyn LlmToolHandler,
+    ) -> Result<LlmResponse, LlmError> {
+        let _ = handler;
+        if !request.tools.is_empty() {
+            return Err(LlmError::InvalidConfig(format!(
+                "{} does not support tool calling",
+                self.name()
+            )));
+        }
+        self.execute(request).await
+    }
+
     /// Check if the provider is supported by this client
     fn supports_provider(&self, provider: &Provider) -> bool;
 
@@ -15,6 +15,24 @@ pub struct ToolResult {
     pub is_error: bool,
 }
 
+impl ToolResult {
+    /// Successful result holding one text block
+    pub fn text(text: impl Into<String>) -> Self {
+        Self {
+            content: serde_json::json!([{"type": "text", "text": text.into()}]),
+            is_error: false,
+        }
+    }
+
+    /// Failed result holding one text block with the error message
+    pub fn error(message: impl Into<String>) -> Self {
+        Self {
+            is_error: true,
+            ..Self::text(message)
+        }
+    }
+}
+
 #[async_trait]
 pub trait McpServer: Send + Sync {
     fn name(&self) -> &str;
@@ -2,6 +2,9 @@ use crate::error::Result;
 use async_trait::async_trait;
 use futures::Stream;
 use std::pin::Pin;
+use std::sync::Arc;
+use tokio::process::ChildStdin;
+use tokio::sync::Mutex;
 
 #[async_trait]
 pub trait Transport: Send + Sync {
@@ -24,4 +27,11 @@ pub trait Transport: Send + Sync {
 
     /// Check if transport is ready
     fn is_ready(&self) -> bool;
+
+    /// Get a clone of the process stdin for writing while messages are read
+    ///
+    /// Transports without a child process return `None`.
+    fn get_stdin(&self) -> Option<Arc<Mutex<ChildStdin>>> {
+        None
+    }
 }
@@ -20,7 +20,7 @@ use uuid::Uuid;
 
 #[cfg(feature = "server")]
 use crate::server::{
-    auth::jwt::Claims,
+    auth::{api_key, jwt::Claims},
     storage::{ApiKey, ApiKeyFilter, Storage},
 };
 
@@ -32,6 +32,8 @@ pub struct CreateApiKeyRequest {
     pub name: Option<String>,
     pub description: Option<String>,
     pub scopes: Vec<String>,
+    #[serde(default)]
+    pub ip_whitelist: Vec<String>,
     pub expires_in_days: Option<i64>,
 }
 
@@ -52,6 +54,7 @@ pub struct UpdateApiKeyRequest {
     pub name: Option<String>,
     pub description: Option<String>,
     pub scopes: Option<Vec<String>>,
+    pub ip_whitelist: Option<Vec<String>>,
 }
 
 #[cfg(feature = "server")]
@@ -62,6 +65,7 @@ pub struct ApiKeyResponse {
     pub name: Option<String>,
     pub description: Option<String>,
     pub scopes: Vec<String>,
+    pub ip_whitelist: Vec<String>,
     pub created_at: chrono::DateTime<chrono::Utc>,
     pub expires_at: Option<chrono::DateTime<chrono::Utc>>,
     pub last_used_at: Option<chrono::DateTime<chrono::Utc>>,
@@ -77,6 +81,7 @@ impl From<ApiKey> for ApiKeyResponse {
             name: key.name,
             description: key.description,
             scopes: key.scopes,
+            ip_whitelist: key.ip_whitelist,
             created_at: key.created_at,
             expires_at: key.expires_at,
             last_used_at: key.last_used_at,
@@ -138,20 +143,17 @@ pub async fn create_api_key(
         }
     };
 
+    if let Some(response) = invalid_ip_whitelist(&payload.ip_whitelist) {
+        return response;
+    }
+
     // Generate random API key
-    let key_bytes: [u8; 32] = rand::random();
-    use base64::{engine::general_purpose, Engine as _};
-    let key = format!("sk_{}", general_purpose::URL_SAFE_NO_PAD.encode(key_bytes));
+    let key = api_key::generate_api_key();
 
     // Hash the key for storage
-    let key_hash = {
-        use sha2::{Digest, Sha256};
-        let mut hasher = Sha256::new();
-        hasher.update(key.as_bytes());
-        format!("{:x}", hasher.finalize())
-    };
+    let key_hash = api_key::hash_api_key(&key);
 
-    let key_prefix = key.chars().take(12).collect::<String>();
+    let key_prefix = api_key::key_prefix(&key);
 
     let expires_at = payload
         .expires_in_days
@@ -165,6 +167,7 @@ pub async fn create_api_key(
         name: payload.name.clone(),
         description: payload.description,
         scopes: payload.scopes.clone(),
+        ip_whitelist: payload.ip_whitelist,
         created_at: Utc::now(),
         expires_at,
         last_used_at: None,
@@ -284,6 +287,12 @@ pub async fn update_api_key(
     if let Some(scopes) = payload.scopes {
         api_key.scopes = scopes;
     }
+    if let Some(ip_whitelist) = payload.ip_whitelist {
+        if let Some(response) = invalid_ip_whitelist(&ip_whitelist) {
+            return response;
+        }
+        api_key.ip_whitelist = ip_whitelist;
+    }
 
     match storage.update_api_key(id, &api_key).await {
         Ok(_) => {
@@ -390,18 +399,9 @@ pub async fn rotate_api_key(
     }
 
     // Generate new key
-    let key_bytes: [u8; 32] = rand::random();
-    use base64::{engine::general_purpose, Engine as _};
-    let new_key = format!("sk_{}", general_purpose::URL_SAFE_NO_PAD.encode(key_bytes));
-
-    let new_key_hash = {
-        use sha2::{Digest, Sha256};
-        let mut hasher = Sha256::new();
-        hasher.update(new_key.as_bytes());
-        format!("{:x}", hasher.finalize())
-    };
-
-    let new_key_prefix = new_key.chars().take(12).collect::<String>();
+    let new_key = api_key::generate_api_key();
+    let new_key_hash = api_key::hash_api_key(&new_key);
+    let new_key_prefix = api_key::key_prefix(&new_key);
 
     // Create new API key with same metadata
     let new_api_key = ApiKey {
@@ -412,6 +412,7 @@ pub async fn rotate_api_key(
         name: old_key.name.clone(),
         description: old_key.description.clone(),
         scopes: old_key.scopes.clone(),
+        ip_whitelist: old_key.ip_whitelist.clone(),
         created_at: Utc::now(),
         expires_at: old_key.expires_at,
         last_used_at: None,
@@ -443,3 +444,19 @@ pub async fn rotate_api_key(
         ),
     }
 }
+
+/// Error response for whitelist entries that are not addresses or CIDR blocks
+#[cfg(feature = "server")]
+fn invalid_ip_whitelist(ip_whitelist: &[String]) -> Option<(StatusCode, Json<serde_json::Value>)> {
+    let invalid: Vec<&String> = ip_whitelist
+        .iter()
+        .filter(|entry| !api_key::is_valid_ip_entry(entry))
+        .collect();
+
+    (!invalid.is_empty()).then(|| {
+        (
+            StatusCode::BAD_REQUEST,
+            Json(json!({"error": format!("Invalid IP whitelist entries: {:?}", invalid)})),
+        )
+    })
+}
@@ -1,7 +1,11 @@
 // Authentication handlers
 
 #[cfg(feature = "server")]
-use axum::{http::StatusCode, response::IntoResponse, Extension, Json};
+use axum::{
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Extension, Json,
+};
 #[cfg(feature = "server")]
 use chrono::Utc;
 #[cfg(feature = "server")]
@@ -14,9 +18,13 @@ use std::sync::Arc;
 use uuid::Uuid;
 
 #[cfg(feature = "server")]
-use crate::server::auth::{Claims, JwtManager};
+use crate::server::auth::{Claims, JwtManager, MfaService};
+#[cfg(feature = "server")]
+use crate::server::storage::MfaStorage;
 #[cfg(feature = "server")]
 use crate::server::storage::{password, User, UserStorage};
+#[cfg(feature = "server")]
+use crate::server::Storage;
 
 // Note: get_current_user and refresh_token require authentication middleware
 // to populate the Claims extension. They will fail without it.
@@ -54,10 +62,13 @@ pub struct UserInfo {
 
 /// Login endpoint
 /// Authenticates user with email and password
+/// Users with MFA get a challenge token to complete at /auth/mfa/verify
 #[cfg(feature = "server")]
 pub async fn login(
     Extension(user_storage): Extension<Arc<dyn UserStorage>>,
     Extension(jwt_manager): Extension<Arc<JwtManager>>,
+    mfa: Option<Extension<Arc<MfaService>>>,
+    storage: Option<Extension<Arc<dyn Storage>>>,
     Json(payload): Json<LoginRequest>,
 ) -> impl IntoResponse {
     // Get user by email
@@ -97,7 +108,16 @@ pub async fn login(
     // Verify password
     match password::verify_password(&payload.password, &user.password_hash) {
         Ok(true) => {
-            // Password is correct, update last login
+            // Password is correct; users with MFA still need a code
+            let mfa = mfa.as_ref().map(|Extension(mfa)| mfa.as_ref());
+            let settings = storage
+                .as_ref()
+                .map(|Extension(storage)| storage.as_ref() as &dyn MfaStorage);
+            if let Some(response) = mfa_required(mfa, settings, user.id).await {
+                return response;
+            }
+
+            // Update last login
             if let Err(e) = user_storage.update_last_login(user.id).await {
                 eprintln!("Failed to update last login: {}", e);
             }
@@ -302,3 +322,144 @@ pub async fn refresh_token(
             .into_response(),
     }
 }
+
+/// Check whether a login must stop for a second factor
+///
+/// Returns the response to answer with instead of a JWT: a challenge token
+/// when the user has MFA, or an error when their MFA state cannot be read.
+/// Without an `MfaService`, users who enrolled before MFA was switched off
+/// are refused, since their login can no longer ask for the code.
+#[cfg(feature = "server")]
+pub(crate) async fn mfa_required(
+    mfa: Option<&MfaService>,
+    settings: Option<&dyn MfaStorage>,
+    user_id: Uuid,
+) -> Option<Response> {
+    let Some(mfa) = mfa else {
+        // Backends without MFA storage have no enrollments to enforce
+        let enrolled = match settings {
+            Some(settings) => matches!(
+                settings.get_mfa_settings(user_id).await,
+                Ok(Some(settings)) if settings.is_enabled
+            ),
+            None => false,
+        };
+        if !enrolled {
+            return None;
+        }
+        tracing::warn!(
+            "Refusing login of user {}: MFA is enrolled but auth.mfa_enabled is off",
+            user_id
+        );
+        return Some(
+            (
+                StatusCode::FORBIDDEN,
+                Json(json!({
+                    "error": "Multi-factor authentication is enrolled for this account but disabled on the server"
+                })),
+            )
+                .into_response(),
+        );
+    };
+
+    match mfa.is_enabled(user_id).await {
+        Ok(true) => Some(mfa_challenge_response(mfa, user_id)),
+        Ok(false) => None,
+        Err(e) => Some(
+            (
+                e.status(),
+                Json(json!({
+                    "error": e.to_string()
+                })),
+            )
+                .into_response(),
+        ),
+    }
+}
+
+/// Answer a first login step of a user with MFA
+#[cfg(feature = "server")]
+fn mfa_challenge_response(mfa: &MfaService, user_id: Uuid) -> Response {
+    match mfa.issue_challenge(user_id) {
+        Ok(challenge_token) => (
+            StatusCode::OK,
+            Json(json!({
+                "mfa_required": true,
+                "challenge_token": challenge_token,
+                "expires_in": mfa.challenge_ttl_secs(),
+            })),
+        )
+            .into_response(),
+        Err(e) => (
+            e.status(),
+            Json(json!({
+                "error": e.to_string()
+            })),
+        )
+            .into_response(),
+    }
+}
+
+/// Answer a completed login with a JWT for the user
+#[cfg(feature = "server")]
+pub(crate) fn token_response(jwt_manager: &JwtManager, user: User) -> Response {
+    match jwt_manager.generate_token(&user.id.to_string(), &user.email, user.roles.clone()) {
+        Ok(token) => {
+            let response = AuthResponse {
+                token,
+                user: UserInfo {
+                    id: user.id.to_string(),
+                    email: user.email,
+                    name: user.name,
+                    roles: user.roles,
+                },
+            };
+            (StatusCode::OK, Json(response)).into_response()
+        }
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": format!("Failed to generate token: {}", e)
+            })),
+        )
+            .into_response(),
+    }
+}
+
+#[cfg(all(test, feature = "server"))]
+mod tests {
+    use super::*;
+    use crate::server::storage::MfaSettings;
+    use crate::testing::MockMfaStorage;
+
+    #[tokio::test]
+    async fn test_mfa_required_refuses_enrolled_users_without_mfa_service() {
+        let settings = MockMfaStorage::new();
+        let enrolled = Uuid::new_v4();
+        settings
+            .upsert_mfa_settings(&MfaSettings {
+                id: Uuid::new_v4(),
+                user_id: enrolled,
+                method: "totp".to_string(),
+                secret_encrypted: None,
+                recovery_code_hashes: Vec::new(),
+                is_enabled: true,
+                last_used_step: None,
+                failed_attempts: 0,
+                locked_until: None,
+                verified_at: Some(Utc::now()),
+                created_at: Utc::now(),
+            })
+            .await
+            .unwrap();
+
+        let response = mfa_required(None, Some(&settings), enrolled).await.unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+        // Users who never enrolled log in with their password alone
+        assert!(mfa_required(None, Some(&settings), Uuid::new_v4())
+            .await
+            .is_none());
+        assert!(mfa_required(None, None, enrolled).await.is_none());
+    }
+}
@@ -18,9 +18,13 @@ use std::sync::Arc;
 #[cfg(feature = "server")]
 use uuid::Uuid;
 
+#[cfg(feature = "server")]
+use crate::dsl::state::Usage;
 #[cfg(feature = "server")]
 use crate::server::auth::jwt::Claims;
 #[cfg(feature = "server")]
+use crate::server::hub::ExecutionHub;
+#[cfg(feature = "server")]
 use crate::server::queue::{Job, WorkQueue};
 #[cfg(feature = "server")]
 use crate::server::{
@@ -56,6 +60,9 @@ pub struct ExecutionResponse {
     pub error: Option<String>,
     pub retry_count: u32,
     pub parent_execution_id: Option<Uuid>,
+    pub completed_tasks: u32,
+    pub total_tasks: u32,
+    pub usage: Usage,
 }
 
 #[cfg(feature = "server")]
@@ -76,6 +83,9 @@ impl From<Execution> for ExecutionResponse {
             error: execution.error,
             retry_count: execution.retry_count,
             parent_execution_id: execution.parent_execution_id,
+            completed_tasks: execution.completed_tasks,
+            total_tasks: execution.total_tasks,
+            usage: execution.usage,
         }
     }
 }
@@ -230,6 +240,9 @@ pub async fn create_execution(
         error: None,
         retry_count: 0,
         parent_execution_id: payload.parent_execution_id,
+        completed_tasks: 0,
+        total_tasks: 0,
+        usage: Default::default(),
     };
 
     // Store execution
@@ -319,10 +332,14 @@ pub async fn get_execution(
 }
 
 /// Cancel a running or queued execution
+///
+/// A worker in this process is signalled directly; workers in other processes
+/// stop once they see the cancelled status in storage.
 #[cfg(feature = "server")]
 pub async fn cancel_execution(
     Path(id): Path<Uuid>,
     Extension(storage): Extension<Arc<dyn Storage>>,
+    Extension(hub): Extension<Arc<ExecutionHub>>,
     Extension(_claims): Extension<Claims>,
 ) -> impl IntoResponse {
     // Get existing execution
@@ -374,15 +391,18 @@ pub async fn cancel_execution(
     execution.completed_at = Some(Utc::now());
 
     match storage.update_execution(id, &execution).await {
-        Ok(_) => (
-            StatusCode::OK,
-            Json(json!({
-                "id": id,
-                "status": "cancelled",
-                "message": "Execution cancelled successfully",
-                "completed_at": execution.completed_at.map(|dt| dt.to_rfc3339())
-            })),
-        ),
+        Ok(_) => {
+            hub.request_cancel(id);
+            (
+                StatusCode::OK,
+                Json(json!({
+                    "id": id,
+                    "status": "cancelled",
+                    "message": "Execution cancelled successfully",
+                    "completed_at": execution.completed_at.map(|dt| dt.to_rfc3339())
+                })),
+            )
+        }
         Err(e) => (
             StatusCode::INTERNAL_SERVER_ERROR,
             Json(json!({
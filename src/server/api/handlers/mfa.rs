@@ -0,0 +1,310 @@
+// Multi-factor authentication handlers
+
+#[cfg(feature = "server")]
+use axum::{
+    extract::{Extension, Path},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+#[cfg(feature = "server")]
+use serde::Deserialize;
+#[cfg(feature = "server")]
+use serde_json::json;
+#[cfg(feature = "server")]
+use std::sync::Arc;
+#[cfg(feature = "server")]
+use uuid::Uuid;
+
+#[cfg(feature = "server")]
+use super::auth::token_response;
+#[cfg(feature = "server")]
+use crate::server::auth::{Claims, JwtManager, MfaError, MfaService};
+#[cfg(feature = "server")]
+use crate::server::storage::UserStorage;
+
+#[cfg(feature = "server")]
+#[derive(Debug, Deserialize)]
+pub struct MfaVerifyRequest {
+    pub challenge_token: String,
+    pub code: String,
+}
+
+#[cfg(feature = "server")]
+#[derive(Debug, Deserialize)]
+pub struct MfaCodeRequest {
+    pub code: String,
+}
+
+/// Complete a login with its challenge token and a TOTP or recovery code
+/// Returns a JWT for the user
+#[cfg(feature = "server")]
+pub async fn verify_login(
+    mfa: Option<Extension<Arc<MfaService>>>,
+    Extension(user_storage): Extension<Arc<dyn UserStorage>>,
+    Extension(jwt_manager): Extension<Arc<JwtManager>>,
+    Json(payload): Json<MfaVerifyRequest>,
+) -> Response {
+    let Some(Extension(mfa)) = mfa else {
+        return not_enabled();
+    };
+
+    let user_id = match mfa
+        .complete_challenge(&payload.challenge_token, &payload.code)
+        .await
+    {
+        Ok(user_id) => user_id,
+        Err(e) => return error_response(e),
+    };
+
+    let user = match user_storage.get_user(user_id).await {
+        Ok(Some(user)) if user.is_active => user,
+        Ok(Some(_)) => {
+            return (
+                StatusCode::FORBIDDEN,
+                Json(json!({
+                    "error": "User account is disabled"
+                })),
+            )
+                .into_response();
+        }
+        Ok(None) => return error_response(MfaError::InvalidChallenge),
+        Err(e) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({
+                    "error": format!("Database error: {}", e)
+                })),
+            )
+                .into_response();
+        }
+    };
+
+    if let Err(e) = user_storage.update_last_login(user.id).await {
+        tracing::warn!("Failed to update last login of user {}: {}", user.id, e);
+    }
+    token_response(&jwt_manager, user)
+}
+
+/// Get the MFA status of the current user
+#[cfg(feature = "server")]
+pub async fn get_status(
+    mfa: Option<Extension<Arc<MfaService>>>,
+    Extension(claims): Extension<Claims>,
+) -> Response {
+    let Some(Extension(mfa)) = mfa else {
+        return not_enabled();
+    };
+    let Some(user_id) = user_id(&claims) else {
+        return invalid_user_id();
+    };
+
+    match mfa.status(user_id).await {
+        Ok(status) => (StatusCode::OK, Json(status)).into_response(),
+        Err(e) => error_response(e),
+    }
+}
+
+/// Start enrolling the current user, returning the secret for their app
+#[cfg(feature = "server")]
+pub async fn enroll(
+    mfa: Option<Extension<Arc<MfaService>>>,
+    Extension(claims): Extension<Claims>,
+) -> Response {
+    let Some(Extension(mfa)) = mfa else {
+        return not_enabled();
+    };
+    let Some(user_id) = user_id(&claims) else {
+        return invalid_user_id();
+    };
+
+    match mfa.enroll(user_id, &claims.email).await {
+        Ok(enrollment) => (StatusCode::OK, Json(enrollment)).into_response(),
+        Err(e) => error_response(e),
+    }
+}
+
+/// Confirm the current user's enrollment with a code from their app
+/// Returns the recovery codes
+#[cfg(feature = "server")]
+pub async fn confirm_enrollment(
+    mfa: Option<Extension<Arc<MfaService>>>,
+    Extension(claims): Extension<Claims>,
+    Json(payload): Json<MfaCodeRequest>,
+) -> Response {
+    let Some(Extension(mfa)) = mfa else {
+        return not_enabled();
+    };
+    let Some(user_id) = user_id(&claims) else {
+        return invalid_user_id();
+    };
+
+    match mfa.confirm(user_id, &payload.code).await {
+        Ok(recovery_codes) => (
+            StatusCode::OK,
+            Json(json!({
+                "enabled": true,
+                "recovery_codes": recovery_codes,
+            })),
+        )
+            .into_response(),
+        Err(e) => error_response(e),
+    }
+}
+
+/// Replace the current user's recovery codes
+#[cfg(feature = "server")]
+pub async fn regenerate_recovery_codes(
+    mfa: Option<Extension<Arc<MfaService>>>,
+    Extension(claims): Extension<Claims>,
+    Json(payload): Json<MfaCodeRequest>,
+) -> Response {
+    let Some(Extension(mfa)) = mfa else {
+        return not_enabled();
+    };
+    let Some(user_id) = user_id(&claims) else {
+        return invalid_user_id();
+    };
+
+    match mfa.regenerate_recovery_codes(user_id, &payload.code).await {
+        Ok(recovery_codes) => (
+            StatusCode::OK,
+            Json(json!({
+                "recovery_codes": recovery_codes,
+            })),
+        )
+            .into_response(),
+        Err(e) => error_response(e),
+    }
+}
+
+/// Turn off MFA for the current user
+#[cfg(feature = "server")]
+pub async fn disable(
+    mfa: Option<Extension<Arc<MfaService>>>,
+    Extension(claims): Extension<Claims>,
+    Json(payload): Json<MfaCodeRequest>,
+) -> Response {
+    let Some(Extension(mfa)) = mfa else {
+        return not_enabled();
+    };
+    let Some(user_id) = user_id(&claims) else {
+        return invalid_user_id();
+    };
+
+    match mfa.disable(user_id, &payload.code).await {
+        Ok(()) => StatusCode::NO_CONTENT.into_response(),
+        Err(e) => error_response(e),
+    }
+}
+
+/// Get the MFA status of a user (admin)
+#[cfg(feature = "server")]
+pub async fn admin_get_status(
+    mfa: Option<Extension<Arc<MfaService>>>,
+    Extension(user_storage): Extension<Arc<dyn UserStorage>>,
+    Path(user_id): Path<Uuid>,
+) -> Response {
+    let Some(Extension(mfa)) = mfa else {
+        return not_enabled();
+    };
+    if let Some(response) = user_not_found(user_storage.as_ref(), user_id).await {
+        return response;
+    }
+
+    match mfa.status(user_id).await {
+        Ok(status) => (StatusCode::OK, Json(status)).into_response(),
+        Err(e) => error_response(e),
+    }
+}
+
+/// Reset the MFA of a user who lost their authenticator (admin)
+/// The user logs in with their password alone until they enroll again
+#[cfg(feature = "server")]
+pub async fn admin_reset(
+    mfa: Option<Extension<Arc<MfaService>>>,
+    Extension(user_storage): Extension<Arc<dyn UserStorage>>,
+    Extension(claims): Extension<Claims>,
+    Path(user_id): Path<Uuid>,
+) -> Response {
+    let Some(Extension(mfa)) = mfa else {
+        return not_enabled();
+    };
+    if let Some(response) = user_not_found(user_storage.as_ref(), user_id).await {
+        return response;
+    }
+
+    match mfa.reset(user_id).await {
+        Ok(()) => {
+            tracing::info!("MFA of user {} reset by {}", user_id, claims.sub);
+            StatusCode::NO_CONTENT.into_response()
+        }
+        Err(e) => error_response(e),
+    }
+}
+
+#[cfg(feature = "server")]
+fn user_id(claims: &Claims) -> Option<Uuid> {
+    Uuid::parse_str(&claims.sub).ok()
+}
+
+#[cfg(feature = "server")]
+async fn user_not_found(user_storage: &dyn UserStorage, user_id: Uuid) -> Option<Response> {
+    match user_storage.get_user(user_id).await {
+        Ok(Some(_)) => None,
+        Ok(None) => Some(
+            (
+                StatusCode::NOT_FOUND,
+                Json(json!({
+                    "error": "User not found"
+                })),
+            )
+                .into_response(),
+        ),
+        Err(e) => Some(
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({
+                    "error": format!("Database error: {}", e)
+                })),
+            )
+                .into_response(),
+        ),
+    }
+}
+
+#[cfg(feature = "server")]
+fn error_response(error: MfaError) -> Response {
+    if error.status().is_server_error() {
+        tracing::warn!("MFA request failed: {}", error);
+    }
+    (
+        error.status(),
+        Json(json!({
+            "error": error.to_string()
+        })),
+    )
+        .into_response()
+}
+
+#[cfg(feature = "server")]
+fn invalid_user_id() -> Response {
+    (
+        StatusCode::BAD_REQUEST,
+        Json(json!({
+            "error": "Invalid user ID"
+        })),
+    )
+        .into_response()
+}
+
+#[cfg(feature = "server")]
+fn not_enabled() -> Response {
+    (
+        StatusCode::NOT_FOUND,
+        Json(json!({
+            "error": "Multi-factor authentication is not enabled on this server"
+        })),
+    )
+        .into_response()
+}
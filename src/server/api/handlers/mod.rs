@@ -24,6 +24,12 @@ pub mod monitoring;
 #[cfg(feature = "server")]
 pub mod auth;
 
+#[cfg(feature = "server")]
+pub mod mfa;
+
+#[cfg(feature = "server")]
+pub mod sso;
+
 #[cfg(feature = "server")]
 pub mod websocket;
 
@@ -1,7 +1,9 @@
 // Monitoring and metrics handlers
 
 #[cfg(feature = "server")]
-use crate::server::storage::WorkflowFilter;
+use crate::dsl::state::Usage;
+#[cfg(feature = "server")]
+use crate::server::storage::{ExecutionStorage, WorkflowFilter};
 #[cfg(feature = "server")]
 use crate::server::{Storage, WorkQueue};
 #[cfg(feature = "server")]
@@ -9,7 +11,13 @@ use axum::{http::StatusCode, response::IntoResponse, Extension, Json};
 #[cfg(feature = "server")]
 use serde_json::json;
 #[cfg(feature = "server")]
+use std::collections::BTreeMap;
+#[cfg(feature = "server")]
+use std::fmt::Write;
+#[cfg(feature = "server")]
 use std::sync::Arc;
+#[cfg(feature = "server")]
+use uuid::Uuid;
 
 /// Health check endpoint
 /// Returns basic health status of the service
@@ -100,8 +108,8 @@ pub async fn liveness_check() -> impl IntoResponse {
 /// Prometheus-compatible metrics endpoint
 /// Returns metrics in Prometheus text format
 #[cfg(feature = "server")]
-pub async fn metrics() -> impl IntoResponse {
-    let metrics = "# HELP workflow_executions_total Total number of workflow executions\n\
+pub async fn metrics(Extension(storage): Extension<Arc<dyn Storage>>) -> impl IntoResponse {
+    let mut metrics = "# HELP workflow_executions_total Total number of workflow executions\n\
          # TYPE workflow_executions_total counter\n\
          workflow_executions_total 0\n\
          \n\
@@ -159,6 +167,7 @@ pub async fn metrics() -> impl IntoResponse {
          system_cpu_usage_percent 0\n\
          "
     .to_string();
+    metrics.push_str(&usage_metrics(storage.as_ref()).await);
 
     (
         StatusCode::OK,
@@ -167,6 +176,52 @@ pub async fn metrics() -> impl IntoResponse {
     )
 }
 
+/// Tokens and cost of all stored executions, per workflow
+///
+/// Publishes no usage series when storage cannot be read, since a partial sum
+/// would make the counters drop.
+#[cfg(feature = "server")]
+async fn usage_metrics<S: ExecutionStorage + ?Sized>(storage: &S) -> String {
+    let usage: BTreeMap<Uuid, Usage> = match storage.usage_by_workflow().await {
+        Ok(usage) => usage.into_iter().collect(),
+        Err(e) => {
+            tracing::warn!("Failed to read workflow usage for metrics: {}", e);
+            return String::new();
+        }
+    };
+
+    let mut metrics = String::from(
+        "\n# HELP workflow_tokens_total Tokens used by workflow executions\n\
+         # TYPE workflow_tokens_total counter\n",
+    );
+    for (workflow_id, usage) in &usage {
+        for (kind, tokens) in [
+            ("input", usage.input_tokens),
+            ("output", usage.output_tokens),
+            ("cache_creation", usage.cache_creation_input_tokens),
+            ("cache_read", usage.cache_read_input_tokens),
+        ] {
+            let _ = writeln!(
+                metrics,
+                "workflow_tokens_total{{workflow_id=\"{}\",type=\"{}\"}} {}",
+                workflow_id, kind, tokens
+            );
+        }
+    }
+    metrics.push_str(
+        "\n# HELP workflow_cost_usd_total Cost in USD of workflow executions\n\
+         # TYPE workflow_cost_usd_total counter\n",
+    );
+    for (workflow_id, usage) in &usage {
+        let _ = writeln!(
+            metrics,
+            "workflow_cost_usd_total{{workflow_id=\"{}\"}} {}",
+            workflow_id, usage.cost_usd
+        );
+    }
+    metrics
+}
+
 /// Stats endpoint - JSON format statistics
 /// Returns various statistics about the system
 #[cfg(feature = "server")]
@@ -198,3 +253,80 @@ pub async fn stats() -> impl IntoResponse {
         })),
     )
 }
+
+#[cfg(all(test, feature = "server"))]
+mod tests {
+    use super::*;
+    use crate::server::storage::{Execution, ExecutionStatus};
+    use crate::testing::MockStorage;
+
+    fn execution(workflow_id: Uuid, input_tokens: u64, cost_usd: f64) -> Execution {
+        Execution {
+            id: Uuid::new_v4(),
+            workflow_id,
+            workflow_version: "1.0.0".to_string(),
+            status: ExecutionStatus::Completed,
+            started_at: None,
+            completed_at: None,
+            created_at: chrono::Utc::now(),
+            triggered_by: None,
+            trigger_type: "manual".to_string(),
+            input_params: None,
+            result: None,
+            error: None,
+            retry_count: 0,
+            parent_execution_id: None,
+            completed_tasks: 0,
+            total_tasks: 0,
+            usage: Usage {
+                input_tokens,
+                output_tokens: 10,
+                cost_usd,
+                ..Default::default()
+            },
+        }
+    }
+
+    #[tokio::test]
+    async fn test_usage_metrics_sum_executions_per_workflow() {
+        let storage = MockStorage::new();
+        let workflow_id = Uuid::new_v4();
+        storage
+            .store_execution(&execution(workflow_id, 100, 0.25))
+            .await
+            .unwrap();
+        storage
+            .store_execution(&execution(workflow_id, 50, 0.5))
+            .await
+            .unwrap();
+
+        let metrics = usage_metrics(&storage).await;
+        assert!(metrics.contains("# TYPE workflow_tokens_total counter"));
+        assert!(metrics.contains(&format!(
+            "workflow_tokens_total{{workflow_id=\"{}\",type=\"input\"}} 150",
+            workflow_id
+        )));
+        assert!(metrics.contains(&format!(
+            "workflow_tokens_total{{workflow_id=\"{}\",type=\"output\"}} 20",
+            workflow_id
+        )));
+        assert!(metrics.contains(&format!(
+            "workflow_cost_usd_total{{workflow_id=\"{}\"}} 0.75",
+            workflow_id
+        )));
+    }
+
+    #[tokio::test]
+    async fn test_usage_metrics_skipped_when_storage_fails() {
+        let storage = MockStorage::new();
+        storage
+            .store_execution(&execution(Uuid::new_v4(), 100, 0.25))
+            .await
+            .unwrap();
+        storage.fail_get();
+
+        let metrics = usage_metrics(&storage).await;
+        assert!(!metrics.contains("workflow_tokens_total"));
+        assert!(!metrics.contains("workflow_cost_usd_total"));
+    }
+}
@@ -23,6 +23,8 @@ use crate::server::auth::jwt::Claims;
 #[cfg(feature = "server")]
 use crate::server::queue::{Job, WorkQueue};
 #[cfg(feature = "server")]
+use crate::server::scheduler::CronSchedule;
+#[cfg(feature = "server")]
 use crate::server::{
     storage::{Schedule, ScheduleFilter},
     Storage,
@@ -167,21 +169,32 @@ pub async fn create_schedule(
         }
     }
 
-    // TODO: Validate cron expression
-    // For now, just accept any string
+    let timezone = payload.timezone.unwrap_or_else(|| "UTC".to_string());
+    let cron = match CronSchedule::parse(&payload.cron_expression, &timezone) {
+        Ok(cron) => cron,
+        Err(e) => {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(json!({
+                    "error": "Invalid schedule",
+                    "message": e.to_string()
+                })),
+            );
+        }
+    };
 
     let schedule = Schedule {
         id: Uuid::new_v4(),
         workflow_id: payload.workflow_id,
         cron_expression: payload.cron_expression,
-        timezone: payload.timezone.unwrap_or_else(|| "UTC".to_string()),
+        timezone,
         is_active: true,
         input_params: payload.input_params,
         created_at: Utc::now(),
         updated_at: Utc::now(),
         created_by: Some(claims.sub.clone()),
         last_run_at: None,
-        next_run_at: None, // TODO: Calculate next run time from cron expression
+        next_run_at: cron.next_after(Utc::now()),
         description: payload.description,
     };
 
@@ -191,6 +204,7 @@ pub async fn create_schedule(
             Json(json!({
                 "id": id,
                 "workflow_id": schedule.workflow_id,
+                "next_run_at": schedule.next_run_at.map(|dt| dt.to_rfc3339()),
                 "message": "Schedule created successfully"
             })),
         ),
@@ -265,21 +279,38 @@ pub async fn update_schedule(
     };
 
     // Update fields
+    let reschedule = payload.cron_expression.is_some()
+        || payload.timezone.is_some()
+        || payload.is_active == Some(true) && !schedule.is_active;
+
     if let Some(cron_expression) = payload.cron_expression {
-        // TODO: Validate cron expression
         schedule.cron_expression = cron_expression;
-        schedule.next_run_at = None; // TODO: Recalculate next run time
     }
 
     if let Some(timezone) = payload.timezone {
         schedule.timezone = timezone;
-        schedule.next_run_at = None; // TODO: Recalculate next run time
     }
 
     if let Some(is_active) = payload.is_active {
         schedule.is_active = is_active;
     }
 
+    // A changed or reactivated schedule starts from its next occurrence
+    if reschedule {
+        match CronSchedule::parse(&schedule.cron_expression, &schedule.timezone) {
+            Ok(cron) => schedule.next_run_at = cron.next_after(Utc::now()),
+            Err(e) => {
+                return (
+                    StatusCode::BAD_REQUEST,
+                    Json(json!({
+                        "error": "Invalid schedule",
+                        "message": e.to_string()
+                    })),
+                );
+            }
+        }
+    }
+
     if let Some(input_params) = payload.input_params {
         schedule.input_params = Some(input_params);
     }
@@ -296,7 +327,8 @@ pub async fn update_schedule(
             Json(json!({
                 "id": id,
                 "message": "Schedule updated successfully",
-                "updated_at": schedule.updated_at.to_rfc3339()
+                "updated_at": schedule.updated_at.to_rfc3339(),
+                "next_run_at": schedule.next_run_at.map(|dt| dt.to_rfc3339())
             })),
         ),
         Err(e) => (
@@ -420,6 +452,9 @@ pub async fn trigger_schedule(
         error: None,
         retry_count: 0,
         parent_execution_id: None,
+        completed_tasks: 0,
+        total_tasks: 0,
+        usage: Default::default(),
     };
 
     let execution_id = match storage.store_execution(&execution).await {
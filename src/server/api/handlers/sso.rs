@@ -0,0 +1,211 @@
+// Single sign-on handlers
+
+#[cfg(feature = "server")]
+use axum::{
+    extract::{Extension, Path, Query},
+    http::{header, HeaderMap, HeaderValue, StatusCode},
+    response::{IntoResponse, Redirect, Response},
+    Json,
+};
+#[cfg(feature = "server")]
+use serde::Deserialize;
+#[cfg(feature = "server")]
+use serde_json::json;
+#[cfg(feature = "server")]
+use std::sync::Arc;
+#[cfg(feature = "server")]
+use uuid::Uuid;
+
+#[cfg(feature = "server")]
+use super::auth::{mfa_required, token_response};
+#[cfg(feature = "server")]
+use crate::server::auth::sso::{hash_state, PENDING_LOGIN_TTL_SECS};
+#[cfg(feature = "server")]
+use crate::server::auth::{Claims, JwtManager, MfaService, OidcError, SsoService};
+#[cfg(feature = "server")]
+use crate::server::storage::MfaStorage;
+#[cfg(feature = "server")]
+use crate::server::Storage;
+
+/// Cookie holding the hash of the login state, checked at the callback
+#[cfg(feature = "server")]
+pub const STATE_COOKIE: &str = "periplon_sso_state";
+
+#[cfg(feature = "server")]
+#[derive(Debug, Deserialize)]
+pub struct CallbackQuery {
+    pub code: Option<String>,
+    pub state: Option<String>,
+    pub error: Option<String>,
+    pub error_description: Option<String>,
+}
+
+/// Start a sign-in, redirecting to the provider
+/// Sets the state cookie that the callback must come back with
+#[cfg(feature = "server")]
+pub async fn sso_login(
+    sso: Option<Extension<Arc<SsoService>>>,
+    Path(provider): Path<String>,
+) -> Response {
+    let Some(Extension(sso)) = sso else {
+        return not_configured();
+    };
+
+    match sso.login_url(&provider).await {
+        Ok(login) => {
+            let cookie = state_cookie(
+                &hash_state(&login.state),
+                PENDING_LOGIN_TTL_SECS,
+                sso.secure_cookie(),
+            );
+            ([(header::SET_COOKIE, cookie)], Redirect::to(&login.url)).into_response()
+        }
+        Err(e) => error_response(e),
+    }
+}
+
+/// Complete a sign-in from the provider's callback
+/// Returns a JWT for the signed-in user
+/// Users with MFA get a challenge token to complete at /auth/mfa/verify
+#[cfg(feature = "server")]
+pub async fn sso_callback(
+    sso: Option<Extension<Arc<SsoService>>>,
+    Extension(jwt_manager): Extension<Arc<JwtManager>>,
+    mfa: Option<Extension<Arc<MfaService>>>,
+    storage: Option<Extension<Arc<dyn Storage>>>,
+    Path(provider): Path<String>,
+    Query(query): Query<CallbackQuery>,
+    headers: HeaderMap,
+) -> Response {
+    let Some(Extension(sso)) = sso else {
+        return not_configured();
+    };
+
+    if let Some(error) = query.error {
+        let message = match query.error_description {
+            Some(description) => format!("{}: {}", error, description),
+            None => error,
+        };
+        return (
+            StatusCode::UNAUTHORIZED,
+            Json(json!({
+                "error": format!("Provider rejected the sign-in: {}", message)
+            })),
+        )
+            .into_response();
+    }
+    let (Some(code), Some(state)) = (query.code, query.state) else {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(json!({
+                "error": "Missing code or state"
+            })),
+        )
+            .into_response();
+    };
+
+    // A callback from another browser, such as one an attacker sends the
+    // user to with the attacker's own code, does not carry the cookie
+    if cookie(&headers, STATE_COOKIE) != Some(hash_state(&state)) {
+        return error_response(OidcError::InvalidState);
+    }
+
+    let mut response = match sso.complete_login(&provider, &code, &state).await {
+        Ok(user) => {
+            // Accounts linked by email keep their second factor
+            let mfa = mfa.as_ref().map(|Extension(mfa)| mfa.as_ref());
+            let settings = storage
+                .as_ref()
+                .map(|Extension(storage)| storage.as_ref() as &dyn MfaStorage);
+            match mfa_required(mfa, settings, user.id).await {
+                Some(response) => response,
+                None => token_response(&jwt_manager, user),
+            }
+        }
+        Err(e) => error_response(e),
+    };
+    response
+        .headers_mut()
+        .append(header::SET_COOKIE, state_cookie("", 0, sso.secure_cookie()));
+    response
+}
+
+/// Refresh the provider tokens of the current user
+/// Returns a JWT carrying the roles from the user's current groups
+#[cfg(feature = "server")]
+pub async fn sso_refresh(
+    sso: Option<Extension<Arc<SsoService>>>,
+    Extension(jwt_manager): Extension<Arc<JwtManager>>,
+    Extension(claims): Extension<Claims>,
+    Path(provider): Path<String>,
+) -> Response {
+    let Some(Extension(sso)) = sso else {
+        return not_configured();
+    };
+    let Ok(user_id) = Uuid::parse_str(&claims.sub) else {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(json!({
+                "error": "Invalid user ID"
+            })),
+        )
+            .into_response();
+    };
+
+    match sso.refresh(&provider, user_id).await {
+        Ok(user) => token_response(&jwt_manager, user),
+        Err(e) => error_response(e),
+    }
+}
+
+/// State cookie for the login callback, kept from scripts and cross-site
+/// requests other than top-level navigations, and from plain HTTP when `secure`
+#[cfg(feature = "server")]
+fn state_cookie(value: &str, max_age_secs: i64, secure: bool) -> HeaderValue {
+    let mut cookie = format!(
+        "{}={}; Path=/api/v1/auth/sso/; Max-Age={}; HttpOnly; SameSite=Lax",
+        STATE_COOKIE, value, max_age_secs
+    );
+    if secure {
+        cookie.push_str("; Secure");
+    }
+    HeaderValue::from_str(&cookie).expect("state cookie is a valid header value")
+}
+
+/// Value of a request cookie
+#[cfg(feature = "server")]
+fn cookie(headers: &HeaderMap, name: &str) -> Option<String> {
+    headers
+        .get_all(header::COOKIE)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(';'))
+        .filter_map(|pair| pair.trim().split_once('='))
+        .find(|(key, _)| *key == name)
+        .map(|(_, value)| value.to_string())
+}
+
+#[cfg(feature = "server")]
+fn error_response(error: OidcError) -> Response {
+    if error.status().is_server_error() {
+        tracing::warn!("Single sign-on failed: {}", error);
+    }
+    (
+        error.status(),
+        Json(json!({
+            "error": error.to_string()
+        })),
+    )
+        .into_response()
+}
+
+#[cfg(feature = "server")]
+fn not_configured() -> Response {
+    (
+        StatusCode::NOT_FOUND,
+        Json(json!({
+            "error": "Single sign-on is not configured"
+        })),
+    )
+        .into_response()
+}
@@ -8,11 +8,13 @@ use axum::{
     },
     http::StatusCode,
     response::{IntoResponse, Response},
+    Extension,
 };
 #[cfg(feature = "server")]
-use futures::{sink::SinkExt, stream::StreamExt};
-#[cfg(feature = "server")]
-use serde::{Deserialize, Serialize};
+use futures::{
+    sink::SinkExt,
+    stream::{SplitSink, SplitStream, StreamExt},
+};
 #[cfg(feature = "server")]
 use std::sync::Arc;
 #[cfg(feature = "server")]
@@ -21,58 +23,11 @@ use tokio::sync::broadcast;
 use uuid::Uuid;
 
 #[cfg(feature = "server")]
-use crate::server::Storage;
-
-/// WebSocket message types for execution streaming
+pub use crate::server::hub::ExecutionStreamMessage;
 #[cfg(feature = "server")]
-#[derive(Debug, Clone, Serialize, Deserialize)]
-#[serde(tag = "type", rename_all = "snake_case")]
-pub enum ExecutionStreamMessage {
-    /// Execution started
-    Started {
-        execution_id: Uuid,
-        workflow_id: Uuid,
-        started_at: String,
-    },
-    /// Task execution update
-    TaskUpdate {
-        execution_id: Uuid,
-        task_id: String,
-        status: String,
-        message: Option<String>,
-    },
-    /// Log message
-    Log {
-        execution_id: Uuid,
-        timestamp: String,
-        level: String,
-        message: String,
-    },
-    /// Execution progress
-    Progress {
-        execution_id: Uuid,
-        completed_tasks: usize,
-        total_tasks: usize,
-        percent: f64,
-    },
-    /// Execution completed
-    Completed {
-        execution_id: Uuid,
-        status: String,
-        completed_at: String,
-        result: Option<serde_json::Value>,
-    },
-    /// Execution failed
-    Failed {
-        execution_id: Uuid,
-        error: String,
-        failed_at: String,
-    },
-    /// Keep-alive ping
-    Ping { timestamp: String },
-    /// Keep-alive pong
-    Pong { timestamp: String },
-}
+use crate::server::hub::{final_status_message, ExecutionHub};
+#[cfg(feature = "server")]
+use crate::server::{Execution, ExecutionStorage, Storage};
 
 /// WebSocket handler for streaming execution updates
 #[cfg(feature = "server")]
@@ -80,6 +35,7 @@ pub async fn execution_stream(
     ws: WebSocketUpgrade,
     Path(execution_id): Path<String>,
     State(storage): State<Arc<dyn Storage>>,
+    Extension(hub): Extension<Arc<ExecutionHub>>,
 ) -> Response {
     let execution_id = match Uuid::parse_str(&execution_id) {
         Ok(id) => id,
@@ -100,89 +56,142 @@ pub async fn execution_stream(
         return (StatusCode::NOT_FOUND, "Execution not found").into_response();
     }
 
-    ws.on_upgrade(move |socket| handle_execution_stream(socket, execution_id, storage))
+    ws.on_upgrade(move |socket| handle_execution_stream(socket, execution_id, storage, hub))
 }
 
+/// Interval between keep-alive pings
+#[cfg(feature = "server")]
+const PING_INTERVAL: std::time::Duration = std::time::Duration::from_secs(30);
+
+/// Interval between storage reads for executions run in other processes
+#[cfg(feature = "server")]
+const POLL_INTERVAL: std::time::Duration = std::time::Duration::from_secs(1);
+
 /// Handle WebSocket connection for execution streaming
+///
+/// Sends the stored state of the execution, then forwards its updates until
+/// the execution finishes or the client disconnects.
 #[cfg(feature = "server")]
-async fn handle_execution_stream(socket: WebSocket, execution_id: Uuid, storage: Arc<dyn Storage>) {
-    let (mut sender, mut receiver) = socket.split();
+async fn handle_execution_stream(
+    socket: WebSocket,
+    execution_id: Uuid,
+    storage: Arc<dyn Storage>,
+    hub: Arc<ExecutionHub>,
+) {
+    let (mut sender, receiver) = socket.split();
 
-    // Create a broadcast channel for this execution
-    let (tx, mut rx) = broadcast::channel::<ExecutionStreamMessage>(100);
+    // Subscribe before reading storage so no update falls in between
+    let mut rx = hub.subscribe(execution_id);
+    stream_execution(&mut sender, receiver, &mut rx, execution_id, &storage, &hub).await;
+    hub.unsubscribe(execution_id, rx);
+}
 
-    // Send initial execution state
-    if let Ok(Some(execution)) = storage.get_execution(execution_id).await {
-        let initial_msg = ExecutionStreamMessage::Started {
-            execution_id,
-            workflow_id: execution.workflow_id,
-            started_at: execution
-                .started_at
-                .map(|t| t.to_rfc3339())
-                .unwrap_or_else(|| "pending".to_string()),
-        };
+/// Send the execution's stored state and updates over the socket
+///
+/// Updates come from the hub while a worker in this process runs the
+/// execution. Otherwise the logs, progress and outcome recorded in storage
+/// are polled, so executions run by standalone workers are followed too.
+#[cfg(feature = "server")]
+async fn stream_execution(
+    sender: &mut SplitSink<WebSocket, Message>,
+    mut receiver: SplitStream<WebSocket>,
+    rx: &mut broadcast::Receiver<ExecutionStreamMessage>,
+    execution_id: Uuid,
+    storage: &Arc<dyn Storage>,
+    hub: &ExecutionHub,
+) {
+    let Ok(Some(execution)) = storage.get_execution(execution_id).await else {
+        return;
+    };
 
-        if let Ok(json) = serde_json::to_string(&initial_msg) {
-            let _ = sender.send(Message::Text(json)).await;
-        }
+    let mut initial = vec![ExecutionStreamMessage::Started {
+        execution_id,
+        workflow_id: execution.workflow_id,
+        started_at: execution
+            .started_at
+            .map(|t| t.to_rfc3339())
+            .unwrap_or_else(|| "pending".to_string()),
+    }];
 
-        // Send current status if completed or failed
-        if let Some(completed_at) = execution.completed_at {
-            let status_msg = match execution.status {
-                crate::server::ExecutionStatus::Completed => ExecutionStreamMessage::Completed {
-                    execution_id,
-                    status: "completed".to_string(),
-                    completed_at: completed_at.to_rfc3339(),
-                    result: execution.result,
-                },
-                crate::server::ExecutionStatus::Failed => ExecutionStreamMessage::Failed {
-                    execution_id,
-                    error: execution
-                        .error
-                        .unwrap_or_else(|| "Unknown error".to_string()),
-                    failed_at: completed_at.to_rfc3339(),
-                },
-                _ => ExecutionStreamMessage::Progress {
-                    execution_id,
-                    completed_tasks: 0,
-                    total_tasks: 0,
-                    percent: 0.0,
-                },
-            };
+    // Replay the logs recorded so far, oldest first
+    let mut polled = StoredProgress::default();
+    initial.extend(polled.new_logs(storage.as_ref(), execution_id).await);
+    initial.extend(polled.new_progress(&execution));
 
-            if let Ok(json) = serde_json::to_string(&status_msg) {
-                let _ = sender.send(Message::Text(json)).await;
-            }
+    let final_message = final_status_message(&execution);
+    let finished = final_message.is_some();
+    initial.extend(final_message);
+
+    for msg in &initial {
+        if send_message(sender, msg).await.is_err() {
+            return;
         }
     }
+    if finished {
+        let _ = sender.close().await;
+        return;
+    }
 
-    // Spawn a task to poll for execution updates
-    let storage_clone = Arc::clone(&storage);
-    let tx_clone = tx.clone();
-    tokio::spawn(async move {
-        poll_execution_updates(execution_id, storage_clone, tx_clone).await;
-    });
+    let mut ping = tokio::time::interval(PING_INTERVAL);
+    ping.tick().await;
+    let mut poll = tokio::time::interval(POLL_INTERVAL);
+    poll.tick().await;
 
-    // Handle both incoming WebSocket messages and broadcast messages
+    // Forward hub or storage updates and handle incoming WebSocket messages
     loop {
         tokio::select! {
-            // Forward broadcast messages to WebSocket
             msg = rx.recv() => {
                 match msg {
                     Ok(msg) => {
-                        if let Ok(json) = serde_json::to_string(&msg) {
-                            if sender.send(Message::Text(json)).await.is_err() {
-                                break;
-                            }
+                        // Logs stored before the replay read them were sent already
+                        if !polled.is_new(&msg) {
+                            continue;
+                        }
+                        if send_message(sender, &msg).await.is_err() {
+                            break;
+                        }
+                        if msg.is_final() {
+                            let _ = sender.close().await;
+                            break;
                         }
                     }
-                    Err(_) => {
-                        // Channel closed
+                    // Slow client missed some updates; carry on with the next
+                    Err(broadcast::error::RecvError::Lagged(_)) => {}
+                    Err(broadcast::error::RecvError::Closed) => break,
+                }
+            }
+            _ = poll.tick(), if !hub.has_publisher(execution_id) => {
+                let Ok(Some(execution)) = storage.get_execution(execution_id).await else {
+                    break;
+                };
+                let mut updates = polled.new_logs(storage.as_ref(), execution_id).await;
+                updates.extend(polled.new_progress(&execution));
+                let final_message = final_status_message(&execution);
+                let finished = final_message.is_some();
+                updates.extend(final_message);
+
+                let mut sent = true;
+                for msg in &updates {
+                    if send_message(sender, msg).await.is_err() {
+                        sent = false;
                         break;
                     }
                 }
+                if finished {
+                    let _ = sender.close().await;
+                }
+                if !sent || finished {
+                    break;
+                }
+            }
+            _ = ping.tick() => {
+                let msg = ExecutionStreamMessage::Ping {
+                    timestamp: chrono::Utc::now().to_rfc3339(),
+                };
+                if send_message(sender, &msg).await.is_err() {
+                    break;
+                }
             }
-            // Handle incoming WebSocket messages (pings, close)
             msg = receiver.next() => {
                 match msg {
                     Some(Ok(Message::Ping(data))) => {
@@ -208,109 +217,160 @@ async fn handle_execution_stream(socket: WebSocket, execution_id: Uuid, storage:
     }
 }
 
-/// Poll for execution updates and send them via broadcast channel
+/// What a stream has already sent from the execution's stored state
 #[cfg(feature = "server")]
-async fn poll_execution_updates(
-    execution_id: Uuid,
-    storage: Arc<dyn Storage>,
-    tx: broadcast::Sender<ExecutionStreamMessage>,
-) {
-    let mut last_log_count = 0;
-    let mut last_status = None;
-
-    loop {
-        // Sleep between polls
-        tokio::time::sleep(tokio::time::Duration::from_secs(1)).await;
+#[derive(Default)]
+struct StoredProgress {
+    /// Number of stored logs sent, from storage or the hub
+    logs: usize,
+    tasks: Option<(u32, u32)>,
+}
 
-        // Get execution state
-        let execution = match storage.get_execution(execution_id).await {
-            Ok(Some(exec)) => exec,
-            _ => continue,
+#[cfg(feature = "server")]
+impl StoredProgress {
+    /// Log messages stored after the ones sent, oldest first
+    async fn new_logs(
+        &mut self,
+        storage: &(impl ExecutionStorage + ?Sized),
+        execution_id: Uuid,
+    ) -> Vec<ExecutionStreamMessage> {
+        let Ok(logs) = storage
+            .get_execution_logs_since(execution_id, self.logs)
+            .await
+        else {
+            return Vec::new();
         };
+        let first = self.logs;
+        self.logs += logs.len();
+        logs.into_iter()
+            .enumerate()
+            .map(|(index, log)| ExecutionStreamMessage::Log {
+                execution_id,
+                sequence: first + index,
+                timestamp: log.timestamp.to_rfc3339(),
+                level: log.level,
+                message: log.message,
+            })
+            .collect()
+    }
 
-        // Check if status changed
-        if Some(&execution.status) != last_status.as_ref() {
-            last_status = Some(execution.status.clone());
-
-            match execution.status {
-                crate::server::ExecutionStatus::Running => {
-                    let msg = ExecutionStreamMessage::Progress {
-                        execution_id,
-                        completed_tasks: 0,
-                        total_tasks: 0,
-                        percent: 0.0,
-                    };
-                    let _ = tx.send(msg);
-                }
-                crate::server::ExecutionStatus::Completed => {
-                    let msg = ExecutionStreamMessage::Completed {
-                        execution_id,
-                        status: "completed".to_string(),
-                        completed_at: execution
-                            .completed_at
-                            .map(|t| t.to_rfc3339())
-                            .unwrap_or_default(),
-                        result: execution.result,
-                    };
-                    let _ = tx.send(msg);
-                    break; // Stop polling when completed
+    /// Whether a hub message still has to be sent, counting the logs it carries
+    fn is_new(&mut self, msg: &ExecutionStreamMessage) -> bool {
+        match msg {
+            ExecutionStreamMessage::Log { sequence, .. } => {
+                if *sequence < self.logs {
+                    return false;
                 }
-                crate::server::ExecutionStatus::Failed => {
-                    let msg = ExecutionStreamMessage::Failed {
-                        execution_id,
-                        error: execution
-                            .error
-                            .unwrap_or_else(|| "Unknown error".to_string()),
-                        failed_at: execution
-                            .completed_at
-                            .map(|t| t.to_rfc3339())
-                            .unwrap_or_default(),
-                    };
-                    let _ = tx.send(msg);
-                    break; // Stop polling when failed
-                }
-                crate::server::ExecutionStatus::Cancelled => {
-                    let msg = ExecutionStreamMessage::Failed {
-                        execution_id,
-                        error: "Execution cancelled".to_string(),
-                        failed_at: execution
-                            .completed_at
-                            .map(|t| t.to_rfc3339())
-                            .unwrap_or_default(),
-                    };
-                    let _ = tx.send(msg);
-                    break; // Stop polling when cancelled
-                }
-                _ => {}
+                self.logs = sequence + 1;
+                true
             }
+            _ => true,
         }
+    }
 
-        // Get new logs
-        if let Ok(logs) = storage.get_execution_logs(execution_id, Some(100)).await {
-            if logs.len() > last_log_count {
-                // Send new logs
-                for log in &logs[last_log_count..] {
-                    let msg = ExecutionStreamMessage::Log {
-                        execution_id,
-                        timestamp: log.timestamp.to_rfc3339(),
-                        level: log.level.clone(),
-                        message: log.message.clone(),
-                    };
-                    let _ = tx.send(msg);
-                }
-                last_log_count = logs.len();
-            }
+    /// Progress message if the task counts changed since the last one sent
+    fn new_progress(&mut self, execution: &Execution) -> Option<ExecutionStreamMessage> {
+        let tasks = (execution.completed_tasks, execution.total_tasks);
+        if self.tasks == Some(tasks) {
+            return None;
         }
+        self.tasks = Some(tasks);
+        Some(ExecutionStreamMessage::progress(
+            execution.id,
+            tasks.0 as usize,
+            tasks.1 as usize,
+        ))
+    }
+}
+
+/// Serialize a stream message and send it as a text frame
+#[cfg(feature = "server")]
+async fn send_message(
+    sender: &mut SplitSink<WebSocket, Message>,
+    msg: &ExecutionStreamMessage,
+) -> Result<(), axum::Error> {
+    match serde_json::to_string(msg) {
+        Ok(json) => sender.send(Message::Text(json)).await,
+        Err(_) => Ok(()),
+    }
+}
+
+#[cfg(all(test, feature = "server"))]
+mod tests {
+    use super::*;
+    use crate::server::ExecutionLog;
+    use crate::testing::MockStorage;
 
-        // Send periodic ping to keep connection alive
-        if tx.receiver_count() > 0 {
-            let msg = ExecutionStreamMessage::Ping {
-                timestamp: chrono::Utc::now().to_rfc3339(),
-            };
-            let _ = tx.send(msg);
-        } else {
-            // No more receivers, stop polling
-            break;
+    fn log(execution_id: Uuid, message: &str) -> ExecutionLog {
+        ExecutionLog {
+            id: None,
+            execution_id,
+            task_execution_id: None,
+            // Logs of the same instant are told apart by position
+            timestamp: chrono::DateTime::UNIX_EPOCH,
+            level: "info".to_string(),
+            message: message.to_string(),
+            metadata: None,
         }
     }
+
+    fn messages(updates: &[ExecutionStreamMessage]) -> Vec<(usize, &str)> {
+        updates
+            .iter()
+            .filter_map(|update| match update {
+                ExecutionStreamMessage::Log {
+                    sequence, message, ..
+                } => Some((*sequence, message.as_str())),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_new_logs_sends_each_stored_log_once() {
+        let storage = MockStorage::new();
+        let execution_id = Uuid::new_v4();
+        let mut polled = StoredProgress::default();
+
+        storage
+            .store_execution_log(&log(execution_id, "first"))
+            .await
+            .unwrap();
+        storage
+            .store_execution_log(&log(execution_id, "second"))
+            .await
+            .unwrap();
+        let updates = polled.new_logs(&storage, execution_id).await;
+        assert_eq!(messages(&updates), vec![(0, "first"), (1, "second")]);
+
+        storage
+            .store_execution_log(&log(execution_id, "third"))
+            .await
+            .unwrap();
+        let updates = polled.new_logs(&storage, execution_id).await;
+        assert_eq!(messages(&updates), vec![(2, "third")]);
+        assert!(polled.new_logs(&storage, execution_id).await.is_empty());
+    }
+
+    #[test]
+    fn test_is_new_skips_replayed_logs() {
+        let execution_id = Uuid::new_v4();
+        let mut polled = StoredProgress {
+            logs: 2,
+            tasks: None,
+        };
+        let hub_log = |sequence| ExecutionStreamMessage::Log {
+            execution_id,
+            sequence,
+            timestamp: String::new(),
+            level: "info".to_string(),
+            message: String::new(),
+        };
+
+        assert!(!polled.is_new(&hub_log(1)));
+        assert!(polled.is_new(&hub_log(2)));
+        assert!(!polled.is_new(&hub_log(2)));
+        assert!(polled.is_new(&hub_log(3)));
+        assert!(polled.is_new(&ExecutionStreamMessage::progress(execution_id, 1, 2)));
+    }
 }
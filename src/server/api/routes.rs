@@ -15,6 +15,8 @@ use crate::server::auth::{middleware as auth_middleware, JwtManager};
 #[cfg(feature = "server")]
 use crate::server::config::{CorsConfig, RateLimitConfig};
 #[cfg(feature = "server")]
+use crate::server::hub::ExecutionHub;
+#[cfg(feature = "server")]
 use crate::server::middleware::{rate_limit_middleware, RateLimiter};
 #[cfg(feature = "server")]
 use crate::server::queue::WorkQueue;
@@ -28,19 +30,24 @@ use std::sync::Arc;
 use tower_http::cors::{Any, CorsLayer};
 
 #[cfg(feature = "server")]
+#[allow(clippy::too_many_arguments)]
 pub fn create_router(
     user_storage: Arc<dyn UserStorage>,
     jwt_manager: Arc<JwtManager>,
     storage: Arc<dyn Storage>,
     queue: Arc<dyn WorkQueue>,
+    hub: Arc<ExecutionHub>,
     cors_config: Option<CorsConfig>,
     rate_limit_config: RateLimitConfig,
+    trusted_proxies: Vec<String>,
 ) -> Router {
-    // Create auth layer for protected routes
-    let auth_layer = auth_middleware::AuthLayer::new(Arc::clone(&jwt_manager));
+    // Create auth layer for protected routes, accepting JWTs and API keys
+    let auth_layer = auth_middleware::AuthLayer::new(Arc::clone(&jwt_manager))
+        .with_api_keys(storage.clone(), Arc::clone(&user_storage))
+        .with_trusted_proxies(trusted_proxies.clone());
 
     // Create rate limiter
-    let rate_limiter = RateLimiter::new(rate_limit_config);
+    let rate_limiter = RateLimiter::new(rate_limit_config).with_trusted_proxies(trusted_proxies);
 
     // Create CORS layer if configured
     let cors_layer = if let Some(cors) = cors_config {
@@ -96,13 +103,54 @@ pub fn create_router(
         .route("/stats", get(handlers::monitoring::stats))
         // Authentication endpoints
         .route("/api/v1/auth/login", post(handlers::auth::login))
-        .route("/api/v1/auth/register", post(handlers::auth::register));
+        .route("/api/v1/auth/register", post(handlers::auth::register))
+        // Single sign-on endpoints
+        .route(
+            "/api/v1/auth/sso/:provider/login",
+            get(handlers::sso::sso_login),
+        )
+        .route(
+            "/api/v1/auth/sso/:provider/callback",
+            get(handlers::sso::sso_callback),
+        )
+        // Second login step for users with MFA
+        .route("/api/v1/auth/mfa/verify", post(handlers::mfa::verify_login));
 
-    // Protected routes (require valid JWT)
+    // Admin routes (require the admin role)
+    let admin_routes = Router::new()
+        .route(
+            "/api/v1/admin/users/:id/mfa",
+            get(handlers::mfa::admin_get_status),
+        )
+        .route(
+            "/api/v1/admin/users/:id/mfa",
+            delete(handlers::mfa::admin_reset),
+        )
+        .route_layer(middleware::from_fn(|req, next| {
+            auth_middleware::require_role("admin", req, next)
+        }));
+
+    // Protected routes (require valid JWT or API key)
     let protected_routes = Router::new()
         // Auth endpoints that need authentication
         .route("/api/v1/auth/me", get(handlers::auth::get_current_user))
         .route("/api/v1/auth/refresh", post(handlers::auth::refresh_token))
+        .route(
+            "/api/v1/auth/sso/:provider/refresh",
+            post(handlers::sso::sso_refresh),
+        )
+        // MFA enrollment and settings
+        .route("/api/v1/auth/mfa", get(handlers::mfa::get_status))
+        .route("/api/v1/auth/mfa/enroll", post(handlers::mfa::enroll))
+        .route(
+            "/api/v1/auth/mfa/enroll/confirm",
+            post(handlers::mfa::confirm_enrollment),
+        )
+        .route(
+            "/api/v1/auth/mfa/recovery-codes",
+            post(handlers::mfa::regenerate_recovery_codes),
+        )
+        .route("/api/v1/auth/mfa/disable", post(handlers::mfa::disable))
         // Workflow endpoints
         .route(
             "/api/v1/workflows",
@@ -252,6 +300,7 @@ pub fn create_router(
         )
         // Queue stats
         .route("/api/v1/queue/stats", get(handlers::queue::get_queue_stats))
+        .merge(admin_routes)
         // Apply authentication middleware to protected routes
         .route_layer(middleware::from_fn(move |req, next| {
             auth_middleware::auth_middleware(auth_layer.clone(), req, next)
@@ -272,6 +321,7 @@ pub fn create_router(
         // Add global extensions
         .layer(Extension(storage))
         .layer(Extension(queue))
+        .layer(Extension(hub))
         .layer(Extension(user_storage))
         .layer(Extension(jwt_manager));
 
@@ -0,0 +1,424 @@
+// API key authentication
+//
+// Requests carrying an `X-API-Key` header are authenticated by hashing the key
+// and looking it up. An accepted key yields the same `Claims` as a JWT for the
+// key's owner, so handlers and role checks treat both alike.
+//
+// Scopes take the form `<resource>:<action>`, where the resource is the first
+// path segment after `/api/v1/` and the action is `read` for GET and HEAD
+// requests and `write` otherwise. `*` grants everything, `<resource>:*` every
+// action on a resource, `*:read` reading any resource, and a `write` scope
+// also allows reading.
+
+#[cfg(feature = "server")]
+use axum::http::{HeaderMap, Method, StatusCode};
+#[cfg(feature = "server")]
+use chrono::Utc;
+#[cfg(feature = "server")]
+use std::net::IpAddr;
+#[cfg(feature = "server")]
+use uuid::Uuid;
+
+#[cfg(feature = "server")]
+use super::jwt::{Claims, JwtManager};
+#[cfg(feature = "server")]
+use crate::server::storage::{ApiKeyStorage, UserStorage};
+
+/// Header carrying an API key
+#[cfg(feature = "server")]
+pub const API_KEY_HEADER: &str = "x-api-key";
+
+/// Generate a new random API key
+#[cfg(feature = "server")]
+pub fn generate_api_key() -> String {
+    use base64::{engine::general_purpose, Engine as _};
+
+    let key_bytes: [u8; 32] = rand::random();
+    format!("sk_{}", general_purpose::URL_SAFE_NO_PAD.encode(key_bytes))
+}
+
+/// Hash of an API key, as stored in place of the key
+#[cfg(feature = "server")]
+pub fn hash_api_key(key: &str) -> String {
+    use sha2::{Digest, Sha256};
+
+    let mut hasher = Sha256::new();
+    hasher.update(key.as_bytes());
+    format!("{:x}", hasher.finalize())
+}
+
+/// Prefix of an API key, shown to identify it
+#[cfg(feature = "server")]
+pub fn key_prefix(key: &str) -> String {
+    key.chars().take(12).collect()
+}
+
+/// API key a request was authenticated with
+///
+/// Stored in the request extensions next to the `Claims`.
+#[cfg(feature = "server")]
+#[derive(Debug, Clone)]
+pub struct ApiKeyIdentity {
+    pub key_id: Uuid,
+    pub scopes: Vec<String>,
+}
+
+/// Reason an API key was rejected
+#[cfg(feature = "server")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ApiKeyRejection {
+    /// No key with this hash exists
+    UnknownKey,
+    /// The key was revoked
+    Inactive,
+    /// The key is past its expiry
+    Expired,
+    /// The key's owner no longer exists or is deactivated
+    InactiveUser,
+    /// The client IP is not on the key's whitelist
+    IpNotAllowed,
+    /// The key lacks the scope the request needs
+    MissingScope(String),
+    /// Keys cannot be used to issue tokens or change the owner's credentials
+    CredentialEndpoint,
+    /// The key or its owner could not be loaded
+    Storage(String),
+}
+
+#[cfg(feature = "server")]
+impl ApiKeyRejection {
+    /// HTTP status to answer the request with
+    pub fn status(&self) -> StatusCode {
+        match self {
+            Self::UnknownKey | Self::Inactive | Self::Expired | Self::InactiveUser => {
+                StatusCode::UNAUTHORIZED
+            }
+            Self::IpNotAllowed | Self::MissingScope(_) | Self::CredentialEndpoint => {
+                StatusCode::FORBIDDEN
+            }
+            Self::Storage(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
+/// Scope a request needs, e.g. `workflows:read`
+#[cfg(feature = "server")]
+pub fn required_scope(method: &Method, path: &str) -> String {
+    let resource = path
+        .strip_prefix("/api/v1/")
+        .and_then(|rest| rest.split('/').next())
+        .filter(|resource| !resource.is_empty())
+        .unwrap_or("*");
+    let action = if method == Method::GET || method == Method::HEAD {
+        "read"
+    } else {
+        "write"
+    };
+    format!("{}:{}", resource, action)
+}
+
+/// Check whether a request would issue tokens or change the owner's credentials
+///
+/// These are the writes under `/api/v1/auth/`: refreshing a JWT (also through
+/// SSO) and managing MFA. A token minted from a key would not carry the key's
+/// scopes, so keys are refused there whatever their scopes. Writes under
+/// `/api/v1/api-keys` are refused too, so a key cannot create, widen or rotate
+/// keys beyond its own scopes and IP whitelist.
+#[cfg(feature = "server")]
+pub fn is_credential_endpoint(method: &Method, path: &str) -> bool {
+    let credentials = path.starts_with("/api/v1/auth/")
+        || path == "/api/v1/api-keys"
+        || path.starts_with("/api/v1/api-keys/");
+    credentials && method != Method::GET && method != Method::HEAD
+}
+
+/// Check whether any of a key's scopes grants the required scope
+#[cfg(feature = "server")]
+pub fn scope_allows(scopes: &[String], required: &str) -> bool {
+    let (resource, action) = required.split_once(':').unwrap_or((required, ""));
+
+    scopes.iter().any(|scope| {
+        if scope == "*" {
+            return true;
+        }
+        let Some((scope_resource, scope_action)) = scope.split_once(':') else {
+            return false;
+        };
+        let resource_matches = scope_resource == "*" || scope_resource == resource;
+        let action_matches = scope_action == "*"
+            || scope_action == action
+            || (scope_action == "write" && action == "read");
+        resource_matches && action_matches
+    })
+}
+
+/// Check whether an IP is on a key's whitelist
+///
+/// Entries are addresses or CIDR blocks. An empty whitelist allows any IP,
+/// while a non-empty one rejects requests whose IP is unknown.
+#[cfg(feature = "server")]
+pub fn ip_allowed(whitelist: &[String], ip: Option<IpAddr>) -> bool {
+    if whitelist.is_empty() {
+        return true;
+    }
+    ip.is_some_and(|ip| in_networks(whitelist, ip))
+}
+
+/// Determine the address a request came from
+///
+/// `peer` is the address of the connection. Clients can set forwarding
+/// headers to anything, so they are only read when the peer is one of the
+/// `trusted_proxies`: `X-Forwarded-For` from the right, skipping trusted
+/// proxies, or else `X-Real-IP`.
+#[cfg(feature = "server")]
+pub fn client_ip(
+    peer: Option<IpAddr>,
+    headers: &HeaderMap,
+    trusted_proxies: &[String],
+) -> Option<IpAddr> {
+    let peer = peer?;
+    if !in_networks(trusted_proxies, peer) {
+        return Some(peer);
+    }
+
+    let forwarded = headers
+        .get_all("x-forwarded-for")
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .map(|entry| entry.trim().parse::<IpAddr>().ok())
+        .collect::<Vec<_>>();
+    if !forwarded.is_empty() {
+        let mut client = peer;
+        for address in forwarded.into_iter().rev() {
+            // An unparsable entry leaves the origin unknown
+            let address = address?;
+            client = address;
+            if !in_networks(trusted_proxies, address) {
+                break;
+            }
+        }
+        return Some(client);
+    }
+
+    match headers.get("x-real-ip") {
+        Some(value) => value.to_str().ok()?.trim().parse().ok(),
+        None => Some(peer),
+    }
+}
+
+/// Check whether an IP is in any of a list of addresses and CIDR blocks
+#[cfg(feature = "server")]
+fn in_networks(networks: &[String], ip: IpAddr) -> bool {
+    networks.iter().any(|entry| match parse_network(entry) {
+        Some((IpAddr::V4(network), prefix)) => match ip {
+            IpAddr::V4(ip) => {
+                let mask = u32::MAX.checked_shl(32 - prefix).unwrap_or(0);
+                u32::from(network) & mask == u32::from(ip) & mask
+            }
+            IpAddr::V6(_) => false,
+        },
+        Some((IpAddr::V6(network), prefix)) => match ip {
+            IpAddr::V6(ip) => {
+                let mask = u128::MAX.checked_shl(128 - prefix).unwrap_or(0);
+                u128::from(network) & mask == u128::from(ip) & mask
+            }
+            IpAddr::V4(_) => false,
+        },
+        None => false,
+    })
+}
+
+/// Check that a whitelist entry is an address or CIDR block
+#[cfg(feature = "server")]
+pub fn is_valid_ip_entry(entry: &str) -> bool {
+    parse_network(entry).is_some()
+}
+
+/// Parse an address or CIDR block into its address and prefix length
+#[cfg(feature = "server")]
+fn parse_network(entry: &str) -> Option<(IpAddr, u32)> {
+    let (address, prefix) = match entry.trim().split_once('/') {
+        Some((address, prefix)) => (address, Some(prefix.parse::<u32>().ok()?)),
+        None => (entry.trim(), None),
+    };
+    let address = address.parse::<IpAddr>().ok()?;
+    let max_prefix = if address.is_ipv4() { 32 } else { 128 };
+    let prefix = prefix.unwrap_or(max_prefix);
+
+    (prefix <= max_prefix).then_some((address, prefix))
+}
+
+/// Authenticate a request made with an API key
+///
+/// Looks the key up by its hash and checks that it is active, unexpired,
+/// allowed from `client_ip` and scoped for the request. On success, the key's
+/// last use is recorded and the claims of its owner are returned.
+#[cfg(feature = "server")]
+pub async fn authenticate(
+    api_keys: &dyn ApiKeyStorage,
+    users: &dyn UserStorage,
+    jwt_manager: &JwtManager,
+    key: &str,
+    method: &Method,
+    path: &str,
+    client_ip: Option<IpAddr>,
+) -> Result<(Claims, ApiKeyIdentity), ApiKeyRejection> {
+    let api_key = api_keys
+        .get_api_key_by_hash(&hash_api_key(key))
+        .await
+        .map_err(|e| ApiKeyRejection::Storage(e.to_string()))?
+        .ok_or(ApiKeyRejection::UnknownKey)?;
+
+    if !api_key.is_active {
+        return Err(ApiKeyRejection::Inactive);
+    }
+    if api_key
+        .expires_at
+        .is_some_and(|expires_at| expires_at <= Utc::now())
+    {
+        return Err(ApiKeyRejection::Expired);
+    }
+    if !ip_allowed(&api_key.ip_whitelist, client_ip) {
+        return Err(ApiKeyRejection::IpNotAllowed);
+    }
+    if is_credential_endpoint(method, path) {
+        return Err(ApiKeyRejection::CredentialEndpoint);
+    }
+    let scope = required_scope(method, path);
+    if !scope_allows(&api_key.scopes, &scope) {
+        return Err(ApiKeyRejection::MissingScope(scope));
+    }
+
+    let user = users
+        .get_user(api_key.user_id)
+        .await
+        .map_err(|e| ApiKeyRejection::Storage(e.to_string()))?
+        .filter(|user| user.is_active)
+        .ok_or(ApiKeyRejection::InactiveUser)?;
+
+    if let Err(e) = api_keys.update_last_used(api_key.id).await {
+        tracing::warn!("Failed to record use of API key {}: {}", api_key.id, e);
+    }
+
+    let claims = jwt_manager.issue_claims(&user.id.to_string(), &user.email, user.roles);
+    let identity = ApiKeyIdentity {
+        key_id: api_key.id,
+        scopes: api_key.scopes,
+    };
+    Ok((claims, identity))
+}
+
+#[cfg(all(test, feature = "server"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_required_scope() {
+        assert_eq!(
+            required_scope(&Method::GET, "/api/v1/workflows/abc"),
+            "workflows:read"
+        );
+        assert_eq!(
+            required_scope(&Method::POST, "/api/v1/executions"),
+            "executions:write"
+        );
+        assert_eq!(required_scope(&Method::DELETE, "/other"), "*:write");
+    }
+
+    #[test]
+    fn test_is_credential_endpoint() {
+        assert!(is_credential_endpoint(
+            &Method::POST,
+            "/api/v1/auth/refresh"
+        ));
+        assert!(is_credential_endpoint(
+            &Method::POST,
+            "/api/v1/auth/sso/okta/refresh"
+        ));
+        assert!(is_credential_endpoint(
+            &Method::POST,
+            "/api/v1/auth/mfa/disable"
+        ));
+        assert!(is_credential_endpoint(&Method::POST, "/api/v1/api-keys"));
+        assert!(is_credential_endpoint(
+            &Method::PUT,
+            "/api/v1/api-keys/6f1c2d4e-0000-0000-0000-000000000000"
+        ));
+        assert!(!is_credential_endpoint(&Method::GET, "/api/v1/auth/me"));
+        assert!(!is_credential_endpoint(&Method::GET, "/api/v1/api-keys"));
+        assert!(!is_credential_endpoint(&Method::POST, "/api/v1/workflows"));
+    }
+
+    #[test]
+    fn test_scope_allows() {
+        let scopes = vec!["workflows:write".to_string(), "*:read".to_string()];
+        assert!(scope_allows(&scopes, "workflows:read"));
+        assert!(scope_allows(&scopes, "workflows:write"));
+        assert!(scope_allows(&scopes, "executions:read"));
+        assert!(!scope_allows(&scopes, "executions:write"));
+        assert!(!scope_allows(&[], "workflows:read"));
+        assert!(scope_allows(&["*".to_string()], "api-keys:write"));
+    }
+
+    #[test]
+    fn test_client_ip() {
+        let trusted = vec!["10.0.0.0/8".to_string()];
+        fn headers(pairs: &[(&'static str, &str)]) -> HeaderMap {
+            let mut headers = HeaderMap::new();
+            for (name, value) in pairs {
+                headers.append(*name, value.parse().unwrap());
+            }
+            headers
+        }
+        let peer = |ip: &str| ip.parse::<IpAddr>().ok();
+
+        // Headers from untrusted peers are ignored
+        let spoofed = headers(&[("x-forwarded-for", "203.0.113.9")]);
+        assert_eq!(
+            client_ip(peer("198.51.100.1"), &spoofed, &trusted),
+            peer("198.51.100.1")
+        );
+        assert_eq!(
+            client_ip(peer("198.51.100.1"), &spoofed, &[]),
+            peer("198.51.100.1")
+        );
+        assert_eq!(client_ip(None, &spoofed, &trusted), None);
+
+        // Behind trusted proxies, the last untrusted hop is the client
+        let chain = headers(&[("x-forwarded-for", "203.0.113.9, 198.51.100.1, 10.0.0.7")]);
+        assert_eq!(
+            client_ip(peer("10.0.0.2"), &chain, &trusted),
+            peer("198.51.100.1")
+        );
+        let real_ip = headers(&[("x-real-ip", "198.51.100.1")]);
+        assert_eq!(
+            client_ip(peer("10.0.0.2"), &real_ip, &trusted),
+            peer("198.51.100.1")
+        );
+        let garbage = headers(&[("x-forwarded-for", "203.0.113.9, unknown")]);
+        assert_eq!(client_ip(peer("10.0.0.2"), &garbage, &trusted), None);
+        assert_eq!(
+            client_ip(peer("10.0.0.2"), &HeaderMap::new(), &trusted),
+            peer("10.0.0.2")
+        );
+    }
+
+    #[test]
+    fn test_ip_allowed() {
+        let whitelist = vec!["10.0.0.0/8".to_string(), "2001:db8::1".to_string()];
+        assert!(ip_allowed(&whitelist, "10.1.2.3".parse().ok()));
+        assert!(!ip_allowed(&whitelist, "192.168.0.1".parse().ok()));
+        assert!(ip_allowed(&whitelist, "2001:db8::1".parse().ok()));
+        assert!(!ip_allowed(&whitelist, None));
+        assert!(ip_allowed(&[], None));
+        assert!(ip_allowed(
+            &["192.168.1.7/32".to_string()],
+            "192.168.1.7".parse().ok()
+        ));
+
+        assert!(is_valid_ip_entry("10.0.0.0/8"));
+        assert!(!is_valid_ip_entry("10.0.0.0/33"));
+        assert!(!is_valid_ip_entry("example.com"));
+    }
+}
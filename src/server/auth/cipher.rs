@@ -0,0 +1,91 @@
+// Encryption of stored credentials
+//
+// Provider tokens and MFA secrets are kept in the database encrypted with
+// AES-256-GCM under a key derived from a server secret.
+
+#[cfg(feature = "server")]
+use aes_gcm::aead::Aead;
+#[cfg(feature = "server")]
+use aes_gcm::{Aes256Gcm, KeyInit, Nonce};
+#[cfg(feature = "server")]
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine as _};
+#[cfg(feature = "server")]
+use rand::RngCore;
+#[cfg(feature = "server")]
+use sha2::{Digest, Sha256};
+
+/// Encryption or decryption failure
+#[cfg(feature = "server")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+pub enum CipherError {
+    #[error("Failed to encrypt token")]
+    Encrypt,
+
+    #[error("Failed to decrypt token")]
+    Decrypt,
+}
+
+/// Encrypts tokens for storage with AES-256-GCM
+#[cfg(feature = "server")]
+pub struct TokenCipher {
+    cipher: Aes256Gcm,
+}
+
+#[cfg(feature = "server")]
+impl TokenCipher {
+    /// Cipher keyed by the SHA-256 hash of a secret
+    pub fn new(secret: &str) -> Self {
+        let key: [u8; 32] = Sha256::digest(secret.as_bytes()).into();
+        Self {
+            cipher: Aes256Gcm::new(&key.into()),
+        }
+    }
+
+    /// Encrypt a token into a base64 string holding the nonce and ciphertext
+    pub fn encrypt(&self, token: &str) -> Result<String, CipherError> {
+        let mut nonce = [0u8; 12];
+        rand::thread_rng().fill_bytes(&mut nonce);
+        let ciphertext = self
+            .cipher
+            .encrypt(&Nonce::from(nonce), token.as_bytes())
+            .map_err(|_| CipherError::Encrypt)?;
+
+        let mut sealed = nonce.to_vec();
+        sealed.extend(ciphertext);
+        Ok(URL_SAFE_NO_PAD.encode(sealed))
+    }
+
+    /// Decrypt a token encrypted with [`TokenCipher::encrypt`]
+    pub fn decrypt(&self, sealed: &str) -> Result<String, CipherError> {
+        let sealed = URL_SAFE_NO_PAD
+            .decode(sealed)
+            .map_err(|_| CipherError::Decrypt)?;
+        if sealed.len() < 12 {
+            return Err(CipherError::Decrypt);
+        }
+        let (nonce, ciphertext) = sealed.split_at(12);
+        let nonce: [u8; 12] = nonce.try_into().map_err(|_| CipherError::Decrypt)?;
+        let token = self
+            .cipher
+            .decrypt(&Nonce::from(nonce), ciphertext)
+            .map_err(|_| CipherError::Decrypt)?;
+        String::from_utf8(token).map_err(|_| CipherError::Decrypt)
+    }
+}
+
+#[cfg(all(test, feature = "server"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_token_cipher_round_trip() {
+        let cipher = TokenCipher::new("secret");
+        let sealed = cipher.encrypt("refresh-token").unwrap();
+        assert!(!sealed.contains("refresh-token"));
+        assert_eq!(cipher.decrypt(&sealed).unwrap(), "refresh-token");
+        assert_eq!(
+            TokenCipher::new("other").decrypt(&sealed),
+            Err(CipherError::Decrypt)
+        );
+    }
+}
@@ -47,19 +47,23 @@ impl JwtManager {
         email: &str,
         roles: Vec<String>,
     ) -> Result<String, jsonwebtoken::errors::Error> {
+        let claims = self.issue_claims(user_id, email, roles);
+        encode(&Header::new(self.algorithm), &claims, &self.encoding_key)
+    }
+
+    /// Build the claims of a new token for a user, without encoding them
+    pub fn issue_claims(&self, user_id: &str, email: &str, roles: Vec<String>) -> Claims {
         let now = Utc::now();
         let expiration = now + Duration::hours(self.expiration_hours);
 
-        let claims = Claims {
+        Claims {
             sub: user_id.to_string(),
             email: email.to_string(),
             exp: expiration.timestamp(),
             iat: now.timestamp(),
             jti: Uuid::new_v4().to_string(),
             roles,
-        };
-
-        encode(&Header::new(self.algorithm), &claims, &self.encoding_key)
+        }
     }
 
     /// Validate and decode a JWT token
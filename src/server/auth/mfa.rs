@@ -0,0 +1,561 @@
+// TOTP multi-factor authentication
+//
+// Users enroll an authenticator app with a secret shown as an otpauth URI,
+// confirm it with a first code and receive single-use recovery codes. Once
+// enabled, a password login returns a short-lived challenge token instead of
+// a JWT; the token and a TOTP or recovery code are exchanged for the JWT.
+//
+// Codes follow RFC 6238: HMAC-SHA1, 6 digits, 30 second steps, accepting one
+// step of clock skew either way. A step is accepted once per user. Wrong codes
+// are counted per user in storage, so that new challenges do not reset them,
+// and lock code entry for a while once too many are entered in a row.
+
+#[cfg(feature = "server")]
+use axum::http::StatusCode;
+#[cfg(feature = "server")]
+use chrono::{DateTime, Duration, Utc};
+#[cfg(feature = "server")]
+use data_encoding::BASE32_NOPAD;
+#[cfg(feature = "server")]
+use hmac::{Hmac, Mac};
+#[cfg(feature = "server")]
+use jsonwebtoken::{decode, encode, Algorithm, DecodingKey, EncodingKey, Header, Validation};
+#[cfg(feature = "server")]
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "server")]
+use sha1::Sha1;
+#[cfg(feature = "server")]
+use sha2::{Digest, Sha256};
+#[cfg(feature = "server")]
+use std::sync::Arc;
+#[cfg(feature = "server")]
+use uuid::Uuid;
+
+#[cfg(feature = "server")]
+use super::cipher::{CipherError, TokenCipher};
+#[cfg(feature = "server")]
+use crate::server::storage::{MfaSettings, MfaStorage, StorageError};
+
+/// Length of a TOTP time step
+#[cfg(feature = "server")]
+pub const TOTP_PERIOD_SECS: i64 = 30;
+
+/// Number of recovery codes issued at a time
+#[cfg(feature = "server")]
+pub const RECOVERY_CODE_COUNT: usize = 10;
+
+/// Wrong codes in a row after which a user's codes are refused for a while
+#[cfg(feature = "server")]
+pub const MAX_FAILED_ATTEMPTS: i32 = 5;
+
+/// How long codes are refused after too many wrong ones
+#[cfg(feature = "server")]
+pub const LOCKOUT_SECS: i64 = 15 * 60;
+
+#[cfg(feature = "server")]
+const CHALLENGE_PURPOSE: &str = "mfa";
+
+/// Multi-factor authentication failure
+#[cfg(feature = "server")]
+#[derive(Debug, thiserror::Error)]
+pub enum MfaError {
+    #[error("MFA enrollment has not been started")]
+    NotEnrolled,
+
+    #[error("MFA is already enabled")]
+    AlreadyEnabled,
+
+    #[error("MFA is not enabled")]
+    NotEnabled,
+
+    #[error("Invalid MFA code")]
+    InvalidCode,
+
+    #[error("Invalid or expired MFA challenge")]
+    InvalidChallenge,
+
+    #[error("Too many invalid MFA codes; try again later")]
+    TooManyAttempts,
+
+    #[error("Storage error: {0}")]
+    Storage(String),
+}
+
+#[cfg(feature = "server")]
+impl MfaError {
+    /// HTTP status to answer the request with
+    pub fn status(&self) -> StatusCode {
+        match self {
+            Self::NotEnrolled | Self::NotEnabled => StatusCode::BAD_REQUEST,
+            Self::AlreadyEnabled => StatusCode::CONFLICT,
+            Self::InvalidCode | Self::InvalidChallenge => StatusCode::UNAUTHORIZED,
+            Self::TooManyAttempts => StatusCode::TOO_MANY_REQUESTS,
+            Self::Storage(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
+#[cfg(feature = "server")]
+impl From<StorageError> for MfaError {
+    fn from(e: StorageError) -> Self {
+        Self::Storage(e.to_string())
+    }
+}
+
+#[cfg(feature = "server")]
+impl From<CipherError> for MfaError {
+    fn from(e: CipherError) -> Self {
+        Self::Storage(e.to_string())
+    }
+}
+
+#[cfg(feature = "server")]
+type Result<T> = std::result::Result<T, MfaError>;
+
+/// Generate a random base32 TOTP secret
+#[cfg(feature = "server")]
+pub fn generate_secret() -> String {
+    let bytes: [u8; 20] = rand::random();
+    BASE32_NOPAD.encode(&bytes)
+}
+
+/// TOTP code of a base32 secret at a Unix time
+///
+/// Returns `None` when the secret is not valid base32.
+#[cfg(feature = "server")]
+pub fn totp(secret: &str, time: i64) -> Option<String> {
+    let key = BASE32_NOPAD
+        .decode(secret.trim_end_matches('=').as_bytes())
+        .ok()?;
+    Some(totp_at_step(&key, time.div_euclid(TOTP_PERIOD_SECS)))
+}
+
+#[cfg(feature = "server")]
+fn totp_at_step(key: &[u8], step: i64) -> String {
+    let mut mac = Hmac::<Sha1>::new_from_slice(key).expect("HMAC accepts keys of any length");
+    mac.update(&step.to_be_bytes());
+    let digest = mac.finalize().into_bytes();
+
+    let offset = (digest[19] & 0x0f) as usize;
+    let binary = u32::from_be_bytes([
+        digest[offset] & 0x7f,
+        digest[offset + 1],
+        digest[offset + 2],
+        digest[offset + 3],
+    ]);
+    format!("{:06}", binary % 1_000_000)
+}
+
+/// Check a TOTP code, returning the time step it matched
+#[cfg(feature = "server")]
+pub fn verify_totp(secret: &str, code: &str, time: i64) -> Option<i64> {
+    let key = BASE32_NOPAD
+        .decode(secret.trim_end_matches('=').as_bytes())
+        .ok()?;
+    let current = time.div_euclid(TOTP_PERIOD_SECS);
+
+    (current - 1..=current + 1).find(|step| constant_time_eq(&totp_at_step(&key, *step), code))
+}
+
+/// URI that authenticator apps import, usually shown as a QR code
+#[cfg(feature = "server")]
+pub fn otpauth_uri(issuer: &str, account: &str, secret: &str) -> String {
+    let mut uri = reqwest::Url::parse("otpauth://totp/").expect("static URI is valid");
+    uri.set_path(&format!("{}:{}", issuer, account));
+    uri.query_pairs_mut()
+        .append_pair("secret", secret)
+        .append_pair("issuer", issuer)
+        .append_pair("algorithm", "SHA1")
+        .append_pair("digits", "6")
+        .append_pair("period", &TOTP_PERIOD_SECS.to_string());
+    uri.into()
+}
+
+/// Generate a set of single-use recovery codes, such as `k3xq-7mzp`
+#[cfg(feature = "server")]
+pub fn generate_recovery_codes() -> Vec<String> {
+    (0..RECOVERY_CODE_COUNT)
+        .map(|_| {
+            let bytes: [u8; 5] = rand::random();
+            let code = BASE32_NOPAD.encode(&bytes).to_lowercase();
+            format!("{}-{}", &code[..4], &code[4..])
+        })
+        .collect()
+}
+
+/// Hash of a recovery code, as stored in place of the code
+///
+/// Dashes, whitespace and case are ignored.
+#[cfg(feature = "server")]
+pub fn hash_recovery_code(code: &str) -> String {
+    let normalized: String = code
+        .chars()
+        .filter(|c| *c != '-' && !c.is_whitespace())
+        .flat_map(char::to_lowercase)
+        .collect();
+    format!("{:x}", Sha256::digest(normalized.as_bytes()))
+}
+
+#[cfg(feature = "server")]
+fn constant_time_eq(a: &str, b: &str) -> bool {
+    a.len() == b.len()
+        && a.bytes()
+            .zip(b.bytes())
+            .fold(0u8, |acc, (x, y)| acc | (x ^ y))
+            == 0
+}
+
+/// Secret to add to an authenticator app
+#[cfg(feature = "server")]
+#[derive(Debug, Clone, Serialize)]
+pub struct Enrollment {
+    pub secret: String,
+    pub otpauth_uri: String,
+}
+
+/// MFA state of a user, without secrets
+#[cfg(feature = "server")]
+#[derive(Debug, Clone, Serialize)]
+pub struct MfaStatus {
+    pub enabled: bool,
+    pub enrollment_pending: bool,
+    pub recovery_codes_remaining: usize,
+    pub verified_at: Option<DateTime<Utc>>,
+}
+
+#[cfg(feature = "server")]
+#[derive(Debug, Serialize, Deserialize)]
+struct ChallengeClaims {
+    sub: String,
+    purpose: String,
+    exp: i64,
+    iat: i64,
+    jti: String,
+}
+
+/// Enrolls users in TOTP and checks their codes
+#[cfg(feature = "server")]
+pub struct MfaService {
+    settings: Arc<dyn MfaStorage>,
+    cipher: TokenCipher,
+    issuer: String,
+    challenge_encoding: EncodingKey,
+    challenge_decoding: DecodingKey,
+    challenge_ttl: Duration,
+}
+
+#[cfg(feature = "server")]
+impl MfaService {
+    /// Create the service
+    ///
+    /// Secrets are encrypted, and challenge tokens signed, with keys derived
+    /// from `secret`. Challenge tokens are signed with a different key than
+    /// JWTs, so neither is accepted in place of the other.
+    pub fn new(
+        settings: Arc<dyn MfaStorage>,
+        secret: &str,
+        issuer: &str,
+        challenge_ttl_secs: u64,
+    ) -> Self {
+        let challenge_key = Sha256::digest(format!("mfa-challenge:{}", secret).as_bytes());
+        Self {
+            settings,
+            cipher: TokenCipher::new(&format!("mfa-secret:{}", secret)),
+            issuer: issuer.to_string(),
+            challenge_encoding: EncodingKey::from_secret(&challenge_key),
+            challenge_decoding: DecodingKey::from_secret(&challenge_key),
+            challenge_ttl: Duration::seconds(challenge_ttl_secs as i64),
+        }
+    }
+
+    /// Seconds a challenge token is valid for
+    pub fn challenge_ttl_secs(&self) -> i64 {
+        self.challenge_ttl.num_seconds()
+    }
+
+    /// Whether a user must enter a code to log in
+    pub async fn is_enabled(&self, user_id: Uuid) -> Result<bool> {
+        Ok(self
+            .settings
+            .get_mfa_settings(user_id)
+            .await?
+            .is_some_and(|settings| settings.is_enabled))
+    }
+
+    /// MFA state of a user
+    pub async fn status(&self, user_id: Uuid) -> Result<MfaStatus> {
+        let settings = self.settings.get_mfa_settings(user_id).await?;
+        Ok(MfaStatus {
+            enabled: settings.as_ref().is_some_and(|s| s.is_enabled),
+            enrollment_pending: settings.as_ref().is_some_and(|s| !s.is_enabled),
+            recovery_codes_remaining: settings
+                .as_ref()
+                .filter(|s| s.is_enabled)
+                .map_or(0, |s| s.recovery_code_hashes.len()),
+            verified_at: settings.and_then(|s| s.verified_at),
+        })
+    }
+
+    /// Start an enrollment with a new secret
+    ///
+    /// MFA stays disabled until the enrollment is confirmed. Starting again
+    /// replaces an unconfirmed secret.
+    pub async fn enroll(&self, user_id: Uuid, account: &str) -> Result<Enrollment> {
+        let existing = self.settings.get_mfa_settings(user_id).await?;
+        if existing.as_ref().is_some_and(|s| s.is_enabled) {
+            return Err(MfaError::AlreadyEnabled);
+        }
+
+        let secret = generate_secret();
+        let settings = MfaSettings {
+            id: existing.map_or_else(Uuid::new_v4, |s| s.id),
+            user_id,
+            method: "totp".to_string(),
+            secret_encrypted: Some(self.cipher.encrypt(&secret)?),
+            recovery_code_hashes: Vec::new(),
+            is_enabled: false,
+            last_used_step: None,
+            failed_attempts: 0,
+            locked_until: None,
+            verified_at: None,
+            created_at: Utc::now(),
+        };
+        self.settings.upsert_mfa_settings(&settings).await?;
+
+        Ok(Enrollment {
+            otpauth_uri: otpauth_uri(&self.issuer, account, &secret),
+            secret,
+        })
+    }
+
+    /// Confirm an enrollment with a code from the app
+    ///
+    /// Enables MFA and returns the recovery codes, which are not shown again.
+    pub async fn confirm(&self, user_id: Uuid, code: &str) -> Result<Vec<String>> {
+        let mut settings = self
+            .settings
+            .get_mfa_settings(user_id)
+            .await?
+            .ok_or(MfaError::NotEnrolled)?;
+        if settings.is_enabled {
+            return Err(MfaError::AlreadyEnabled);
+        }
+
+        let secret = self.secret(&settings)?;
+        let step = verify_totp(&secret, code.trim(), Utc::now().timestamp())
+            .ok_or(MfaError::InvalidCode)?;
+
+        let recovery_codes = generate_recovery_codes();
+        settings.recovery_code_hashes = recovery_codes
+            .iter()
+            .map(|code| hash_recovery_code(code))
+            .collect();
+        settings.is_enabled = true;
+        settings.last_used_step = Some(step);
+        settings.verified_at = Some(Utc::now());
+        self.settings.upsert_mfa_settings(&settings).await?;
+
+        Ok(recovery_codes)
+    }
+
+    /// Replace a user's recovery codes, after checking a code
+    pub async fn regenerate_recovery_codes(
+        &self,
+        user_id: Uuid,
+        code: &str,
+    ) -> Result<Vec<String>> {
+        self.verify(user_id, code).await?;
+        let mut settings = self
+            .settings
+            .get_mfa_settings(user_id)
+            .await?
+            .ok_or(MfaError::NotEnabled)?;
+
+        let recovery_codes = generate_recovery_codes();
+        settings.recovery_code_hashes = recovery_codes
+            .iter()
+            .map(|code| hash_recovery_code(code))
+            .collect();
+        self.settings.upsert_mfa_settings(&settings).await?;
+
+        Ok(recovery_codes)
+    }
+
+    /// Turn MFA off, after checking a code
+    pub async fn disable(&self, user_id: Uuid, code: &str) -> Result<()> {
+        self.verify(user_id, code).await?;
+        self.settings.delete_mfa_settings(user_id).await?;
+        Ok(())
+    }
+
+    /// Turn MFA off without a code, for admins helping a locked-out user
+    pub async fn reset(&self, user_id: Uuid) -> Result<()> {
+        self.settings.delete_mfa_settings(user_id).await?;
+        Ok(())
+    }
+
+    /// Issue the challenge token of a login that awaits its code
+    pub fn issue_challenge(&self, user_id: Uuid) -> Result<String> {
+        let now = Utc::now();
+        let claims = ChallengeClaims {
+            sub: user_id.to_string(),
+            purpose: CHALLENGE_PURPOSE.to_string(),
+            exp: (now + self.challenge_ttl).timestamp(),
+            iat: now.timestamp(),
+            jti: Uuid::new_v4().to_string(),
+        };
+        encode(
+            &Header::new(Algorithm::HS256),
+            &claims,
+            &self.challenge_encoding,
+        )
+        .map_err(|e| MfaError::Storage(format!("Failed to sign challenge: {}", e)))
+    }
+
+    /// Complete a login with its challenge token and a code
+    ///
+    /// A challenge is used up by the first code entered for it, right or
+    /// wrong, so a replayed token is refused before its code is checked and
+    /// cannot use up a TOTP step or recovery code. Returns the ID of the user
+    /// to issue a JWT for.
+    pub async fn complete_challenge(&self, challenge: &str, code: &str) -> Result<Uuid> {
+        let claims = decode::<ChallengeClaims>(
+            challenge,
+            &self.challenge_decoding,
+            &Validation::new(Algorithm::HS256),
+        )
+        .map_err(|_| MfaError::InvalidChallenge)?
+        .claims;
+        if claims.purpose != CHALLENGE_PURPOSE {
+            return Err(MfaError::InvalidChallenge);
+        }
+        let user_id = Uuid::parse_str(&claims.sub).map_err(|_| MfaError::InvalidChallenge)?;
+
+        let expires_at =
+            DateTime::from_timestamp(claims.exp, 0).ok_or(MfaError::InvalidChallenge)?;
+        if !self
+            .settings
+            .consume_mfa_challenge(&claims.jti, expires_at)
+            .await?
+        {
+            return Err(MfaError::InvalidChallenge);
+        }
+        self.verify(user_id, code).await?;
+        Ok(user_id)
+    }
+
+    /// Check a TOTP or recovery code of a user with MFA enabled
+    ///
+    /// A TOTP step or recovery code is accepted once. After too many wrong
+    /// codes in a row, every code is refused until the lockout ends.
+    async fn verify(&self, user_id: Uuid, code: &str) -> Result<()> {
+        let settings = self
+            .settings
+            .get_mfa_settings(user_id)
+            .await?
+            .filter(|settings| settings.is_enabled)
+            .ok_or(MfaError::NotEnabled)?;
+        if settings
+            .locked_until
+            .is_some_and(|locked_until| locked_until > Utc::now())
+        {
+            return Err(MfaError::TooManyAttempts);
+        }
+
+        let code = code.trim();
+        let accepted = if code.len() == 6 && code.bytes().all(|b| b.is_ascii_digit()) {
+            let secret = self.secret(&settings)?;
+            match verify_totp(&secret, code, Utc::now().timestamp()) {
+                Some(step) => self.settings.record_totp_step(user_id, step).await?,
+                None => false,
+            }
+        } else {
+            self.settings
+                .consume_recovery_code(user_id, &hash_recovery_code(code))
+                .await?
+        };
+
+        if accepted {
+            if settings.failed_attempts > 0 {
+                self.settings.reset_mfa_failures(user_id).await?;
+            }
+            Ok(())
+        } else {
+            let locked_until = Utc::now() + Duration::seconds(LOCKOUT_SECS);
+            if self
+                .settings
+                .record_mfa_failure(user_id, MAX_FAILED_ATTEMPTS, locked_until)
+                .await?
+            {
+                tracing::warn!(
+                    "MFA of user {} locked for {}s after {} invalid codes",
+                    user_id,
+                    LOCKOUT_SECS,
+                    MAX_FAILED_ATTEMPTS
+                );
+            }
+            Err(MfaError::InvalidCode)
+        }
+    }
+
+    fn secret(&self, settings: &MfaSettings) -> Result<String> {
+        let sealed = settings
+            .secret_encrypted
+            .as_deref()
+            .ok_or(MfaError::NotEnrolled)?;
+        Ok(self.cipher.decrypt(sealed)?)
+    }
+}
+
+#[cfg(all(test, feature = "server"))]
+mod tests {
+    use super::*;
+
+    // RFC 6238 test secret "12345678901234567890" in base32
+    const RFC_SECRET: &str = "GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ";
+
+    #[test]
+    fn test_totp_rfc6238_vectors() {
+        assert_eq!(totp(RFC_SECRET, 59).unwrap(), "287082");
+        assert_eq!(totp(RFC_SECRET, 1_111_111_109).unwrap(), "081804");
+        assert_eq!(totp(RFC_SECRET, 1_234_567_890).unwrap(), "005924");
+        assert_eq!(totp(RFC_SECRET, 20_000_000_000).unwrap(), "353130");
+        assert!(totp("not base32!", 59).is_none());
+    }
+
+    #[test]
+    fn test_verify_totp_window() {
+        let now = 1_111_111_109;
+        let step = now / TOTP_PERIOD_SECS;
+        assert_eq!(verify_totp(RFC_SECRET, "081804", now), Some(step));
+
+        let previous = totp(RFC_SECRET, now - TOTP_PERIOD_SECS).unwrap();
+        assert_eq!(verify_totp(RFC_SECRET, &previous, now), Some(step - 1));
+        let stale = totp(RFC_SECRET, now - 2 * TOTP_PERIOD_SECS).unwrap();
+        assert_eq!(verify_totp(RFC_SECRET, &stale, now), None);
+    }
+
+    #[test]
+    fn test_otpauth_uri() {
+        let uri = otpauth_uri("Periplon", "ada@example.com", "ABC234");
+        assert_eq!(
+            uri,
+            "otpauth://totp/Periplon:ada@example.com?secret=ABC234&issuer=Periplon&algorithm=SHA1&digits=6&period=30"
+        );
+    }
+
+    #[test]
+    fn test_recovery_codes() {
+        let codes = generate_recovery_codes();
+        assert_eq!(codes.len(), RECOVERY_CODE_COUNT);
+        assert!(codes
+            .iter()
+            .all(|code| code.len() == 9 && &code[4..5] == "-"));
+        assert_eq!(
+            hash_recovery_code(&codes[0]),
+            hash_recovery_code(&codes[0].replace('-', " ").to_uppercase())
+        );
+        assert_ne!(hash_recovery_code(&codes[0]), hash_recovery_code(&codes[1]));
+        assert_eq!(generate_secret().len(), 32);
+    }
+}
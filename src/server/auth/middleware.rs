@@ -3,7 +3,7 @@
 #[cfg(feature = "server")]
 use axum::{
     body::Body,
-    extract::Request,
+    extract::{ConnectInfo, Request},
     http::{HeaderMap, StatusCode},
     middleware::Next,
     response::{IntoResponse, Response},
@@ -12,17 +12,33 @@ use axum::{
 #[cfg(feature = "server")]
 use serde_json::json;
 #[cfg(feature = "server")]
+use std::net::SocketAddr;
+#[cfg(feature = "server")]
 use std::sync::Arc;
 
+#[cfg(feature = "server")]
+use super::api_key::{self, API_KEY_HEADER};
 #[cfg(feature = "server")]
 use super::jwt::{Claims, JwtManager};
+#[cfg(feature = "server")]
+use crate::server::storage::{ApiKeyStorage, UserStorage};
 
 /// Shared authentication state
 #[cfg(feature = "server")]
 #[derive(Clone)]
 pub struct AuthLayer {
     jwt_manager: Arc<JwtManager>,
+    api_keys: Option<ApiKeyAuth>,
     public_paths: Vec<String>,
+    trusted_proxies: Vec<String>,
+}
+
+/// Storage needed to authenticate API keys
+#[cfg(feature = "server")]
+#[derive(Clone)]
+struct ApiKeyAuth {
+    keys: Arc<dyn ApiKeyStorage>,
+    users: Arc<dyn UserStorage>,
 }
 
 #[cfg(feature = "server")]
@@ -30,6 +46,7 @@ impl AuthLayer {
     pub fn new(jwt_manager: Arc<JwtManager>) -> Self {
         Self {
             jwt_manager,
+            api_keys: None,
             public_paths: vec![
                 "/health".to_string(),
                 "/ready".to_string(),
@@ -39,6 +56,7 @@ impl AuthLayer {
                 "/api/v1/auth/login".to_string(),
                 "/api/v1/auth/register".to_string(),
             ],
+            trusted_proxies: Vec::new(),
         }
     }
 
@@ -46,6 +64,25 @@ impl AuthLayer {
         self.public_paths = paths;
         self
     }
+
+    /// Also accept API keys sent in the `X-API-Key` header
+    pub fn with_api_keys(
+        mut self,
+        keys: Arc<dyn ApiKeyStorage>,
+        users: Arc<dyn UserStorage>,
+    ) -> Self {
+        self.api_keys = Some(ApiKeyAuth { keys, users });
+        self
+    }
+
+    /// Proxies (addresses or CIDR blocks) whose forwarding headers are believed
+    ///
+    /// Without them, API key IP whitelists are checked against the address of
+    /// the connection.
+    pub fn with_trusted_proxies(mut self, proxies: Vec<String>) -> Self {
+        self.trusted_proxies = proxies;
+        self
+    }
 }
 
 /// Extract JWT token from Authorization header
@@ -57,7 +94,21 @@ fn extract_token(headers: &HeaderMap) -> Option<String> {
         .and_then(|value| value.strip_prefix("Bearer ").map(|s| s.to_string()))
 }
 
+/// Extract API key from the X-API-Key header
+#[cfg(feature = "server")]
+fn extract_api_key(headers: &HeaderMap) -> Option<String> {
+    headers
+        .get(API_KEY_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .map(|value| value.to_string())
+}
+
 /// Authentication middleware
+///
+/// Accepts a JWT bearer token, or an API key when the layer was built with
+/// [`AuthLayer::with_api_keys`]. Either way the caller's `Claims` are stored in
+/// the request extensions; API key requests also get an
+/// [`ApiKeyIdentity`](super::api_key::ApiKeyIdentity).
 #[cfg(feature = "server")]
 pub async fn auth_middleware(
     auth_layer: AuthLayer,
@@ -71,6 +122,31 @@ pub async fn auth_middleware(
         return Ok(next.run(request).await);
     }
 
+    // Authenticate with an API key if one was sent
+    if let (Some(api_keys), Some(key)) = (&auth_layer.api_keys, extract_api_key(request.headers()))
+    {
+        let peer = request
+            .extensions()
+            .get::<ConnectInfo<SocketAddr>>()
+            .map(|ConnectInfo(address)| address.ip());
+        let client_ip = api_key::client_ip(peer, request.headers(), &auth_layer.trusted_proxies);
+        let (claims, identity) = api_key::authenticate(
+            api_keys.keys.as_ref(),
+            api_keys.users.as_ref(),
+            &auth_layer.jwt_manager,
+            &key,
+            request.method(),
+            path,
+            client_ip,
+        )
+        .await
+        .map_err(|rejection| rejection.status())?;
+
+        request.extensions_mut().insert(claims);
+        request.extensions_mut().insert(identity);
+        return Ok(next.run(request).await);
+    }
+
     // Extract token from header
     let token = extract_token(request.headers()).ok_or(StatusCode::UNAUTHORIZED)?;
 
@@ -177,4 +253,13 @@ mod tests {
         let token = extract_token(&headers);
         assert_eq!(token, None);
     }
+
+    #[test]
+    fn test_extract_api_key() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(extract_api_key(&headers), None);
+
+        headers.insert("X-API-Key", "sk_test_123".parse().unwrap());
+        assert_eq!(extract_api_key(&headers), Some("sk_test_123".to_string()));
+    }
 }
@@ -1,20 +1,47 @@
 // Authentication and authorization module
 
+#[cfg(feature = "server")]
+pub mod api_key;
+
+#[cfg(feature = "server")]
+pub mod cipher;
+
 #[cfg(feature = "server")]
 pub mod jwt;
 
+#[cfg(feature = "server")]
+pub mod mfa;
+
 #[cfg(feature = "server")]
 pub mod middleware;
 
+#[cfg(feature = "server")]
+pub mod oidc;
+
+#[cfg(feature = "server")]
+pub mod sso;
+
 #[cfg(feature = "server")]
 pub mod authorization;
 
+#[cfg(feature = "server")]
+pub use api_key::{ApiKeyIdentity, ApiKeyRejection, API_KEY_HEADER};
+
 #[cfg(feature = "server")]
 pub use jwt::{Claims, JwtManager};
 
+#[cfg(feature = "server")]
+pub use mfa::{MfaError, MfaService};
+
 #[cfg(feature = "server")]
 pub use middleware::AuthLayer;
 
+#[cfg(feature = "server")]
+pub use oidc::OidcError;
+
+#[cfg(feature = "server")]
+pub use sso::SsoService;
+
 #[cfg(feature = "server")]
 pub use authorization::{
     check_permission, check_resource_access, AuthorizationService, PostgresAuthorizationService,
@@ -0,0 +1,423 @@
+// OpenID Connect client
+//
+// Implements the authorization code flow with PKCE against providers found
+// through OIDC discovery. ID tokens are checked against the provider's JWKS.
+
+#[cfg(feature = "server")]
+use axum::http::StatusCode;
+#[cfg(feature = "server")]
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine as _};
+#[cfg(feature = "server")]
+use jsonwebtoken::jwk::JwkSet;
+#[cfg(feature = "server")]
+use jsonwebtoken::{decode, decode_header, Algorithm, DecodingKey, Validation};
+#[cfg(feature = "server")]
+use serde::Deserialize;
+#[cfg(feature = "server")]
+use serde_json::{Map, Value};
+#[cfg(feature = "server")]
+use sha2::{Digest, Sha256};
+#[cfg(feature = "server")]
+use std::collections::HashMap;
+#[cfg(feature = "server")]
+use tokio::sync::RwLock;
+
+#[cfg(feature = "server")]
+use super::cipher::CipherError;
+#[cfg(feature = "server")]
+use crate::server::config::OAuthProvider;
+
+/// Single sign-on failure
+#[cfg(feature = "server")]
+#[derive(Debug, thiserror::Error)]
+pub enum OidcError {
+    #[error("Unknown SSO provider: {0}")]
+    UnknownProvider(String),
+
+    #[error("Provider '{0}' does not support single sign-on")]
+    NotOidc(String),
+
+    #[error("Unknown or expired login state")]
+    InvalidState,
+
+    #[error("Provider request failed: {0}")]
+    Provider(String),
+
+    #[error("Invalid ID token: {0}")]
+    InvalidIdToken(String),
+
+    #[error("{0}")]
+    Forbidden(String),
+
+    #[error("No refresh token is stored for provider '{0}'")]
+    NoRefreshToken(String),
+
+    #[error("Storage error: {0}")]
+    Storage(String),
+}
+
+#[cfg(feature = "server")]
+impl OidcError {
+    /// HTTP status to answer the request with
+    pub fn status(&self) -> StatusCode {
+        match self {
+            Self::UnknownProvider(_) | Self::NotOidc(_) => StatusCode::NOT_FOUND,
+            Self::InvalidState | Self::NoRefreshToken(_) => StatusCode::BAD_REQUEST,
+            Self::Provider(_) => StatusCode::BAD_GATEWAY,
+            Self::InvalidIdToken(_) => StatusCode::UNAUTHORIZED,
+            Self::Forbidden(_) => StatusCode::FORBIDDEN,
+            Self::Storage(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
+#[cfg(feature = "server")]
+impl From<CipherError> for OidcError {
+    fn from(e: CipherError) -> Self {
+        Self::Storage(e.to_string())
+    }
+}
+
+#[cfg(feature = "server")]
+type Result<T> = std::result::Result<T, OidcError>;
+
+/// Endpoints of a provider, from its discovery document
+#[cfg(feature = "server")]
+#[derive(Debug, Clone, Deserialize)]
+pub struct ProviderMetadata {
+    pub issuer: String,
+    pub authorization_endpoint: String,
+    pub token_endpoint: String,
+    pub jwks_uri: String,
+}
+
+/// Response of a provider's token endpoint
+#[cfg(feature = "server")]
+#[derive(Debug, Clone, Deserialize)]
+pub struct TokenResponse {
+    pub access_token: String,
+    #[serde(default)]
+    pub refresh_token: Option<String>,
+    #[serde(default)]
+    pub id_token: Option<String>,
+    #[serde(default)]
+    pub expires_in: Option<i64>,
+    #[serde(default)]
+    pub scope: Option<String>,
+}
+
+/// PKCE code verifier and its S256 challenge
+#[cfg(feature = "server")]
+#[derive(Debug, Clone)]
+pub struct Pkce {
+    pub verifier: String,
+    pub challenge: String,
+}
+
+#[cfg(feature = "server")]
+impl Pkce {
+    pub fn new() -> Self {
+        let verifier = random_token();
+        let challenge = URL_SAFE_NO_PAD.encode(Sha256::digest(verifier.as_bytes()));
+        Self {
+            verifier,
+            challenge,
+        }
+    }
+}
+
+#[cfg(feature = "server")]
+impl Default for Pkce {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Random URL-safe token, for states, nonces and PKCE verifiers
+#[cfg(feature = "server")]
+pub fn random_token() -> String {
+    let bytes: [u8; 32] = rand::random();
+    URL_SAFE_NO_PAD.encode(bytes)
+}
+
+/// Client for the providers' OIDC endpoints
+///
+/// Discovery documents and key sets are cached per issuer.
+#[cfg(feature = "server")]
+pub struct OidcClient {
+    http: reqwest::Client,
+    metadata: RwLock<HashMap<String, ProviderMetadata>>,
+    keys: RwLock<HashMap<String, JwkSet>>,
+}
+
+#[cfg(feature = "server")]
+impl OidcClient {
+    pub fn new() -> Self {
+        Self {
+            http: reqwest::Client::new(),
+            metadata: RwLock::new(HashMap::new()),
+            keys: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// Discover a provider's endpoints from its issuer
+    pub async fn discover(&self, provider: &OAuthProvider) -> Result<ProviderMetadata> {
+        let issuer = provider
+            .issuer
+            .as_deref()
+            .ok_or_else(|| OidcError::NotOidc(provider.name.clone()))?;
+        if let Some(metadata) = self.metadata.read().await.get(issuer) {
+            return Ok(metadata.clone());
+        }
+
+        let url = format!(
+            "{}/.well-known/openid-configuration",
+            issuer.trim_end_matches('/')
+        );
+        let metadata: ProviderMetadata = self.get_json(&url).await?;
+        if metadata.issuer.trim_end_matches('/') != issuer.trim_end_matches('/') {
+            return Err(OidcError::Provider(format!(
+                "Discovery document of '{}' names issuer '{}'",
+                issuer, metadata.issuer
+            )));
+        }
+
+        self.metadata
+            .write()
+            .await
+            .insert(issuer.to_string(), metadata.clone());
+        Ok(metadata)
+    }
+
+    /// URL sending the user to the provider to sign in
+    pub fn authorization_url(
+        &self,
+        provider: &OAuthProvider,
+        metadata: &ProviderMetadata,
+        state: &str,
+        nonce: &str,
+        pkce: &Pkce,
+    ) -> Result<String> {
+        let scopes = if provider.scopes.iter().any(|scope| scope == "openid") {
+            provider.scopes.join(" ")
+        } else {
+            let mut scopes = vec!["openid".to_string()];
+            scopes.extend(provider.scopes.iter().cloned());
+            scopes.join(" ")
+        };
+
+        reqwest::Url::parse_with_params(
+            &metadata.authorization_endpoint,
+            &[
+                ("response_type", "code"),
+                ("client_id", provider.client_id.as_str()),
+                ("redirect_uri", redirect_uri(provider)?),
+                ("scope", scopes.as_str()),
+                ("state", state),
+                ("nonce", nonce),
+                ("code_challenge", pkce.challenge.as_str()),
+                ("code_challenge_method", "S256"),
+            ],
+        )
+        .map(String::from)
+        .map_err(|e| OidcError::Provider(format!("Invalid authorization endpoint: {}", e)))
+    }
+
+    /// Exchange an authorization code for tokens
+    pub async fn exchange_code(
+        &self,
+        provider: &OAuthProvider,
+        metadata: &ProviderMetadata,
+        code: &str,
+        verifier: &str,
+    ) -> Result<TokenResponse> {
+        self.token_request(
+            provider,
+            metadata,
+            &[
+                ("grant_type", "authorization_code"),
+                ("code", code),
+                ("redirect_uri", redirect_uri(provider)?),
+                ("code_verifier", verifier),
+            ],
+        )
+        .await
+    }
+
+    /// Get new tokens with a refresh token
+    pub async fn refresh(
+        &self,
+        provider: &OAuthProvider,
+        metadata: &ProviderMetadata,
+        refresh_token: &str,
+    ) -> Result<TokenResponse> {
+        self.token_request(
+            provider,
+            metadata,
+            &[
+                ("grant_type", "refresh_token"),
+                ("refresh_token", refresh_token),
+            ],
+        )
+        .await
+    }
+
+    /// Validate an ID token and return its claims
+    ///
+    /// Checks the signature against the provider's keys, the issuer, the
+    /// audience, the expiry and, when given, the nonce.
+    pub async fn validate_id_token(
+        &self,
+        provider: &OAuthProvider,
+        metadata: &ProviderMetadata,
+        id_token: &str,
+        nonce: Option<&str>,
+    ) -> Result<Map<String, Value>> {
+        let header =
+            decode_header(id_token).map_err(|e| OidcError::InvalidIdToken(e.to_string()))?;
+        if matches!(
+            header.alg,
+            Algorithm::HS256 | Algorithm::HS384 | Algorithm::HS512
+        ) {
+            return Err(OidcError::InvalidIdToken(
+                "Symmetric signatures are not accepted".to_string(),
+            ));
+        }
+
+        let key = self.signing_key(metadata, header.kid.as_deref()).await?;
+        let mut validation = Validation::new(header.alg);
+        validation.set_issuer(&[&metadata.issuer]);
+        validation.set_audience(&[&provider.client_id]);
+
+        let claims = decode::<Map<String, Value>>(id_token, &key, &validation)
+            .map_err(|e| OidcError::InvalidIdToken(e.to_string()))?
+            .claims;
+
+        if let Some(nonce) = nonce {
+            if claims.get("nonce").and_then(Value::as_str) != Some(nonce) {
+                return Err(OidcError::InvalidIdToken("Nonce mismatch".to_string()));
+            }
+        }
+        Ok(claims)
+    }
+
+    /// Key of the provider that signed a token
+    ///
+    /// The key set is fetched again once when no key matches, so rotated keys
+    /// are picked up.
+    async fn signing_key(
+        &self,
+        metadata: &ProviderMetadata,
+        kid: Option<&str>,
+    ) -> Result<DecodingKey> {
+        for refresh in [false, true] {
+            if refresh || !self.keys.read().await.contains_key(&metadata.jwks_uri) {
+                let keys: JwkSet = self.get_json(&metadata.jwks_uri).await?;
+                self.keys
+                    .write()
+                    .await
+                    .insert(metadata.jwks_uri.clone(), keys);
+            }
+
+            let keys = self.keys.read().await;
+            let jwk = match kid {
+                Some(kid) => keys[&metadata.jwks_uri].find(kid),
+                None if keys[&metadata.jwks_uri].keys.len() == 1 => {
+                    keys[&metadata.jwks_uri].keys.first()
+                }
+                None => None,
+            };
+            if let Some(jwk) = jwk {
+                return DecodingKey::from_jwk(jwk)
+                    .map_err(|e| OidcError::InvalidIdToken(e.to_string()));
+            }
+        }
+
+        Err(OidcError::InvalidIdToken(format!(
+            "No signing key matches key ID {:?}",
+            kid
+        )))
+    }
+
+    async fn token_request(
+        &self,
+        provider: &OAuthProvider,
+        metadata: &ProviderMetadata,
+        params: &[(&str, &str)],
+    ) -> Result<TokenResponse> {
+        let mut form = params.to_vec();
+        form.push(("client_id", &provider.client_id));
+        form.push(("client_secret", &provider.client_secret));
+
+        let response = self
+            .http
+            .post(&metadata.token_endpoint)
+            .form(&form)
+            .send()
+            .await
+            .map_err(|e| OidcError::Provider(e.to_string()))?;
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            return Err(OidcError::Provider(format!(
+                "Token endpoint returned {}: {}",
+                status, body
+            )));
+        }
+        response
+            .json()
+            .await
+            .map_err(|e| OidcError::Provider(format!("Invalid token response: {}", e)))
+    }
+
+    async fn get_json<T: serde::de::DeserializeOwned>(&self, url: &str) -> Result<T> {
+        let response = self
+            .http
+            .get(url)
+            .send()
+            .await
+            .map_err(|e| OidcError::Provider(e.to_string()))?;
+        if !response.status().is_success() {
+            return Err(OidcError::Provider(format!(
+                "{} returned {}",
+                url,
+                response.status()
+            )));
+        }
+        response
+            .json()
+            .await
+            .map_err(|e| OidcError::Provider(format!("Invalid response from {}: {}", url, e)))
+    }
+}
+
+#[cfg(feature = "server")]
+impl Default for OidcClient {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(feature = "server")]
+fn redirect_uri(provider: &OAuthProvider) -> Result<&str> {
+    provider
+        .redirect_uri
+        .as_deref()
+        .ok_or_else(|| OidcError::NotOidc(provider.name.clone()))
+}
+
+#[cfg(all(test, feature = "server"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pkce_challenge() {
+        let pkce = Pkce::new();
+        assert_eq!(pkce.verifier.len(), 43);
+        assert_eq!(
+            pkce.challenge,
+            URL_SAFE_NO_PAD.encode(Sha256::digest(pkce.verifier.as_bytes()))
+        );
+        assert_ne!(Pkce::new().verifier, pkce.verifier);
+    }
+}
@@ -0,0 +1,611 @@
+// Single sign-on
+//
+// Signs users in through the OIDC providers of the OAuth configuration.
+// Users are matched to accounts by their provider subject, then by verified
+// email, and created on first sign-in when the provider allows it. The groups
+// in the ID token decide the roles and team memberships listed in the
+// provider's group mappings; roles and teams no mapping mentions are left
+// alone.
+
+#[cfg(feature = "server")]
+use chrono::{Duration, Utc};
+#[cfg(feature = "server")]
+use serde_json::{Map, Value};
+#[cfg(feature = "server")]
+use sha2::{Digest, Sha256};
+#[cfg(feature = "server")]
+use std::collections::{BTreeSet, HashMap};
+#[cfg(feature = "server")]
+use std::sync::Arc;
+#[cfg(feature = "server")]
+use uuid::Uuid;
+
+#[cfg(feature = "server")]
+use super::cipher::TokenCipher;
+#[cfg(feature = "server")]
+use super::oidc::{random_token, OidcClient, OidcError, Pkce, TokenResponse};
+#[cfg(feature = "server")]
+use crate::server::config::{OAuthConfig, OAuthProvider};
+#[cfg(feature = "server")]
+use crate::server::storage::{
+    password, OAuthConnection, OAuthConnectionStorage, OrganizationStorage, PendingLogin,
+    TeamFilter, TeamMember, TeamStorage, User, UserStorage,
+};
+
+#[cfg(feature = "server")]
+type Result<T> = std::result::Result<T, OidcError>;
+
+/// How long a started login can be completed, in seconds
+#[cfg(feature = "server")]
+pub const PENDING_LOGIN_TTL_SECS: i64 = 600;
+
+/// Started login: the provider URL to send the user to and the login's state
+#[cfg(feature = "server")]
+pub struct LoginRedirect {
+    pub url: String,
+    pub state: String,
+}
+
+/// SHA-256 hash of a login state, hex encoded
+///
+/// Pending logins are stored under it, and the login cookie carries it to
+/// tie the callback to the browser that started the login.
+#[cfg(feature = "server")]
+pub fn hash_state(state: &str) -> String {
+    format!("{:x}", Sha256::digest(state.as_bytes()))
+}
+
+/// Signs users in through OIDC providers
+#[cfg(feature = "server")]
+pub struct SsoService {
+    providers: HashMap<String, OAuthProvider>,
+    client: OidcClient,
+    cipher: TokenCipher,
+    users: Arc<dyn UserStorage>,
+    organizations: Arc<dyn OrganizationStorage>,
+    teams: Arc<dyn TeamStorage>,
+    connections: Arc<dyn OAuthConnectionStorage>,
+    secure_cookie: bool,
+}
+
+#[cfg(feature = "server")]
+impl SsoService {
+    /// Create the service for the providers of `config` that have an issuer
+    ///
+    /// Provider tokens are encrypted with `token_encryption_key`, or with
+    /// `jwt_secret` when it is not set.
+    pub fn new(
+        config: &OAuthConfig,
+        jwt_secret: &str,
+        users: Arc<dyn UserStorage>,
+        organizations: Arc<dyn OrganizationStorage>,
+        teams: Arc<dyn TeamStorage>,
+        connections: Arc<dyn OAuthConnectionStorage>,
+    ) -> Self {
+        let providers = config
+            .providers
+            .iter()
+            .filter(|provider| provider.issuer.is_some())
+            .map(|provider| (provider.name.clone(), provider.clone()))
+            .collect();
+        let key = config.token_encryption_key.as_deref().unwrap_or(jwt_secret);
+
+        Self {
+            providers,
+            client: OidcClient::new(),
+            cipher: TokenCipher::new(key),
+            users,
+            organizations,
+            teams,
+            connections,
+            secure_cookie: config.secure_cookie,
+        }
+    }
+
+    /// Whether the state cookie is marked `Secure`
+    pub fn secure_cookie(&self) -> bool {
+        self.secure_cookie
+    }
+
+    /// Start a login, returning the provider URL to send the user to
+    ///
+    /// The login is stored until its callback or for `PENDING_LOGIN_TTL_SECS`.
+    pub async fn login_url(&self, provider: &str) -> Result<LoginRedirect> {
+        let provider = self.provider(provider)?;
+        let metadata = self.client.discover(provider).await?;
+
+        let state = random_token();
+        let nonce = random_token();
+        let pkce = Pkce::new();
+        let url = self
+            .client
+            .authorization_url(provider, &metadata, &state, &nonce, &pkce)?;
+
+        let login = PendingLogin {
+            state_hash: hash_state(&state),
+            provider: provider.name.clone(),
+            verifier_encrypted: self.cipher.encrypt(&pkce.verifier)?,
+            nonce,
+            expires_at: Utc::now() + Duration::seconds(PENDING_LOGIN_TTL_SECS),
+        };
+        self.connections
+            .insert_pending_login(&login)
+            .await
+            .map_err(storage)?;
+        Ok(LoginRedirect { url, state })
+    }
+
+    /// Complete a login from the provider's callback
+    ///
+    /// Exchanges the code, validates the ID token, finds or creates the user
+    /// and applies the group mappings. Each state can be used once.
+    pub async fn complete_login(&self, provider: &str, code: &str, state: &str) -> Result<User> {
+        let login = self
+            .connections
+            .take_pending_login(&hash_state(state))
+            .await
+            .map_err(storage)?
+            .filter(|login| login.provider == provider)
+            .ok_or(OidcError::InvalidState)?;
+        let verifier = self.cipher.decrypt(&login.verifier_encrypted)?;
+
+        let provider = self.provider(provider)?;
+        let metadata = self.client.discover(provider).await?;
+        let tokens = self
+            .client
+            .exchange_code(provider, &metadata, code, &verifier)
+            .await?;
+        let id_token = tokens
+            .id_token
+            .as_deref()
+            .ok_or_else(|| OidcError::InvalidIdToken("No ID token was returned".to_string()))?;
+        let claims = self
+            .client
+            .validate_id_token(provider, &metadata, id_token, Some(&login.nonce))
+            .await?;
+        let subject = subject(&claims)?;
+
+        let connection = self
+            .connections
+            .get_oauth_connection(&provider.name, &subject)
+            .await
+            .map_err(storage)?;
+        let user = self
+            .resolve_user(provider, &claims, connection.as_ref())
+            .await?;
+
+        self.sync_groups(provider, &user, &claims).await?;
+        self.store_connection(provider, user.id, &subject, &tokens, claims, connection)
+            .await?;
+        if let Err(e) = self.users.update_last_login(user.id).await {
+            tracing::warn!("Failed to update last login of {}: {}", user.id, e);
+        }
+
+        self.reload(user.id).await
+    }
+
+    /// Refresh a user's provider tokens and apply their current groups
+    pub async fn refresh(&self, provider: &str, user_id: Uuid) -> Result<User> {
+        let provider = self.provider(provider)?;
+        let connection = self
+            .connections
+            .get_user_oauth_connection(user_id, &provider.name)
+            .await
+            .map_err(storage)?
+            .filter(|connection| connection.refresh_token_encrypted.is_some())
+            .ok_or_else(|| OidcError::NoRefreshToken(provider.name.clone()))?;
+        let refresh_token = self.cipher.decrypt(
+            connection
+                .refresh_token_encrypted
+                .as_deref()
+                .unwrap_or_default(),
+        )?;
+
+        let metadata = self.client.discover(provider).await?;
+        let tokens = self
+            .client
+            .refresh(provider, &metadata, &refresh_token)
+            .await?;
+
+        // A refreshed ID token carries the current groups; without one the
+        // claims from the last sign-in are kept
+        let claims = match tokens.id_token.as_deref() {
+            Some(id_token) => {
+                let claims = self
+                    .client
+                    .validate_id_token(provider, &metadata, id_token, None)
+                    .await?;
+                if subject(&claims)? != connection.provider_user_id {
+                    return Err(OidcError::InvalidIdToken(
+                        "Refreshed ID token is for another subject".to_string(),
+                    ));
+                }
+                claims
+            }
+            None => match &connection.profile_data {
+                Value::Object(claims) => claims.clone(),
+                _ => Map::new(),
+            },
+        };
+
+        let user = self.reload(user_id).await?;
+        if !user.is_active {
+            return Err(OidcError::Forbidden("User account is disabled".to_string()));
+        }
+
+        self.sync_groups(provider, &user, &claims).await?;
+        let subject = connection.provider_user_id.clone();
+        self.store_connection(
+            provider,
+            user_id,
+            &subject,
+            &tokens,
+            claims,
+            Some(connection),
+        )
+        .await?;
+
+        self.reload(user_id).await
+    }
+
+    fn provider(&self, name: &str) -> Result<&OAuthProvider> {
+        self.providers
+            .get(name)
+            .ok_or_else(|| OidcError::UnknownProvider(name.to_string()))
+    }
+
+    async fn reload(&self, user_id: Uuid) -> Result<User> {
+        self.users
+            .get_user(user_id)
+            .await
+            .map_err(storage)?
+            .ok_or_else(|| OidcError::Storage(format!("User {} not found", user_id)))
+    }
+
+    /// Find the user of a sign-in, creating one when allowed
+    async fn resolve_user(
+        &self,
+        provider: &OAuthProvider,
+        claims: &Map<String, Value>,
+        connection: Option<&OAuthConnection>,
+    ) -> Result<User> {
+        let linked = match connection {
+            Some(connection) => self
+                .users
+                .get_user(connection.user_id)
+                .await
+                .map_err(storage)?,
+            None => None,
+        };
+
+        let user = match linked {
+            Some(user) => user,
+            None => {
+                let email = claims
+                    .get("email")
+                    .and_then(Value::as_str)
+                    .map(str::to_lowercase)
+                    .ok_or_else(|| {
+                        OidcError::InvalidIdToken("ID token has no email claim".to_string())
+                    })?;
+
+                match self
+                    .users
+                    .get_user_by_email(&email)
+                    .await
+                    .map_err(storage)?
+                {
+                    // Linking an existing account on an unverified email
+                    // would let anyone who registers it at the provider
+                    // take the account over
+                    Some(_) if !email_verified(claims) => {
+                        return Err(OidcError::Forbidden(format!(
+                            "An account for {} exists, but the provider has not verified the email",
+                            email
+                        )));
+                    }
+                    Some(user) => user,
+                    None if provider.auto_provision => {
+                        self.provision(provider, claims, email).await?
+                    }
+                    None => {
+                        return Err(OidcError::Forbidden(format!(
+                            "No account exists for {}",
+                            email
+                        )));
+                    }
+                }
+            }
+        };
+
+        if !user.is_active {
+            return Err(OidcError::Forbidden("User account is disabled".to_string()));
+        }
+        Ok(user)
+    }
+
+    /// Create the account of a first sign-in
+    async fn provision(
+        &self,
+        provider: &OAuthProvider,
+        claims: &Map<String, Value>,
+        email: String,
+    ) -> Result<User> {
+        // SSO users sign in through the provider; the random password only
+        // fills the column
+        let password_hash = password::hash_password(&random_token())
+            .map_err(|e| OidcError::Storage(format!("Password hashing error: {}", e)))?;
+        let name = ["name", "preferred_username"]
+            .iter()
+            .find_map(|claim| claims.get(*claim).and_then(Value::as_str))
+            .unwrap_or(&email)
+            .to_string();
+
+        let now = Utc::now();
+        let user = User {
+            id: Uuid::new_v4(),
+            email,
+            name,
+            password_hash,
+            roles: provider.default_roles.clone(),
+            is_active: true,
+            email_verified: email_verified(claims),
+            created_at: now,
+            updated_at: now,
+            last_login_at: None,
+        };
+        let id = self.users.create_user(&user).await.map_err(storage)?;
+
+        Ok(User { id, ..user })
+    }
+
+    /// Apply a provider's group mappings to a user
+    async fn sync_groups(
+        &self,
+        provider: &OAuthProvider,
+        user: &User,
+        claims: &Map<String, Value>,
+    ) -> Result<()> {
+        let groups = groups(claims, &provider.groups_claim);
+        let is_member = |group: &str| groups.iter().any(|g| g == group);
+
+        let mut managed_roles = BTreeSet::new();
+        let mut granted_roles = BTreeSet::new();
+        let mut team_roles: HashMap<(&str, &str), Option<&str>> = HashMap::new();
+        for mapping in &provider.group_mappings {
+            let member = is_member(&mapping.group);
+            for role in &mapping.roles {
+                managed_roles.insert(role.as_str());
+                if member {
+                    granted_roles.insert(role.as_str());
+                }
+            }
+
+            if let (Some(organization), Some(team)) = (&mapping.organization, &mapping.team) {
+                let entry = team_roles
+                    .entry((organization.as_str(), team.as_str()))
+                    .or_insert(None);
+                if member && entry.is_none_or(|role| rank(&mapping.team_role) > rank(role)) {
+                    *entry = Some(mapping.team_role.as_str());
+                }
+            }
+        }
+
+        for role in &granted_roles {
+            if !user.roles.iter().any(|r| r == role) {
+                self.users.add_role(user.id, role).await.map_err(storage)?;
+            }
+        }
+        for role in managed_roles.difference(&granted_roles) {
+            if user.roles.iter().any(|r| r == role) {
+                self.users
+                    .remove_role(user.id, role)
+                    .await
+                    .map_err(storage)?;
+            }
+        }
+
+        for ((organization, team), role) in team_roles {
+            self.sync_team(user.id, organization, team, role).await?;
+        }
+        Ok(())
+    }
+
+    /// Give a user a role in a team, or remove them when `role` is `None`
+    async fn sync_team(
+        &self,
+        user_id: Uuid,
+        organization: &str,
+        team: &str,
+        role: Option<&str>,
+    ) -> Result<()> {
+        let Some(organization_id) = self
+            .organizations
+            .get_organization_by_slug(organization)
+            .await
+            .map_err(storage)?
+            .map(|organization| organization.id)
+        else {
+            tracing::warn!(
+                "SSO group mapping names unknown organization '{}'",
+                organization
+            );
+            return Ok(());
+        };
+        let filter = TeamFilter {
+            organization_id: Some(organization_id),
+            name: Some(team.to_string()),
+            ..Default::default()
+        };
+        let Some(team_id) = self
+            .teams
+            .list_teams(&filter)
+            .await
+            .map_err(storage)?
+            .into_iter()
+            .find(|t| t.name == team)
+            .map(|team| team.id)
+        else {
+            tracing::warn!(
+                "SSO group mapping names unknown team '{}' in '{}'",
+                team,
+                organization
+            );
+            return Ok(());
+        };
+
+        let current = self
+            .teams
+            .get_team_members(team_id)
+            .await
+            .map_err(storage)?
+            .into_iter()
+            .find(|member| member.user_id == user_id);
+
+        if let Some(member) = &current {
+            if role == Some(member.role.as_str()) {
+                return Ok(());
+            }
+            self.teams
+                .remove_team_member(team_id, user_id)
+                .await
+                .map_err(storage)?;
+        }
+        if let Some(role) = role {
+            let member = TeamMember {
+                id: Uuid::new_v4(),
+                team_id,
+                user_id,
+                role: role.to_string(),
+                added_at: Utc::now(),
+                added_by: None,
+            };
+            self.teams.add_team_member(&member).await.map_err(storage)?;
+        }
+        Ok(())
+    }
+
+    /// Store the provider tokens and claims of a user
+    async fn store_connection(
+        &self,
+        provider: &OAuthProvider,
+        user_id: Uuid,
+        subject: &str,
+        tokens: &TokenResponse,
+        claims: Map<String, Value>,
+        existing: Option<OAuthConnection>,
+    ) -> Result<()> {
+        let refresh_token_encrypted = match &tokens.refresh_token {
+            Some(token) => Some(self.cipher.encrypt(token)?),
+            None => existing
+                .as_ref()
+                .and_then(|connection| connection.refresh_token_encrypted.clone()),
+        };
+        let scopes = match &tokens.scope {
+            Some(scope) => scope.split_whitespace().map(String::from).collect(),
+            None => provider.scopes.clone(),
+        };
+
+        let now = Utc::now();
+        let connection = OAuthConnection {
+            id: existing
+                .as_ref()
+                .map_or_else(Uuid::new_v4, |connection| connection.id),
+            user_id,
+            provider: provider.name.clone(),
+            provider_user_id: subject.to_string(),
+            access_token_encrypted: Some(self.cipher.encrypt(&tokens.access_token)?),
+            refresh_token_encrypted,
+            expires_at: tokens
+                .expires_in
+                .map(|seconds| now + Duration::seconds(seconds)),
+            scopes,
+            profile_data: Value::Object(claims),
+            created_at: existing.map_or(now, |connection| connection.created_at),
+            updated_at: now,
+        };
+        self.connections
+            .upsert_oauth_connection(&connection)
+            .await
+            .map_err(storage)?;
+        Ok(())
+    }
+}
+
+#[cfg(feature = "server")]
+fn storage(e: impl std::fmt::Display) -> OidcError {
+    OidcError::Storage(e.to_string())
+}
+
+#[cfg(feature = "server")]
+fn subject(claims: &Map<String, Value>) -> Result<String> {
+    claims
+        .get("sub")
+        .and_then(Value::as_str)
+        .map(String::from)
+        .ok_or_else(|| OidcError::InvalidIdToken("ID token has no subject".to_string()))
+}
+
+/// Whether the provider verified the email, given as a bool or a string
+#[cfg(feature = "server")]
+fn email_verified(claims: &Map<String, Value>) -> bool {
+    match claims.get("email_verified") {
+        Some(Value::Bool(verified)) => *verified,
+        Some(Value::String(verified)) => verified == "true",
+        _ => false,
+    }
+}
+
+/// Groups listed in a claim, given as an array or a single string
+#[cfg(feature = "server")]
+fn groups(claims: &Map<String, Value>, claim: &str) -> Vec<String> {
+    match claims.get(claim) {
+        Some(Value::Array(groups)) => groups
+            .iter()
+            .filter_map(Value::as_str)
+            .map(String::from)
+            .collect(),
+        Some(Value::String(group)) => vec![group.clone()],
+        _ => Vec::new(),
+    }
+}
+
+/// Precedence of team roles, highest first: owner, admin, member
+#[cfg(feature = "server")]
+fn rank(role: &str) -> u8 {
+    match role {
+        "owner" => 3,
+        "admin" => 2,
+        _ => 1,
+    }
+}
+
+#[cfg(all(test, feature = "server"))]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn claims(value: Value) -> Map<String, Value> {
+        match value {
+            Value::Object(map) => map,
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn test_groups_claim() {
+        let array = claims(json!({"groups": ["eng", "ops", 3]}));
+        assert_eq!(groups(&array, "groups"), vec!["eng", "ops"]);
+        let single = claims(json!({"roles": "eng"}));
+        assert_eq!(groups(&single, "roles"), vec!["eng"]);
+        assert!(groups(&single, "groups").is_empty());
+    }
+
+    #[test]
+    fn test_email_verified() {
+        assert!(email_verified(&claims(json!({"email_verified": true}))));
+        assert!(email_verified(&claims(json!({"email_verified": "true"}))));
+        assert!(!email_verified(&claims(json!({"email_verified": false}))));
+        assert!(!email_verified(&claims(json!({}))));
+    }
+}
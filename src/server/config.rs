@@ -42,6 +42,8 @@ pub struct Config {
     pub monitoring: MonitoringConfig,
     #[serde(default)]
     pub reliability: ReliabilityConfig,
+    #[serde(default)]
+    pub scheduler: SchedulerConfig,
 }
 
 #[cfg(feature = "server")]
@@ -70,6 +72,11 @@ pub struct ServerConfig {
 
     #[serde(default)]
     pub cors: Option<CorsConfig>,
+
+    /// Proxies whose `X-Forwarded-For` and `X-Real-IP` headers are believed
+    /// when checking API key IP whitelists (addresses or CIDR blocks)
+    #[serde(default)]
+    pub trusted_proxies: Vec<String>,
 }
 
 #[cfg(feature = "server")]
@@ -280,6 +287,14 @@ pub struct AuthConfig {
     #[serde(default)]
     pub mfa_enabled: bool,
 
+    /// Issuer shown by authenticator apps
+    #[serde(default = "default_mfa_issuer")]
+    pub mfa_issuer: String,
+
+    /// How long a login can wait for its MFA code
+    #[serde(default = "default_mfa_challenge_ttl_secs")]
+    pub mfa_challenge_ttl_secs: u64,
+
     #[serde(default)]
     pub oauth: Option<OAuthConfig>,
 }
@@ -289,6 +304,14 @@ pub struct AuthConfig {
 pub struct OAuthConfig {
     pub enabled: bool,
     pub providers: Vec<OAuthProvider>,
+
+    /// Key encrypting provider tokens at rest; defaults to the JWT secret
+    #[serde(default)]
+    pub token_encryption_key: Option<String>,
+
+    /// Send the SSO state cookie over HTTPS only; turn off for plain-HTTP development
+    #[serde(default = "default_true")]
+    pub secure_cookie: bool,
 }
 
 #[cfg(feature = "server")]
@@ -298,6 +321,53 @@ pub struct OAuthProvider {
     pub client_id: String,
     pub client_secret: String,
     pub scopes: Vec<String>,
+
+    /// OIDC issuer URL; providers with an issuer offer single sign-on
+    #[serde(default)]
+    pub issuer: Option<String>,
+
+    /// Callback URL registered with the provider
+    #[serde(default)]
+    pub redirect_uri: Option<String>,
+
+    /// ID token claim listing the user's groups
+    #[serde(default = "default_groups_claim")]
+    pub groups_claim: String,
+
+    /// Create users on their first sign-in
+    #[serde(default = "default_true")]
+    pub auto_provision: bool,
+
+    /// Roles given to users created on sign-in
+    #[serde(default = "default_sso_roles")]
+    pub default_roles: Vec<String>,
+
+    /// Roles and team memberships granted to provider groups
+    #[serde(default)]
+    pub group_mappings: Vec<GroupMapping>,
+}
+
+/// Grants for the members of a provider group
+#[cfg(feature = "server")]
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct GroupMapping {
+    pub group: String,
+
+    /// Server roles of the group's members
+    #[serde(default)]
+    pub roles: Vec<String>,
+
+    /// Slug of the organization of `team`
+    #[serde(default)]
+    pub organization: Option<String>,
+
+    /// Team the group's members join
+    #[serde(default)]
+    pub team: Option<String>,
+
+    /// Role in the team: owner, admin or member
+    #[serde(default = "default_team_role")]
+    pub team_role: String,
 }
 
 #[cfg(feature = "server")]
@@ -357,6 +427,46 @@ pub struct ReliabilityConfig {
     pub health_check_interval_secs: u64,
 }
 
+#[cfg(feature = "server")]
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct SchedulerConfig {
+    /// Run the cron scheduler inside `periplon-executor server`
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+
+    #[serde(default = "default_scheduler_poll_interval_secs")]
+    pub poll_interval_secs: u64,
+
+    /// Occurrences older than this when the scheduler sees them count as missed
+    #[serde(default = "default_misfire_grace_secs")]
+    pub misfire_grace_secs: u64,
+
+    #[serde(default)]
+    pub catch_up: CatchUpPolicy,
+
+    /// Most missed occurrences started per schedule with `catch_up = "all"`
+    #[serde(default = "default_max_catch_up_runs")]
+    pub max_catch_up_runs: usize,
+
+    /// How long a replica stays leader without renewing its lease
+    #[serde(default = "default_lease_ttl_secs")]
+    pub lease_ttl_secs: u64,
+}
+
+/// What the scheduler does with occurrences missed while no replica was leading
+#[cfg(feature = "server")]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CatchUpPolicy {
+    /// Record missed occurrences as skipped and wait for the next one
+    Skip,
+    /// Start the latest missed occurrence, skip the others
+    #[default]
+    Once,
+    /// Start every missed occurrence, up to `max_catch_up_runs`
+    All,
+}
+
 // Default value functions
 #[cfg(feature = "server")]
 fn default_host() -> String {
@@ -483,6 +593,16 @@ fn default_password_min_length() -> usize {
     12
 }
 
+#[cfg(feature = "server")]
+fn default_mfa_issuer() -> String {
+    "Periplon".to_string()
+}
+
+#[cfg(feature = "server")]
+fn default_mfa_challenge_ttl_secs() -> u64 {
+    300
+}
+
 #[cfg(feature = "server")]
 fn default_true() -> bool {
     true
@@ -538,6 +658,41 @@ fn default_health_check_interval_secs() -> u64 {
     30
 }
 
+#[cfg(feature = "server")]
+fn default_scheduler_poll_interval_secs() -> u64 {
+    15
+}
+
+#[cfg(feature = "server")]
+fn default_misfire_grace_secs() -> u64 {
+    60
+}
+
+#[cfg(feature = "server")]
+fn default_max_catch_up_runs() -> usize {
+    10
+}
+
+#[cfg(feature = "server")]
+fn default_lease_ttl_secs() -> u64 {
+    60
+}
+
+#[cfg(feature = "server")]
+fn default_groups_claim() -> String {
+    "groups".to_string()
+}
+
+#[cfg(feature = "server")]
+fn default_sso_roles() -> Vec<String> {
+    vec!["user".to_string()]
+}
+
+#[cfg(feature = "server")]
+fn default_team_role() -> String {
+    "member".to_string()
+}
+
 // Default implementations for all config structs
 #[cfg(feature = "server")]
 impl Default for ServerConfig {
@@ -551,6 +706,7 @@ impl Default for ServerConfig {
             environment: default_environment(),
             tls: None,
             cors: None,
+            trusted_proxies: Vec::new(),
         }
     }
 }
@@ -625,6 +781,8 @@ impl Default for AuthConfig {
             session_max_idle_secs: default_session_max_idle_secs(),
             password_min_length: default_password_min_length(),
             mfa_enabled: false,
+            mfa_issuer: default_mfa_issuer(),
+            mfa_challenge_ttl_secs: default_mfa_challenge_ttl_secs(),
             oauth: None,
         }
     }
@@ -669,6 +827,20 @@ impl Default for ReliabilityConfig {
     }
 }
 
+#[cfg(feature = "server")]
+impl Default for SchedulerConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            poll_interval_secs: default_scheduler_poll_interval_secs(),
+            misfire_grace_secs: default_misfire_grace_secs(),
+            catch_up: CatchUpPolicy::default(),
+            max_catch_up_runs: default_max_catch_up_runs(),
+            lease_ttl_secs: default_lease_ttl_secs(),
+        }
+    }
+}
+
 #[cfg(feature = "server")]
 impl Config {
     /// Load configuration from file, environment variables, and CLI args
@@ -732,6 +904,9 @@ impl Config {
                 provider.client_id = substitute(&provider.client_id)?;
                 provider.client_secret = substitute(&provider.client_secret)?;
             }
+            if let Some(key) = &oauth.token_encryption_key {
+                oauth.token_encryption_key = Some(substitute(key)?);
+            }
         }
 
         // Substitute in storage config
@@ -777,6 +952,13 @@ impl Config {
             _ => {}
         }
 
+        // MFA settings are only stored by the PostgreSQL backend
+        if self.auth.mfa_enabled && !matches!(self.storage.backend, StorageBackend::Postgres(_)) {
+            return Err(ConfigError::ValidationError(
+                "auth.mfa_enabled requires the postgres storage backend".to_string(),
+            ));
+        }
+
         // Ensure secrets are set (required for JWT signing)
         if self.auth.jwt_secret.is_empty() || self.auth.jwt_secret.contains("${") {
             if self.server.environment == "production" {
@@ -790,6 +972,54 @@ impl Config {
             }
         }
 
+        if let Some(entry) = self
+            .server
+            .trusted_proxies
+            .iter()
+            .find(|entry| !crate::server::auth::api_key::is_valid_ip_entry(entry))
+        {
+            return Err(ConfigError::ValidationError(format!(
+                "server.trusted_proxies entry '{}' is not an address or CIDR block",
+                entry
+            )));
+        }
+
+        // A leader must renew its lease before it expires
+        if self.scheduler.enabled
+            && self.scheduler.lease_ttl_secs <= self.scheduler.poll_interval_secs
+        {
+            return Err(ConfigError::ValidationError(
+                "scheduler.lease_ttl_secs must be greater than scheduler.poll_interval_secs"
+                    .to_string(),
+            ));
+        }
+
+        // Single sign-on providers need a callback, and teams an organization
+        if let Some(oauth) = self.auth.oauth.as_ref().filter(|oauth| oauth.enabled) {
+            for provider in oauth.providers.iter().filter(|p| p.issuer.is_some()) {
+                if provider.redirect_uri.is_none() {
+                    return Err(ConfigError::ValidationError(format!(
+                        "OAuth provider '{}' has an issuer but no redirect_uri",
+                        provider.name
+                    )));
+                }
+                for mapping in &provider.group_mappings {
+                    if mapping.team.is_some() != mapping.organization.is_some() {
+                        return Err(ConfigError::ValidationError(format!(
+                            "Group mapping '{}' of OAuth provider '{}' must set both organization and team",
+                            mapping.group, provider.name
+                        )));
+                    }
+                    if !["owner", "admin", "member"].contains(&mapping.team_role.as_str()) {
+                        return Err(ConfigError::ValidationError(format!(
+                            "Group mapping '{}' of OAuth provider '{}' has unknown team role '{}'",
+                            mapping.group, provider.name, mapping.team_role
+                        )));
+                    }
+                }
+            }
+        }
+
         // Validate TLS certs exist if enabled
         if let Some(tls) = &self.server.tls {
             if tls.enabled {
@@ -828,9 +1058,8 @@ impl Config {
 mod tests {
     use super::*;
 
-    #[test]
-    fn test_default_config() {
-        let config = Config {
+    fn test_config() -> Config {
+        Config {
             server: ServerConfig {
                 host: default_host(),
                 port: default_port(),
@@ -840,6 +1069,7 @@ mod tests {
                 environment: default_environment(),
                 tls: None,
                 cors: None,
+                trusted_proxies: Vec::new(),
             },
             storage: StorageConfig {
                 backend: StorageBackend::Filesystem(FilesystemStorageConfig {
@@ -869,6 +1099,8 @@ mod tests {
                 session_max_idle_secs: default_session_max_idle_secs(),
                 password_min_length: default_password_min_length(),
                 mfa_enabled: false,
+                mfa_issuer: default_mfa_issuer(),
+                mfa_challenge_ttl_secs: default_mfa_challenge_ttl_secs(),
                 oauth: None,
             },
             rate_limit: RateLimitConfig {
@@ -892,9 +1124,34 @@ mod tests {
                 circuit_breaker_timeout_secs: default_circuit_breaker_timeout_secs(),
                 health_check_interval_secs: default_health_check_interval_secs(),
             },
-        };
+            scheduler: SchedulerConfig::default(),
+        }
+    }
+
+    #[test]
+    fn test_default_config() {
+        let config = test_config();
 
         assert_eq!(config.server.port, 8080);
         assert_eq!(config.server.worker_concurrency, 3);
     }
+
+    #[test]
+    fn test_mfa_requires_postgres() {
+        let mut config = test_config();
+        config.auth.mfa_enabled = true;
+        assert!(matches!(
+            config.validate(),
+            Err(ConfigError::ValidationError(message)) if message.contains("mfa_enabled")
+        ));
+
+        config.storage.backend = StorageBackend::Postgres(PostgresStorageConfig {
+            url: "postgres://localhost/periplon".to_string(),
+            max_connections: default_max_connections(),
+            min_connections: default_min_connections(),
+            connection_timeout: default_connection_timeout(),
+            idle_timeout: default_idle_timeout(),
+        });
+        assert!(config.validate().is_ok());
+    }
 }
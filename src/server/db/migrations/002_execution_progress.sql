@@ -0,0 +1,5 @@
+-- Task progress counts of executions
+
+ALTER TABLE executions
+    ADD COLUMN completed_tasks INT NOT NULL DEFAULT 0,
+    ADD COLUMN total_tasks INT NOT NULL DEFAULT 0;
@@ -0,0 +1,7 @@
+-- Leader lease held by the replica running the cron scheduler
+
+CREATE TABLE scheduler_leases (
+    name VARCHAR(100) PRIMARY KEY,
+    holder VARCHAR(255) NOT NULL,
+    expires_at TIMESTAMPTZ NOT NULL
+);
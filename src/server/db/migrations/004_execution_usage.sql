@@ -0,0 +1,4 @@
+-- Tokens and cost used by executions
+
+ALTER TABLE executions
+    ADD COLUMN usage JSONB NOT NULL DEFAULT '{}'::jsonb;
@@ -0,0 +1,37 @@
+-- Accounts of users at OAuth/OIDC providers, with their encrypted tokens
+
+CREATE TABLE IF NOT EXISTS oauth_connections (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    user_id UUID REFERENCES users(id) ON DELETE CASCADE,
+    provider VARCHAR(50) NOT NULL,
+    provider_user_id VARCHAR(255) NOT NULL,
+    access_token_encrypted TEXT,
+    refresh_token_encrypted TEXT,
+    expires_at TIMESTAMPTZ,
+    scopes TEXT[],
+    profile_data JSONB,
+    created_at TIMESTAMPTZ DEFAULT NOW(),
+    updated_at TIMESTAMPTZ DEFAULT NOW(),
+    UNIQUE(provider, provider_user_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_oauth_connections_user_id ON oauth_connections(user_id);
+CREATE INDEX IF NOT EXISTS idx_oauth_connections_provider ON oauth_connections(provider);
+
+DROP TRIGGER IF EXISTS update_oauth_connections_updated_at ON oauth_connections;
+CREATE TRIGGER update_oauth_connections_updated_at BEFORE UPDATE ON oauth_connections
+    FOR EACH ROW EXECUTE FUNCTION update_updated_at_column();
+
+-- Single sign-on logins waiting for the provider's callback, stored under a
+-- hash of their state so any replica can complete them. The PKCE verifier is
+-- encrypted; rows are deleted when used or once expired.
+
+CREATE TABLE IF NOT EXISTS sso_pending_logins (
+    state_hash TEXT PRIMARY KEY,
+    provider VARCHAR(50) NOT NULL,
+    verifier_encrypted TEXT NOT NULL,
+    nonce TEXT NOT NULL,
+    expires_at TIMESTAMPTZ NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_sso_pending_logins_expires_at ON sso_pending_logins(expires_at);
@@ -0,0 +1,33 @@
+-- TOTP multi-factor authentication settings of users
+--
+-- Recovery codes are stored as SHA-256 hashes, and the last accepted TOTP
+-- time step is kept so a code cannot be used twice. Wrong codes are counted
+-- per user, across login challenges, and lock code entry for a while once
+-- too many are entered in a row.
+
+CREATE TABLE IF NOT EXISTS mfa_settings (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    user_id UUID REFERENCES users(id) ON DELETE CASCADE UNIQUE,
+    method VARCHAR(50) NOT NULL CHECK (method IN ('totp', 'sms', 'email')),
+    secret_encrypted TEXT,
+    recovery_code_hashes TEXT[] NOT NULL DEFAULT '{}',
+    is_enabled BOOLEAN DEFAULT FALSE,
+    last_used_step BIGINT,
+    failed_attempts INTEGER NOT NULL DEFAULT 0,
+    locked_until TIMESTAMPTZ,
+    verified_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_mfa_settings_user_id ON mfa_settings(user_id);
+CREATE INDEX IF NOT EXISTS idx_mfa_settings_is_enabled ON mfa_settings(is_enabled);
+
+-- Login challenges completed with an MFA code, so a challenge token cannot
+-- complete a second login. Rows are removed once the token has expired.
+
+CREATE TABLE IF NOT EXISTS mfa_challenges (
+    jti TEXT PRIMARY KEY,
+    expires_at TIMESTAMPTZ NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_mfa_challenges_expires_at ON mfa_challenges(expires_at);
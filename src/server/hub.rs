@@ -0,0 +1,316 @@
+// Broadcast hub for live execution updates
+
+#[cfg(feature = "server")]
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "server")]
+use std::collections::{HashMap, HashSet};
+#[cfg(feature = "server")]
+use std::sync::RwLock;
+#[cfg(feature = "server")]
+use tokio::sync::broadcast;
+#[cfg(feature = "server")]
+use tokio_util::sync::CancellationToken;
+#[cfg(feature = "server")]
+use uuid::Uuid;
+
+#[cfg(feature = "server")]
+use super::{Execution, ExecutionStatus};
+
+/// Update of an execution streamed to WebSocket clients
+#[cfg(feature = "server")]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ExecutionStreamMessage {
+    /// Execution started
+    Started {
+        execution_id: Uuid,
+        workflow_id: Uuid,
+        started_at: String,
+    },
+    /// Task execution update
+    TaskUpdate {
+        execution_id: Uuid,
+        task_id: String,
+        status: String,
+        message: Option<String>,
+    },
+    /// Log message
+    Log {
+        execution_id: Uuid,
+        /// Position of the log among the execution's stored logs
+        sequence: usize,
+        timestamp: String,
+        level: String,
+        message: String,
+    },
+    /// Execution progress
+    Progress {
+        execution_id: Uuid,
+        completed_tasks: usize,
+        total_tasks: usize,
+        percent: f64,
+    },
+    /// Execution completed
+    Completed {
+        execution_id: Uuid,
+        status: String,
+        completed_at: String,
+        result: Option<serde_json::Value>,
+    },
+    /// Execution failed
+    Failed {
+        execution_id: Uuid,
+        error: String,
+        failed_at: String,
+    },
+    /// Keep-alive ping
+    Ping { timestamp: String },
+    /// Keep-alive pong
+    Pong { timestamp: String },
+}
+
+#[cfg(feature = "server")]
+impl ExecutionStreamMessage {
+    /// Progress message with the completion percentage of `total_tasks`
+    pub fn progress(execution_id: Uuid, completed_tasks: usize, total_tasks: usize) -> Self {
+        let percent = if total_tasks == 0 {
+            0.0
+        } else {
+            completed_tasks as f64 * 100.0 / total_tasks as f64
+        };
+        Self::Progress {
+            execution_id,
+            completed_tasks,
+            total_tasks,
+            percent,
+        }
+    }
+
+    /// Whether this message ends the stream of an execution
+    pub fn is_final(&self) -> bool {
+        matches!(self, Self::Completed { .. } | Self::Failed { .. })
+    }
+}
+
+/// Messages buffered per execution for slow subscribers
+#[cfg(feature = "server")]
+const CHANNEL_CAPACITY: usize = 256;
+
+/// Fans out execution updates from workers to WebSocket subscribers
+///
+/// Each execution gets its own broadcast channel, created by its first
+/// subscriber and dropped when the last one unsubscribes or by `close` once
+/// the execution has finished. The hub is in-process: only workers running in
+/// the same server publish on it, and they `open` an execution before they do.
+/// Subscribers of executions without a publisher follow the logs and progress
+/// that workers in other processes record in storage.
+///
+/// The hub also carries cancellation requests from the API to workers in the
+/// same process. Workers elsewhere notice cancellation from the execution's
+/// status in storage.
+#[cfg(feature = "server")]
+#[derive(Default)]
+pub struct ExecutionHub {
+    channels: RwLock<HashMap<Uuid, broadcast::Sender<ExecutionStreamMessage>>>,
+    publishers: RwLock<HashSet<Uuid>>,
+    cancellations: RwLock<HashMap<Uuid, CancellationToken>>,
+}
+
+#[cfg(feature = "server")]
+impl ExecutionHub {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Receive updates published for an execution from now on
+    pub fn subscribe(&self, execution_id: Uuid) -> broadcast::Receiver<ExecutionStreamMessage> {
+        self.channels
+            .write()
+            .unwrap()
+            .entry(execution_id)
+            .or_insert_with(|| broadcast::channel(CHANNEL_CAPACITY).0)
+            .subscribe()
+    }
+
+    /// Stop receiving updates, dropping the channel once nobody listens
+    pub fn unsubscribe(
+        &self,
+        execution_id: Uuid,
+        receiver: broadcast::Receiver<ExecutionStreamMessage>,
+    ) {
+        drop(receiver);
+        let mut channels = self.channels.write().unwrap();
+        if channels
+            .get(&execution_id)
+            .is_some_and(|sender| sender.receiver_count() == 0)
+        {
+            channels.remove(&execution_id);
+        }
+    }
+
+    /// Mark an execution as run by a worker publishing on this hub
+    pub fn open(&self, execution_id: Uuid) {
+        self.publishers.write().unwrap().insert(execution_id);
+    }
+
+    /// Whether a worker in this process publishes the execution's updates
+    pub fn has_publisher(&self, execution_id: Uuid) -> bool {
+        self.publishers.read().unwrap().contains(&execution_id)
+    }
+
+    /// Send an update to the execution's current subscribers
+    pub fn publish(&self, execution_id: Uuid, message: ExecutionStreamMessage) {
+        if let Some(sender) = self.channels.read().unwrap().get(&execution_id) {
+            // No subscribers is not an error
+            let _ = sender.send(message);
+        }
+    }
+
+    /// Drop the execution's channel; subscribers see it closed after draining
+    pub fn close(&self, execution_id: Uuid) {
+        self.channels.write().unwrap().remove(&execution_id);
+        self.publishers.write().unwrap().remove(&execution_id);
+        self.cancellations.write().unwrap().remove(&execution_id);
+    }
+
+    /// Token cancelled when cancellation of a running execution is requested
+    pub fn track_cancellation(&self, execution_id: Uuid) -> CancellationToken {
+        self.cancellations
+            .write()
+            .unwrap()
+            .entry(execution_id)
+            .or_default()
+            .clone()
+    }
+
+    /// Cancel an execution running in this process, returning whether one was
+    pub fn request_cancel(&self, execution_id: Uuid) -> bool {
+        match self.cancellations.read().unwrap().get(&execution_id) {
+            Some(token) => {
+                token.cancel();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Number of subscribers of an execution
+    pub fn subscriber_count(&self, execution_id: Uuid) -> usize {
+        self.channels
+            .read()
+            .unwrap()
+            .get(&execution_id)
+            .map_or(0, |sender| sender.receiver_count())
+    }
+}
+
+/// Message reporting the outcome of a finished execution
+#[cfg(feature = "server")]
+pub fn final_status_message(execution: &Execution) -> Option<ExecutionStreamMessage> {
+    let finished_at = execution
+        .completed_at
+        .map(|t| t.to_rfc3339())
+        .unwrap_or_default();
+    match execution.status {
+        ExecutionStatus::Completed => Some(ExecutionStreamMessage::Completed {
+            execution_id: execution.id,
+            status: "completed".to_string(),
+            completed_at: finished_at,
+            result: execution.result.clone(),
+        }),
+        ExecutionStatus::Failed => Some(ExecutionStreamMessage::Failed {
+            execution_id: execution.id,
+            error: execution
+                .error
+                .clone()
+                .unwrap_or_else(|| "Unknown error".to_string()),
+            failed_at: finished_at,
+        }),
+        ExecutionStatus::Cancelled => Some(ExecutionStreamMessage::Failed {
+            execution_id: execution.id,
+            error: "Execution cancelled".to_string(),
+            failed_at: finished_at,
+        }),
+        _ => None,
+    }
+}
+
+#[cfg(all(test, feature = "server"))]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_publish_reaches_subscribers_until_closed() {
+        let hub = ExecutionHub::new();
+        let execution_id = Uuid::new_v4();
+
+        // Updates without subscribers are dropped
+        hub.publish(
+            execution_id,
+            ExecutionStreamMessage::Ping {
+                timestamp: "t0".to_string(),
+            },
+        );
+
+        let mut rx = hub.subscribe(execution_id);
+        assert_eq!(hub.subscriber_count(execution_id), 1);
+        hub.publish(
+            execution_id,
+            ExecutionStreamMessage::Ping {
+                timestamp: "t1".to_string(),
+            },
+        );
+        hub.close(execution_id);
+
+        match rx.recv().await.unwrap() {
+            ExecutionStreamMessage::Ping { timestamp } => assert_eq!(timestamp, "t1"),
+            other => panic!("unexpected message {:?}", other),
+        }
+        assert!(matches!(
+            rx.recv().await,
+            Err(broadcast::error::RecvError::Closed)
+        ));
+    }
+
+    #[test]
+    fn test_last_unsubscribe_drops_channel() {
+        let hub = ExecutionHub::new();
+        let execution_id = Uuid::new_v4();
+
+        let first = hub.subscribe(execution_id);
+        let second = hub.subscribe(execution_id);
+        hub.unsubscribe(execution_id, first);
+        assert_eq!(hub.subscriber_count(execution_id), 1);
+        assert!(hub.channels.read().unwrap().contains_key(&execution_id));
+
+        hub.unsubscribe(execution_id, second);
+        assert!(!hub.channels.read().unwrap().contains_key(&execution_id));
+    }
+
+    #[test]
+    fn test_open_marks_execution_published_until_closed() {
+        let hub = ExecutionHub::new();
+        let execution_id = Uuid::new_v4();
+
+        assert!(!hub.has_publisher(execution_id));
+        hub.open(execution_id);
+        assert!(hub.has_publisher(execution_id));
+        hub.close(execution_id);
+        assert!(!hub.has_publisher(execution_id));
+    }
+
+    #[test]
+    fn test_request_cancel_reaches_tracked_execution() {
+        let hub = ExecutionHub::new();
+        let execution_id = Uuid::new_v4();
+
+        assert!(!hub.request_cancel(execution_id));
+
+        let token = hub.track_cancellation(execution_id);
+        assert!(hub.request_cancel(execution_id));
+        assert!(token.is_cancelled());
+
+        hub.close(execution_id);
+        assert!(!hub.request_cancel(execution_id));
+    }
+}
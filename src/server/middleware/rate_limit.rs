@@ -3,7 +3,7 @@
 #[cfg(feature = "server")]
 use axum::{
     body::Body,
-    extract::Request,
+    extract::{ConnectInfo, Request},
     http::{HeaderMap, HeaderValue, StatusCode},
     middleware::Next,
     response::{IntoResponse, Response},
@@ -14,10 +14,14 @@ use serde_json::json;
 #[cfg(feature = "server")]
 use std::collections::HashMap;
 #[cfg(feature = "server")]
+use std::net::{IpAddr, SocketAddr};
+#[cfg(feature = "server")]
 use std::sync::{Arc, Mutex};
 #[cfg(feature = "server")]
 use std::time::{Duration, Instant};
 
+#[cfg(feature = "server")]
+use crate::server::auth::api_key;
 #[cfg(feature = "server")]
 use crate::server::config::RateLimitConfig;
 
@@ -30,6 +34,7 @@ pub struct RateLimiter {
     // key -> (count, window_start)
     requests: Arc<Mutex<HashMap<String, (u32, Instant)>>>,
     window_duration: Duration,
+    trusted_proxies: Vec<String>,
 }
 
 #[cfg(feature = "server")]
@@ -39,9 +44,18 @@ impl RateLimiter {
             config,
             requests: Arc::new(Mutex::new(HashMap::new())),
             window_duration: Duration::from_secs(60), // 1 minute window
+            trusted_proxies: Vec::new(),
         }
     }
 
+    /// Proxies (addresses or CIDR blocks) whose forwarding headers are believed
+    ///
+    /// Without them, per-IP limits apply to the address of the connection.
+    pub fn with_trusted_proxies(mut self, proxies: Vec<String>) -> Self {
+        self.trusted_proxies = proxies;
+        self
+    }
+
     /// Check if request should be rate limited
     /// Returns (allowed, remaining, reset_seconds)
     fn check_rate_limit(&self, key: &str, limit: u32) -> (bool, u32, u64) {
@@ -69,26 +83,12 @@ impl RateLimiter {
     }
 
     /// Get IP address from request
-    fn get_client_ip(headers: &HeaderMap) -> Option<String> {
-        // Try X-Forwarded-For first (for proxies)
-        if let Some(forwarded) = headers.get("x-forwarded-for") {
-            if let Ok(forwarded_str) = forwarded.to_str() {
-                // Take first IP in the chain
-                return forwarded_str
-                    .split(',')
-                    .next()
-                    .map(|s| s.trim().to_string());
-            }
-        }
-
-        // Try X-Real-IP
-        if let Some(real_ip) = headers.get("x-real-ip") {
-            if let Ok(ip_str) = real_ip.to_str() {
-                return Some(ip_str.to_string());
-            }
-        }
-
-        None
+    fn get_client_ip(&self, request: &Request<Body>) -> Option<IpAddr> {
+        let peer = request
+            .extensions()
+            .get::<ConnectInfo<SocketAddr>>()
+            .map(|ConnectInfo(address)| address.ip());
+        api_key::client_ip(peer, request.headers(), &self.trusted_proxies)
     }
 
     /// Get API key from request
@@ -129,7 +129,7 @@ pub async fn rate_limit_middleware(
     let mut reset: Option<u64> = None;
 
     // Check global rate limit (per IP)
-    if let Some(ip) = RateLimiter::get_client_ip(headers) {
+    if let Some(ip) = limiter.get_client_ip(&request) {
         let (allowed, rem, rst) = limiter.check_rate_limit(
             &format!("global:{}", ip),
             limiter.config.per_ip_requests_per_minute,
@@ -301,4 +301,31 @@ mod tests {
         assert!(!allowed);
         assert_eq!(remaining, 0);
     }
+
+    #[test]
+    fn test_client_ip_ignores_forwarding_headers_of_untrusted_peers() {
+        let request = |peer: &str| {
+            let mut request = Request::builder()
+                .header("x-forwarded-for", "203.0.113.7")
+                .body(Body::empty())
+                .unwrap();
+            request
+                .extensions_mut()
+                .insert(ConnectInfo(SocketAddr::new(peer.parse().unwrap(), 443)));
+            request
+        };
+
+        let limiter = RateLimiter::new(RateLimitConfig::default());
+        assert_eq!(
+            limiter.get_client_ip(&request("198.51.100.1")),
+            Some("198.51.100.1".parse().unwrap())
+        );
+
+        let limiter = limiter.with_trusted_proxies(vec!["10.0.0.0/8".to_string()]);
+        assert_eq!(
+            limiter.get_client_ip(&request("10.0.0.2")),
+            Some("203.0.113.7".parse().unwrap())
+        );
+        assert_eq!(limiter.get_client_ip(&Request::new(Body::empty())), None);
+    }
 }
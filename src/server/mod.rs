@@ -15,6 +15,12 @@ pub mod api;
 #[cfg(feature = "server")]
 pub mod worker;
 
+#[cfg(feature = "server")]
+pub mod hub;
+
+#[cfg(feature = "server")]
+pub mod scheduler;
+
 #[cfg(feature = "server")]
 pub mod db;
 
@@ -30,8 +36,12 @@ pub mod web_ui;
 #[cfg(feature = "server")]
 pub use config::{Config, ConfigError};
 #[cfg(feature = "server")]
+pub use hub::ExecutionHub;
+#[cfg(feature = "server")]
 pub use queue::{Job, QueueError, QueueStats, WorkQueue};
 #[cfg(feature = "server")]
+pub use scheduler::{CronSchedule, Scheduler, SchedulerError};
+#[cfg(feature = "server")]
 pub use storage::{
     Checkpoint, CheckpointStorage, Execution, ExecutionFilter, ExecutionLog, ExecutionStatus,
     ExecutionStorage, Storage, StorageError, WorkflowFilter, WorkflowMetadata, WorkflowStorage,
@@ -0,0 +1,234 @@
+// Cron expression parsing with timezone support
+
+#[cfg(feature = "server")]
+use chrono::{DateTime, Utc};
+#[cfg(feature = "server")]
+use chrono_tz::Tz;
+#[cfg(feature = "server")]
+use std::str::FromStr;
+
+#[cfg(feature = "server")]
+use super::traits::{Result, SchedulerError};
+
+/// Weekday names in standard cron numbering, where 0 (and 7) is Sunday
+#[cfg(feature = "server")]
+const WEEKDAYS: [&str; 7] = ["SUN", "MON", "TUE", "WED", "THU", "FRI", "SAT"];
+
+/// A cron expression evaluated in a schedule's timezone
+///
+/// Accepts standard five-field expressions (`minute hour day month weekday`)
+/// as well as six- and seven-field expressions with leading seconds and a
+/// trailing year. Weekdays count from 0 or 7 for Sunday in every form, as in
+/// crontab. Occurrences are computed in the timezone, so `0 9 * * *` fires at
+/// 09:00 local time across daylight saving changes.
+#[cfg(feature = "server")]
+#[derive(Debug, Clone)]
+pub struct CronSchedule {
+    schedule: ::cron::Schedule,
+    timezone: Tz,
+}
+
+#[cfg(feature = "server")]
+impl CronSchedule {
+    /// Parse an expression and an IANA timezone name such as `Europe/Berlin`
+    pub fn parse(expression: &str, timezone: &str) -> Result<Self> {
+        let mut fields: Vec<String> = expression.split_whitespace().map(str::to_string).collect();
+        // The cron crate expects a seconds field, and numbers weekdays from 1
+        // for Sunday
+        match fields.len() {
+            5 => fields.insert(0, "0".to_string()),
+            6 | 7 => {}
+            count => {
+                return Err(SchedulerError::InvalidCron(
+                    expression.to_string(),
+                    format!("expected 5 to 7 fields, found {}", count),
+                ))
+            }
+        }
+        fields[5] = weekdays_by_name(&fields[5]);
+        let normalized = fields.join(" ");
+
+        let schedule = ::cron::Schedule::from_str(&normalized)
+            .map_err(|e| SchedulerError::InvalidCron(expression.to_string(), e.to_string()))?;
+        let timezone = Tz::from_str(timezone)
+            .map_err(|_| SchedulerError::InvalidTimezone(timezone.to_string()))?;
+
+        Ok(Self { schedule, timezone })
+    }
+
+    pub fn timezone(&self) -> Tz {
+        self.timezone
+    }
+
+    /// First occurrence strictly after `after`
+    pub fn next_after(&self, after: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        self.schedule
+            .after(&after.with_timezone(&self.timezone))
+            .next()
+            .map(|next| next.with_timezone(&Utc))
+    }
+
+    /// Occurrences in `(after, until]`, keeping only the latest `limit`
+    pub fn occurrences_between(
+        &self,
+        after: DateTime<Utc>,
+        until: DateTime<Utc>,
+        limit: usize,
+    ) -> Vec<DateTime<Utc>> {
+        let mut occurrences = std::collections::VecDeque::with_capacity(limit);
+        for next in self.schedule.after(&after.with_timezone(&self.timezone)) {
+            let next = next.with_timezone(&Utc);
+            if next > until {
+                break;
+            }
+            if occurrences.len() == limit {
+                occurrences.pop_front();
+            }
+            if limit > 0 {
+                occurrences.push_back(next);
+            }
+        }
+        occurrences.into()
+    }
+}
+
+/// Rewrite the numbers of a standard weekday field to day names
+///
+/// Each list element with numbers (`1`, `1-5`, `*/2`, `1-5/2`) becomes the
+/// names of the days it selects. Elements that are not plain numbers in 0-7,
+/// such as names, are kept for the cron crate to accept or reject.
+#[cfg(feature = "server")]
+fn weekdays_by_name(field: &str) -> String {
+    let rewrite = |element: &str| -> Option<String> {
+        let (range, step) = match element.split_once('/') {
+            Some((range, step)) => (range, Some(step.parse::<usize>().ok()?)),
+            None => (element, None),
+        };
+        let day = |text: &str| text.parse::<usize>().ok().filter(|day| *day <= 7);
+        let (first, last) = match range.split_once('-') {
+            _ if range == "*" && step.is_none() => return None,
+            _ if range == "*" => (0, 6),
+            Some((first, last)) => (day(first)?, day(last)?),
+            // `n/step` runs to the end of the week
+            None if step.is_some() => (day(range)?, 6),
+            None => (day(range)?, day(range)?),
+        };
+        if step == Some(0) || first > last {
+            return None;
+        }
+
+        let mut days: Vec<usize> = (first..=last)
+            .step_by(step.unwrap_or(1))
+            .map(|day| day % 7)
+            .collect();
+        days.sort_unstable();
+        days.dedup();
+        Some(
+            days.iter()
+                .map(|day| WEEKDAYS[*day])
+                .collect::<Vec<_>>()
+                .join(","),
+        )
+    };
+
+    field
+        .split(',')
+        .map(|element| rewrite(element).unwrap_or_else(|| element.to_string()))
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+#[cfg(all(test, feature = "server"))]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_five_field_expression_in_timezone() {
+        let cron = CronSchedule::parse("30 9 * * *", "Europe/Berlin").unwrap();
+
+        // 09:30 in Berlin is 07:30 UTC in summer and 08:30 UTC in winter
+        let summer = Utc.with_ymd_and_hms(2024, 7, 1, 0, 0, 0).unwrap();
+        assert_eq!(
+            cron.next_after(summer),
+            Some(Utc.with_ymd_and_hms(2024, 7, 1, 7, 30, 0).unwrap())
+        );
+        let winter = Utc.with_ymd_and_hms(2024, 12, 1, 0, 0, 0).unwrap();
+        assert_eq!(
+            cron.next_after(winter),
+            Some(Utc.with_ymd_and_hms(2024, 12, 1, 8, 30, 0).unwrap())
+        );
+    }
+
+    #[test]
+    fn test_occurrences_between_keeps_latest() {
+        let cron = CronSchedule::parse("0 * * * *", "UTC").unwrap();
+        let after = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap();
+        let until = Utc.with_ymd_and_hms(2024, 1, 1, 5, 0, 0).unwrap();
+
+        let all = cron.occurrences_between(after, until, 10);
+        assert_eq!(all.len(), 5);
+        assert_eq!(all[0], Utc.with_ymd_and_hms(2024, 1, 1, 1, 0, 0).unwrap());
+        assert_eq!(all[4], until);
+
+        let latest = cron.occurrences_between(after, until, 2);
+        assert_eq!(latest, all[3..].to_vec());
+    }
+
+    /// Weekdays of the occurrences in the week starting Sunday, 7 July 2024
+    fn weekdays_of(expression: &str) -> Vec<chrono::Weekday> {
+        use chrono::Datelike;
+
+        let cron = CronSchedule::parse(expression, "UTC").unwrap();
+        let after = Utc.with_ymd_and_hms(2024, 7, 6, 23, 0, 0).unwrap();
+        let until = Utc.with_ymd_and_hms(2024, 7, 13, 23, 0, 0).unwrap();
+        cron.occurrences_between(after, until, 10)
+            .iter()
+            .map(|next| next.weekday())
+            .collect()
+    }
+
+    #[test]
+    fn test_weekdays_count_from_sunday() {
+        use chrono::Weekday::*;
+
+        assert_eq!(weekdays_of("0 9 * * 1-5"), vec![Mon, Tue, Wed, Thu, Fri]);
+        assert_eq!(weekdays_of("0 9 * * 0"), vec![Sun]);
+        assert_eq!(weekdays_of("0 9 * * 7"), vec![Sun]);
+        assert_eq!(weekdays_of("0 9 * * 5-7"), vec![Sun, Fri, Sat]);
+        assert_eq!(weekdays_of("0 9 * * */3"), vec![Sun, Wed, Sat]);
+        assert_eq!(weekdays_of("0 9 * * MON,6"), vec![Mon, Sat]);
+        assert_eq!(weekdays_of("0 9 * * *").len(), 7);
+    }
+
+    #[test]
+    fn test_weekdays_mean_the_same_days_in_every_form() {
+        use chrono::Weekday::*;
+
+        let weekdays = vec![Mon, Tue, Wed, Thu, Fri];
+        assert_eq!(weekdays_of("0 9 * * 1-5"), weekdays);
+        assert_eq!(weekdays_of("0 0 9 * * 1-5"), weekdays);
+        assert_eq!(weekdays_of("0 0 9 * * 1-5 *"), weekdays);
+        assert_eq!(weekdays_of("0 0 9 * * 0"), vec![Sun]);
+    }
+
+    #[test]
+    fn test_rejects_invalid_input() {
+        assert!(matches!(
+            CronSchedule::parse("* * *", "UTC"),
+            Err(SchedulerError::InvalidCron(..))
+        ));
+        assert!(matches!(
+            CronSchedule::parse("61 * * * *", "UTC"),
+            Err(SchedulerError::InvalidCron(..))
+        ));
+        assert!(matches!(
+            CronSchedule::parse("0 9 * * 8", "UTC"),
+            Err(SchedulerError::InvalidCron(..))
+        ));
+        assert!(matches!(
+            CronSchedule::parse("0 * * * *", "Mars/Olympus"),
+            Err(SchedulerError::InvalidTimezone(..))
+        ));
+    }
+}
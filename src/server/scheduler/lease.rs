@@ -0,0 +1,281 @@
+// Leader lease implementations
+
+#[cfg(feature = "server")]
+use async_trait::async_trait;
+#[cfg(feature = "server")]
+use chrono::{DateTime, Utc};
+#[cfg(feature = "server")]
+use redis::aio::ConnectionManager;
+#[cfg(feature = "server")]
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "server")]
+use sqlx::PgPool;
+#[cfg(feature = "server")]
+use std::path::PathBuf;
+#[cfg(feature = "server")]
+use std::time::Duration;
+#[cfg(feature = "server")]
+use tokio::fs;
+
+#[cfg(feature = "server")]
+use super::traits::{LeaderLease, Result, SchedulerError};
+
+#[cfg(feature = "server")]
+#[derive(Debug, Serialize, Deserialize)]
+struct LeaseRecord {
+    holder: String,
+    expires_at: DateTime<Utc>,
+}
+
+/// Lease kept in a JSON file
+///
+/// Suitable for replicas sharing a local or network filesystem. A new holder
+/// writes the file atomically and reads it back to detect a concurrent
+/// writer, which is best-effort rather than strict mutual exclusion.
+#[cfg(feature = "server")]
+pub struct FilesystemLease {
+    path: PathBuf,
+}
+
+#[cfg(feature = "server")]
+impl FilesystemLease {
+    pub fn new(path: PathBuf) -> Self {
+        Self { path }
+    }
+
+    async fn read(&self) -> Result<Option<LeaseRecord>> {
+        match fs::read_to_string(&self.path).await {
+            // A torn or corrupt file is treated as no lease
+            Ok(json) => Ok(serde_json::from_str(&json).ok()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(SchedulerError::LeaseError(e.to_string())),
+        }
+    }
+}
+
+#[cfg(feature = "server")]
+#[async_trait]
+impl LeaderLease for FilesystemLease {
+    async fn try_acquire(&self, holder: &str, ttl: Duration) -> Result<bool> {
+        let now = Utc::now();
+        if let Some(record) = self.read().await? {
+            if record.holder != holder && record.expires_at > now {
+                return Ok(false);
+            }
+        }
+
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)
+                .await
+                .map_err(|e| SchedulerError::LeaseError(e.to_string()))?;
+        }
+
+        let record = LeaseRecord {
+            holder: holder.to_string(),
+            expires_at: now
+                + chrono::Duration::from_std(ttl)
+                    .map_err(|e| SchedulerError::LeaseError(e.to_string()))?,
+        };
+        let json = serde_json::to_string(&record)
+            .map_err(|e| SchedulerError::LeaseError(e.to_string()))?;
+
+        let tmp_path = self
+            .path
+            .with_extension(format!("{}.tmp", uuid::Uuid::new_v4()));
+        fs::write(&tmp_path, json)
+            .await
+            .map_err(|e| SchedulerError::LeaseError(e.to_string()))?;
+        fs::rename(&tmp_path, &self.path)
+            .await
+            .map_err(|e| SchedulerError::LeaseError(e.to_string()))?;
+
+        // Another replica may have written the file at the same time
+        Ok(self
+            .read()
+            .await?
+            .is_some_and(|record| record.holder == holder))
+    }
+
+    async fn release(&self, holder: &str) -> Result<()> {
+        if let Some(record) = self.read().await? {
+            if record.holder == holder {
+                fs::remove_file(&self.path)
+                    .await
+                    .map_err(|e| SchedulerError::LeaseError(e.to_string()))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Lease kept as a row of the `scheduler_leases` table
+#[cfg(feature = "server")]
+pub struct PostgresLease {
+    pool: PgPool,
+    name: String,
+}
+
+#[cfg(feature = "server")]
+impl PostgresLease {
+    pub async fn new(database_url: &str, name: Option<String>) -> Result<Self> {
+        let pool = PgPool::connect(database_url)
+            .await
+            .map_err(|e| SchedulerError::LeaseError(e.to_string()))?;
+
+        Ok(Self::from_pool(pool, name))
+    }
+
+    pub fn from_pool(pool: PgPool, name: Option<String>) -> Self {
+        Self {
+            pool,
+            name: name.unwrap_or_else(|| "scheduler".to_string()),
+        }
+    }
+}
+
+#[cfg(feature = "server")]
+#[async_trait]
+impl LeaderLease for PostgresLease {
+    async fn try_acquire(&self, holder: &str, ttl: Duration) -> Result<bool> {
+        // The row is taken over only when it is ours or has expired
+        let row = sqlx::query(
+            r#"
+            INSERT INTO scheduler_leases (name, holder, expires_at)
+            VALUES ($1, $2, NOW() + make_interval(secs => $3))
+            ON CONFLICT (name) DO UPDATE SET
+                holder = EXCLUDED.holder,
+                expires_at = EXCLUDED.expires_at
+            WHERE scheduler_leases.holder = EXCLUDED.holder
+               OR scheduler_leases.expires_at < NOW()
+            RETURNING holder
+            "#,
+        )
+        .bind(&self.name)
+        .bind(holder)
+        .bind(ttl.as_secs_f64())
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| SchedulerError::LeaseError(e.to_string()))?;
+
+        Ok(row.is_some())
+    }
+
+    async fn release(&self, holder: &str) -> Result<()> {
+        sqlx::query("DELETE FROM scheduler_leases WHERE name = $1 AND holder = $2")
+            .bind(&self.name)
+            .bind(holder)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| SchedulerError::LeaseError(e.to_string()))?;
+
+        Ok(())
+    }
+}
+
+/// Lease kept as a Redis key with an expiry
+#[cfg(feature = "server")]
+pub struct RedisLease {
+    conn: ConnectionManager,
+    key: String,
+}
+
+#[cfg(feature = "server")]
+impl RedisLease {
+    pub async fn new(redis_url: &str, key: Option<String>) -> Result<Self> {
+        let client = redis::Client::open(redis_url).map_err(|e| {
+            SchedulerError::LeaseError(format!("Failed to connect to Redis: {}", e))
+        })?;
+
+        let conn = ConnectionManager::new(client).await.map_err(|e| {
+            SchedulerError::LeaseError(format!("Failed to create connection manager: {}", e))
+        })?;
+
+        Ok(Self::from_connection(conn, key))
+    }
+
+    pub fn from_connection(conn: ConnectionManager, key: Option<String>) -> Self {
+        Self {
+            conn,
+            key: key.unwrap_or_else(|| "dsl:scheduler:leader".to_string()),
+        }
+    }
+}
+
+#[cfg(feature = "server")]
+#[async_trait]
+impl LeaderLease for RedisLease {
+    async fn try_acquire(&self, holder: &str, ttl: Duration) -> Result<bool> {
+        // Take the key if it is free, or extend it if we already hold it
+        let script = redis::Script::new(
+            r#"
+            if redis.call('SET', KEYS[1], ARGV[1], 'NX', 'PX', ARGV[2]) then
+                return 1
+            end
+            if redis.call('GET', KEYS[1]) == ARGV[1] then
+                redis.call('PEXPIRE', KEYS[1], ARGV[2])
+                return 1
+            end
+            return 0
+            "#,
+        );
+
+        let mut conn = self.conn.clone();
+        let acquired: i32 = script
+            .key(&self.key)
+            .arg(holder)
+            .arg(ttl.as_millis() as u64)
+            .invoke_async(&mut conn)
+            .await
+            .map_err(|e| SchedulerError::LeaseError(e.to_string()))?;
+
+        Ok(acquired == 1)
+    }
+
+    async fn release(&self, holder: &str) -> Result<()> {
+        let script = redis::Script::new(
+            r#"
+            if redis.call('GET', KEYS[1]) == ARGV[1] then
+                return redis.call('DEL', KEYS[1])
+            end
+            return 0
+            "#,
+        );
+
+        let mut conn = self.conn.clone();
+        let _: i32 = script
+            .key(&self.key)
+            .arg(holder)
+            .invoke_async(&mut conn)
+            .await
+            .map_err(|e| SchedulerError::LeaseError(e.to_string()))?;
+
+        Ok(())
+    }
+}
+
+#[cfg(all(test, feature = "server"))]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_filesystem_lease_has_one_holder_until_expiry() {
+        let dir = tempfile::tempdir().unwrap();
+        let lease = FilesystemLease::new(dir.path().join("scheduler.lease"));
+        let ttl = Duration::from_secs(60);
+
+        assert!(lease.try_acquire("a", ttl).await.unwrap());
+        assert!(!lease.try_acquire("b", ttl).await.unwrap());
+        // The holder renews
+        assert!(lease.try_acquire("a", ttl).await.unwrap());
+
+        // An expired lease can be taken over
+        assert!(lease.try_acquire("a", Duration::ZERO).await.unwrap());
+        assert!(lease.try_acquire("b", ttl).await.unwrap());
+
+        // Only the holder can release it
+        lease.release("a").await.unwrap();
+        assert!(!lease.try_acquire("a", ttl).await.unwrap());
+        lease.release("b").await.unwrap();
+        assert!(lease.try_acquire("a", ttl).await.unwrap());
+    }
+}
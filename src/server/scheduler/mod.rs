@@ -0,0 +1,22 @@
+// Cron scheduler module
+
+#[cfg(feature = "server")]
+pub mod traits;
+
+#[cfg(feature = "server")]
+pub mod cron;
+
+#[cfg(feature = "server")]
+pub mod lease;
+
+#[cfg(feature = "server")]
+pub mod service;
+
+#[cfg(feature = "server")]
+pub use self::cron::CronSchedule;
+#[cfg(feature = "server")]
+pub use lease::{FilesystemLease, PostgresLease, RedisLease};
+#[cfg(feature = "server")]
+pub use service::Scheduler;
+#[cfg(feature = "server")]
+pub use traits::{LeaderLease, Result, SchedulerError};
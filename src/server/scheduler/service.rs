@@ -0,0 +1,305 @@
+// Scheduler loop that starts executions for due schedules
+
+#[cfg(feature = "server")]
+use chrono::{DateTime, Utc};
+#[cfg(feature = "server")]
+use serde_json::json;
+#[cfg(feature = "server")]
+use std::sync::Arc;
+#[cfg(feature = "server")]
+use std::time::Duration;
+#[cfg(feature = "server")]
+use tracing::{error, info, warn};
+#[cfg(feature = "server")]
+use uuid::Uuid;
+
+#[cfg(feature = "server")]
+use super::cron::CronSchedule;
+#[cfg(feature = "server")]
+use super::traits::{LeaderLease, Result};
+#[cfg(feature = "server")]
+use crate::server::config::{CatchUpPolicy, SchedulerConfig};
+#[cfg(feature = "server")]
+use crate::server::queue::{Job, WorkQueue};
+#[cfg(feature = "server")]
+use crate::server::storage::{
+    Execution, ExecutionStatus, Schedule, ScheduleRun, ScheduleRunStatus, Storage,
+};
+
+/// Most occurrences of one schedule handled in a single pass
+#[cfg(feature = "server")]
+const MAX_TRACKED_OCCURRENCES: usize = 100;
+
+/// Fires due schedules by queueing executions for workers
+///
+/// Every replica may run a scheduler; only the one holding the leader lease
+/// acts on a pass, renewing it before each schedule. For each due schedule,
+/// occurrences older than the misfire grace period are missed and handled by
+/// the catch-up policy. The rest start an execution. Every occurrence is
+/// recorded as a `ScheduleRun`.
+#[cfg(feature = "server")]
+pub struct Scheduler {
+    id: String,
+    storage: Arc<dyn Storage>,
+    queue: Arc<dyn WorkQueue>,
+    lease: Arc<dyn LeaderLease>,
+    config: SchedulerConfig,
+}
+
+#[cfg(feature = "server")]
+impl Scheduler {
+    pub fn new(
+        id: String,
+        storage: Arc<dyn Storage>,
+        queue: Arc<dyn WorkQueue>,
+        lease: Arc<dyn LeaderLease>,
+        config: SchedulerConfig,
+    ) -> Self {
+        Self {
+            id,
+            storage,
+            queue,
+            lease,
+            config,
+        }
+    }
+
+    /// Run scheduling passes every poll interval
+    pub async fn run(&self) {
+        info!("Scheduler {} started", self.id);
+
+        let mut interval =
+            tokio::time::interval(Duration::from_secs(self.config.poll_interval_secs.max(1)));
+        loop {
+            interval.tick().await;
+            match self.tick(Utc::now()).await {
+                Ok(0) => {}
+                Ok(queued) => info!("Scheduler {} queued {} executions", self.id, queued),
+                Err(e) => error!("Scheduler {} pass failed: {}", self.id, e),
+            }
+        }
+    }
+
+    /// Run one scheduling pass as of `now`, returning the executions queued
+    pub async fn tick(&self, now: DateTime<Utc>) -> Result<usize> {
+        let ttl = Duration::from_secs(self.config.lease_ttl_secs);
+        if !self.lease.try_acquire(&self.id, ttl).await? {
+            return Ok(0);
+        }
+
+        let mut queued = 0;
+        for schedule in self.storage.get_due_schedules(now).await? {
+            // Renew before each schedule so a long pass cannot outlive the
+            // lease and overlap with another replica's pass
+            if !self.lease.try_acquire(&self.id, ttl).await? {
+                warn!("Scheduler {} lost the leader lease during a pass", self.id);
+                break;
+            }
+
+            let schedule_id = schedule.id;
+            match self.process_schedule(schedule, now).await {
+                Ok(count) => queued += count,
+                Err(e) => error!("Failed to process schedule {}: {}", schedule_id, e),
+            }
+        }
+
+        Ok(queued)
+    }
+
+    /// Give up leadership so another replica can take over immediately
+    pub async fn shutdown(&self) -> Result<()> {
+        self.lease.release(&self.id).await
+    }
+
+    async fn process_schedule(&self, mut schedule: Schedule, now: DateTime<Utc>) -> Result<usize> {
+        let cron = match CronSchedule::parse(&schedule.cron_expression, &schedule.timezone) {
+            Ok(cron) => cron,
+            Err(e) => {
+                // Deactivate so the schedule is not picked up on every pass
+                warn!("Deactivating schedule {}: {}", schedule.id, e);
+                self.record_run(
+                    &schedule,
+                    None,
+                    now,
+                    ScheduleRunStatus::Failed,
+                    Some(e.to_string()),
+                )
+                .await?;
+                schedule.is_active = false;
+                schedule.next_run_at = None;
+                self.storage.update_schedule(schedule.id, &schedule).await?;
+                return Ok(0);
+            }
+        };
+
+        let Some(first) = schedule.next_run_at else {
+            // Schedules stored without a next run start counting from now
+            advance(&mut schedule, &cron, now);
+            self.storage.update_schedule(schedule.id, &schedule).await?;
+            return Ok(0);
+        };
+
+        let mut due = vec![first];
+        due.extend(cron.occurrences_between(first, now, MAX_TRACKED_OCCURRENCES));
+        if due.len() > MAX_TRACKED_OCCURRENCES {
+            due.drain(..due.len() - MAX_TRACKED_OCCURRENCES);
+        }
+
+        let grace = chrono::Duration::seconds(self.config.misfire_grace_secs as i64);
+        let (missed, on_time): (Vec<_>, Vec<_>) = due
+            .into_iter()
+            .partition(|&scheduled_for| scheduled_for < now - grace);
+
+        let catch_up = match self.config.catch_up {
+            CatchUpPolicy::Skip => &missed[..0],
+            CatchUpPolicy::Once if on_time.is_empty() => &missed[missed.len().saturating_sub(1)..],
+            CatchUpPolicy::Once => &missed[..0],
+            CatchUpPolicy::All => {
+                &missed[missed.len().saturating_sub(self.config.max_catch_up_runs)..]
+            }
+        };
+
+        // Move past the due occurrences before queueing any of them, so a
+        // failed update cannot fire the same occurrences again next pass
+        advance(&mut schedule, &cron, now);
+        self.storage.update_schedule(schedule.id, &schedule).await?;
+
+        let mut queued = 0;
+        for &scheduled_for in missed.iter().chain(on_time.iter()) {
+            if on_time.contains(&scheduled_for) || catch_up.contains(&scheduled_for) {
+                if self.start_run(&schedule, scheduled_for, now).await? {
+                    queued += 1;
+                    schedule.last_run_at = Some(now);
+                }
+            } else {
+                self.record_run(
+                    &schedule,
+                    None,
+                    scheduled_for,
+                    ScheduleRunStatus::Skipped,
+                    Some("Missed while no scheduler was running".to_string()),
+                )
+                .await?;
+            }
+        }
+
+        if queued > 0 {
+            self.storage.update_schedule(schedule.id, &schedule).await?;
+        }
+
+        Ok(queued)
+    }
+
+    /// Queue an execution for one occurrence, returning whether it was queued
+    async fn start_run(
+        &self,
+        schedule: &Schedule,
+        scheduled_for: DateTime<Utc>,
+        now: DateTime<Utc>,
+    ) -> Result<bool> {
+        let Some((workflow, _)) = self.storage.get_workflow(schedule.workflow_id).await? else {
+            self.record_run(
+                schedule,
+                None,
+                scheduled_for,
+                ScheduleRunStatus::Failed,
+                Some(format!("Workflow {} not found", schedule.workflow_id)),
+            )
+            .await?;
+            return Ok(false);
+        };
+
+        let mut execution = Execution {
+            id: Uuid::new_v4(),
+            workflow_id: schedule.workflow_id,
+            workflow_version: workflow.version.clone(),
+            status: ExecutionStatus::Queued,
+            started_at: None,
+            completed_at: None,
+            created_at: now,
+            triggered_by: schedule.created_by.clone(),
+            trigger_type: "scheduled".to_string(),
+            input_params: schedule.input_params.clone(),
+            result: None,
+            error: None,
+            retry_count: 0,
+            parent_execution_id: None,
+            completed_tasks: 0,
+            total_tasks: 0,
+            usage: Default::default(),
+        };
+        let execution_id = self.storage.store_execution(&execution).await?;
+
+        let job = Job::new(
+            schedule.workflow_id,
+            execution_id,
+            json!({
+                "workflow": workflow,
+                "input_params": schedule.input_params,
+            }),
+        );
+
+        match self.queue.enqueue(job).await {
+            Ok(_) => {
+                self.record_run(
+                    schedule,
+                    Some(execution_id),
+                    scheduled_for,
+                    ScheduleRunStatus::Scheduled,
+                    None,
+                )
+                .await?;
+                Ok(true)
+            }
+            Err(e) => {
+                let message = format!("Failed to queue job: {}", e);
+                execution.status = ExecutionStatus::Failed;
+                execution.error = Some(message.clone());
+                self.storage
+                    .update_execution(execution_id, &execution)
+                    .await?;
+                self.record_run(
+                    schedule,
+                    Some(execution_id),
+                    scheduled_for,
+                    ScheduleRunStatus::Failed,
+                    Some(message),
+                )
+                .await?;
+                Ok(false)
+            }
+        }
+    }
+
+    async fn record_run(
+        &self,
+        schedule: &Schedule,
+        execution_id: Option<Uuid>,
+        scheduled_for: DateTime<Utc>,
+        status: ScheduleRunStatus,
+        error: Option<String>,
+    ) -> Result<()> {
+        let now = Utc::now();
+        let run = ScheduleRun {
+            id: Uuid::new_v4(),
+            schedule_id: schedule.id,
+            execution_id,
+            scheduled_for,
+            started_at: execution_id.map(|_| now),
+            status,
+            error,
+            created_at: now,
+        };
+        self.storage.store_schedule_run(&run).await?;
+        Ok(())
+    }
+}
+
+/// Move a schedule to its next occurrence, deactivating it when there is none
+#[cfg(feature = "server")]
+fn advance(schedule: &mut Schedule, cron: &CronSchedule, now: DateTime<Utc>) {
+    schedule.next_run_at = cron.next_after(now);
+    if schedule.next_run_at.is_none() {
+        schedule.is_active = false;
+    }
+}
@@ -0,0 +1,48 @@
+// Scheduler traits
+
+#[cfg(feature = "server")]
+use async_trait::async_trait;
+#[cfg(feature = "server")]
+use std::time::Duration;
+#[cfg(feature = "server")]
+use thiserror::Error;
+
+#[cfg(feature = "server")]
+use crate::server::{QueueError, StorageError};
+
+#[cfg(feature = "server")]
+#[derive(Debug, Error)]
+pub enum SchedulerError {
+    #[error("Invalid cron expression '{0}': {1}")]
+    InvalidCron(String, String),
+
+    #[error("Invalid timezone: {0}")]
+    InvalidTimezone(String),
+
+    #[error("Lease error: {0}")]
+    LeaseError(String),
+
+    #[error("Storage error: {0}")]
+    Storage(#[from] StorageError),
+
+    #[error("Queue error: {0}")]
+    Queue(#[from] QueueError),
+}
+
+#[cfg(feature = "server")]
+pub type Result<T> = std::result::Result<T, SchedulerError>;
+
+/// Elects a single scheduler among server replicas
+///
+/// Every replica calls `try_acquire` before each scheduling pass. Only the
+/// holder of an unexpired lease fires schedules; the lease passes to another
+/// replica once its holder stops renewing it for `ttl`.
+#[cfg(feature = "server")]
+#[async_trait]
+pub trait LeaderLease: Send + Sync {
+    /// Acquire or renew the lease, returning whether `holder` is the leader
+    async fn try_acquire(&self, holder: &str, ttl: Duration) -> Result<bool>;
+
+    /// Give up the lease if `holder` has it
+    async fn release(&self, holder: &str) -> Result<()>;
+}
@@ -7,6 +7,8 @@ use chrono::{DateTime, Utc};
 #[cfg(feature = "server")]
 use serde_json;
 #[cfg(feature = "server")]
+use std::collections::HashMap;
+#[cfg(feature = "server")]
 use std::path::PathBuf;
 #[cfg(feature = "server")]
 use tokio::fs;
@@ -17,6 +19,12 @@ use uuid::Uuid;
 use super::traits::*;
 #[cfg(feature = "server")]
 use crate::dsl::schema::DSLWorkflow;
+#[cfg(feature = "server")]
+use crate::dsl::Usage;
+
+/// Directory under the base path holding schedules and their runs
+#[cfg(feature = "server")]
+const SCHEDULES_DIR: &str = "schedules";
 
 #[cfg(feature = "server")]
 pub struct FilesystemStorage {
@@ -56,6 +64,7 @@ impl FilesystemStorage {
             &self.executions_dir,
             &self.checkpoints_dir,
             &self.logs_dir,
+            SCHEDULES_DIR,
         ];
 
         for dir in dirs {
@@ -85,6 +94,14 @@ impl FilesystemStorage {
             .join(&self.checkpoints_dir)
             .join(execution_id.to_string())
     }
+
+    fn schedules_path(&self) -> PathBuf {
+        self.base_path.join(SCHEDULES_DIR)
+    }
+
+    fn schedule_dir(&self, id: Uuid) -> PathBuf {
+        self.schedules_path().join(id.to_string())
+    }
 }
 
 #[cfg(feature = "server")]
@@ -356,6 +373,24 @@ impl ExecutionStorage for FilesystemStorage {
         Ok(())
     }
 
+    async fn update_execution_progress(
+        &self,
+        id: Uuid,
+        completed_tasks: u32,
+        total_tasks: u32,
+        usage: &Usage,
+    ) -> Result<()> {
+        let mut execution = self
+            .get_execution(id)
+            .await?
+            .ok_or_else(|| StorageError::NotFound(format!("Execution {} not found", id)))?;
+        execution.completed_tasks = completed_tasks;
+        execution.total_tasks = total_tasks;
+        execution.usage = *usage;
+        self.store_execution(&execution).await?;
+        Ok(())
+    }
+
     async fn list_executions(&self, filter: &ExecutionFilter) -> Result<Vec<Execution>> {
         let executions_path = self.base_path.join(&self.executions_dir);
         let mut entries = fs::read_dir(&executions_path)
@@ -410,6 +445,21 @@ impl ExecutionStorage for FilesystemStorage {
         Ok(executions)
     }
 
+    async fn usage_by_workflow(&self) -> Result<HashMap<Uuid, Usage>> {
+        let filter = ExecutionFilter {
+            limit: None,
+            ..Default::default()
+        };
+        let mut usage: HashMap<Uuid, Usage> = HashMap::new();
+        for execution in self.list_executions(&filter).await? {
+            usage
+                .entry(execution.workflow_id)
+                .or_default()
+                .add(&execution.usage);
+        }
+        Ok(usage)
+    }
+
     async fn store_execution_log(&self, log: &ExecutionLog) -> Result<()> {
         let execution_dir = self.execution_dir(log.execution_id);
         let logs_dir = execution_dir.join("logs");
@@ -469,6 +519,32 @@ impl ExecutionStorage for FilesystemStorage {
         Ok(logs)
     }
 
+    async fn get_execution_logs_since(
+        &self,
+        execution_id: Uuid,
+        skip: usize,
+    ) -> Result<Vec<ExecutionLog>> {
+        let log_file = self
+            .execution_dir(execution_id)
+            .join("logs")
+            .join("current.jsonl");
+
+        if !log_file.exists() {
+            return Ok(Vec::new());
+        }
+
+        let content = fs::read_to_string(&log_file)
+            .await
+            .map_err(|e| StorageError::IoError(e.to_string()))?;
+
+        // Lines are appended in the order the logs were stored
+        Ok(content
+            .lines()
+            .skip(skip)
+            .filter_map(|line| serde_json::from_str(line).ok())
+            .collect())
+    }
+
     async fn delete_execution(&self, id: Uuid) -> Result<()> {
         let execution_dir = self.execution_dir(id);
 
@@ -574,56 +650,184 @@ impl CheckpointStorage for FilesystemStorage {
 #[cfg(feature = "server")]
 #[async_trait]
 impl ScheduleStorage for FilesystemStorage {
-    async fn store_schedule(&self, _schedule: &Schedule) -> Result<Uuid> {
-        Err(StorageError::IoError(
-            "Schedule storage not implemented for filesystem backend".to_string(),
-        ))
+    async fn store_schedule(&self, schedule: &Schedule) -> Result<Uuid> {
+        let schedule_dir = self.schedule_dir(schedule.id);
+
+        fs::create_dir_all(&schedule_dir)
+            .await
+            .map_err(|e| StorageError::IoError(e.to_string()))?;
+
+        let schedule_path = schedule_dir.join("schedule.json");
+        let json = serde_json::to_string_pretty(&schedule)
+            .map_err(|e| StorageError::SerializationError(e.to_string()))?;
+        fs::write(&schedule_path, json)
+            .await
+            .map_err(|e| StorageError::IoError(e.to_string()))?;
+
+        Ok(schedule.id)
     }
 
-    async fn get_schedule(&self, _id: Uuid) -> Result<Option<Schedule>> {
-        Err(StorageError::IoError(
-            "Schedule storage not implemented for filesystem backend".to_string(),
-        ))
+    async fn get_schedule(&self, id: Uuid) -> Result<Option<Schedule>> {
+        let schedule_path = self.schedule_dir(id).join("schedule.json");
+
+        if !schedule_path.exists() {
+            return Ok(None);
+        }
+
+        let json = fs::read_to_string(&schedule_path)
+            .await
+            .map_err(|e| StorageError::IoError(e.to_string()))?;
+        let schedule: Schedule = serde_json::from_str(&json)
+            .map_err(|e| StorageError::SerializationError(e.to_string()))?;
+
+        Ok(Some(schedule))
     }
 
-    async fn update_schedule(&self, _id: Uuid, _schedule: &Schedule) -> Result<()> {
-        Err(StorageError::IoError(
-            "Schedule storage not implemented for filesystem backend".to_string(),
-        ))
+    async fn update_schedule(&self, id: Uuid, schedule: &Schedule) -> Result<()> {
+        if !self.schedule_dir(id).exists() {
+            return Err(StorageError::NotFound(format!("Schedule {} not found", id)));
+        }
+
+        self.store_schedule(schedule).await?;
+        Ok(())
     }
 
-    async fn delete_schedule(&self, _id: Uuid) -> Result<()> {
-        Err(StorageError::IoError(
-            "Schedule storage not implemented for filesystem backend".to_string(),
-        ))
+    async fn delete_schedule(&self, id: Uuid) -> Result<()> {
+        let schedule_dir = self.schedule_dir(id);
+
+        if !schedule_dir.exists() {
+            return Err(StorageError::NotFound(format!("Schedule {} not found", id)));
+        }
+
+        fs::remove_dir_all(&schedule_dir)
+            .await
+            .map_err(|e| StorageError::IoError(e.to_string()))?;
+
+        Ok(())
     }
 
-    async fn list_schedules(&self, _filter: &ScheduleFilter) -> Result<Vec<Schedule>> {
-        Err(StorageError::IoError(
-            "Schedule storage not implemented for filesystem backend".to_string(),
-        ))
+    async fn list_schedules(&self, filter: &ScheduleFilter) -> Result<Vec<Schedule>> {
+        let mut entries = fs::read_dir(self.schedules_path())
+            .await
+            .map_err(|e| StorageError::IoError(e.to_string()))?;
+
+        let mut schedules = Vec::new();
+
+        while let Some(entry) = entries
+            .next_entry()
+            .await
+            .map_err(|e| StorageError::IoError(e.to_string()))?
+        {
+            let id_str = entry.file_name().to_string_lossy().to_string();
+            let Ok(id) = Uuid::parse_str(&id_str) else {
+                continue;
+            };
+            let Some(schedule) = self.get_schedule(id).await? else {
+                continue;
+            };
+
+            if filter
+                .workflow_id
+                .is_some_and(|workflow_id| schedule.workflow_id != workflow_id)
+                || filter
+                    .is_active
+                    .is_some_and(|is_active| schedule.is_active != is_active)
+                || filter
+                    .created_by
+                    .as_ref()
+                    .is_some_and(|created_by| schedule.created_by.as_ref() != Some(created_by))
+            {
+                continue;
+            }
+
+            schedules.push(schedule);
+        }
+
+        schedules.sort_by_key(|s| std::cmp::Reverse(s.created_at));
+
+        let offset = filter.offset.unwrap_or(0);
+        let limit = filter.limit.unwrap_or(schedules.len());
+
+        Ok(schedules.into_iter().skip(offset).take(limit).collect())
     }
 
-    async fn get_due_schedules(&self, _before: DateTime<Utc>) -> Result<Vec<Schedule>> {
-        Err(StorageError::IoError(
-            "Schedule storage not implemented for filesystem backend".to_string(),
-        ))
+    async fn get_due_schedules(&self, before: DateTime<Utc>) -> Result<Vec<Schedule>> {
+        let filter = ScheduleFilter {
+            is_active: Some(true),
+            limit: None,
+            ..Default::default()
+        };
+
+        let mut schedules: Vec<_> = self
+            .list_schedules(&filter)
+            .await?
+            .into_iter()
+            .filter(|s| s.next_run_at.is_none_or(|next| next <= before))
+            .collect();
+        schedules.sort_by_key(|s| s.next_run_at);
+
+        Ok(schedules)
     }
 
-    async fn store_schedule_run(&self, _run: &ScheduleRun) -> Result<Uuid> {
-        Err(StorageError::IoError(
-            "Schedule storage not implemented for filesystem backend".to_string(),
-        ))
+    async fn store_schedule_run(&self, run: &ScheduleRun) -> Result<Uuid> {
+        let schedule_dir = self.schedule_dir(run.schedule_id);
+
+        fs::create_dir_all(&schedule_dir)
+            .await
+            .map_err(|e| StorageError::IoError(e.to_string()))?;
+
+        let runs_file = schedule_dir.join("runs.jsonl");
+        let run_line = serde_json::to_string(&run)
+            .map_err(|e| StorageError::SerializationError(e.to_string()))?;
+
+        // Append to runs file
+        let mut content = if runs_file.exists() {
+            fs::read_to_string(&runs_file)
+                .await
+                .map_err(|e| StorageError::IoError(e.to_string()))?
+        } else {
+            String::new()
+        };
+
+        content.push_str(&run_line);
+        content.push('\n');
+
+        fs::write(&runs_file, content)
+            .await
+            .map_err(|e| StorageError::IoError(e.to_string()))?;
+
+        Ok(run.id)
     }
 
     async fn get_schedule_runs(
         &self,
-        _schedule_id: Uuid,
-        _limit: Option<usize>,
+        schedule_id: Uuid,
+        limit: Option<usize>,
     ) -> Result<Vec<ScheduleRun>> {
-        Err(StorageError::IoError(
-            "Schedule storage not implemented for filesystem backend".to_string(),
-        ))
+        let runs_file = self.schedule_dir(schedule_id).join("runs.jsonl");
+
+        if !runs_file.exists() {
+            return Ok(Vec::new());
+        }
+
+        let content = fs::read_to_string(&runs_file)
+            .await
+            .map_err(|e| StorageError::IoError(e.to_string()))?;
+
+        let mut runs = content
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| {
+                serde_json::from_str::<ScheduleRun>(line)
+                    .map_err(|e| StorageError::SerializationError(e.to_string()))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        // Newest first, like the PostgreSQL backend
+        runs.sort_by_key(|run| std::cmp::Reverse(run.scheduled_for));
+        runs.truncate(limit.unwrap_or(100));
+
+        Ok(runs)
     }
 }
 
@@ -776,3 +980,108 @@ impl ApiKeyStorage for FilesystemStorage {
         ))
     }
 }
+
+#[cfg(feature = "server")]
+#[async_trait]
+impl OAuthConnectionStorage for FilesystemStorage {
+    async fn upsert_oauth_connection(&self, _connection: &OAuthConnection) -> Result<Uuid> {
+        Err(StorageError::IoError(
+            "OAuth connection storage not implemented for filesystem backend".to_string(),
+        ))
+    }
+
+    async fn get_oauth_connection(
+        &self,
+        _provider: &str,
+        _provider_user_id: &str,
+    ) -> Result<Option<OAuthConnection>> {
+        Err(StorageError::IoError(
+            "OAuth connection storage not implemented for filesystem backend".to_string(),
+        ))
+    }
+
+    async fn get_user_oauth_connection(
+        &self,
+        _user_id: Uuid,
+        _provider: &str,
+    ) -> Result<Option<OAuthConnection>> {
+        Err(StorageError::IoError(
+            "OAuth connection storage not implemented for filesystem backend".to_string(),
+        ))
+    }
+
+    async fn delete_oauth_connection(&self, _id: Uuid) -> Result<()> {
+        Err(StorageError::IoError(
+            "OAuth connection storage not implemented for filesystem backend".to_string(),
+        ))
+    }
+
+    async fn insert_pending_login(&self, _login: &PendingLogin) -> Result<()> {
+        Err(StorageError::IoError(
+            "OAuth connection storage not implemented for filesystem backend".to_string(),
+        ))
+    }
+
+    async fn take_pending_login(&self, _state_hash: &str) -> Result<Option<PendingLogin>> {
+        Err(StorageError::IoError(
+            "OAuth connection storage not implemented for filesystem backend".to_string(),
+        ))
+    }
+}
+
+#[cfg(feature = "server")]
+#[async_trait]
+impl MfaStorage for FilesystemStorage {
+    async fn upsert_mfa_settings(&self, _settings: &MfaSettings) -> Result<Uuid> {
+        Err(StorageError::IoError(
+            "MFA storage not implemented for filesystem backend".to_string(),
+        ))
+    }
+
+    async fn get_mfa_settings(&self, _user_id: Uuid) -> Result<Option<MfaSettings>> {
+        Err(StorageError::IoError(
+            "MFA storage not implemented for filesystem backend".to_string(),
+        ))
+    }
+
+    async fn delete_mfa_settings(&self, _user_id: Uuid) -> Result<()> {
+        Err(StorageError::IoError(
+            "MFA storage not implemented for filesystem backend".to_string(),
+        ))
+    }
+
+    async fn record_totp_step(&self, _user_id: Uuid, _step: i64) -> Result<bool> {
+        Err(StorageError::IoError(
+            "MFA storage not implemented for filesystem backend".to_string(),
+        ))
+    }
+
+    async fn consume_mfa_challenge(&self, _jti: &str, _expires_at: DateTime<Utc>) -> Result<bool> {
+        Err(StorageError::IoError(
+            "MFA storage not implemented for filesystem backend".to_string(),
+        ))
+    }
+
+    async fn consume_recovery_code(&self, _user_id: Uuid, _code_hash: &str) -> Result<bool> {
+        Err(StorageError::IoError(
+            "MFA storage not implemented for filesystem backend".to_string(),
+        ))
+    }
+
+    async fn record_mfa_failure(
+        &self,
+        _user_id: Uuid,
+        _max_failures: i32,
+        _locked_until: DateTime<Utc>,
+    ) -> Result<bool> {
+        Err(StorageError::IoError(
+            "MFA storage not implemented for filesystem backend".to_string(),
+        ))
+    }
+
+    async fn reset_mfa_failures(&self, _user_id: Uuid) -> Result<()> {
+        Err(StorageError::IoError(
+            "MFA storage not implemented for filesystem backend".to_string(),
+        ))
+    }
+}
@@ -27,10 +27,11 @@ pub mod user_s3;
 #[cfg(feature = "server")]
 pub use traits::{
     ApiKey, ApiKeyFilter, ApiKeyStorage, Checkpoint, CheckpointStorage, Execution, ExecutionFilter,
-    ExecutionLog, ExecutionStatus, ExecutionStorage, Organization, OrganizationFilter,
-    OrganizationStorage, Result, Schedule, ScheduleFilter, ScheduleRun, ScheduleRunStatus,
-    ScheduleStorage, Storage, StorageError, Team, TeamFilter, TeamMember, TeamStorage,
-    WorkflowFilter, WorkflowMetadata, WorkflowStorage,
+    ExecutionLog, ExecutionStatus, ExecutionStorage, MfaSettings, MfaStorage, OAuthConnection,
+    OAuthConnectionStorage, Organization, OrganizationFilter, OrganizationStorage, PendingLogin,
+    Result, Schedule, ScheduleFilter, ScheduleRun, ScheduleRunStatus, ScheduleStorage, Storage,
+    StorageError, Team, TeamFilter, TeamMember, TeamStorage, WorkflowFilter, WorkflowMetadata,
+    WorkflowStorage,
 };
 
 #[cfg(feature = "server")]
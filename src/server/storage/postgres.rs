@@ -9,12 +9,16 @@ use chrono::{DateTime, Utc};
 #[cfg(feature = "server")]
 use sqlx::{PgPool, Row};
 #[cfg(feature = "server")]
+use std::collections::HashMap;
+#[cfg(feature = "server")]
 use uuid::Uuid;
 
 #[cfg(feature = "server")]
 use super::traits::*;
 #[cfg(feature = "server")]
 use crate::dsl::schema::DSLWorkflow;
+#[cfg(feature = "server")]
+use crate::dsl::Usage;
 
 #[cfg(feature = "server")]
 pub struct PostgresStorage {
@@ -339,9 +343,10 @@ impl ExecutionStorage for PostgresStorage {
                 id, workflow_id, workflow_version, status,
                 started_at, completed_at, created_at,
                 triggered_by, trigger_type, input_params,
-                result, error, retry_count, parent_execution_id
+                result, error, retry_count, parent_execution_id,
+                completed_tasks, total_tasks, usage
             )
-            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17)
             ON CONFLICT (id) DO UPDATE
             SET
                 status = $4,
@@ -349,7 +354,10 @@ impl ExecutionStorage for PostgresStorage {
                 completed_at = $6,
                 result = $11,
                 error = $12,
-                retry_count = $13
+                retry_count = $13,
+                completed_tasks = $15,
+                total_tasks = $16,
+                usage = $17
             "#,
         )
         .bind(id)
@@ -371,6 +379,9 @@ impl ExecutionStorage for PostgresStorage {
         .bind(&execution.error)
         .bind(execution.retry_count as i32)
         .bind(execution.parent_execution_id)
+        .bind(execution.completed_tasks as i32)
+        .bind(execution.total_tasks as i32)
+        .bind(serde_json::to_value(execution.usage).unwrap_or_default())
         .execute(&self.pool)
         .await
         .map_err(|e| StorageError::DatabaseError(e.to_string()))?;
@@ -385,7 +396,8 @@ impl ExecutionStorage for PostgresStorage {
                 id, workflow_id, workflow_version, status,
                 started_at, completed_at, created_at,
                 triggered_by, trigger_type, input_params,
-                result, error, retry_count, parent_execution_id
+                result, error, retry_count, parent_execution_id,
+                completed_tasks, total_tasks, usage
             FROM executions
             WHERE id = $1
             "#,
@@ -426,6 +438,9 @@ impl ExecutionStorage for PostgresStorage {
                 error: row.get("error"),
                 retry_count: row.get::<i32, _>("retry_count") as u32,
                 parent_execution_id: row.get("parent_execution_id"),
+                completed_tasks: row.get::<i32, _>("completed_tasks") as u32,
+                total_tasks: row.get::<i32, _>("total_tasks") as u32,
+                usage: row_usage(&row),
             }))
         } else {
             Ok(None)
@@ -437,6 +452,38 @@ impl ExecutionStorage for PostgresStorage {
         Ok(())
     }
 
+    async fn update_execution_progress(
+        &self,
+        id: Uuid,
+        completed_tasks: u32,
+        total_tasks: u32,
+        usage: &Usage,
+    ) -> Result<()> {
+        let result = sqlx::query(
+            r#"
+            UPDATE executions
+            SET completed_tasks = $2, total_tasks = $3, usage = $4
+            WHERE id = $1
+            "#,
+        )
+        .bind(id)
+        .bind(completed_tasks as i32)
+        .bind(total_tasks as i32)
+        .bind(serde_json::to_value(usage).unwrap_or_default())
+        .execute(&self.pool)
+        .await
+        .map_err(|e| StorageError::DatabaseError(e.to_string()))?;
+
+        if result.rows_affected() == 0 {
+            return Err(StorageError::NotFound(format!(
+                "Execution {} not found",
+                id
+            )));
+        }
+
+        Ok(())
+    }
+
     async fn list_executions(&self, filter: &ExecutionFilter) -> Result<Vec<Execution>> {
         let limit = filter.limit.unwrap_or(100) as i64;
         let offset = filter.offset.unwrap_or(0) as i64;
@@ -447,7 +494,8 @@ impl ExecutionStorage for PostgresStorage {
                 id, workflow_id, workflow_version, status,
                 started_at, completed_at, created_at,
                 triggered_by, trigger_type, input_params,
-                result, error, retry_count, parent_execution_id
+                result, error, retry_count, parent_execution_id,
+                completed_tasks, total_tasks, usage
             FROM executions
             WHERE 1=1
             "#,
@@ -510,12 +558,53 @@ impl ExecutionStorage for PostgresStorage {
                 error: row.get("error"),
                 retry_count: row.get::<i32, _>("retry_count") as u32,
                 parent_execution_id: row.get("parent_execution_id"),
+                completed_tasks: row.get::<i32, _>("completed_tasks") as u32,
+                total_tasks: row.get::<i32, _>("total_tasks") as u32,
+                usage: row_usage(&row),
             });
         }
 
         Ok(results)
     }
 
+    async fn usage_by_workflow(&self) -> Result<HashMap<Uuid, Usage>> {
+        let rows = sqlx::query(
+            r#"
+            SELECT
+                workflow_id,
+                COALESCE(SUM((usage->>'input_tokens')::BIGINT), 0)::BIGINT AS input_tokens,
+                COALESCE(SUM((usage->>'output_tokens')::BIGINT), 0)::BIGINT AS output_tokens,
+                COALESCE(SUM((usage->>'cache_creation_input_tokens')::BIGINT), 0)::BIGINT
+                    AS cache_creation_input_tokens,
+                COALESCE(SUM((usage->>'cache_read_input_tokens')::BIGINT), 0)::BIGINT
+                    AS cache_read_input_tokens,
+                COALESCE(SUM((usage->>'cost_usd')::DOUBLE PRECISION), 0) AS cost_usd
+            FROM executions
+            GROUP BY workflow_id
+            "#,
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| StorageError::DatabaseError(e.to_string()))?;
+
+        Ok(rows
+            .iter()
+            .map(|row| {
+                let tokens = |column: &str| row.get::<i64, _>(column) as u64;
+                (
+                    row.get("workflow_id"),
+                    Usage {
+                        input_tokens: tokens("input_tokens"),
+                        output_tokens: tokens("output_tokens"),
+                        cache_creation_input_tokens: tokens("cache_creation_input_tokens"),
+                        cache_read_input_tokens: tokens("cache_read_input_tokens"),
+                        cost_usd: row.get("cost_usd"),
+                    },
+                )
+            })
+            .collect())
+    }
+
     async fn store_execution_log(&self, log: &ExecutionLog) -> Result<()> {
         sqlx::query(
             r#"
@@ -577,6 +666,43 @@ impl ExecutionStorage for PostgresStorage {
         Ok(logs)
     }
 
+    async fn get_execution_logs_since(
+        &self,
+        execution_id: Uuid,
+        skip: usize,
+    ) -> Result<Vec<ExecutionLog>> {
+        // Log IDs increase in insertion order
+        let rows = sqlx::query(
+            r#"
+            SELECT id, execution_id, task_execution_id, timestamp, level, message, metadata
+            FROM execution_logs
+            WHERE execution_id = $1
+            ORDER BY id
+            OFFSET $2
+            "#,
+        )
+        .bind(execution_id)
+        .bind(skip as i64)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| StorageError::DatabaseError(e.to_string()))?;
+
+        let logs = rows
+            .into_iter()
+            .map(|row| ExecutionLog {
+                id: Some(row.get("id")),
+                execution_id: row.get("execution_id"),
+                task_execution_id: row.get("task_execution_id"),
+                timestamp: row.get("timestamp"),
+                level: row.get("level"),
+                message: row.get("message"),
+                metadata: row.get("metadata"),
+            })
+            .collect();
+
+        Ok(logs)
+    }
+
     async fn delete_execution(&self, id: Uuid) -> Result<()> {
         let result = sqlx::query("DELETE FROM executions WHERE id = $1")
             .bind(id)
@@ -1559,8 +1685,8 @@ impl ApiKeyStorage for PostgresStorage {
             r#"
             INSERT INTO api_keys (
                 id, user_id, key_hash, key_prefix, name, description,
-                scopes, created_at, expires_at, last_used_at, is_active
-            ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)
+                scopes, ip_whitelist, created_at, expires_at, last_used_at, is_active
+            ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8::inet[], $9, $10, $11, $12)
             "#,
         )
         .bind(api_key.id)
@@ -1570,6 +1696,7 @@ impl ApiKeyStorage for PostgresStorage {
         .bind(&api_key.name)
         .bind(&api_key.description)
         .bind(&api_key.scopes)
+        .bind(&api_key.ip_whitelist)
         .bind(api_key.created_at)
         .bind(api_key.expires_at)
         .bind(api_key.last_used_at)
@@ -1584,8 +1711,9 @@ impl ApiKeyStorage for PostgresStorage {
     async fn get_api_key(&self, id: Uuid) -> Result<Option<ApiKey>> {
         let row = sqlx::query(
             r#"
-            SELECT id, user_id, key_hash, key_prefix, name, description,
-                   scopes, created_at, expires_at, last_used_at, is_active
+            SELECT id, user_id, key_hash, key_prefix, name, description, scopes,
+                   COALESCE(ip_whitelist::text[], '{}') AS ip_whitelist,
+                   created_at, expires_at, last_used_at, is_active
             FROM api_keys WHERE id = $1
             "#,
         )
@@ -1603,6 +1731,7 @@ impl ApiKeyStorage for PostgresStorage {
                 name: row.get("name"),
                 description: row.get("description"),
                 scopes: row.get("scopes"),
+                ip_whitelist: row.get("ip_whitelist"),
                 created_at: row.get("created_at"),
                 expires_at: row.get("expires_at"),
                 last_used_at: row.get("last_used_at"),
@@ -1616,8 +1745,9 @@ impl ApiKeyStorage for PostgresStorage {
     async fn get_api_key_by_hash(&self, key_hash: &str) -> Result<Option<ApiKey>> {
         let row = sqlx::query(
             r#"
-            SELECT id, user_id, key_hash, key_prefix, name, description,
-                   scopes, created_at, expires_at, last_used_at, is_active
+            SELECT id, user_id, key_hash, key_prefix, name, description, scopes,
+                   COALESCE(ip_whitelist::text[], '{}') AS ip_whitelist,
+                   created_at, expires_at, last_used_at, is_active
             FROM api_keys WHERE key_hash = $1 AND is_active = TRUE
             "#,
         )
@@ -1635,6 +1765,7 @@ impl ApiKeyStorage for PostgresStorage {
                 name: row.get("name"),
                 description: row.get("description"),
                 scopes: row.get("scopes"),
+                ip_whitelist: row.get("ip_whitelist"),
                 created_at: row.get("created_at"),
                 expires_at: row.get("expires_at"),
                 last_used_at: row.get("last_used_at"),
@@ -1650,7 +1781,7 @@ impl ApiKeyStorage for PostgresStorage {
             r#"
             UPDATE api_keys SET
                 name = $2, description = $3, scopes = $4,
-                expires_at = $5, is_active = $6
+                expires_at = $5, is_active = $6, ip_whitelist = $7::inet[]
             WHERE id = $1
             "#,
         )
@@ -1660,6 +1791,7 @@ impl ApiKeyStorage for PostgresStorage {
         .bind(&api_key.scopes)
         .bind(api_key.expires_at)
         .bind(api_key.is_active)
+        .bind(&api_key.ip_whitelist)
         .execute(&self.pool)
         .await
         .map_err(|e| StorageError::DatabaseError(e.to_string()))?;
@@ -1702,8 +1834,9 @@ impl ApiKeyStorage for PostgresStorage {
     async fn list_api_keys(&self, filter: &ApiKeyFilter) -> Result<Vec<ApiKey>> {
         let mut query = String::from(
             r#"
-            SELECT id, user_id, key_hash, key_prefix, name, description,
-                   scopes, created_at, expires_at, last_used_at, is_active
+            SELECT id, user_id, key_hash, key_prefix, name, description, scopes,
+                   COALESCE(ip_whitelist::text[], '{}') AS ip_whitelist,
+                   created_at, expires_at, last_used_at, is_active
             FROM api_keys WHERE 1=1
             "#,
         );
@@ -1754,6 +1887,7 @@ impl ApiKeyStorage for PostgresStorage {
                 name: row.get("name"),
                 description: row.get("description"),
                 scopes: row.get("scopes"),
+                ip_whitelist: row.get("ip_whitelist"),
                 created_at: row.get("created_at"),
                 expires_at: row.get("expires_at"),
                 last_used_at: row.get("last_used_at"),
@@ -1774,3 +1908,361 @@ impl ApiKeyStorage for PostgresStorage {
         Ok(())
     }
 }
+
+#[cfg(feature = "server")]
+#[async_trait]
+impl OAuthConnectionStorage for PostgresStorage {
+    async fn upsert_oauth_connection(&self, connection: &OAuthConnection) -> Result<Uuid> {
+        let row = sqlx::query(
+            r#"
+            INSERT INTO oauth_connections (
+                id, user_id, provider, provider_user_id, access_token_encrypted,
+                refresh_token_encrypted, expires_at, scopes, profile_data,
+                created_at, updated_at
+            ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)
+            ON CONFLICT (provider, provider_user_id) DO UPDATE SET
+                user_id = EXCLUDED.user_id,
+                access_token_encrypted = EXCLUDED.access_token_encrypted,
+                refresh_token_encrypted = EXCLUDED.refresh_token_encrypted,
+                expires_at = EXCLUDED.expires_at,
+                scopes = EXCLUDED.scopes,
+                profile_data = EXCLUDED.profile_data,
+                updated_at = EXCLUDED.updated_at
+            RETURNING id
+            "#,
+        )
+        .bind(connection.id)
+        .bind(connection.user_id)
+        .bind(&connection.provider)
+        .bind(&connection.provider_user_id)
+        .bind(&connection.access_token_encrypted)
+        .bind(&connection.refresh_token_encrypted)
+        .bind(connection.expires_at)
+        .bind(&connection.scopes)
+        .bind(&connection.profile_data)
+        .bind(connection.created_at)
+        .bind(connection.updated_at)
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| StorageError::DatabaseError(e.to_string()))?;
+
+        Ok(row.get("id"))
+    }
+
+    async fn get_oauth_connection(
+        &self,
+        provider: &str,
+        provider_user_id: &str,
+    ) -> Result<Option<OAuthConnection>> {
+        let row = sqlx::query(
+            r#"
+            SELECT id, user_id, provider, provider_user_id, access_token_encrypted,
+                   refresh_token_encrypted, expires_at, COALESCE(scopes, '{}') AS scopes,
+                   COALESCE(profile_data, '{}'::jsonb) AS profile_data, created_at, updated_at
+            FROM oauth_connections WHERE provider = $1 AND provider_user_id = $2
+            "#,
+        )
+        .bind(provider)
+        .bind(provider_user_id)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| StorageError::DatabaseError(e.to_string()))?;
+
+        Ok(row.as_ref().map(row_oauth_connection))
+    }
+
+    async fn get_user_oauth_connection(
+        &self,
+        user_id: Uuid,
+        provider: &str,
+    ) -> Result<Option<OAuthConnection>> {
+        let row = sqlx::query(
+            r#"
+            SELECT id, user_id, provider, provider_user_id, access_token_encrypted,
+                   refresh_token_encrypted, expires_at, COALESCE(scopes, '{}') AS scopes,
+                   COALESCE(profile_data, '{}'::jsonb) AS profile_data, created_at, updated_at
+            FROM oauth_connections WHERE user_id = $1 AND provider = $2
+            ORDER BY updated_at DESC LIMIT 1
+            "#,
+        )
+        .bind(user_id)
+        .bind(provider)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| StorageError::DatabaseError(e.to_string()))?;
+
+        Ok(row.as_ref().map(row_oauth_connection))
+    }
+
+    async fn delete_oauth_connection(&self, id: Uuid) -> Result<()> {
+        let result = sqlx::query("DELETE FROM oauth_connections WHERE id = $1")
+            .bind(id)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| StorageError::DatabaseError(e.to_string()))?;
+
+        if result.rows_affected() == 0 {
+            return Err(StorageError::NotFound(format!(
+                "OAuth connection {} not found",
+                id
+            )));
+        }
+
+        Ok(())
+    }
+
+    async fn insert_pending_login(&self, login: &PendingLogin) -> Result<()> {
+        sqlx::query("DELETE FROM sso_pending_logins WHERE expires_at < NOW()")
+            .execute(&self.pool)
+            .await
+            .map_err(|e| StorageError::DatabaseError(e.to_string()))?;
+
+        sqlx::query(
+            r#"
+            INSERT INTO sso_pending_logins (
+                state_hash, provider, verifier_encrypted, nonce, expires_at
+            ) VALUES ($1, $2, $3, $4, $5)
+            "#,
+        )
+        .bind(&login.state_hash)
+        .bind(&login.provider)
+        .bind(&login.verifier_encrypted)
+        .bind(&login.nonce)
+        .bind(login.expires_at)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| StorageError::DatabaseError(e.to_string()))?;
+
+        Ok(())
+    }
+
+    async fn take_pending_login(&self, state_hash: &str) -> Result<Option<PendingLogin>> {
+        // Deleting as it is read lets only one callback use the login
+        let row = sqlx::query(
+            r#"
+            DELETE FROM sso_pending_logins WHERE state_hash = $1
+            RETURNING state_hash, provider, verifier_encrypted, nonce, expires_at
+            "#,
+        )
+        .bind(state_hash)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| StorageError::DatabaseError(e.to_string()))?;
+
+        Ok(row
+            .map(|row| PendingLogin {
+                state_hash: row.get("state_hash"),
+                provider: row.get("provider"),
+                verifier_encrypted: row.get("verifier_encrypted"),
+                nonce: row.get("nonce"),
+                expires_at: row.get("expires_at"),
+            })
+            .filter(|login| login.expires_at > Utc::now()))
+    }
+}
+
+#[cfg(feature = "server")]
+#[async_trait]
+impl MfaStorage for PostgresStorage {
+    async fn upsert_mfa_settings(&self, settings: &MfaSettings) -> Result<Uuid> {
+        let row = sqlx::query(
+            r#"
+            INSERT INTO mfa_settings (
+                id, user_id, method, secret_encrypted, recovery_code_hashes,
+                is_enabled, last_used_step, failed_attempts, locked_until,
+                verified_at, created_at
+            ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)
+            ON CONFLICT (user_id) DO UPDATE SET
+                method = EXCLUDED.method,
+                secret_encrypted = EXCLUDED.secret_encrypted,
+                recovery_code_hashes = EXCLUDED.recovery_code_hashes,
+                is_enabled = EXCLUDED.is_enabled,
+                last_used_step = EXCLUDED.last_used_step,
+                failed_attempts = EXCLUDED.failed_attempts,
+                locked_until = EXCLUDED.locked_until,
+                verified_at = EXCLUDED.verified_at
+            RETURNING id
+            "#,
+        )
+        .bind(settings.id)
+        .bind(settings.user_id)
+        .bind(&settings.method)
+        .bind(&settings.secret_encrypted)
+        .bind(&settings.recovery_code_hashes)
+        .bind(settings.is_enabled)
+        .bind(settings.last_used_step)
+        .bind(settings.failed_attempts)
+        .bind(settings.locked_until)
+        .bind(settings.verified_at)
+        .bind(settings.created_at)
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| StorageError::DatabaseError(e.to_string()))?;
+
+        Ok(row.get("id"))
+    }
+
+    async fn get_mfa_settings(&self, user_id: Uuid) -> Result<Option<MfaSettings>> {
+        let row = sqlx::query(
+            r#"
+            SELECT id, user_id, method, secret_encrypted,
+                   COALESCE(recovery_code_hashes, '{}') AS recovery_code_hashes,
+                   COALESCE(is_enabled, FALSE) AS is_enabled, last_used_step,
+                   failed_attempts, locked_until, verified_at,
+                   COALESCE(created_at, NOW()) AS created_at
+            FROM mfa_settings WHERE user_id = $1
+            "#,
+        )
+        .bind(user_id)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| StorageError::DatabaseError(e.to_string()))?;
+
+        Ok(row.map(|row| MfaSettings {
+            id: row.get("id"),
+            user_id: row.get("user_id"),
+            method: row.get("method"),
+            secret_encrypted: row.get("secret_encrypted"),
+            recovery_code_hashes: row.get("recovery_code_hashes"),
+            is_enabled: row.get("is_enabled"),
+            last_used_step: row.get("last_used_step"),
+            failed_attempts: row.get("failed_attempts"),
+            locked_until: row.get("locked_until"),
+            verified_at: row.get("verified_at"),
+            created_at: row.get("created_at"),
+        }))
+    }
+
+    async fn delete_mfa_settings(&self, user_id: Uuid) -> Result<()> {
+        sqlx::query("DELETE FROM mfa_settings WHERE user_id = $1")
+            .bind(user_id)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| StorageError::DatabaseError(e.to_string()))?;
+
+        Ok(())
+    }
+
+    async fn record_totp_step(&self, user_id: Uuid, step: i64) -> Result<bool> {
+        // The condition makes concurrent uses of one code race safely
+        let result = sqlx::query(
+            r#"
+            UPDATE mfa_settings SET last_used_step = $2
+            WHERE user_id = $1 AND (last_used_step IS NULL OR last_used_step < $2)
+            "#,
+        )
+        .bind(user_id)
+        .bind(step)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| StorageError::DatabaseError(e.to_string()))?;
+
+        Ok(result.rows_affected() == 1)
+    }
+
+    async fn consume_mfa_challenge(&self, jti: &str, expires_at: DateTime<Utc>) -> Result<bool> {
+        sqlx::query("DELETE FROM mfa_challenges WHERE expires_at < NOW()")
+            .execute(&self.pool)
+            .await
+            .map_err(|e| StorageError::DatabaseError(e.to_string()))?;
+
+        // The primary key makes concurrent uses of one challenge race safely
+        let result = sqlx::query(
+            r#"
+            INSERT INTO mfa_challenges (jti, expires_at) VALUES ($1, $2)
+            ON CONFLICT (jti) DO NOTHING
+            "#,
+        )
+        .bind(jti)
+        .bind(expires_at)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| StorageError::DatabaseError(e.to_string()))?;
+
+        Ok(result.rows_affected() == 1)
+    }
+
+    async fn consume_recovery_code(&self, user_id: Uuid, code_hash: &str) -> Result<bool> {
+        let result = sqlx::query(
+            r#"
+            UPDATE mfa_settings
+            SET recovery_code_hashes = array_remove(recovery_code_hashes, $2)
+            WHERE user_id = $1 AND $2 = ANY(recovery_code_hashes)
+            "#,
+        )
+        .bind(user_id)
+        .bind(code_hash)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| StorageError::DatabaseError(e.to_string()))?;
+
+        Ok(result.rows_affected() == 1)
+    }
+
+    async fn record_mfa_failure(
+        &self,
+        user_id: Uuid,
+        max_failures: i32,
+        locked_until: DateTime<Utc>,
+    ) -> Result<bool> {
+        // Counting in the update keeps concurrent wrong codes from being lost
+        let row = sqlx::query(
+            r#"
+            UPDATE mfa_settings SET
+                failed_attempts = CASE WHEN failed_attempts + 1 >= $2
+                    THEN 0 ELSE failed_attempts + 1 END,
+                locked_until = CASE WHEN failed_attempts + 1 >= $2
+                    THEN $3 ELSE locked_until END
+            WHERE user_id = $1
+            RETURNING failed_attempts = 0 AS locked
+            "#,
+        )
+        .bind(user_id)
+        .bind(max_failures)
+        .bind(locked_until)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| StorageError::DatabaseError(e.to_string()))?;
+
+        Ok(row.is_some_and(|row| row.get("locked")))
+    }
+
+    async fn reset_mfa_failures(&self, user_id: Uuid) -> Result<()> {
+        sqlx::query(
+            "UPDATE mfa_settings SET failed_attempts = 0, locked_until = NULL WHERE user_id = $1",
+        )
+        .bind(user_id)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| StorageError::DatabaseError(e.to_string()))?;
+
+        Ok(())
+    }
+}
+
+/// OAuth connection of a row
+#[cfg(feature = "server")]
+fn row_oauth_connection(row: &sqlx::postgres::PgRow) -> OAuthConnection {
+    OAuthConnection {
+        id: row.get("id"),
+        user_id: row.get("user_id"),
+        provider: row.get("provider"),
+        provider_user_id: row.get("provider_user_id"),
+        access_token_encrypted: row.get("access_token_encrypted"),
+        refresh_token_encrypted: row.get("refresh_token_encrypted"),
+        expires_at: row.get("expires_at"),
+        scopes: row.get("scopes"),
+        profile_data: row.get("profile_data"),
+        created_at: row.get("created_at"),
+        updated_at: row.get("updated_at"),
+    }
+}
+
+/// Usage column of an execution row
+#[cfg(feature = "server")]
+fn row_usage(row: &sqlx::postgres::PgRow) -> Usage {
+    row.try_get::<serde_json::Value, _>("usage")
+        .ok()
+        .and_then(|usage| serde_json::from_value(usage).ok())
+        .unwrap_or_default()
+}
@@ -10,14 +10,19 @@ use uuid::Uuid;
 #[cfg(feature = "server")]
 use super::traits::{
     ApiKey, ApiKeyFilter, ApiKeyStorage, Checkpoint, CheckpointStorage, Execution, ExecutionFilter,
-    ExecutionLog, ExecutionStorage, Organization, OrganizationFilter, OrganizationStorage, Result,
-    Schedule, ScheduleFilter, ScheduleRun, ScheduleStorage, StorageError, Team, TeamFilter,
+    ExecutionLog, ExecutionStorage, MfaSettings, MfaStorage, OAuthConnection,
+    OAuthConnectionStorage, Organization, OrganizationFilter, OrganizationStorage, PendingLogin,
+    Result, Schedule, ScheduleFilter, ScheduleRun, ScheduleStorage, StorageError, Team, TeamFilter,
     TeamMember, TeamStorage, WorkflowFilter, WorkflowMetadata, WorkflowStorage,
 };
 #[cfg(feature = "server")]
 use crate::dsl::schema::DSLWorkflow;
 #[cfg(feature = "server")]
+use crate::dsl::Usage;
+#[cfg(feature = "server")]
 use chrono::{DateTime, Utc};
+#[cfg(feature = "server")]
+use std::collections::HashMap;
 
 /// S3-based storage backend
 ///
@@ -92,9 +97,13 @@ impl S3Storage {
     }
 
     fn execution_log_key(&self, execution_id: Uuid, timestamp: i64) -> String {
+        // The random suffix keeps logs of the same instant apart
         format!(
-            "{}/executions/{}/logs/{}.json",
-            self.prefix, execution_id, timestamp
+            "{}/executions/{}/logs/{:020}-{}.json",
+            self.prefix,
+            execution_id,
+            timestamp,
+            Uuid::new_v4()
         )
     }
 
@@ -436,6 +445,25 @@ impl ExecutionStorage for S3Storage {
         self.put_json(&key, execution).await
     }
 
+    async fn update_execution_progress(
+        &self,
+        id: Uuid,
+        completed_tasks: u32,
+        total_tasks: u32,
+        usage: &Usage,
+    ) -> Result<()> {
+        let mut execution = self
+            .get_execution(id)
+            .await?
+            .ok_or_else(|| StorageError::NotFound(format!("Execution {} not found", id)))?;
+        execution.completed_tasks = completed_tasks;
+        execution.total_tasks = total_tasks;
+        execution.usage = *usage;
+
+        let key = self.execution_key(id);
+        self.put_json(&key, &execution).await
+    }
+
     async fn list_executions(&self, filter: &ExecutionFilter) -> Result<Vec<Execution>> {
         let prefix = format!("{}/executions/", self.prefix);
         let keys = self.list_objects(&prefix).await?;
@@ -506,8 +534,26 @@ impl ExecutionStorage for S3Storage {
         Ok(results.into_iter().skip(offset).take(limit).collect())
     }
 
+    async fn usage_by_workflow(&self) -> Result<HashMap<Uuid, Usage>> {
+        let filter = ExecutionFilter {
+            limit: None,
+            ..Default::default()
+        };
+        let mut usage: HashMap<Uuid, Usage> = HashMap::new();
+        for execution in self.list_executions(&filter).await? {
+            usage
+                .entry(execution.workflow_id)
+                .or_default()
+                .add(&execution.usage);
+        }
+        Ok(usage)
+    }
+
     async fn store_execution_log(&self, log: &ExecutionLog) -> Result<()> {
-        let key = self.execution_log_key(log.execution_id, log.timestamp.timestamp());
+        let key = self.execution_log_key(
+            log.execution_id,
+            log.timestamp.timestamp_nanos_opt().unwrap_or_default(),
+        );
         self.put_json(&key, log).await
     }
 
@@ -538,6 +584,26 @@ impl ExecutionStorage for S3Storage {
         Ok(logs)
     }
 
+    async fn get_execution_logs_since(
+        &self,
+        execution_id: Uuid,
+        skip: usize,
+    ) -> Result<Vec<ExecutionLog>> {
+        let prefix = format!("{}/executions/{}/logs/", self.prefix, execution_id);
+        let mut keys = self.list_objects(&prefix).await?;
+        // Keys start with the log's timestamp, so they sort in storage order
+        keys.sort();
+
+        let mut logs = Vec::new();
+        for key in keys.into_iter().skip(skip) {
+            if let Some(log) = self.get_json::<ExecutionLog>(&key).await? {
+                logs.push(log);
+            }
+        }
+
+        Ok(logs)
+    }
+
     async fn delete_execution(&self, id: Uuid) -> Result<()> {
         // Check if exists
         if self.get_execution(id).await?.is_none() {
@@ -825,6 +891,111 @@ impl ApiKeyStorage for S3Storage {
     }
 }
 
+#[cfg(feature = "server")]
+#[async_trait]
+impl OAuthConnectionStorage for S3Storage {
+    async fn upsert_oauth_connection(&self, _connection: &OAuthConnection) -> Result<Uuid> {
+        Err(StorageError::S3Error(
+            "OAuth connection storage not implemented for S3 backend".to_string(),
+        ))
+    }
+
+    async fn get_oauth_connection(
+        &self,
+        _provider: &str,
+        _provider_user_id: &str,
+    ) -> Result<Option<OAuthConnection>> {
+        Err(StorageError::S3Error(
+            "OAuth connection storage not implemented for S3 backend".to_string(),
+        ))
+    }
+
+    async fn get_user_oauth_connection(
+        &self,
+        _user_id: Uuid,
+        _provider: &str,
+    ) -> Result<Option<OAuthConnection>> {
+        Err(StorageError::S3Error(
+            "OAuth connection storage not implemented for S3 backend".to_string(),
+        ))
+    }
+
+    async fn delete_oauth_connection(&self, _id: Uuid) -> Result<()> {
+        Err(StorageError::S3Error(
+            "OAuth connection storage not implemented for S3 backend".to_string(),
+        ))
+    }
+
+    async fn insert_pending_login(&self, _login: &PendingLogin) -> Result<()> {
+        Err(StorageError::S3Error(
+            "OAuth connection storage not implemented for S3 backend".to_string(),
+        ))
+    }
+
+    async fn take_pending_login(&self, _state_hash: &str) -> Result<Option<PendingLogin>> {
+        Err(StorageError::S3Error(
+            "OAuth connection storage not implemented for S3 backend".to_string(),
+        ))
+    }
+}
+
+#[cfg(feature = "server")]
+#[async_trait]
+impl MfaStorage for S3Storage {
+    async fn upsert_mfa_settings(&self, _settings: &MfaSettings) -> Result<Uuid> {
+        Err(StorageError::S3Error(
+            "MFA storage not implemented for S3 backend".to_string(),
+        ))
+    }
+
+    async fn get_mfa_settings(&self, _user_id: Uuid) -> Result<Option<MfaSettings>> {
+        Err(StorageError::S3Error(
+            "MFA storage not implemented for S3 backend".to_string(),
+        ))
+    }
+
+    async fn delete_mfa_settings(&self, _user_id: Uuid) -> Result<()> {
+        Err(StorageError::S3Error(
+            "MFA storage not implemented for S3 backend".to_string(),
+        ))
+    }
+
+    async fn record_totp_step(&self, _user_id: Uuid, _step: i64) -> Result<bool> {
+        Err(StorageError::S3Error(
+            "MFA storage not implemented for S3 backend".to_string(),
+        ))
+    }
+
+    async fn consume_mfa_challenge(&self, _jti: &str, _expires_at: DateTime<Utc>) -> Result<bool> {
+        Err(StorageError::S3Error(
+            "MFA storage not implemented for S3 backend".to_string(),
+        ))
+    }
+
+    async fn consume_recovery_code(&self, _user_id: Uuid, _code_hash: &str) -> Result<bool> {
+        Err(StorageError::S3Error(
+            "MFA storage not implemented for S3 backend".to_string(),
+        ))
+    }
+
+    async fn record_mfa_failure(
+        &self,
+        _user_id: Uuid,
+        _max_failures: i32,
+        _locked_until: DateTime<Utc>,
+    ) -> Result<bool> {
+        Err(StorageError::S3Error(
+            "MFA storage not implemented for S3 backend".to_string(),
+        ))
+    }
+
+    async fn reset_mfa_failures(&self, _user_id: Uuid) -> Result<()> {
+        Err(StorageError::S3Error(
+            "MFA storage not implemented for S3 backend".to_string(),
+        ))
+    }
+}
+
 #[cfg(all(test, feature = "server"))]
 mod tests {
 
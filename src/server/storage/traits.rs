@@ -7,12 +7,16 @@ use chrono::{DateTime, Utc};
 #[cfg(feature = "server")]
 use serde::{Deserialize, Serialize};
 #[cfg(feature = "server")]
+use std::collections::HashMap;
+#[cfg(feature = "server")]
 use thiserror::Error;
 #[cfg(feature = "server")]
 use uuid::Uuid;
 
 #[cfg(feature = "server")]
 use crate::dsl::schema::DSLWorkflow;
+#[cfg(feature = "server")]
+use crate::dsl::state::Usage;
 
 #[cfg(feature = "server")]
 #[derive(Debug, Error)]
@@ -164,6 +168,15 @@ pub struct Execution {
     pub error: Option<String>,
     pub retry_count: u32,
     pub parent_execution_id: Option<Uuid>,
+    /// Tasks that reached a final status (succeeded, failed or skipped)
+    #[serde(default)]
+    pub completed_tasks: u32,
+    /// Tasks in the workflow, known once execution starts
+    #[serde(default)]
+    pub total_tasks: u32,
+    /// Tokens and cost used by the workflow's agent and LLM calls so far
+    #[serde(default)]
+    pub usage: Usage,
 }
 
 #[cfg(feature = "server")]
@@ -221,9 +234,24 @@ pub trait ExecutionStorage: Send + Sync {
     /// Update execution
     async fn update_execution(&self, id: Uuid, execution: &Execution) -> Result<()>;
 
+    /// Update only the task counts and usage of an execution
+    ///
+    /// Leaves the status and every other field as stored, so progress written
+    /// by a worker does not undo a concurrent status change such as a cancel.
+    async fn update_execution_progress(
+        &self,
+        id: Uuid,
+        completed_tasks: u32,
+        total_tasks: u32,
+        usage: &Usage,
+    ) -> Result<()>;
+
     /// List executions with filtering
     async fn list_executions(&self, filter: &ExecutionFilter) -> Result<Vec<Execution>>;
 
+    /// Usage of all stored executions, summed per workflow
+    async fn usage_by_workflow(&self) -> Result<HashMap<Uuid, Usage>>;
+
     /// Store execution log entry
     async fn store_execution_log(&self, log: &ExecutionLog) -> Result<()>;
 
@@ -234,6 +262,13 @@ pub trait ExecutionStorage: Send + Sync {
         limit: Option<usize>,
     ) -> Result<Vec<ExecutionLog>>;
 
+    /// Execution logs after the first `skip` recorded, oldest first
+    async fn get_execution_logs_since(
+        &self,
+        execution_id: Uuid,
+        skip: usize,
+    ) -> Result<Vec<ExecutionLog>>;
+
     /// Delete execution
     async fn delete_execution(&self, id: Uuid) -> Result<()>;
 }
@@ -524,6 +559,9 @@ pub struct ApiKey {
     pub name: Option<String>,
     pub description: Option<String>,
     pub scopes: Vec<String>,
+    /// Addresses or CIDR blocks the key may be used from; empty allows any
+    #[serde(default)]
+    pub ip_whitelist: Vec<String>,
     pub created_at: DateTime<Utc>,
     pub expires_at: Option<DateTime<Utc>>,
     pub last_used_at: Option<DateTime<Utc>>,
@@ -579,6 +617,140 @@ pub trait ApiKeyStorage: Send + Sync {
     async fn update_last_used(&self, id: Uuid) -> Result<()>;
 }
 
+// ============================================================================
+// OAuth Connection Storage
+// ============================================================================
+
+/// Link between a user and their account at an OAuth/OIDC provider
+///
+/// Provider tokens are stored encrypted.
+#[cfg(feature = "server")]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OAuthConnection {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub provider: String,
+    pub provider_user_id: String,
+    pub access_token_encrypted: Option<String>,
+    pub refresh_token_encrypted: Option<String>,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub scopes: Vec<String>,
+    pub profile_data: serde_json::Value,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Single sign-on login waiting for the provider's callback
+///
+/// Stored under a SHA-256 hash of its state, with the PKCE verifier encrypted.
+#[cfg(feature = "server")]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PendingLogin {
+    pub state_hash: String,
+    pub provider: String,
+    pub verifier_encrypted: String,
+    pub nonce: String,
+    pub expires_at: DateTime<Utc>,
+}
+
+#[cfg(feature = "server")]
+#[async_trait]
+pub trait OAuthConnectionStorage: Send + Sync {
+    /// Store a connection, replacing the one for the same provider account
+    async fn upsert_oauth_connection(&self, connection: &OAuthConnection) -> Result<Uuid>;
+
+    /// Get the connection of a provider account
+    async fn get_oauth_connection(
+        &self,
+        provider: &str,
+        provider_user_id: &str,
+    ) -> Result<Option<OAuthConnection>>;
+
+    /// Get a user's connection to a provider
+    async fn get_user_oauth_connection(
+        &self,
+        user_id: Uuid,
+        provider: &str,
+    ) -> Result<Option<OAuthConnection>>;
+
+    /// Delete a connection
+    async fn delete_oauth_connection(&self, id: Uuid) -> Result<()>;
+
+    /// Store a started login, removing expired ones
+    async fn insert_pending_login(&self, login: &PendingLogin) -> Result<()>;
+
+    /// Remove a started login, returning it unless it has expired
+    async fn take_pending_login(&self, state_hash: &str) -> Result<Option<PendingLogin>>;
+}
+
+// ============================================================================
+// MFA Storage
+// ============================================================================
+
+/// Multi-factor authentication settings of a user
+///
+/// The TOTP secret is stored encrypted and recovery codes as SHA-256 hashes.
+/// Settings exist but are disabled while an enrollment awaits confirmation.
+#[cfg(feature = "server")]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MfaSettings {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub method: String,
+    pub secret_encrypted: Option<String>,
+    pub recovery_code_hashes: Vec<String>,
+    pub is_enabled: bool,
+    /// Last TOTP time step accepted, so a code cannot be used twice
+    pub last_used_step: Option<i64>,
+    /// Wrong codes entered since the last correct code or lockout
+    pub failed_attempts: i32,
+    /// Codes are refused until this time after too many wrong ones
+    pub locked_until: Option<DateTime<Utc>>,
+    pub verified_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[cfg(feature = "server")]
+#[async_trait]
+pub trait MfaStorage: Send + Sync {
+    /// Store a user's settings, replacing any existing ones
+    async fn upsert_mfa_settings(&self, settings: &MfaSettings) -> Result<Uuid>;
+
+    /// Get a user's settings
+    async fn get_mfa_settings(&self, user_id: Uuid) -> Result<Option<MfaSettings>>;
+
+    /// Delete a user's settings
+    async fn delete_mfa_settings(&self, user_id: Uuid) -> Result<()>;
+
+    /// Record a used TOTP time step
+    ///
+    /// Returns false when the step is not later than the last one used.
+    async fn record_totp_step(&self, user_id: Uuid, step: i64) -> Result<bool>;
+
+    /// Record a completed login challenge by its token ID
+    ///
+    /// Returns false when the challenge was completed before. Records of
+    /// challenges past `expires_at` may be removed.
+    async fn consume_mfa_challenge(&self, jti: &str, expires_at: DateTime<Utc>) -> Result<bool>;
+
+    /// Remove a recovery code hash, returning whether it was present
+    async fn consume_recovery_code(&self, user_id: Uuid, code_hash: &str) -> Result<bool>;
+
+    /// Count a wrong code
+    ///
+    /// The `max_failures`th wrong code in a row sets `locked_until` and starts
+    /// the count over. Returns whether this locked the user's MFA.
+    async fn record_mfa_failure(
+        &self,
+        user_id: Uuid,
+        max_failures: i32,
+        locked_until: DateTime<Utc>,
+    ) -> Result<bool>;
+
+    /// Reset the count of wrong codes after a correct one
+    async fn reset_mfa_failures(&self, user_id: Uuid) -> Result<()>;
+}
+
 // ============================================================================
 // Combined Storage Trait
 // ============================================================================
@@ -592,6 +764,8 @@ pub trait Storage:
     + OrganizationStorage
     + TeamStorage
     + ApiKeyStorage
+    + OAuthConnectionStorage
+    + MfaStorage
     + Send
     + Sync
 {
@@ -607,6 +781,8 @@ impl<T> Storage for T where
         + OrganizationStorage
         + TeamStorage
         + ApiKeyStorage
+        + OAuthConnectionStorage
+        + MfaStorage
         + Send
         + Sync
 {
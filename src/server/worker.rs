@@ -1,30 +1,47 @@
 // Background worker for executing workflows
 
+#[cfg(feature = "server")]
+use std::collections::HashSet;
 #[cfg(feature = "server")]
 use std::sync::Arc;
 #[cfg(feature = "server")]
 use std::time::Duration;
 #[cfg(feature = "server")]
+use tokio::sync::mpsc::UnboundedReceiver;
+#[cfg(feature = "server")]
 use tokio::time::sleep;
 #[cfg(feature = "server")]
-use tracing::{error, info, warn};
+use tokio_util::sync::CancellationToken;
 #[cfg(feature = "server")]
-use uuid::Uuid;
+use tracing::{error, info, warn};
 
+#[cfg(feature = "server")]
+use super::hub::ExecutionHub;
+#[cfg(feature = "server")]
+use super::hub::{final_status_message, ExecutionStreamMessage};
 #[cfg(feature = "server")]
 use super::queue::{Job, WorkQueue};
 #[cfg(feature = "server")]
-use super::storage::{ExecutionStatus, Storage};
+use super::storage::{Execution, ExecutionLog, ExecutionStatus, Storage};
+#[cfg(feature = "server")]
+use crate::dsl::events::{ExecutionEvent, RetryReason};
 #[cfg(feature = "server")]
 use crate::dsl::schema::DSLWorkflow;
 #[cfg(feature = "server")]
 use crate::dsl::DSLExecutor;
+#[cfg(feature = "server")]
+use uuid::Uuid;
+
+/// How often a worker checks storage for cancellation of its execution
+#[cfg(feature = "server")]
+const CANCELLATION_POLL_INTERVAL: Duration = Duration::from_secs(2);
 
 #[cfg(feature = "server")]
 pub struct Worker {
     worker_id: String,
     queue: Arc<dyn WorkQueue>,
     storage: Arc<dyn Storage>,
+    hub: Arc<ExecutionHub>,
     concurrency: usize,
     poll_interval: Duration,
     _heartbeat_interval: Duration,
@@ -42,12 +59,19 @@ impl Worker {
             worker_id,
             queue,
             storage,
+            hub: Arc::new(ExecutionHub::new()),
             concurrency,
             poll_interval: Duration::from_secs(1),
             _heartbeat_interval: Duration::from_secs(30),
         }
     }
 
+    /// Publish live updates on a hub shared with the WebSocket handlers
+    pub fn with_hub(mut self, hub: Arc<ExecutionHub>) -> Self {
+        self.hub = hub;
+        self
+    }
+
     /// Start the worker (runs indefinitely)
     pub async fn run(&self) {
         info!(
@@ -89,6 +113,17 @@ impl Worker {
             .await?
             .ok_or_else(|| format!("Execution {} not found", execution_id))?;
 
+        // Executions cancelled while queued never start
+        if execution.status == ExecutionStatus::Cancelled {
+            info!(
+                "Worker {} skipping cancelled execution {}",
+                self.worker_id, execution_id
+            );
+            self.queue.complete(job_id).await?;
+            self.publish_final(&execution);
+            return Ok(());
+        }
+
         // Get workflow from storage
         let (workflow, _metadata) = self
             .storage
@@ -103,6 +138,16 @@ impl Worker {
         self.storage
             .update_execution(execution_id, &updated_execution)
             .await?;
+        // Subscribers follow this execution on the hub from here on
+        self.hub.open(execution_id);
+        self.hub.publish(
+            execution_id,
+            ExecutionStreamMessage::Started {
+                execution_id,
+                workflow_id: updated_execution.workflow_id,
+                started_at: chrono::Utc::now().to_rfc3339(),
+            },
+        );
 
         // Start heartbeat task
         let queue = Arc::clone(&self.queue);
@@ -116,14 +161,54 @@ impl Worker {
             }
         });
 
+        // Stop the workflow when the execution is cancelled through the API
+        let cancellation = self.hub.track_cancellation(execution_id);
+        let cancellation_watch = tokio::spawn(watch_cancellation(
+            Arc::clone(&self.storage),
+            execution_id,
+            cancellation.clone(),
+        ));
+
         // Execute workflow
-        let result = self.execute_workflow(workflow, execution_id).await;
+        let result = self
+            .execute_workflow(workflow, &updated_execution, cancellation.clone())
+            .await;
 
         // Cancel heartbeat
         heartbeat_handle.abort();
+        cancellation_watch.abort();
+
+        // Keep the task counts recorded while the workflow ran
+        let updated_execution = self
+            .storage
+            .get_execution(execution_id)
+            .await?
+            .unwrap_or(updated_execution);
+
+        // A cancel request that arrives as the workflow finishes still wins
+        let cancelled =
+            cancellation.is_cancelled() || updated_execution.status == ExecutionStatus::Cancelled;
 
         // Update execution based on result
         match result {
+            result if cancelled => {
+                // Cancelled executions are not retried
+                let mut final_execution = updated_execution;
+                final_execution.status = ExecutionStatus::Cancelled;
+                final_execution
+                    .completed_at
+                    .get_or_insert_with(chrono::Utc::now);
+                if let Ok(output) = result {
+                    final_execution.result = Some(output);
+                }
+                self.storage
+                    .update_execution(execution_id, &final_execution)
+                    .await?;
+                self.queue.complete(job_id).await?;
+                self.publish_final(&final_execution);
+
+                info!("Worker {} cancelled job {}", self.worker_id, job_id);
+            }
             Ok(output) => {
                 let mut final_execution = updated_execution;
                 final_execution.status = ExecutionStatus::Completed;
@@ -132,6 +217,7 @@ impl Worker {
                 self.storage
                     .update_execution(execution_id, &final_execution)
                     .await?;
+                self.publish_final(&final_execution);
 
                 // Mark job as complete
                 self.queue.complete(job_id).await?;
@@ -140,6 +226,11 @@ impl Worker {
             }
             Err(e) => {
                 let error_msg = e.to_string();
+                // Running again would spend the budget again
+                let over_budget = matches!(
+                    e.downcast_ref::<crate::error::Error>(),
+                    Some(crate::error::Error::BudgetExceeded(_))
+                );
 
                 let mut final_execution = updated_execution;
                 final_execution.status = ExecutionStatus::Failed;
@@ -148,9 +239,10 @@ impl Worker {
                 self.storage
                     .update_execution(execution_id, &final_execution)
                     .await?;
+                self.publish_final(&final_execution);
 
                 // Check if should retry
-                if job.attempts < job.max_retries {
+                if job.attempts < job.max_retries && !over_budget {
                     warn!(
                         "Worker {} requeueing job {} (attempt {}/{})",
                         self.worker_id, job_id, job.attempts, job.max_retries
@@ -171,19 +263,45 @@ impl Worker {
         Ok(())
     }
 
+    /// Send the outcome of an execution to its subscribers and close its channel
+    fn publish_final(&self, execution: &Execution) {
+        if let Some(message) = final_status_message(execution) {
+            self.hub.publish(execution.id, message);
+        }
+        self.hub.close(execution.id);
+    }
+
     async fn execute_workflow(
         &self,
         workflow: DSLWorkflow,
-        _execution_id: Uuid,
+        execution: &Execution,
+        cancellation: CancellationToken,
     ) -> Result<serde_json::Value, Box<dyn std::error::Error + Send + Sync>> {
         // Create executor
         let mut executor = DSLExecutor::new(workflow)?;
+        executor.set_console_output(false);
+        executor.set_cancellation_token(cancellation);
+
+        // Record progress while the workflow runs
+        let recorder = ExecutionRecorder::new(
+            execution.clone(),
+            Arc::clone(&self.storage),
+            Arc::clone(&self.hub),
+        );
+        let recording = tokio::spawn(recorder.run(executor.events().channel()));
 
         // Execute workflow
-        executor.execute().await?;
+        let result = match executor.initialize().await {
+            Ok(()) => executor.execute().await,
+            Err(e) => Err(e),
+        };
+        let _ = executor.shutdown().await;
 
         // Get final state
-        let state = executor.get_state();
+        let state = executor.get_state().cloned();
+        drop(executor);
+        let _ = recording.await;
+        result?;
 
         // Convert to JSON
         let output = if let Some(state) = state {
@@ -199,6 +317,397 @@ impl Worker {
     }
 }
 
+/// Cancel `token` once the execution's stored status is cancelled
+///
+/// Picks up cancellation requested through a server in another process.
+#[cfg(feature = "server")]
+async fn watch_cancellation(
+    storage: Arc<dyn Storage>,
+    execution_id: Uuid,
+    token: CancellationToken,
+) {
+    loop {
+        tokio::select! {
+            _ = token.cancelled() => return,
+            _ = sleep(CANCELLATION_POLL_INTERVAL) => {}
+        }
+        match storage.get_execution(execution_id).await {
+            Ok(Some(execution)) if execution.status == ExecutionStatus::Cancelled => {
+                token.cancel();
+                return;
+            }
+            Ok(_) => {}
+            Err(e) => warn!(
+                "Failed to check cancellation of execution {}: {}",
+                execution_id, e
+            ),
+        }
+    }
+}
+
+/// Records the executor events of one execution
+///
+/// Each event is stored as an `ExecutionLog` row and published on the hub,
+/// together with task status updates and the execution's task counts.
+#[cfg(feature = "server")]
+struct ExecutionRecorder {
+    execution: Execution,
+    storage: Arc<dyn Storage>,
+    hub: Arc<ExecutionHub>,
+    /// Tasks of the top-level workflow
+    tasks: HashSet<String>,
+    /// Tasks of the top-level workflow that reached a final status
+    finished: HashSet<String>,
+    /// Number of workflows running, counting child workflows
+    depth: usize,
+    /// Number of logs stored for the execution
+    logged: usize,
+}
+
+#[cfg(feature = "server")]
+impl ExecutionRecorder {
+    fn new(execution: Execution, storage: Arc<dyn Storage>, hub: Arc<ExecutionHub>) -> Self {
+        Self {
+            execution,
+            storage,
+            hub,
+            tasks: HashSet::new(),
+            finished: HashSet::new(),
+            depth: 0,
+            logged: 0,
+        }
+    }
+
+    /// Record events until the top-level workflow finishes or the executor is dropped
+    async fn run(mut self, mut events: UnboundedReceiver<ExecutionEvent>) {
+        // Logs of earlier attempts come first
+        match self
+            .storage
+            .get_execution_logs_since(self.execution.id, 0)
+            .await
+        {
+            Ok(logs) => self.logged = logs.len(),
+            Err(e) => warn!(
+                "Failed to count logs of execution {}: {}",
+                self.execution.id, e
+            ),
+        }
+
+        while let Some(event) = events.recv().await {
+            self.record(&event).await;
+
+            match event {
+                ExecutionEvent::WorkflowStarted { .. } => self.depth += 1,
+                ExecutionEvent::WorkflowFinished { .. } => {
+                    if self.depth <= 1 {
+                        break;
+                    }
+                    self.depth -= 1;
+                }
+                _ => {}
+            }
+        }
+    }
+
+    async fn record(&mut self, event: &ExecutionEvent) {
+        if let Some((level, message)) = log_entry(event) {
+            self.log(level, message, event).await;
+        }
+
+        let (task_id, status, message) = match event {
+            ExecutionEvent::WorkflowStarted { tasks, .. } if self.depth == 0 => {
+                self.tasks = tasks.iter().cloned().collect();
+                self.execution.total_tasks = tasks.len() as u32;
+                self.save_progress().await;
+                return;
+            }
+            // Usage of child workflows counts towards the execution too
+            ExecutionEvent::UsageRecorded { usage, .. } => {
+                self.execution.usage.add(usage);
+                self.save_execution().await;
+                return;
+            }
+            ExecutionEvent::TaskStarted {
+                task_id,
+                description,
+            } => (task_id, "running", Some(description.clone())),
+            ExecutionEvent::TaskRetrying {
+                task_id, message, ..
+            } => (task_id, "retrying", Some(message.clone())),
+            ExecutionEvent::TaskSucceeded { task_id, .. } => (task_id, "completed", None),
+            ExecutionEvent::TaskFailed { task_id, error } => {
+                (task_id, "failed", Some(error.clone()))
+            }
+            ExecutionEvent::TaskSkipped { task_id, reason } => {
+                (task_id, "skipped", Some(reason.clone()))
+            }
+            _ => return,
+        };
+
+        self.hub.publish(
+            self.execution.id,
+            ExecutionStreamMessage::TaskUpdate {
+                execution_id: self.execution.id,
+                task_id: task_id.clone(),
+                status: status.to_string(),
+                message,
+            },
+        );
+
+        if matches!(status, "completed" | "failed" | "skipped")
+            && self.tasks.contains(task_id)
+            && self.finished.insert(task_id.clone())
+        {
+            self.execution.completed_tasks = self.finished.len() as u32;
+            self.save_progress().await;
+        }
+    }
+
+    /// Store a log and publish it with its position among the stored logs
+    ///
+    /// Logs that could not be stored are not published either, so streams
+    /// that replay logs from storage see the same sequence as the hub.
+    async fn log(&mut self, level: &str, message: String, event: &ExecutionEvent) {
+        let log = ExecutionLog {
+            id: None,
+            execution_id: self.execution.id,
+            task_execution_id: None,
+            timestamp: chrono::Utc::now(),
+            level: level.to_string(),
+            message,
+            metadata: serde_json::to_value(event).ok(),
+        };
+        if let Err(e) = self.storage.store_execution_log(&log).await {
+            warn!(
+                "Failed to store log of execution {}: {}",
+                self.execution.id, e
+            );
+            return;
+        }
+        let sequence = self.logged;
+        self.logged += 1;
+        self.hub.publish(
+            self.execution.id,
+            ExecutionStreamMessage::Log {
+                execution_id: self.execution.id,
+                sequence,
+                timestamp: log.timestamp.to_rfc3339(),
+                level: log.level,
+                message: log.message,
+            },
+        );
+    }
+
+    async fn save_progress(&self) {
+        self.save_execution().await;
+        self.hub.publish(
+            self.execution.id,
+            ExecutionStreamMessage::progress(
+                self.execution.id,
+                self.execution.completed_tasks as usize,
+                self.execution.total_tasks as usize,
+            ),
+        );
+    }
+
+    async fn save_execution(&self) {
+        // Only the counts and usage are updated; the status may have changed
+        // meanwhile, e.g. to cancelled
+        let saved = self
+            .storage
+            .update_execution_progress(
+                self.execution.id,
+                self.execution.completed_tasks,
+                self.execution.total_tasks,
+                &self.execution.usage,
+            )
+            .await;
+        if let Err(e) = saved {
+            warn!(
+                "Failed to update progress of execution {}: {}",
+                self.execution.id, e
+            );
+        }
+    }
+}
+
+/// Log level and message of an event, if it is logged
+#[cfg(feature = "server")]
+fn log_entry(event: &ExecutionEvent) -> Option<(&'static str, String)> {
+    Some(match event {
+        ExecutionEvent::WorkflowStarted { workflow, tasks } => (
+            "info",
+            format!("Workflow '{}' started with {} tasks", workflow, tasks.len()),
+        ),
+        ExecutionEvent::WorkflowFinished {
+            workflow,
+            success: true,
+            duration_ms,
+            ..
+        } => (
+            "info",
+            format!("Workflow '{}' completed in {}ms", workflow, duration_ms),
+        ),
+        ExecutionEvent::WorkflowFinished {
+            workflow, error, ..
+        } => (
+            "error",
+            format!(
+                "Workflow '{}' failed: {}",
+                workflow,
+                error.as_deref().unwrap_or("unknown error")
+            ),
+        ),
+        ExecutionEvent::TaskStarted {
+            task_id,
+            description,
+        } => (
+            "info",
+            format!("Task '{}' started: {}", task_id, description),
+        ),
+        ExecutionEvent::TaskRetrying {
+            task_id,
+            attempt,
+            reason,
+            message,
+            ..
+        } => {
+            let reason = match reason {
+                RetryReason::Error => "error",
+                RetryReason::DefinitionOfDone => "definition of done",
+                RetryReason::OutputSchema => "output schema",
+            };
+            (
+                "warn",
+                format!(
+                    "Task '{}' retry {} ({}): {}",
+                    task_id, attempt, reason, message
+                ),
+            )
+        }
+        ExecutionEvent::TaskFallback {
+            task_id,
+            agent,
+            error,
+        } => (
+            "warn",
+            format!(
+                "Task '{}' falling back to agent '{}': {}",
+                task_id, agent, error
+            ),
+        ),
+        ExecutionEvent::TaskSucceeded {
+            task_id,
+            duration_ms,
+        } => (
+            "info",
+            format!("Task '{}' completed in {}ms", task_id, duration_ms),
+        ),
+        ExecutionEvent::TaskFailed { task_id, error } => {
+            ("error", format!("Task '{}' failed: {}", task_id, error))
+        }
+        ExecutionEvent::TaskSkipped { task_id, reason } => {
+            ("info", format!("Task '{}' skipped: {}", task_id, reason))
+        }
+        ExecutionEvent::LoopIterationStarted {
+            task_id,
+            iteration,
+            total,
+            ..
+        } => (
+            "debug",
+            match total {
+                Some(total) => format!(
+                    "Task '{}' iteration {}/{} started",
+                    task_id,
+                    iteration + 1,
+                    total
+                ),
+                None => format!("Task '{}' iteration {} started", task_id, iteration + 1),
+            },
+        ),
+        ExecutionEvent::LoopIterationFinished {
+            task_id,
+            iteration,
+            error: None,
+        } => (
+            "debug",
+            format!("Task '{}' iteration {} completed", task_id, iteration + 1),
+        ),
+        ExecutionEvent::LoopIterationFinished {
+            task_id,
+            iteration,
+            error: Some(error),
+        } => (
+            "warn",
+            format!(
+                "Task '{}' iteration {} failed: {}",
+                task_id,
+                iteration + 1,
+                error
+            ),
+        ),
+        ExecutionEvent::AgentMessage { agent, message, .. } => {
+            ("info", format!("[{}] {}", agent, message.display_text()?))
+        }
+        ExecutionEvent::DefinitionOfDoneChecked {
+            task_id, met: true, ..
+        } => (
+            "info",
+            format!("Task '{}' met its definition of done", task_id),
+        ),
+        ExecutionEvent::DefinitionOfDoneChecked {
+            task_id, feedback, ..
+        } => (
+            "warn",
+            format!(
+                "Task '{}' did not meet its definition of done: {}",
+                task_id,
+                feedback.as_deref().unwrap_or("")
+            ),
+        ),
+        ExecutionEvent::UsageRecorded { task_id, usage, .. } => (
+            "info",
+            format!(
+                "Task '{}' used {} tokens (${:.4})",
+                task_id,
+                usage.total_tokens(),
+                usage.cost_usd
+            ),
+        ),
+        ExecutionEvent::ToolDenied {
+            task_id,
+            agent,
+            tool,
+            reason,
+        } => (
+            "warn",
+            format!(
+                "Task '{}': denied {} for agent '{}': {}",
+                task_id, tool, agent, reason
+            ),
+        ),
+        ExecutionEvent::NotificationSent {
+            message,
+            error: None,
+            ..
+        } => ("info", format!("Notification sent: {}", message)),
+        ExecutionEvent::NotificationSent {
+            message,
+            error: Some(error),
+            ..
+        } => (
+            "warn",
+            format!("Notification '{}' failed: {}", message, error),
+        ),
+        ExecutionEvent::Progress { message, .. } => ("info", message.trim().to_string()),
+        ExecutionEvent::Warning { message, .. } => ("warn", message.clone()),
+        // Task outputs are recorded in the execution's state; streamed
+        // responses would flood the log with fragments
+        ExecutionEvent::TaskOutput { .. } => return None,
+    })
+}
+
 #[cfg(all(test, feature = "server"))]
 mod tests {
 
@@ -6,7 +6,7 @@
 #[cfg(feature = "server")]
 use async_trait::async_trait;
 #[cfg(feature = "server")]
-use chrono::Utc;
+use chrono::{DateTime, Utc};
 #[cfg(feature = "server")]
 use std::collections::HashMap;
 #[cfg(feature = "server")]
@@ -17,7 +17,11 @@ use uuid::Uuid;
 #[cfg(feature = "server")]
 use crate::server::auth::authorization::AuthorizationService;
 #[cfg(feature = "server")]
-use crate::server::storage::traits::StorageError;
+use crate::server::storage::traits::{
+    ApiKey, ApiKeyFilter, ApiKeyStorage, MfaSettings, MfaStorage, OAuthConnection,
+    OAuthConnectionStorage, Organization, OrganizationFilter, OrganizationStorage, PendingLogin,
+    StorageError, Team, TeamFilter, TeamMember, TeamStorage,
+};
 #[cfg(feature = "server")]
 use crate::server::storage::user_storage::{Result, User, UserStorage};
 
@@ -431,6 +435,558 @@ impl AuthorizationService for MockAuthorizationService {
     }
 }
 
+/// Mock API key storage for testing API key authentication
+#[cfg(feature = "server")]
+pub struct MockApiKeyStorage {
+    keys: Arc<Mutex<HashMap<Uuid, ApiKey>>>,
+}
+
+#[cfg(feature = "server")]
+impl MockApiKeyStorage {
+    pub fn new() -> Self {
+        Self {
+            keys: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Add a test API key
+    pub fn add_key(&self, api_key: ApiKey) {
+        self.keys.lock().unwrap().insert(api_key.id, api_key);
+    }
+
+    /// Get a stored API key
+    pub fn key(&self, id: Uuid) -> Option<ApiKey> {
+        self.keys.lock().unwrap().get(&id).cloned()
+    }
+}
+
+#[cfg(feature = "server")]
+impl Default for MockApiKeyStorage {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(feature = "server")]
+#[async_trait]
+impl ApiKeyStorage for MockApiKeyStorage {
+    async fn store_api_key(&self, api_key: &ApiKey) -> Result<Uuid> {
+        self.add_key(api_key.clone());
+        Ok(api_key.id)
+    }
+
+    async fn get_api_key(&self, id: Uuid) -> Result<Option<ApiKey>> {
+        Ok(self.key(id))
+    }
+
+    async fn get_api_key_by_hash(&self, key_hash: &str) -> Result<Option<ApiKey>> {
+        Ok(self
+            .keys
+            .lock()
+            .unwrap()
+            .values()
+            .find(|key| key.key_hash == key_hash)
+            .cloned())
+    }
+
+    async fn update_api_key(&self, id: Uuid, api_key: &ApiKey) -> Result<()> {
+        let mut keys = self.keys.lock().unwrap();
+        if !keys.contains_key(&id) {
+            return Err(StorageError::NotFound(format!("API key {} not found", id)));
+        }
+        keys.insert(id, api_key.clone());
+        Ok(())
+    }
+
+    async fn revoke_api_key(&self, id: Uuid) -> Result<()> {
+        match self.keys.lock().unwrap().get_mut(&id) {
+            Some(key) => {
+                key.is_active = false;
+                Ok(())
+            }
+            None => Err(StorageError::NotFound(format!("API key {} not found", id))),
+        }
+    }
+
+    async fn delete_api_key(&self, id: Uuid) -> Result<()> {
+        match self.keys.lock().unwrap().remove(&id) {
+            Some(_) => Ok(()),
+            None => Err(StorageError::NotFound(format!("API key {} not found", id))),
+        }
+    }
+
+    async fn list_api_keys(&self, filter: &ApiKeyFilter) -> Result<Vec<ApiKey>> {
+        Ok(self
+            .keys
+            .lock()
+            .unwrap()
+            .values()
+            .filter(|key| filter.user_id.is_none_or(|user_id| key.user_id == user_id))
+            .filter(|key| {
+                filter
+                    .is_active
+                    .is_none_or(|active| key.is_active == active)
+            })
+            .cloned()
+            .collect())
+    }
+
+    async fn update_last_used(&self, id: Uuid) -> Result<()> {
+        if let Some(key) = self.keys.lock().unwrap().get_mut(&id) {
+            key.last_used_at = Some(Utc::now());
+        }
+        Ok(())
+    }
+}
+
+/// Mock OAuth connection storage for testing single sign-on
+#[cfg(feature = "server")]
+pub struct MockOAuthConnectionStorage {
+    connections: Arc<Mutex<HashMap<Uuid, OAuthConnection>>>,
+    pending_logins: Arc<Mutex<HashMap<String, PendingLogin>>>,
+}
+
+#[cfg(feature = "server")]
+impl MockOAuthConnectionStorage {
+    pub fn new() -> Self {
+        Self {
+            connections: Arc::new(Mutex::new(HashMap::new())),
+            pending_logins: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Get all stored connections
+    pub fn connections(&self) -> Vec<OAuthConnection> {
+        self.connections.lock().unwrap().values().cloned().collect()
+    }
+
+    /// Get all stored pending logins
+    pub fn pending_logins(&self) -> Vec<PendingLogin> {
+        self.pending_logins
+            .lock()
+            .unwrap()
+            .values()
+            .cloned()
+            .collect()
+    }
+}
+
+#[cfg(feature = "server")]
+impl Default for MockOAuthConnectionStorage {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(feature = "server")]
+#[async_trait]
+impl OAuthConnectionStorage for MockOAuthConnectionStorage {
+    async fn upsert_oauth_connection(&self, connection: &OAuthConnection) -> Result<Uuid> {
+        let mut connections = self.connections.lock().unwrap();
+        let existing = connections
+            .values()
+            .find(|c| {
+                c.provider == connection.provider
+                    && c.provider_user_id == connection.provider_user_id
+            })
+            .map(|c| c.id);
+        let id = existing.unwrap_or(connection.id);
+        connections.insert(
+            id,
+            OAuthConnection {
+                id,
+                ..connection.clone()
+            },
+        );
+        Ok(id)
+    }
+
+    async fn get_oauth_connection(
+        &self,
+        provider: &str,
+        provider_user_id: &str,
+    ) -> Result<Option<OAuthConnection>> {
+        Ok(self
+            .connections
+            .lock()
+            .unwrap()
+            .values()
+            .find(|c| c.provider == provider && c.provider_user_id == provider_user_id)
+            .cloned())
+    }
+
+    async fn get_user_oauth_connection(
+        &self,
+        user_id: Uuid,
+        provider: &str,
+    ) -> Result<Option<OAuthConnection>> {
+        Ok(self
+            .connections
+            .lock()
+            .unwrap()
+            .values()
+            .find(|c| c.user_id == user_id && c.provider == provider)
+            .cloned())
+    }
+
+    async fn delete_oauth_connection(&self, id: Uuid) -> Result<()> {
+        match self.connections.lock().unwrap().remove(&id) {
+            Some(_) => Ok(()),
+            None => Err(StorageError::NotFound(format!(
+                "OAuth connection {} not found",
+                id
+            ))),
+        }
+    }
+
+    async fn insert_pending_login(&self, login: &PendingLogin) -> Result<()> {
+        let mut pending_logins = self.pending_logins.lock().unwrap();
+        let now = Utc::now();
+        pending_logins.retain(|_, login| login.expires_at >= now);
+        pending_logins.insert(login.state_hash.clone(), login.clone());
+        Ok(())
+    }
+
+    async fn take_pending_login(&self, state_hash: &str) -> Result<Option<PendingLogin>> {
+        Ok(self
+            .pending_logins
+            .lock()
+            .unwrap()
+            .remove(state_hash)
+            .filter(|login| login.expires_at > Utc::now()))
+    }
+}
+
+/// Mock MFA settings storage for testing multi-factor authentication
+#[cfg(feature = "server")]
+pub struct MockMfaStorage {
+    settings: Arc<Mutex<HashMap<Uuid, MfaSettings>>>,
+    challenges: Arc<Mutex<HashMap<String, DateTime<Utc>>>>,
+}
+
+#[cfg(feature = "server")]
+impl MockMfaStorage {
+    pub fn new() -> Self {
+        Self {
+            settings: Arc::new(Mutex::new(HashMap::new())),
+            challenges: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Number of completed login challenges still recorded
+    pub fn challenge_count(&self) -> usize {
+        self.challenges.lock().unwrap().len()
+    }
+
+    /// Get a user's stored settings
+    pub fn settings(&self, user_id: Uuid) -> Option<MfaSettings> {
+        self.settings.lock().unwrap().get(&user_id).cloned()
+    }
+}
+
+#[cfg(feature = "server")]
+impl Default for MockMfaStorage {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(feature = "server")]
+#[async_trait]
+impl MfaStorage for MockMfaStorage {
+    async fn upsert_mfa_settings(&self, settings: &MfaSettings) -> Result<Uuid> {
+        self.settings
+            .lock()
+            .unwrap()
+            .insert(settings.user_id, settings.clone());
+        Ok(settings.id)
+    }
+
+    async fn get_mfa_settings(&self, user_id: Uuid) -> Result<Option<MfaSettings>> {
+        Ok(self.settings(user_id))
+    }
+
+    async fn delete_mfa_settings(&self, user_id: Uuid) -> Result<()> {
+        self.settings.lock().unwrap().remove(&user_id);
+        Ok(())
+    }
+
+    async fn record_totp_step(&self, user_id: Uuid, step: i64) -> Result<bool> {
+        match self.settings.lock().unwrap().get_mut(&user_id) {
+            Some(settings) if settings.last_used_step.is_none_or(|last| last < step) => {
+                settings.last_used_step = Some(step);
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+
+    async fn consume_mfa_challenge(&self, jti: &str, expires_at: DateTime<Utc>) -> Result<bool> {
+        let mut challenges = self.challenges.lock().unwrap();
+        let now = Utc::now();
+        challenges.retain(|_, expires_at| *expires_at >= now);
+        if challenges.contains_key(jti) {
+            return Ok(false);
+        }
+        challenges.insert(jti.to_string(), expires_at);
+        Ok(true)
+    }
+
+    async fn consume_recovery_code(&self, user_id: Uuid, code_hash: &str) -> Result<bool> {
+        match self.settings.lock().unwrap().get_mut(&user_id) {
+            Some(settings) => {
+                let count = settings.recovery_code_hashes.len();
+                settings
+                    .recovery_code_hashes
+                    .retain(|hash| hash != code_hash);
+                Ok(settings.recovery_code_hashes.len() < count)
+            }
+            None => Ok(false),
+        }
+    }
+
+    async fn record_mfa_failure(
+        &self,
+        user_id: Uuid,
+        max_failures: i32,
+        locked_until: DateTime<Utc>,
+    ) -> Result<bool> {
+        match self.settings.lock().unwrap().get_mut(&user_id) {
+            Some(settings) if settings.failed_attempts + 1 >= max_failures => {
+                settings.failed_attempts = 0;
+                settings.locked_until = Some(locked_until);
+                Ok(true)
+            }
+            Some(settings) => {
+                settings.failed_attempts += 1;
+                Ok(false)
+            }
+            None => Ok(false),
+        }
+    }
+
+    async fn reset_mfa_failures(&self, user_id: Uuid) -> Result<()> {
+        if let Some(settings) = self.settings.lock().unwrap().get_mut(&user_id) {
+            settings.failed_attempts = 0;
+            settings.locked_until = None;
+        }
+        Ok(())
+    }
+}
+
+/// Mock organization and team storage for testing team membership
+#[cfg(feature = "server")]
+pub struct MockOrganizationStorage {
+    state: Arc<Mutex<OrganizationStorageState>>,
+}
+
+#[cfg(feature = "server")]
+#[derive(Default)]
+struct OrganizationStorageState {
+    organizations: HashMap<Uuid, Organization>,
+    teams: HashMap<Uuid, Team>,
+    members: Vec<TeamMember>,
+}
+
+#[cfg(feature = "server")]
+impl MockOrganizationStorage {
+    pub fn new() -> Self {
+        Self {
+            state: Arc::new(Mutex::new(OrganizationStorageState::default())),
+        }
+    }
+
+    /// Add a test organization with the given slug
+    pub fn add_organization(&self, slug: &str) -> Uuid {
+        let now = Utc::now();
+        let organization = Organization {
+            id: Uuid::new_v4(),
+            name: slug.to_string(),
+            slug: slug.to_string(),
+            description: None,
+            logo_url: None,
+            plan: "free".to_string(),
+            settings: serde_json::json!({}),
+            created_at: now,
+            updated_at: now,
+            is_active: true,
+        };
+        let id = organization.id;
+        self.state
+            .lock()
+            .unwrap()
+            .organizations
+            .insert(id, organization);
+        id
+    }
+
+    /// Add a test team to an organization
+    pub fn add_team(&self, organization_id: Uuid, name: &str) -> Uuid {
+        let now = Utc::now();
+        let team = Team {
+            id: Uuid::new_v4(),
+            organization_id,
+            name: name.to_string(),
+            description: None,
+            created_at: now,
+            updated_at: now,
+        };
+        let id = team.id;
+        self.state.lock().unwrap().teams.insert(id, team);
+        id
+    }
+
+    /// Get the members of a team
+    pub fn members(&self, team_id: Uuid) -> Vec<TeamMember> {
+        self.state
+            .lock()
+            .unwrap()
+            .members
+            .iter()
+            .filter(|member| member.team_id == team_id)
+            .cloned()
+            .collect()
+    }
+}
+
+#[cfg(feature = "server")]
+impl Default for MockOrganizationStorage {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(feature = "server")]
+#[async_trait]
+impl OrganizationStorage for MockOrganizationStorage {
+    async fn store_organization(&self, organization: &Organization) -> Result<Uuid> {
+        self.state
+            .lock()
+            .unwrap()
+            .organizations
+            .insert(organization.id, organization.clone());
+        Ok(organization.id)
+    }
+
+    async fn get_organization(&self, id: Uuid) -> Result<Option<Organization>> {
+        Ok(self.state.lock().unwrap().organizations.get(&id).cloned())
+    }
+
+    async fn get_organization_by_slug(&self, slug: &str) -> Result<Option<Organization>> {
+        Ok(self
+            .state
+            .lock()
+            .unwrap()
+            .organizations
+            .values()
+            .find(|organization| organization.slug == slug)
+            .cloned())
+    }
+
+    async fn update_organization(&self, id: Uuid, organization: &Organization) -> Result<()> {
+        self.state
+            .lock()
+            .unwrap()
+            .organizations
+            .insert(id, organization.clone());
+        Ok(())
+    }
+
+    async fn delete_organization(&self, id: Uuid) -> Result<()> {
+        self.state.lock().unwrap().organizations.remove(&id);
+        Ok(())
+    }
+
+    async fn list_organizations(&self, filter: &OrganizationFilter) -> Result<Vec<Organization>> {
+        Ok(self
+            .state
+            .lock()
+            .unwrap()
+            .organizations
+            .values()
+            .filter(|organization| {
+                filter
+                    .slug
+                    .as_ref()
+                    .is_none_or(|slug| &organization.slug == slug)
+            })
+            .cloned()
+            .collect())
+    }
+}
+
+#[cfg(feature = "server")]
+#[async_trait]
+impl TeamStorage for MockOrganizationStorage {
+    async fn store_team(&self, team: &Team) -> Result<Uuid> {
+        self.state
+            .lock()
+            .unwrap()
+            .teams
+            .insert(team.id, team.clone());
+        Ok(team.id)
+    }
+
+    async fn get_team(&self, id: Uuid) -> Result<Option<Team>> {
+        Ok(self.state.lock().unwrap().teams.get(&id).cloned())
+    }
+
+    async fn update_team(&self, id: Uuid, team: &Team) -> Result<()> {
+        self.state.lock().unwrap().teams.insert(id, team.clone());
+        Ok(())
+    }
+
+    async fn delete_team(&self, id: Uuid) -> Result<()> {
+        self.state.lock().unwrap().teams.remove(&id);
+        Ok(())
+    }
+
+    async fn list_teams(&self, filter: &TeamFilter) -> Result<Vec<Team>> {
+        Ok(self
+            .state
+            .lock()
+            .unwrap()
+            .teams
+            .values()
+            .filter(|team| {
+                filter
+                    .organization_id
+                    .is_none_or(|id| team.organization_id == id)
+            })
+            .filter(|team| filter.name.as_ref().is_none_or(|name| &team.name == name))
+            .cloned()
+            .collect())
+    }
+
+    async fn add_team_member(&self, member: &TeamMember) -> Result<Uuid> {
+        self.state.lock().unwrap().members.push(member.clone());
+        Ok(member.id)
+    }
+
+    async fn remove_team_member(&self, team_id: Uuid, user_id: Uuid) -> Result<()> {
+        self.state
+            .lock()
+            .unwrap()
+            .members
+            .retain(|member| !(member.team_id == team_id && member.user_id == user_id));
+        Ok(())
+    }
+
+    async fn get_team_members(&self, team_id: Uuid) -> Result<Vec<TeamMember>> {
+        Ok(self.members(team_id))
+    }
+
+    async fn get_user_teams(&self, user_id: Uuid) -> Result<Vec<Team>> {
+        let state = self.state.lock().unwrap();
+        Ok(state
+            .members
+            .iter()
+            .filter(|member| member.user_id == user_id)
+            .filter_map(|member| state.teams.get(&member.team_id).cloned())
+            .collect())
+    }
+}
+
 #[cfg(all(test, feature = "server"))]
 mod tests {
     use super::*;
@@ -6,6 +6,8 @@
 #[cfg(feature = "server")]
 use crate::dsl::schema::DSLWorkflow;
 #[cfg(feature = "server")]
+use crate::dsl::Usage;
+#[cfg(feature = "server")]
 use crate::server::storage::{
     Checkpoint, CheckpointStorage, Execution, ExecutionFilter, ExecutionLog, ExecutionStatus,
     ExecutionStorage, Result, Schedule, ScheduleFilter, ScheduleRun, ScheduleStorage, StorageError,
@@ -278,6 +280,31 @@ impl ExecutionStorage for MockStorage {
         }
     }
 
+    async fn update_execution_progress(
+        &self,
+        id: Uuid,
+        completed_tasks: u32,
+        total_tasks: u32,
+        usage: &Usage,
+    ) -> Result<()> {
+        let mut state = self.state.lock().unwrap();
+
+        if state.should_fail_store {
+            return Err(StorageError::IoError(
+                "Update execution failure".to_string(),
+            ));
+        }
+
+        let execution = state
+            .executions
+            .get_mut(&id)
+            .ok_or_else(|| StorageError::NotFound(format!("Execution {} not found", id)))?;
+        execution.completed_tasks = completed_tasks;
+        execution.total_tasks = total_tasks;
+        execution.usage = *usage;
+        Ok(())
+    }
+
     async fn list_executions(&self, filter: &ExecutionFilter) -> Result<Vec<Execution>> {
         let state = self.state.lock().unwrap();
 
@@ -303,6 +330,23 @@ impl ExecutionStorage for MockStorage {
         Ok(executions)
     }
 
+    async fn usage_by_workflow(&self) -> Result<HashMap<Uuid, Usage>> {
+        let state = self.state.lock().unwrap();
+
+        if state.should_fail_get {
+            return Err(StorageError::IoError("Get usage failure".to_string()));
+        }
+
+        let mut usage: HashMap<Uuid, Usage> = HashMap::new();
+        for execution in state.executions.values() {
+            usage
+                .entry(execution.workflow_id)
+                .or_default()
+                .add(&execution.usage);
+        }
+        Ok(usage)
+    }
+
     async fn store_execution_log(&self, log: &ExecutionLog) -> Result<()> {
         let mut state = self.state.lock().unwrap();
         let logs = state.execution_logs.entry(log.execution_id).or_default();
@@ -323,6 +367,19 @@ impl ExecutionStorage for MockStorage {
             .unwrap_or_default())
     }
 
+    async fn get_execution_logs_since(
+        &self,
+        execution_id: Uuid,
+        skip: usize,
+    ) -> Result<Vec<ExecutionLog>> {
+        let state = self.state.lock().unwrap();
+        Ok(state
+            .execution_logs
+            .get(&execution_id)
+            .map(|logs| logs.iter().skip(skip).cloned().collect())
+            .unwrap_or_default())
+    }
+
     async fn delete_execution(&self, id: Uuid) -> Result<()> {
         let mut state = self.state.lock().unwrap();
 
@@ -541,6 +598,8 @@ mod tests {
             imports: Default::default(),
             notifications: None,
             limits: None,
+            max_concurrency: None,
+            providers: HashMap::new(),
         };
 
         let metadata = WorkflowMetadata {
@@ -574,6 +633,9 @@ mod tests {
             error: None,
             retry_count: 0,
             parent_execution_id: None,
+            completed_tasks: 0,
+            total_tasks: 0,
+            usage: Default::default(),
         }
     }
 
@@ -4,10 +4,18 @@
 //! view routing, and modal system following hexagonal architecture.
 
 use super::events::{AppEvent, EventHandler, ExecutionUpdate, KeyEvent};
+use super::runner::{ExecutionHandle, StopReason};
 use super::state::{AppState, ConfirmAction, InputAction, Modal, ViewMode};
 use super::theme::Theme;
 use super::ui::WorkflowListView;
-use crate::dsl::{parse_workflow_file, DSLExecutor};
+use super::views::execution_monitor::{
+    ExecutionMonitorState, ExecutionStatus, LogLevel, MonitorPanel,
+};
+use super::views::state_browser::StateBrowserState;
+use crate::dsl::{
+    parse_workflow, parse_workflow_file, validate_workflow, DSLWorkflow, StatePersistence,
+    WorkflowStatus,
+};
 use crate::error::Result;
 use crossterm::event::KeyCode;
 use crossterm::terminal::{
@@ -19,7 +27,6 @@ use ratatui::Terminal;
 use std::io;
 use std::path::PathBuf;
 use std::time::Duration;
-use tokio::sync::mpsc;
 
 /// Configuration for TUI application
 #[derive(Debug, Clone)]
@@ -78,13 +85,8 @@ pub struct App {
     /// Theme
     theme: Theme,
 
-    /// Workflow executor (for running workflows)
-    #[allow(dead_code)]
-    executor: Option<DSLExecutor>,
-
-    /// Execution update channel
-    #[allow(dead_code)]
-    execution_tx: Option<mpsc::UnboundedSender<AppEvent>>,
+    /// Workflow running in the background
+    execution: Option<ExecutionHandle>,
 }
 
 impl App {
@@ -114,14 +116,18 @@ impl App {
             _ => Theme::default(), // dark
         };
 
+        let mut state = AppState::new();
+        if let Some(state_dir) = &config.state_dir {
+            state.state_browser = StateBrowserState::new(state_dir.clone());
+        }
+
         Ok(Self {
             config,
-            state: AppState::new(),
+            state,
             terminal,
             event_handler,
             theme,
-            executor: None,
-            execution_tx: None,
+            execution: None,
         })
     }
 
@@ -181,7 +187,12 @@ impl App {
                     );
                 }
                 ViewMode::ExecutionMonitor => {
-                    Self::render_execution_monitor_static(frame, area);
+                    Self::render_execution_monitor_static(
+                        frame,
+                        area,
+                        self.state.execution_monitor.as_ref(),
+                        &self.theme,
+                    );
                 }
                 ViewMode::StateBrowser => {
                     Self::render_state_browser_static(
@@ -338,8 +349,8 @@ impl App {
             ConfirmAction::DiscardChanges => {
                 self.discard_changes().await?;
             }
-            ConfirmAction::ExecuteWorkflow(_path) => {
-                self.execute_selected_workflow().await?;
+            ConfirmAction::ExecuteWorkflow(path) => {
+                self.execute_workflow_file(path).await?;
             }
         }
 
@@ -482,6 +493,10 @@ impl App {
                 self.state.viewer_state.scroll_to_bottom(max_lines);
             }
 
+            KeyCode::Char('r') if key.is_ctrl() => {
+                self.run_current_workflow().await?;
+            }
+
             KeyCode::Char('e') => {
                 // Switch to editor mode - load current workflow into editor
                 if self.state.current_workflow.is_some() {
@@ -951,6 +966,49 @@ impl App {
                 self.confirm_stop_execution();
             }
 
+            KeyCode::Char('p') if key.is_ctrl() => {
+                self.toggle_pause_execution();
+            }
+
+            KeyCode::Char('c') if key.is_ctrl() => {
+                self.cancel_execution();
+            }
+
+            KeyCode::Char('s') if !self.is_executing() => {
+                // Open the state saved by the finished run
+                self.browse_execution_state();
+            }
+
+            KeyCode::Tab => {
+                if let Some(monitor) = &mut self.state.execution_monitor {
+                    monitor.next_panel();
+                }
+            }
+
+            KeyCode::Up | KeyCode::Char('k') => {
+                if let Some(monitor) = &mut self.state.execution_monitor {
+                    match monitor.focus {
+                        MonitorPanel::LogOutput => monitor.scroll_logs_up(),
+                        _ => monitor.scroll_tasks_up(),
+                    }
+                }
+            }
+
+            KeyCode::Down | KeyCode::Char('j') => {
+                if let Some(monitor) = &mut self.state.execution_monitor {
+                    match monitor.focus {
+                        MonitorPanel::LogOutput => monitor.scroll_logs_down(),
+                        _ => monitor.scroll_tasks_down(),
+                    }
+                }
+            }
+
+            KeyCode::End => {
+                if let Some(monitor) = &mut self.state.execution_monitor {
+                    monitor.toggle_auto_scroll();
+                }
+            }
+
             _ => {}
         }
 
@@ -1004,8 +1062,8 @@ impl App {
                 // Resume selected workflow
                 if self.state.state_browser.can_resume_selected() {
                     if let Some(entry) = self.state.state_browser.selected_state() {
-                        // TODO: Implement resume workflow logic
-                        println!("Resuming workflow: {}", entry.workflow_name);
+                        let workflow_name = entry.workflow_name.clone();
+                        self.resume_workflow(&workflow_name).await?;
                     }
                 }
             }
@@ -1097,28 +1155,28 @@ impl App {
 
     /// Handle execution update events
     async fn handle_execution_update(&mut self, update: ExecutionUpdate) -> Result<()> {
-        if let Some(exec_state) = &mut self.state.execution_state {
-            match update {
-                ExecutionUpdate::TaskStarted(task) => {
-                    exec_state.current_task = Some(task);
-                }
-                ExecutionUpdate::TaskCompleted(task) => {
-                    exec_state.completed_tasks.push(task);
-                    exec_state.current_task = None;
-                }
-                ExecutionUpdate::TaskFailed { task, error } => {
-                    exec_state.failed_tasks.push(task);
-                    exec_state.current_task = None;
-                    self.show_error("Task Failed", &error);
-                }
-                ExecutionUpdate::LogMessage { level, message } => {
-                    // Add to logs (implementation in state)
-                    let _ = (level, message); // Suppress warning for now
-                }
-                ExecutionUpdate::StatusChanged(status) => {
-                    let _ = status; // Suppress warning for now
-                }
-            }
+        let finished = matches!(update, ExecutionUpdate::Finished { .. });
+
+        // Tasks may print to stdout while they run; redraw the whole screen
+        // whenever one starts or ends
+        if matches!(
+            update,
+            ExecutionUpdate::TaskStarted(_)
+                | ExecutionUpdate::TaskCompleted(_)
+                | ExecutionUpdate::TaskFailed { .. }
+                | ExecutionUpdate::Finished { .. }
+        ) {
+            self.terminal.clear()?;
+        }
+
+        if let Some(monitor) = &mut self.state.execution_monitor {
+            monitor.apply_update(update);
+        }
+
+        if finished {
+            self.execution = None;
+            // The run's saved state is now listed in the state browser
+            let _ = self.state.state_browser.load_states();
         }
 
         Ok(())
@@ -1252,18 +1310,195 @@ impl App {
     }
 
     /// Run current workflow
+    ///
+    /// In the editor, the workflow being edited is run, including unsaved
+    /// changes.
     async fn run_current_workflow(&mut self) -> Result<()> {
-        if let Some(workflow) = &self.state.current_workflow {
-            self.state.view_mode = ViewMode::ExecutionMonitor;
-            // TODO: Start execution
-            let _ = workflow; // Suppress warning
+        let workflow = if self.state.view_mode == ViewMode::Editor {
+            match parse_workflow(&self.state.editor_state.content) {
+                Ok(workflow) => workflow,
+                Err(e) => {
+                    self.show_error("Invalid Workflow", &e.to_string());
+                    return Ok(());
+                }
+            }
+        } else if let Some(workflow) = &self.state.current_workflow {
+            workflow.clone()
+        } else {
+            return Ok(());
+        };
+
+        if let Err(e) = validate_workflow(&workflow) {
+            self.show_error("Invalid Workflow", &e.to_string());
+            return Ok(());
         }
+
+        let monitor = ExecutionMonitorState::new(workflow.clone());
+        self.start_execution(workflow, monitor, false);
         Ok(())
     }
 
+    /// Resume a paused workflow from its saved state
+    async fn resume_workflow(&mut self, workflow_name: &str) -> Result<()> {
+        let Some(workflow) = self.find_workflow(workflow_name) else {
+            self.show_error(
+                "Resume Failed",
+                &format!("No workflow file defines '{}'", workflow_name),
+            );
+            return Ok(());
+        };
+
+        let saved_state = StatePersistence::new(&self.state.state_browser.state_dir)
+            .and_then(|persistence| persistence.load_state(workflow_name));
+        match saved_state {
+            Ok(saved_state) => {
+                let monitor =
+                    ExecutionMonitorState::from_workflow_state(workflow.clone(), &saved_state);
+                self.start_execution(workflow, monitor, true);
+            }
+            Err(e) => self.show_error("Resume Failed", &e.to_string()),
+        }
+        Ok(())
+    }
+
+    /// Find the workflow with the given name, preferring the one loaded
+    fn find_workflow(&self, workflow_name: &str) -> Option<DSLWorkflow> {
+        if let Some(workflow) = &self.state.current_workflow {
+            if workflow.name == workflow_name {
+                return Some(workflow.clone());
+            }
+        }
+
+        self.state
+            .workflows
+            .iter()
+            .filter_map(|entry| parse_workflow_file(&entry.path).ok())
+            .find(|workflow| workflow.name == workflow_name)
+    }
+
+    /// Check whether a workflow is running in the background
+    fn is_executing(&self) -> bool {
+        self.execution
+            .as_ref()
+            .is_some_and(|execution| !execution.is_finished())
+    }
+
+    /// Run a workflow in the background and show it in the execution monitor
+    fn start_execution(
+        &mut self,
+        workflow: DSLWorkflow,
+        monitor: ExecutionMonitorState,
+        resume: bool,
+    ) {
+        if self.config.readonly {
+            self.show_error(
+                "Read-only Mode",
+                "Workflows cannot be executed in read-only mode",
+            );
+            return;
+        }
+        if let Some(execution) = self.execution.as_ref().filter(|_| self.is_executing()) {
+            let message = format!("Workflow '{}' is still running", execution.workflow_name);
+            self.show_error("Execution Running", &message);
+            return;
+        }
+
+        self.state.execution_monitor = Some(monitor);
+        self.state.view_mode = ViewMode::ExecutionMonitor;
+
+        match ExecutionHandle::spawn(
+            workflow,
+            &self.state.state_browser.state_dir,
+            resume,
+            self.event_handler.sender(),
+        ) {
+            Ok(execution) => self.execution = Some(execution),
+            Err(e) => {
+                if let Some(monitor) = &mut self.state.execution_monitor {
+                    monitor.finish(None, Some(e.to_string()));
+                }
+                self.show_error("Execution Failed", &e.to_string());
+            }
+        }
+    }
+
+    /// Pause the running workflow, or resume the paused one
+    fn toggle_pause_execution(&mut self) {
+        let executing = self.is_executing();
+        let Some(monitor) = &mut self.state.execution_monitor else {
+            return;
+        };
+
+        match monitor.status {
+            ExecutionStatus::Running => {
+                if let Some(execution) = &mut self.execution {
+                    execution.stop(StopReason::Pause);
+                    monitor.pause();
+                }
+            }
+            // Resume once the paused run has saved its state
+            ExecutionStatus::Paused if !executing => {
+                let Some(mut monitor) = self.state.execution_monitor.take() else {
+                    return;
+                };
+                monitor.resume();
+                monitor.add_log(
+                    LogLevel::Info,
+                    "Resuming from checkpoint".to_string(),
+                    None,
+                    None,
+                );
+                let workflow = monitor.workflow.clone();
+                self.start_execution(workflow, monitor, true);
+            }
+            _ => {}
+        }
+    }
+
+    /// Cancel the running or paused workflow
+    fn cancel_execution(&mut self) {
+        if let Some(monitor) = &mut self.state.execution_monitor {
+            if matches!(
+                monitor.status,
+                ExecutionStatus::Running | ExecutionStatus::Paused
+            ) {
+                match &mut self.execution {
+                    Some(execution) => execution.stop(StopReason::Cancel),
+                    // A run that already stopped paused left a checkpoint
+                    // that must no longer be resumed
+                    None => {
+                        let state_dir = &self.state.state_browser.state_dir;
+                        if let Ok(persistence) = StatePersistence::new(state_dir) {
+                            if let Ok(mut state) = persistence.load_state(&monitor.workflow.name) {
+                                if state.status == WorkflowStatus::Paused {
+                                    state.mark_cancelled();
+                                    let _ = persistence.save_state(&state);
+                                }
+                            }
+                        }
+                        let _ = self.state.state_browser.load_states();
+                    }
+                }
+                monitor.cancel();
+            }
+        }
+    }
+
+    /// Open the state browser on the state saved by the monitored run
+    fn browse_execution_state(&mut self) {
+        if let Some(monitor) = &self.state.execution_monitor {
+            let workflow_name = monitor.workflow.name.clone();
+            let _ = self.state.state_browser.load_states();
+            if self.state.state_browser.select_state(&workflow_name) {
+                let _ = self.state.state_browser.load_details();
+            }
+            self.state.view_mode = ViewMode::StateBrowser;
+        }
+    }
+
     /// Stop workflow execution
     async fn stop_execution(&mut self) -> Result<()> {
-        // TODO: Implement execution stopping
+        self.cancel_execution();
         self.state.execution_state = None;
         self.state.view_mode = ViewMode::WorkflowList;
         Ok(())
@@ -1367,10 +1602,12 @@ tasks:
         Ok(())
     }
 
-    /// Execute selected workflow
-    async fn execute_selected_workflow(&mut self) -> Result<()> {
-        // TODO: Implement workflow execution
-        Ok(())
+    /// Execute a workflow file
+    async fn execute_workflow_file(&mut self, path: PathBuf) -> Result<()> {
+        let workflow = parse_workflow_file(&path)?;
+        self.state.current_workflow = Some(workflow);
+        self.state.current_workflow_path = Some(path);
+        self.run_current_workflow().await
     }
 
     /// Generate workflow from description
@@ -1510,8 +1747,17 @@ tasks:
         generator::render(frame, area, state, theme);
     }
 
-    fn render_execution_monitor_static(_frame: &mut ratatui::Frame, _area: ratatui::layout::Rect) {
-        // TODO: Implement in ui module
+    fn render_execution_monitor_static(
+        frame: &mut ratatui::Frame,
+        area: ratatui::layout::Rect,
+        monitor: Option<&ExecutionMonitorState>,
+        theme: &Theme,
+    ) {
+        use super::views::execution_monitor;
+
+        if let Some(monitor) = monitor {
+            execution_monitor::render(frame, area, monitor, theme);
+        }
     }
 
     fn render_help_static(
@@ -2,6 +2,8 @@
 //!
 //! Manages keyboard input, terminal events, and custom application events.
 
+use crate::dsl::events::{ExecutionEvent, RetryReason};
+use crate::dsl::state::{Usage, WorkflowState};
 use crossterm::event::{self, Event as CrosstermEvent, KeyCode, KeyModifiers};
 use std::time::Duration;
 use tokio::sync::mpsc;
@@ -60,11 +62,192 @@ impl KeyEvent {
 /// Execution update event
 #[derive(Debug, Clone)]
 pub enum ExecutionUpdate {
+    /// A workflow started, with its tasks in execution order
+    WorkflowStarted {
+        workflow: String,
+        tasks: Vec<String>,
+    },
     TaskStarted(String),
     TaskCompleted(String),
-    TaskFailed { task: String, error: String },
-    LogMessage { level: String, message: String },
+    TaskFailed {
+        task: String,
+        error: String,
+    },
+    TaskSkipped {
+        task: String,
+        reason: String,
+    },
+    /// Tokens and cost of one agent or LLM call made by a task
+    UsageRecorded {
+        task: String,
+        usage: Usage,
+    },
+    LogMessage {
+        level: String,
+        message: String,
+    },
     StatusChanged(String),
+    /// The execution ended, with the final workflow state
+    Finished {
+        state: Option<Box<WorkflowState>>,
+        error: Option<String>,
+    },
+}
+
+impl ExecutionUpdate {
+    /// Update shown by the execution monitor for an executor event
+    ///
+    /// Returns `None` for events the monitor does not display, such as agent
+    /// messages without text.
+    pub fn from_event(event: &ExecutionEvent) -> Option<Self> {
+        let log = |level: &str, message: String| ExecutionUpdate::LogMessage {
+            level: level.to_string(),
+            message,
+        };
+
+        Some(match event {
+            ExecutionEvent::WorkflowStarted { workflow, tasks } => Self::WorkflowStarted {
+                workflow: workflow.clone(),
+                tasks: tasks.clone(),
+            },
+            ExecutionEvent::WorkflowFinished {
+                workflow,
+                success: true,
+                ..
+            } => log("info", format!("Workflow '{}' completed", workflow)),
+            ExecutionEvent::WorkflowFinished {
+                workflow, error, ..
+            } => log(
+                "error",
+                format!(
+                    "Workflow '{}' failed: {}",
+                    workflow,
+                    error.as_deref().unwrap_or("unknown error")
+                ),
+            ),
+            ExecutionEvent::TaskStarted { task_id, .. } => Self::TaskStarted(task_id.clone()),
+            ExecutionEvent::TaskRetrying {
+                task_id,
+                attempt,
+                reason,
+                message,
+                ..
+            } => {
+                let reason = match reason {
+                    RetryReason::Error => "error",
+                    RetryReason::DefinitionOfDone => "definition of done",
+                    RetryReason::OutputSchema => "output schema",
+                };
+                log(
+                    "warn",
+                    format!(
+                        "Task '{}' retry {} ({}): {}",
+                        task_id, attempt, reason, message
+                    ),
+                )
+            }
+            ExecutionEvent::TaskFallback {
+                task_id,
+                agent,
+                error,
+            } => log(
+                "warn",
+                format!(
+                    "Task '{}' falling back to agent '{}': {}",
+                    task_id, agent, error
+                ),
+            ),
+            ExecutionEvent::TaskSucceeded { task_id, .. } => Self::TaskCompleted(task_id.clone()),
+            ExecutionEvent::TaskFailed { task_id, error } => Self::TaskFailed {
+                task: task_id.clone(),
+                error: error.clone(),
+            },
+            ExecutionEvent::TaskSkipped { task_id, reason } => Self::TaskSkipped {
+                task: task_id.clone(),
+                reason: reason.clone(),
+            },
+            ExecutionEvent::LoopIterationStarted {
+                task_id, iteration, ..
+            } => log(
+                "debug",
+                format!("Task '{}' iteration {} started", task_id, iteration + 1),
+            ),
+            ExecutionEvent::LoopIterationFinished {
+                task_id,
+                iteration,
+                error: None,
+            } => log(
+                "debug",
+                format!("Task '{}' iteration {} completed", task_id, iteration + 1),
+            ),
+            ExecutionEvent::LoopIterationFinished {
+                task_id,
+                iteration,
+                error: Some(error),
+            } => log(
+                "warn",
+                format!(
+                    "Task '{}' iteration {} failed: {}",
+                    task_id,
+                    iteration + 1,
+                    error
+                ),
+            ),
+            ExecutionEvent::AgentMessage { agent, message, .. } => {
+                log("info", format!("[{}] {}", agent, message.display_text()?))
+            }
+            ExecutionEvent::DefinitionOfDoneChecked {
+                task_id, met: true, ..
+            } => log(
+                "info",
+                format!("Task '{}' met its definition of done", task_id),
+            ),
+            ExecutionEvent::DefinitionOfDoneChecked {
+                task_id, feedback, ..
+            } => log(
+                "warn",
+                format!(
+                    "Task '{}' did not meet its definition of done: {}",
+                    task_id,
+                    feedback.as_deref().unwrap_or("")
+                ),
+            ),
+            ExecutionEvent::UsageRecorded { task_id, usage, .. } => Self::UsageRecorded {
+                task: task_id.clone(),
+                usage: *usage,
+            },
+            ExecutionEvent::ToolDenied {
+                task_id,
+                agent,
+                tool,
+                reason,
+            } => log(
+                "warn",
+                format!(
+                    "Task '{}': denied {} for agent '{}': {}",
+                    task_id, tool, agent, reason
+                ),
+            ),
+            ExecutionEvent::NotificationSent {
+                message,
+                error: None,
+                ..
+            } => log("info", format!("Notification sent: {}", message)),
+            ExecutionEvent::NotificationSent {
+                message,
+                error: Some(error),
+                ..
+            } => log(
+                "warn",
+                format!("Notification '{}' failed: {}", message, error),
+            ),
+            ExecutionEvent::Progress { message, .. } => log("info", message.trim().to_string()),
+            ExecutionEvent::Warning { message, .. } => log("warn", message.clone()),
+            // Output is shown with the task's result; streamed responses
+            // would fill the log with fragments
+            ExecutionEvent::TaskOutput { .. } => return None,
+        })
+    }
 }
 
 /// Event handler that polls terminal events and forwards them
@@ -203,6 +386,45 @@ mod tests {
         assert_eq!(updates.len(), 5);
     }
 
+    #[test]
+    fn test_execution_update_from_event() {
+        let update = ExecutionUpdate::from_event(&ExecutionEvent::TaskSucceeded {
+            task_id: "build".to_string(),
+            duration_ms: 10,
+        });
+        assert!(matches!(update, Some(ExecutionUpdate::TaskCompleted(task)) if task == "build"));
+
+        let update = ExecutionUpdate::from_event(&ExecutionEvent::TaskFallback {
+            task_id: "build".to_string(),
+            agent: "backup".to_string(),
+            error: "timeout".to_string(),
+        });
+        match update {
+            Some(ExecutionUpdate::LogMessage { level, message }) => {
+                assert_eq!(level, "warn");
+                assert_eq!(
+                    message,
+                    "Task 'build' falling back to agent 'backup': timeout"
+                );
+            }
+            other => panic!("Expected LogMessage, got {:?}", other),
+        }
+
+        let usage = Usage {
+            input_tokens: 10,
+            output_tokens: 5,
+            ..Default::default()
+        };
+        let update = ExecutionUpdate::from_event(&ExecutionEvent::UsageRecorded {
+            task_id: "build".to_string(),
+            agent: None,
+            usage,
+        });
+        assert!(
+            matches!(update, Some(ExecutionUpdate::UsageRecorded { usage, .. }) if usage.input_tokens == 10)
+        );
+    }
+
     #[test]
     fn test_app_event_variants() {
         let events = [
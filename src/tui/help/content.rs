@@ -372,12 +372,13 @@ The monitor displays:
 
 | Key | Action |
 |-----|--------|
-| `Space` | Pause/Resume execution |
-| `s` | Stop execution (with confirmation) |
-| `l` | Toggle log view |
-| `t` | Toggle task graph view |
-| `f` | Follow mode (auto-scroll logs) |
-| `↑/↓` | Scroll logs |
+| `Ctrl+P` | Pause/Resume execution |
+| `Ctrl+C` | Cancel execution |
+| `Ctrl+S` | Stop execution (with confirmation) |
+| `Tab` | Switch between tasks and logs |
+| `↑/↓` | Scroll tasks or logs |
+| `End` | Follow mode (auto-scroll logs) |
+| `s` | Open the finished run in the state browser |
 
 ## Log Filtering
 
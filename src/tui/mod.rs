@@ -8,6 +8,7 @@
 //!
 //! - `app.rs`: Core application state, event loop, view router, modal system
 //! - `events.rs`: Event handling and input processing
+//! - `runner.rs`: Background workflow execution feeding the execution monitor
 //! - `ui/`: View components (workflow list, editor, execution monitor, help)
 //! - `state.rs`: Application state management
 //! - `theme.rs`: Color schemes and styling
@@ -29,6 +30,7 @@
 pub mod app;
 pub mod events;
 pub mod help;
+pub mod runner;
 pub mod state;
 pub mod theme;
 pub mod ui;
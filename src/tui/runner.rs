@@ -0,0 +1,143 @@
+//! Background workflow execution for the TUI
+//!
+//! Runs a `DSLExecutor` on a tokio task and reports its progress to the event
+//! loop as `ExecutionUpdate`s. State persistence is always enabled, so a
+//! paused run can be resumed from its checkpoint and every run can be opened
+//! in the state browser.
+
+use super::events::{AppEvent, ExecutionUpdate};
+use crate::dsl::{DSLExecutor, DSLWorkflow, StatePersistence, WorkflowStatus};
+use crate::error::Result;
+use std::path::Path;
+use std::sync::{Arc, OnceLock};
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use tokio_util::sync::CancellationToken;
+
+/// Why the user stopped a running execution
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StopReason {
+    /// Stopped to be resumed later
+    Pause,
+    /// Stopped for good
+    Cancel,
+}
+
+/// Handle of a workflow running in the background
+#[derive(Debug)]
+pub struct ExecutionHandle {
+    /// Name of the running workflow
+    pub workflow_name: String,
+
+    /// Set once the user stopped the execution
+    pub stop_reason: Option<StopReason>,
+
+    /// The stop reason, as seen by the background task
+    reason: Arc<OnceLock<StopReason>>,
+    cancellation: CancellationToken,
+    task: JoinHandle<()>,
+}
+
+impl ExecutionHandle {
+    /// Start executing a workflow in the background
+    ///
+    /// Every execution event is sent to `tx` as an `ExecutionUpdate`, followed
+    /// by `ExecutionUpdate::Finished` with the final workflow state. With
+    /// `resume`, the run continues from the state saved in `state_dir`. A run
+    /// stopped with `StopReason::Cancel` is saved as cancelled rather than
+    /// paused.
+    pub fn spawn(
+        workflow: DSLWorkflow,
+        state_dir: &Path,
+        resume: bool,
+        tx: mpsc::UnboundedSender<AppEvent>,
+    ) -> Result<Self> {
+        let workflow_name = workflow.name.clone();
+        let mut executor = DSLExecutor::new(workflow)?;
+        // The executor reports all its output as events, which the monitor
+        // shows; printing them would draw over the TUI
+        executor.set_console_output(false);
+        executor.enable_state_persistence(Some(&state_dir.to_string_lossy()))?;
+        // Subscribe before resuming so its progress reaches the monitor too
+        let mut events = executor.events().channel();
+        if resume {
+            executor.try_resume()?;
+        }
+
+        let cancellation = executor.cancellation_token();
+        let reason = Arc::new(OnceLock::new());
+        let stopped = Arc::clone(&reason);
+        let state_dir = state_dir.to_path_buf();
+
+        let task = tokio::spawn(async move {
+            let forward = |event| {
+                if let Some(update) = ExecutionUpdate::from_event(&event) {
+                    let _ = tx.send(AppEvent::ExecutionUpdate(update));
+                }
+            };
+
+            let result = {
+                let run = async {
+                    executor.initialize().await?;
+                    executor.execute().await
+                };
+                tokio::pin!(run);
+                loop {
+                    tokio::select! {
+                        Some(event) = events.recv() => forward(event),
+                        result = &mut run => break result,
+                    }
+                }
+            };
+            // Deliver the events emitted last before reporting the end
+            while let Ok(event) = events.try_recv() {
+                forward(event);
+            }
+
+            // The executor pauses every stopped run; a cancelled one is not
+            // to be resumed
+            let mut state = executor.get_state().cloned();
+            if let Some(state) = &mut state {
+                if stopped.get() == Some(&StopReason::Cancel)
+                    && state.status == WorkflowStatus::Paused
+                {
+                    state.mark_cancelled();
+                    if let Ok(persistence) = StatePersistence::new(&state_dir) {
+                        let _ = persistence.save_state(state);
+                    }
+                }
+            }
+
+            let _ = tx.send(AppEvent::ExecutionUpdate(ExecutionUpdate::Finished {
+                state: state.map(Box::new),
+                error: result.err().map(|e| e.to_string()),
+            }));
+        });
+
+        Ok(Self {
+            workflow_name,
+            stop_reason: None,
+            reason,
+            cancellation,
+            task,
+        })
+    }
+
+    /// Stop the execution
+    ///
+    /// Running tasks are interrupted. A paused run is saved with its
+    /// checkpoint so it can be resumed from the state browser; a cancelled
+    /// one is saved as cancelled. The first reason given wins.
+    pub fn stop(&mut self, reason: StopReason) {
+        if self.stop_reason.is_none() {
+            self.stop_reason = Some(reason);
+            let _ = self.reason.set(reason);
+        }
+        self.cancellation.cancel();
+    }
+
+    /// Check whether the background task has ended
+    pub fn is_finished(&self) -> bool {
+        self.task.is_finished()
+    }
+}
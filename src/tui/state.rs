@@ -6,6 +6,7 @@
 use crate::dsl::DSLWorkflow;
 use crate::tui::help::{HelpContext, HelpViewState};
 use crate::tui::views::editor::EditorMode;
+use crate::tui::views::execution_monitor::ExecutionMonitorState;
 use crate::tui::views::generator::GeneratorState;
 use crate::tui::views::state_browser::StateBrowserState;
 use std::path::PathBuf;
@@ -34,6 +35,9 @@ pub struct AppState {
     /// Workflow execution state
     pub execution_state: Option<ExecutionState>,
 
+    /// Execution monitor of the last workflow run from the TUI
+    pub execution_monitor: Option<ExecutionMonitorState>,
+
     /// Search query (for workflow filtering)
     pub search_query: String,
 
@@ -70,6 +74,7 @@ impl AppState {
             current_workflow: None,
             current_workflow_path: None,
             execution_state: None,
+            execution_monitor: None,
             search_query: String::new(),
             viewer_state: ViewerState::new(),
             editor_state: EditorState::new(),
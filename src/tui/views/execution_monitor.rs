@@ -36,8 +36,9 @@
 //! ```
 
 use crate::dsl::schema::DSLWorkflow;
-use crate::dsl::state::{WorkflowState, WorkflowStatus as DslWorkflowStatus};
+use crate::dsl::state::{Usage, WorkflowState, WorkflowStatus as DslWorkflowStatus};
 use crate::dsl::task_graph::TaskStatus as DslTaskStatus;
+use crate::tui::events::ExecutionUpdate;
 use crate::tui::theme::Theme;
 use ratatui::{
     layout::{Constraint, Direction, Layout, Rect},
@@ -191,6 +192,17 @@ pub struct TokenUsage {
     pub cache_write_tokens: u64,
 }
 
+impl From<&Usage> for TokenUsage {
+    fn from(usage: &Usage) -> Self {
+        Self {
+            input_tokens: usage.input_tokens,
+            output_tokens: usage.output_tokens,
+            cache_read_tokens: usage.cache_read_input_tokens,
+            cache_write_tokens: usage.cache_creation_input_tokens,
+        }
+    }
+}
+
 /// Log entry
 #[derive(Debug, Clone)]
 pub struct LogEntry {
@@ -342,6 +354,7 @@ impl ExecutionMonitorState {
             DslWorkflowStatus::Completed => ExecutionStatus::Completed,
             DslWorkflowStatus::Failed => ExecutionStatus::Failed,
             DslWorkflowStatus::Paused => ExecutionStatus::Paused,
+            DslWorkflowStatus::Cancelled => ExecutionStatus::Cancelled,
         };
 
         // Calculate statistics from state
@@ -365,7 +378,7 @@ impl ExecutionMonitorState {
             estimated_time_remaining: None,
         };
 
-        Self {
+        let mut monitor = Self {
             workflow,
             status: monitor_status,
             tasks,
@@ -382,7 +395,9 @@ impl ExecutionMonitorState {
             show_task_details: false,
             stats,
             focus: MonitorPanel::TaskList,
-        }
+        };
+        monitor.apply_state_usage(state);
+        monitor
     }
 
     /// Sync monitor state with WorkflowState
@@ -415,6 +430,7 @@ impl ExecutionMonitorState {
             DslWorkflowStatus::Completed => ExecutionStatus::Completed,
             DslWorkflowStatus::Failed => ExecutionStatus::Failed,
             DslWorkflowStatus::Paused => ExecutionStatus::Paused,
+            DslWorkflowStatus::Cancelled => ExecutionStatus::Cancelled,
         };
 
         self.end_time = state.ended_at;
@@ -429,6 +445,203 @@ impl ExecutionMonitorState {
             .filter(|t| t.status == TaskStatus::Running)
             .count();
         self.recalculate_avg_duration();
+        self.apply_state_usage(state);
+    }
+
+    /// Take the tokens and cost of each task, and the totals, from WorkflowState
+    fn apply_state_usage(&mut self, state: &WorkflowState) {
+        for (task_id, usage) in &state.usage_by_task() {
+            if let Some(task) = self.tasks.get_mut(task_id) {
+                task.cost = Some(usage.cost_usd);
+                task.tokens = Some(TokenUsage::from(usage));
+            }
+        }
+
+        let total = state.total_usage();
+        self.stats.total_cost = total.cost_usd;
+        self.stats.total_input_tokens = total.input_tokens;
+        self.stats.total_output_tokens = total.output_tokens;
+    }
+
+    /// Track the tasks of a started workflow, in execution order
+    ///
+    /// Tasks without an entry are added as pending, so the monitor lists
+    /// every task before it starts.
+    pub fn track_tasks(&mut self, tasks: &[String]) {
+        for task_id in tasks {
+            self.task_entry(task_id);
+        }
+
+        let mut order = tasks.to_vec();
+        order.extend(
+            self.task_order
+                .iter()
+                .filter(|task_id| !tasks.contains(task_id))
+                .cloned(),
+        );
+        self.task_order = order;
+        self.stats.total_tasks = self.task_order.len();
+    }
+
+    /// Entry of a task, added as pending when the monitor does not know it yet
+    fn task_entry(&mut self, task_id: &str) -> &mut TaskExecutionState {
+        if !self.tasks.contains_key(task_id) {
+            let spec = self.workflow.tasks.get(task_id);
+            self.tasks.insert(
+                task_id.to_string(),
+                TaskExecutionState {
+                    task_id: task_id.to_string(),
+                    description: spec
+                        .map(|t| t.description.clone())
+                        .unwrap_or_else(|| task_id.to_string()),
+                    status: TaskStatus::Pending,
+                    agent: spec.and_then(|t| t.agent.clone()),
+                    start_time: None,
+                    end_time: None,
+                    dependencies: spec.map(|t| t.depends_on.clone()).unwrap_or_default(),
+                    progress: 0,
+                    error: None,
+                    output_path: spec.and_then(|t| t.output.clone()),
+                    cost: None,
+                    tokens: None,
+                },
+            );
+            self.stats.pending_tasks += 1;
+            if !self.task_order.iter().any(|id| id == task_id) {
+                self.task_order.push(task_id.to_string());
+                self.stats.total_tasks = self.stats.total_tasks.max(self.task_order.len());
+            }
+        }
+        self.tasks
+            .get_mut(task_id)
+            .expect("task entry was just added")
+    }
+
+    /// Add the tokens and cost of one call made by a task
+    pub fn record_usage(&mut self, task_id: &str, usage: &Usage) {
+        if let Some(task) = self.tasks.get_mut(task_id) {
+            task.cost = Some(task.cost.unwrap_or(0.0) + usage.cost_usd);
+            let tokens = task.tokens.get_or_insert(TokenUsage {
+                input_tokens: 0,
+                output_tokens: 0,
+                cache_read_tokens: 0,
+                cache_write_tokens: 0,
+            });
+            tokens.input_tokens += usage.input_tokens;
+            tokens.output_tokens += usage.output_tokens;
+            tokens.cache_read_tokens += usage.cache_read_input_tokens;
+            tokens.cache_write_tokens += usage.cache_creation_input_tokens;
+        }
+
+        self.stats.total_cost += usage.cost_usd;
+        self.stats.total_input_tokens += usage.input_tokens;
+        self.stats.total_output_tokens += usage.output_tokens;
+    }
+
+    /// Apply an update reported by the running executor
+    pub fn apply_update(&mut self, update: ExecutionUpdate) {
+        match update {
+            ExecutionUpdate::WorkflowStarted { workflow, tasks } => {
+                // Subflows report through the same events; only the monitored
+                // workflow's tasks are listed up front
+                if workflow == self.workflow.name {
+                    self.track_tasks(&tasks);
+                }
+                self.add_log(
+                    LogLevel::Info,
+                    format!("Workflow '{}' started", workflow),
+                    None,
+                    None,
+                );
+            }
+            ExecutionUpdate::TaskStarted(task) => {
+                self.update_task(task.clone(), TaskStatus::Running);
+                self.add_task_log(LogLevel::Info, format!("Task '{}' started", task), task);
+            }
+            ExecutionUpdate::TaskCompleted(task) => {
+                self.update_task(task.clone(), TaskStatus::Completed);
+                self.task_entry(&task).progress = 100;
+                self.add_task_log(LogLevel::Info, format!("Task '{}' completed", task), task);
+            }
+            ExecutionUpdate::TaskFailed { task, error } => {
+                self.update_task(task.clone(), TaskStatus::Failed);
+                self.task_entry(&task).error = Some(error.clone());
+                self.add_task_log(
+                    LogLevel::Error,
+                    format!("Task '{}' failed: {}", task, error),
+                    task,
+                );
+            }
+            ExecutionUpdate::TaskSkipped { task, reason } => {
+                self.update_task(task.clone(), TaskStatus::Skipped);
+                self.add_task_log(
+                    LogLevel::Info,
+                    format!("Task '{}' skipped: {}", task, reason),
+                    task,
+                );
+            }
+            ExecutionUpdate::UsageRecorded { task, usage } => {
+                self.record_usage(&task, &usage);
+            }
+            ExecutionUpdate::LogMessage { level, message } => {
+                let level = match level.as_str() {
+                    "debug" => LogLevel::Debug,
+                    "warn" | "warning" => LogLevel::Warning,
+                    "error" => LogLevel::Error,
+                    _ => LogLevel::Info,
+                };
+                self.add_log(level, message, None, None);
+            }
+            ExecutionUpdate::StatusChanged(status) => {
+                self.add_log(LogLevel::Info, format!("Status: {}", status), None, None);
+            }
+            ExecutionUpdate::Finished { state, error } => {
+                self.finish(state.as_deref(), error);
+            }
+        }
+    }
+
+    /// Log a message about a task, attributed to the task's agent
+    fn add_task_log(&mut self, level: LogLevel, message: String, task_id: String) {
+        let agent = self.tasks.get(&task_id).and_then(|t| t.agent.clone());
+        self.add_log(level, message, Some(task_id), agent);
+    }
+
+    /// Record the end of the execution from its final WorkflowState
+    ///
+    /// A cancelled execution stays cancelled, although the executor leaves
+    /// its state paused so it can be resumed later.
+    pub fn finish(&mut self, state: Option<&WorkflowState>, error: Option<String>) {
+        let cancelled = self.status == ExecutionStatus::Cancelled;
+        match state {
+            Some(state) => self.sync_with_workflow_state(state),
+            None => self.complete(error.is_none()),
+        }
+        if cancelled {
+            self.cancel();
+        }
+
+        let (level, message) = match self.status {
+            ExecutionStatus::Completed => (LogLevel::Info, "Workflow completed".to_string()),
+            ExecutionStatus::Cancelled => (LogLevel::Warning, "Execution cancelled".to_string()),
+            ExecutionStatus::Paused => (
+                LogLevel::Warning,
+                match error {
+                    Some(error) if self.pause_time.is_none() => {
+                        format!("Execution paused: {}", error)
+                    }
+                    _ => "Execution paused".to_string(),
+                },
+            ),
+            _ => (
+                LogLevel::Error,
+                format!(
+                    "Execution failed: {}",
+                    error.as_deref().unwrap_or("unknown error")
+                ),
+            ),
+        };
+        self.add_log(level, message, None, None);
     }
 
     /// Add a log entry
@@ -454,29 +667,28 @@ impl ExecutionMonitorState {
     }
 
     /// Update task status
+    ///
+    /// Tasks the monitor does not know yet, such as subtasks, are added.
     pub fn update_task(&mut self, task_id: String, status: TaskStatus) {
-        if let Some(task) = self.tasks.get_mut(&task_id) {
-            let prev_status = task.status;
-            task.status = status;
-
-            // Update timestamps
-            match status {
-                TaskStatus::Running => {
-                    if task.start_time.is_none() {
-                        task.start_time = Some(SystemTime::now());
-                    }
-                }
-                TaskStatus::Completed | TaskStatus::Failed | TaskStatus::Skipped => {
-                    if task.end_time.is_none() {
-                        task.end_time = Some(SystemTime::now());
-                    }
-                }
-                _ => {}
+        let task = self.task_entry(&task_id);
+        let prev_status = task.status;
+        task.status = status;
+
+        // Update timestamps
+        match status {
+            TaskStatus::Running if task.start_time.is_none() => {
+                task.start_time = Some(SystemTime::now());
             }
-
-            // Update statistics
-            self.update_statistics(prev_status, status);
+            TaskStatus::Completed | TaskStatus::Failed | TaskStatus::Skipped
+                if task.end_time.is_none() =>
+            {
+                task.end_time = Some(SystemTime::now());
+            }
+            _ => {}
         }
+
+        // Update statistics
+        self.update_statistics(prev_status, status);
     }
 
     /// Update execution statistics
@@ -1187,7 +1399,7 @@ fn render_shortcuts(frame: &mut Frame, area: Rect, state: &ExecutionMonitorState
         ExecutionStatus::Paused => {
             "Ctrl+P: Resume | Ctrl+C: Cancel | Tab: Switch Panel | Esc: Back"
         }
-        _ => "Tab: Switch Panel | Esc: Back",
+        _ => "s: Browse State | Tab: Switch Panel | Esc: Back",
     };
 
     let paragraph = Paragraph::new(shortcuts).style(Style::default().fg(theme.muted).bg(theme.bg));
@@ -1256,6 +1468,8 @@ mod tests {
             imports: HashMap::new(),
             notifications: None,
             limits: None,
+            max_concurrency: None,
+            providers: HashMap::new(),
         }
     }
 
@@ -1315,6 +1529,57 @@ mod tests {
         assert_eq!(state.logs[0].level, LogLevel::Info);
     }
 
+    #[test]
+    fn test_apply_updates() {
+        let workflow = create_test_workflow();
+        let mut state = ExecutionMonitorState::new(workflow);
+
+        state.apply_update(ExecutionUpdate::WorkflowStarted {
+            workflow: "Test Workflow".to_string(),
+            tasks: vec!["task2".to_string(), "task1".to_string()],
+        });
+        assert_eq!(state.task_order, vec!["task2", "task1"]);
+        assert_eq!(state.stats.pending_tasks, 2);
+
+        state.apply_update(ExecutionUpdate::TaskStarted("task2".to_string()));
+        state.apply_update(ExecutionUpdate::UsageRecorded {
+            task: "task2".to_string(),
+            usage: Usage {
+                input_tokens: 100,
+                output_tokens: 20,
+                cost_usd: 0.01,
+                ..Default::default()
+            },
+        });
+        state.apply_update(ExecutionUpdate::TaskCompleted("task2".to_string()));
+        state.apply_update(ExecutionUpdate::TaskFailed {
+            task: "task1".to_string(),
+            error: "boom".to_string(),
+        });
+
+        let task2 = &state.tasks["task2"];
+        assert_eq!(task2.status, TaskStatus::Completed);
+        assert_eq!(task2.tokens.unwrap().input_tokens, 100);
+        assert_eq!(state.tasks["task1"].error.as_deref(), Some("boom"));
+        assert_eq!(state.stats.completed_tasks, 1);
+        assert_eq!(state.stats.failed_tasks, 1);
+        assert_eq!(state.stats.pending_tasks, 0);
+        assert_eq!(state.stats.total_output_tokens, 20);
+        assert!((state.stats.total_cost - 0.01).abs() < f64::EPSILON);
+        assert_eq!(state.logs.last().unwrap().level, LogLevel::Error);
+
+        // A cancelled run stays cancelled although its saved state is paused
+        let mut workflow_state =
+            WorkflowState::new("Test Workflow".to_string(), "1.0.0".to_string());
+        workflow_state.mark_paused();
+        state.cancel();
+        state.apply_update(ExecutionUpdate::Finished {
+            state: Some(Box::new(workflow_state)),
+            error: Some("Execution cancelled".to_string()),
+        });
+        assert_eq!(state.status, ExecutionStatus::Cancelled);
+    }
+
     #[test]
     fn test_format_duration() {
         assert_eq!(format_duration(Duration::from_secs(45)), "00:45");
@@ -1172,6 +1172,8 @@ mod tests {
             imports: std::collections::HashMap::new(),
             notifications: None,
             limits: None,
+            max_concurrency: None,
+            providers: std::collections::HashMap::new(),
         };
 
         state.generated_workflow = Some(workflow);
@@ -245,6 +245,19 @@ impl StateBrowserState {
         filtered.get(self.selected_index).copied()
     }
 
+    /// Select the state of a workflow, returning false if it is not listed
+    pub fn select_state(&mut self, workflow_name: &str) -> bool {
+        let index = self
+            .filtered_states()
+            .iter()
+            .position(|entry| entry.workflow_name == workflow_name);
+        if let Some(index) = index {
+            self.selected_index = index;
+            self.update_list_state();
+        }
+        index.is_some()
+    }
+
     /// Delete selected state
     pub fn delete_selected(&mut self) -> Result<()> {
         let filtered = self.filtered_states();
@@ -378,6 +391,7 @@ impl StateEntry {
             WorkflowStatus::Completed => "Completed",
             WorkflowStatus::Failed => "Failed",
             WorkflowStatus::Paused => "Paused",
+            WorkflowStatus::Cancelled => "Cancelled",
         }
     }
 
@@ -388,6 +402,7 @@ impl StateEntry {
             WorkflowStatus::Completed => Color::Green,
             WorkflowStatus::Failed => Color::Red,
             WorkflowStatus::Paused => Color::Cyan,
+            WorkflowStatus::Cancelled => Color::DarkGray,
         }
     }
 
@@ -621,6 +636,7 @@ fn build_details_text<'a>(state: &'a WorkflowState, theme: &'a Theme) -> Text<'a
                 WorkflowStatus::Completed => Color::Green,
                 WorkflowStatus::Failed => Color::Red,
                 WorkflowStatus::Paused => Color::Cyan,
+                WorkflowStatus::Cancelled => Color::DarkGray,
             }),
         ),
     ]));
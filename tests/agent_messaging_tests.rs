@@ -0,0 +1,268 @@
+//! Agent Messaging Tests
+//!
+//! Verifies that workflow participants can use the message bus during
+//! execution: agents through the `send_message` and `read_messages` tools,
+//! and other tasks through `messages:` inputs and results. Payloads are checked
+//! against their message type's schema, and messages survive a resume.
+
+use periplon_sdk::dsl::executor::DSLExecutor;
+use periplon_sdk::dsl::message_bus::MessageBus;
+use periplon_sdk::dsl::message_tools;
+use periplon_sdk::dsl::{
+    parse_workflow, validate_workflow, ExecutionEvent, TaskStatus, WorkflowStatus,
+};
+use periplon_sdk::AgentOptions;
+use serde_json::json;
+
+const COMMUNICATION: &str = r#"
+communication:
+  channels:
+    updates:
+      description: "Progress updates"
+      participants: [announcer, reporter]
+      message_format: json
+  message_types:
+    status:
+      schema:
+        type: object
+        required: [step, done]
+        properties:
+          step: { type: integer }
+          done: { type: boolean }
+"#;
+
+fn workflow_yaml(announce: &str, report: &str) -> String {
+    format!(
+        r#"
+name: "Messaging"
+version: "1.0.0"
+{}
+tasks:
+  announce:
+    description: "Announce progress"
+    script:
+      language: bash
+      content: |
+        echo '{}'
+    messages:
+      as: announcer
+      publish:
+        channel: updates
+        message_type: status
+  report:
+    description: "Report the updates"
+    depends_on: [announce]
+    script:
+      language: bash
+      content: |
+        {}
+    messages:
+      as: reporter
+      read: [updates]
+"#,
+        COMMUNICATION, announce, report
+    )
+}
+
+#[test]
+fn test_validator_checks_task_messages() {
+    let yaml = r#"
+name: "Messaging"
+version: "1.0.0"
+communication:
+  channels:
+    updates:
+      description: "Progress updates"
+      participants: [announcer]
+      message_format: json
+  message_types:
+    status:
+      schema: { type: not-a-type }
+tasks:
+  outsider:
+    description: "Not a participant"
+    script: { language: bash, content: "true" }
+    messages:
+      read: [updates, missing]
+  announce:
+    description: "Unknown type"
+    script: { language: bash, content: "true" }
+    messages:
+      as: announcer
+      publish: { channel: updates, message_type: other }
+  both:
+    description: "Two targets"
+    script: { language: bash, content: "true" }
+    messages:
+      as: announcer
+      publish: { channel: updates, to: announcer, message_type: status }
+"#;
+    let workflow = parse_workflow(yaml).unwrap();
+    let error = validate_workflow(&workflow).unwrap_err().to_string();
+    assert!(error.contains("Message type 'status' has an invalid schema"));
+    assert!(error.contains(
+        "Task 'outsider' uses channel 'updates' as 'outsider', which is not a participant"
+    ));
+    assert!(error.contains("Task 'outsider' references unknown channel 'missing'"));
+    assert!(error.contains("Task 'announce' publishes unknown message type 'other'"));
+    assert!(error.contains("Task 'both' must publish to either a channel or an agent"));
+
+    let valid = parse_workflow(&workflow_yaml(r#"{"step": 1, "done": false}"#, "true")).unwrap();
+    assert!(validate_workflow(&valid).is_ok());
+}
+
+#[tokio::test]
+async fn test_tasks_publish_and_read_channel_messages() {
+    let yaml = workflow_yaml(
+        r#"{"step": 1, "done": false}"#,
+        "echo 'received: ${task.messages.updates}'",
+    );
+    let workflow = parse_workflow(&yaml).unwrap();
+    let mut executor = DSLExecutor::new(workflow).unwrap();
+    executor.initialize().await.unwrap();
+    executor.execute().await.unwrap();
+
+    let state = executor.get_state().unwrap();
+    let output = &state.get_task_output("report").unwrap().content;
+    assert!(output.contains("received: "));
+    assert!(output.contains(r#""from":"announcer""#));
+    assert!(output.contains(r#""payload":{"done":false,"step":1}"#));
+
+    let transcript = &state.messages.transcripts["updates"];
+    assert_eq!(transcript.len(), 1);
+    assert_eq!(transcript[0].message_type, "status");
+    assert_eq!(executor.message_bus().transcript("updates").len(), 1);
+}
+
+#[tokio::test]
+async fn test_invalid_payload_fails_task() {
+    let yaml = workflow_yaml(r#"{"step": "one"}"#, "true");
+    let workflow = parse_workflow(&yaml).unwrap();
+    let mut executor = DSLExecutor::new(workflow).unwrap();
+    executor.initialize().await.unwrap();
+    assert!(executor.execute().await.is_err());
+
+    let state = executor.get_state().unwrap();
+    assert_eq!(state.get_task_status("announce"), Some(TaskStatus::Failed));
+    assert!(state.task_errors["announce"]
+        .contains("Message of type 'status' does not match its schema"));
+    assert!(state.messages.is_empty());
+}
+
+#[tokio::test]
+async fn test_messages_survive_resume() {
+    let state_dir = tempfile::tempdir().unwrap();
+    let state_path = state_dir.path().to_string_lossy().to_string();
+
+    // Stop the run while the reading task is running
+    let workflow =
+        parse_workflow(&workflow_yaml(r#"{"step": 2, "done": true}"#, "sleep 30")).unwrap();
+    let mut executor = DSLExecutor::new(workflow).unwrap();
+    executor.set_console_output(false);
+    executor
+        .enable_state_persistence(Some(&state_path))
+        .unwrap();
+    let cancellation = executor.cancellation_token();
+    let mut events = executor.events().channel();
+    tokio::spawn(async move {
+        while let Some(event) = events.recv().await {
+            if matches!(&event, ExecutionEvent::TaskStarted { task_id, .. } if task_id == "report")
+            {
+                cancellation.cancel();
+            }
+        }
+    });
+    executor.initialize().await.unwrap();
+    assert!(executor.execute().await.is_err());
+    assert_eq!(executor.get_state().unwrap().status, WorkflowStatus::Paused);
+
+    // The resumed run reads the message sent before it stopped
+    let workflow = parse_workflow(&workflow_yaml(
+        r#"{"step": 2, "done": true}"#,
+        "echo '${task.messages.updates}'",
+    ))
+    .unwrap();
+    let mut executor = DSLExecutor::new(workflow).unwrap();
+    executor
+        .enable_state_persistence(Some(&state_path))
+        .unwrap();
+    assert!(executor.try_resume().unwrap());
+    executor.initialize().await.unwrap();
+    executor.execute().await.unwrap();
+
+    let state = executor.get_state().unwrap();
+    assert_eq!(state.status, WorkflowStatus::Completed);
+    assert_eq!(state.messages.transcripts["updates"].len(), 1);
+    assert!(state
+        .get_task_output("report")
+        .unwrap()
+        .content
+        .contains(r#""step":2"#));
+}
+
+#[tokio::test]
+async fn test_agent_message_tools() {
+    let bus = std::sync::Arc::new(MessageBus::new());
+    bus.create_channel(
+        "updates".to_string(),
+        "Progress updates".to_string(),
+        vec!["coder".to_string(), "reviewer".to_string()],
+        "json".to_string(),
+    )
+    .await
+    .unwrap();
+    bus.register_agent("reviewer".to_string()).await.unwrap();
+
+    let mut coder = AgentOptions::default();
+    message_tools::attach(&mut coder, "coder", bus.clone());
+    let mut reviewer = AgentOptions::default();
+    message_tools::attach(&mut reviewer, "reviewer", bus.clone());
+    assert_eq!(coder.allowed_tools, message_tools::tool_names());
+
+    let coder_tools = &coder.sdk_mcp_servers[message_tools::SERVER_NAME];
+    let reviewer_tools = &reviewer.sdk_mcp_servers[message_tools::SERVER_NAME];
+    let sent = coder_tools
+        .call_tool(
+            "send_message",
+            json!({"channel": "updates", "message_type": "status", "payload": {"step": 1}}),
+        )
+        .await
+        .unwrap();
+    assert!(!sent.is_error);
+    let direct = coder_tools
+        .call_tool(
+            "send_message",
+            json!({"to": "reviewer", "message_type": "note", "payload": "ready"}),
+        )
+        .await
+        .unwrap();
+    assert!(!direct.is_error);
+
+    let read = reviewer_tools
+        .call_tool("read_messages", json!({"channel": "updates"}))
+        .await
+        .unwrap();
+    assert!(read.content[0]["text"]
+        .as_str()
+        .unwrap()
+        .contains(r#""from": "coder""#));
+    let again = reviewer_tools
+        .call_tool("read_messages", json!({"channel": "updates"}))
+        .await
+        .unwrap();
+    assert_eq!(again.content[0]["text"], "No new messages");
+    let inbox = reviewer_tools
+        .call_tool("read_messages", json!({}))
+        .await
+        .unwrap();
+    assert!(inbox.content[0]["text"].as_str().unwrap().contains("ready"));
+
+    let missing_target = coder_tools
+        .call_tool(
+            "send_message",
+            json!({"message_type": "note", "payload": {}}),
+        )
+        .await
+        .unwrap();
+    assert!(missing_target.is_error);
+}
@@ -0,0 +1,469 @@
+//! API Key Authentication Tests
+//!
+//! Verifies that the auth middleware accepts API keys sent in the `X-API-Key`
+//! header: keys are looked up by hash, their expiry, status, scopes and IP
+//! whitelist are enforced, and accepted keys produce the same claims as a JWT
+//! for their owner.
+
+#![cfg(feature = "server")]
+
+use axum::body::Body;
+use axum::extract::{ConnectInfo, Extension};
+use axum::http::{Request, StatusCode};
+use axum::middleware;
+use axum::routing::{get, post, put};
+use axum::{Json, Router};
+use chrono::{Duration, Utc};
+use periplon_sdk::server::auth::api_key::hash_api_key;
+use periplon_sdk::server::auth::middleware::{auth_middleware, require_role, AuthLayer};
+use periplon_sdk::server::auth::{ApiKeyIdentity, Claims, JwtManager};
+use periplon_sdk::server::storage::user_storage::User;
+use periplon_sdk::server::storage::ApiKey;
+use periplon_sdk::testing::{MockApiKeyStorage, MockUserStorage};
+use serde_json::{json, Value};
+use std::net::SocketAddr;
+use std::sync::Arc;
+use tower::ServiceExt;
+use uuid::Uuid;
+
+struct Fixture {
+    router: Router,
+    keys: Arc<MockApiKeyStorage>,
+    users: Arc<MockUserStorage>,
+    jwt_manager: Arc<JwtManager>,
+}
+
+async fn whoami(
+    Extension(claims): Extension<Claims>,
+    identity: Option<Extension<ApiKeyIdentity>>,
+) -> Json<Value> {
+    Json(json!({
+        "sub": claims.sub,
+        "email": claims.email,
+        "roles": claims.roles,
+        "key_id": identity.map(|Extension(identity)| identity.key_id),
+    }))
+}
+
+fn fixture() -> Fixture {
+    let keys = Arc::new(MockApiKeyStorage::new());
+    let users = Arc::new(MockUserStorage::new());
+    let jwt_manager = Arc::new(JwtManager::new("test_secret_key_123", 24));
+    let auth_layer = AuthLayer::new(Arc::clone(&jwt_manager))
+        .with_api_keys(keys.clone(), users.clone())
+        .with_trusted_proxies(vec!["10.0.0.0/8".to_string()]);
+
+    let admin_routes = Router::new()
+        .route("/api/v1/admin", get(whoami))
+        .route_layer(middleware::from_fn(|req, next| {
+            require_role("admin", req, next)
+        }));
+    let router = Router::new()
+        .route("/api/v1/workflows", get(whoami).post(whoami))
+        .route("/api/v1/auth/me", get(whoami))
+        .route("/api/v1/auth/refresh", post(whoami))
+        .route("/api/v1/api-keys", get(whoami).post(whoami))
+        .route("/api/v1/api-keys/:id", put(whoami))
+        .route("/api/v1/api-keys/:id/rotate", post(whoami))
+        .merge(admin_routes)
+        .route_layer(middleware::from_fn(move |req, next| {
+            auth_middleware(auth_layer.clone(), req, next)
+        }));
+
+    Fixture {
+        router,
+        keys,
+        users,
+        jwt_manager,
+    }
+}
+
+fn add_user(fixture: &Fixture, roles: &[&str]) -> User {
+    let user = User {
+        id: Uuid::new_v4(),
+        email: "owner@example.com".to_string(),
+        name: "Key Owner".to_string(),
+        password_hash: String::new(),
+        roles: roles.iter().map(|role| role.to_string()).collect(),
+        is_active: true,
+        email_verified: true,
+        created_at: Utc::now(),
+        updated_at: Utc::now(),
+        last_login_at: None,
+    };
+    fixture.users.add_user(user.clone());
+    user
+}
+
+fn add_key(fixture: &Fixture, user: &User, key: &str, scopes: &[&str]) -> ApiKey {
+    let api_key = ApiKey {
+        id: Uuid::new_v4(),
+        user_id: user.id,
+        key_hash: hash_api_key(key),
+        key_prefix: key.chars().take(12).collect(),
+        name: Some("ci".to_string()),
+        description: None,
+        scopes: scopes.iter().map(|scope| scope.to_string()).collect(),
+        ip_whitelist: Vec::new(),
+        created_at: Utc::now(),
+        expires_at: None,
+        last_used_at: None,
+        is_active: true,
+    };
+    fixture.keys.add_key(api_key.clone());
+    api_key
+}
+
+async fn send(router: &Router, method: &str, path: &str, headers: &[(&str, &str)]) -> Response {
+    send_from(router, None, method, path, headers).await
+}
+
+/// Send a request over a connection from `peer`
+async fn send_from(
+    router: &Router,
+    peer: Option<&str>,
+    method: &str,
+    path: &str,
+    headers: &[(&str, &str)],
+) -> Response {
+    let mut request = Request::builder().method(method).uri(path);
+    for (name, value) in headers {
+        request = request.header(*name, *value);
+    }
+    if let Some(peer) = peer {
+        let address: SocketAddr = format!("{}:40000", peer).parse().unwrap();
+        request = request.extension(ConnectInfo(address));
+    }
+    let response = router
+        .clone()
+        .oneshot(request.body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+
+    let status = response.status();
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    Response {
+        status,
+        body: serde_json::from_slice(&body).unwrap_or(Value::Null),
+    }
+}
+
+struct Response {
+    status: StatusCode,
+    body: Value,
+}
+
+#[tokio::test]
+async fn test_api_key_produces_owner_claims() {
+    let fixture = fixture();
+    let user = add_user(&fixture, &["user"]);
+    let api_key = add_key(&fixture, &user, "sk_valid", &["workflows:read"]);
+
+    let response = send(
+        &fixture.router,
+        "GET",
+        "/api/v1/workflows",
+        &[("X-API-Key", "sk_valid")],
+    )
+    .await;
+    assert_eq!(response.status, StatusCode::OK);
+    assert_eq!(response.body["sub"], user.id.to_string());
+    assert_eq!(response.body["email"], "owner@example.com");
+    assert_eq!(response.body["roles"], json!(["user"]));
+    assert_eq!(response.body["key_id"], api_key.id.to_string());
+
+    // The key's use is recorded
+    assert!(fixture.keys.key(api_key.id).unwrap().last_used_at.is_some());
+
+    // JWTs are still accepted, with the same claims
+    let token = fixture
+        .jwt_manager
+        .generate_token(&user.id.to_string(), &user.email, user.roles.clone())
+        .unwrap();
+    let response = send(
+        &fixture.router,
+        "GET",
+        "/api/v1/workflows",
+        &[("Authorization", &format!("Bearer {}", token))],
+    )
+    .await;
+    assert_eq!(response.status, StatusCode::OK);
+    assert_eq!(response.body["sub"], user.id.to_string());
+    assert_eq!(response.body["key_id"], Value::Null);
+}
+
+#[tokio::test]
+async fn test_rejected_api_keys() {
+    let fixture = fixture();
+    let user = add_user(&fixture, &["user"]);
+
+    let mut revoked = add_key(&fixture, &user, "sk_revoked", &["*"]);
+    revoked.is_active = false;
+    fixture.keys.add_key(revoked);
+
+    let mut expired = add_key(&fixture, &user, "sk_expired", &["*"]);
+    expired.expires_at = Some(Utc::now() - Duration::minutes(1));
+    fixture.keys.add_key(expired);
+
+    let mut inactive_owner = add_user(&fixture, &["user"]);
+    inactive_owner.email = "inactive@example.com".to_string();
+    inactive_owner.is_active = false;
+    fixture.users.add_user(inactive_owner.clone());
+    add_key(&fixture, &inactive_owner, "sk_inactive_owner", &["*"]);
+
+    for key in [
+        "sk_unknown",
+        "sk_revoked",
+        "sk_expired",
+        "sk_inactive_owner",
+    ] {
+        let response = send(
+            &fixture.router,
+            "GET",
+            "/api/v1/workflows",
+            &[("X-API-Key", key)],
+        )
+        .await;
+        assert_eq!(response.status, StatusCode::UNAUTHORIZED, "key {}", key);
+    }
+}
+
+#[tokio::test]
+async fn test_api_key_scopes() {
+    let fixture = fixture();
+    let user = add_user(&fixture, &["user"]);
+    add_key(&fixture, &user, "sk_reader", &["workflows:read"]);
+    add_key(&fixture, &user, "sk_writer", &["workflows:write"]);
+    add_key(&fixture, &user, "sk_other", &["executions:*"]);
+
+    let cases = [
+        ("GET", "sk_reader", StatusCode::OK),
+        ("POST", "sk_reader", StatusCode::FORBIDDEN),
+        ("GET", "sk_writer", StatusCode::OK),
+        ("POST", "sk_writer", StatusCode::OK),
+        ("GET", "sk_other", StatusCode::FORBIDDEN),
+    ];
+    for (method, key, expected) in cases {
+        let response = send(
+            &fixture.router,
+            method,
+            "/api/v1/workflows",
+            &[("X-API-Key", key)],
+        )
+        .await;
+        assert_eq!(response.status, expected, "{} with {}", method, key);
+    }
+}
+
+#[tokio::test]
+async fn test_api_key_cannot_issue_tokens() {
+    let fixture = fixture();
+    let user = add_user(&fixture, &["user"]);
+    add_key(&fixture, &user, "sk_auth", &["auth:write"]);
+    add_key(&fixture, &user, "sk_all", &["*"]);
+
+    for key in ["sk_auth", "sk_all"] {
+        let response = send(
+            &fixture.router,
+            "POST",
+            "/api/v1/auth/refresh",
+            &[("X-API-Key", key)],
+        )
+        .await;
+        assert_eq!(response.status, StatusCode::FORBIDDEN, "{}", key);
+    }
+
+    let me = send(
+        &fixture.router,
+        "GET",
+        "/api/v1/auth/me",
+        &[("X-API-Key", "sk_auth")],
+    )
+    .await;
+    assert_eq!(me.status, StatusCode::OK);
+
+    // A JWT can still be refreshed
+    let token = fixture
+        .jwt_manager
+        .generate_token(&user.id.to_string(), &user.email, user.roles.clone())
+        .unwrap();
+    let response = send(
+        &fixture.router,
+        "POST",
+        "/api/v1/auth/refresh",
+        &[("Authorization", &format!("Bearer {}", token))],
+    )
+    .await;
+    assert_eq!(response.status, StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_api_key_cannot_manage_api_keys() {
+    let fixture = fixture();
+    let user = add_user(&fixture, &["user"]);
+    let api_key = add_key(&fixture, &user, "sk_keys", &["api-keys:write"]);
+    add_key(&fixture, &user, "sk_all", &["*"]);
+
+    // Creating, widening or rotating keys would escape the key's own limits
+    let own_key = format!("/api/v1/api-keys/{}", api_key.id);
+    let rotate = format!("/api/v1/api-keys/{}/rotate", api_key.id);
+    for key in ["sk_keys", "sk_all"] {
+        for (method, path) in [
+            ("POST", "/api/v1/api-keys"),
+            ("PUT", own_key.as_str()),
+            ("POST", rotate.as_str()),
+        ] {
+            let response = send(&fixture.router, method, path, &[("X-API-Key", key)]).await;
+            assert_eq!(
+                response.status,
+                StatusCode::FORBIDDEN,
+                "{} {} with {}",
+                method,
+                path,
+                key
+            );
+        }
+    }
+
+    let list = send(
+        &fixture.router,
+        "GET",
+        "/api/v1/api-keys",
+        &[("X-API-Key", "sk_keys")],
+    )
+    .await;
+    assert_eq!(list.status, StatusCode::OK);
+
+    // Keys are still managed with a JWT
+    let token = fixture
+        .jwt_manager
+        .generate_token(&user.id.to_string(), &user.email, user.roles.clone())
+        .unwrap();
+    let response = send(
+        &fixture.router,
+        "POST",
+        "/api/v1/api-keys",
+        &[("Authorization", &format!("Bearer {}", token))],
+    )
+    .await;
+    assert_eq!(response.status, StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_api_key_ip_whitelist() {
+    let fixture = fixture();
+    let user = add_user(&fixture, &["user"]);
+    let mut api_key = add_key(&fixture, &user, "sk_office", &["*"]);
+    api_key.ip_whitelist = vec!["203.0.113.0/24".to_string()];
+    fixture.keys.add_key(api_key);
+
+    let key = [("X-API-Key", "sk_office")];
+    let allowed = send_from(
+        &fixture.router,
+        Some("203.0.113.9"),
+        "GET",
+        "/api/v1/workflows",
+        &key,
+    )
+    .await;
+    assert_eq!(allowed.status, StatusCode::OK);
+
+    let outside = send_from(
+        &fixture.router,
+        Some("198.51.100.1"),
+        "GET",
+        "/api/v1/workflows",
+        &key,
+    )
+    .await;
+    assert_eq!(outside.status, StatusCode::FORBIDDEN);
+
+    // Behind a trusted proxy, the forwarded client address is checked
+    let proxied = send_from(
+        &fixture.router,
+        Some("10.0.0.2"),
+        "GET",
+        "/api/v1/workflows",
+        &[
+            ("X-API-Key", "sk_office"),
+            ("X-Forwarded-For", "198.51.100.1, 203.0.113.9"),
+        ],
+    )
+    .await;
+    assert_eq!(proxied.status, StatusCode::OK);
+
+    let proxied_outside = send_from(
+        &fixture.router,
+        Some("10.0.0.2"),
+        "GET",
+        "/api/v1/workflows",
+        &[
+            ("X-API-Key", "sk_office"),
+            ("X-Forwarded-For", "203.0.113.9, 198.51.100.1"),
+        ],
+    )
+    .await;
+    assert_eq!(proxied_outside.status, StatusCode::FORBIDDEN);
+
+    let unknown_ip = send(
+        &fixture.router,
+        "GET",
+        "/api/v1/workflows",
+        &[("X-API-Key", "sk_office")],
+    )
+    .await;
+    assert_eq!(unknown_ip.status, StatusCode::FORBIDDEN);
+}
+
+#[tokio::test]
+async fn test_api_key_ip_whitelist_ignores_spoofed_headers() {
+    let fixture = fixture();
+    let user = add_user(&fixture, &["user"]);
+    let mut api_key = add_key(&fixture, &user, "sk_office", &["*"]);
+    api_key.ip_whitelist = vec!["203.0.113.0/24".to_string()];
+    fixture.keys.add_key(api_key);
+
+    for header in ["X-Forwarded-For", "X-Real-IP"] {
+        let response = send_from(
+            &fixture.router,
+            Some("198.51.100.1"),
+            "GET",
+            "/api/v1/workflows",
+            &[("X-API-Key", "sk_office"), (header, "203.0.113.9")],
+        )
+        .await;
+        assert_eq!(response.status, StatusCode::FORBIDDEN, "{}", header);
+    }
+}
+
+#[tokio::test]
+async fn test_require_role_with_api_key() {
+    let fixture = fixture();
+    let admin = add_user(&fixture, &["user", "admin"]);
+    add_key(&fixture, &admin, "sk_admin", &["*"]);
+    let mut user = add_user(&fixture, &["user"]);
+    user.email = "user@example.com".to_string();
+    fixture.users.add_user(user.clone());
+    add_key(&fixture, &user, "sk_user", &["*"]);
+
+    let response = send(
+        &fixture.router,
+        "GET",
+        "/api/v1/admin",
+        &[("X-API-Key", "sk_admin")],
+    )
+    .await;
+    assert_eq!(response.status, StatusCode::OK);
+    assert_eq!(response.body["roles"], json!(["user", "admin"]));
+
+    let response = send(
+        &fixture.router,
+        "GET",
+        "/api/v1/admin",
+        &[("X-API-Key", "sk_user")],
+    )
+    .await;
+    assert_eq!(response.status, StatusCode::FORBIDDEN);
+}
@@ -117,6 +117,8 @@ fn test_bypass_permissions_in_agent_options() {
             allowed_directories: vec![],
         },
         max_turns: None,
+        max_concurrency: None,
+        profile: None,
     };
 
     // Verify the permission mode is set correctly
@@ -0,0 +1,183 @@
+//! Fake HTTP server
+//!
+//! Answers requests with canned responses and records the requests it
+//! received, standing in for HTTP endpoints and LLM providers. Test files
+//! include it on its own, as `mod common { pub mod http; }`.
+
+// Each test binary uses only some of the helpers
+#![allow(dead_code)]
+
+use serde_json::Value;
+use std::sync::{Arc, Mutex};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+
+/// Canned response of a fake server
+#[derive(Debug, Clone)]
+pub struct CannedResponse {
+    status: u16,
+    headers: Vec<(String, String)>,
+    body: String,
+    piece_size: Option<usize>,
+}
+
+impl CannedResponse {
+    /// `200 OK` response with the given content type and body
+    pub fn new(content_type: &str, body: impl Into<String>) -> Self {
+        Self {
+            status: 200,
+            headers: vec![("Content-Type".to_string(), content_type.to_string())],
+            body: body.into(),
+            piece_size: None,
+        }
+    }
+
+    /// `200 OK` response with a JSON body
+    pub fn json(body: impl ToString) -> Self {
+        Self::new("application/json", body.to_string())
+    }
+
+    /// Answer with another status
+    pub fn status(mut self, status: u16) -> Self {
+        self.status = status;
+        self
+    }
+
+    /// Add a response header
+    pub fn header(mut self, name: &str, value: &str) -> Self {
+        self.headers.push((name.to_string(), value.to_string()));
+        self
+    }
+
+    /// Write the body in pieces of `size` bytes, so that clients reading a
+    /// stream get events split across chunks
+    pub fn in_pieces(mut self, size: usize) -> Self {
+        self.piece_size = Some(size);
+        self
+    }
+}
+
+/// Request received by a fake server
+#[derive(Debug, Clone)]
+pub struct RecordedRequest {
+    /// Request line, headers and body as received
+    pub raw: String,
+    /// Body announced by `Content-Length`
+    pub body: String,
+}
+
+impl RecordedRequest {
+    /// Body parsed as JSON, or `Null` when it is not JSON
+    pub fn json(&self) -> Value {
+        serde_json::from_str(&self.body).unwrap_or(Value::Null)
+    }
+}
+
+/// HTTP server on localhost answering with canned responses
+pub struct FakeServer {
+    /// Base URL, such as `http://127.0.0.1:41234`
+    pub url: String,
+    requests: Arc<Mutex<Vec<RecordedRequest>>>,
+}
+
+impl FakeServer {
+    /// Start a server answering successive requests with `responses`
+    ///
+    /// The last response repeats for any further requests.
+    pub async fn start(responses: Vec<CannedResponse>) -> Self {
+        assert!(!responses.is_empty(), "a fake server needs a response");
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let log = Arc::clone(&requests);
+
+        tokio::spawn(async move {
+            loop {
+                let Ok((mut socket, _)) = listener.accept().await else {
+                    break;
+                };
+                let request = read_request(&mut socket).await;
+                let index = {
+                    let mut log = log.lock().unwrap();
+                    log.push(request);
+                    log.len() - 1
+                };
+                let response = &responses[index.min(responses.len() - 1)];
+                write_response(&mut socket, response).await;
+            }
+        });
+
+        Self {
+            url: format!("http://{}", addr),
+            requests,
+        }
+    }
+
+    /// Start a server answering every request with one response
+    pub async fn answering(response: CannedResponse) -> Self {
+        Self::start(vec![response]).await
+    }
+
+    /// Requests received so far, oldest first
+    pub fn requests(&self) -> Vec<RecordedRequest> {
+        self.requests.lock().unwrap().clone()
+    }
+
+    /// JSON bodies of the requests received so far, oldest first
+    pub fn json_bodies(&self) -> Vec<Value> {
+        self.requests().iter().map(RecordedRequest::json).collect()
+    }
+}
+
+/// Read the headers, then the body announced by `Content-Length`
+async fn read_request(socket: &mut tokio::net::TcpStream) -> RecordedRequest {
+    let mut buf = Vec::new();
+    let mut chunk = [0u8; 4096];
+    loop {
+        let n = socket.read(&mut chunk).await.unwrap_or(0);
+        if n == 0 {
+            break;
+        }
+        buf.extend_from_slice(&chunk[..n]);
+        let text = String::from_utf8_lossy(&buf);
+        if let Some(header_end) = text.find("\r\n\r\n") {
+            let content_length = text
+                .lines()
+                .find_map(|l| {
+                    l.to_ascii_lowercase()
+                        .strip_prefix("content-length:")
+                        .map(|v| v.trim().parse::<usize>().unwrap_or(0))
+                })
+                .unwrap_or(0);
+            if buf.len() >= header_end + 4 + content_length {
+                break;
+            }
+        }
+    }
+
+    let raw = String::from_utf8_lossy(&buf).to_string();
+    let body = raw
+        .split_once("\r\n\r\n")
+        .map(|(_, body)| body.to_string())
+        .unwrap_or_default();
+    RecordedRequest { raw, body }
+}
+
+async fn write_response(socket: &mut tokio::net::TcpStream, response: &CannedResponse) {
+    let mut head = format!("HTTP/1.1 {} X\r\n", response.status);
+    for (name, value) in &response.headers {
+        head.push_str(&format!("{}: {}\r\n", name, value));
+    }
+    head.push_str(&format!(
+        "Content-Length: {}\r\nConnection: close\r\n\r\n",
+        response.body.len()
+    ));
+    let _ = socket.write_all(head.as_bytes()).await;
+
+    let body = response.body.as_bytes();
+    for piece in body.chunks(response.piece_size.unwrap_or(body.len().max(1))) {
+        let _ = socket.write_all(piece).await;
+        let _ = socket.flush().await;
+    }
+    let _ = socket.shutdown().await;
+}
@@ -163,6 +163,7 @@ impl DSLWorkflowBuilder {
             imports: self.imports,
             notifications: self.notifications,
             limits: self.limits,
+            providers: HashMap::new(),
         }
     }
 }
@@ -0,0 +1,205 @@
+//! DAG Scheduler Tests
+//!
+//! Verifies that independent tasks run concurrently as soon as their
+//! dependencies complete, bounded by the workflow and agent `max_concurrency`.
+
+use periplon_sdk::dsl::executor::DSLExecutor;
+use periplon_sdk::dsl::parse_workflow;
+use periplon_sdk::dsl::TaskStatus;
+use std::path::Path;
+
+/// Build a workflow whose tasks each log `+<id>` on start and `-<id>` on exit
+fn fan_out_workflow(log: &Path, max_concurrency: Option<usize>, workers: usize) -> String {
+    let mut yaml = String::from("name: \"Fan Out\"\nversion: \"1.0.0\"\n");
+    if let Some(max) = max_concurrency {
+        yaml.push_str(&format!("max_concurrency: {}\n", max));
+    }
+    yaml.push_str("tasks:\n");
+
+    let task = |id: &str, depends_on: &[String]| {
+        format!(
+            r#"  {id}:
+    description: "Task {id}"
+    depends_on: [{deps}]
+    script:
+      language: bash
+      content: |
+        echo "+{id}" >> "{log}"
+        sleep 0.5
+        echo "-{id}" >> "{log}"
+"#,
+            id = id,
+            deps = depends_on.join(", "),
+            log = log.display(),
+        )
+    };
+
+    yaml.push_str(&task("setup", &[]));
+    let workers: Vec<String> = (0..workers).map(|i| format!("worker_{}", i)).collect();
+    for worker in &workers {
+        yaml.push_str(&task(worker, &["setup".to_string()]));
+    }
+    yaml.push_str(&task("report", &workers));
+    yaml
+}
+
+/// Replay the start/exit log and return the highest number of overlapping tasks
+fn peak_concurrency(log: &Path) -> usize {
+    let mut running = 0usize;
+    let mut peak = 0usize;
+    for line in std::fs::read_to_string(log).unwrap().lines() {
+        if line.starts_with('+') {
+            running += 1;
+            peak = peak.max(running);
+        } else if line.starts_with('-') {
+            running -= 1;
+        }
+    }
+    peak
+}
+
+async fn run(yaml: &str) -> DSLExecutor {
+    let workflow = parse_workflow(yaml).expect("Failed to parse workflow");
+    let mut executor = DSLExecutor::new(workflow).unwrap();
+    executor.initialize().await.unwrap();
+    executor.execute().await.expect("workflow should succeed");
+    executor
+}
+
+#[tokio::test]
+async fn test_independent_tasks_run_concurrently() {
+    let dir = tempfile::tempdir().unwrap();
+    let log = dir.path().join("events.log");
+
+    let executor = run(&fan_out_workflow(&log, None, 6)).await;
+
+    // All six workers overlap; setup and report run alone
+    assert_eq!(peak_concurrency(&log), 6);
+    let events = std::fs::read_to_string(&log).unwrap();
+    let lines: Vec<&str> = events.lines().collect();
+    assert_eq!(lines.first(), Some(&"+setup"));
+    assert_eq!(lines[1], "-setup");
+    assert_eq!(lines[lines.len() - 2], "+report");
+    assert!(executor.is_complete());
+}
+
+#[tokio::test]
+async fn test_max_concurrency_bounds_running_tasks() {
+    let dir = tempfile::tempdir().unwrap();
+    let log = dir.path().join("events.log");
+
+    let executor = run(&fan_out_workflow(&log, Some(2), 6)).await;
+
+    assert_eq!(peak_concurrency(&log), 2);
+    assert_eq!(
+        executor.task_graph().get_task_status("report"),
+        Some(TaskStatus::Completed)
+    );
+}
+
+#[tokio::test]
+async fn test_failure_stops_dispatch_of_dependents() {
+    let dir = tempfile::tempdir().unwrap();
+    let log = dir.path().join("events.log");
+
+    let yaml = format!(
+        r#"
+name: "Failing Branch"
+version: "1.0.0"
+tasks:
+  broken:
+    description: "Fails immediately"
+    script:
+      language: bash
+      content: "exit 1"
+  slow:
+    description: "Independent task still in flight"
+    script:
+      language: bash
+      content: |
+        sleep 0.3
+        echo "slow" >> "{log}"
+  after:
+    description: "Never dispatched"
+    depends_on: [broken]
+    script:
+      language: bash
+      content: 'echo "after" >> "{log}"'
+"#,
+        log = log.display()
+    );
+
+    let workflow = parse_workflow(&yaml).expect("Failed to parse workflow");
+    let mut executor = DSLExecutor::new(workflow).unwrap();
+    executor.initialize().await.unwrap();
+    executor
+        .execute()
+        .await
+        .expect_err("failed task should fail the workflow");
+
+    // The in-flight task finishes, the dependent never starts
+    assert_eq!(std::fs::read_to_string(&log).unwrap(), "slow\n");
+    let graph = executor.task_graph();
+    assert_eq!(graph.get_task_status("broken"), Some(TaskStatus::Failed));
+    assert_eq!(graph.get_task_status("slow"), Some(TaskStatus::Completed));
+    assert_eq!(graph.get_task_status("after"), Some(TaskStatus::Pending));
+}
+
+/// Put a stand-in agent CLI first on PATH
+///
+/// Each prompt it receives logs `+` and `-` around a short sleep, then gets a
+/// one-line answer.
+fn install_stand_in_cli(dir: &Path, log: &Path) {
+    let script = format!(
+        r#"#!/bin/sh
+if [ "$1" = "-v" ]; then echo "2.0.0"; exit 0; fi
+while read -r line; do
+  echo "+" >> "{log}"
+  sleep 0.5
+  echo "-" >> "{log}"
+  echo '{{"type":"assistant","message":{{"model":"stand-in","content":[{{"type":"text","text":"done"}}]}}}}'
+  echo '{{"type":"result","subtype":"success","duration_ms":1,"duration_api_ms":1,"is_error":false,"num_turns":1,"session_id":"session-'$$'","result":"done"}}'
+done
+"#,
+        log = log.display()
+    );
+    let cli = dir.join("claude");
+    std::fs::write(&cli, script).unwrap();
+    use std::os::unix::fs::PermissionsExt;
+    std::fs::set_permissions(&cli, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+    let path = std::env::var("PATH").unwrap_or_default();
+    std::env::set_var("PATH", format!("{}:{}", dir.display(), path));
+}
+
+#[tokio::test]
+async fn test_agent_max_concurrency_bounds_its_tasks() {
+    let dir = tempfile::tempdir().unwrap();
+    let log = dir.path().join("events.log");
+    install_stand_in_cli(dir.path(), &log);
+
+    let mut yaml = String::from(
+        r#"
+name: "Agent Fan Out"
+version: "1.0.0"
+agents:
+  writer:
+    description: "Writes sections"
+    max_concurrency: 2
+tasks:
+"#,
+    );
+    for i in 0..5 {
+        yaml.push_str(&format!(
+            "  section_{i}:\n    description: \"Write section {i}\"\n    agent: writer\n"
+        ));
+    }
+
+    let executor = run(&yaml).await;
+
+    // Two sections are written at a time, not one and not all five
+    assert_eq!(peak_concurrency(&log), 2);
+    let events = std::fs::read_to_string(&log).unwrap();
+    assert_eq!(events.lines().filter(|line| *line == "+").count(), 5);
+    assert!(executor.is_complete());
+}
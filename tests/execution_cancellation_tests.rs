@@ -0,0 +1,429 @@
+//! Execution Cancellation Tests
+//!
+//! Verifies that cancelling a running workflow kills its processes, stops
+//! loops, runs the error and post-workflow hooks, checkpoints the state as
+//! paused, and that server workers stop executions cancelled through the API.
+
+use periplon_sdk::dsl::events::ExecutionEvent;
+use periplon_sdk::dsl::state::WorkflowStatus;
+use periplon_sdk::dsl::task_graph::TaskStatus;
+use periplon_sdk::dsl::{parse_workflow, DSLExecutor};
+use periplon_sdk::error::Error;
+use std::time::{Duration, Instant};
+use tokio_util::sync::CancellationToken;
+
+/// Cancel `token` after `delay`
+fn cancel_after(token: CancellationToken, delay: Duration) {
+    tokio::spawn(async move {
+        tokio::time::sleep(delay).await;
+        token.cancel();
+    });
+}
+
+#[tokio::test]
+async fn test_cancel_kills_script_and_runs_hooks() {
+    let dir = tempfile::tempdir().unwrap();
+    let yaml = format!(
+        r#"
+name: "Cancellable"
+version: "1.0.0"
+tasks:
+  slow:
+    description: "Sleeps"
+    script:
+      language: bash
+      content: "sleep 30"
+    on_error:
+      retry: 3
+      retry_delay_secs: 0
+workflows:
+  main:
+    description: "Main"
+    steps: []
+    hooks:
+      post_workflow: ["touch {dir}/post"]
+      on_error: ["echo \"$WORKFLOW_ERROR\" > {dir}/error"]
+"#,
+        dir = dir.path().display()
+    );
+
+    let mut executor = DSLExecutor::new(parse_workflow(&yaml).unwrap()).unwrap();
+    executor.set_console_output(false);
+    let mut channel = executor.events().channel();
+    executor.initialize().await.unwrap();
+
+    let started = Instant::now();
+    cancel_after(executor.cancellation_token(), Duration::from_millis(300));
+    let result = executor.execute().await;
+
+    assert!(matches!(result, Err(Error::Cancelled)));
+    assert!(started.elapsed() < Duration::from_secs(10));
+
+    // Hooks ran and saw the cancellation
+    assert!(dir.path().join("post").exists());
+    let error = std::fs::read_to_string(dir.path().join("error")).unwrap();
+    assert_eq!(error.trim(), "Execution cancelled");
+
+    // Cancelled tasks are not retried
+    let mut events = Vec::new();
+    while let Ok(event) = channel.try_recv() {
+        events.push(event);
+    }
+    assert!(!events
+        .iter()
+        .any(|event| matches!(event, ExecutionEvent::TaskRetrying { .. })));
+    assert!(events.iter().any(|event| matches!(
+        event,
+        ExecutionEvent::WorkflowFinished { success: false, .. }
+    )));
+}
+
+#[tokio::test]
+async fn test_cancel_stops_loop_iterations() {
+    let yaml = r#"
+name: "Cancellable Loop"
+version: "1.0.0"
+tasks:
+  poll:
+    description: "Poll repeatedly"
+    loop:
+      type: repeat
+      count: 50
+    script:
+      language: bash
+      content: "sleep 0.1"
+"#;
+
+    let mut executor = DSLExecutor::new(parse_workflow(yaml).unwrap()).unwrap();
+    executor.set_console_output(false);
+    let mut channel = executor.events().channel();
+    executor.initialize().await.unwrap();
+
+    cancel_after(executor.cancellation_token(), Duration::from_millis(500));
+    let result = executor.execute().await;
+    assert!(matches!(result, Err(Error::Cancelled)));
+
+    let mut iterations = 0;
+    while let Ok(event) = channel.try_recv() {
+        if matches!(event, ExecutionEvent::LoopIterationStarted { .. }) {
+            iterations += 1;
+        }
+    }
+    assert!(iterations > 0);
+    assert!(iterations < 50);
+}
+
+#[tokio::test]
+async fn test_cancelled_state_is_paused_for_resume() {
+    let state_dir = tempfile::tempdir().unwrap();
+    let yaml = r#"
+name: "Cancellable State"
+version: "1.0.0"
+tasks:
+  fetch:
+    description: "Fetch"
+    script:
+      language: bash
+      content: "echo fetched"
+  process:
+    description: "Process"
+    depends_on: [fetch]
+    script:
+      language: bash
+      content: "sleep 30"
+"#;
+
+    let mut executor = DSLExecutor::new(parse_workflow(yaml).unwrap()).unwrap();
+    executor.set_console_output(false);
+    executor
+        .enable_state_persistence(Some(state_dir.path().to_str().unwrap()))
+        .unwrap();
+    executor.initialize().await.unwrap();
+
+    cancel_after(executor.cancellation_token(), Duration::from_millis(500));
+    assert!(matches!(executor.execute().await, Err(Error::Cancelled)));
+
+    let state = executor.get_state().unwrap();
+    assert_eq!(state.status, WorkflowStatus::Paused);
+    assert_eq!(state.get_task_status("fetch"), Some(TaskStatus::Completed));
+    assert_eq!(state.get_task_status("process"), Some(TaskStatus::Pending));
+}
+
+#[cfg(feature = "server")]
+mod worker {
+    use chrono::Utc;
+    use periplon_sdk::dsl::parse_workflow;
+    use periplon_sdk::server::hub::ExecutionStreamMessage;
+    use periplon_sdk::server::queue::{Job, WorkQueue};
+    use periplon_sdk::server::storage::filesystem::FilesystemStorage;
+    use periplon_sdk::server::storage::{
+        Execution, ExecutionStatus, ExecutionStorage, WorkflowMetadata, WorkflowStorage,
+    };
+    use periplon_sdk::server::worker::Worker;
+    use periplon_sdk::server::ExecutionHub;
+    use periplon_sdk::testing::MockQueue;
+    use serde_json::json;
+    use std::sync::Arc;
+    use std::time::Duration;
+    use uuid::Uuid;
+
+    const WORKFLOW: &str = r#"
+name: "Long Running"
+version: "1.0.0"
+tasks:
+  wait:
+    description: "Wait"
+    script:
+      language: bash
+      content: "sleep 30"
+"#;
+
+    struct Fixture {
+        _dir: tempfile::TempDir,
+        storage: Arc<FilesystemStorage>,
+        queue: Arc<MockQueue>,
+        hub: Arc<ExecutionHub>,
+        workflow_id: Uuid,
+    }
+
+    async fn setup() -> Fixture {
+        setup_with(WORKFLOW).await
+    }
+
+    async fn setup_with(workflow: &str) -> Fixture {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = Arc::new(
+            FilesystemStorage::new(
+                dir.path().to_path_buf(),
+                "workflows".to_string(),
+                "executions".to_string(),
+                "checkpoints".to_string(),
+                "logs".to_string(),
+            )
+            .await
+            .unwrap(),
+        );
+
+        let metadata = WorkflowMetadata {
+            id: Uuid::new_v4(),
+            name: "Long Running".to_string(),
+            version: "1.0.0".to_string(),
+            description: None,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+            created_by: None,
+            tags: vec![],
+            is_active: true,
+        };
+        storage
+            .store_workflow(&parse_workflow(workflow).unwrap(), &metadata)
+            .await
+            .unwrap();
+
+        Fixture {
+            _dir: dir,
+            storage,
+            queue: Arc::new(MockQueue::new()),
+            hub: Arc::new(ExecutionHub::new()),
+            workflow_id: metadata.id,
+        }
+    }
+
+    impl Fixture {
+        async fn enqueue(&self) -> Uuid {
+            let execution = Execution {
+                id: Uuid::new_v4(),
+                workflow_id: self.workflow_id,
+                workflow_version: "1.0.0".to_string(),
+                status: ExecutionStatus::Queued,
+                started_at: None,
+                completed_at: None,
+                created_at: Utc::now(),
+                triggered_by: None,
+                trigger_type: "manual".to_string(),
+                input_params: None,
+                result: None,
+                error: None,
+                retry_count: 0,
+                parent_execution_id: None,
+                completed_tasks: 0,
+                total_tasks: 0,
+                usage: Default::default(),
+            };
+            self.storage.store_execution(&execution).await.unwrap();
+            self.queue
+                .enqueue(Job::new(self.workflow_id, execution.id, json!({})))
+                .await
+                .unwrap();
+            execution.id
+        }
+
+        fn start_worker(&self) -> tokio::task::JoinHandle<()> {
+            let worker = Worker::new(
+                "worker-1".to_string(),
+                self.queue.clone(),
+                self.storage.clone(),
+                1,
+            )
+            .with_hub(Arc::clone(&self.hub));
+            tokio::spawn(async move { worker.run().await })
+        }
+
+        /// Mark the execution cancelled in storage, as the cancel endpoint does
+        async fn cancel_in_storage(&self, execution_id: Uuid) {
+            let mut execution = self
+                .storage
+                .get_execution(execution_id)
+                .await
+                .unwrap()
+                .unwrap();
+            execution.status = ExecutionStatus::Cancelled;
+            execution.completed_at = Some(Utc::now());
+            self.storage
+                .update_execution(execution_id, &execution)
+                .await
+                .unwrap();
+        }
+
+        /// Wait for the final stream message of an execution
+        async fn final_message(
+            &self,
+            updates: &mut tokio::sync::broadcast::Receiver<ExecutionStreamMessage>,
+        ) -> ExecutionStreamMessage {
+            tokio::time::timeout(Duration::from_secs(15), async {
+                loop {
+                    let message = updates.recv().await.unwrap();
+                    if message.is_final() {
+                        return message;
+                    }
+                }
+            })
+            .await
+            .expect("execution should stop well before its script finishes")
+        }
+    }
+
+    #[tokio::test]
+    async fn test_worker_stops_execution_cancelled_through_hub() {
+        let fixture = setup().await;
+        let execution_id = fixture.enqueue().await;
+        let mut updates = fixture.hub.subscribe(execution_id);
+        let handle = fixture.start_worker();
+
+        // Wait until the task is running, then cancel as the API does
+        loop {
+            if let ExecutionStreamMessage::TaskUpdate { status, .. } = updates.recv().await.unwrap()
+            {
+                if status == "running" {
+                    break;
+                }
+            }
+        }
+        fixture.cancel_in_storage(execution_id).await;
+        assert!(fixture.hub.request_cancel(execution_id));
+
+        let message = fixture.final_message(&mut updates).await;
+        handle.abort();
+
+        match message {
+            ExecutionStreamMessage::Failed { error, .. } => {
+                assert_eq!(error, "Execution cancelled")
+            }
+            other => panic!("unexpected final message {:?}", other),
+        }
+        let stored = fixture
+            .storage
+            .get_execution(execution_id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(stored.status, ExecutionStatus::Cancelled);
+
+        // Cancelled jobs are completed, not retried
+        assert_eq!(fixture.queue.completed_count(), 1);
+        assert_eq!(fixture.queue.requeue_count(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_worker_notices_cancellation_in_storage() {
+        let fixture = setup().await;
+        let execution_id = fixture.enqueue().await;
+        let mut updates = fixture.hub.subscribe(execution_id);
+        let handle = fixture.start_worker();
+
+        tokio::time::sleep(Duration::from_millis(500)).await;
+        // Cancelled by a server in another process: only storage changes
+        fixture.cancel_in_storage(execution_id).await;
+
+        let message = fixture.final_message(&mut updates).await;
+        handle.abort();
+
+        assert!(matches!(message, ExecutionStreamMessage::Failed { .. }));
+        let stored = fixture
+            .storage
+            .get_execution(execution_id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(stored.status, ExecutionStatus::Cancelled);
+        assert_eq!(fixture.queue.completed_count(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_worker_keeps_cancellation_of_workflow_finishing_meanwhile() {
+        // Finishes before the worker polls storage for cancellation
+        let fixture = setup_with(
+            r#"
+name: "Short"
+version: "1.0.0"
+tasks:
+  wait:
+    description: "Wait"
+    script:
+      language: bash
+      content: "sleep 1"
+"#,
+        )
+        .await;
+        let execution_id = fixture.enqueue().await;
+        let mut updates = fixture.hub.subscribe(execution_id);
+        let handle = fixture.start_worker();
+
+        tokio::time::sleep(Duration::from_millis(300)).await;
+        fixture.cancel_in_storage(execution_id).await;
+
+        let message = fixture.final_message(&mut updates).await;
+        handle.abort();
+
+        assert!(matches!(message, ExecutionStreamMessage::Failed { .. }));
+        let stored = fixture
+            .storage
+            .get_execution(execution_id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(stored.status, ExecutionStatus::Cancelled);
+        assert_eq!(fixture.queue.completed_count(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_worker_skips_execution_cancelled_while_queued() {
+        let fixture = setup().await;
+        let execution_id = fixture.enqueue().await;
+        fixture.cancel_in_storage(execution_id).await;
+
+        let handle = fixture.start_worker();
+        tokio::time::sleep(Duration::from_millis(500)).await;
+        handle.abort();
+
+        let stored = fixture
+            .storage
+            .get_execution(execution_id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(stored.status, ExecutionStatus::Cancelled);
+        assert!(stored.started_at.is_none());
+        assert_eq!(fixture.queue.completed_count(), 1);
+    }
+}
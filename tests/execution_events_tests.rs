@@ -0,0 +1,228 @@
+//! Execution Events Tests
+//!
+//! Verifies that the executor reports workflow, task and loop progress as typed
+//! events to subscribed observers and event channels.
+
+use periplon_sdk::dsl::events::{ExecutionEvent, OutputStream, RetryReason};
+use periplon_sdk::dsl::{parse_workflow, DSLExecutor};
+use std::sync::{Arc, Mutex};
+
+/// Short label of an event for comparing sequences
+///
+/// Console lines (progress, warnings and task output) have no label.
+fn label(event: &ExecutionEvent) -> Option<String> {
+    Some(match event {
+        ExecutionEvent::WorkflowStarted { .. } => "workflow_started".to_string(),
+        ExecutionEvent::WorkflowFinished { success, .. } => {
+            format!("workflow_finished:{}", success)
+        }
+        ExecutionEvent::TaskStarted { task_id, .. } => format!("started:{}", task_id),
+        ExecutionEvent::TaskRetrying { task_id, .. } => format!("retrying:{}", task_id),
+        ExecutionEvent::TaskFallback { task_id, .. } => format!("fallback:{}", task_id),
+        ExecutionEvent::TaskSucceeded { task_id, .. } => format!("succeeded:{}", task_id),
+        ExecutionEvent::TaskFailed { task_id, .. } => format!("failed:{}", task_id),
+        ExecutionEvent::TaskSkipped { task_id, .. } => format!("skipped:{}", task_id),
+        ExecutionEvent::LoopIterationStarted { iteration, .. } => {
+            format!("iteration_started:{}", iteration)
+        }
+        ExecutionEvent::LoopIterationFinished { iteration, .. } => {
+            format!("iteration_finished:{}", iteration)
+        }
+        ExecutionEvent::AgentMessage { task_id, .. } => format!("agent_message:{}", task_id),
+        ExecutionEvent::DefinitionOfDoneChecked { task_id, .. } => format!("dod:{}", task_id),
+        ExecutionEvent::UsageRecorded { task_id, .. } => format!("usage:{}", task_id),
+        ExecutionEvent::ToolDenied { task_id, tool, .. } => {
+            format!("tool_denied:{}:{}", task_id, tool)
+        }
+        ExecutionEvent::NotificationSent { .. } => "notification".to_string(),
+        ExecutionEvent::Progress { .. }
+        | ExecutionEvent::Warning { .. }
+        | ExecutionEvent::TaskOutput { .. } => return None,
+    })
+}
+
+#[tokio::test]
+async fn test_events_follow_workflow_progress() {
+    let yaml = r#"
+name: "Events"
+version: "1.0.0"
+tasks:
+  build:
+    description: "Build"
+    script:
+      language: bash
+      content: "echo built"
+  repeat:
+    description: "Repeat twice"
+    depends_on: [build]
+    loop:
+      type: repeat
+      count: 2
+    script:
+      language: bash
+      content: "echo again"
+  publish:
+    description: "Publish"
+    depends_on: [repeat]
+    condition:
+      type: expression
+      expression: "1 > 2"
+    script:
+      language: bash
+      content: "echo published"
+"#;
+
+    let mut executor = DSLExecutor::new(parse_workflow(yaml).unwrap()).unwrap();
+    executor.set_console_output(false);
+
+    let seen = Arc::new(Mutex::new(Vec::new()));
+    let log = seen.clone();
+    executor.add_observer(Arc::new(move |event: &ExecutionEvent| {
+        log.lock().unwrap().extend(label(event));
+    }));
+    let mut channel = executor.events().channel();
+
+    executor.initialize().await.unwrap();
+    executor.execute().await.unwrap();
+
+    let expected = vec![
+        "workflow_started",
+        "started:build",
+        "succeeded:build",
+        "started:repeat",
+        "iteration_started:0",
+        "iteration_finished:0",
+        "iteration_started:1",
+        "iteration_finished:1",
+        "succeeded:repeat",
+        "started:publish",
+        "skipped:publish",
+        "workflow_finished:true",
+    ];
+    assert_eq!(*seen.lock().unwrap(), expected);
+
+    // The channel received the same events
+    let mut received = Vec::new();
+    while let Ok(event) = channel.try_recv() {
+        received.extend(label(&event));
+    }
+    assert_eq!(received, expected);
+}
+
+#[tokio::test]
+async fn test_failed_task_reports_retries_and_failure() {
+    let yaml = r#"
+name: "Failing Events"
+version: "1.0.0"
+tasks:
+  flaky:
+    description: "Always fails"
+    script:
+      language: bash
+      content: "exit 3"
+    on_error:
+      retry: 1
+      retry_delay_secs: 0
+"#;
+
+    let mut executor = DSLExecutor::new(parse_workflow(yaml).unwrap()).unwrap();
+    executor.set_console_output(false);
+    let mut channel = executor.events().channel();
+
+    executor.initialize().await.unwrap();
+    assert!(executor.execute().await.is_err());
+
+    let mut events = Vec::new();
+    while let Ok(event) = channel.try_recv() {
+        if label(&event).is_some() {
+            events.push(event);
+        }
+    }
+    let labels: Vec<_> = events.iter().filter_map(label).collect();
+    assert_eq!(
+        labels,
+        vec![
+            "workflow_started",
+            "started:flaky",
+            "retrying:flaky",
+            "failed:flaky",
+            "workflow_finished:false",
+        ]
+    );
+
+    assert!(matches!(
+        &events[2],
+        ExecutionEvent::TaskRetrying {
+            attempt: 1,
+            reason: RetryReason::Error,
+            delay_secs: 0,
+            ..
+        }
+    ));
+    let ExecutionEvent::WorkflowFinished { error, .. } = &events[4] else {
+        panic!("expected workflow_finished, got {:?}", events[4]);
+    };
+    assert!(error.is_some());
+}
+
+#[tokio::test]
+async fn test_console_output_is_reported_as_events() {
+    let yaml = r#"
+name: "Console Events"
+version: "1.0.0"
+tasks:
+  greet:
+    description: "Greet"
+    script:
+      language: bash
+      content: "echo hello; echo careful >&2"
+"#;
+
+    let mut executor = DSLExecutor::new(parse_workflow(yaml).unwrap()).unwrap();
+    executor.set_console_output(false);
+    let mut channel = executor.events().channel();
+
+    executor.initialize().await.unwrap();
+    executor.execute().await.unwrap();
+
+    let mut progress = Vec::new();
+    let mut output = Vec::new();
+    while let Ok(event) = channel.try_recv() {
+        match event {
+            ExecutionEvent::Progress { task_id, message } => progress.push((task_id, message)),
+            ExecutionEvent::TaskOutput {
+                task_id,
+                stream,
+                text,
+            } => output.push((task_id, stream, text)),
+            _ => {}
+        }
+    }
+
+    assert_eq!(
+        progress,
+        vec![
+            (None, "Initialized 0 agents and 0 channels".to_string()),
+            (None, "Initialized workflow state tracking".to_string()),
+            (
+                Some("greet".to_string()),
+                "Executing bash script...".to_string()
+            ),
+        ]
+    );
+    assert_eq!(
+        output,
+        vec![
+            (
+                "greet".to_string(),
+                OutputStream::Stdout,
+                "hello\n".to_string()
+            ),
+            (
+                "greet".to_string(),
+                OutputStream::Stderr,
+                "careful\n".to_string()
+            ),
+        ]
+    );
+}
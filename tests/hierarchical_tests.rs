@@ -1,6 +1,6 @@
 //! Tests for hierarchical task execution
 
-use periplon_sdk::dsl::{parse_workflow, validate_workflow};
+use periplon_sdk::dsl::{parse_workflow, validate_workflow, DSLExecutor, TaskStatus};
 
 /// Test that hierarchical tasks are properly represented in the workflow structure
 #[test]
@@ -105,3 +105,42 @@ fn test_parse_file_organizer_with_subtasks() {
     let parent_task = workflow.tasks.get("organize_downloads").unwrap();
     assert_eq!(parent_task.subtasks.len(), 3);
 }
+
+#[tokio::test]
+async fn test_nested_subtasks_run_without_their_parents() {
+    let yaml = r#"
+name: "Nested Subtasks"
+version: "1.0.0"
+
+tasks:
+  grandparent:
+    description: "Grandparent task"
+    subtasks:
+      - parent:
+          description: "Parent task"
+          subtasks:
+            - child:
+                description: "Child task"
+                script:
+                  language: bash
+                  content: "true"
+"#;
+
+    let workflow = parse_workflow(yaml).unwrap();
+    let mut executor = DSLExecutor::new(workflow).unwrap();
+    executor.set_console_output(false);
+    executor.initialize().await.unwrap();
+
+    // Only the leaf is scheduled, with no dependency on its organizational parents
+    let graph = executor.task_graph();
+    assert_eq!(graph.get_all_tasks(), vec!["grandparent.parent.child"]);
+    let child = graph.get_task("grandparent.parent.child").unwrap();
+    assert!(child.spec.depends_on.is_empty());
+
+    executor.execute().await.unwrap();
+    let state = executor.get_state().unwrap();
+    assert_eq!(
+        state.get_task_status("grandparent.parent.child"),
+        Some(TaskStatus::Completed)
+    );
+}
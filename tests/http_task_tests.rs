@@ -0,0 +1,224 @@
+//! HTTP Task Execution Tests
+//!
+//! Runs `http` tasks end-to-end against a fake in-process HTTP server.
+
+mod common {
+    pub mod http;
+}
+
+use common::http::{CannedResponse, FakeServer};
+use periplon_sdk::dsl::executor::DSLExecutor;
+use periplon_sdk::dsl::parse_workflow;
+
+/// Start a server that answers every request with the given status and body
+async fn start_server(status: u16, body: &str) -> FakeServer {
+    FakeServer::answering(
+        CannedResponse::json(body)
+            .status(status)
+            .header("X-Test", "yes"),
+    )
+    .await
+}
+
+#[tokio::test]
+async fn test_http_task_records_response_as_output() {
+    let server = start_server(200, r#"{"items":[{"id":7}]}"#).await;
+    let base_url = &server.url;
+    let state_dir = tempfile::tempdir().unwrap();
+
+    let yaml = format!(
+        r#"
+name: "HTTP Task Test"
+version: "1.0.0"
+inputs:
+  resource:
+    type: string
+    default: "items"
+secrets:
+  api_token:
+    source:
+      type: value
+      value: "s3cr3t"
+tasks:
+  fetch:
+    description: "Fetch items"
+    http:
+      method: POST
+      url: "{base_url}/${{workflow.resource}}"
+      headers:
+        X-Resource: "${{workflow.resource}}"
+      body: '{{"name": "${{workflow.resource}}"}}'
+      auth:
+        type: bearer
+        token: "${{secret.api_token}}"
+"#
+    );
+
+    let workflow = parse_workflow(&yaml).expect("Failed to parse workflow");
+    let mut executor = DSLExecutor::new(workflow).unwrap();
+    executor
+        .enable_state_persistence(Some(state_dir.path().to_str().unwrap()))
+        .unwrap();
+    executor.initialize().await.unwrap();
+    executor.execute().await.expect("HTTP task should succeed");
+
+    let received = server.requests();
+    assert_eq!(received.len(), 1);
+    let request = &received[0].raw;
+    assert!(request.starts_with("POST /items HTTP/1.1"));
+    assert!(request.to_lowercase().contains("x-resource: items"));
+    assert!(request
+        .to_lowercase()
+        .contains("authorization: bearer s3cr3t"));
+    assert!(request.ends_with(r#"{"name": "items"}"#));
+
+    let state = executor.get_state().expect("state should be tracked");
+    let output = state.get_task_output("fetch").expect("output recorded");
+    let parsed: serde_json::Value = serde_json::from_str(&output.content).unwrap();
+    assert_eq!(parsed["status"], 200);
+    assert_eq!(parsed["headers"]["x-test"], "yes");
+    assert_eq!(parsed["body"]["items"][0]["id"], 7);
+}
+
+#[tokio::test]
+async fn test_http_task_error_status_uses_retry_policy() {
+    let server = start_server(503, r#"{"error":"unavailable"}"#).await;
+    let base_url = &server.url;
+
+    let yaml = format!(
+        r#"
+name: "HTTP Retry Test"
+version: "1.0.0"
+tasks:
+  flaky:
+    description: "Call a failing endpoint"
+    http:
+      method: GET
+      url: "{base_url}/health"
+    on_error:
+      retry: 2
+      retry_delay_secs: 0
+"#
+    );
+
+    let workflow = parse_workflow(&yaml).expect("Failed to parse workflow");
+    let mut executor = DSLExecutor::new(workflow).unwrap();
+    executor.initialize().await.unwrap();
+    let result = executor.execute().await;
+
+    let err = result.expect_err("non-2xx response should fail the task");
+    assert!(err.to_string().contains("503"));
+    // Initial attempt plus two retries
+    assert_eq!(server.requests().len(), 3);
+}
+
+#[tokio::test]
+async fn test_http_task_error_quotes_redacted_response_body() {
+    let body = format!(
+        r#"{{"error":"token s3cr3t-t0ken rejected","detail":"{}"}}"#,
+        "x".repeat(2000)
+    );
+    let server = start_server(401, &body).await;
+    let base_url = &server.url;
+
+    let yaml = format!(
+        r#"
+name: "HTTP Error Body Test"
+version: "1.0.0"
+secrets:
+  api_token:
+    source:
+      type: value
+      value: "s3cr3t-t0ken"
+tasks:
+  call:
+    description: "Call with a rejected token"
+    http:
+      method: GET
+      url: "{base_url}/items?token=${{secret.api_token}}"
+"#
+    );
+
+    let workflow = parse_workflow(&yaml).expect("Failed to parse workflow");
+    let mut executor = DSLExecutor::new(workflow).unwrap();
+    executor.initialize().await.unwrap();
+    let err = executor.execute().await.expect_err("401 fails the task");
+    let message = err.to_string();
+
+    assert!(message.contains("401"));
+    assert!(
+        message.contains(r#"{"error":"token [REDACTED] rejected""#),
+        "{}",
+        message
+    );
+    assert!(!message.contains("s3cr3t-t0ken"));
+    // Long bodies are cut short
+    assert!(message.contains("xxx..."));
+    assert!(message.len() < 1000);
+}
+
+#[tokio::test]
+async fn test_http_task_send_error_redacts_url() {
+    // Reserve a port and close it again so the connection is refused
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+    drop(listener);
+
+    let yaml = format!(
+        r#"
+name: "HTTP Send Error Test"
+version: "1.0.0"
+secrets:
+  api_token:
+    source:
+      type: value
+      value: "s3cr3t-t0ken"
+tasks:
+  call:
+    description: "Call a server that is not listening"
+    http:
+      method: GET
+      url: "http://127.0.0.1:{port}/items?token=${{secret.api_token}}"
+"#
+    );
+
+    let workflow = parse_workflow(&yaml).expect("Failed to parse workflow");
+    let mut executor = DSLExecutor::new(workflow).unwrap();
+    executor.initialize().await.unwrap();
+    let err = executor.execute().await.expect_err("connection is refused");
+    let message = err.to_string();
+
+    assert!(message.contains("token=[REDACTED]"), "{}", message);
+    assert!(!message.contains("s3cr3t-t0ken"), "{}", message);
+}
+
+#[tokio::test]
+async fn test_http_task_undefined_secret_fails() {
+    let server = start_server(200, "{}").await;
+    let base_url = &server.url;
+
+    let yaml = format!(
+        r#"
+name: "HTTP Secret Test"
+version: "1.0.0"
+tasks:
+  call:
+    description: "Call with missing secret"
+    http:
+      method: GET
+      url: "{base_url}/"
+      auth:
+        type: api_key
+        header: "X-API-Key"
+        key: "${{secret.missing}}"
+"#
+    );
+
+    let workflow = parse_workflow(&yaml).expect("Failed to parse workflow");
+    let mut executor = DSLExecutor::new(workflow).unwrap();
+    executor.initialize().await.unwrap();
+    let err = executor.execute().await.expect_err("missing secret");
+
+    assert!(err.to_string().contains("missing"));
+    assert!(server.requests().is_empty());
+}
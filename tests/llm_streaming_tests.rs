@@ -0,0 +1,175 @@
+//! LLM Streaming Tests
+//!
+//! Verifies that `HttpLlmClient` streams deltas and sends the full message
+//! history, and that `llm` tasks with `stream: true` record the streamed text.
+
+mod common {
+    pub mod http;
+}
+
+use common::http::{CannedResponse, FakeServer};
+use futures::StreamExt;
+use periplon_sdk::adapters::secondary::HttpLlmClient;
+use periplon_sdk::domain::Provider;
+use periplon_sdk::dsl::executor::DSLExecutor;
+use periplon_sdk::dsl::parse_workflow;
+use periplon_sdk::ports::secondary::{LlmClient, LlmMessage, LlmRequest, LlmStreamEvent};
+use serde_json::json;
+use std::collections::HashMap;
+
+/// Start a fake server answering every request with the given body, written
+/// in small pieces so that events arrive split across chunks
+async fn start_server(content_type: &str, body: String) -> FakeServer {
+    FakeServer::answering(CannedResponse::new(content_type, body).in_pieces(7)).await
+}
+
+fn ollama_stream(tokens: &[&str]) -> String {
+    let mut body = String::new();
+    for token in tokens {
+        body.push_str(
+            &json!({"message": {"role": "assistant", "content": token}, "done": false}).to_string(),
+        );
+        body.push('\n');
+    }
+    body.push_str(
+        &json!({
+            "message": {"role": "assistant", "content": ""},
+            "done": true,
+            "done_reason": "stop",
+            "prompt_eval_count": 12,
+            "eval_count": 3,
+        })
+        .to_string(),
+    );
+    body.push('\n');
+    body
+}
+
+fn request(provider: Provider, endpoint: String) -> LlmRequest {
+    LlmRequest {
+        provider,
+        model: "test-model".to_string(),
+        prompt: "And in French?".to_string(),
+        system_prompt: Some("Answer briefly".to_string()),
+        endpoint: Some(endpoint),
+        api_key: Some("test-key".to_string()),
+        auth: None,
+        auth_header: None,
+        api_version: None,
+        temperature: None,
+        max_tokens: None,
+        top_p: None,
+        top_k: None,
+        stop: vec![],
+        timeout_secs: None,
+        extra_params: HashMap::new(),
+        response_schema: None,
+        messages: vec![
+            LlmMessage::user("Say hello"),
+            LlmMessage::assistant("Hello"),
+        ],
+        tools: vec![],
+        max_tool_iterations: None,
+    }
+}
+
+async fn collect(client: &HttpLlmClient, request: LlmRequest) -> (Vec<String>, String, u32) {
+    let mut stream = client.execute_stream(request).await.unwrap();
+    let mut deltas = Vec::new();
+    let mut done = None;
+    while let Some(event) = stream.next().await {
+        match event.unwrap() {
+            LlmStreamEvent::Delta(text) => deltas.push(text),
+            LlmStreamEvent::Done(response) => done = Some(response),
+        }
+    }
+    let response = done.expect("stream ends with Done");
+    let total = response.usage.map(|u| u.total_tokens).unwrap_or(0);
+    (deltas, response.content, total)
+}
+
+#[tokio::test]
+async fn test_ollama_streams_ndjson_deltas() {
+    let server = start_server("application/x-ndjson", ollama_stream(&["Bon", "jour", "!"])).await;
+
+    let client = HttpLlmClient::new();
+    let (deltas, content, total) =
+        collect(&client, request(Provider::Ollama, server.url.clone())).await;
+
+    assert_eq!(deltas, vec!["Bon", "jour", "!"]);
+    assert_eq!(content, "Bonjour!");
+    assert_eq!(total, 15);
+
+    let requests = server.json_bodies();
+    assert_eq!(requests[0]["stream"], json!(true));
+    let roles: Vec<&str> = requests[0]["messages"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|m| m["role"].as_str().unwrap())
+        .collect();
+    assert_eq!(roles, vec!["system", "user", "assistant", "user"]);
+}
+
+#[tokio::test]
+async fn test_openai_streams_sse_deltas() {
+    let body = [
+        r#"data: {"model":"gpt-test","choices":[{"delta":{"role":"assistant"}}]}"#,
+        r#"data: {"choices":[{"delta":{"content":"Bon"}}]}"#,
+        r#"data: {"choices":[{"delta":{"content":"jour"},"finish_reason":"stop"}]}"#,
+        r#"data: {"choices":[],"usage":{"prompt_tokens":9,"completion_tokens":2,"total_tokens":11}}"#,
+        "data: [DONE]",
+    ]
+    .join("\n\n")
+        + "\n\n";
+    let server = start_server("text/event-stream", body).await;
+
+    let client = HttpLlmClient::new();
+    let (deltas, content, total) =
+        collect(&client, request(Provider::OpenAI, server.url.clone())).await;
+
+    assert_eq!(deltas, vec!["Bon", "jour"]);
+    assert_eq!(content, "Bonjour");
+    assert_eq!(total, 11);
+    let requests = server.json_bodies();
+    assert_eq!(requests[0]["stream_options"]["include_usage"], json!(true));
+    assert_eq!(requests[0]["messages"][2]["content"], json!("Hello"));
+}
+
+#[tokio::test]
+async fn test_streaming_llm_task_records_full_output() {
+    let server = start_server(
+        "application/x-ndjson",
+        ollama_stream(&["{\"ok\":", " true}"]),
+    )
+    .await;
+
+    let yaml = format!(
+        r#"
+name: "Streaming LLM"
+version: "1.0.0"
+tasks:
+  ask:
+    description: "Ask the model"
+    llm:
+      provider: ollama
+      model: "test-model"
+      endpoint: "{}"
+      prompt: "Reply with JSON"
+      stream: true
+"#,
+        server.url
+    );
+
+    let workflow = parse_workflow(&yaml).unwrap();
+    let mut executor = DSLExecutor::new(workflow).unwrap();
+    executor.initialize().await.unwrap();
+    executor.execute().await.unwrap();
+
+    let output = executor
+        .get_state()
+        .unwrap()
+        .get_task_output("ask")
+        .unwrap();
+    assert_eq!(output.structured(), json!({"ok": true}));
+}
@@ -0,0 +1,266 @@
+//! LLM Tool Calling Tests
+//!
+//! Verifies that `llm` tasks offer their tools to the model, run the calls it
+//! makes (other tasks and built-in tools), and send the results back until the
+//! model answers.
+
+mod common {
+    pub mod http;
+}
+
+use common::http::{CannedResponse, FakeServer};
+use periplon_sdk::dsl::executor::DSLExecutor;
+use periplon_sdk::dsl::{parse_workflow, validate_workflow};
+use serde_json::{json, Value};
+
+/// Start a fake provider answering successive requests with the given JSON
+/// bodies (the last one repeats)
+async fn start_server(responses: Vec<Value>) -> FakeServer {
+    FakeServer::start(responses.into_iter().map(CannedResponse::json).collect()).await
+}
+
+#[tokio::test]
+async fn test_ollama_model_calls_task_and_builtin_tools() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(dir.path().join("notes.txt"), "remember the milk").unwrap();
+
+    let server = start_server(vec![
+        json!({
+            "message": {
+                "role": "assistant",
+                "content": "",
+                "tool_calls": [
+                    {"function": {"name": "weather", "arguments": {"city": "Lyon"}}},
+                    {"function": {"name": "read_file", "arguments": {"path": "notes.txt"}}}
+                ]
+            },
+            "done": true
+        }),
+        json!({
+            "message": {"role": "assistant", "content": "Sunny in Lyon; buy milk."},
+            "done": true,
+            "prompt_eval_count": 10,
+            "eval_count": 5
+        }),
+    ])
+    .await;
+
+    let yaml = format!(
+        r#"
+name: "Tool Calling"
+version: "1.0.0"
+cwd: "{cwd}"
+tasks:
+  weather:
+    description: "Get the weather for a city"
+    inputs:
+      city: "Paris"
+    script:
+      language: bash
+      content: "echo \"sunny in ${{task.city}}\""
+
+  assistant:
+    description: "Answer using tools"
+    llm:
+      provider: ollama
+      model: "test-model"
+      endpoint: "{endpoint}"
+      prompt: "What's the weather and what did I note?"
+      tools:
+        - type: task
+          task: weather
+        - type: builtin
+          name: read_file
+"#,
+        cwd = dir.path().display(),
+        endpoint = server.url
+    );
+
+    let workflow = parse_workflow(&yaml).unwrap();
+    validate_workflow(&workflow).unwrap();
+    let mut executor = DSLExecutor::new(workflow).unwrap();
+    executor.initialize().await.unwrap();
+    executor.execute().await.unwrap();
+
+    let requests = server.json_bodies();
+    assert_eq!(requests.len(), 2);
+
+    // Tools are declared in the function format, with parameters from the task inputs
+    let tools = requests[0]["tools"].as_array().unwrap();
+    assert_eq!(tools[0]["function"]["name"], json!("weather"));
+    assert_eq!(
+        tools[0]["function"]["parameters"]["properties"]["city"]["type"],
+        json!("string")
+    );
+
+    // The second request carries the assistant's calls and both results
+    let messages = requests[1]["messages"].as_array().unwrap();
+    let results: Vec<&str> = messages
+        .iter()
+        .filter(|m| m["role"] == "tool")
+        .map(|m| m["content"].as_str().unwrap().trim())
+        .collect();
+    assert_eq!(results, vec!["sunny in Lyon", "remember the milk"]);
+
+    let output = executor
+        .get_state()
+        .unwrap()
+        .get_task_output("assistant")
+        .unwrap();
+    assert_eq!(output.content, "Sunny in Lyon; buy milk.");
+    // The tool task only ran when called
+    assert!(executor
+        .get_state()
+        .unwrap()
+        .get_task_output("weather")
+        .is_none());
+}
+
+#[tokio::test]
+async fn test_openai_tool_errors_are_reported_to_the_model() {
+    let server = start_server(vec![
+        json!({
+            "choices": [{
+                "message": {
+                    "role": "assistant",
+                    "content": null,
+                    "tool_calls": [{
+                        "id": "call_1",
+                        "type": "function",
+                        "function": {"name": "read_file", "arguments": "{\"path\": \"/etc/hostname\"}"}
+                    }]
+                },
+                "finish_reason": "tool_calls"
+            }]
+        }),
+        json!({
+            "choices": [{
+                "message": {"role": "assistant", "content": "I cannot read that file."},
+                "finish_reason": "stop"
+            }]
+        }),
+    ])
+    .await;
+
+    let yaml = format!(
+        r#"
+name: "Denied Tool"
+version: "1.0.0"
+tasks:
+  assistant:
+    description: "Try to read outside the sandbox"
+    llm:
+      provider: openai
+      model: "gpt-test"
+      endpoint: "{}"
+      api_key: "test-key"
+      prompt: "Read /etc/hostname"
+      tools:
+        - type: builtin
+          name: read_file
+"#,
+        server.url
+    );
+
+    let workflow = parse_workflow(&yaml).unwrap();
+    let mut executor = DSLExecutor::new(workflow).unwrap();
+    executor.initialize().await.unwrap();
+    executor.execute().await.unwrap();
+
+    let requests = server.json_bodies();
+    let messages = requests[1]["messages"].as_array().unwrap();
+    let result = messages.last().unwrap();
+    assert_eq!(result["role"], json!("tool"));
+    assert_eq!(result["tool_call_id"], json!("call_1"));
+    assert!(result["content"]
+        .as_str()
+        .unwrap()
+        .contains("outside the allowed directories"));
+}
+
+#[test]
+fn test_builtin_tools_require_permission_mode() {
+    let yaml = r#"
+name: "Shell Tool"
+version: "1.0.0"
+tasks:
+  assistant:
+    description: "Run commands"
+    llm:
+      provider: ollama
+      model: "test-model"
+      prompt: "List files"
+      tools:
+        - type: builtin
+          name: shell
+"#;
+
+    let workflow = parse_workflow(yaml).unwrap();
+    let err = validate_workflow(&workflow).unwrap_err().to_string();
+    assert!(err.contains("built-in tool 'shell' requires permissions mode 'bypassPermissions'"));
+
+    let allowed = yaml.replace(
+        "      tools:",
+        "      permissions:\n        mode: bypassPermissions\n      tools:",
+    );
+    validate_workflow(&parse_workflow(&allowed).unwrap()).unwrap();
+}
+
+#[tokio::test]
+async fn test_shell_commands_are_stopped_after_the_task_timeout() {
+    let server = start_server(vec![
+        json!({
+            "message": {
+                "role": "assistant",
+                "content": "",
+                "tool_calls": [
+                    {"function": {"name": "shell", "arguments": {"command": "sleep 30"}}}
+                ]
+            },
+            "done": true
+        }),
+        json!({
+            "message": {"role": "assistant", "content": "The command hung."},
+            "done": true
+        }),
+    ])
+    .await;
+
+    let yaml = format!(
+        r#"
+name: "Hanging Shell"
+version: "1.0.0"
+tasks:
+  assistant:
+    description: "Run a command that never finishes"
+    llm:
+      provider: ollama
+      model: "test-model"
+      endpoint: "{}"
+      prompt: "Wait for a while"
+      timeout_secs: 1
+      permissions:
+        mode: bypassPermissions
+      tools:
+        - type: builtin
+          name: shell
+"#,
+        server.url
+    );
+
+    let workflow = parse_workflow(&yaml).unwrap();
+    let mut executor = DSLExecutor::new(workflow).unwrap();
+    executor.initialize().await.unwrap();
+    let started = std::time::Instant::now();
+    executor.execute().await.unwrap();
+    assert!(started.elapsed() < std::time::Duration::from_secs(10));
+
+    let requests = server.json_bodies();
+    let messages = requests[1]["messages"].as_array().unwrap();
+    let result = messages.last().unwrap();
+    assert_eq!(result["role"], json!("tool"));
+    assert!(result["content"]
+        .as_str()
+        .unwrap()
+        .contains("Command timed out after 1 seconds"));
+}
@@ -0,0 +1,346 @@
+//! MCP Tool Task Execution Tests
+//!
+//! Runs `mcp_tool` tasks end-to-end against a stand-in stdio MCP server
+//! implemented as a small shell script, and against a stand-in server speaking
+//! the legacy HTTP+SSE transport.
+
+use periplon_sdk::dsl::executor::DSLExecutor;
+use periplon_sdk::dsl::{parse_workflow, validate_workflow};
+use serde_json::{json, Value};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::{mpsc, Mutex};
+
+/// Stand-in MCP server speaking newline-delimited JSON-RPC on stdio
+///
+/// Supports `initialize`, `tools/list` and `tools/call` for a `greet` tool
+/// (text result) and a `count` tool (structured result). Calling `fail`
+/// returns a tool error. Every request line is appended to `$MCP_LOG`.
+const STAND_IN_SERVER: &str = r#"#!/bin/sh
+while IFS= read -r line; do
+  [ -n "$MCP_LOG" ] && printf '%s\n' "$line" >> "$MCP_LOG"
+  id=$(printf '%s' "$line" | grep -o '"id":[0-9]*' | head -n 1 | cut -d: -f2)
+  [ -z "$id" ] && continue
+  case "$line" in
+    *'"method":"initialize"'*)
+      printf '{"jsonrpc":"2.0","id":%s,"result":{"protocolVersion":"2024-11-05","capabilities":{"tools":{}},"serverInfo":{"name":"stand-in","version":"0.1"}}}\n' "$id" ;;
+    *'"method":"tools/list"'*)
+      printf '{"jsonrpc":"2.0","id":%s,"result":{"tools":[{"name":"greet","inputSchema":{"type":"object"}},{"name":"count","inputSchema":{"type":"object"}},{"name":"fail","inputSchema":{"type":"object"}}]}}\n' "$id" ;;
+    *'"name":"greet"'*)
+      who=$(printf '%s' "$line" | grep -o '"who":"[^"]*"' | cut -d'"' -f4)
+      printf '{"jsonrpc":"2.0","method":"notifications/message","params":{"level":"info"}}\n'
+      printf '{"jsonrpc":"2.0","id":%s,"result":{"content":[{"type":"text","text":"Hello, %s! (%s)"}]}}\n' "$id" "$who" "$GREETING_SUFFIX" ;;
+    *'"name":"count"'*)
+      printf '{"jsonrpc":"2.0","id":%s,"result":{"content":[{"type":"text","text":"3"}],"structuredContent":{"count":3}}}\n' "$id" ;;
+    *'"name":"fail"'*)
+      printf '{"jsonrpc":"2.0","id":%s,"result":{"content":[{"type":"text","text":"disk on fire"}],"isError":true}}\n' "$id" ;;
+    *)
+      printf '{"jsonrpc":"2.0","id":%s,"error":{"code":-32601,"message":"unknown method"}}\n' "$id" ;;
+  esac
+done
+"#;
+
+fn write_server(dir: &Path) -> PathBuf {
+    let path = dir.join("stand_in_mcp.sh");
+    std::fs::write(&path, STAND_IN_SERVER).unwrap();
+    path
+}
+
+fn workflow_yaml(server: &Path, log: &Path, tasks: &str) -> String {
+    format!(
+        r#"
+name: "MCP Tool Test"
+version: "1.0.0"
+inputs:
+  person:
+    type: string
+    default: "Ada"
+secrets:
+  suffix:
+    source:
+      type: value
+      value: "from secret"
+mcp_servers:
+  stand_in:
+    type: stdio
+    command: "sh"
+    args: ["{server}"]
+    env:
+      MCP_LOG: "{log}"
+      GREETING_SUFFIX: "${{secret.suffix}}"
+tasks:
+{tasks}"#,
+        server = server.display(),
+        log = log.display(),
+    )
+}
+
+#[tokio::test]
+async fn test_mcp_tool_task_records_tool_result() {
+    let dir = tempfile::tempdir().unwrap();
+    let server = write_server(dir.path());
+    let log = dir.path().join("requests.log");
+
+    let yaml = workflow_yaml(
+        &server,
+        &log,
+        r#"
+  greet:
+    description: "Greet someone"
+    mcp_tool:
+      server: stand_in
+      tool: greet
+      parameters:
+        who: "${workflow.person}"
+        options:
+          tags: ["${workflow.person}", 1]
+  count:
+    description: "Count things"
+    depends_on: [greet]
+    mcp_tool:
+      server: stand_in
+      tool: count
+      timeout_secs: 10
+"#,
+    );
+
+    let workflow = parse_workflow(&yaml).expect("Failed to parse workflow");
+    validate_workflow(&workflow).expect("workflow should validate");
+    let mut executor = DSLExecutor::new(workflow).unwrap();
+    executor
+        .enable_state_persistence(Some(dir.path().join("state").to_str().unwrap()))
+        .unwrap();
+    executor.initialize().await.unwrap();
+    executor
+        .execute()
+        .await
+        .expect("MCP tool tasks should succeed");
+
+    let state = executor.get_state().expect("state should be tracked");
+    let greeting = state.get_task_output("greet").expect("greet output");
+    // The secret reached the server but is redacted from the output
+    assert_eq!(greeting.content, "Hello, Ada! ([REDACTED])");
+    let count = state.get_task_output("count").expect("count output");
+    assert_eq!(count.content, r#"{"count":3}"#);
+
+    // One server process handles the whole workflow: a single handshake
+    let requests = std::fs::read_to_string(&log).unwrap();
+    assert_eq!(requests.matches(r#""method":"initialize""#).count(), 1);
+    assert_eq!(requests.matches(r#""method":"tools/list""#).count(), 1);
+    assert!(requests.contains(r#""tags":["Ada",1]"#));
+
+    executor.shutdown().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_mcp_tool_error_result_fails_task() {
+    let dir = tempfile::tempdir().unwrap();
+    let server = write_server(dir.path());
+    let log = dir.path().join("requests.log");
+
+    let yaml = workflow_yaml(
+        &server,
+        &log,
+        r#"
+  broken:
+    description: "Call a failing tool"
+    mcp_tool:
+      server: stand_in
+      tool: fail
+"#,
+    );
+
+    let workflow = parse_workflow(&yaml).expect("Failed to parse workflow");
+    let mut executor = DSLExecutor::new(workflow).unwrap();
+    executor.initialize().await.unwrap();
+    let err = executor
+        .execute()
+        .await
+        .expect_err("tool error should fail");
+
+    assert!(err.to_string().contains("disk on fire"));
+}
+
+#[tokio::test]
+async fn test_mcp_tool_unknown_tool_fails() {
+    let dir = tempfile::tempdir().unwrap();
+    let server = write_server(dir.path());
+    let log = dir.path().join("requests.log");
+
+    let yaml = workflow_yaml(
+        &server,
+        &log,
+        r#"
+  missing:
+    description: "Call a tool the server does not provide"
+    mcp_tool:
+      server: stand_in
+      tool: teleport
+"#,
+    );
+
+    let workflow = parse_workflow(&yaml).expect("Failed to parse workflow");
+    let mut executor = DSLExecutor::new(workflow).unwrap();
+    executor.initialize().await.unwrap();
+    let err = executor
+        .execute()
+        .await
+        .expect_err("unknown tool should fail");
+
+    let message = err.to_string();
+    assert!(message.contains("does not provide tool 'teleport'"));
+    assert!(message.contains("greet"));
+    // The tool is never invoked
+    let requests = std::fs::read_to_string(&log).unwrap();
+    assert!(!requests.contains("tools/call"));
+}
+
+/// Start a stand-in MCP server speaking the legacy HTTP+SSE transport
+///
+/// `GET /sse` opens the event stream and announces `/messages` as the endpoint.
+/// Each message POSTed there is answered with 202 Accepted, and its response
+/// is sent on the event stream. Only a `greet` tool is provided.
+async fn start_sse_server() -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let (events_tx, events_rx) = mpsc::unbounded_channel::<Value>();
+    let events_rx = Arc::new(Mutex::new(events_rx));
+
+    tokio::spawn(async move {
+        while let Ok((socket, _)) = listener.accept().await {
+            tokio::spawn(handle_sse_connection(
+                socket,
+                events_tx.clone(),
+                events_rx.clone(),
+            ));
+        }
+    });
+
+    format!("http://{}/sse", addr)
+}
+
+async fn handle_sse_connection(
+    mut socket: TcpStream,
+    events_tx: mpsc::UnboundedSender<Value>,
+    events_rx: Arc<Mutex<mpsc::UnboundedReceiver<Value>>>,
+) {
+    // Read the head, then the body announced by Content-Length
+    let mut buf = Vec::new();
+    let mut chunk = [0u8; 4096];
+    let (head, body) = loop {
+        let n = socket.read(&mut chunk).await.unwrap_or(0);
+        if n == 0 {
+            return;
+        }
+        buf.extend_from_slice(&chunk[..n]);
+        let text = String::from_utf8_lossy(&buf).to_string();
+        if let Some(header_end) = text.find("\r\n\r\n") {
+            let content_length = text
+                .lines()
+                .find_map(|l| {
+                    l.to_ascii_lowercase()
+                        .strip_prefix("content-length:")
+                        .map(|v| v.trim().parse::<usize>().unwrap_or(0))
+                })
+                .unwrap_or(0);
+            if buf.len() >= header_end + 4 + content_length {
+                break (
+                    text[..header_end].to_string(),
+                    text[header_end + 4..].to_string(),
+                );
+            }
+        }
+    };
+
+    if head.starts_with("GET /sse") {
+        let _ = socket
+            .write_all(
+                b"HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nConnection: close\r\n\r\n\
+                  event: endpoint\r\ndata: /messages?session=1\r\n\r\n",
+            )
+            .await;
+        let mut events = events_rx.lock().await;
+        while let Some(message) = events.recv().await {
+            let event = format!("event: message\ndata: {}\n\n", message);
+            // Split each event so it arrives across reads
+            let (first, rest) = event.split_at(event.len() / 2);
+            for piece in [first, rest] {
+                if socket.write_all(piece.as_bytes()).await.is_err() {
+                    return;
+                }
+                let _ = socket.flush().await;
+            }
+        }
+        return;
+    }
+
+    let _ = socket
+        .write_all(b"HTTP/1.1 202 Accepted\r\nContent-Length: 0\r\nConnection: close\r\n\r\n")
+        .await;
+    let request: Value = serde_json::from_str(&body).unwrap_or(Value::Null);
+    let Some(id) = request.get("id").cloned() else {
+        return;
+    };
+    let result = match request["method"].as_str() {
+        Some("initialize") => json!({
+            "protocolVersion": "2024-11-05",
+            "capabilities": {"tools": {}},
+            "serverInfo": {"name": "stand-in-sse", "version": "0.1"},
+        }),
+        Some("tools/list") => json!({
+            "tools": [{"name": "greet", "inputSchema": {"type": "object"}}],
+        }),
+        _ => json!({
+            "content": [{
+                "type": "text",
+                "text": format!("Hello over SSE, {}!", request["params"]["arguments"]["who"].as_str().unwrap_or("?")),
+            }],
+        }),
+    };
+    let _ = events_tx.send(json!({"jsonrpc": "2.0", "id": id, "result": result}));
+}
+
+#[tokio::test]
+async fn test_mcp_tool_task_over_legacy_sse() {
+    let url = start_sse_server().await;
+    let yaml = format!(
+        r#"
+name: "MCP SSE Test"
+version: "1.0.0"
+mcp_servers:
+  legacy:
+    type: sse
+    url: "{url}"
+tasks:
+  greet:
+    description: "Greet over SSE"
+    mcp_tool:
+      server: legacy
+      tool: greet
+      timeout_secs: 10
+      parameters:
+        who: "Grace"
+"#
+    );
+
+    let dir = tempfile::tempdir().unwrap();
+    let workflow = parse_workflow(&yaml).expect("Failed to parse workflow");
+    validate_workflow(&workflow).expect("workflow should validate");
+    let mut executor = DSLExecutor::new(workflow).unwrap();
+    executor
+        .enable_state_persistence(Some(dir.path().join("state").to_str().unwrap()))
+        .unwrap();
+    executor.initialize().await.unwrap();
+    executor
+        .execute()
+        .await
+        .expect("MCP tool task over SSE should succeed");
+
+    let state = executor.get_state().expect("state should be tracked");
+    let greeting = state.get_task_output("greet").expect("greet output");
+    assert_eq!(greeting.content, "Hello over SSE, Grace!");
+
+    executor.shutdown().await.unwrap();
+}
@@ -0,0 +1,543 @@
+//! Multi-Factor Authentication Tests
+//!
+//! Verifies TOTP enrollment, the two-step login with challenge tokens,
+//! single-use codes and challenges, recovery codes, the challenge attempt limit
+//! and the admin reset endpoints.
+
+#![cfg(feature = "server")]
+
+use axum::body::Body;
+use axum::extract::Extension;
+use axum::http::{Request, StatusCode};
+use axum::middleware;
+use axum::routing::{delete, get, post};
+use axum::Router;
+use chrono::Utc;
+use periplon_sdk::server::api::handlers::{auth, mfa};
+use periplon_sdk::server::auth::mfa::{totp, MAX_FAILED_ATTEMPTS, TOTP_PERIOD_SECS};
+use periplon_sdk::server::auth::middleware::{auth_middleware, require_role, AuthLayer};
+use periplon_sdk::server::auth::{JwtManager, MfaService};
+use periplon_sdk::server::storage::user_storage::{User, UserStorage};
+use periplon_sdk::server::storage::{password, MfaStorage};
+use periplon_sdk::testing::{MockMfaStorage, MockUserStorage};
+use serde_json::{json, Value};
+use std::sync::Arc;
+use tower::ServiceExt;
+use uuid::Uuid;
+
+const PASSWORD: &str = "correct horse battery";
+
+struct Fixture {
+    router: Router,
+    users: Arc<MockUserStorage>,
+    settings: Arc<MockMfaStorage>,
+    jwt_manager: Arc<JwtManager>,
+}
+
+fn fixture_with(mfa_enabled: bool) -> Fixture {
+    let users = Arc::new(MockUserStorage::new());
+    let settings = Arc::new(MockMfaStorage::new());
+    let jwt_manager = Arc::new(JwtManager::new("test_secret_key_123", 24));
+    let auth_layer = AuthLayer::new(Arc::clone(&jwt_manager));
+
+    let admin_routes = Router::new()
+        .route(
+            "/api/v1/admin/users/:id/mfa",
+            get(mfa::admin_get_status).merge(delete(mfa::admin_reset)),
+        )
+        .route_layer(middleware::from_fn(|req, next| {
+            require_role("admin", req, next)
+        }));
+    let protected_routes = Router::new()
+        .route("/api/v1/auth/me", get(auth::get_current_user))
+        .route("/api/v1/auth/mfa", get(mfa::get_status))
+        .route("/api/v1/auth/mfa/enroll", post(mfa::enroll))
+        .route(
+            "/api/v1/auth/mfa/enroll/confirm",
+            post(mfa::confirm_enrollment),
+        )
+        .route(
+            "/api/v1/auth/mfa/recovery-codes",
+            post(mfa::regenerate_recovery_codes),
+        )
+        .route("/api/v1/auth/mfa/disable", post(mfa::disable))
+        .merge(admin_routes)
+        .route_layer(middleware::from_fn(move |req, next| {
+            auth_middleware(auth_layer.clone(), req, next)
+        }));
+
+    let user_storage: Arc<dyn UserStorage> = users.clone();
+    let mut router = Router::new()
+        .route("/api/v1/auth/login", post(auth::login))
+        .route("/api/v1/auth/mfa/verify", post(mfa::verify_login))
+        .merge(protected_routes)
+        .layer(Extension(user_storage))
+        .layer(Extension(Arc::clone(&jwt_manager)));
+    if mfa_enabled {
+        let service = MfaService::new(settings.clone(), "test_secret_key_123", "Periplon", 300);
+        router = router.layer(Extension(Arc::new(service)));
+    }
+
+    Fixture {
+        router,
+        users,
+        settings,
+        jwt_manager,
+    }
+}
+
+fn fixture() -> Fixture {
+    fixture_with(true)
+}
+
+fn add_user(fixture: &Fixture, email: &str, roles: &[&str]) -> User {
+    let user = User {
+        id: Uuid::new_v4(),
+        email: email.to_string(),
+        name: "Ada Lovelace".to_string(),
+        password_hash: password::hash_password(PASSWORD).unwrap(),
+        roles: roles.iter().map(|role| role.to_string()).collect(),
+        is_active: true,
+        email_verified: true,
+        created_at: Utc::now(),
+        updated_at: Utc::now(),
+        last_login_at: None,
+    };
+    fixture.users.add_user(user.clone());
+    user
+}
+
+fn token_for(fixture: &Fixture, user: &User) -> String {
+    fixture
+        .jwt_manager
+        .generate_token(&user.id.to_string(), &user.email, user.roles.clone())
+        .unwrap()
+}
+
+async fn send(
+    router: &Router,
+    method: &str,
+    path: &str,
+    token: Option<&str>,
+    body: Option<Value>,
+) -> (StatusCode, Value) {
+    let mut request = Request::builder().method(method).uri(path);
+    if let Some(token) = token {
+        request = request.header("Authorization", format!("Bearer {}", token));
+    }
+    let body = match body {
+        Some(body) => {
+            request = request.header("Content-Type", "application/json");
+            Body::from(body.to_string())
+        }
+        None => Body::empty(),
+    };
+    let response = router
+        .clone()
+        .oneshot(request.body(body).unwrap())
+        .await
+        .unwrap();
+
+    let status = response.status();
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    (status, serde_json::from_slice(&body).unwrap_or(Value::Null))
+}
+
+async fn login(fixture: &Fixture, email: &str) -> (StatusCode, Value) {
+    send(
+        &fixture.router,
+        "POST",
+        "/api/v1/auth/login",
+        None,
+        Some(json!({"email": email, "password": PASSWORD})),
+    )
+    .await
+}
+
+async fn verify(fixture: &Fixture, challenge: &str, code: &str) -> (StatusCode, Value) {
+    send(
+        &fixture.router,
+        "POST",
+        "/api/v1/auth/mfa/verify",
+        None,
+        Some(json!({"challenge_token": challenge, "code": code})),
+    )
+    .await
+}
+
+/// TOTP code for the next time step, which is accepted after the current one
+fn next_code(secret: &str) -> String {
+    totp(secret, Utc::now().timestamp() + TOTP_PERIOD_SECS).unwrap()
+}
+
+/// Enroll and confirm MFA, returning the secret and recovery codes
+async fn enable_mfa(fixture: &Fixture, token: &str) -> (String, Vec<String>) {
+    let (status, body) = send(
+        &fixture.router,
+        "POST",
+        "/api/v1/auth/mfa/enroll",
+        Some(token),
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    let secret = body["secret"].as_str().unwrap().to_string();
+
+    let code = totp(&secret, Utc::now().timestamp()).unwrap();
+    let (status, body) = send(
+        &fixture.router,
+        "POST",
+        "/api/v1/auth/mfa/enroll/confirm",
+        Some(token),
+        Some(json!({"code": code})),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    let recovery_codes = body["recovery_codes"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|code| code.as_str().unwrap().to_string())
+        .collect();
+    (secret, recovery_codes)
+}
+
+#[tokio::test]
+async fn test_mfa_enrollment_and_two_step_login() {
+    let fixture = fixture();
+    let user = add_user(&fixture, "ada@example.com", &["user"]);
+    let token = token_for(&fixture, &user);
+
+    // Enrollment returns a secret for the authenticator app
+    let (status, body) = send(
+        &fixture.router,
+        "POST",
+        "/api/v1/auth/mfa/enroll",
+        Some(&token),
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    let secret = body["secret"].as_str().unwrap().to_string();
+    let uri = body["otpauth_uri"].as_str().unwrap();
+    assert!(uri.starts_with("otpauth://totp/Periplon:ada@example.com?"));
+    assert!(uri.contains(&format!("secret={}", secret)));
+
+    // The secret is stored encrypted
+    let stored = fixture.settings.settings(user.id).unwrap();
+    assert!(!stored.is_enabled);
+    assert_ne!(stored.secret_encrypted.as_deref(), Some(secret.as_str()));
+
+    // Until confirmed, logins need no code
+    let (status, body) = login(&fixture, "ada@example.com").await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(body["token"].is_string());
+    let (_, body) = send(
+        &fixture.router,
+        "GET",
+        "/api/v1/auth/mfa",
+        Some(&token),
+        None,
+    )
+    .await;
+    assert_eq!(body["enabled"], false);
+    assert_eq!(body["enrollment_pending"], true);
+
+    // Confirming needs a valid code
+    let (status, _) = send(
+        &fixture.router,
+        "POST",
+        "/api/v1/auth/mfa/enroll/confirm",
+        Some(&token),
+        Some(json!({"code": "abcdef"})),
+    )
+    .await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    let confirm_code = totp(&secret, Utc::now().timestamp()).unwrap();
+    let (status, body) = send(
+        &fixture.router,
+        "POST",
+        "/api/v1/auth/mfa/enroll/confirm",
+        Some(&token),
+        Some(json!({"code": confirm_code})),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["recovery_codes"].as_array().unwrap().len(), 10);
+
+    // Enrolling again is refused once enabled
+    let (status, _) = send(
+        &fixture.router,
+        "POST",
+        "/api/v1/auth/mfa/enroll",
+        Some(&token),
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::CONFLICT);
+
+    // A password login now returns a challenge instead of a JWT
+    let (status, body) = login(&fixture, "ada@example.com").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["mfa_required"], true);
+    assert_eq!(body["expires_in"], 300);
+    assert!(body["token"].is_null());
+    let challenge = body["challenge_token"].as_str().unwrap().to_string();
+
+    // The challenge token is not a JWT for protected routes
+    let (status, _) = send(
+        &fixture.router,
+        "GET",
+        "/api/v1/auth/me",
+        Some(&challenge),
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+    // A code already used is refused, and the challenge with it
+    let (status, _) = verify(&fixture, &challenge, &confirm_code).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    let (status, _) = verify(&fixture, &challenge, &next_code(&secret)).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+    let (_, body) = login(&fixture, "ada@example.com").await;
+    let challenge = body["challenge_token"].as_str().unwrap().to_string();
+    let (status, body) = verify(&fixture, &challenge, &next_code(&secret)).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(body["user"]["id"], user.id.to_string());
+    let jwt = body["token"].as_str().unwrap();
+    let (status, body) = send(&fixture.router, "GET", "/api/v1/auth/me", Some(jwt), None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["email"], "ada@example.com");
+    assert!(fixture
+        .users
+        .get_user(user.id)
+        .await
+        .unwrap()
+        .unwrap()
+        .last_login_at
+        .is_some());
+}
+
+#[tokio::test]
+async fn test_mfa_recovery_codes() {
+    let fixture = fixture();
+    let user = add_user(&fixture, "ada@example.com", &["user"]);
+    let token = token_for(&fixture, &user);
+    let (secret, recovery_codes) = enable_mfa(&fixture, &token).await;
+
+    // A recovery code completes a login once, in any case
+    let (_, body) = login(&fixture, "ada@example.com").await;
+    let challenge = body["challenge_token"].as_str().unwrap().to_string();
+    let (status, _) = verify(&fixture, &challenge, &recovery_codes[0].to_uppercase()).await;
+    assert_eq!(status, StatusCode::OK);
+
+    let (_, body) = login(&fixture, "ada@example.com").await;
+    let challenge = body["challenge_token"].as_str().unwrap().to_string();
+    let (status, _) = verify(&fixture, &challenge, &recovery_codes[0]).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+    let (_, body) = send(
+        &fixture.router,
+        "GET",
+        "/api/v1/auth/mfa",
+        Some(&token),
+        None,
+    )
+    .await;
+    assert_eq!(body["enabled"], true);
+    assert_eq!(body["recovery_codes_remaining"], 9);
+
+    // Regenerating replaces every code
+    let (status, body) = send(
+        &fixture.router,
+        "POST",
+        "/api/v1/auth/mfa/recovery-codes",
+        Some(&token),
+        Some(json!({"code": next_code(&secret)})),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    let new_codes = body["recovery_codes"].as_array().unwrap();
+    assert_eq!(new_codes.len(), 10);
+
+    let (_, body) = login(&fixture, "ada@example.com").await;
+    let challenge = body["challenge_token"].as_str().unwrap().to_string();
+    let (status, _) = verify(&fixture, &challenge, &recovery_codes[1]).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    let (_, body) = login(&fixture, "ada@example.com").await;
+    let challenge = body["challenge_token"].as_str().unwrap().to_string();
+    let (status, _) = verify(&fixture, &challenge, new_codes[0].as_str().unwrap()).await;
+    assert_eq!(status, StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_mfa_challenge_completes_one_login() {
+    let fixture = fixture();
+    let user = add_user(&fixture, "ada@example.com", &["user"]);
+    let token = token_for(&fixture, &user);
+    let (secret, recovery_codes) = enable_mfa(&fixture, &token).await;
+
+    let (_, body) = login(&fixture, "ada@example.com").await;
+    let challenge = body["challenge_token"].as_str().unwrap().to_string();
+    let (status, _) = verify(&fixture, &challenge, &recovery_codes[0]).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(fixture.settings.challenge_count(), 1);
+
+    // Replaying the challenge is refused even with another valid code,
+    // without using that code up
+    let (status, body) = verify(&fixture, &challenge, &recovery_codes[1]).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    assert!(body["token"].is_null());
+    let code = next_code(&secret);
+    let (status, _) = verify(&fixture, &challenge, &code).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+    // A new challenge still completes with those codes
+    let (_, body) = login(&fixture, "ada@example.com").await;
+    let challenge = body["challenge_token"].as_str().unwrap().to_string();
+    let (status, _) = verify(&fixture, &challenge, &recovery_codes[1]).await;
+    assert_eq!(status, StatusCode::OK);
+    let (_, body) = login(&fixture, "ada@example.com").await;
+    let challenge = body["challenge_token"].as_str().unwrap().to_string();
+    let (status, _) = verify(&fixture, &challenge, &code).await;
+    assert_eq!(status, StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_mfa_failed_attempt_lockout() {
+    let fixture = fixture();
+    let user = add_user(&fixture, "ada@example.com", &["user"]);
+    let token = token_for(&fixture, &user);
+    let (secret, recovery_codes) = enable_mfa(&fixture, &token).await;
+
+    let (status, _) = verify(&fixture, "not-a-challenge", &next_code(&secret)).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+    // Wrong codes count across challenges, so logging in again does not
+    // reset them
+    for _ in 0..MAX_FAILED_ATTEMPTS {
+        let (_, body) = login(&fixture, "ada@example.com").await;
+        let challenge = body["challenge_token"].as_str().unwrap().to_string();
+        let (status, _) = verify(&fixture, &challenge, "wrong-code").await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+    }
+    let (_, body) = login(&fixture, "ada@example.com").await;
+    let challenge = body["challenge_token"].as_str().unwrap().to_string();
+    let (status, _) = verify(&fixture, &challenge, &next_code(&secret)).await;
+    assert_eq!(status, StatusCode::TOO_MANY_REQUESTS);
+
+    // Codes are accepted again once the lockout ends
+    let mut settings = fixture.settings.settings(user.id).unwrap();
+    assert!(settings.locked_until.unwrap() > Utc::now());
+    settings.locked_until = Some(Utc::now());
+    fixture
+        .settings
+        .upsert_mfa_settings(&settings)
+        .await
+        .unwrap();
+    let (_, body) = login(&fixture, "ada@example.com").await;
+    let challenge = body["challenge_token"].as_str().unwrap().to_string();
+    let (status, _) = verify(&fixture, &challenge, &next_code(&secret)).await;
+    assert_eq!(status, StatusCode::OK);
+
+    // A correct code resets the count
+    let (_, body) = login(&fixture, "ada@example.com").await;
+    let challenge = body["challenge_token"].as_str().unwrap().to_string();
+    let (status, _) = verify(&fixture, &challenge, "wrong-code").await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    assert_eq!(
+        fixture.settings.settings(user.id).unwrap().failed_attempts,
+        1
+    );
+    let (_, body) = login(&fixture, "ada@example.com").await;
+    let challenge = body["challenge_token"].as_str().unwrap().to_string();
+    let (status, _) = verify(&fixture, &challenge, &recovery_codes[0]).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(
+        fixture.settings.settings(user.id).unwrap().failed_attempts,
+        0
+    );
+}
+
+#[tokio::test]
+async fn test_mfa_disable_and_admin_reset() {
+    let fixture = fixture();
+    let user = add_user(&fixture, "ada@example.com", &["user"]);
+    let admin = add_user(&fixture, "admin@example.com", &["user", "admin"]);
+    let token = token_for(&fixture, &user);
+    let admin_token = token_for(&fixture, &admin);
+    let (secret, _) = enable_mfa(&fixture, &token).await;
+
+    // Disabling needs a valid code
+    let (status, _) = send(
+        &fixture.router,
+        "POST",
+        "/api/v1/auth/mfa/disable",
+        Some(&token),
+        Some(json!({"code": "wrong-code"})),
+    )
+    .await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    let (status, _) = send(
+        &fixture.router,
+        "POST",
+        "/api/v1/auth/mfa/disable",
+        Some(&token),
+        Some(json!({"code": next_code(&secret)})),
+    )
+    .await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+    assert!(fixture.settings.settings(user.id).is_none());
+
+    // Admins reset the MFA of users who lost their authenticator
+    enable_mfa(&fixture, &token).await;
+    let path = format!("/api/v1/admin/users/{}/mfa", user.id);
+    let (status, _) = send(&fixture.router, "DELETE", &path, Some(&token), None).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    let (status, body) = send(&fixture.router, "GET", &path, Some(&admin_token), None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["enabled"], true);
+    let (status, _) = send(&fixture.router, "DELETE", &path, Some(&admin_token), None).await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+
+    let (status, body) = login(&fixture, "ada@example.com").await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(body["token"].is_string());
+
+    let unknown = format!("/api/v1/admin/users/{}/mfa", Uuid::new_v4());
+    let (status, _) = send(
+        &fixture.router,
+        "DELETE",
+        &unknown,
+        Some(&admin_token),
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_mfa_not_enabled_on_server() {
+    let fixture = fixture_with(false);
+    let user = add_user(&fixture, "ada@example.com", &["user"]);
+    let token = token_for(&fixture, &user);
+
+    let (status, _) = send(
+        &fixture.router,
+        "POST",
+        "/api/v1/auth/mfa/enroll",
+        Some(&token),
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    let (status, body) = login(&fixture, "ada@example.com").await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(body["token"].is_string());
+}
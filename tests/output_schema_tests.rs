@@ -0,0 +1,154 @@
+//! Output Schema Tests
+//!
+//! Verifies that task results are validated against their JSON Schemas,
+//! retried with the validation errors as feedback, and failed once the
+//! schema retries are exhausted.
+
+mod common {
+    pub mod http;
+}
+
+use common::http::{CannedResponse, FakeServer};
+use periplon_sdk::dsl::executor::DSLExecutor;
+use periplon_sdk::dsl::parse_workflow;
+use periplon_sdk::dsl::TaskStatus;
+use serde_json::json;
+
+/// Start a fake Ollama server answering successive chat requests with the
+/// given responses (the last one repeats)
+async fn start_ollama(responses: Vec<&str>) -> FakeServer {
+    let responses = responses
+        .into_iter()
+        .map(|content| {
+            CannedResponse::json(json!({
+                "message": {"role": "assistant", "content": content},
+                "done": true,
+            }))
+        })
+        .collect();
+    FakeServer::start(responses).await
+}
+
+fn llm_workflow(endpoint: &str) -> String {
+    format!(
+        r#"
+name: "Typed LLM Output"
+version: "1.0.0"
+tasks:
+  extract:
+    description: "Extract a person"
+    schema_retries: 1
+    llm:
+      provider: ollama
+      model: "test-model"
+      endpoint: "{}"
+      prompt: "Extract the person from: Ada, 36"
+      schema:
+        type: object
+        properties:
+          name: {{type: string}}
+          age: {{type: integer}}
+        required: [name, age]
+"#,
+        endpoint
+    )
+}
+
+#[tokio::test]
+async fn test_llm_schema_uses_native_format_and_retries_with_feedback() {
+    let server = start_ollama(vec![r#"{"name": "Ada"}"#, r#"{"name": "Ada", "age": 36}"#]).await;
+
+    let workflow = parse_workflow(&llm_workflow(&server.url)).unwrap();
+    let mut executor = DSLExecutor::new(workflow).unwrap();
+    executor.initialize().await.unwrap();
+    executor.execute().await.expect("second answer matches");
+
+    let requests = server.json_bodies();
+    assert_eq!(requests.len(), 2);
+    // The schema is passed as Ollama's structured output format
+    assert_eq!(requests[0]["format"]["required"], json!(["name", "age"]));
+    // The retry carries the validation errors
+    let retry_prompt = requests[1]["messages"][0]["content"].as_str().unwrap();
+    assert!(retry_prompt.contains("did not match the required JSON Schema"));
+    assert!(retry_prompt.contains("\"age\" is a required property"));
+
+    let output = executor
+        .get_state()
+        .unwrap()
+        .get_task_output("extract")
+        .unwrap();
+    assert_eq!(output.structured(), json!({"name": "Ada", "age": 36}));
+}
+
+#[tokio::test]
+async fn test_llm_schema_fails_task_after_retries() {
+    let server = start_ollama(vec!["not json"]).await;
+
+    let workflow = parse_workflow(&llm_workflow(&server.url)).unwrap();
+    let mut executor = DSLExecutor::new(workflow).unwrap();
+    executor.initialize().await.unwrap();
+    let err = executor.execute().await.unwrap_err().to_string();
+
+    assert!(err.contains("output did not match its schema after 1 retries"));
+    assert_eq!(server.requests().len(), 2);
+    assert_eq!(
+        executor.task_graph().get_task_status("extract"),
+        Some(TaskStatus::Failed)
+    );
+}
+
+#[tokio::test]
+async fn test_declared_output_schema_validates_script_result() {
+    let dir = tempfile::tempdir().unwrap();
+    let counter = dir.path().join("runs");
+
+    // The first run prints an id of the wrong type, the second a valid result
+    let yaml = format!(
+        r#"
+name: "Typed Script Output"
+version: "1.0.0"
+tasks:
+  fetch:
+    description: "Emit an item"
+    script:
+      language: bash
+      content: |
+        echo run >> "{counter}"
+        if [ "$(wc -l < "{counter}")" -eq 1 ]; then
+          echo '{{"id": "seven"}}'
+        else
+          echo '{{"id": 7}}'
+        fi
+    outputs:
+      item:
+        source:
+          type: task_output
+          task: fetch
+        schema:
+          type: object
+          properties:
+            id: {{type: integer}}
+          required: [id]
+"#,
+        counter = counter.display()
+    );
+
+    let workflow = parse_workflow(&yaml).unwrap();
+    let mut executor = DSLExecutor::new(workflow).unwrap();
+    executor.initialize().await.unwrap();
+    executor
+        .execute()
+        .await
+        .expect("retry produces a valid item");
+
+    assert_eq!(
+        std::fs::read_to_string(&counter).unwrap().lines().count(),
+        2
+    );
+    let output = executor
+        .get_state()
+        .unwrap()
+        .get_task_output("fetch")
+        .unwrap();
+    assert_eq!(output.structured(), json!({"id": 7}));
+}
@@ -706,6 +706,7 @@ fn create_test_predefined_task() -> PredefinedTask {
                             source: OutputDataSource::State {
                                 key: "result_value".to_string(),
                             },
+                            schema: None,
                         },
                         output_type: Some("string".to_string()),
                     },
@@ -0,0 +1,207 @@
+//! Provider Profile Tests
+//!
+//! Verifies the workflow-level `providers:` registry: `llm` tasks that
+//! reference a profile reach an OpenAI-compatible or Azure OpenAI endpoint with
+//! the profile's model and auth header, and the validator checks profiles and
+//! their references.
+
+mod common {
+    pub mod http;
+}
+
+use common::http::{CannedResponse, FakeServer};
+use periplon_sdk::domain::{AuthStyle, Provider};
+use periplon_sdk::dsl::executor::DSLExecutor;
+use periplon_sdk::dsl::{parse_workflow, validate_workflow, TaskStatus};
+use serde_json::json;
+
+/// Start a fake chat completions server
+async fn start_server() -> FakeServer {
+    FakeServer::answering(CannedResponse::json(json!({
+        "model": "served-model",
+        "choices": [{
+            "message": {"role": "assistant", "content": "pong"},
+            "finish_reason": "stop",
+        }],
+        "usage": {"prompt_tokens": 7, "completion_tokens": 2, "total_tokens": 9},
+    })))
+    .await
+}
+
+#[test]
+fn test_provider_profiles_parse() {
+    let yaml = r#"
+name: "Profiles"
+version: "1.0.0"
+providers:
+  local_vllm:
+    provider: openai_compatible
+    endpoint: "http://localhost:8000/v1"
+    model: "Qwen/Qwen2.5-7B-Instruct"
+    auth: none
+  azure:
+    provider: azure_openai
+    endpoint: "https://example.openai.azure.com"
+    api_key: "${secret.azure_key}"
+    api_version: "2025-01-01-preview"
+    model: "gpt4o-prod"
+tasks:
+  ask:
+    description: "Ask"
+    llm:
+      profile: local_vllm
+      prompt: "Hi"
+"#;
+    let workflow = parse_workflow(yaml).unwrap();
+    let vllm = &workflow.providers["local_vllm"];
+    assert_eq!(vllm.provider, Provider::OpenAICompatible);
+    assert_eq!(vllm.auth, Some(AuthStyle::None));
+    let azure = &workflow.providers["azure"];
+    assert_eq!(azure.provider, Provider::AzureOpenAI);
+    assert_eq!(azure.api_version.as_deref(), Some("2025-01-01-preview"));
+
+    let llm = workflow.tasks["ask"].llm.as_ref().unwrap();
+    let resolved = llm.with_profile(vllm);
+    assert_eq!(resolved.provider, Provider::OpenAICompatible);
+    assert_eq!(resolved.model, "Qwen/Qwen2.5-7B-Instruct");
+    assert_eq!(
+        resolved.endpoint.as_deref(),
+        Some("http://localhost:8000/v1")
+    );
+    assert!(validate_workflow(&workflow).is_ok());
+}
+
+#[test]
+fn test_validator_checks_profiles() {
+    let yaml = r#"
+name: "Profiles"
+version: "1.0.0"
+providers:
+  no_endpoint:
+    provider: openai_compatible
+    model: "llama"
+  gemini:
+    provider: google
+agents:
+  coder:
+    description: "Coder"
+    profile: gemini
+  reviewer:
+    description: "Reviewer"
+    profile: missing
+tasks:
+  ask:
+    description: "Ask"
+    llm:
+      profile: gemini
+      prompt: "Hi"
+  inline:
+    description: "Inline"
+    llm:
+      provider: openai_compatible
+      model: "any-model-name"
+      prompt: "Hi"
+"#;
+    let workflow = parse_workflow(yaml).unwrap();
+    let error = validate_workflow(&workflow).unwrap_err().to_string();
+    assert!(error.contains("Provider profile 'no_endpoint' requires an endpoint"));
+    assert!(error
+        .contains("Agent 'coder' uses provider profile 'gemini', but Google cannot run agents"));
+    assert!(error.contains("Agent 'reviewer' references unknown provider profile 'missing'"));
+    assert!(error.contains("Task 'ask' llm has no model"));
+    assert!(error.contains("Task 'inline' llm provider OpenAICompatible requires an endpoint"));
+    // Self-hosted model names are not checked against a fixed list
+    assert!(!error.contains("any-model-name"));
+}
+
+#[tokio::test]
+async fn test_llm_task_uses_openai_compatible_profile() {
+    let server = start_server().await;
+    let yaml = format!(
+        r#"
+name: "Profiles"
+version: "1.0.0"
+providers:
+  local:
+    provider: openai_compatible
+    endpoint: "{}/v1"
+    api_key: "local-key"
+    auth: api_key
+    auth_header: "X-Api-Key"
+    model: "Qwen/Qwen2.5-7B-Instruct"
+tasks:
+  ask:
+    description: "Ask"
+    llm:
+      profile: local
+      prompt: "ping"
+  override:
+    description: "Override the model"
+    depends_on: [ask]
+    llm:
+      profile: local
+      model: "other-model"
+      prompt: "ping"
+"#,
+        server.url
+    );
+
+    let workflow = parse_workflow(&yaml).unwrap();
+    validate_workflow(&workflow).unwrap();
+    let mut executor = DSLExecutor::new(workflow).unwrap();
+    executor.initialize().await.unwrap();
+    executor.execute().await.unwrap();
+
+    let state = executor.get_state().unwrap();
+    assert_eq!(state.get_task_status("ask"), Some(TaskStatus::Completed));
+    assert_eq!(state.task_usage["ask"].input_tokens, 7);
+
+    let requests: Vec<String> = server.requests().into_iter().map(|r| r.raw).collect();
+    assert_eq!(requests.len(), 2);
+    assert!(requests[0].starts_with("POST /v1/chat/completions HTTP/1.1"));
+    assert!(requests[0]
+        .to_ascii_lowercase()
+        .contains("x-api-key: local-key"));
+    assert!(!requests[0].to_ascii_lowercase().contains("authorization:"));
+    assert!(requests[0].contains(r#""model":"Qwen/Qwen2.5-7B-Instruct""#));
+    assert!(requests[1].contains(r#""model":"other-model""#));
+}
+
+#[tokio::test]
+async fn test_llm_task_uses_azure_deployment_url() {
+    let server = start_server().await;
+    let yaml = format!(
+        r#"
+name: "Azure"
+version: "1.0.0"
+providers:
+  azure:
+    provider: azure_openai
+    endpoint: "{}"
+    api_key: "azure-key"
+    api_version: "2025-01-01-preview"
+    model: "gpt4o-prod"
+tasks:
+  ask:
+    description: "Ask"
+    llm:
+      profile: azure
+      prompt: "ping"
+"#,
+        server.url
+    );
+
+    let workflow = parse_workflow(&yaml).unwrap();
+    validate_workflow(&workflow).unwrap();
+    let mut executor = DSLExecutor::new(workflow).unwrap();
+    executor.initialize().await.unwrap();
+    executor.execute().await.unwrap();
+
+    let requests: Vec<String> = server.requests().into_iter().map(|r| r.raw).collect();
+    assert!(requests[0].starts_with(
+        "POST /openai/deployments/gpt4o-prod/chat/completions?api-version=2025-01-01-preview HTTP/1.1"
+    ));
+    assert!(requests[0]
+        .to_ascii_lowercase()
+        .contains("api-key: azure-key"));
+}
@@ -48,6 +48,8 @@ fn create_test_workflow(name: &str) -> (DSLWorkflow, WorkflowMetadata) {
         imports: HashMap::new(),
         notifications: None,
         limits: None,
+        max_concurrency: None,
+        providers: HashMap::new(),
     };
 
     let metadata = WorkflowMetadata {
@@ -0,0 +1,322 @@
+//! Scheduler Tests
+//!
+//! Verifies that the cron scheduler queues executions for due schedules,
+//! applies the catch-up policy to missed occurrences, records schedule runs
+//! and only fires from the replica holding the leader lease, stopping a pass
+//! once the lease is lost.
+
+#![cfg(feature = "server")]
+
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use periplon_sdk::dsl::parse_workflow;
+use periplon_sdk::server::config::{CatchUpPolicy, SchedulerConfig};
+use periplon_sdk::server::scheduler::{self, FilesystemLease, LeaderLease};
+use periplon_sdk::server::storage::filesystem::FilesystemStorage;
+use periplon_sdk::server::storage::{
+    ExecutionStorage, Schedule, ScheduleRunStatus, ScheduleStorage, Storage, WorkflowMetadata,
+    WorkflowStorage,
+};
+use periplon_sdk::server::Scheduler;
+use periplon_sdk::testing::MockQueue;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use tempfile::TempDir;
+use uuid::Uuid;
+
+const WORKFLOW: &str = r#"
+name: "Nightly"
+version: "1.2.0"
+tasks:
+  report:
+    description: "Report"
+    script:
+      language: bash
+      content: "echo report"
+"#;
+
+struct Fixture {
+    _dir: TempDir,
+    storage: Arc<FilesystemStorage>,
+    queue: Arc<MockQueue>,
+    lease: Arc<FilesystemLease>,
+    workflow_id: Uuid,
+}
+
+async fn setup() -> Fixture {
+    let dir = tempfile::tempdir().unwrap();
+    let storage = Arc::new(
+        FilesystemStorage::new(
+            dir.path().to_path_buf(),
+            "workflows".to_string(),
+            "executions".to_string(),
+            "checkpoints".to_string(),
+            "logs".to_string(),
+        )
+        .await
+        .unwrap(),
+    );
+
+    let workflow = parse_workflow(WORKFLOW).unwrap();
+    let metadata = WorkflowMetadata {
+        id: Uuid::new_v4(),
+        name: "Nightly".to_string(),
+        version: "1.2.0".to_string(),
+        description: None,
+        created_at: Utc::now(),
+        updated_at: Utc::now(),
+        created_by: None,
+        tags: vec![],
+        is_active: true,
+    };
+    storage.store_workflow(&workflow, &metadata).await.unwrap();
+
+    Fixture {
+        lease: Arc::new(FilesystemLease::new(dir.path().join("scheduler.lease"))),
+        _dir: dir,
+        storage,
+        queue: Arc::new(MockQueue::new()),
+        workflow_id: metadata.id,
+    }
+}
+
+impl Fixture {
+    fn scheduler(&self, id: &str, catch_up: CatchUpPolicy) -> Scheduler {
+        let storage: Arc<dyn Storage> = self.storage.clone();
+        Scheduler::new(
+            id.to_string(),
+            storage,
+            self.queue.clone(),
+            self.lease.clone(),
+            SchedulerConfig {
+                catch_up,
+                max_catch_up_runs: 3,
+                ..Default::default()
+            },
+        )
+    }
+
+    async fn add_schedule(&self, cron_expression: &str, next_run_at: DateTime<Utc>) -> Uuid {
+        let schedule = Schedule {
+            id: Uuid::new_v4(),
+            workflow_id: self.workflow_id,
+            cron_expression: cron_expression.to_string(),
+            timezone: "UTC".to_string(),
+            is_active: true,
+            input_params: Some(serde_json::json!({"env": "prod"})),
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+            created_by: Some("alice".to_string()),
+            last_run_at: None,
+            next_run_at: Some(next_run_at),
+            description: None,
+        };
+        self.storage.store_schedule(&schedule).await.unwrap()
+    }
+
+    /// Scheduled times of started and skipped runs, oldest first
+    async fn runs(&self, schedule_id: Uuid) -> (Vec<u32>, Vec<u32>) {
+        let mut runs = self
+            .storage
+            .get_schedule_runs(schedule_id, None)
+            .await
+            .unwrap();
+        runs.reverse();
+
+        let hours = |status: ScheduleRunStatus| -> Vec<u32> {
+            runs.iter()
+                .filter(|run| run.status == status)
+                .map(|run| run.scheduled_for.format("%H").to_string().parse().unwrap())
+                .collect()
+        };
+        (
+            hours(ScheduleRunStatus::Scheduled),
+            hours(ScheduleRunStatus::Skipped),
+        )
+    }
+}
+
+fn at(hour: u32, minute: u32) -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(2024, 3, 1, hour, minute, 0).unwrap()
+}
+
+#[tokio::test]
+async fn test_due_schedule_queues_execution() {
+    let fixture = setup().await;
+    let schedule_id = fixture.add_schedule("0 * * * *", at(10, 0)).await;
+    let scheduler = fixture.scheduler("scheduler-1", CatchUpPolicy::Once);
+
+    // Not due yet
+    assert_eq!(scheduler.tick(at(9, 59)).await.unwrap(), 0);
+
+    let now = at(10, 0) + Duration::seconds(10);
+    assert_eq!(scheduler.tick(now).await.unwrap(), 1);
+
+    let jobs = fixture.queue.get_pending();
+    assert_eq!(jobs.len(), 1);
+    assert_eq!(jobs[0].workflow_id, fixture.workflow_id);
+    assert_eq!(jobs[0].payload["input_params"]["env"], "prod");
+
+    let execution = fixture
+        .storage
+        .get_execution(jobs[0].execution_id)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(execution.trigger_type, "scheduled");
+    assert_eq!(execution.workflow_version, "1.2.0");
+    assert_eq!(execution.triggered_by.as_deref(), Some("alice"));
+
+    let runs = fixture
+        .storage
+        .get_schedule_runs(schedule_id, None)
+        .await
+        .unwrap();
+    assert_eq!(runs.len(), 1);
+    assert_eq!(runs[0].scheduled_for, at(10, 0));
+    assert_eq!(runs[0].execution_id, Some(execution.id));
+
+    let schedule = fixture
+        .storage
+        .get_schedule(schedule_id)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(schedule.next_run_at, Some(at(11, 0)));
+    assert_eq!(schedule.last_run_at, Some(now));
+
+    // The same occurrence does not fire twice
+    assert_eq!(scheduler.tick(now).await.unwrap(), 0);
+}
+
+#[tokio::test]
+async fn test_catch_up_policies() {
+    // Down from 05:00 until 10:30: six hourly occurrences were missed
+    let now = at(10, 30);
+    let cases = [
+        (CatchUpPolicy::Skip, vec![], vec![5, 6, 7, 8, 9, 10]),
+        (CatchUpPolicy::Once, vec![10], vec![5, 6, 7, 8, 9]),
+        (CatchUpPolicy::All, vec![8, 9, 10], vec![5, 6, 7]),
+    ];
+
+    for (policy, started, skipped) in cases {
+        let fixture = setup().await;
+        let schedule_id = fixture.add_schedule("0 * * * *", at(5, 0)).await;
+        let scheduler = fixture.scheduler("scheduler-1", policy);
+
+        assert_eq!(scheduler.tick(now).await.unwrap(), started.len());
+        assert_eq!(fixture.queue.pending_count(), started.len());
+        assert_eq!(fixture.runs(schedule_id).await, (started, skipped));
+
+        let schedule = fixture
+            .storage
+            .get_schedule(schedule_id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(schedule.next_run_at, Some(at(11, 0)));
+    }
+}
+
+#[tokio::test]
+async fn test_only_the_leader_fires() {
+    let fixture = setup().await;
+    let leader = fixture.scheduler("scheduler-1", CatchUpPolicy::Once);
+    let follower = fixture.scheduler("scheduler-2", CatchUpPolicy::Once);
+
+    fixture.add_schedule("*/5 * * * *", at(10, 0)).await;
+    assert_eq!(leader.tick(at(10, 0)).await.unwrap(), 1);
+
+    fixture.add_schedule("*/5 * * * *", at(10, 0)).await;
+    assert_eq!(follower.tick(at(10, 0)).await.unwrap(), 0);
+    assert_eq!(fixture.queue.pending_count(), 1);
+
+    // Leadership moves once the leader steps down
+    leader.shutdown().await.unwrap();
+    assert_eq!(follower.tick(at(10, 0)).await.unwrap(), 1);
+    assert!(!fixture
+        .lease
+        .try_acquire("scheduler-1", std::time::Duration::from_secs(60))
+        .await
+        .unwrap());
+}
+
+/// Lease that is lost to another replica after a number of acquisitions
+struct ExpiringLease {
+    remaining: AtomicUsize,
+}
+
+#[async_trait]
+impl LeaderLease for ExpiringLease {
+    async fn try_acquire(
+        &self,
+        _holder: &str,
+        _ttl: std::time::Duration,
+    ) -> scheduler::Result<bool> {
+        Ok(self
+            .remaining
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| n.checked_sub(1))
+            .is_ok())
+    }
+
+    async fn release(&self, _holder: &str) -> scheduler::Result<()> {
+        Ok(())
+    }
+}
+
+#[tokio::test]
+async fn test_pass_stops_when_lease_is_lost() {
+    let fixture = setup().await;
+    fixture.add_schedule("*/5 * * * *", at(10, 0)).await;
+    fixture.add_schedule("*/5 * * * *", at(10, 0)).await;
+
+    // The lease is renewed before each schedule: starting the pass and the
+    // first schedule succeed, the second renewal fails
+    let storage: Arc<dyn Storage> = fixture.storage.clone();
+    let scheduler = Scheduler::new(
+        "scheduler-1".to_string(),
+        storage,
+        fixture.queue.clone(),
+        Arc::new(ExpiringLease {
+            remaining: AtomicUsize::new(2),
+        }),
+        SchedulerConfig::default(),
+    );
+    assert_eq!(scheduler.tick(at(10, 0)).await.unwrap(), 1);
+    assert_eq!(fixture.queue.pending_count(), 1);
+
+    // The schedule left behind is still due for the next leader
+    let due = fixture.storage.get_due_schedules(at(10, 0)).await.unwrap();
+    assert_eq!(due.len(), 1);
+    assert_eq!(due[0].next_run_at, Some(at(10, 0)));
+}
+
+#[tokio::test]
+async fn test_invalid_schedule_is_deactivated() {
+    let fixture = setup().await;
+    let schedule_id = fixture.add_schedule("not a cron", at(10, 0)).await;
+    let scheduler = fixture.scheduler("scheduler-1", CatchUpPolicy::Once);
+
+    assert_eq!(scheduler.tick(at(10, 0)).await.unwrap(), 0);
+
+    let schedule = fixture
+        .storage
+        .get_schedule(schedule_id)
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(!schedule.is_active);
+
+    let runs = fixture
+        .storage
+        .get_schedule_runs(schedule_id, None)
+        .await
+        .unwrap();
+    assert_eq!(runs.len(), 1);
+    assert_eq!(runs[0].status, ScheduleRunStatus::Failed);
+    assert!(runs[0]
+        .error
+        .as_deref()
+        .unwrap()
+        .contains("Invalid cron expression"));
+}
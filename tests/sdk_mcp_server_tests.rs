@@ -0,0 +1,137 @@
+//! SDK MCP Server Tests
+//!
+//! Verifies that in-process MCP servers built from Rust functions expose
+//! schemars-derived input schemas, run their handlers, and are registered on
+//! `AgentOptions` as `sdk` servers.
+
+use periplon_sdk::adapters::secondary::SdkMcpServer;
+use periplon_sdk::error::Error;
+use periplon_sdk::options::McpServerConfig;
+use periplon_sdk::ports::secondary::{McpServer, ToolResult};
+use periplon_sdk::AgentOptions;
+use schemars::JsonSchema;
+use serde::Deserialize;
+use serde_json::json;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+#[derive(Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+enum Operation {
+    Add,
+    Multiply,
+}
+
+#[derive(Deserialize, JsonSchema)]
+struct CalculateArgs {
+    /// Operation to apply
+    operation: Operation,
+    /// Operands
+    values: Vec<f64>,
+    /// Decimal places to round to
+    #[serde(default)]
+    precision: Option<u32>,
+}
+
+fn calculator() -> SdkMcpServer {
+    SdkMcpServer::builder("calculator")
+        .tool(
+            "calculate",
+            "Apply an operation to numbers",
+            |args: CalculateArgs| async move {
+                if args.values.is_empty() {
+                    return Err(Error::InvalidInput("no values".to_string()));
+                }
+                let result: f64 = match args.operation {
+                    Operation::Add => args.values.iter().sum(),
+                    Operation::Multiply => args.values.iter().product(),
+                };
+                let scale = 10f64.powi(args.precision.unwrap_or(0) as i32);
+                Ok(ToolResult::text(
+                    ((result * scale).round() / scale).to_string(),
+                ))
+            },
+        )
+        .build()
+}
+
+#[tokio::test]
+async fn test_schema_is_derived_from_arguments_type() {
+    let tools = calculator().list_tools().await.unwrap();
+    assert_eq!(tools.len(), 1);
+
+    let schema = &tools[0].input_schema;
+    assert_eq!(tools[0].description, "Apply an operation to numbers");
+    assert_eq!(schema["required"], json!(["operation", "values"]));
+    assert_eq!(schema["properties"]["values"]["type"], "array");
+    assert_eq!(
+        schema["properties"]["precision"]["description"],
+        "Decimal places to round to"
+    );
+    // Nested types are described in the schema's definitions
+    assert!(schema["definitions"]["Operation"].is_object());
+}
+
+#[tokio::test]
+async fn test_handlers_run_with_typed_arguments() {
+    let server = calculator();
+
+    let result = server
+        .call_tool(
+            "calculate",
+            json!({"operation": "multiply", "values": [1.5, 3], "precision": 1}),
+        )
+        .await
+        .unwrap();
+    assert!(!result.is_error);
+    assert_eq!(result.content, json!([{"type": "text", "text": "4.5"}]));
+
+    let result = server
+        .call_tool("calculate", json!({"operation": "add", "values": []}))
+        .await
+        .unwrap();
+    assert!(result.is_error);
+
+    let result = server
+        .call_tool("calculate", json!({"operation": "divide", "values": [1]}))
+        .await
+        .unwrap();
+    assert!(result.is_error);
+}
+
+#[tokio::test]
+async fn test_handlers_can_share_state() {
+    let calls = Arc::new(AtomicUsize::new(0));
+    let counter = Arc::clone(&calls);
+
+    #[derive(Deserialize, JsonSchema)]
+    struct Empty {}
+
+    let server = SdkMcpServer::builder("counter")
+        .tool("increment", "Count calls", move |_: Empty| {
+            let counter = Arc::clone(&counter);
+            async move {
+                let count = counter.fetch_add(1, Ordering::SeqCst) + 1;
+                Ok(ToolResult::text(count.to_string()))
+            }
+        })
+        .build();
+
+    server.call_tool("increment", json!({})).await.unwrap();
+    let result = server.call_tool("increment", json!({})).await.unwrap();
+    assert_eq!(result.content[0]["text"], "2");
+    assert_eq!(calls.load(Ordering::SeqCst), 2);
+}
+
+#[test]
+fn test_server_is_registered_as_sdk_server() {
+    let mut options = AgentOptions::default();
+    options.add_sdk_mcp_server(Arc::new(calculator()));
+
+    assert!(matches!(
+        options.mcp_servers.get("calculator"),
+        Some(McpServerConfig::Sdk { name }) if name == "calculator"
+    ));
+    assert!(options.sdk_mcp_servers.contains_key("calculator"));
+    assert!(format!("{:?}", options).contains("calculator"));
+}
@@ -0,0 +1,167 @@
+//! Workflow Secrets Tests
+//!
+//! Verifies that declared secrets are resolved before execution through the
+//! built-in and registered providers, reach the tasks that reference them, and
+//! are redacted from task outputs and persisted state.
+
+use async_trait::async_trait;
+use periplon_sdk::dsl::executor::DSLExecutor;
+use periplon_sdk::dsl::parse_workflow;
+use periplon_sdk::dsl::schema::SecretSource;
+use periplon_sdk::dsl::secrets::{encrypt_secrets, SecretProvider};
+use periplon_sdk::error::{Error, Result};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+#[tokio::test]
+async fn test_secrets_reach_tasks_and_are_redacted() {
+    let dir = tempfile::tempdir().unwrap();
+    let vault = dir.path().join("secrets.enc");
+    let entries = HashMap::from([("db_password".to_string(), "hunter2-db".to_string())]);
+    std::fs::write(&vault, encrypt_secrets(&entries, "open sesame").unwrap()).unwrap();
+    std::env::set_var("SECRETS_TEST_PASSPHRASE", "open sesame");
+
+    let yaml = format!(
+        r#"
+name: "Secrets"
+version: "1.0.0"
+secrets:
+  api_token:
+    source:
+      type: value
+      value: "tok-12345"
+  greeting:
+    source:
+      type: command
+      command: "echo"
+      args: ["hello-from-command"]
+  db:
+    source:
+      type: encrypted
+      path: "{vault}"
+      key: db_password
+      passphrase_env: SECRETS_TEST_PASSPHRASE
+tasks:
+  use_secrets:
+    description: "Use the secrets"
+    script:
+      language: bash
+      content: |
+        printf '%s %s %s' "${{secret.api_token}}" "${{secret.greeting}}" "${{secret.db}}" > "{received}"
+        echo "token is ${{secret.api_token}}"
+"#,
+        vault = vault.display(),
+        received = dir.path().join("received.txt").display()
+    );
+
+    let workflow = parse_workflow(&yaml).unwrap();
+    let state_dir = dir.path().join("state");
+    let mut executor = DSLExecutor::new(workflow).unwrap();
+    executor
+        .enable_state_persistence(Some(state_dir.to_str().unwrap()))
+        .unwrap();
+    executor.initialize().await.unwrap();
+    executor.execute().await.unwrap();
+
+    // The task received the resolved values
+    let received = std::fs::read_to_string(dir.path().join("received.txt")).unwrap();
+    assert_eq!(received, "tok-12345 hello-from-command hunter2-db");
+
+    // Its recorded output does not contain them
+    let output = executor
+        .get_state()
+        .unwrap()
+        .get_task_output("use_secrets")
+        .unwrap();
+    assert_eq!(output.content.trim(), "token is [REDACTED]");
+
+    // Neither does the persisted state
+    for entry in std::fs::read_dir(&state_dir).unwrap() {
+        let content = std::fs::read_to_string(entry.unwrap().path()).unwrap();
+        assert!(!content.contains("tok-12345"));
+    }
+}
+
+/// Provider answering from a fixed map, standing in for a vault client
+struct StaticVault(HashMap<String, String>);
+
+#[async_trait]
+impl SecretProvider for StaticVault {
+    async fn resolve(&self, name: &str, source: &SecretSource) -> Result<String> {
+        let SecretSource::Provider { key, .. } = source else {
+            return Err(Error::InvalidInput(
+                "expected a provider source".to_string(),
+            ));
+        };
+        let key = key.as_deref().unwrap_or(name);
+        self.0
+            .get(key)
+            .cloned()
+            .ok_or_else(|| Error::InvalidInput(format!("no vault entry '{}'", key)))
+    }
+}
+
+#[tokio::test]
+async fn test_registered_provider_resolves_secrets() {
+    let yaml = r#"
+name: "Vault Secrets"
+version: "1.0.0"
+secrets:
+  deploy_key:
+    source:
+      type: provider
+      provider: vault
+      key: "ci/deploy"
+tasks:
+  show:
+    description: "Show the key length"
+    command:
+      executable: "sh"
+      args: ["-c", "echo ${secret.deploy_key} | wc -c"]
+"#;
+
+    let mut executor = DSLExecutor::new(parse_workflow(yaml).unwrap()).unwrap();
+    executor.register_secret_provider(
+        "vault",
+        Arc::new(StaticVault(HashMap::from([(
+            "ci/deploy".to_string(),
+            "ssh-key-value".to_string(),
+        )]))),
+    );
+    executor.initialize().await.unwrap();
+    executor.execute().await.unwrap();
+
+    let output = executor
+        .get_state()
+        .unwrap()
+        .get_task_output("show")
+        .unwrap();
+    assert_eq!(output.content.trim(), "14");
+    assert_eq!(
+        executor.secrets().get("deploy_key").as_deref(),
+        Some("ssh-key-value")
+    );
+}
+
+#[tokio::test]
+async fn test_unresolvable_secret_fails_initialization() {
+    let yaml = r#"
+name: "Missing Secret"
+version: "1.0.0"
+secrets:
+  token:
+    source:
+      type: env
+      var: PERIPLON_TEST_SECRET_THAT_IS_NOT_SET
+tasks:
+  noop:
+    description: "Never runs"
+    script:
+      language: bash
+      content: "true"
+"#;
+
+    let mut executor = DSLExecutor::new(parse_workflow(yaml).unwrap()).unwrap();
+    let err = executor.initialize().await.unwrap_err().to_string();
+    assert!(err.contains("PERIPLON_TEST_SECRET_THAT_IS_NOT_SET"));
+}
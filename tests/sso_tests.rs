@@ -0,0 +1,756 @@
+//! Single Sign-On Tests
+//!
+//! Runs the OIDC login flow against a stand-in provider served on localhost:
+//! discovery, PKCE, state, state cookie and ID token checks, just-in-time
+//! provisioning, linking by verified email, group mappings, token refresh and
+//! the MFA challenge for users with a second factor.
+
+#![cfg(feature = "server")]
+
+use axum::body::Body;
+use axum::extract::{Extension, Form, Query};
+use axum::http::{header, Request, StatusCode};
+use axum::response::{IntoResponse, Redirect, Response};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine as _};
+use chrono::Utc;
+use jsonwebtoken::{encode, Algorithm, EncodingKey, Header};
+use periplon_sdk::server::api::handlers::mfa::verify_login;
+use periplon_sdk::server::api::handlers::sso::{sso_callback, sso_login};
+use periplon_sdk::server::auth::mfa::{totp, TOTP_PERIOD_SECS};
+use periplon_sdk::server::auth::{JwtManager, MfaService, SsoService};
+use periplon_sdk::server::config::{GroupMapping, OAuthConfig, OAuthProvider};
+use periplon_sdk::server::storage::user_storage::{User, UserStorage};
+use periplon_sdk::testing::{
+    MockMfaStorage, MockOAuthConnectionStorage, MockOrganizationStorage, MockUserStorage,
+};
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use tower::ServiceExt;
+use uuid::Uuid;
+
+const CLIENT_ID: &str = "periplon";
+const CLIENT_SECRET: &str = "client-secret";
+const REDIRECT_URI: &str = "http://localhost:8080/api/v1/auth/sso/corp/callback";
+const KEY_ID: &str = "test-key";
+const MODULUS: &str = "y4XoW_bq4l2gpjUD7dH_nQAFxxsqybiSpGjBaU_ixyEvSMR5rae_Boqt5KV8ckIZyAr092KAS8qUPEtW1RM9iXrnoi_6niBWDYOVcmseOUVL5-3VNXZWaJCTLGKiEaaIvj-o1TsToUsqXnAN8vA7LRuwo2gCAl861EX6FXi2_1AP5TEPtApdxFUT9EE7ChVeCI8TfWsxAAMSjkp2CFbyjDe2LXl4mX_da4hrWaNVQDKFnjU6Sw_sZMhafJzaKks1t0Y-Y5ladKL1JfaS3BPSEs1AdS7_kzhwTl8DOrcZ68WNF8kbHzRrSazWZpQxSZUddU9CftbYxQBhXHBcIeI9kQ";
+
+// ============================================================================
+// Stand-in OIDC provider
+// ============================================================================
+
+struct AuthorizationCode {
+    challenge: String,
+    nonce: String,
+}
+
+struct ProviderState {
+    issuer: String,
+    /// Claims of the user signing in at the provider
+    identity: Value,
+    /// Audience of issued ID tokens, when not the client ID
+    audience: Option<String>,
+    codes: HashMap<String, AuthorizationCode>,
+    refresh_tokens: Vec<String>,
+}
+
+type SharedState = Arc<Mutex<ProviderState>>;
+
+/// OIDC provider serving discovery, authorization, token and key endpoints
+struct StandInProvider {
+    state: SharedState,
+}
+
+impl StandInProvider {
+    async fn start() -> Self {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let issuer = format!("http://{}", listener.local_addr().unwrap());
+        let state = Arc::new(Mutex::new(ProviderState {
+            issuer,
+            identity: json!({}),
+            audience: None,
+            codes: HashMap::new(),
+            refresh_tokens: Vec::new(),
+        }));
+
+        let app = Router::new()
+            .route("/.well-known/openid-configuration", get(discovery))
+            .route("/authorize", get(authorize))
+            .route("/token", post(token))
+            .route("/jwks", get(jwks))
+            .layer(Extension(Arc::clone(&state)));
+        tokio::spawn(async move {
+            axum::serve(listener, app).await.unwrap();
+        });
+
+        Self { state }
+    }
+
+    fn issuer(&self) -> String {
+        self.state.lock().unwrap().issuer.clone()
+    }
+
+    /// Set the user who signs in next
+    fn sign_in_as(&self, identity: Value) {
+        self.state.lock().unwrap().identity = identity;
+    }
+
+    fn set_audience(&self, audience: &str) {
+        self.state.lock().unwrap().audience = Some(audience.to_string());
+    }
+}
+
+async fn discovery(Extension(state): Extension<SharedState>) -> Json<Value> {
+    let issuer = state.lock().unwrap().issuer.clone();
+    Json(json!({
+        "issuer": issuer,
+        "authorization_endpoint": format!("{}/authorize", issuer),
+        "token_endpoint": format!("{}/token", issuer),
+        "jwks_uri": format!("{}/jwks", issuer),
+        "response_types_supported": ["code"],
+        "code_challenge_methods_supported": ["S256"],
+    }))
+}
+
+async fn authorize(
+    Extension(state): Extension<SharedState>,
+    Query(params): Query<HashMap<String, String>>,
+) -> Response {
+    if params.get("client_id").map(String::as_str) != Some(CLIENT_ID)
+        || params.get("code_challenge_method").map(String::as_str) != Some("S256")
+    {
+        return StatusCode::BAD_REQUEST.into_response();
+    }
+
+    let code = Uuid::new_v4().to_string();
+    state.lock().unwrap().codes.insert(
+        code.clone(),
+        AuthorizationCode {
+            challenge: params["code_challenge"].clone(),
+            nonce: params["nonce"].clone(),
+        },
+    );
+    let location = reqwest::Url::parse_with_params(
+        &params["redirect_uri"],
+        &[("code", code.as_str()), ("state", params["state"].as_str())],
+    )
+    .unwrap();
+    Redirect::to(location.as_str()).into_response()
+}
+
+async fn token(
+    Extension(state): Extension<SharedState>,
+    Form(params): Form<HashMap<String, String>>,
+) -> Response {
+    let invalid_grant = || {
+        (
+            StatusCode::BAD_REQUEST,
+            Json(json!({"error": "invalid_grant"})),
+        )
+            .into_response()
+    };
+    if params.get("client_id").map(String::as_str) != Some(CLIENT_ID)
+        || params.get("client_secret").map(String::as_str) != Some(CLIENT_SECRET)
+    {
+        return (
+            StatusCode::UNAUTHORIZED,
+            Json(json!({"error": "invalid_client"})),
+        )
+            .into_response();
+    }
+
+    let mut state = state.lock().unwrap();
+    let nonce = match params.get("grant_type").map(String::as_str) {
+        Some("authorization_code") => {
+            let Some(code) = state.codes.remove(&params["code"]) else {
+                return invalid_grant();
+            };
+            let verifier = params.get("code_verifier").cloned().unwrap_or_default();
+            if URL_SAFE_NO_PAD.encode(Sha256::digest(verifier.as_bytes())) != code.challenge {
+                return invalid_grant();
+            }
+            Some(code.nonce)
+        }
+        Some("refresh_token") => {
+            if !state.refresh_tokens.contains(&params["refresh_token"]) {
+                return invalid_grant();
+            }
+            None
+        }
+        _ => return invalid_grant(),
+    };
+
+    let mut claims = state.identity.clone();
+    claims["iss"] = json!(state.issuer);
+    claims["aud"] = json!(state.audience.as_deref().unwrap_or(CLIENT_ID));
+    claims["iat"] = json!(Utc::now().timestamp());
+    claims["exp"] = json!(Utc::now().timestamp() + 300);
+    if let Some(nonce) = nonce {
+        claims["nonce"] = json!(nonce);
+    }
+    let mut header = Header::new(Algorithm::RS256);
+    header.kid = Some(KEY_ID.to_string());
+    let key = EncodingKey::from_rsa_pem(include_bytes!("fixtures/oidc/test_key.pem")).unwrap();
+    let id_token = encode(&header, &claims, &key).unwrap();
+
+    let refresh_token = format!("refresh-{}", Uuid::new_v4());
+    state.refresh_tokens.push(refresh_token.clone());
+    Json(json!({
+        "access_token": format!("access-{}", Uuid::new_v4()),
+        "token_type": "Bearer",
+        "expires_in": 3600,
+        "refresh_token": refresh_token,
+        "id_token": id_token,
+        "scope": "openid email profile groups",
+    }))
+    .into_response()
+}
+
+async fn jwks() -> Json<Value> {
+    Json(json!({
+        "keys": [{
+            "kty": "RSA",
+            "kid": KEY_ID,
+            "use": "sig",
+            "alg": "RS256",
+            "n": MODULUS,
+            "e": "AQAB",
+        }]
+    }))
+}
+
+// ============================================================================
+// Fixture
+// ============================================================================
+
+struct Fixture {
+    provider: StandInProvider,
+    router: Router,
+    sso: Arc<SsoService>,
+    mfa: Arc<MfaService>,
+    users: Arc<MockUserStorage>,
+    organizations: Arc<MockOrganizationStorage>,
+    connections: Arc<MockOAuthConnectionStorage>,
+    platform_team: Uuid,
+}
+
+async fn fixture(auto_provision: bool) -> Fixture {
+    let provider = StandInProvider::start().await;
+    let users = Arc::new(MockUserStorage::new());
+    let organizations = Arc::new(MockOrganizationStorage::new());
+    let connections = Arc::new(MockOAuthConnectionStorage::new());
+    let acme = organizations.add_organization("acme");
+    let platform_team = organizations.add_team(acme, "Platform");
+
+    let config = OAuthConfig {
+        enabled: true,
+        providers: vec![OAuthProvider {
+            name: "corp".to_string(),
+            client_id: CLIENT_ID.to_string(),
+            client_secret: CLIENT_SECRET.to_string(),
+            scopes: vec!["email".to_string(), "profile".to_string()],
+            issuer: Some(provider.issuer()),
+            redirect_uri: Some(REDIRECT_URI.to_string()),
+            groups_claim: "groups".to_string(),
+            auto_provision,
+            default_roles: vec!["user".to_string()],
+            group_mappings: vec![
+                GroupMapping {
+                    group: "engineering".to_string(),
+                    roles: vec!["developer".to_string()],
+                    organization: Some("acme".to_string()),
+                    team: Some("Platform".to_string()),
+                    team_role: "member".to_string(),
+                },
+                GroupMapping {
+                    group: "platform-admins".to_string(),
+                    roles: vec!["admin".to_string()],
+                    organization: Some("acme".to_string()),
+                    team: Some("Platform".to_string()),
+                    team_role: "admin".to_string(),
+                },
+            ],
+        }],
+        token_encryption_key: None,
+        secure_cookie: true,
+    };
+    let sso = Arc::new(SsoService::new(
+        &config,
+        "test_secret_key_123",
+        users.clone(),
+        organizations.clone(),
+        organizations.clone(),
+        connections.clone(),
+    ));
+
+    let mfa = Arc::new(MfaService::new(
+        Arc::new(MockMfaStorage::new()),
+        "test_secret_key_123",
+        "Periplon",
+        300,
+    ));
+
+    let user_storage: Arc<dyn UserStorage> = users.clone();
+    let router = Router::new()
+        .route("/api/v1/auth/sso/:provider/login", get(sso_login))
+        .route("/api/v1/auth/sso/:provider/callback", get(sso_callback))
+        .route("/api/v1/auth/mfa/verify", post(verify_login))
+        .layer(Extension(Arc::clone(&sso)))
+        .layer(Extension(Arc::clone(&mfa)))
+        .layer(Extension(user_storage))
+        .layer(Extension(Arc::new(JwtManager::new(
+            "test_secret_key_123",
+            24,
+        ))));
+
+    Fixture {
+        provider,
+        router,
+        sso,
+        mfa,
+        users,
+        organizations,
+        connections,
+        platform_team,
+    }
+}
+
+fn identity(email: &str, verified: bool, groups: &[&str]) -> Value {
+    json!({
+        "sub": format!("corp|{}", email),
+        "email": email,
+        "email_verified": verified,
+        "name": "Ada Lovelace",
+        "groups": groups,
+    })
+}
+
+async fn get_path(router: &Router, path: &str) -> (StatusCode, Option<String>, Value) {
+    get_with_cookie(router, path, None).await
+}
+
+async fn get_with_cookie(
+    router: &Router,
+    path: &str,
+    cookie: Option<&str>,
+) -> (StatusCode, Option<String>, Value) {
+    let mut request = Request::builder().uri(path);
+    if let Some(cookie) = cookie {
+        request = request.header(header::COOKIE, cookie);
+    }
+    let response = router
+        .clone()
+        .oneshot(request.body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    let status = response.status();
+    let location = response
+        .headers()
+        .get(header::LOCATION)
+        .map(|value| value.to_str().unwrap().to_string());
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    (
+        status,
+        location,
+        serde_json::from_slice(&body).unwrap_or(Value::Null),
+    )
+}
+
+/// Callback of a login signed in at the provider
+struct Callback {
+    query: String,
+    /// State cookie set when the login started
+    cookie: String,
+}
+
+/// Start a login and sign in at the provider
+async fn authorize_at_provider(fixture: &Fixture) -> Callback {
+    let response = fixture
+        .router
+        .clone()
+        .oneshot(
+            Request::builder()
+                .uri("/api/v1/auth/sso/corp/login")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::SEE_OTHER);
+    let authorization_url = response.headers()[header::LOCATION].to_str().unwrap();
+    assert!(authorization_url.contains("code_challenge_method=S256"));
+    let set_cookie = response.headers()[header::SET_COOKIE].to_str().unwrap();
+    assert!(set_cookie.contains("HttpOnly"));
+    assert!(set_cookie.contains("SameSite=Lax"));
+    assert!(set_cookie.contains("Secure"));
+    let cookie = set_cookie.split(';').next().unwrap().to_string();
+
+    let client = reqwest::Client::builder()
+        .redirect(reqwest::redirect::Policy::none())
+        .build()
+        .unwrap();
+    let response = client.get(authorization_url).send().await.unwrap();
+    assert!(response.status().is_redirection());
+    let callback = reqwest::Url::parse(
+        response.headers()[reqwest::header::LOCATION]
+            .to_str()
+            .unwrap(),
+    )
+    .unwrap();
+    assert!(callback.as_str().starts_with(REDIRECT_URI));
+    Callback {
+        query: callback.query().unwrap().to_string(),
+        cookie,
+    }
+}
+
+async fn complete(fixture: &Fixture, query: &str, cookie: &str) -> StatusCode {
+    let path = format!("/api/v1/auth/sso/corp/callback?{}", query);
+    get_with_cookie(&fixture.router, &path, Some(cookie))
+        .await
+        .0
+}
+
+async fn sign_in(fixture: &Fixture) -> (StatusCode, Value) {
+    let callback = authorize_at_provider(fixture).await;
+    let (status, _, body) = get_with_cookie(
+        &fixture.router,
+        &format!("/api/v1/auth/sso/corp/callback?{}", callback.query),
+        Some(&callback.cookie),
+    )
+    .await;
+    (status, body)
+}
+
+fn query_param(query: &str, name: &str) -> String {
+    reqwest::Url::parse(&format!("http://callback/?{}", query))
+        .unwrap()
+        .query_pairs()
+        .find(|(key, _)| key == name)
+        .unwrap()
+        .1
+        .to_string()
+}
+
+fn existing_user(email: &str) -> User {
+    User {
+        id: Uuid::new_v4(),
+        email: email.to_string(),
+        name: "Existing".to_string(),
+        password_hash: String::new(),
+        roles: vec!["user".to_string()],
+        is_active: true,
+        email_verified: true,
+        created_at: Utc::now(),
+        updated_at: Utc::now(),
+        last_login_at: None,
+    }
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[tokio::test]
+async fn test_sso_provisions_user_with_group_roles() {
+    let fixture = fixture(true).await;
+    fixture.provider.sign_in_as(identity(
+        "ada@example.com",
+        true,
+        &["engineering", "platform-admins", "unmapped"],
+    ));
+
+    let (status, body) = sign_in(&fixture).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert!(body["token"].as_str().is_some());
+    assert_eq!(body["user"]["email"], "ada@example.com");
+    assert_eq!(body["user"]["name"], "Ada Lovelace");
+    assert_eq!(body["user"]["roles"], json!(["user", "admin", "developer"]));
+
+    let user = fixture
+        .users
+        .get_user_by_email("ada@example.com")
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(user.email_verified);
+    assert!(user.last_login_at.is_some());
+
+    // The highest team role of the user's groups applies
+    let members = fixture.organizations.members(fixture.platform_team);
+    assert_eq!(members.len(), 1);
+    assert_eq!(members[0].user_id, user.id);
+    assert_eq!(members[0].role, "admin");
+
+    // Provider tokens are stored encrypted, with the ID token claims
+    let connections = fixture.connections.connections();
+    assert_eq!(connections.len(), 1);
+    let connection = &connections[0];
+    assert_eq!(connection.user_id, user.id);
+    assert_eq!(connection.provider, "corp");
+    assert_eq!(connection.provider_user_id, "corp|ada@example.com");
+    let access_token = connection.access_token_encrypted.as_deref().unwrap();
+    assert!(!access_token.starts_with("access-"));
+    assert!(connection.refresh_token_encrypted.is_some());
+    assert!(connection.expires_at.is_some());
+    assert_eq!(
+        connection.scopes,
+        vec!["openid", "email", "profile", "groups"]
+    );
+    assert_eq!(connection.profile_data["email"], "ada@example.com");
+
+    // Signing in again reuses the account and the connection
+    let (status, body) = sign_in(&fixture).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["user"]["id"], user.id.to_string());
+    assert_eq!(fixture.users.user_count(), 1);
+    assert_eq!(fixture.connections.connections().len(), 1);
+}
+
+#[tokio::test]
+async fn test_sso_refresh_applies_group_changes() {
+    let fixture = fixture(true).await;
+    fixture.provider.sign_in_as(identity(
+        "ada@example.com",
+        true,
+        &["engineering", "platform-admins"],
+    ));
+    let (status, body) = sign_in(&fixture).await;
+    assert_eq!(status, StatusCode::OK);
+    let user_id = Uuid::parse_str(body["user"]["id"].as_str().unwrap()).unwrap();
+
+    // Roles no mapping mentions are left alone
+    fixture.users.add_role(user_id, "auditor").await.unwrap();
+    let refresh_token_before = fixture.connections.connections()[0]
+        .refresh_token_encrypted
+        .clone();
+
+    // The user leaves the admin group at the provider
+    fixture
+        .provider
+        .sign_in_as(identity("ada@example.com", true, &["engineering"]));
+    let user = fixture.sso.refresh("corp", user_id).await.unwrap();
+    assert_eq!(user.roles, vec!["user", "developer", "auditor"]);
+    let members = fixture.organizations.members(fixture.platform_team);
+    assert_eq!(members.len(), 1);
+    assert_eq!(members[0].role, "member");
+    assert_ne!(
+        fixture.connections.connections()[0].refresh_token_encrypted,
+        refresh_token_before
+    );
+
+    // And then leaves engineering too
+    fixture
+        .provider
+        .sign_in_as(identity("ada@example.com", true, &[]));
+    let user = fixture.sso.refresh("corp", user_id).await.unwrap();
+    assert_eq!(user.roles, vec!["user", "auditor"]);
+    assert!(fixture
+        .organizations
+        .members(fixture.platform_team)
+        .is_empty());
+
+    // Users without a connection have nothing to refresh
+    let error = fixture
+        .sso
+        .refresh("corp", Uuid::new_v4())
+        .await
+        .unwrap_err();
+    assert_eq!(error.status(), StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn test_sso_checks_state_and_pkce() {
+    let fixture = fixture(true).await;
+    fixture
+        .provider
+        .sign_in_as(identity("ada@example.com", true, &[]));
+
+    // An unknown state is rejected
+    let (status, _, _) = get_path(
+        &fixture.router,
+        "/api/v1/auth/sso/corp/callback?code=abc&state=forged",
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    // Started logins are stored with their verifier encrypted
+    let first = authorize_at_provider(&fixture).await;
+    let second = authorize_at_provider(&fixture).await;
+    let pending = fixture.connections.pending_logins();
+    assert_eq!(pending.len(), 2);
+    let first_hash = format!(
+        "{:x}",
+        Sha256::digest(query_param(&first.query, "state").as_bytes())
+    );
+    let login = pending
+        .iter()
+        .find(|login| login.state_hash == first_hash)
+        .unwrap();
+    assert_eq!(login.provider, "corp");
+    assert!(login.expires_at > Utc::now());
+    assert!(first.cookie.ends_with(&first_hash));
+
+    // A code redeemed under another login's state fails the PKCE check
+    let query = format!(
+        "code={}&state={}",
+        query_param(&first.query, "code"),
+        query_param(&second.query, "state")
+    );
+    assert_eq!(
+        complete(&fixture, &query, &second.cookie).await,
+        StatusCode::BAD_GATEWAY
+    );
+    assert_eq!(fixture.connections.pending_logins().len(), 1);
+
+    // The callback needs the state cookie of the browser that started the
+    // login, so it cannot be completed in another one
+    let third = authorize_at_provider(&fixture).await;
+    let (status, _, _) = get_path(
+        &fixture.router,
+        &format!("/api/v1/auth/sso/corp/callback?{}", third.query),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(
+        complete(&fixture, &third.query, &first.cookie).await,
+        StatusCode::BAD_REQUEST
+    );
+
+    // Each state can be used once
+    assert_eq!(
+        complete(&fixture, &third.query, &third.cookie).await,
+        StatusCode::OK
+    );
+    assert_eq!(
+        complete(&fixture, &third.query, &third.cookie).await,
+        StatusCode::BAD_REQUEST
+    );
+
+    // Provider errors and unknown providers
+    let (status, _, body) = get_path(
+        &fixture.router,
+        "/api/v1/auth/sso/corp/callback?error=access_denied",
+    )
+    .await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    assert!(body["error"].as_str().unwrap().contains("access_denied"));
+    let (status, _, _) = get_path(&fixture.router, "/api/v1/auth/sso/other/login").await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_sso_rejects_id_token_for_other_client() {
+    let fixture = fixture(true).await;
+    fixture
+        .provider
+        .sign_in_as(identity("ada@example.com", true, &[]));
+    fixture.provider.set_audience("another-client");
+
+    let (status, _) = sign_in(&fixture).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    assert_eq!(fixture.users.user_count(), 0);
+}
+
+#[tokio::test]
+async fn test_sso_links_existing_account_by_verified_email() {
+    let fixture = fixture(true).await;
+    let existing = existing_user("grace@example.com");
+    fixture.users.add_user(existing.clone());
+
+    // An unverified email does not take over the account
+    fixture
+        .provider
+        .sign_in_as(identity("grace@example.com", false, &[]));
+    let (status, _) = sign_in(&fixture).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    assert!(fixture.connections.connections().is_empty());
+
+    fixture
+        .provider
+        .sign_in_as(identity("Grace@Example.com", true, &["engineering"]));
+    let (status, body) = sign_in(&fixture).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["user"]["id"], existing.id.to_string());
+    assert_eq!(body["user"]["roles"], json!(["user", "developer"]));
+    assert_eq!(fixture.users.user_count(), 1);
+    assert_eq!(fixture.connections.connections()[0].user_id, existing.id);
+}
+
+#[tokio::test]
+async fn test_sso_login_requires_mfa_code() {
+    let fixture = fixture(true).await;
+    let existing = existing_user("grace@example.com");
+    fixture.users.add_user(existing.clone());
+    let enrollment = fixture
+        .mfa
+        .enroll(existing.id, "grace@example.com")
+        .await
+        .unwrap();
+    let code = totp(&enrollment.secret, Utc::now().timestamp()).unwrap();
+    fixture.mfa.confirm(existing.id, &code).await.unwrap();
+
+    // Signing in through the provider only completes the first step
+    fixture
+        .provider
+        .sign_in_as(identity("grace@example.com", true, &[]));
+    let (status, body) = sign_in(&fixture).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["mfa_required"], true);
+    assert!(body["token"].is_null());
+    let challenge = body["challenge_token"].as_str().unwrap().to_string();
+
+    let next_code = totp(
+        &enrollment.secret,
+        Utc::now().timestamp() + TOTP_PERIOD_SECS,
+    )
+    .unwrap();
+    let response = fixture
+        .router
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/api/v1/auth/mfa/verify")
+                .header(header::CONTENT_TYPE, "application/json")
+                .body(Body::from(
+                    json!({"challenge_token": challenge, "code": next_code}).to_string(),
+                ))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    let body: Value = serde_json::from_slice(&body).unwrap();
+    assert!(body["token"].is_string());
+    assert_eq!(body["user"]["id"], existing.id.to_string());
+}
+
+#[tokio::test]
+async fn test_sso_without_auto_provisioning() {
+    let fixture = fixture(false).await;
+    fixture
+        .provider
+        .sign_in_as(identity("new@example.com", true, &[]));
+    let (status, _) = sign_in(&fixture).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    assert_eq!(fixture.users.user_count(), 0);
+
+    let mut disabled = existing_user("disabled@example.com");
+    disabled.is_active = false;
+    fixture.users.add_user(disabled);
+    fixture
+        .provider
+        .sign_in_as(identity("disabled@example.com", true, &[]));
+    let (status, body) = sign_in(&fixture).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    assert_eq!(body["error"], "User account is disabled");
+}
@@ -8,6 +8,7 @@
 
 use chrono::Utc;
 use periplon_sdk::dsl::schema::DSLWorkflow;
+use periplon_sdk::dsl::Usage;
 use periplon_sdk::server::storage::filesystem::FilesystemStorage;
 use periplon_sdk::server::storage::{
     Checkpoint, CheckpointStorage, Execution, ExecutionFilter, ExecutionLog, ExecutionStatus,
@@ -44,6 +45,8 @@ fn create_test_workflow(name: &str) -> (DSLWorkflow, WorkflowMetadata) {
         imports: HashMap::new(),
         notifications: None,
         limits: None,
+        max_concurrency: None,
+        providers: HashMap::new(),
     };
 
     let metadata = WorkflowMetadata {
@@ -97,6 +100,9 @@ fn create_test_execution(workflow_id: Uuid, status: ExecutionStatus) -> Executio
         },
         retry_count: 0,
         parent_execution_id: None,
+        completed_tasks: 0,
+        total_tasks: 0,
+        usage: Default::default(),
     }
 }
 
@@ -323,6 +329,35 @@ async fn test_filesystem_update_execution_status() {
     assert!(retrieved.started_at.is_some());
 }
 
+#[tokio::test]
+async fn test_filesystem_update_execution_progress_keeps_status() {
+    let (storage, _temp) = setup_filesystem_storage().await;
+    let execution = create_test_execution(Uuid::new_v4(), ExecutionStatus::Cancelled);
+    let execution_id = execution.id;
+    storage.store_execution(&execution).await.unwrap();
+
+    let usage = Usage {
+        input_tokens: 10,
+        output_tokens: 5,
+        ..Default::default()
+    };
+    storage
+        .update_execution_progress(execution_id, 2, 3, &usage)
+        .await
+        .unwrap();
+
+    let retrieved = storage.get_execution(execution_id).await.unwrap().unwrap();
+    assert_eq!(retrieved.status, ExecutionStatus::Cancelled);
+    assert_eq!(retrieved.completed_tasks, 2);
+    assert_eq!(retrieved.total_tasks, 3);
+    assert_eq!(retrieved.usage, usage);
+
+    assert!(storage
+        .update_execution_progress(Uuid::new_v4(), 0, 0, &usage)
+        .await
+        .is_err());
+}
+
 #[tokio::test]
 async fn test_filesystem_list_executions_by_workflow() {
     let (storage, _temp) = setup_filesystem_storage().await;
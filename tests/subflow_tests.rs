@@ -115,6 +115,8 @@ fn test_subflow_validation_missing_reference() {
         mcp_servers: HashMap::new(),
         subflows: HashMap::new(),
         imports: HashMap::new(),
+        max_concurrency: None,
+        providers: HashMap::new(),
     };
 
     let task = TaskSpec {
@@ -156,6 +158,8 @@ fn test_subflow_validation_agent_and_subflow_mutually_exclusive() {
         mcp_servers: HashMap::new(),
         subflows: HashMap::new(),
         imports: HashMap::new(),
+        max_concurrency: None,
+        providers: HashMap::new(),
     };
 
     workflow.agents.insert(
@@ -172,6 +176,8 @@ fn test_subflow_validation_agent_and_subflow_mutually_exclusive() {
             tools: vec![],
             permissions: PermissionsSpec::default(),
             max_turns: None,
+            max_concurrency: None,
+            profile: None,
         },
     );
 
@@ -227,6 +233,8 @@ fn test_subflow_validation_missing_required_input() {
         mcp_servers: HashMap::new(),
         subflows: HashMap::new(),
         imports: HashMap::new(),
+        max_concurrency: None,
+        providers: HashMap::new(),
     };
 
     let mut inputs = HashMap::new();
@@ -292,6 +300,8 @@ fn test_subflow_validation_valid_inline() {
         mcp_servers: HashMap::new(),
         subflows: HashMap::new(),
         imports: HashMap::new(),
+        max_concurrency: None,
+        providers: HashMap::new(),
     };
 
     let mut subflow_agents = HashMap::new();
@@ -309,6 +319,8 @@ fn test_subflow_validation_valid_inline() {
             tools: vec!["Read".to_string()],
             permissions: PermissionsSpec::default(),
             max_turns: None,
+            max_concurrency: None,
+            profile: None,
         },
     );
 
@@ -348,6 +360,8 @@ fn test_subflow_validation_valid_inline() {
             tools: vec![],
             permissions: PermissionsSpec::default(),
             max_turns: None,
+            max_concurrency: None,
+            profile: None,
         },
     );
 
@@ -488,3 +502,231 @@ agents:
         panic!("Expected task_output source");
     }
 }
+
+/// Build a parent workflow that runs the `report` subflow through a command task
+fn report_workflow(subflow_source: &str, task_inputs: &str) -> String {
+    format!(
+        r#"
+name: "Report Workflow"
+version: "1.0.0"
+
+inputs:
+  quarter:
+    type: string
+    default: "Q3"
+
+subflows:
+  report:
+{subflow_source}
+
+tasks:
+  make_report:
+    description: "Build the report"
+    subflow: "report"
+    inputs:
+{task_inputs}
+"#
+    )
+}
+
+const INLINE_REPORT_SUBFLOW: &str = r#"    inputs:
+      title:
+        type: string
+        required: true
+      copies:
+        type: number
+        default: 1
+    outputs:
+      heading:
+        source:
+          type: task_output
+          task: render
+    tasks:
+      render:
+        description: "Render heading"
+        command:
+          executable: "echo"
+          args: ["Report ${workflow.title} x${workflow.copies}"]
+      publish:
+        description: "Publish"
+        depends_on: [render]
+        command:
+          executable: "echo"
+          args: ["published"]"#;
+
+#[tokio::test]
+async fn test_subflow_task_runs_child_workflow() {
+    use periplon_sdk::dsl::DSLExecutor;
+
+    let yaml = report_workflow(
+        INLINE_REPORT_SUBFLOW,
+        r#"      title: "${workflow.quarter}"
+      copies: "2""#,
+    );
+    let workflow = parse_workflow(&yaml).unwrap();
+    validate_workflow(&workflow).unwrap();
+
+    let state_dir = tempfile::tempdir().unwrap();
+    let mut executor = DSLExecutor::new(workflow).unwrap();
+    executor
+        .enable_state_persistence(Some(state_dir.path().to_str().unwrap()))
+        .unwrap();
+    executor.initialize().await.unwrap();
+    executor.execute().await.expect("subflow should run");
+
+    let state = executor.get_state().unwrap();
+
+    // Declared outputs surface as the parent task's output
+    let output = state.get_task_output("make_report").expect("task output");
+    let parsed: serde_json::Value = serde_json::from_str(&output.content).unwrap();
+    assert_eq!(parsed, serde_json::json!({"heading": "Report Q3 x2"}));
+
+    // The child's state is nested under the parent task
+    let child = state
+        .get_subflow_state("make_report")
+        .expect("nested subflow state");
+    assert_eq!(child.workflow_name, "Report Workflow/report");
+    assert_eq!(child.get_completed_tasks().len(), 2);
+    assert!(child
+        .get_task_output("publish")
+        .unwrap()
+        .content
+        .contains("published"));
+}
+
+#[tokio::test]
+async fn test_subflow_task_rejects_invalid_inputs() {
+    use periplon_sdk::dsl::DSLExecutor;
+
+    let yaml = report_workflow(
+        INLINE_REPORT_SUBFLOW,
+        r#"      title: "Q4"
+      copies: "several""#,
+    );
+    let workflow = parse_workflow(&yaml).unwrap();
+    let mut executor = DSLExecutor::new(workflow).unwrap();
+    executor.initialize().await.unwrap();
+
+    let err = executor.execute().await.unwrap_err().to_string();
+    assert!(err.contains("input 'copies' expects type 'number'"));
+}
+
+#[tokio::test]
+async fn test_file_subflow_fetched_at_runtime() {
+    use periplon_sdk::dsl::DSLExecutor;
+
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(
+        dir.path().join("greet.yaml"),
+        r#"
+name: "greet"
+version: "1.0.0"
+inputs:
+  who:
+    type: string
+    required: true
+tasks:
+  say:
+    description: "Say hello"
+    command:
+      executable: "echo"
+      args: ["hello ${workflow.who}"]
+"#,
+    )
+    .unwrap();
+
+    let yaml = format!(
+        r#"
+name: "Runtime Fetch"
+version: "1.0.0"
+cwd: "{}"
+
+subflows:
+  greet:
+    source:
+      type: file
+      path: "greet.yaml"
+
+tasks:
+  hello:
+    description: "Greet"
+    subflow: "greet"
+    inputs:
+      who: "world"
+"#,
+        dir.path().display()
+    );
+
+    let workflow = parse_workflow(&yaml).unwrap();
+    let state_dir = tempfile::tempdir().unwrap();
+    let mut executor = DSLExecutor::new(workflow).unwrap();
+    executor
+        .enable_state_persistence(Some(state_dir.path().to_str().unwrap()))
+        .unwrap();
+    executor.initialize().await.unwrap();
+    executor
+        .execute()
+        .await
+        .expect("fetched subflow should run");
+
+    // Without declared outputs every child task output is returned
+    let output = executor
+        .get_state()
+        .unwrap()
+        .get_task_output("hello")
+        .unwrap()
+        .content
+        .clone();
+    let parsed: serde_json::Value = serde_json::from_str(&output).unwrap();
+    assert_eq!(parsed, serde_json::json!({"say": "hello world"}));
+}
+
+#[tokio::test]
+async fn test_subflow_events_are_scoped_to_parent_task() {
+    use periplon_sdk::dsl::events::ExecutionEvent;
+    use periplon_sdk::dsl::DSLExecutor;
+
+    // The parent has its own `render` task, like the subflow
+    let yaml = format!(
+        r#"{}
+  render:
+    description: "Render the parent heading"
+    command:
+      executable: "echo"
+      args: ["parent"]
+"#,
+        report_workflow(INLINE_REPORT_SUBFLOW, r#"      title: "Q1""#)
+    );
+    let workflow = parse_workflow(&yaml).unwrap();
+    validate_workflow(&workflow).unwrap();
+
+    let mut executor = DSLExecutor::new(workflow).unwrap();
+    executor.events().console().set_enabled(false);
+    let mut events = executor.events().channel();
+    executor.initialize().await.unwrap();
+    executor.execute().await.expect("workflow should run");
+
+    let mut succeeded = Vec::new();
+    while let Ok(event) = events.try_recv() {
+        if let ExecutionEvent::TaskSucceeded { task_id, .. } = event {
+            succeeded.push(task_id);
+        }
+    }
+    succeeded.sort();
+    assert_eq!(
+        succeeded,
+        vec![
+            "make_report",
+            "make_report/publish",
+            "make_report/render",
+            "render"
+        ]
+    );
+
+    let state = executor.get_state().unwrap();
+    assert!(state
+        .get_task_output("render")
+        .unwrap()
+        .content
+        .contains("parent"));
+}
@@ -0,0 +1,91 @@
+//! Task Output Reference Tests
+//!
+//! Verifies that tasks record structured results and that later tasks can
+//! reference fields inside them with `${task.<id>.output.<path>}`.
+
+use periplon_sdk::dsl::executor::DSLExecutor;
+use periplon_sdk::dsl::parse_workflow;
+use serde_json::json;
+
+async fn run(yaml: &str) -> DSLExecutor {
+    let workflow = parse_workflow(yaml).expect("Failed to parse workflow");
+    let mut executor = DSLExecutor::new(workflow).unwrap();
+    executor.initialize().await.unwrap();
+    executor.execute().await.expect("workflow should succeed");
+    executor
+}
+
+#[tokio::test]
+async fn test_script_stdout_is_parsed_as_json() {
+    let executor = run(r#"
+name: "Structured Output"
+version: "1.0.0"
+tasks:
+  fetch:
+    description: "Emit JSON"
+    script:
+      language: bash
+      content: |
+        echo '{"items": [{"id": 7}, {"id": 9}], "total": 2}'
+"#)
+    .await;
+
+    let state = executor.get_state().expect("state is tracked");
+    let output = state.get_task_output("fetch").unwrap();
+    assert_eq!(
+        output.structured(),
+        json!({"items": [{"id": 7}, {"id": 9}], "total": 2})
+    );
+}
+
+#[tokio::test]
+async fn test_nested_reference_between_tasks() {
+    let dir = tempfile::tempdir().unwrap();
+    let result = dir.path().join("result.txt");
+
+    let yaml = format!(
+        r#"
+name: "Nested References"
+version: "1.0.0"
+tasks:
+  fetch:
+    description: "Emit JSON"
+    script:
+      language: bash
+      content: |
+        echo '{{"items": [{{"id": 7, "name": "first"}}, {{"id": 9}}]}}'
+  use:
+    description: "Consume a field"
+    depends_on: [fetch]
+    command:
+      executable: "bash"
+      args:
+        - "-c"
+        - "echo ${{task.fetch.output.items[0].id}}-${{task.fetch.output.items[0].name}} > {result}"
+"#,
+        result = result.display()
+    );
+    run(&yaml).await;
+
+    assert_eq!(std::fs::read_to_string(&result).unwrap().trim(), "7-first");
+}
+
+#[tokio::test]
+async fn test_plain_text_output_is_kept_as_string() {
+    let executor = run(r#"
+name: "Plain Output"
+version: "1.0.0"
+tasks:
+  greet:
+    description: "Emit text"
+    command:
+      executable: "echo"
+      args: ["hello world"]
+"#)
+    .await;
+
+    let state = executor.get_state().unwrap();
+    let output = state.get_task_output("greet").unwrap();
+    assert_eq!(output.structured(), json!("hello world"));
+    assert_eq!(output.content, "hello world\n");
+}
@@ -0,0 +1,108 @@
+//! Task Session Tests
+//!
+//! Verifies parsing and validation of the task `session:` option, and that
+//! the session IDs recorded in the workflow state survive a checkpoint so
+//! resumed workflows can reattach to their conversations.
+
+use periplon_sdk::dsl::state::{StatePersistence, WorkflowState};
+use periplon_sdk::dsl::{parse_workflow, validate_workflow, SessionSpec};
+
+const WORKFLOW: &str = r#"
+name: "Sessions"
+version: "1.0.0"
+agents:
+  writer:
+    description: "Writes"
+tasks:
+  outline:
+    description: "Outline the article"
+    agent: writer
+    session: new
+  draft:
+    description: "Draft it"
+    agent: writer
+    session: continue
+    depends_on: [outline]
+  alternative:
+    description: "Try another angle"
+    agent: writer
+    session: fork
+    depends_on: [draft]
+  review:
+    description: "Review"
+    agent: writer
+    session: editorial
+  summary:
+    description: "Summarize"
+    agent: writer
+"#;
+
+#[test]
+fn test_session_values_parse() {
+    let workflow = parse_workflow(WORKFLOW).unwrap();
+    let session = |task: &str| workflow.tasks[task].session.clone();
+
+    assert_eq!(session("outline"), Some(SessionSpec::New));
+    assert_eq!(session("draft"), Some(SessionSpec::Continue));
+    assert_eq!(session("alternative"), Some(SessionSpec::Fork));
+    assert_eq!(
+        session("review"),
+        Some(SessionSpec::Named("editorial".to_string()))
+    );
+    assert_eq!(session("summary"), None);
+    assert!(validate_workflow(&workflow).is_ok());
+
+    // Sessions serialize back to the strings they were written as
+    let yaml = serde_yaml::to_string(&workflow.tasks["review"]).unwrap();
+    assert!(yaml.contains("session: editorial"));
+}
+
+#[test]
+fn test_session_requires_agent_task() {
+    let workflow = parse_workflow(
+        r#"
+name: "Bad Session"
+version: "1.0.0"
+tasks:
+  build:
+    description: "Build"
+    session: continue
+    script:
+      language: bash
+      content: "make"
+"#,
+    )
+    .unwrap();
+
+    let error = validate_workflow(&workflow).unwrap_err().to_string();
+    assert!(error.contains("Task 'build' sets a session but does not run an agent"));
+}
+
+#[test]
+fn test_sessions_survive_checkpoint() {
+    let dir = tempfile::tempdir().unwrap();
+    let persistence = StatePersistence::new(dir.path()).unwrap();
+
+    let mut state = WorkflowState::new("Sessions".to_string(), "1.0.0".to_string());
+    state.record_session("writer", None, "session-1");
+    state.record_session("writer", Some("editorial"), "session-2");
+    persistence.save_state(&state).unwrap();
+
+    let loaded = persistence.load_state("Sessions").unwrap();
+    assert_eq!(loaded.agent_session("writer"), Some("session-2"));
+    assert_eq!(loaded.named_session("editorial"), Some("session-2"));
+    assert_eq!(loaded.agent_session("reviewer"), None);
+}
+
+#[test]
+fn test_state_without_sessions_loads() {
+    let state = WorkflowState::new("Old".to_string(), "1.0.0".to_string());
+    let mut json = serde_json::to_value(&state).unwrap();
+    let fields = json.as_object_mut().unwrap();
+    assert!(!fields.contains_key("agent_sessions"));
+    fields.remove("named_sessions");
+
+    let loaded: WorkflowState = serde_json::from_value(json).unwrap();
+    assert!(loaded.agent_sessions.is_empty());
+    assert!(loaded.named_sessions.is_empty());
+}
@@ -82,6 +82,8 @@ fi
         inputs: HashMap::new(),
         outputs: HashMap::new(),
         limits: None,
+        max_concurrency: None,
+        providers: HashMap::new(),
     };
 
     // Add task with retry configuration
@@ -175,6 +177,8 @@ exit 1
         inputs: HashMap::new(),
         outputs: HashMap::new(),
         limits: None,
+        max_concurrency: None,
+        providers: HashMap::new(),
     };
 
     // Add task with exponential backoff
@@ -305,6 +309,8 @@ exit 0
         inputs: HashMap::new(),
         outputs: HashMap::new(),
         limits: None,
+        max_concurrency: None,
+        providers: HashMap::new(),
     };
 
     // Note: Fallback agents are designed for agent-based tasks, not command tasks
@@ -395,6 +401,8 @@ exit 1
         inputs: HashMap::new(),
         outputs: HashMap::new(),
         limits: None,
+        max_concurrency: None,
+        providers: HashMap::new(),
     };
 
     let task = TaskSpec {
@@ -509,6 +517,8 @@ exit 1
         inputs: HashMap::new(),
         outputs: HashMap::new(),
         limits: None,
+        max_concurrency: None,
+        providers: HashMap::new(),
     };
 
     let task = TaskSpec {
@@ -612,6 +622,8 @@ exit 0
         inputs: HashMap::new(),
         outputs: HashMap::new(),
         limits: None,
+        max_concurrency: None,
+        providers: HashMap::new(),
     };
 
     let task = TaskSpec {
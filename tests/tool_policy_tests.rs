@@ -0,0 +1,191 @@
+//! Tool Policy Tests
+//!
+//! Verifies that agent tool guards apply the workflow's `tools:` configuration
+//! and allowed directories, route checked tools through the permission
+//! callback, and report denials as events and debugger side effects.
+
+use periplon_sdk::domain::{PermissionResult, ToolPermissionContext};
+use periplon_sdk::dsl::debugger::{DebuggerState, SideEffectType};
+use periplon_sdk::dsl::events::{ExecutionEvent, ExecutionEvents};
+use periplon_sdk::dsl::parse_workflow;
+use periplon_sdk::dsl::tool_policy::{FileScope, ToolGuard, ToolPolicy};
+use periplon_sdk::AgentOptions;
+use serde_json::json;
+use std::path::PathBuf;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+const WORKFLOW: &str = r#"
+name: "Guarded"
+version: "1.0.0"
+agents:
+  writer:
+    description: "Writes docs"
+    tools: [Read, Write, Bash, WebSearch]
+    permissions:
+      allowed_directories: [docs]
+tools:
+  disallowed: [WebFetch]
+  constraints:
+    Bash:
+      allowed_commands: [ls, cat]
+    Write:
+      allowed_extensions: [md]
+      max_file_size: 1000
+    WebSearch:
+      rate_limit: 1
+"#;
+
+struct Fixture {
+    guard: Arc<ToolGuard>,
+    events: Arc<ExecutionEvents>,
+    debugger: Arc<Mutex<DebuggerState>>,
+}
+
+fn setup() -> Fixture {
+    let workflow = parse_workflow(WORKFLOW).unwrap();
+    let agent = &workflow.agents["writer"];
+    let events = Arc::new(ExecutionEvents::new());
+    events.console().set_enabled(false);
+    let debugger = Arc::new(Mutex::new(DebuggerState::new()));
+
+    let guard = Arc::new(ToolGuard::new(
+        "writer".to_string(),
+        agent.tools.clone(),
+        Arc::new(ToolPolicy::new(workflow.tools.clone().unwrap())),
+        FileScope::new(
+            PathBuf::from("/project"),
+            &agent.permissions.allowed_directories,
+        ),
+        events.clone(),
+        Some(debugger.clone()),
+    ));
+    guard.set_task("write_docs");
+
+    Fixture {
+        guard,
+        events,
+        debugger,
+    }
+}
+
+fn is_allowed(result: &PermissionResult) -> bool {
+    matches!(result, PermissionResult::Allow { .. })
+}
+
+#[test]
+fn test_checked_tools_are_not_pre_approved() {
+    let fixture = setup();
+    let mut options = AgentOptions {
+        allowed_tools: vec![
+            "Read".to_string(),
+            "Write".to_string(),
+            "Bash".to_string(),
+            "WebSearch".to_string(),
+        ],
+        ..Default::default()
+    };
+    fixture.guard.apply_to(&mut options);
+
+    assert_eq!(options.allowed_tools, vec!["Read".to_string()]);
+    assert_eq!(options.disallowed_tools, vec!["WebFetch".to_string()]);
+    assert_eq!(
+        options.permission_prompt_tool_name.as_deref(),
+        Some("stdio")
+    );
+    assert!(options.can_use_tool.is_some());
+}
+
+#[tokio::test]
+async fn test_callback_enforces_constraints() {
+    let fixture = setup();
+    let callback = fixture.guard.callback();
+    let call = |tool: &str, input: serde_json::Value| {
+        let context = ToolPermissionContext {
+            signal: None,
+            suggestions: vec![],
+        };
+        callback(tool.to_string(), input, context)
+    };
+
+    assert!(is_allowed(
+        &call("Bash", json!({"command": "ls docs"})).await
+    ));
+    assert!(!is_allowed(
+        &call("Bash", json!({"command": "rm -rf docs"})).await
+    ));
+    assert!(is_allowed(
+        &call(
+            "Write",
+            json!({"file_path": "docs/guide.md", "content": "# Guide"})
+        )
+        .await
+    ));
+    assert!(!is_allowed(
+        &call(
+            "Write",
+            json!({"file_path": "src/lib.md", "content": "# Guide"})
+        )
+        .await
+    ));
+    assert!(!is_allowed(
+        &call(
+            "Write",
+            json!({"file_path": "docs/big.md", "content": "x".repeat(1001)})
+        )
+        .await
+    ));
+    assert!(!is_allowed(
+        &call("WebFetch", json!({"url": "https://example.com"})).await
+    ));
+    // Not one of the agent's tools
+    assert!(!is_allowed(&call("Glob", json!({"pattern": "*"})).await));
+
+    // One search per minute
+    assert!(is_allowed(
+        &call("WebSearch", json!({"query": "rust"})).await
+    ));
+    assert!(!is_allowed(
+        &call("WebSearch", json!({"query": "rust"})).await
+    ));
+}
+
+#[tokio::test]
+async fn test_denials_are_reported() {
+    let fixture = setup();
+    let mut channel = fixture.events.channel();
+
+    let result = fixture
+        .guard
+        .check("Bash", json!({"command": "curl example.com"}))
+        .await;
+    let PermissionResult::Deny { message, interrupt } = result else {
+        panic!("curl should be denied");
+    };
+    assert_eq!(message, "Command 'curl example.com' is not allowed");
+    assert!(!interrupt);
+
+    match channel.try_recv().unwrap() {
+        ExecutionEvent::ToolDenied {
+            task_id,
+            agent,
+            tool,
+            reason,
+        } => {
+            assert_eq!(task_id, "write_docs");
+            assert_eq!(agent, "writer");
+            assert_eq!(tool, "Bash");
+            assert_eq!(reason, message);
+        }
+        other => panic!("unexpected event {:?}", other),
+    }
+
+    let debugger = fixture.debugger.lock().await;
+    let effects = debugger.side_effects.effects_for_task("write_docs");
+    assert_eq!(effects.len(), 1);
+    assert!(matches!(
+        &effects[0].effect_type,
+        SideEffectType::ToolDenied { tool_name, input, .. }
+            if tool_name == "Bash" && input["command"] == "curl example.com"
+    ));
+}
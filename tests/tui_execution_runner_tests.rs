@@ -0,0 +1,241 @@
+//! TUI Execution Runner Tests
+//!
+//! Verifies that workflows run from the TUI report their progress to the
+//! execution monitor: task status, logs and token usage arrive as
+//! `ExecutionUpdate`s, a paused run resumes from its checkpoint, a cancelled
+//! one is saved as cancelled, and the final state is listed in the state
+//! browser.
+
+#![cfg(feature = "tui")]
+
+mod common {
+    pub mod http;
+}
+
+use common::http::{CannedResponse, FakeServer};
+use periplon_sdk::dsl::{parse_workflow, StatePersistence, WorkflowStatus};
+use periplon_sdk::tui::events::{AppEvent, ExecutionUpdate};
+use periplon_sdk::tui::runner::{ExecutionHandle, StopReason};
+use periplon_sdk::tui::views::execution_monitor::{
+    ExecutionMonitorState, ExecutionStatus, TaskStatus,
+};
+use periplon_sdk::tui::views::state_browser::StateBrowserState;
+use serde_json::json;
+use std::time::Duration;
+use tokio::sync::mpsc;
+
+/// Start a fake Ollama server reporting 12 input and 3 output tokens per request
+async fn start_server() -> FakeServer {
+    FakeServer::answering(CannedResponse::json(json!({
+        "model": "test-model",
+        "message": {"role": "assistant", "content": "done"},
+        "done": true,
+        "done_reason": "stop",
+        "prompt_eval_count": 12,
+        "eval_count": 3,
+    })))
+    .await
+}
+
+/// Apply updates to the monitor until the execution finishes
+async fn run_to_end(
+    rx: &mut mpsc::UnboundedReceiver<AppEvent>,
+    monitor: &mut ExecutionMonitorState,
+) -> Vec<ExecutionUpdate> {
+    let mut updates = Vec::new();
+    loop {
+        let event = tokio::time::timeout(Duration::from_secs(30), rx.recv())
+            .await
+            .expect("execution did not finish")
+            .unwrap();
+        if let AppEvent::ExecutionUpdate(update) = event {
+            updates.push(update.clone());
+            let finished = matches!(update, ExecutionUpdate::Finished { .. });
+            monitor.apply_update(update);
+            if finished {
+                return updates;
+            }
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_monitor_follows_background_execution() {
+    let server = start_server().await;
+    let yaml = format!(
+        r#"
+name: "TUI Run"
+version: "1.0.0"
+tasks:
+  ask:
+    description: "Ask the model"
+    llm:
+      provider: ollama
+      model: "test-model"
+      endpoint: "{}"
+      prompt: "Hello"
+  check:
+    description: "Check the answer"
+    depends_on: [ask]
+    script:
+      language: bash
+      content: "true"
+"#,
+        server.url
+    );
+    let workflow = parse_workflow(&yaml).unwrap();
+    let state_dir = tempfile::tempdir().unwrap();
+    let (tx, mut rx) = mpsc::unbounded_channel();
+
+    let mut monitor = ExecutionMonitorState::new(workflow.clone());
+    let _handle = ExecutionHandle::spawn(workflow, state_dir.path(), false, tx).unwrap();
+    let updates = run_to_end(&mut rx, &mut monitor).await;
+
+    // Initialization progress is logged before the workflow starts
+    let first = updates
+        .iter()
+        .find(|update| !matches!(update, ExecutionUpdate::LogMessage { .. }));
+    assert!(matches!(
+        first,
+        Some(ExecutionUpdate::WorkflowStarted { tasks, .. }) if tasks == &["ask", "check"]
+    ));
+    match updates.last() {
+        Some(ExecutionUpdate::Finished { state, error }) => {
+            assert!(error.is_none());
+            assert_eq!(state.as_ref().unwrap().status, WorkflowStatus::Completed);
+        }
+        other => panic!("Expected Finished, got {:?}", other),
+    }
+
+    assert_eq!(monitor.status, ExecutionStatus::Completed);
+    assert_eq!(monitor.tasks["ask"].status, TaskStatus::Completed);
+    assert_eq!(monitor.tasks["check"].status, TaskStatus::Completed);
+    assert_eq!(monitor.tasks["ask"].tokens.unwrap().input_tokens, 12);
+    assert_eq!(monitor.stats.total_output_tokens, 3);
+    assert_eq!(monitor.progress_percentage(), 100);
+    assert!(monitor
+        .logs
+        .iter()
+        .any(|log| log.message == "Task 'check' completed"));
+
+    // The final state can be browsed right away
+    let mut browser = StateBrowserState::new(state_dir.path().to_path_buf());
+    browser.load_states().unwrap();
+    assert!(browser.select_state("TUI Run"));
+    browser.load_details().unwrap();
+    assert_eq!(
+        browser.current_state.as_ref().unwrap().status,
+        WorkflowStatus::Completed
+    );
+}
+
+#[tokio::test]
+async fn test_paused_execution_resumes_from_checkpoint() {
+    let yaml = |slow: &str| {
+        format!(
+            r#"
+name: "Pausable"
+version: "1.0.0"
+tasks:
+  first:
+    description: "Quick step"
+    script:
+      language: bash
+      content: "true"
+  second:
+    description: "Slow step"
+    depends_on: [first]
+    script:
+      language: bash
+      content: "{}"
+"#,
+            slow
+        )
+    };
+    let workflow = parse_workflow(&yaml("sleep 30")).unwrap();
+    let state_dir = tempfile::tempdir().unwrap();
+    let (tx, mut rx) = mpsc::unbounded_channel();
+    let mut monitor = ExecutionMonitorState::new(workflow.clone());
+    let mut handle = ExecutionHandle::spawn(workflow, state_dir.path(), false, tx.clone()).unwrap();
+
+    // Pause once the slow task is running
+    loop {
+        if let Some(AppEvent::ExecutionUpdate(update)) = rx.recv().await {
+            let started = matches!(&update, ExecutionUpdate::TaskStarted(task) if task == "second");
+            monitor.apply_update(update);
+            if started {
+                break;
+            }
+        }
+    }
+    handle.stop(StopReason::Pause);
+    monitor.pause();
+    run_to_end(&mut rx, &mut monitor).await;
+
+    assert_eq!(handle.stop_reason, Some(StopReason::Pause));
+    assert_eq!(monitor.status, ExecutionStatus::Paused);
+    assert_eq!(monitor.tasks["first"].status, TaskStatus::Completed);
+
+    // Resuming skips the completed task
+    monitor.resume();
+    let resumed = parse_workflow(&yaml("true")).unwrap();
+    let _handle = ExecutionHandle::spawn(resumed, state_dir.path(), true, tx).unwrap();
+    let updates = run_to_end(&mut rx, &mut monitor).await;
+
+    assert!(!updates
+        .iter()
+        .any(|update| matches!(update, ExecutionUpdate::TaskStarted(task) if task == "first")));
+    assert_eq!(monitor.status, ExecutionStatus::Completed);
+    assert_eq!(monitor.tasks["second"].status, TaskStatus::Completed);
+}
+
+#[tokio::test]
+async fn test_stop_reason_decides_final_status() {
+    let yaml = r#"
+name: "Stoppable"
+version: "1.0.0"
+tasks:
+  slow:
+    description: "Slow step"
+    script:
+      language: bash
+      content: "sleep 30"
+"#;
+
+    for (reason, status) in [
+        (StopReason::Pause, WorkflowStatus::Paused),
+        (StopReason::Cancel, WorkflowStatus::Cancelled),
+    ] {
+        let workflow = parse_workflow(yaml).unwrap();
+        let state_dir = tempfile::tempdir().unwrap();
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let mut monitor = ExecutionMonitorState::new(workflow.clone());
+        let mut handle = ExecutionHandle::spawn(workflow, state_dir.path(), false, tx).unwrap();
+
+        loop {
+            if let Some(AppEvent::ExecutionUpdate(update)) = rx.recv().await {
+                let started =
+                    matches!(&update, ExecutionUpdate::TaskStarted(task) if task == "slow");
+                monitor.apply_update(update);
+                if started {
+                    break;
+                }
+            }
+        }
+        handle.stop(reason);
+        let updates = run_to_end(&mut rx, &mut monitor).await;
+
+        match updates.last() {
+            Some(ExecutionUpdate::Finished { state, .. }) => {
+                assert_eq!(state.as_ref().unwrap().status, status, "{:?}", reason);
+            }
+            other => panic!("Expected Finished, got {:?}", other),
+        }
+        let saved = StatePersistence::new(state_dir.path())
+            .unwrap()
+            .load_state("Stoppable")
+            .unwrap();
+        assert_eq!(saved.status, status, "{:?}", reason);
+        assert_eq!(saved.can_resume(), reason == StopReason::Pause);
+    }
+}
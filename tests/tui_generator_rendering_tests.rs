@@ -96,6 +96,8 @@ fn create_test_workflow() -> DSLWorkflow {
         imports: HashMap::new(),
         notifications: None,
         limits: None,
+        max_concurrency: None,
+        providers: HashMap::new(),
     }
 }
 
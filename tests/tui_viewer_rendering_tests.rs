@@ -97,6 +97,8 @@ fn create_minimal_workflow() -> DSLWorkflow {
         imports: HashMap::new(),
         notifications: None,
         limits: None,
+        max_concurrency: None,
+        providers: HashMap::new(),
     }
 }
 
@@ -117,6 +119,8 @@ fn create_complete_workflow() -> DSLWorkflow {
             create_cwd: None,
             inputs: HashMap::new(),
             outputs: HashMap::new(),
+            max_concurrency: None,
+            profile: None,
         },
     );
 
@@ -134,6 +138,8 @@ fn create_complete_workflow() -> DSLWorkflow {
             create_cwd: None,
             inputs: HashMap::new(),
             outputs: HashMap::new(),
+            max_concurrency: None,
+            profile: None,
         },
     );
 
@@ -187,6 +193,8 @@ fn create_complete_workflow() -> DSLWorkflow {
         imports: HashMap::new(),
         notifications: None,
         limits: None,
+        max_concurrency: None,
+        providers: HashMap::new(),
     }
 }
 
@@ -433,6 +441,7 @@ fn test_condensed_view_with_outputs() {
                 path: "result.json".to_string(),
             },
             description: None,
+            schema: None,
         },
     );
     outputs.insert(
@@ -442,6 +451,7 @@ fn test_condensed_view_with_outputs() {
                 path: "summary.txt".to_string(),
             },
             description: None,
+            schema: None,
         },
     );
     workflow.outputs = outputs;
@@ -0,0 +1,201 @@
+//! Usage and Budget Tests
+//!
+//! Verifies that the tokens reported by `llm` tasks are recorded per task in
+//! the workflow state, and that a `budget:` limit stops the workflow, failing
+//! or pausing it.
+
+mod common {
+    pub mod http;
+}
+
+use common::http::{CannedResponse, FakeServer};
+use periplon_sdk::domain::ResultMessage;
+use periplon_sdk::dsl::executor::DSLExecutor;
+use periplon_sdk::dsl::{
+    parse_workflow, validate_workflow, BudgetAction, ExecutionEvent, TaskStatus, Usage,
+    WorkflowStatus,
+};
+use periplon_sdk::error::Error;
+use serde_json::json;
+
+/// Start a fake Ollama server reporting 12 input and 3 output tokens per request
+async fn start_server() -> FakeServer {
+    FakeServer::answering(CannedResponse::json(json!({
+        "model": "test-model",
+        "message": {"role": "assistant", "content": "done"},
+        "done": true,
+        "done_reason": "stop",
+        "prompt_eval_count": 12,
+        "eval_count": 3,
+    })))
+    .await
+}
+
+/// Three chained `llm` tasks under the given budget
+fn workflow_yaml(endpoint: &str, budget: &str) -> String {
+    let task = |name: &str, depends_on: Option<&str>| {
+        format!(
+            r#"
+  {}:
+    description: "Step {}"
+    {}
+    llm:
+      provider: ollama
+      model: "test-model"
+      endpoint: "{}"
+      prompt: "Continue"
+"#,
+            name,
+            name,
+            depends_on
+                .map(|dep| format!("depends_on: [{}]", dep))
+                .unwrap_or_default(),
+            endpoint
+        )
+    };
+
+    format!(
+        r#"
+name: "Budgeted"
+version: "1.0.0"
+limits:
+  budget:
+{}
+tasks:{}{}{}"#,
+        budget,
+        task("first", None),
+        task("second", Some("first")),
+        task("third", Some("second"))
+    )
+}
+
+#[test]
+fn test_budget_config_parses_and_validates() {
+    let yaml = r#"
+name: "Budgeted"
+version: "1.0.0"
+limits:
+  budget:
+    max_task_tokens: 1000
+    max_workflow_usd: 2.5
+    on_exceeded: pause
+tasks:
+  noop:
+    description: "Nothing"
+    script:
+      language: bash
+      content: "true"
+"#;
+    let workflow = parse_workflow(yaml).unwrap();
+    let budget = workflow.limits.as_ref().unwrap().budget.clone().unwrap();
+    assert_eq!(budget.max_task_tokens, Some(1000));
+    assert_eq!(budget.max_workflow_usd, Some(2.5));
+    assert_eq!(budget.max_task_usd, None);
+    assert_eq!(budget.on_exceeded, BudgetAction::Pause);
+    assert!(validate_workflow(&workflow).is_ok());
+
+    let negative = parse_workflow(&yaml.replace("2.5", "-1.0")).unwrap();
+    let error = validate_workflow(&negative).unwrap_err().to_string();
+    assert!(error.contains("Budget max_workflow_usd must be a positive amount"));
+}
+
+#[test]
+fn test_usage_from_agent_result() {
+    let result = ResultMessage {
+        subtype: "success".to_string(),
+        duration_ms: 1200,
+        duration_api_ms: 1000,
+        is_error: false,
+        num_turns: 2,
+        session_id: "session-1".to_string(),
+        total_cost_usd: Some(0.0125),
+        usage: Some(json!({
+            "input_tokens": 40,
+            "output_tokens": 25,
+            "cache_creation_input_tokens": 100,
+            "cache_read_input_tokens": 300,
+        })),
+        result: Some("done".to_string()),
+    };
+
+    let usage = Usage::from_result(&result);
+    assert_eq!(usage.input_tokens, 40);
+    assert_eq!(usage.output_tokens, 25);
+    assert_eq!(usage.total_tokens(), 465);
+    assert_eq!(usage.cost_usd, 0.0125);
+}
+
+#[tokio::test]
+async fn test_llm_usage_is_recorded_per_task() {
+    let server = start_server().await;
+    let yaml = workflow_yaml(&server.url, "    max_workflow_tokens: 1000");
+
+    let workflow = parse_workflow(&yaml).unwrap();
+    let mut executor = DSLExecutor::new(workflow).unwrap();
+    let mut events = executor.events().channel();
+    executor.initialize().await.unwrap();
+    executor.execute().await.unwrap();
+
+    let state = executor.get_state().unwrap();
+    assert_eq!(state.task_usage["first"].input_tokens, 12);
+    assert_eq!(state.task_usage["first"].output_tokens, 3);
+    assert_eq!(state.total_usage().total_tokens(), 45);
+    assert!(state.agent_usage.is_empty());
+
+    drop(executor);
+    let mut recorded = 0;
+    while let Some(event) = events.recv().await {
+        if let ExecutionEvent::UsageRecorded { usage, agent, .. } = event {
+            assert_eq!(usage.total_tokens(), 15);
+            assert_eq!(agent, None);
+            recorded += 1;
+        }
+    }
+    assert_eq!(recorded, 3);
+}
+
+#[tokio::test]
+async fn test_exceeded_budget_aborts_workflow() {
+    let server = start_server().await;
+    let yaml = workflow_yaml(&server.url, "    max_workflow_tokens: 20");
+
+    let workflow = parse_workflow(&yaml).unwrap();
+    let mut executor = DSLExecutor::new(workflow).unwrap();
+    executor.initialize().await.unwrap();
+    let error = executor.execute().await.unwrap_err();
+
+    assert!(matches!(error, Error::BudgetExceeded(_)));
+    assert_eq!(
+        error.to_string(),
+        "Budget exceeded: Workflow used 30 tokens, over its budget of 20"
+    );
+
+    let state = executor.get_state().unwrap();
+    assert_eq!(state.status, WorkflowStatus::Failed);
+    assert_eq!(state.get_task_status("second"), Some(TaskStatus::Completed));
+    assert_eq!(state.get_task_status("third"), Some(TaskStatus::Pending));
+}
+
+#[tokio::test]
+async fn test_exceeded_task_budget_pauses_workflow() {
+    let server = start_server().await;
+    let yaml = workflow_yaml(
+        &server.url,
+        "    max_task_tokens: 10\n    on_exceeded: pause",
+    );
+
+    let workflow = parse_workflow(&yaml).unwrap();
+    let mut executor = DSLExecutor::new(workflow).unwrap();
+    executor.initialize().await.unwrap();
+    let error = executor.execute().await.unwrap_err();
+
+    assert_eq!(
+        error.to_string(),
+        "Budget exceeded: Task 'first' used 15 tokens, over its budget of 10"
+    );
+
+    let state = executor.get_state().unwrap();
+    assert_eq!(state.status, WorkflowStatus::Paused);
+    assert!(state.can_resume());
+    assert_eq!(state.get_task_status("second"), Some(TaskStatus::Pending));
+}
@@ -49,6 +49,8 @@ fn create_test_workflow(name: &str) -> (DSLWorkflow, WorkflowMetadata) {
         imports: HashMap::new(),
         notifications: None,
         limits: None,
+        max_concurrency: None,
+        providers: HashMap::new(),
     };
 
     let metadata = WorkflowMetadata {
@@ -102,6 +104,9 @@ fn create_test_execution(workflow_id: Uuid, status: ExecutionStatus) -> Executio
         },
         retry_count: 0,
         parent_execution_id: None,
+        completed_tasks: 0,
+        total_tasks: 0,
+        usage: Default::default(),
     }
 }
 
@@ -40,6 +40,8 @@ mod worker_tests {
             imports: Default::default(),
             notifications: None,
             limits: None,
+            max_concurrency: None,
+            providers: Default::default(),
         };
 
         let metadata = WorkflowMetadata {
@@ -73,6 +75,9 @@ mod worker_tests {
             error: None,
             retry_count: 0,
             parent_execution_id: None,
+            completed_tasks: 0,
+            total_tasks: 0,
+            usage: Default::default(),
         }
     }
 
@@ -0,0 +1,199 @@
+//! Worker Progress Tests
+//!
+//! Verifies that a worker records task transitions as execution logs, keeps
+//! the execution's task counts up to date, and publishes live updates on the
+//! execution hub while a workflow runs.
+
+#![cfg(feature = "server")]
+
+use chrono::Utc;
+use periplon_sdk::dsl::parse_workflow;
+use periplon_sdk::server::hub::ExecutionStreamMessage;
+use periplon_sdk::server::queue::{Job, WorkQueue};
+use periplon_sdk::server::storage::filesystem::FilesystemStorage;
+use periplon_sdk::server::storage::{
+    Execution, ExecutionStatus, ExecutionStorage, WorkflowMetadata, WorkflowStorage,
+};
+use periplon_sdk::server::worker::Worker;
+use periplon_sdk::server::ExecutionHub;
+use periplon_sdk::testing::MockQueue;
+use serde_json::json;
+use std::sync::Arc;
+use std::time::Duration;
+use uuid::Uuid;
+
+const WORKFLOW: &str = r#"
+name: "Progress"
+version: "1.0.0"
+tasks:
+  build:
+    description: "Build"
+    script:
+      language: bash
+      content: "echo built"
+  test:
+    description: "Test"
+    depends_on: [build]
+    script:
+      language: bash
+      content: "echo tested"
+  deploy:
+    description: "Deploy"
+    depends_on: [test]
+    condition:
+      type: expression
+      expression: "1 > 2"
+    script:
+      language: bash
+      content: "echo deployed"
+"#;
+
+#[tokio::test]
+async fn test_worker_records_and_streams_progress() {
+    let queue = Arc::new(MockQueue::new());
+    let dir = tempfile::tempdir().unwrap();
+    let storage = Arc::new(
+        FilesystemStorage::new(
+            dir.path().to_path_buf(),
+            "workflows".to_string(),
+            "executions".to_string(),
+            "checkpoints".to_string(),
+            "logs".to_string(),
+        )
+        .await
+        .unwrap(),
+    );
+    let hub = Arc::new(ExecutionHub::new());
+
+    let workflow = parse_workflow(WORKFLOW).unwrap();
+    let metadata = WorkflowMetadata {
+        id: Uuid::new_v4(),
+        name: "Progress".to_string(),
+        version: "1.0.0".to_string(),
+        description: None,
+        created_at: Utc::now(),
+        updated_at: Utc::now(),
+        created_by: None,
+        tags: vec![],
+        is_active: true,
+    };
+    storage.store_workflow(&workflow, &metadata).await.unwrap();
+
+    let execution = Execution {
+        id: Uuid::new_v4(),
+        workflow_id: metadata.id,
+        workflow_version: "1.0.0".to_string(),
+        status: ExecutionStatus::Queued,
+        started_at: None,
+        completed_at: None,
+        created_at: Utc::now(),
+        triggered_by: None,
+        trigger_type: "manual".to_string(),
+        input_params: None,
+        result: None,
+        error: None,
+        retry_count: 0,
+        parent_execution_id: None,
+        completed_tasks: 0,
+        total_tasks: 0,
+        usage: Default::default(),
+    };
+    let execution_id = execution.id;
+    storage.store_execution(&execution).await.unwrap();
+
+    let mut updates = hub.subscribe(execution_id);
+    queue
+        .enqueue(Job::new(metadata.id, execution_id, json!({})))
+        .await
+        .unwrap();
+
+    let worker = Worker::new("worker-1".to_string(), queue.clone(), storage.clone(), 1)
+        .with_hub(Arc::clone(&hub));
+    let handle = tokio::spawn(async move { worker.run().await });
+
+    // Collect live updates until the execution finishes
+    let mut messages = Vec::new();
+    tokio::time::timeout(Duration::from_secs(30), async {
+        while let Ok(message) = updates.recv().await {
+            let done = message.is_final();
+            messages.push(message);
+            if done {
+                break;
+            }
+        }
+    })
+    .await
+    .expect("execution should finish");
+    handle.abort();
+
+    assert!(matches!(
+        messages.first(),
+        Some(ExecutionStreamMessage::Started { .. })
+    ));
+    assert!(matches!(
+        messages.last(),
+        Some(ExecutionStreamMessage::Completed { .. })
+    ));
+
+    let task_updates: Vec<_> = messages
+        .iter()
+        .filter_map(|message| match message {
+            ExecutionStreamMessage::TaskUpdate {
+                task_id, status, ..
+            } => Some(format!("{}:{}", task_id, status)),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(
+        task_updates,
+        vec![
+            "build:running",
+            "build:completed",
+            "test:running",
+            "test:completed",
+            "deploy:running",
+            "deploy:skipped",
+        ]
+    );
+
+    let progress: Vec<_> = messages
+        .iter()
+        .filter_map(|message| match message {
+            ExecutionStreamMessage::Progress {
+                completed_tasks,
+                total_tasks,
+                ..
+            } => Some((*completed_tasks, *total_tasks)),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(progress, vec![(0, 3), (1, 3), (2, 3), (3, 3)]);
+
+    // Live logs are numbered in the order they were stored
+    let sequences: Vec<_> = messages
+        .iter()
+        .filter_map(|message| match message {
+            ExecutionStreamMessage::Log { sequence, .. } => Some(*sequence),
+            _ => None,
+        })
+        .collect();
+    assert!(!sequences.is_empty());
+    assert_eq!(sequences, (0..sequences.len()).collect::<Vec<_>>());
+
+    // Storage holds the final counts and the logs
+    let stored = storage.get_execution(execution_id).await.unwrap().unwrap();
+    assert_eq!(stored.status, ExecutionStatus::Completed);
+    assert_eq!((stored.completed_tasks, stored.total_tasks), (3, 3));
+
+    let logs = storage
+        .get_execution_logs(execution_id, None)
+        .await
+        .unwrap();
+    assert!(logs
+        .iter()
+        .any(|log| log.level == "info" && log.message.starts_with("Task 'test' completed")));
+    assert!(logs
+        .iter()
+        .any(|log| log.message == "Task 'deploy' skipped: condition not met"));
+    assert!(logs.iter().all(|log| log.metadata.is_some()));
+}
@@ -40,6 +40,8 @@ mod workflow_api_tests {
                 create_cwd: None,
                 inputs: Default::default(),
                 outputs: Default::default(),
+                max_concurrency: None,
+                profile: None,
             },
         );
 
@@ -81,8 +83,11 @@ mod workflow_api_tests {
                     definition_of_done: None,
                     loop_control: None,
                     inject_context: false,
+                    session: None,
                     context: None,
                     limits: None,
+                    schema_retries: None,
+                    messages: None,
                 },
             );
         }
@@ -108,6 +113,8 @@ mod workflow_api_tests {
             imports: Default::default(),
             notifications: None,
             limits: None,
+            max_concurrency: None,
+            providers: HashMap::new(),
         }
     }
 
@@ -143,6 +150,9 @@ mod workflow_api_tests {
             result: None,
             retry_count: 0,
             parent_execution_id: None,
+            completed_tasks: 0,
+            total_tasks: 0,
+            usage: Default::default(),
         }
     }
 
@@ -250,6 +260,13 @@ mod workflow_api_tests {
             metadata: HashMap::new(),
             loop_states: HashMap::new(),
             loop_results: HashMap::new(),
+            subflow_states: HashMap::new(),
+            stage_statuses: HashMap::new(),
+            agent_sessions: HashMap::new(),
+            named_sessions: HashMap::new(),
+            task_usage: HashMap::new(),
+            agent_usage: HashMap::new(),
+            messages: Default::default(),
         };
 
         // Add task states
@@ -0,0 +1,225 @@
+//! Workflow Stage Execution Tests
+//!
+//! Runs `workflows:` stages end-to-end: stage ordering, sequential and parallel
+//! execution modes, `on_stage_complete` hooks and resume at stage granularity.
+
+use periplon_sdk::dsl::executor::DSLExecutor;
+use periplon_sdk::dsl::{parse_workflow, StatePersistence, TaskStatus, WorkflowStatus};
+use std::path::Path;
+
+/// Script task that logs `+<id>` and `-<id>` around a short sleep
+fn logged_task(id: &str, log: &Path) -> String {
+    format!(
+        r#"  {id}:
+    description: "Task {id}"
+    script:
+      language: bash
+      content: |
+        echo "+{id}" >> "{log}"
+        sleep 0.3
+        echo "-{id}" >> "{log}"
+"#,
+        id = id,
+        log = log.display()
+    )
+}
+
+fn read_log(log: &Path) -> Vec<String> {
+    std::fs::read_to_string(log)
+        .unwrap()
+        .lines()
+        .map(|line| line.to_string())
+        .collect()
+}
+
+fn position(lines: &[String], entry: &str) -> usize {
+    lines
+        .iter()
+        .position(|line| line == entry)
+        .unwrap_or_else(|| panic!("'{}' missing from log: {:?}", entry, lines))
+}
+
+#[tokio::test]
+async fn test_stages_run_in_order_with_their_modes() {
+    let dir = tempfile::tempdir().unwrap();
+    let log = dir.path().join("events.log");
+
+    let yaml = format!(
+        r#"
+name: "Staged Workflow"
+version: "1.0.0"
+tasks:
+{prepare}{package}workflows:
+  release:
+    description: "Release pipeline"
+    hooks:
+      on_stage_complete:
+        - 'echo "stage:$WORKFLOW_STAGE" >> "{log}"'
+    steps:
+      - stage: publish
+        depends_on: [build]
+        mode: parallel
+        agents: []
+        tasks:
+          - upload:
+              description: "Upload artifacts"
+              script:
+                language: bash
+                content: |
+                  echo "+upload" >> "{log}"
+                  sleep 0.3
+                  echo "-upload" >> "{log}"
+          - announce:
+              description: "Announce release"
+              script:
+                language: bash
+                content: |
+                  echo "+announce" >> "{log}"
+                  sleep 0.3
+                  echo "-announce" >> "{log}"
+      - stage: build
+        agents: []
+        tasks:
+          - prepare:
+              description: "Prepare"
+          - package:
+              description: "Package"
+"#,
+        prepare = logged_task("prepare", &log),
+        package = logged_task("package", &log),
+        log = log.display()
+    );
+
+    let workflow = parse_workflow(&yaml).expect("Failed to parse workflow");
+    let mut executor = DSLExecutor::new(workflow).unwrap();
+    executor
+        .enable_state_persistence(Some(dir.path().join("state").to_str().unwrap()))
+        .unwrap();
+    executor.initialize().await.unwrap();
+    executor
+        .execute()
+        .await
+        .expect("staged workflow should run");
+
+    let lines = read_log(&log);
+
+    // Sequential stage: package starts only after prepare finished
+    assert!(position(&lines, "-prepare") < position(&lines, "+package"));
+
+    // The build stage hook runs before the dependent stage starts
+    let build_done = position(&lines, "stage:build");
+    assert!(position(&lines, "-package") < build_done);
+    assert!(build_done < position(&lines, "+upload"));
+    assert!(build_done < position(&lines, "+announce"));
+
+    // Parallel stage: both tasks start before either finishes
+    let last_start = position(&lines, "+upload").max(position(&lines, "+announce"));
+    let first_end = position(&lines, "-upload").min(position(&lines, "-announce"));
+    assert!(last_start < first_end);
+    assert_eq!(lines.last().map(String::as_str), Some("stage:publish"));
+
+    let state = executor.get_state().expect("state should be tracked");
+    assert_eq!(
+        state.get_stage_status("release.build"),
+        Some(TaskStatus::Completed)
+    );
+    assert_eq!(
+        state.get_stage_status("release.publish"),
+        Some(TaskStatus::Completed)
+    );
+}
+
+#[tokio::test]
+async fn test_resume_skips_completed_stages() {
+    let dir = tempfile::tempdir().unwrap();
+    let log = dir.path().join("events.log");
+    let marker = dir.path().join("ready");
+    let state_dir = dir.path().join("state");
+
+    let yaml = format!(
+        r#"
+name: "Resumable Stages"
+version: "1.0.0"
+tasks:
+  fetch:
+    description: "Fetch inputs"
+    script:
+      language: bash
+      content: 'echo "fetch" >> "{log}"'
+  process:
+    description: "Process inputs once they are ready"
+    script:
+      language: bash
+      content: |
+        test -f "{marker}" || exit 1
+        echo "process" >> "{log}"
+workflows:
+  pipeline:
+    description: "Two stage pipeline"
+    hooks:
+      on_stage_complete:
+        - 'echo "stage:$WORKFLOW_STAGE" >> "{log}"'
+    steps:
+      - stage: gather
+        agents: []
+        tasks:
+          - fetch:
+              description: "Fetch inputs"
+      - stage: work
+        depends_on: [gather]
+        agents: []
+        tasks:
+          - process:
+              description: "Process inputs"
+"#,
+        log = log.display(),
+        marker = marker.display()
+    );
+
+    // First run: the second stage fails
+    let workflow = parse_workflow(&yaml).expect("Failed to parse workflow");
+    let mut executor = DSLExecutor::new(workflow.clone()).unwrap();
+    executor
+        .enable_state_persistence(Some(state_dir.to_str().unwrap()))
+        .unwrap();
+    executor.initialize().await.unwrap();
+    executor
+        .execute()
+        .await
+        .expect_err("process should fail without the marker");
+    assert_eq!(read_log(&log), vec!["fetch", "stage:gather"]);
+
+    // Simulate an interrupted run and forget the task-level progress so only
+    // the stage record remains
+    let persistence = StatePersistence::new(&state_dir).unwrap();
+    let mut saved = persistence.load_state("Resumable Stages").unwrap();
+    assert_eq!(
+        saved.get_stage_status("pipeline.gather"),
+        Some(TaskStatus::Completed)
+    );
+    assert_eq!(
+        saved.get_stage_status("pipeline.work"),
+        Some(TaskStatus::Failed)
+    );
+    saved.status = WorkflowStatus::Running;
+    saved.update_task_status("fetch", TaskStatus::Pending);
+    persistence.save_state(&saved).unwrap();
+
+    // Second run resumes at the failed stage
+    std::fs::write(&marker, "").unwrap();
+    let mut executor = DSLExecutor::new(workflow).unwrap();
+    executor
+        .enable_state_persistence(Some(state_dir.to_str().unwrap()))
+        .unwrap();
+    assert!(executor.try_resume().unwrap());
+    executor.initialize().await.unwrap();
+    executor
+        .execute()
+        .await
+        .expect("resumed run should succeed");
+
+    assert_eq!(
+        read_log(&log),
+        vec!["fetch", "stage:gather", "process", "stage:work"]
+    );
+}